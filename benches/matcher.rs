@@ -0,0 +1,97 @@
+//! Fuzzy and substring matching over repositories with many branches, and
+//! the `--tui` finder's matching.
+//!
+//! Run with `cargo bench --bench matcher`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use ggo::matcher::{filter_branches, fuzzy_filter_branches, Candidates, Finder, MatchBonuses};
+
+/// Branch names shaped like a busy monorepo's: a prefix, a ticket ID, some
+/// words, and a sprinkling of uppercase
+fn branches(count: usize) -> Vec<String> {
+    const PREFIXES: [&str; 5] = ["feature", "fix", "chore", "release", "users/alex"];
+    const WORDS: [&str; 8] = [
+        "auth", "billing", "api", "search", "Cache", "login", "export", "ui",
+    ];
+    (0..count)
+        .map(|i| {
+            format!(
+                "{}/PROJ-{}-{}-{}",
+                PREFIXES[i % PREFIXES.len()],
+                1000 + i,
+                WORDS[i % WORDS.len()],
+                WORDS[(i / WORDS.len()) % WORDS.len()],
+            )
+        })
+        .collect()
+}
+
+fn bench_fuzzy(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fuzzy_filter_branches");
+    for count in [100, 1_000, 12_000] {
+        let branches = branches(count);
+        for pattern in ["auth", "fix api", "proj-4521"] {
+            group.bench_with_input(
+                BenchmarkId::new(pattern, count),
+                &branches,
+                |b, branches| b.iter(|| fuzzy_filter_branches(black_box(branches), pattern, true)),
+            );
+        }
+    }
+    group.finish();
+}
+
+/// Matching a list folded once, the way rankings and menus do: no sorting or
+/// copying of names
+fn bench_candidates(c: &mut Criterion) {
+    let mut group = c.benchmark_group("candidates_fuzzy_matches");
+    let bonuses = MatchBonuses::default();
+    for count in [1_000, 12_000] {
+        let branches = branches(count);
+        let candidates = Candidates::new(&branches[..]);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(count),
+            &candidates,
+            |b, candidates| b.iter(|| black_box(candidates).fuzzy_matches("auth", true, &bonuses)),
+        );
+    }
+    group.finish();
+}
+
+/// One keystroke in the `--tui` finder: the names are converted once, then
+/// each query is parsed and scored against all of them, best first
+fn bench_finder(c: &mut Criterion) {
+    let mut group = c.benchmark_group("finder_matches");
+    for count in [1_000, 10_000] {
+        let branches = branches(count);
+        let mut finder = Finder::new(branches.iter().map(String::as_str));
+        for query in ["a", "auth", "proj-4521", "^fix !ui"] {
+            group.bench_function(BenchmarkId::new(query, count), |b| {
+                b.iter(|| black_box(finder.matches(black_box(query))))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_substring(c: &mut Criterion) {
+    let mut group = c.benchmark_group("filter_branches");
+    for count in [1_000, 12_000] {
+        let branches = branches(count);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(count),
+            &branches,
+            |b, branches| b.iter(|| filter_branches(black_box(branches), "cache", true)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_fuzzy,
+    bench_candidates,
+    bench_finder,
+    bench_substring
+);
+criterion_main!(benches);
@@ -0,0 +1,91 @@
+//! Template aliases: an alias whose target has `{}` placeholders is expanded
+//! with the words after it into the pattern to match, so with
+//! `ggo alias rel "release/{}"`, `ggo rel 1.42` matches `release/1.42`.
+
+use crate::error::{GgoError, Result};
+use crate::validation;
+
+/// Where an argument goes in a template alias's target
+pub const PLACEHOLDER: &str = "{}";
+
+/// Whether an alias target is a template rather than a branch name
+pub fn is_template(target: &str) -> bool {
+    target.contains(PLACEHOLDER)
+}
+
+/// Check a template: braces only as `{}`, and a valid branch name once the
+/// placeholders are filled in
+pub fn validate_template(template: &str) -> Result<()> {
+    let invalid =
+        |reason: &str| GgoError::InvalidAliasTemplate(template.to_string(), reason.to_string());
+
+    if !is_template(template) {
+        return Err(invalid("no '{}' placeholder"));
+    }
+    if template.replace(PLACEHOLDER, "").contains(['{', '}']) {
+        return Err(invalid("braces are only allowed as '{}'"));
+    }
+
+    validation::validate_branch_name(&template.replace(PLACEHOLDER, "x"))
+        .map_err(|e| invalid(&e.summary()))
+}
+
+/// Fill the template's placeholders with `args`, in order
+pub fn expand(alias: &str, template: &str, args: &[String]) -> Result<String> {
+    let expected = template.matches(PLACEHOLDER).count();
+    if args.len() != expected {
+        return Err(GgoError::AliasArguments {
+            alias: alias.to_string(),
+            template: template.to_string(),
+            expected,
+            given: args.len(),
+        });
+    }
+
+    let mut parts = template.split(PLACEHOLDER);
+    let mut pattern = parts.next().unwrap_or_default().to_string();
+    for (arg, part) in args.iter().zip(parts) {
+        pattern.push_str(arg);
+        pattern.push_str(part);
+    }
+    Ok(pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_template() {
+        assert!(validate_template("release/{}").is_ok());
+        assert!(validate_template("{}/fix-{}").is_ok());
+
+        assert!(validate_template("release/1.42").is_err());
+        assert!(validate_template("release/{version}").is_err());
+        assert!(validate_template("release/{}}").is_err());
+        assert!(validate_template("release..{}").is_err());
+    }
+
+    #[test]
+    fn test_expand() {
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            expand("rel", "release/{}", &args(&["1.42"])).unwrap(),
+            "release/1.42"
+        );
+        assert_eq!(
+            expand("fix", "{}/fix-{}", &args(&["alice", "login"])).unwrap(),
+            "alice/fix-login"
+        );
+        assert!(matches!(
+            expand("rel", "release/{}", &[]),
+            Err(GgoError::AliasArguments {
+                expected: 1,
+                given: 0,
+                ..
+            })
+        ));
+        assert!(expand("rel", "release/{}", &args(&["1", "2"])).is_err());
+    }
+}
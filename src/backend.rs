@@ -0,0 +1,256 @@
+//! The git operations a checkout needs, behind a trait so the checkout flow
+//! can run against an in-memory repository in tests.
+//!
+//! [`CliBackend`] is the default: libgit2 for reading refs and the `git`
+//! executable (`git switch`) for checkouts, so git's own hooks and settings
+//! apply. Building with the `gix` feature uses gitoxide for all of it
+//! instead ([`GixBackend`]), which opens repositories faster; the rest of
+//! [`crate::git`] then runs on gitoxide too. Building without the default
+//! `libgit2` feature leaves libgit2 out, and [`CliBackend`] with it.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::error::Result;
+use crate::git;
+#[cfg(feature = "gix")]
+use crate::git::gitoxide;
+#[cfg(feature = "libgit2")]
+use crate::git::libgit2;
+use crate::validation;
+
+/// Operations on the current repository used to resolve and switch branches
+pub trait GitBackend {
+    /// Names of all local branches
+    fn local_branches(&self) -> Result<Vec<String>>;
+
+    /// Whether the local branch exists, with a single ref lookup
+    fn branch_exists(&self, branch: &str) -> Result<bool>;
+
+    /// Fingerprint of the branch refs for caching the branch list, if it
+    /// can be trusted (see [`git::refs_fingerprint`])
+    fn refs_fingerprint(&self) -> Result<Option<String>>;
+
+    /// Branch descriptions ('git branch --edit-description'), by branch
+    fn branch_descriptions(&self) -> Result<HashMap<String, String>>;
+
+    /// The checked-out branch
+    fn current_branch(&self) -> Result<String>;
+
+    /// The commit HEAD is detached at, or None when a branch is checked out
+    fn detached_head(&self) -> Result<Option<git::DetachTarget>>;
+
+    /// Root of the current worktree
+    fn repo_root(&self) -> Result<String>;
+
+    /// Whether the worktree has uncommitted changes
+    fn has_uncommitted_changes(&self) -> Result<bool>;
+
+    /// Commits on `branch` that are on no remote
+    fn unpushed_commits(&self, branch: &str) -> Result<usize>;
+
+    /// Another worktree that has `branch` checked out
+    fn worktree_for_branch(&self, branch: &str) -> Result<Option<PathBuf>>;
+
+    /// Check out the local branch in the current worktree
+    fn checkout(&self, branch: &str) -> Result<()>;
+
+    /// Check out the branch's commit with a detached HEAD
+    fn checkout_detached(&self, branch: &str) -> Result<()>;
+
+    /// Create a local branch at the current HEAD commit
+    fn create_branch(&self, branch: &str) -> Result<()>;
+}
+
+/// The backend this build uses
+pub fn backend() -> &'static dyn GitBackend {
+    #[cfg(feature = "gix")]
+    {
+        &GixBackend
+    }
+    #[cfg(not(feature = "gix"))]
+    {
+        &CliBackend
+    }
+}
+
+/// libgit2 for reads, `git switch` for checkouts (libgit2 when the
+/// installed git predates it)
+#[cfg(feature = "libgit2")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CliBackend;
+
+#[cfg(feature = "libgit2")]
+impl GitBackend for CliBackend {
+    fn local_branches(&self) -> Result<Vec<String>> {
+        libgit2::local_branches_in(&libgit2::open()?)
+    }
+
+    fn branch_exists(&self, branch: &str) -> Result<bool> {
+        Ok(libgit2::branch_exists_in(&libgit2::open()?, branch))
+    }
+
+    fn refs_fingerprint(&self) -> Result<Option<String>> {
+        let common = libgit2::common_dir(&libgit2::open()?);
+        Ok(git::refs_fingerprint_at(&common, SystemTime::now()))
+    }
+
+    fn branch_descriptions(&self) -> Result<HashMap<String, String>> {
+        libgit2::branch_descriptions_in(&libgit2::open()?)
+    }
+
+    fn current_branch(&self) -> Result<String> {
+        libgit2::current_branch_in(&libgit2::open()?)
+    }
+
+    fn detached_head(&self) -> Result<Option<git::DetachTarget>> {
+        libgit2::detached_head_in(&libgit2::open()?)
+    }
+
+    fn repo_root(&self) -> Result<String> {
+        git::repo_root(libgit2::workdir(&libgit2::open()?))
+    }
+
+    fn has_uncommitted_changes(&self) -> Result<bool> {
+        libgit2::has_uncommitted_changes_in(&libgit2::open()?)
+    }
+
+    fn unpushed_commits(&self, branch: &str) -> Result<usize> {
+        libgit2::unpushed_commits_in(&libgit2::open()?, branch)
+    }
+
+    fn worktree_for_branch(&self, branch: &str) -> Result<Option<PathBuf>> {
+        libgit2::worktree_for_branch_in(&libgit2::open()?, branch)
+    }
+
+    fn checkout(&self, branch: &str) -> Result<()> {
+        // Validate branch name before attempting checkout
+        validation::validate_branch_name(branch)?;
+        libgit2::checkout(branch)
+    }
+
+    fn checkout_detached(&self, branch: &str) -> Result<()> {
+        validation::validate_branch_name(branch)?;
+        libgit2::checkout_detached(branch)
+    }
+
+    fn create_branch(&self, branch: &str) -> Result<()> {
+        validation::validate_branch_name(branch)?;
+        libgit2::create_branch_in(&libgit2::open()?, branch)
+    }
+}
+
+/// gitoxide for everything, checkouts included. The changed files are
+/// written by gix rather than `git switch`, so git's post-checkout hook
+/// doesn't run.
+#[cfg(feature = "gix")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GixBackend;
+
+#[cfg(feature = "gix")]
+impl GitBackend for GixBackend {
+    fn local_branches(&self) -> Result<Vec<String>> {
+        gitoxide::local_branches_in(&gitoxide::open()?)
+    }
+
+    fn branch_exists(&self, branch: &str) -> Result<bool> {
+        Ok(gitoxide::branch_exists_in(&gitoxide::open()?, branch))
+    }
+
+    fn refs_fingerprint(&self) -> Result<Option<String>> {
+        let common = gitoxide::common_dir(&gitoxide::open()?);
+        Ok(git::refs_fingerprint_at(&common, SystemTime::now()))
+    }
+
+    fn branch_descriptions(&self) -> Result<HashMap<String, String>> {
+        gitoxide::branch_descriptions_in(&gitoxide::open()?)
+    }
+
+    fn current_branch(&self) -> Result<String> {
+        gitoxide::current_branch_in(&gitoxide::open()?)
+    }
+
+    fn detached_head(&self) -> Result<Option<git::DetachTarget>> {
+        gitoxide::detached_head_in(&gitoxide::open()?)
+    }
+
+    fn repo_root(&self) -> Result<String> {
+        git::repo_root(gitoxide::workdir(&gitoxide::open()?))
+    }
+
+    fn has_uncommitted_changes(&self) -> Result<bool> {
+        gitoxide::has_uncommitted_changes_in(&gitoxide::open()?)
+    }
+
+    fn unpushed_commits(&self, branch: &str) -> Result<usize> {
+        gitoxide::unpushed_commits_in(&gitoxide::open()?, branch)
+    }
+
+    fn worktree_for_branch(&self, branch: &str) -> Result<Option<PathBuf>> {
+        gitoxide::worktree_for_branch_in(&gitoxide::open()?, branch)
+    }
+
+    fn checkout(&self, branch: &str) -> Result<()> {
+        validation::validate_branch_name(branch)?;
+        gitoxide::checkout(branch)
+    }
+
+    fn checkout_detached(&self, branch: &str) -> Result<()> {
+        validation::validate_branch_name(branch)?;
+        gitoxide::checkout_detached(branch)
+    }
+
+    fn create_branch(&self, branch: &str) -> Result<()> {
+        validation::validate_branch_name(branch)?;
+        gitoxide::create_branch_in(&gitoxide::open()?, branch)
+    }
+}
+
+#[cfg(all(test, feature = "libgit2"))]
+mod tests {
+    use super::*;
+
+    fn setup_test_repo() -> (tempfile::TempDir, git2::Repository) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(temp_dir.path()).unwrap();
+        {
+            let signature = git2::Signature::now("Test User", "test@example.com").unwrap();
+            let tree_id = repo.index().unwrap().write_tree().unwrap();
+            let tree = repo.find_tree(tree_id).unwrap();
+            let commit = repo
+                .commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+                .unwrap();
+            let commit = repo.find_commit(commit).unwrap();
+            repo.branch("feature/auth", &commit, false).unwrap();
+            repo.branch("bugfix/login", &commit, false).unwrap();
+        }
+        (temp_dir, repo)
+    }
+
+    #[test]
+    fn test_local_branches_in() {
+        let (_temp_dir, repo) = setup_test_repo();
+        let head = repo.head().unwrap().shorthand().unwrap().to_string();
+
+        let mut branches = libgit2::local_branches_in(&repo).unwrap();
+        branches.sort();
+
+        let mut expected = vec!["bugfix/login".to_string(), "feature/auth".to_string(), head];
+        expected.sort();
+        assert_eq!(branches, expected);
+    }
+
+    #[cfg(feature = "gix")]
+    #[test]
+    fn test_gix_lists_the_same_branches() {
+        let (temp_dir, repo) = setup_test_repo();
+        let gix_repo = gix::open(temp_dir.path()).unwrap();
+
+        let mut expected = libgit2::local_branches_in(&repo).unwrap();
+        let mut branches = gitoxide::local_branches_in(&gix_repo).unwrap();
+        expected.sort();
+        branches.sort();
+        assert_eq!(branches, expected);
+    }
+}
@@ -1,4 +1,4 @@
-use clap::{Parser, Subcommand};
+use clap::{ArgGroup, Parser, Subcommand};
 
 /// ggo - Smart Git Navigation Tool
 ///
@@ -10,16 +10,97 @@ use clap::{Parser, Subcommand};
 ///     ggo exo          Matches 'expo-feature-branch' with fuzzy matching
 ///     ggo feature      Checkout best branch matching 'feature'
 ///     ggo -            Go back to previous branch (like cd -)
+///     ggo -2           Go back two branch switches
+///     ggo --history    Show the branch checkout history
 ///     ggo -l feat      List all branches matching 'feat' with scores
+///     ggo -l feat -n 5 Only the five best-ranked of them
+///     ggo --nth 3      Check out the third branch of that listing (or just 'ggo 3')
 ///     ggo -i FEAT      Case-insensitive match for 'FEAT'
 ///     ggo --no-fuzzy feat   Use exact substring matching instead of fuzzy
 ///     ggo --interactive feat   Show interactive menu to select branch
+///     ggo --tui feat   Pick from a full-screen list with commit preview
+///     ggo --fzf feat   Pick with fzf (or skim, via behavior.fzf_command)
+///     ggo --first feat   Take the top match even when the scores are close
+///     ggo -c feature/foo   Create 'feature/foo' from HEAD if nothing matches
+///     ggo --detach v1.2   Check out the best tag or recent commit matching 'v1.2', detached
 ///     ggo --stats      Show usage statistics
+///     ggo --stats api  The top branches of the repository best matching 'api'
+///     ggo --stats --this-repo --since 30d --sort switches   Last month's busiest branches here
+///     ggo --stats --heatmap   Also show switches by weekday and hour
+///     ggo --stats --format csv > branches.csv   Every branch with its score, for spreadsheets
+///     ggo --stats --slow   The slowest recent runs, from the log (see log.enabled)
+///     ggo --log-file /tmp/ggo.jsonl feat   Log this run's candidates, scores and pick
+///     ggo --data-dir ~/dotfiles/ggo --stats   Use the history kept in another directory
+///     ggo --json -l feat   List matches as JSON (also works with --stats)
+///     ggo -l feat --format "{name}\t{switches}\t{last_used}"   List matches with your own columns
+///     ggo -C ~/src/api main   Check out 'main' in another repository
+///     ggo --no-hooks main   Switch without running checkout hooks
+///     ggo -f main      Switch even if a pre-checkout guard objects
+///     ggo --quiet auth || echo $?   Exit code only: 2 no match, 3 not a repo, 4 cancelled, 5 checkout failed
+///
+///     ggo find -m "retry queue" Check out the branch whose tip commit mentions it
+///     ggo find -a alice -t src/pay --unmerged -l   List branches with matching unmerged work
+///
+///     ggo contains 1a2b3c4      Check out the branch that has commit 1a2b3c4
+///
+///     ggo undo                  Take back the last switch (and its history entry)
+///     ggo explain auth          Show how the branches matching 'auth' are scored
+///
+///     ggo recent                List recently used branches
+///     ggo recent -i             Pick a recently used branch to check out
 ///
 ///     ggo alias m master        Create alias 'm' for branch 'master'
 ///     ggo alias m               Show what alias 'm' points to
 ///     ggo alias --list          List all aliases
+///     ggo alias --list --all    List the aliases of every repository
 ///     ggo alias --remove m      Remove alias 'm'
+///     ggo alias --rename m mn   Rename alias 'm' to 'mn'
+///     ggo alias rel "release/{}"  Template alias: 'ggo rel 1.42' matches release/1.42
+///     ggo alias export > aliases.toml   Share this repository's aliases
+///     ggo alias import aliases.toml     Create aliases from a shared file
+///
+///     ggo open --pr auth        Open the pull request for the best 'auth' match
+///     ggo pr 1234               Fetch pull request #1234 into 'pr/1234' and check it out
+///
+///     eval "$(ggo init bash)"   Shell integration: cd for 'ggo wt', Ctrl-G picker
+///     ggo setup                 Install the shell integration and completions, step by step
+///     ggo self-update --check   See whether a newer ggo release is out
+///
+///     ggo wt auth               Go to the worktree of the best 'auth' branch
+///     ggo wt -c auth            ...creating one next to the repository if needed
+///
+///     ggo repo api              Jump to the repository matching 'api'
+///
+///     ggo tag v1.4              Check out the highest 'v1.4' tag, detached
+///     ggo tag v1.4 -b hotfix    Create branch 'hotfix' at that tag and switch to it
+///
+///     ggo stack on main         Record that the current branch is built on 'main'
+///     ggo stack                 Show the current branch's stack
+///     ggo up / ggo down         Check out the next branch up or down the stack
+///
+///     ggo note feat/api "waiting on review"   Annotate a branch
+///     ggo -l --notes review     List branches whose name or note matches 'review'
+///
+///     ggo group add backend feature/api 'svc/*'   Group branches by name or pattern
+///     ggo group backend         Pick a branch from the 'backend' group
+///     ggo --group backend -l    List only the branches in the group
+///
+///     ggo rename-record old new Keep the history of a branch renamed outside ggo
+///     ggo forget 'spike/*'      Erase ggo's records of matching branches
+///     ggo forget --repo ~/old   Erase everything ggo stored about a repository
+///
+///     ggo export > ggo.json     Save usage history, aliases and previous branches
+///     ggo import --merge ggo.json   Add another machine's history to this one
+///     ggo sync                  Share history with your other machines (see sync.repo)
+///
+///     ggo delete old-feat       Pick branches matching 'old-feat' to delete
+///     ggo prune --fetch         Clean up merged branches and ones deleted upstream
+///     ggo batch spike -a forget Pick branches matching 'spike' and forget their records
+///
+///     ggo hooks trust           Let this repository's .ggo.toml hooks run (until it changes)
+///
+///     ggo config list           Show all configuration values
+///     ggo config set behavior.picker tui   Change a configuration value
 ///
 /// NOTE:
 ///     ggo learns from your usage patterns. The more you use a branch,
@@ -28,22 +109,43 @@ use clap::{Parser, Subcommand};
 #[derive(Parser, Debug)]
 #[command(name = "ggo")]
 #[command(disable_version_flag = true)]
+#[command(allow_negative_numbers = true)]
+#[command(group(ArgGroup::new("formatted").args(["list", "stats"]).multiple(true)))]
 #[command(about = "Smart Git Navigation Tool", long_about = None)]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>,
 
-    /// Search pattern to match branch names (use '-' to go back to previous branch)
+    /// Search pattern to match branch names (use '-' to go back to previous branch, '-N' to go back N switches)
     pub pattern: Option<String>,
 
+    /// Arguments for a template alias (e.g. '1.42' in 'ggo rel 1.42' with
+    /// 'ggo alias rel "release/{}"')
+    #[arg(value_name = "ARGS")]
+    pub args: Vec<String>,
+
     /// List matching branches without checking out
     #[arg(short, long)]
     pub list: bool,
 
+    /// With --list: show only the N best-ranked branches (0 for all)
+    #[arg(
+        short = 'n',
+        long,
+        value_name = "N",
+        default_value = "0",
+        requires = "list"
+    )]
+    pub limit: usize,
+
     /// Case-insensitive pattern matching
     #[arg(short = 'i', long = "ignore-case")]
     pub ignore_case: bool,
 
+    /// Case-sensitive pattern matching, even for all-lowercase patterns
+    #[arg(long = "case-sensitive", conflicts_with = "ignore_case")]
+    pub case_sensitive: bool,
+
     /// Disable fuzzy matching (use exact substring matching instead)
     #[arg(long = "no-fuzzy")]
     pub no_fuzzy: bool,
@@ -52,37 +154,650 @@ pub struct Cli {
     #[arg(long)]
     pub interactive: bool,
 
-    /// Show usage statistics
+    /// Use the full-screen picker (live filter, commit preview) to select a branch
+    #[arg(long)]
+    pub tui: bool,
+
+    /// Pick the branch with fzf or skim (see behavior.fzf_command)
+    #[arg(long, conflicts_with = "tui")]
+    pub fzf: bool,
+
+    /// Never prompt: take the top match when several are close (the default
+    /// when stdin/stdout isn't a terminal)
+    #[arg(long = "no-interactive", conflicts_with_all = ["interactive", "tui", "fzf"])]
+    pub no_interactive: bool,
+
+    /// Show the menu whenever several branches match, even with a clear
+    /// winner (behavior.auto_select = never for this run)
+    #[arg(long, conflicts_with_all = ["no_interactive", "print"])]
+    pub pick: bool,
+
+    /// Check out the top match without the menu, however close the scores
+    /// (behavior.auto_select = always for this run)
+    #[arg(long, conflicts_with_all = ["pick", "interactive", "tui", "fzf"])]
+    pub first: bool,
+
+    /// Create a branch named after the pattern if no branch matches
+    #[arg(short = 'c', long)]
+    pub create: bool,
+
+    /// If the branch is checked out in another worktree, check out its commit here (detached HEAD)
+    #[arg(long = "force-detach")]
+    pub force_detach: bool,
+
+    /// Check out the tag or recent commit matching the pattern with a detached HEAD
+    /// (not recorded in branch history)
+    #[arg(long, conflicts_with_all = ["list", "create", "force_detach", "interactive", "tui", "fzf"])]
+    pub detach: bool,
+
+    /// Match tags instead of branches (highest version first) and check out the pick
+    /// with a detached HEAD, like 'ggo tag'
+    #[arg(long, conflicts_with_all = ["list", "create", "force_detach", "interactive", "tui", "fzf", "detach"])]
+    pub tags: bool,
+
+    /// Switch even if a pre-checkout guard or hook objects
+    #[arg(short = 'f', long)]
+    pub force: bool,
+
+    /// Print the branch the pattern resolves to instead of checking it out
+    /// (takes the top match without prompting, for use in scripts)
+    #[arg(
+        short = 'p',
+        long,
+        conflicts_with_all = ["list", "interactive", "tui", "fzf", "create", "force_detach"]
+    )]
+    pub print: bool,
+
+    /// Check out entry N of the last 'ggo -l' listing in this repository (a
+    /// bare 'ggo N' right after the listing does the same)
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with_all = ["list", "interactive", "tui", "fzf", "create", "detach", "tags"]
+    )]
+    pub nth: Option<u64>,
+
+    /// Boost branches you usually switch to next from the current branch
+    #[arg(long)]
+    pub suggest: bool,
+
+    /// Also match the pattern against branch notes (see 'ggo note')
+    #[arg(long)]
+    pub notes: bool,
+
+    /// Only offer the branches in this group (see 'ggo group') when matching,
+    /// listing or picking
+    #[arg(long, value_name = "GROUP", global = true)]
+    pub group: Option<String>,
+
+    /// Show usage statistics; with a pattern, those of the repository whose
+    /// name matches it best
     #[arg(long)]
     pub stats: bool,
 
+    /// With --stats: only count the current repository
+    #[arg(long = "this-repo", requires = "stats", conflicts_with = "pattern")]
+    pub this_repo: bool,
+
+    /// With --stats: number of top branches to show
+    #[arg(long, value_name = "N", default_value = "10", requires = "stats")]
+    pub top: usize,
+
+    /// With --stats: order of the top branches
+    #[arg(
+        long,
+        default_value = "score",
+        value_parser = ["switches", "recent", "score"],
+        requires = "stats"
+    )]
+    pub sort: String,
+
+    /// With --stats: only branches used within this long (e.g. 30d, 12h, 2w)
+    #[arg(long, value_name = "AGE", requires = "stats")]
+    pub since: Option<String>,
+
+    /// With --stats: show when you switch branches, by weekday and hour
+    #[arg(long, requires = "stats")]
+    pub heatmap: bool,
+
+    /// With --stats: show the slowest recent runs from the invocation log
+    /// instead (the --log-file one, if given)
+    #[arg(long, requires = "stats", conflicts_with_all = ["heatmap", "format"])]
+    pub slow: bool,
+
+    /// With --list: print each branch as a template, e.g. "{name}\t{score}\t{last_used}".
+    /// With --stats: dump every branch with its switches, last use and
+    /// frecency score (json or csv) instead of the summary
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        value_parser = parse_format,
+        requires = "formatted",
+        conflicts_with_all = ["json", "heatmap"]
+    )]
+    pub format: Option<String>,
+
+    /// Emit machine-readable JSON (for --list, --stats and query)
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Run as if ggo was started in PATH (like 'git -C')
+    #[arg(short = 'C', long = "repo", value_name = "PATH", global = true)]
+    pub repo: Option<std::path::PathBuf>,
+
+    /// Check out without recording anything (history, frecency, previous branch);
+    /// also enabled by GGO_NO_RECORD=1
+    #[arg(long = "no-record", global = true)]
+    pub no_record: bool,
+
+    /// Don't run pre- and post-checkout hooks for this run; also enabled by GGO_NO_HOOKS=1
+    #[arg(long = "no-hooks", global = true)]
+    pub no_hooks: bool,
+
+    /// Append this run's decisions (candidates, scores, pick, timing) to
+    /// PATH as a JSON line, whether or not log.enabled is set
+    #[arg(long = "log-file", value_name = "PATH", global = true)]
+    pub log_file: Option<std::path::PathBuf>,
+
+    /// Keep the history database (and backups, logs) in DIR; overrides
+    /// GGO_DATA_DIR and storage.data_dir
+    #[arg(long = "data-dir", value_name = "DIR", global = true)]
+    pub data_dir: Option<std::path::PathBuf>,
+
+    /// When to color output (auto: on terminals, unless NO_COLOR is set)
+    #[arg(
+        long,
+        value_name = "WHEN",
+        default_value = "auto",
+        value_parser = ["auto", "always", "never"],
+        global = true
+    )]
+    pub color: String,
+
+    /// Print only results and errors: no advisory messages, and errors without
+    /// their hints (exit codes are unchanged)
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    /// Log what ggo is doing to stderr: -v info, -vv debug, -vvv trace
+    /// (RUST_LOG is used without it)
+    #[arg(
+        short,
+        long,
+        action = clap::ArgAction::Count,
+        global = true,
+        conflicts_with = "quiet"
+    )]
+    pub verbose: u8,
+
+    /// Skip the automatic maintenance run even if it is due (see maintenance.auto)
+    #[arg(long = "no-auto-maintenance", global = true)]
+    pub no_auto_maintenance: bool,
+
+    /// Show the checkout history stack (use 'ggo -N' to jump back)
+    #[arg(long)]
+    pub history: bool,
+
     /// Print version
-    #[arg(short = 'v', short_alias = 'V', long)]
+    #[arg(short = 'V', long)]
     pub version: bool,
 
     /// Generate shell completion script
     #[arg(long = "generate-completion", value_name = "SHELL")]
     pub generate_completion: Option<String>,
+
+    /// Write the man page and every shell's completion script into DIR, for
+    /// packages to install
+    #[arg(long = "generate-assets", value_name = "DIR", hide = true)]
+    pub generate_assets: Option<std::path::PathBuf>,
+
+    /// Same as 'ggo record-current', for shells set up by an older 'ggo init'
+    #[arg(long = "record-current", hide = true)]
+    pub record_current: bool,
 }
 
 #[derive(Subcommand, Debug, PartialEq)]
 pub enum Commands {
     /// Manage branch aliases
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Alias {
+        #[command(subcommand)]
+        action: Option<AliasCommand>,
+
         /// Alias name (not required when using --list)
         #[arg(required_unless_present = "list")]
         alias: Option<String>,
 
-        /// Branch name (if provided, creates/updates alias; if omitted, shows what alias points to)
+        /// Branch name (if provided, creates/updates alias; if omitted, shows
+        /// what alias points to). With --rename, the alias's new name.
         branch: Option<String>,
 
         /// List all aliases for the current repository
         #[arg(short, long)]
         list: bool,
 
+        /// With --list, show the aliases of every repository, marking ones
+        /// whose branch or repository no longer exists
+        #[arg(short, long, requires = "list")]
+        all: bool,
+
         /// Remove the alias
         #[arg(short, long)]
         remove: bool,
+
+        /// Rename the alias to the name given after it
+        #[arg(long, requires = "branch", conflicts_with_all = ["list", "remove"])]
+        rename: bool,
+    },
+
+    /// Undo the last ggo switch: go back and forget it was ever recorded
+    Undo,
+
+    /// Print the branches matching a pattern, ranked, for editor branch
+    /// pickers (with --json: scores, aliases, pins and notes); never checks
+    /// anything out
+    Query {
+        /// Pattern to match branch names (default: every branch, by frecency)
+        pattern: Option<String>,
+
+        /// Maximum number of branches to print (0 for all)
+        #[arg(short = 'n', long = "limit", default_value = "0")]
+        limit: usize,
+    },
+
+    /// List recently used branches in this repository (most recent first)
+    Recent {
+        /// Maximum number of branches to show
+        #[arg(short = 'n', long = "limit", default_value = "10")]
+        limit: usize,
+
+        /// Pick one of the recent branches to check out
+        #[arg(short, long)]
+        interactive: bool,
+    },
+
+    /// Show the checked-out branch, the previous one and the current
+    /// branch's frecency rank, e.g. for a shell prompt (reads the history
+    /// without writing anything)
+    Current {
+        /// One tab-separated line: branch, previous branch, rank (empty
+        /// fields when unknown, no output outside a repository)
+        #[arg(long)]
+        porcelain: bool,
+    },
+
+    /// Record the current branch as used if it was switched to with plain
+    /// git (called by the 'ggo init' prompt hook)
+    #[command(hide = true)]
+    RecordCurrent,
+
+    /// Switch to the branch you committed on most recently (by git's
+    /// user.email, blended with frecency: config key frecency.activity_weight),
+    /// including branches only on a remote
+    SwitchLastModified {
+        /// List the branches by your commit activity instead of switching
+        #[arg(short, long, conflicts_with = "interactive")]
+        list: bool,
+
+        /// Pick one of them to check out
+        #[arg(short, long)]
+        interactive: bool,
+    },
+
+    /// Exclude branches matching a glob pattern (e.g. 'release/*') from matching
+    /// and history (no pattern: list excluded patterns)
+    Ignore {
+        /// Glob pattern; '*' matches any characters, '?' one character
+        pattern: Option<String>,
+
+        /// Stop excluding the pattern
+        #[arg(short, long, requires = "pattern")]
+        remove: bool,
+    },
+
+    /// Pin a branch so it always lists first (no branch: list pinned branches)
+    Pin {
+        /// Branch to pin
+        branch: Option<String>,
+    },
+
+    /// Unpin a branch
+    Unpin {
+        /// Branch to unpin
+        branch: String,
+    },
+
+    /// Attach a note to a branch, shown in 'ggo -l' and the menu (no text: show
+    /// the note; no branch: list every note in the repository)
+    Note {
+        /// Branch to annotate
+        branch: Option<String>,
+
+        /// Note text, e.g. "waiting on review"
+        text: Option<String>,
+
+        /// Remove the branch's note
+        #[arg(short, long, requires = "branch", conflicts_with = "text")]
+        remove: bool,
+    },
+
+    /// Named groups of branches: list them, or pick a branch from one (groups
+    /// can also be defined by pattern under [groups] in '.ggo.toml')
+    #[command(args_conflicts_with_subcommands = true)]
+    Group {
+        #[command(subcommand)]
+        action: Option<GroupCommand>,
+
+        /// Group to pick a branch from
+        name: Option<String>,
+    },
+
+    /// Move ggo's history of a branch (usage, aliases, pins, stacks) to its
+    /// new name after renaming it outside ggo
+    RenameRecord {
+        /// Old branch name
+        old: String,
+
+        /// New branch name
+        new: String,
+    },
+
+    /// Show the stack the current branch is in, or change how branches are
+    /// stacked (for stacked pull requests)
+    Stack {
+        #[command(subcommand)]
+        action: Option<StackCommand>,
+
+        /// Show every stack in the repository
+        #[arg(short, long)]
+        all: bool,
+    },
+
+    /// Check out the branch stacked on the current one (or N levels up)
+    Up {
+        /// Number of levels to move
+        #[arg(default_value = "1")]
+        steps: usize,
+    },
+
+    /// Check out the branch the current one is stacked on (or N levels down)
+    Down {
+        /// Number of levels to move
+        #[arg(default_value = "1")]
+        steps: usize,
+    },
+
+    /// Find the branch holding some work by its commit message, author or the
+    /// files it changes, and check it out
+    #[command(group(ArgGroup::new("criteria").required(true).multiple(true)))]
+    Find {
+        /// Text in the commit message (case-insensitive)
+        #[arg(short, long, group = "criteria")]
+        message: Option<String>,
+
+        /// Part of the author's name or email (case-insensitive)
+        #[arg(short, long, group = "criteria")]
+        author: Option<String>,
+
+        /// File or directory the commit changes
+        #[arg(short, long, group = "criteria", value_name = "PATH")]
+        touches: Option<String>,
+
+        /// Search every commit not yet on the default branch, not only branch tips
+        #[arg(short, long)]
+        unmerged: bool,
+
+        /// Only list the branches found
+        #[arg(short, long)]
+        list: bool,
+    },
+
+    /// Check out the branch containing a commit (best by frecency, or pick one)
+    Contains {
+        /// Commit id or any revision git understands
+        commit: String,
+    },
+
+    /// Show how each branch matching a pattern is scored and which rule
+    /// (alias, auto-select, menu) would pick one, without checking out
+    Explain {
+        /// Search pattern, as given to 'ggo <pattern>'
+        pattern: String,
+    },
+
+    /// Predict the branches you're most likely to switch to next from the current one
+    Suggest {
+        /// Maximum number of suggestions to show
+        #[arg(short = 'n', long = "limit", default_value = "5")]
+        limit: usize,
+    },
+
+    /// Open a branch (or its pull request) on GitHub, GitLab or Bitbucket
+    Open {
+        /// Branch name, alias or pattern (default: the current branch)
+        pattern: Option<String>,
+
+        /// Open the pull/merge request page instead of the branch
+        #[arg(long)]
+        pr: bool,
+
+        /// Print the URL instead of opening a browser
+        #[arg(long)]
+        url: bool,
+    },
+
+    /// Print the shell integration: a cd-capable wrapper, a Ctrl-G picker
+    /// binding and a prompt hook (e.g. eval "$(ggo init bash)" in ~/.bashrc)
+    Init {
+        /// Shell to print the integration for
+        #[arg(value_parser = ggo::shell::Shell::NAMES)]
+        shell: String,
+    },
+
+    /// Jump to the repository matching the pattern, from the usage history and
+    /// the directories under search.roots
+    Repo {
+        /// Pattern to match repository names (a pattern with '/' matches the whole path)
+        pattern: Option<String>,
+    },
+
+    /// Go to the worktree of the branch matching the pattern, or create one for it
+    Wt {
+        /// Pattern to match branch names (every branch when omitted)
+        pattern: Option<String>,
+
+        /// Create a worktree without asking if the branch has none
+        #[arg(short = 'c', long)]
+        create: bool,
+
+        /// Directory for a new worktree (default: '<repo>-<branch>' next to the main worktree)
+        #[arg(long, value_name = "DIR")]
+        path: Option<std::path::PathBuf>,
+    },
+
+    /// Check out a tag matching the pattern (highest version first) with a detached HEAD
+    Tag {
+        /// Pattern to match tag names (all tags when omitted)
+        pattern: Option<String>,
+
+        /// Create a branch named NAME at the tag and switch to it instead
+        #[arg(short = 'b', long = "branch", value_name = "NAME")]
+        branch: Option<String>,
+    },
+
+    /// Fetch a GitHub pull request or GitLab merge request into 'pr/<number>' and check it out
+    Pr {
+        /// Pull/merge request number ('1234' or '#1234')
+        #[arg(value_parser = parse_pull_request_number)]
+        number: u64,
+    },
+
+    /// Forget what ggo stored about branches (usage, aliases, pins, history),
+    /// or about a whole repository with 'ggo forget --repo <path>'
+    Forget {
+        /// Branch name or glob (e.g. 'spike/*'); omit with --repo to forget the repository
+        pattern: Option<String>,
+
+        /// Show what would be forgotten without removing anything
+        #[arg(short = 'n', long = "dry-run")]
+        dry_run: bool,
+    },
+
+    /// Write usage history, aliases and previous branches of all repositories
+    Export {
+        /// Output format
+        #[arg(long, default_value = "json", value_parser = ["json", "csv"])]
+        format: String,
+
+        /// File to write (default: standard output)
+        path: Option<std::path::PathBuf>,
+    },
+
+    /// Load history written by 'ggo export' (JSON or CSV)
+    Import {
+        /// File to read (default or '-': standard input)
+        path: Option<std::path::PathBuf>,
+
+        /// Add switch counts to existing ones and keep the newest timestamps,
+        /// instead of overwriting matching records
+        #[arg(long)]
+        merge: bool,
+    },
+
+    /// Merge the history other machines pushed to the sync repository
+    /// (config key sync.repo), then commit and push this machine's history
+    Sync,
+
+    /// Move the history to another storage backend and switch to it (config
+    /// key storage.backend), e.g. 'ggo migrate-storage encrypted'
+    MigrateStorage {
+        /// Backend to move to
+        #[arg(value_parser = ["sqlite", "json", "encrypted"])]
+        to: String,
+
+        /// Replace any history the target backend already holds
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Answer JSON-RPC requests from editor plugins (list-ranked, checkout,
+    /// record, aliases) on a unix socket, keeping the database open
+    Serve {
+        /// Socket to listen on (default: ggo.sock in the data directory)
+        #[arg(long, value_name = "PATH")]
+        socket: Option<std::path::PathBuf>,
+    },
+
+    /// Delete branches matching a pattern (and everything ggo stored about them)
+    Delete {
+        /// Pattern to match branch names
+        pattern: String,
+
+        /// Force deletion of branches that are not fully merged
+        #[arg(short = 'D', long)]
+        force: bool,
+
+        /// Delete without prompting: only the branch named exactly, or the only
+        /// one containing the pattern (frequently used ones also need --force)
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Pick branches matching a pattern and delete, pin, unpin or forget all of them
+    Batch {
+        /// Pattern to match branch names
+        pattern: String,
+
+        /// What to do with the selected branches (asked after selecting if omitted)
+        #[arg(short, long, value_parser = ["delete", "pin", "unpin", "forget", "group"])]
+        action: Option<String>,
+
+        /// Group to add the branches to with '--action group' (asked if omitted)
+        #[arg(long, value_name = "GROUP")]
+        into: Option<String>,
+
+        /// Force deletion of branches that are not fully merged
+        #[arg(short = 'D', long)]
+        force: bool,
+
+        /// Apply the action to all matching branches without prompting (delete
+        /// only takes the branch named exactly, or the only one containing the pattern)
+        #[arg(short, long, requires = "action")]
+        yes: bool,
+    },
+
+    /// Delete local branches that are merged into the default branch or whose upstream is gone
+    Prune {
+        /// Run 'git fetch --prune' first so deleted remote branches are detected
+        #[arg(long)]
+        fetch: bool,
+
+        /// Only list the branches that would be pruned
+        #[arg(short = 'n', long = "dry-run")]
+        dry_run: bool,
+
+        /// Prune all listed branches without prompting
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// List branches with no commit and no checkout for a while, with their
+    /// merge status, and pick ones to delete (without a terminal, e.g. from
+    /// cron, it only lists them unless --yes is given)
+    Stale {
+        /// Days without a commit or a checkout before a branch is stale
+        #[arg(long, default_value = "90", value_name = "N")]
+        days: u32,
+
+        /// Only branches whose last commit is yours (by git's user.email)
+        #[arg(long)]
+        mine: bool,
+
+        /// Allow deleting branches that are not merged into the default branch
+        #[arg(short = 'D', long)]
+        force: bool,
+
+        /// Only list the stale branches
+        #[arg(short = 'n', long = "dry-run")]
+        dry_run: bool,
+
+        /// Delete all listed branches without prompting
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Set ggo up step by step: shell integration, completions, a config file
+    /// and this repository's reflog history
+    Setup {
+        /// Shell to set up (default: from $SHELL)
+        #[arg(long, value_parser = ggo::shell::Shell::NAMES)]
+        shell: Option<String>,
+
+        /// Do every step without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Replace this ggo with the latest GitHub release for this platform
+    #[cfg(feature = "self-update")]
+    SelfUpdate {
+        /// Only report whether a newer release is available
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Show the hooks in this repository's '.ggo.toml', or allow or stop
+    /// them running on switches
+    Hooks {
+        #[command(subcommand)]
+        action: Option<HooksCommand>,
+    },
+
+    /// View or change configuration (~/.config/ggo/config.toml)
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
     },
 
     /// Database maintenance and cleanup
@@ -95,6 +810,10 @@ pub enum Commands {
         #[arg(long)]
         deleted: bool,
 
+        /// Drop checkout events older than history.retention_days
+        #[arg(long)]
+        compact: bool,
+
         /// Optimize database (VACUUM and ANALYZE)
         #[arg(long)]
         optimize: bool,
@@ -102,9 +821,137 @@ pub enum Commands {
         /// Show database size
         #[arg(long)]
         size: bool,
+
+        /// Show what would be removed (with counts and ages) without removing anything
+        #[arg(short = 'n', long = "dry-run")]
+        dry_run: bool,
+
+        /// Only clean up records of the current repository (use -C/--repo <path>
+        /// for another one, even if it no longer exists)
+        #[arg(long)]
+        current: bool,
+
+        /// Replace the database with the backup taken before the last schema
+        /// migration (e.g. after downgrading ggo)
+        #[arg(long)]
+        restore_backup: bool,
     },
 }
 
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum StackCommand {
+    /// Stack a branch (default: the current one) on a parent branch
+    On {
+        /// Branch it is built on
+        parent: String,
+
+        /// Branch to stack instead of the current one
+        #[arg(short, long)]
+        branch: Option<String>,
+    },
+
+    /// Take a branch (default: the current one) out of its stack; branches
+    /// stacked on it move down to its parent
+    Remove {
+        /// Branch to take out instead of the current one
+        branch: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum AliasCommand {
+    /// Print this repository's aliases as TOML (e.g. > aliases.toml)
+    Export,
+
+    /// Create the aliases in a file written by 'ggo alias export', skipping
+    /// ones whose branch doesn't exist here or whose name is already taken
+    Import {
+        /// File to read (default or '-': standard input)
+        path: Option<std::path::PathBuf>,
+
+        /// Replace existing aliases of the same name
+        #[arg(short, long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum GroupCommand {
+    /// Add branches or glob patterns (e.g. 'svc/*') to a group, creating it
+    Add {
+        /// Group name
+        name: String,
+
+        /// Branches or glob patterns to add
+        #[arg(required = true)]
+        branches: Vec<String>,
+    },
+
+    /// Remove branches or patterns from a group (none given: the whole group)
+    Remove {
+        /// Group name
+        name: String,
+
+        /// Branches or glob patterns to remove
+        branches: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum HooksCommand {
+    /// Run the hooks in '.ggo.toml' on switches in this repository, until the
+    /// file changes
+    Trust,
+
+    /// Stop running the hooks in '.ggo.toml'
+    Untrust,
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum ConfigCommand {
+    /// Print the value of a config key (e.g. behavior.auto_select_threshold)
+    Get {
+        /// Config key in section.field form
+        key: String,
+    },
+
+    /// Set a config key and save the config file
+    Set {
+        /// Config key in section.field form
+        key: String,
+
+        /// New value
+        value: String,
+    },
+
+    /// List all config keys with their current values
+    List,
+
+    /// Open the config file in $VISUAL / $EDITOR
+    Edit,
+
+    /// Print the path to the config file
+    Path,
+}
+
+/// `json`, `csv` or a template; which one fits is checked against --list
+/// and --stats once both are known
+fn parse_format(value: &str) -> Result<String, String> {
+    if matches!(value, "json" | "csv") || value.contains('{') {
+        Ok(value.to_string())
+    } else {
+        Err(format!(
+            "'{}' is neither json, csv nor a template with {{placeholders}}",
+            value
+        ))
+    }
+}
+
+fn parse_pull_request_number(value: &str) -> Result<u64, String> {
+    ggo::forge::parse_pull_request_number(value)
+        .ok_or_else(|| format!("'{}' is not a pull request number", value))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -156,6 +1003,15 @@ mod tests {
         assert!(cli.ignore_case);
     }
 
+    #[test]
+    fn test_parse_case_sensitive() {
+        let cli = Cli::parse_from(["ggo", "--case-sensitive", "auth"]);
+        assert!(cli.case_sensitive);
+        assert!(!cli.ignore_case);
+
+        assert!(Cli::try_parse_from(["ggo", "-i", "--case-sensitive", "auth"]).is_err());
+    }
+
     #[test]
     fn test_parse_with_long_ignore_case() {
         let args = vec!["ggo", "--ignore-case", "TEST"];
@@ -166,30 +1022,195 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_with_no_fuzzy() {
-        let args = vec!["ggo", "--no-fuzzy", "main"];
-        let cli = Cli::parse_from(args);
+    fn test_parse_with_no_fuzzy() {
+        let args = vec!["ggo", "--no-fuzzy", "main"];
+        let cli = Cli::parse_from(args);
+
+        assert_eq!(cli.pattern, Some("main".to_string()));
+        assert!(cli.no_fuzzy);
+    }
+
+    #[test]
+    fn test_parse_with_interactive() {
+        let args = vec!["ggo", "--interactive", "feature"];
+        let cli = Cli::parse_from(args);
+
+        assert_eq!(cli.pattern, Some("feature".to_string()));
+        assert!(cli.interactive);
+    }
+
+    #[test]
+    fn test_parse_with_create() {
+        let args = vec!["ggo", "-c", "feature/new"];
+        let cli = Cli::parse_from(args);
+
+        assert_eq!(cli.pattern, Some("feature/new".to_string()));
+        assert!(cli.create);
+    }
+
+    #[test]
+    fn test_parse_with_long_create() {
+        let args = vec!["ggo", "--create", "feature/new"];
+        let cli = Cli::parse_from(args);
+
+        assert!(cli.create);
+    }
+
+    #[test]
+    fn test_parse_with_tui() {
+        let args = vec!["ggo", "--tui", "feature"];
+        let cli = Cli::parse_from(args);
+
+        assert_eq!(cli.pattern, Some("feature".to_string()));
+        assert!(cli.tui);
+    }
+
+    #[test]
+    fn test_parse_with_fzf() {
+        let cli = Cli::parse_from(["ggo", "--fzf", "feature"]);
+        assert!(cli.fzf);
+
+        assert!(Cli::try_parse_from(["ggo", "--fzf", "--tui", "feature"]).is_err());
+        assert!(Cli::try_parse_from(["ggo", "--fzf", "-p", "feature"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_with_force_detach() {
+        let args = vec!["ggo", "--force-detach", "feature"];
+        let cli = Cli::parse_from(args);
+
+        assert_eq!(cli.pattern, Some("feature".to_string()));
+        assert!(cli.force_detach);
+    }
+
+    #[test]
+    fn test_parse_history_position_pattern() {
+        let args = vec!["ggo", "-2"];
+        let cli = Cli::parse_from(args);
+
+        assert_eq!(cli.pattern, Some("-2".to_string()));
+    }
+
+    #[test]
+    fn test_parse_history_flag() {
+        let args = vec!["ggo", "--history"];
+        let cli = Cli::parse_from(args);
+
+        assert_eq!(cli.pattern, None);
+        assert!(cli.history);
+    }
+
+    #[test]
+    fn test_parse_repo_path() {
+        let cli = Cli::parse_from(["ggo", "-C", "/tmp/repo", "feat"]);
+        assert_eq!(cli.repo, Some(std::path::PathBuf::from("/tmp/repo")));
+        assert_eq!(cli.pattern, Some("feat".to_string()));
+
+        // Global, so it also works after a subcommand
+        let cli = Cli::parse_from(["ggo", "recent", "--repo", "/tmp/repo"]);
+        assert_eq!(cli.repo, Some(std::path::PathBuf::from("/tmp/repo")));
+    }
+
+    #[test]
+    fn test_parse_json_with_list() {
+        let args = vec!["ggo", "--json", "-l", "feat"];
+        let cli = Cli::parse_from(args);
+
+        assert!(cli.json);
+        assert!(cli.list);
+        assert_eq!(cli.pattern, Some("feat".to_string()));
+    }
+
+    #[test]
+    fn test_parse_json_with_stats() {
+        let args = vec!["ggo", "--stats", "--json"];
+        let cli = Cli::parse_from(args);
+
+        assert!(cli.json);
+        assert!(cli.stats);
+    }
+
+    #[test]
+    fn test_parse_stats_only() {
+        let args = vec!["ggo", "--stats"];
+        let cli = Cli::parse_from(args);
+
+        assert_eq!(cli.pattern, None);
+        assert!(cli.stats);
+    }
+
+    #[test]
+    fn test_parse_stats_repo_pattern() {
+        let cli = Cli::parse_from(["ggo", "--stats", "api"]);
+        assert!(cli.stats);
+        assert_eq!(cli.pattern, Some("api".to_string()));
 
-        assert_eq!(cli.pattern, Some("main".to_string()));
-        assert!(cli.no_fuzzy);
+        assert!(Cli::try_parse_from(["ggo", "--stats", "--this-repo", "api"]).is_err());
     }
 
     #[test]
-    fn test_parse_with_interactive() {
-        let args = vec!["ggo", "--interactive", "feature"];
-        let cli = Cli::parse_from(args);
-
-        assert_eq!(cli.pattern, Some("feature".to_string()));
-        assert!(cli.interactive);
+    fn test_parse_explain_command() {
+        let cli = Cli::parse_from(["ggo", "-i", "explain", "auth"]);
+        assert!(cli.ignore_case);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Explain {
+                pattern: "auth".to_string()
+            })
+        );
+        assert!(Cli::try_parse_from(["ggo", "explain"]).is_err());
     }
 
     #[test]
-    fn test_parse_stats_only() {
-        let args = vec!["ggo", "--stats"];
+    fn test_parse_stats_filters() {
+        let cli = Cli::parse_from(["ggo", "--stats"]);
+        assert!(!cli.this_repo);
+        assert_eq!(cli.top, 10);
+        assert_eq!(cli.sort, "score");
+        assert_eq!(cli.since, None);
+        assert!(!cli.heatmap);
+        assert_eq!(cli.format, None);
+
+        let args = vec![
+            "ggo",
+            "--stats",
+            "--this-repo",
+            "--top",
+            "5",
+            "--sort",
+            "recent",
+            "--since",
+            "30d",
+            "--heatmap",
+        ];
         let cli = Cli::parse_from(args);
+        assert!(cli.this_repo);
+        assert!(cli.heatmap);
+        assert_eq!(cli.top, 5);
+        assert_eq!(cli.sort, "recent");
+        assert_eq!(cli.since, Some("30d".to_string()));
+
+        assert!(Cli::try_parse_from(["ggo", "--stats", "--sort", "name"]).is_err());
+        assert!(Cli::try_parse_from(["ggo", "--top", "5"]).is_err());
+        assert!(Cli::try_parse_from(["ggo", "--heatmap"]).is_err());
+
+        let cli = Cli::parse_from(["ggo", "--stats", "--format", "csv"]);
+        assert_eq!(cli.format, Some("csv".to_string()));
+        assert!(Cli::try_parse_from(["ggo", "--stats", "--format", "xml"]).is_err());
+        assert!(Cli::try_parse_from(["ggo", "--stats", "--json", "--format", "csv"]).is_err());
+    }
 
-        assert_eq!(cli.pattern, None);
-        assert!(cli.stats);
+    #[test]
+    fn test_parse_list_format() {
+        let cli = Cli::parse_from(["ggo", "-l", "feat", "--format", "{name}\\t{score}"]);
+        assert!(cli.list);
+        assert_eq!(cli.format, Some("{name}\\t{score}".to_string()));
+
+        // Needs --list or --stats
+        assert!(Cli::try_parse_from(["ggo", "feat", "--format", "{name}"]).is_err());
+        assert!(
+            Cli::try_parse_from(["ggo", "-l", "feat", "--json", "--format", "{name}"]).is_err()
+        );
     }
 
     #[test]
@@ -256,6 +1277,17 @@ mod tests {
         assert_eq!(cli.pattern, Some("日本語".to_string()));
     }
 
+    #[test]
+    fn test_parse_list_limit() {
+        let cli = Cli::parse_from(["ggo", "-l", "feat", "-n", "5"]);
+        assert_eq!(cli.limit, 5);
+        assert_eq!(cli.pattern, Some("feat".to_string()));
+        assert_eq!(Cli::parse_from(["ggo", "-l", "feat"]).limit, 0);
+
+        // Only limits a listing
+        assert!(Cli::try_parse_from(["ggo", "feat", "--limit", "5"]).is_err());
+    }
+
     #[test]
     fn test_parse_list_before_pattern() {
         let args = vec!["ggo", "-l", "test"];
@@ -339,6 +1371,7 @@ mod tests {
         assert!(!cli.ignore_case);
         assert!(!cli.no_fuzzy);
         assert!(!cli.interactive);
+        assert!(!cli.create);
         assert!(!cli.stats);
     }
 
@@ -362,6 +1395,162 @@ mod tests {
         assert!(help.contains("--stats"));
     }
 
+    #[test]
+    fn test_parse_delete() {
+        let cli = Cli::parse_from(vec!["ggo", "delete", "-D", "feat"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Delete {
+                pattern: "feat".to_string(),
+                force: true,
+                yes: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_delete_requires_pattern() {
+        assert!(Cli::try_parse_from(vec!["ggo", "delete"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_batch() {
+        let cli = Cli::parse_from(vec!["ggo", "batch", "spike", "-a", "pin", "-y"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Batch {
+                pattern: "spike".to_string(),
+                action: Some("pin".to_string()),
+                into: None,
+                force: false,
+                yes: true,
+            })
+        );
+
+        assert!(Cli::try_parse_from(vec!["ggo", "batch", "spike", "-a", "archive"]).is_err());
+        // Without the menu there's nothing to ask for the action
+        assert!(Cli::try_parse_from(vec!["ggo", "batch", "spike", "--yes"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_hooks() {
+        let cli = Cli::parse_from(["ggo", "hooks", "trust"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Hooks {
+                action: Some(HooksCommand::Trust)
+            })
+        );
+
+        let cli = Cli::parse_from(["ggo", "hooks"]);
+        assert_eq!(cli.command, Some(Commands::Hooks { action: None }));
+        assert!(Cli::try_parse_from(["ggo", "hooks", "trust", "extra"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_group() {
+        let cli = Cli::parse_from(["ggo", "group", "add", "backend", "feature/api", "svc/*"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Group {
+                action: Some(GroupCommand::Add {
+                    name: "backend".to_string(),
+                    branches: vec!["feature/api".to_string(), "svc/*".to_string()],
+                }),
+                name: None,
+            })
+        );
+        assert!(Cli::try_parse_from(["ggo", "group", "add", "backend"]).is_err());
+
+        let cli = Cli::parse_from(["ggo", "group", "backend"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Group {
+                action: None,
+                name: Some("backend".to_string()),
+            })
+        );
+
+        let cli = Cli::parse_from(["ggo", "-l", "feat", "--group", "backend"]);
+        assert_eq!(cli.group.as_deref(), Some("backend"));
+        let cli = Cli::parse_from(["ggo", "recent", "--group", "backend"]);
+        assert_eq!(cli.group.as_deref(), Some("backend"));
+    }
+
+    #[test]
+    fn test_parse_prune() {
+        let cli = Cli::parse_from(vec!["ggo", "prune", "--fetch", "-n"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Prune {
+                fetch: true,
+                dry_run: true,
+                yes: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_stale() {
+        let cli = Cli::parse_from(vec!["ggo", "stale"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Stale {
+                days: 90,
+                mine: false,
+                force: false,
+                dry_run: false,
+                yes: false,
+            })
+        );
+
+        let cli = Cli::parse_from(vec!["ggo", "stale", "--days", "30", "--mine", "-D", "-y"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Stale {
+                days: 30,
+                mine: true,
+                force: true,
+                dry_run: false,
+                yes: true,
+            })
+        );
+    }
+
+    // Config command tests
+    #[test]
+    fn test_parse_config_set() {
+        let args = vec!["ggo", "config", "set", "behavior.picker", "tui"];
+        let cli = Cli::parse_from(args);
+
+        assert_eq!(
+            cli.command,
+            Some(Commands::Config {
+                action: ConfigCommand::Set {
+                    key: "behavior.picker".to_string(),
+                    value: "tui".to_string(),
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_config_get_requires_key() {
+        let result = Cli::try_parse_from(vec!["ggo", "config", "get"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_config_path() {
+        let cli = Cli::parse_from(vec!["ggo", "config", "path"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Config {
+                action: ConfigCommand::Path
+            })
+        );
+    }
+
     // Cleanup command tests
     #[test]
     fn test_parse_cleanup_default() {
@@ -372,16 +1561,45 @@ mod tests {
             Some(Commands::Cleanup {
                 older_than,
                 deleted,
+                compact,
                 optimize,
                 size,
+                dry_run,
+                current,
+                restore_backup,
             }) => {
                 assert_eq!(older_than, 365); // Default value
+                assert!(!restore_backup);
                 assert!(!deleted);
+                assert!(!compact);
                 assert!(!optimize);
                 assert!(!size);
+                assert!(!dry_run);
+                assert!(!current);
+            }
+            _ => panic!("Expected Cleanup command"),
+        }
+    }
+
+    #[test]
+    fn test_parse_cleanup_dry_run_scoped() {
+        let cli = Cli::parse_from(["ggo", "cleanup", "--deleted", "-n", "--current"]);
+        match cli.command {
+            Some(Commands::Cleanup {
+                deleted,
+                dry_run,
+                current,
+                ..
+            }) => {
+                assert!(deleted);
+                assert!(dry_run);
+                assert!(current);
             }
             _ => panic!("Expected Cleanup command"),
         }
+
+        let cli = Cli::parse_from(["ggo", "cleanup", "--deleted", "--repo", "/old"]);
+        assert_eq!(cli.repo, Some(std::path::PathBuf::from("/old")));
     }
 
     #[test]
@@ -423,6 +1641,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_cleanup_compact() {
+        let args = vec!["ggo", "cleanup", "--compact"];
+        let cli = Cli::parse_from(args);
+
+        match cli.command {
+            Some(Commands::Cleanup { compact, .. }) => {
+                assert!(compact);
+            }
+            _ => panic!("Expected Cleanup command"),
+        }
+    }
+
     #[test]
     fn test_parse_cleanup_size() {
         let args = vec!["ggo", "cleanup", "--size"];
@@ -436,6 +1667,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_cleanup_restore_backup() {
+        let cli = Cli::parse_from(["ggo", "cleanup", "--restore-backup"]);
+
+        match cli.command {
+            Some(Commands::Cleanup { restore_backup, .. }) => {
+                assert!(restore_backup);
+            }
+            _ => panic!("Expected Cleanup command"),
+        }
+    }
+
     #[test]
     fn test_parse_cleanup_all_flags() {
         let args = vec![
@@ -444,6 +1687,7 @@ mod tests {
             "--older-than",
             "30",
             "--deleted",
+            "--compact",
             "--optimize",
             "--size",
         ];
@@ -453,11 +1697,14 @@ mod tests {
             Some(Commands::Cleanup {
                 older_than,
                 deleted,
+                compact,
                 optimize,
                 size,
+                ..
             }) => {
                 assert_eq!(older_than, 30);
                 assert!(deleted);
+                assert!(compact);
                 assert!(optimize);
                 assert!(size);
             }
@@ -465,7 +1712,733 @@ mod tests {
         }
     }
 
+    // Recent command tests
+    #[test]
+    fn test_parse_current() {
+        let cli = Cli::parse_from(["ggo", "current"]);
+        assert_eq!(cli.command, Some(Commands::Current { porcelain: false }));
+
+        let cli = Cli::parse_from(["ggo", "current", "--porcelain"]);
+        assert_eq!(cli.command, Some(Commands::Current { porcelain: true }));
+    }
+
+    #[test]
+    fn test_parse_recent_default() {
+        let args = vec!["ggo", "recent"];
+        let cli = Cli::parse_from(args);
+
+        match cli.command {
+            Some(Commands::Recent { limit, interactive }) => {
+                assert_eq!(limit, 10);
+                assert!(!interactive);
+            }
+            _ => panic!("Expected Recent command"),
+        }
+    }
+
+    #[test]
+    fn test_parse_ignore() {
+        let cli = Cli::parse_from(["ggo", "ignore", "release/*"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Ignore {
+                pattern: Some("release/*".to_string()),
+                remove: false
+            })
+        );
+
+        let cli = Cli::parse_from(["ggo", "ignore", "--remove", "release/*"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Ignore {
+                pattern: Some("release/*".to_string()),
+                remove: true
+            })
+        );
+
+        assert!(Cli::try_parse_from(["ggo", "ignore", "--remove"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_no_record() {
+        let cli = Cli::parse_from(["ggo", "--no-record", "feature"]);
+        assert!(cli.no_record);
+        assert_eq!(cli.pattern, Some("feature".to_string()));
+
+        // Global, so it also applies to subcommands that check out
+        let cli = Cli::parse_from(["ggo", "pr", "12", "--no-record"]);
+        assert!(cli.no_record);
+    }
+
+    #[test]
+    fn test_parse_detach() {
+        let cli = Cli::parse_from(["ggo", "--detach", "v1.2"]);
+        assert!(cli.detach);
+        assert_eq!(cli.pattern, Some("v1.2".to_string()));
+
+        assert!(Cli::try_parse_from(["ggo", "--detach", "-l", "v1"]).is_err());
+        assert!(Cli::try_parse_from(["ggo", "--detach", "--force-detach", "v1"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_force() {
+        let cli = Cli::parse_from(["ggo", "-f", "main"]);
+        assert!(cli.force);
+
+        // Deleting keeps its own --force
+        let cli = Cli::parse_from(["ggo", "delete", "old", "--force"]);
+        assert!(!cli.force);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Delete { force: true, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_slow_stats_and_log_file() {
+        let cli = Cli::parse_from(["ggo", "--stats", "--slow", "--top", "3"]);
+        assert!(cli.slow);
+        assert_eq!(cli.top, 3);
+        assert!(Cli::try_parse_from(["ggo", "--slow"]).is_err());
+        assert!(Cli::try_parse_from(["ggo", "--stats", "--slow", "--heatmap"]).is_err());
+
+        let cli = Cli::parse_from(["ggo", "--log-file", "/tmp/ggo.jsonl", "feat"]);
+        assert_eq!(
+            cli.log_file,
+            Some(std::path::PathBuf::from("/tmp/ggo.jsonl"))
+        );
+        assert_eq!(cli.pattern, Some("feat".to_string()));
+
+        let cli = Cli::parse_from(["ggo", "recent", "--log-file", "run.jsonl"]);
+        assert!(cli.log_file.is_some());
+    }
+
+    #[test]
+    fn test_parse_data_dir() {
+        let cli = Cli::parse_from(["ggo", "--data-dir", "~/dotfiles/ggo", "--stats"]);
+        assert_eq!(
+            cli.data_dir,
+            Some(std::path::PathBuf::from("~/dotfiles/ggo"))
+        );
+
+        let cli = Cli::parse_from(["ggo", "recent", "--data-dir", "/srv/ggo"]);
+        assert_eq!(cli.data_dir, Some(std::path::PathBuf::from("/srv/ggo")));
+        assert!(Cli::parse_from(["ggo", "feat"]).data_dir.is_none());
+    }
+
+    #[test]
+    fn test_parse_no_hooks() {
+        let cli = Cli::parse_from(["ggo", "--no-hooks", "feature"]);
+        assert!(cli.no_hooks);
+
+        let cli = Cli::parse_from(["ggo", "undo", "--no-hooks"]);
+        assert!(cli.no_hooks);
+    }
+
+    #[test]
+    fn test_parse_forget() {
+        let cli = Cli::parse_from(["ggo", "forget", "spike/*", "--dry-run"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Forget {
+                pattern: Some("spike/*".to_string()),
+                dry_run: true,
+            })
+        );
+
+        let cli = Cli::parse_from(["ggo", "forget", "--repo", "/old/repo"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Forget {
+                pattern: None,
+                dry_run: false,
+            })
+        );
+        assert_eq!(cli.repo, Some(std::path::PathBuf::from("/old/repo")));
+    }
+
+    #[test]
+    fn test_parse_export_import() {
+        let cli = Cli::parse_from(["ggo", "export"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Export {
+                format: "json".to_string(),
+                path: None,
+            })
+        );
+
+        let cli = Cli::parse_from(["ggo", "export", "--format", "csv", "out.csv"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Export {
+                format: "csv".to_string(),
+                path: Some(std::path::PathBuf::from("out.csv")),
+            })
+        );
+        assert!(Cli::try_parse_from(["ggo", "export", "--format", "xml"]).is_err());
+
+        let cli = Cli::parse_from(["ggo", "import", "--merge", "in.json"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Import {
+                path: Some(std::path::PathBuf::from("in.json")),
+                merge: true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_sync() {
+        let cli = Cli::parse_from(["ggo", "sync"]);
+        assert_eq!(cli.command, Some(Commands::Sync));
+    }
+
+    #[test]
+    fn test_parse_migrate_storage() {
+        let cli = Cli::parse_from(["ggo", "migrate-storage", "encrypted", "--force"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::MigrateStorage {
+                to: "encrypted".to_string(),
+                force: true
+            })
+        );
+
+        assert!(Cli::try_parse_from(["ggo", "migrate-storage", "postgres"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_serve() {
+        let cli = Cli::parse_from(["ggo", "serve"]);
+        assert_eq!(cli.command, Some(Commands::Serve { socket: None }));
+
+        let cli = Cli::parse_from(["ggo", "serve", "--socket", "/tmp/ggo.sock"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Serve {
+                socket: Some(std::path::PathBuf::from("/tmp/ggo.sock"))
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let cli = Cli::parse_from(["ggo", "query", "auth", "--json", "--limit", "20"]);
+        assert!(cli.json);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Query {
+                pattern: Some("auth".to_string()),
+                limit: 20
+            })
+        );
+
+        let cli = Cli::parse_from(["ggo", "query"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Query {
+                pattern: None,
+                limit: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_switch_last_modified() {
+        let cli = Cli::parse_from(["ggo", "switch-last-modified"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::SwitchLastModified {
+                list: false,
+                interactive: false
+            })
+        );
+
+        let cli = Cli::parse_from(["ggo", "switch-last-modified", "-l"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::SwitchLastModified {
+                list: true,
+                interactive: false
+            })
+        );
+
+        assert!(Cli::try_parse_from(["ggo", "switch-last-modified", "-l", "-i"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_undo() {
+        let cli = Cli::parse_from(["ggo", "undo"]);
+        assert_eq!(cli.command, Some(Commands::Undo));
+    }
+
+    #[test]
+    fn test_parse_setup() {
+        let cli = Cli::parse_from(["ggo", "setup"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Setup {
+                shell: None,
+                yes: false
+            })
+        );
+
+        let cli = Cli::parse_from(["ggo", "setup", "--shell", "fish", "-y"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Setup {
+                shell: Some("fish".to_string()),
+                yes: true
+            })
+        );
+        assert!(Cli::try_parse_from(["ggo", "setup", "--shell", "tcsh"]).is_err());
+    }
+
+    #[cfg(feature = "self-update")]
+    #[test]
+    fn test_parse_self_update() {
+        let cli = Cli::parse_from(["ggo", "self-update"]);
+        assert_eq!(cli.command, Some(Commands::SelfUpdate { check: false }));
+
+        let cli = Cli::parse_from(["ggo", "self-update", "--check"]);
+        assert_eq!(cli.command, Some(Commands::SelfUpdate { check: true }));
+    }
+
+    #[test]
+    fn test_parse_init() {
+        let cli = Cli::parse_from(["ggo", "init", "zsh"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Init {
+                shell: "zsh".to_string()
+            })
+        );
+
+        assert!(Cli::try_parse_from(["ggo", "init", "powershell"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_record_current() {
+        let cli = Cli::parse_from(["ggo", "--record-current"]);
+        assert!(cli.record_current);
+        assert!(cli.pattern.is_none());
+
+        let cli = Cli::parse_from(["ggo", "record-current"]);
+        assert_eq!(cli.command, Some(Commands::RecordCurrent));
+    }
+
+    #[test]
+    fn test_parse_find() {
+        let cli = Cli::parse_from(["ggo", "find", "-m", "retry", "-t", "src/pay", "-u"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Find {
+                message: Some("retry".to_string()),
+                author: None,
+                touches: Some("src/pay".to_string()),
+                unmerged: true,
+                list: false,
+            })
+        );
+
+        // At least one criterion
+        assert!(Cli::try_parse_from(["ggo", "find"]).is_err());
+        assert!(Cli::try_parse_from(["ggo", "find", "--unmerged"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_contains() {
+        let cli = Cli::parse_from(["ggo", "contains", "1a2b3c4"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Contains {
+                commit: "1a2b3c4".to_string()
+            })
+        );
+        assert!(Cli::try_parse_from(["ggo", "contains"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_note() {
+        let cli = Cli::parse_from(["ggo", "note", "feat/api", "waiting on review"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Note {
+                branch: Some("feat/api".to_string()),
+                text: Some("waiting on review".to_string()),
+                remove: false,
+            })
+        );
+
+        let cli = Cli::parse_from(["ggo", "note", "-r", "feat/api"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Note {
+                branch: Some("feat/api".to_string()),
+                text: None,
+                remove: true,
+            })
+        );
+
+        assert!(Cli::try_parse_from(["ggo", "note", "-r"]).is_err());
+        assert!(Cli::try_parse_from(["ggo", "note", "-r", "feat/api", "text"]).is_err());
+
+        let cli = Cli::parse_from(["ggo", "-l", "--notes", "review"]);
+        assert!(cli.list && cli.notes);
+    }
+
+    #[test]
+    fn test_parse_rename_record() {
+        let cli = Cli::parse_from(["ggo", "rename-record", "feat/old", "feat/new"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::RenameRecord {
+                old: "feat/old".to_string(),
+                new: "feat/new".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_stack() {
+        let cli = Cli::parse_from(["ggo", "stack"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Stack {
+                action: None,
+                all: false
+            })
+        );
+
+        let cli = Cli::parse_from(["ggo", "stack", "on", "main", "-b", "feature/a"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Stack {
+                action: Some(StackCommand::On {
+                    parent: "main".to_string(),
+                    branch: Some("feature/a".to_string()),
+                }),
+                all: false
+            })
+        );
+
+        let cli = Cli::parse_from(["ggo", "up", "2"]);
+        assert_eq!(cli.command, Some(Commands::Up { steps: 2 }));
+        let cli = Cli::parse_from(["ggo", "down"]);
+        assert_eq!(cli.command, Some(Commands::Down { steps: 1 }));
+    }
+
+    #[test]
+    fn test_parse_repo() {
+        let cli = Cli::parse_from(["ggo", "repo", "api"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Repo {
+                pattern: Some("api".to_string())
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_wt() {
+        let cli = Cli::parse_from(["ggo", "wt", "auth", "-c", "--path", "/tmp/auth"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Wt {
+                pattern: Some("auth".to_string()),
+                create: true,
+                path: Some(std::path::PathBuf::from("/tmp/auth")),
+            })
+        );
+
+        let cli = Cli::parse_from(["ggo", "wt"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Wt {
+                pattern: None,
+                create: false,
+                path: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_tag() {
+        let cli = Cli::parse_from(["ggo", "tag", "v1.2", "-b", "hotfix/1.2"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Tag {
+                pattern: Some("v1.2".to_string()),
+                branch: Some("hotfix/1.2".to_string()),
+            })
+        );
+
+        let cli = Cli::parse_from(["ggo", "tag"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Tag {
+                pattern: None,
+                branch: None,
+            })
+        );
+
+        let cli = Cli::parse_from(["ggo", "--tags", "v1"]);
+        assert!(cli.tags);
+        assert!(Cli::try_parse_from(["ggo", "--tags", "--detach", "v1"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_pr() {
+        let cli = Cli::parse_from(["ggo", "pr", "1234"]);
+        assert_eq!(cli.command, Some(Commands::Pr { number: 1234 }));
+
+        let cli = Cli::parse_from(["ggo", "pr", "#42"]);
+        assert_eq!(cli.command, Some(Commands::Pr { number: 42 }));
+
+        assert!(Cli::try_parse_from(["ggo", "pr", "abc"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_open() {
+        let cli = Cli::parse_from(["ggo", "open", "auth", "--pr", "--url"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Open {
+                pattern: Some("auth".to_string()),
+                pr: true,
+                url: true,
+            })
+        );
+
+        let cli = Cli::parse_from(["ggo", "open"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Open {
+                pattern: None,
+                pr: false,
+                url: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_pin_and_unpin() {
+        let cli = Cli::parse_from(["ggo", "pin", "main"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Pin {
+                branch: Some("main".to_string())
+            })
+        );
+
+        let cli = Cli::parse_from(["ggo", "pin"]);
+        assert_eq!(cli.command, Some(Commands::Pin { branch: None }));
+
+        let cli = Cli::parse_from(["ggo", "unpin", "main"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Unpin {
+                branch: "main".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_suggest() {
+        let cli = Cli::parse_from(["ggo", "suggest"]);
+        assert_eq!(cli.command, Some(Commands::Suggest { limit: 5 }));
+
+        let cli = Cli::parse_from(["ggo", "suggest", "-n", "2"]);
+        assert_eq!(cli.command, Some(Commands::Suggest { limit: 2 }));
+    }
+
+    #[test]
+    fn test_parse_nth() {
+        let cli = Cli::parse_from(["ggo", "--nth", "3"]);
+        assert_eq!(cli.nth, Some(3));
+        assert_eq!(cli.pattern, None);
+
+        assert!(Cli::try_parse_from(["ggo", "--nth", "0"]).is_err());
+        assert!(Cli::try_parse_from(["ggo", "--nth", "2", "-l", "feat"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_pick_and_first_flags() {
+        assert!(Cli::parse_from(["ggo", "--pick", "feat"]).pick);
+        assert!(Cli::parse_from(["ggo", "--first", "feat"]).first);
+
+        assert!(Cli::try_parse_from(["ggo", "--pick", "--first", "feat"]).is_err());
+        assert!(Cli::try_parse_from(["ggo", "--first", "--tui", "feat"]).is_err());
+        assert!(Cli::try_parse_from(["ggo", "--pick", "--no-interactive", "feat"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_no_interactive_flag() {
+        let cli = Cli::parse_from(["ggo", "--no-interactive", "feat"]);
+        assert!(cli.no_interactive);
+
+        assert!(Cli::try_parse_from(["ggo", "--no-interactive", "--interactive", "feat"]).is_err());
+        assert!(Cli::try_parse_from(["ggo", "--no-interactive", "--tui", "feat"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_color_flag() {
+        let cli = Cli::parse_from(["ggo", "-l", "feat"]);
+        assert_eq!(cli.color, "auto");
+
+        let cli = Cli::parse_from(["ggo", "--stats", "--color", "never"]);
+        assert_eq!(cli.color, "never");
+
+        assert!(Cli::try_parse_from(["ggo", "--color", "sometimes", "feat"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_alias() {
+        let cli = Cli::parse_from(["ggo", "alias", "m", "master"]);
+        assert_eq!(
+            cli.command,
+            Some(Commands::Alias {
+                action: None,
+                alias: Some("m".to_string()),
+                branch: Some("master".to_string()),
+                list: false,
+                all: false,
+                remove: false,
+                rename: false,
+            })
+        );
+
+        let cli = Cli::parse_from(["ggo", "alias", "--list", "--all"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Alias {
+                list: true,
+                all: true,
+                alias: None,
+                ..
+            })
+        ));
+        assert!(Cli::try_parse_from(["ggo", "alias", "--all", "m"]).is_err());
+
+        let cli = Cli::parse_from(["ggo", "alias", "--rename", "m", "mn"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Alias { rename: true, .. })
+        ));
+        assert!(Cli::try_parse_from(["ggo", "alias", "--rename", "m"]).is_err());
+        assert!(Cli::try_parse_from(["ggo", "alias", "--rename", "-r", "m", "mn"]).is_err());
+
+        let cli = Cli::parse_from(["ggo", "alias", "export"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Alias {
+                action: Some(AliasCommand::Export),
+                alias: None,
+                ..
+            })
+        ));
+        let cli = Cli::parse_from(["ggo", "alias", "import", "aliases.toml"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Alias {
+                action: Some(AliasCommand::Import {
+                    path: Some(_),
+                    force: false
+                }),
+                ..
+            })
+        ));
+        assert!(Cli::try_parse_from(["ggo", "alias"]).is_err());
+
+        let cli = Cli::parse_from(["ggo", "alias", "rel", "release/{}"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Alias { branch: Some(b), .. }) if b == "release/{}"
+        ));
+        let cli = Cli::parse_from(["ggo", "rel", "1.42"]);
+        assert_eq!(cli.pattern.as_deref(), Some("rel"));
+        assert_eq!(cli.args, vec!["1.42".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_quiet_flag() {
+        let cli = Cli::parse_from(["ggo", "--quiet", "feat"]);
+        assert!(cli.quiet);
+
+        let cli = Cli::parse_from(["ggo", "alias", "--list", "--quiet"]);
+        assert!(cli.quiet);
+
+        let cli = Cli::parse_from(["ggo", "-q", "feat"]);
+        assert!(cli.quiet);
+    }
+
+    #[test]
+    fn test_parse_verbosity() {
+        assert_eq!(Cli::parse_from(["ggo", "feat"]).verbose, 0);
+        assert_eq!(Cli::parse_from(["ggo", "-v", "feat"]).verbose, 1);
+        assert_eq!(Cli::parse_from(["ggo", "-vv", "feat"]).verbose, 2);
+        assert_eq!(
+            Cli::parse_from(["ggo", "recent", "-v", "-v", "-v"]).verbose,
+            3
+        );
+        assert!(Cli::try_parse_from(["ggo", "-v", "-q", "feat"]).is_err());
+
+        // -V stays the short version flag
+        assert!(Cli::parse_from(["ggo", "-V"]).version);
+    }
+
+    #[test]
+    fn test_parse_print_flag() {
+        let cli = Cli::parse_from(["ggo", "-p", "feat"]);
+        assert!(cli.print);
+        assert_eq!(cli.pattern, Some("feat".to_string()));
+
+        let cli = Cli::parse_from(["ggo", "--print", "-"]);
+        assert!(cli.print);
+
+        assert!(Cli::try_parse_from(["ggo", "-p", "-l", "feat"]).is_err());
+        assert!(Cli::try_parse_from(["ggo", "-p", "--interactive", "feat"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_suggest_flag() {
+        let cli = Cli::parse_from(["ggo", "--suggest", "feat"]);
+        assert!(cli.suggest);
+        assert_eq!(cli.pattern, Some("feat".to_string()));
+    }
+
+    #[test]
+    fn test_parse_recent_with_limit_and_interactive() {
+        let args = vec!["ggo", "recent", "-n", "3", "--interactive"];
+        let cli = Cli::parse_from(args);
+
+        match cli.command {
+            Some(Commands::Recent { limit, interactive }) => {
+                assert_eq!(limit, 3);
+                assert!(interactive);
+            }
+            _ => panic!("Expected Recent command"),
+        }
+    }
+
+    #[test]
+    fn test_parse_recent_invalid_limit() {
+        let args = vec!["ggo", "recent", "-n", "many"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
     // Shell completion tests
+    #[test]
+    fn test_parse_generate_assets() {
+        let cli = Cli::parse_from(["ggo", "--generate-assets", "target/assets"]);
+
+        assert_eq!(
+            cli.generate_assets,
+            Some(std::path::PathBuf::from("target/assets"))
+        );
+    }
+
     #[test]
     fn test_parse_generate_completion_bash() {
         let args = vec!["ggo", "--generate-completion", "bash"];
@@ -0,0 +1,263 @@
+//! 'ggo alias': saved shortcuts to branches, and their export and import.
+
+use std::collections::HashSet;
+
+use crate::error::{GgoError, Result};
+use crate::export::AliasFile;
+use crate::switch::repo_key;
+use crate::{alias, config, git, storage, style, validation};
+
+/// Handle alias subcommand operations
+pub fn handle_alias_command(
+    storage: &storage::Storage,
+    alias: Option<&str>,
+    branch: Option<&str>,
+    list: bool,
+    remove: bool,
+    rename: bool,
+    config: &config::Config,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+
+    // Handle --list flag
+    if list {
+        let aliases = storage.aliases(&repo_path)?;
+        if aliases.is_empty() {
+            println!("No aliases defined for this repository");
+        } else {
+            println!("Aliases for this repository:\n");
+            for a in aliases {
+                println!("  {} → {}", a.alias, a.branch_name);
+            }
+        }
+        return Ok(());
+    }
+
+    // Alias is required for other operations
+    let alias = alias.ok_or_else(|| GgoError::Other("Alias name is required".to_string()))?;
+
+    // Handle --remove flag
+    if remove {
+        storage.delete_alias(&repo_path, alias)?;
+        println!("Removed alias '{}'", alias);
+        return Ok(());
+    }
+
+    // With --rename, the second argument is the alias's new name
+    if rename {
+        let new =
+            branch.ok_or_else(|| GgoError::Other("New alias name is required".to_string()))?;
+        validation::validate_alias_name(new)?;
+        if storage.alias(&repo_path, new)?.is_some() {
+            return Err(GgoError::AliasExists(new.to_string()));
+        }
+        if !storage.rename_alias(&repo_path, alias, new)? {
+            return Err(GgoError::AliasNotFound(alias.to_string()));
+        }
+        println!("Renamed alias '{}' to '{}'", alias, new);
+        return Ok(());
+    }
+
+    // A target with '{}' is a template filled in when the alias is used
+    if let Some(template) = branch.filter(|b| alias::is_template(b)) {
+        validation::validate_alias_name(alias)?;
+        alias::validate_template(template)?;
+
+        storage.create_alias(&repo_path, alias, template)?;
+        let usage = vec!["<value>"; template.matches(alias::PLACEHOLDER).count()].join(" ");
+        println!(
+            "Created template alias '{}' → '{}' (use: ggo {} {})",
+            alias, template, alias, usage
+        );
+        return Ok(());
+    }
+
+    // If branch is provided, create/update alias
+    if let Some(branch_name) = branch {
+        // Validate alias name
+        validation::validate_alias_name(alias)?;
+
+        // Validate branch name
+        validation::validate_branch_name(branch_name)?;
+
+        // Validate that branch exists
+        let branches = git::get_branches()?;
+        if !branches.contains(&branch_name.to_string()) {
+            return Err(GgoError::BranchNotFound(branch_name.to_string()));
+        }
+
+        // Create/update the alias
+        storage.create_alias(&repo_path, alias, branch_name)?;
+        println!("Created alias '{}' → '{}'", alias, branch_name);
+        return Ok(());
+    }
+
+    // No branch provided: show what alias points to
+    match storage.alias(&repo_path, alias)? {
+        Some(branch_name) => {
+            println!("{} → {}", alias, branch_name);
+        }
+        None => {
+            println!("Alias '{}' not found", alias);
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle 'ggo alias --list --all': every repository's aliases, marking
+/// ones whose branch (✓/✗) or repository is gone. Repositories keyed by
+/// remote URL have no path to check.
+pub fn list_all_aliases(storage: &storage::Storage, config: &config::Config) -> Result<()> {
+    let all = storage.all_aliases()?;
+    if all.is_empty() {
+        println!("No aliases defined");
+        return Ok(());
+    }
+
+    let current = repo_key(storage, config).ok();
+    for (i, (repo_path, aliases)) in all.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+
+        let path = std::path::Path::new(repo_path);
+        let branches = path.is_absolute().then(|| git::branches_at(path)).flatten();
+        let note = if current.as_deref() == Some(repo_path.as_str()) {
+            " (current)"
+        } else if path.is_absolute() && branches.is_none() {
+            " (repository not found)"
+        } else {
+            ""
+        };
+        println!(
+            "{}{}",
+            style::paint(style::Role::Header, repo_path),
+            style::paint(style::Role::Dim, note)
+        );
+
+        for a in aliases {
+            let (mark, detail) = if alias::is_template(&a.branch_name) {
+                (" ", " (template)")
+            } else {
+                match &branches {
+                    Some(branches) if branches.contains(&a.branch_name) => ("✓", ""),
+                    Some(_) => ("✗", " (branch deleted)"),
+                    None => (" ", ""),
+                }
+            };
+            println!(
+                "  {} {} → {}{}",
+                mark,
+                style::paint(style::Role::Alias, &a.alias),
+                a.branch_name,
+                style::paint(style::Role::Dim, detail)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Expand a template alias (`ggo alias rel "release/{}"`) with the words
+/// after it, so `ggo rel 1.42` matches the pattern `release/1.42`. None if
+/// `pattern` isn't a template alias; extra words are an error then.
+pub fn expand_alias_template(
+    storage: &storage::Storage,
+    pattern: &str,
+    args: &[String],
+    config: &config::Config,
+) -> Result<Option<String>> {
+    let target = match repo_key(storage, config) {
+        Ok(repo_path) => storage.alias(&repo_path, pattern)?,
+        Err(_) => None,
+    };
+
+    match target {
+        Some(template) if alias::is_template(&template) => {
+            alias::expand(pattern, &template, args).map(Some)
+        }
+        _ if args.is_empty() => Ok(None),
+        _ => Err(GgoError::Other(format!(
+            "Unexpected argument '{}'\n\nOnly template aliases (such as 'release/{{}}') take arguments. To match several words, quote them: ggo \"{} {}\"",
+            args[0],
+            pattern,
+            args.join(" ")
+        ))),
+    }
+}
+
+/// Handle 'ggo alias export': print this repository's aliases as TOML
+pub fn handle_alias_export(storage: &storage::Storage, config: &config::Config) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+    let file = AliasFile {
+        aliases: storage
+            .aliases(&repo_path)?
+            .into_iter()
+            .map(|a| (a.alias, a.branch_name))
+            .collect(),
+    };
+    print!("{}", file.render()?);
+
+    Ok(())
+}
+
+/// Handle 'ggo alias import': create the aliases in a file or standard input
+/// whose names are valid and whose branches exist, and report the rest
+pub fn handle_alias_import(
+    storage: &storage::Storage,
+    path: Option<&std::path::Path>,
+    force: bool,
+    config: &config::Config,
+) -> Result<()> {
+    let content = match path.filter(|p| p.as_os_str() != "-") {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => std::io::read_to_string(std::io::stdin())?,
+    };
+    let file = AliasFile::parse(&content)?;
+
+    let repo_path = repo_key(storage, config)?;
+    let branches: HashSet<String> = git::get_branches()?.into_iter().collect();
+
+    let mut imported = 0;
+    let mut skipped = Vec::new();
+    let mut conflicts = 0;
+    for (alias, branch) in &file.aliases {
+        // Never replace an alias pointing elsewhere without being asked
+        let existing = storage
+            .alias(&repo_path, alias)?
+            .filter(|existing| existing != branch && !force);
+        if let Err(e) = validation::validate_alias_name(alias) {
+            skipped.push((alias, branch, e.summary()));
+        } else if let Some(existing) = existing {
+            skipped.push((alias, branch, format!("already an alias for {}", existing)));
+            conflicts += 1;
+        } else if alias::is_template(branch) {
+            match alias::validate_template(branch) {
+                Ok(()) => {
+                    storage.create_alias(&repo_path, alias, branch)?;
+                    imported += 1;
+                }
+                Err(e) => skipped.push((alias, branch, e.summary())),
+            }
+        } else if !branches.contains(branch) {
+            skipped.push((alias, branch, "no such branch".to_string()));
+        } else {
+            storage.create_alias(&repo_path, alias, branch)?;
+            imported += 1;
+        }
+    }
+
+    println!("Imported {} aliases", imported);
+    if !skipped.is_empty() {
+        println!("Skipped {}:", skipped.len());
+        for (alias, branch, reason) in skipped {
+            println!("  {} → {} ({})", alias, branch, reason);
+        }
+    }
+    if conflicts > 0 {
+        println!("\nRun again with --force to replace the existing aliases.");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,258 @@
+//! 'ggo batch': one action (delete, pin, forget, ...) applied to every
+//! branch a pattern matches, with failures reported per branch.
+
+use crate::error::{GgoError, Result};
+use crate::ranking::rank_matching_branches;
+use crate::style::{self, Role};
+use crate::switch::repo_key;
+use crate::{config, git, interactive, storage, validation};
+
+use super::delete::{delete_branches, unattended_target, Force};
+use super::display_columns;
+
+/// Apply `apply` to each branch in turn. A failure is reported next to its
+/// branch and doesn't stop the rest; the error at the end counts them.
+pub(crate) fn run_batch(
+    branches: &[String],
+    verb: &str,
+    mut apply: impl FnMut(&str) -> Result<()>,
+) -> Result<()> {
+    let mut failed = 0;
+    for branch in branches {
+        if let Err(e) = apply(branch) {
+            eprintln!(
+                "{} '{}': {}",
+                style::paint_err(Role::Error, "Error:"),
+                branch,
+                e
+            );
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        return Err(GgoError::Other(format!(
+            "Failed to {} {} of {} branch(es)",
+            verb,
+            failed,
+            branches.len()
+        )));
+    }
+
+    Ok(())
+}
+
+/// What 'ggo batch' does with the selected branches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchAction {
+    Delete,
+    Pin,
+    Unpin,
+    Forget,
+    Group,
+}
+
+impl BatchAction {
+    const ALL: [BatchAction; 5] = [
+        Self::Delete,
+        Self::Pin,
+        Self::Unpin,
+        Self::Forget,
+        Self::Group,
+    ];
+
+    /// The action named on the command line
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Delete => "delete",
+            Self::Pin => "pin",
+            Self::Unpin => "unpin",
+            Self::Forget => "forget",
+            Self::Group => "group",
+        }
+    }
+
+    /// What the action menu shows
+    fn description(self) -> &'static str {
+        match self {
+            Self::Delete => "Delete the branches and their records",
+            Self::Pin => "Pin them to the top of the rankings",
+            Self::Unpin => "Unpin them",
+            Self::Forget => "Forget their usage records, keeping the branches",
+            Self::Group => "Add them to a group",
+        }
+    }
+}
+
+/// How 'ggo batch' was asked to act
+pub struct BatchOptions<'a> {
+    /// None: ask after the branches are selected
+    pub action: Option<BatchAction>,
+    /// Group for [`BatchAction::Group`] (None: ask)
+    pub into: Option<&'a str>,
+    /// Delete branches that aren't fully merged
+    pub force: bool,
+    /// Take every match, without the menus
+    pub yes: bool,
+}
+
+/// Handle 'ggo batch': pick branches matching `pattern`, then apply one action
+/// to all of them, reporting failures per branch
+pub fn handle_batch_command(
+    storage: &storage::Storage,
+    pattern: &str,
+    options: &BatchOptions,
+    can_prompt: bool,
+    config: &config::Config,
+) -> Result<()> {
+    validation::validate_pattern(pattern)?;
+    if !options.yes && !can_prompt {
+        return Err(GgoError::NotATerminal(
+            "Passing --action with -y to apply it to every matching branch",
+        ));
+    }
+
+    let repo_path = repo_key(storage, config)?;
+    let scoring = config.scoring_for(&repo_path);
+    let half_life_days = scoring.half_life_days;
+
+    let branches = git::get_branches()?;
+    let records = storage.branch_records(&repo_path).unwrap_or_default();
+    let ignore_case = config.behavior.ignore_case_for(pattern);
+
+    let selected = if options.yes && options.action == Some(BatchAction::Delete) {
+        // Deleting unprompted takes one named branch, like 'ggo delete -y'
+        vec![unattended_target(&branches, pattern, ignore_case)?]
+    } else {
+        let ranked = rank_matching_branches(
+            &branches,
+            pattern,
+            ignore_case,
+            config.behavior.default_fuzzy,
+            &records,
+            &scoring,
+            &config.matching.bonuses(),
+        );
+        if ranked.is_empty() {
+            return Err(GgoError::NoMatchingBranches(pattern.to_string()));
+        }
+
+        let candidates: Vec<String> = ranked.into_iter().map(|(b, _)| b).collect();
+        if options.yes {
+            candidates
+        } else {
+            interactive::select_branches(
+                "Select branches:",
+                &candidates,
+                &records,
+                display_columns(config),
+                half_life_days,
+            )?
+        }
+    };
+
+    // Nothing was picked by hand, so say what's about to happen
+    if let Some(action) = options.action.filter(|_| options.yes) {
+        println!("Branches to {}: {}", action.name(), selected.join(", "));
+    }
+
+    if selected.is_empty() {
+        println!("No branches selected.");
+        return Ok(());
+    }
+
+    let action = match options.action {
+        Some(action) => action,
+        None => {
+            let descriptions: Vec<&str> = BatchAction::ALL
+                .iter()
+                .map(|action| action.description())
+                .collect();
+            BatchAction::ALL[interactive::select_batch_action(selected.len(), &descriptions)?]
+        }
+    };
+
+    match action {
+        BatchAction::Delete => delete_branches(
+            storage,
+            &repo_path,
+            &selected,
+            &records,
+            if options.force { Force::All } else { Force::No },
+            options.yes,
+            half_life_days,
+        ),
+        BatchAction::Pin => run_batch(&selected, action.name(), |branch| {
+            storage.pin_branch(&repo_path, branch)?;
+            println!("Pinned '{}'", branch);
+            Ok(())
+        }),
+        BatchAction::Unpin => run_batch(&selected, action.name(), |branch| {
+            if storage.unpin_branch(&repo_path, branch)? {
+                println!("Unpinned '{}'", branch);
+            } else {
+                println!("'{}' is not pinned", branch);
+            }
+            Ok(())
+        }),
+        BatchAction::Forget => run_batch(&selected, action.name(), |branch| {
+            storage.forget_branches(&repo_path, &[branch])?;
+            println!("Forgot '{}'", branch);
+            Ok(())
+        }),
+        BatchAction::Group => {
+            let group = match options.into {
+                Some(group) => group.to_string(),
+                None => match interactive::prompt_group(selected.len())? {
+                    Some(group) => group,
+                    None => {
+                        println!("No group given.");
+                        return Ok(());
+                    }
+                },
+            };
+            validation::validate_group_name(&group)?;
+
+            run_batch(&selected, "add to the group", |branch| {
+                storage.add_to_group(&repo_path, &group, &[branch])?;
+                println!("Added '{}' to '{}'", branch, group);
+                Ok(())
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_batch_continues_past_failures() {
+        let branches = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let mut applied = Vec::new();
+
+        let result = run_batch(&branches, "pin", |branch| {
+            if branch == "b" {
+                return Err(GgoError::BranchNotFound(branch.to_string()));
+            }
+            applied.push(branch.to_string());
+            Ok(())
+        });
+
+        assert_eq!(applied, vec!["a", "c"]);
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("Failed to pin 1 of 3 branch(es)"));
+    }
+
+    #[test]
+    fn test_batch_action_names() {
+        for action in BatchAction::ALL {
+            assert_eq!(BatchAction::from_name(action.name()), Some(action));
+        }
+        assert_eq!(BatchAction::from_name("archive"), None);
+    }
+}
@@ -0,0 +1,987 @@
+//! The default command, `ggo <pattern>`: ranking the matching branches,
+//! picking one (top match, menu, fzf or the full-screen picker) and
+//! switching to it. Also tags and detached checkouts, and the ranking
+//! boosts the other pickers share.
+
+use std::collections::HashMap;
+
+use tracing::debug;
+
+use crate::backend::{self, GitBackend};
+use crate::config::AutoSelect;
+use crate::error::{GgoError, Result, PICK_TOP_MATCH_HINT};
+use crate::query::cached_branches;
+use crate::switch::{
+    self, check_pre_checkout, repo_key, run_post_checkout_hooks, save_previous_location,
+    SwitchOptions,
+};
+use crate::{
+    config, constants, frecency, fzf, git, interactive, logfile, matcher, ranking, storage, style,
+    tui, validation,
+};
+
+use super::delete::{delete_branches, Force};
+use super::{display_columns, load_branch_notes, load_pinned_branches};
+
+/// Boost ranked branches by how often they follow the current branch,
+/// leaving the ranking unchanged if there's no history to go on
+pub(crate) fn boost_likely_next(
+    backend: &dyn GitBackend,
+    storage: &storage::Storage,
+    repo_path: &str,
+    ranked: Vec<(String, f64)>,
+) -> Vec<(String, f64)> {
+    let Ok(current) = backend.current_branch() else {
+        return ranked;
+    };
+
+    match storage.transitions_from(repo_path, &current) {
+        Ok(transitions) => ranking::apply_transition_boost(ranked, &transitions),
+        Err(e) => {
+            style::warn(format!("Could not load branch transitions: {}", e));
+            ranked
+        }
+    }
+}
+
+/// How much each branch was used in the current session (see
+/// `frecency.session_minutes`). The checked-out branch is left out: switching
+/// back and forth should land on the other branch.
+pub(crate) fn load_session_weights(
+    backend: &dyn GitBackend,
+    storage: &storage::Storage,
+    repo_path: &str,
+    config: &config::Config,
+) -> HashMap<String, f64> {
+    let window = i64::from(config.frecency.session_minutes) * 60;
+    if window == 0 {
+        return HashMap::new();
+    }
+
+    let lookback = window * constants::scoring::SESSION_LOOKBACK;
+    let mut weights = match storage.recent_checkout_events(repo_path, lookback) {
+        Ok(events) => frecency::session_weights(&events, window),
+        Err(e) => {
+            debug!("Session history unavailable: {}", e);
+            return HashMap::new();
+        }
+    };
+    if let Ok(current) = backend.current_branch() {
+        weights.remove(&current);
+    }
+    weights
+}
+
+/// Lift the branches used in the current session above the rest
+pub(crate) fn boost_session(
+    backend: &dyn GitBackend,
+    storage: &storage::Storage,
+    repo_path: &str,
+    ranked: Vec<(String, f64)>,
+    config: &config::Config,
+) -> Vec<(String, f64)> {
+    let weights = load_session_weights(backend, storage, repo_path, config);
+    if weights.is_empty() {
+        return ranked;
+    }
+    let threshold = config.scoring_for(repo_path).auto_select_threshold;
+    ranking::apply_session_boost(ranked, &weights, threshold)
+}
+
+/// Let the user choose among ranked branches with the selected picker.
+/// Actions taken from the select menu (delete, alias, pin) are carried out
+/// and the menu is shown again until a branch is picked.
+pub(crate) fn pick_branch(
+    storage: &storage::Storage,
+    repo_path: &str,
+    branches: &[String],
+    records: &[storage::BranchRecord],
+    picker: config::Picker,
+    config: &config::Config,
+    half_life_days: f64,
+) -> Result<String> {
+    // The inline menu gets unwieldy with huge lists; filter those as you type
+    let picker = if picker == config::Picker::Select
+        && branches.len() > constants::picker::MENU_MAX_BRANCHES
+    {
+        config::Picker::Tui
+    } else {
+        picker
+    };
+
+    match picker {
+        config::Picker::Tui => {
+            let pinned = load_pinned_branches(storage, repo_path);
+            let current = backend::backend().current_branch().ok();
+            return tui::select_branch(
+                branches,
+                records,
+                &pinned,
+                current.as_deref(),
+                half_life_days,
+            );
+        }
+        config::Picker::Fzf => {
+            let pinned = load_pinned_branches(storage, repo_path);
+            return fzf::select_branch(
+                &config.behavior.fzf_command,
+                branches,
+                records,
+                &pinned,
+                half_life_days,
+            );
+        }
+        config::Picker::Select => {}
+    }
+
+    let mut branches = branches.to_vec();
+    loop {
+        let pinned = storage.pinned_branches(repo_path).unwrap_or_default();
+        let notes = storage.branch_notes(repo_path).unwrap_or_default();
+
+        let details = git::list_branches().unwrap_or_default();
+        match interactive::select_branch(
+            &branches,
+            records,
+            &pinned,
+            &notes,
+            &details,
+            display_columns(config),
+            half_life_days,
+        )? {
+            interactive::MenuAction::Checkout(branch) => return Ok(branch),
+            interactive::MenuAction::Delete(branch) => {
+                if interactive::confirm_delete_branch(&branch)? {
+                    let deleted = [branch.clone()];
+                    match delete_branches(
+                        storage,
+                        repo_path,
+                        &deleted,
+                        records,
+                        Force::No,
+                        false,
+                        half_life_days,
+                    ) {
+                        Ok(()) => branches.retain(|b| b != &branch),
+                        Err(e) => eprintln!("Error: {}", e),
+                    }
+                }
+            }
+            interactive::MenuAction::Alias(branch) => {
+                if let Some(alias) = interactive::prompt_alias(&branch)? {
+                    match validation::validate_alias_name(&alias)
+                        .and_then(|_| Ok(storage.create_alias(repo_path, &alias, &branch)?))
+                    {
+                        Ok(()) => println!("Created alias '{}' → '{}'", alias, branch),
+                        Err(e) => eprintln!("Error: {}", e),
+                    }
+                }
+            }
+            interactive::MenuAction::TogglePin(branch) => {
+                let result = if pinned.contains(&branch) {
+                    storage
+                        .unpin_branch(repo_path, &branch)
+                        .map(|_| format!("Unpinned '{}'", branch))
+                } else {
+                    storage
+                        .pin_branch(repo_path, &branch)
+                        .map(|_| format!("Pinned '{}'", branch))
+                };
+
+                match result {
+                    Ok(message) => println!("{}", message),
+                    Err(e) => style::warn(format!("Could not update pin: {}", e)),
+                }
+            }
+        }
+
+        if branches.is_empty() {
+            return Err(GgoError::UserCancelled);
+        }
+    }
+}
+
+/// Create a branch named after the pattern from the current HEAD.
+/// With `--create` the branch is created directly, otherwise the user is asked first
+/// (never without a terminal to ask on).
+fn create_branch_from_pattern(
+    backend: &dyn GitBackend,
+    pattern: &str,
+    create: bool,
+    can_prompt: bool,
+) -> Result<String> {
+    if create {
+        validation::validate_branch_name(pattern)?;
+    } else if validation::validate_branch_name(pattern).is_err() {
+        return Err(GgoError::NoMatchingBranches(pattern.to_string()));
+    } else if !can_prompt || !interactive::confirm_create_branch(pattern)? {
+        return Err(GgoError::NoBranchToCheckOut(pattern.to_string()));
+    }
+
+    backend.create_branch(pattern)?;
+    println!("Created branch '{}' from current HEAD", pattern);
+
+    Ok(pattern.to_string())
+}
+
+/// Options controlling how a pattern is resolved and checked out
+pub struct CheckoutOptions<'a> {
+    pub ignore_case: bool,
+    pub use_fuzzy: bool,
+    pub interactive: bool,
+    pub create: bool,
+    pub switch: SwitchOptions<'a>,
+    pub suggest: bool,
+    /// Also match the pattern against branch notes
+    pub notes: bool,
+    /// Print the resolved branch instead of checking it out
+    pub print: bool,
+    pub picker: config::Picker,
+    /// Whether the top match is taken without the menu
+    pub auto_select: AutoSelect,
+}
+
+pub fn find_and_checkout_branch(
+    storage: &storage::Storage,
+    repo_path: &str,
+    pattern: &str,
+    options: &CheckoutOptions,
+    config: &config::Config,
+) -> Result<()> {
+    // Check if pattern is an exact alias match (highest priority)
+    // Note: get_alias() only returns aliases for the current repo (scoped by repo_path)
+    // This ensures we never try to use an alias from a different repository
+    if let Ok(Some(branch_name)) = storage.alias(repo_path, pattern) {
+        // No branch list is needed: like any pick, the aliased branch is
+        // verified once right before checkout, which also catches stale
+        // aliases pointing to deleted branches
+        if !options.print && !options.switch.quiet {
+            println!("Using alias '{}' → '{}'", pattern, branch_name);
+        }
+        match switch_or_print(
+            storage,
+            config,
+            repo_path,
+            &branch_name,
+            &options.switch,
+            options.print,
+        ) {
+            Err(GgoError::BranchNotFound(missing)) if missing == branch_name => {
+                style::warn(format!(
+                    "Alias '{}' points to non-existent branch '{}'. Falling back to pattern matching.",
+                    pattern, branch_name
+                ));
+            }
+            result => return result,
+        }
+    }
+
+    let branches = cached_branches(options.switch.backend, storage, repo_path)?;
+
+    // Try to load branch history, but continue without it if it fails
+    let records = match storage.branch_records(repo_path) {
+        Ok(r) => r,
+        Err(e) => {
+            style::warn(format!(
+                "Could not load branch history: {}\n   Frecency ranking will not be available.",
+                e
+            ));
+            vec![]
+        }
+    };
+
+    // Aliases may point at excluded branches; pattern matching never offers them
+    let scoring = config.scoring_for(repo_path);
+    let matchable = config.behavior.offered_branches(branches);
+    let mut ranked = ranking::score_matching_branches(
+        &matchable,
+        pattern,
+        options.ignore_case,
+        options.use_fuzzy,
+        &records,
+        &scoring,
+        &config.matching.bonuses(),
+    );
+
+    let descriptions = options
+        .switch
+        .backend
+        .branch_descriptions()
+        .unwrap_or_default();
+    ranked = ranking::add_description_matches(
+        ranked,
+        &matcher::filter_by_text(&matchable, &descriptions, pattern, options.ignore_case),
+        options.use_fuzzy,
+        &records,
+        &scoring,
+    );
+
+    if options.notes {
+        ranked = ranking::add_note_matches(
+            ranked,
+            &matchable,
+            &load_branch_notes(storage, repo_path),
+            pattern,
+            options.ignore_case,
+            &records,
+            scoring.half_life_days,
+        );
+    }
+
+    ranked = boost_session(options.switch.backend, storage, repo_path, ranked, config);
+    if options.suggest {
+        ranked = boost_likely_next(options.switch.backend, storage, repo_path, ranked);
+    }
+
+    let pinned = load_pinned_branches(storage, repo_path);
+    let mut ranked = ranking::float_pinned(ranked, &pinned);
+
+    // Picking the current branch would do nothing; keep it only if it's all
+    // that matched, so the result is "already on" rather than "no match"
+    if config.behavior.skip_current && ranked.len() > 1 {
+        if let Ok(current) = options.switch.backend.current_branch() {
+            ranked.retain(|(branch, _)| *branch != current);
+        }
+    }
+    logfile::record_candidates(pattern, &ranked);
+
+    // Determine which branch to checkout
+    let branch_to_checkout = if ranked.is_empty() {
+        if options.print {
+            return Err(GgoError::NoMatchingBranches(pattern.to_string()));
+        }
+        // Nothing matched: offer to create a branch named after the pattern
+        create_branch_from_pattern(
+            options.switch.backend,
+            pattern,
+            options.create,
+            options.switch.can_prompt,
+        )?
+    } else if options.interactive {
+        if !options.switch.can_prompt {
+            return Err(GgoError::NotATerminal(PICK_TOP_MATCH_HINT));
+        }
+        // Always use interactive mode if explicitly requested
+        let branch_list: Vec<String> = ranked.iter().map(|(b, _)| b.clone()).collect();
+        pick_branch(
+            storage,
+            repo_path,
+            &branch_list,
+            &records,
+            options.picker,
+            config,
+            scoring.half_life_days,
+        )?
+    } else if ranked.len() == 1 {
+        // Single match: use it
+        ranked[0].0.clone()
+    } else {
+        // Multiple matches: auto-select if the top score is far enough ahead
+        let should_auto_select = ranking::auto_selects(
+            &ranked,
+            options.auto_select,
+            scoring.auto_select_threshold,
+            config.behavior.auto_select_min_score,
+        );
+
+        // --print never prompts: the top match is the answer
+        if should_auto_select || options.print {
+            ranked[0].0.clone()
+        } else if !options.switch.can_prompt {
+            // No terminal for the menu: fall back to the top match
+            if !options.switch.quiet {
+                eprintln!(
+                    "{} branches match '{}'; picking the top match '{}' (no interactive terminal)",
+                    ranked.len(),
+                    pattern,
+                    ranked[0].0
+                );
+            }
+            ranked[0].0.clone()
+        } else {
+            // Scores are close, show interactive menu
+            let branch_list: Vec<String> = ranked.iter().map(|(b, _)| b.clone()).collect();
+            pick_branch(
+                storage,
+                repo_path,
+                &branch_list,
+                &records,
+                options.picker,
+                config,
+                scoring.half_life_days,
+            )?
+        }
+    };
+
+    switch_or_print(
+        storage,
+        config,
+        repo_path,
+        &branch_to_checkout,
+        &options.switch,
+        options.print,
+    )
+}
+
+/// Handle 'ggo --detach': check out the tag or recent commit matching the
+/// pattern with a detached HEAD
+pub fn checkout_detach_target(
+    storage: &storage::Storage,
+    config: &config::Config,
+    pattern: &str,
+    ignore_case: bool,
+    use_fuzzy: bool,
+    switch: &SwitchOptions,
+    print: bool,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+
+    let targets = git::detach_targets(constants::git::DETACH_RECENT_COMMITS)?;
+    let mut matches = match_detach_targets(targets, pattern, ignore_case, use_fuzzy);
+    if matches.is_empty() {
+        // Older commits and revisions like HEAD~3
+        matches.extend(git::resolve_commit(pattern)?);
+    }
+
+    let target = match matches.len() {
+        0 => return Err(GgoError::NoDetachTarget(pattern.to_string())),
+        1 => &matches[0],
+        _ if print || !switch.can_prompt => &matches[0],
+        _ => &matches[interactive::select_detach_target(&matches)?],
+    };
+
+    if print {
+        println!("{}", target.commit);
+        return Ok(());
+    }
+
+    switch_to_detach_target(storage, config, &repo_path, target, switch)
+}
+
+/// Handle 'ggo tag' and 'ggo --tags': check out the tag matching the pattern
+/// (the best match, highest version first) with a detached HEAD, or create
+/// `new_branch` at it and switch to that
+#[allow(clippy::too_many_arguments)]
+pub fn checkout_tag(
+    storage: &storage::Storage,
+    config: &config::Config,
+    pattern: Option<&str>,
+    new_branch: Option<&str>,
+    ignore_case: bool,
+    use_fuzzy: bool,
+    switch: &SwitchOptions,
+    print: bool,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+    if let Some(name) = new_branch {
+        validation::validate_branch_name(name)?;
+    }
+
+    let tags = git::list_tags()?;
+    if tags.is_empty() {
+        return Err(GgoError::Other("This repository has no tags".to_string()));
+    }
+
+    let pattern = pattern.unwrap_or_default();
+    let names: Vec<String> = tags.iter().map(|t| t.name.clone()).collect();
+    let ranked: Vec<String> = if names.iter().any(|name| name == pattern) {
+        vec![pattern.to_string()]
+    } else {
+        ranking::rank_tags(&names, pattern, ignore_case, use_fuzzy)
+            .into_iter()
+            .map(|m| m.tag)
+            .collect()
+    };
+    let matches: Vec<git::DetachTarget> = ranked
+        .iter()
+        .filter_map(|name| tags.iter().find(|t| &t.name == name).cloned())
+        .collect();
+
+    let tag = match matches.len() {
+        0 => return Err(GgoError::NoMatchingTags(pattern.to_string())),
+        1 => &matches[0],
+        _ if print || !switch.can_prompt => &matches[0],
+        _ => &matches[interactive::select_detach_target(&matches)?],
+    };
+
+    if print {
+        println!("{}", tag.name);
+        return Ok(());
+    }
+
+    match new_branch {
+        Some(name) => {
+            git::create_branch_at(name, &tag.commit)?;
+            println!("Created branch '{}' at tag '{}'", name, tag.name);
+            switch_to_branch(storage, config, &repo_path, name, switch)
+        }
+        None => switch_to_detach_target(storage, config, &repo_path, tag, switch),
+    }
+}
+
+/// Check out the resolved branch, or with `--print` only write its name to stdout
+pub fn switch_or_print(
+    storage: &storage::Storage,
+    config: &config::Config,
+    repo_path: &str,
+    branch: &str,
+    switch: &SwitchOptions,
+    print: bool,
+) -> Result<()> {
+    logfile::record_selection(branch);
+    if print {
+        if !switch.backend.branch_exists(branch)? {
+            return Err(GgoError::BranchNotFound(branch.to_string()));
+        }
+        println!("{}", branch);
+        return Ok(());
+    }
+
+    switch_to_branch(storage, config, repo_path, branch, switch)
+}
+
+/// Switch to a branch (see [`switch::switch_to_branch`]) and say what happened
+pub(crate) fn switch_to_branch(
+    storage: &storage::Storage,
+    config: &config::Config,
+    repo_path: &str,
+    branch: &str,
+    switch: &SwitchOptions,
+) -> Result<()> {
+    let switched = switch::switch_to_branch(storage, config, repo_path, branch, switch)?;
+    println!("{}", switched);
+    Ok(())
+}
+
+/// Check out a tag or commit with a detached HEAD. Nothing is recorded for
+/// frecency, but the branch left behind is saved for 'ggo -'.
+pub(crate) fn switch_to_detach_target(
+    storage: &storage::Storage,
+    config: &config::Config,
+    repo_path: &str,
+    target: &git::DetachTarget,
+    switch: &SwitchOptions,
+) -> Result<()> {
+    let current_branch = switch.backend.current_branch().ok();
+    check_pre_checkout(
+        storage,
+        config,
+        switch,
+        current_branch.as_deref(),
+        &target.name,
+        None,
+        true,
+    )?;
+    save_previous_location(
+        storage,
+        repo_path,
+        switch.backend,
+        current_branch.as_deref(),
+        &target.commit,
+        switch.warn,
+    );
+    git::checkout_commit(&target.commit)?;
+
+    println!(
+        "HEAD is now detached at '{}' ({})",
+        target.name, target.summary
+    );
+    run_post_checkout_hooks(
+        storage,
+        config,
+        switch,
+        current_branch.as_deref(),
+        &target.name,
+        true,
+    );
+    Ok(())
+}
+
+/// Tags and recent commits matching `pattern`: an exact tag name alone,
+/// otherwise matching tags ranked like 'ggo tag', then the commits whose id
+/// starts with the pattern or whose subject contains it
+fn match_detach_targets(
+    targets: Vec<git::DetachTarget>,
+    pattern: &str,
+    ignore_case: bool,
+    use_fuzzy: bool,
+) -> Vec<git::DetachTarget> {
+    if let Some(tag) = targets.iter().find(|t| t.is_tag && t.name == pattern) {
+        return vec![tag.clone()];
+    }
+
+    let (tags, commits): (Vec<_>, Vec<_>) = targets.into_iter().partition(|t| t.is_tag);
+
+    let tag_names: Vec<String> = tags.iter().map(|t| t.name.clone()).collect();
+    let matched_names: Vec<String> =
+        ranking::rank_tags(&tag_names, pattern, ignore_case, use_fuzzy)
+            .into_iter()
+            .map(|m| m.tag)
+            .collect();
+    let mut matched: Vec<git::DetachTarget> = matched_names
+        .iter()
+        .filter_map(|name| tags.iter().find(|t| &t.name == name).cloned())
+        .collect();
+
+    let commit_id_prefix = pattern.len() >= 4 && pattern.chars().all(|c| c.is_ascii_hexdigit());
+    let id_prefix = pattern.to_lowercase();
+    matched.extend(commits.into_iter().filter(|commit| {
+        (commit_id_prefix && commit.commit.starts_with(&id_prefix))
+            || matcher::matches(&commit.summary, pattern, ignore_case)
+    }));
+
+    matched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::print_warning;
+    use std::cell::RefCell;
+    use std::path::PathBuf;
+
+    /// In-memory repository for exercising the checkout flow
+    struct FakeBackend {
+        branches: RefCell<Vec<String>>,
+        /// Listed branches whose ref is gone by checkout time
+        deleted: Vec<String>,
+        current: RefCell<String>,
+        /// HEAD is detached here instead of on `current`
+        detached: RefCell<Option<git::DetachTarget>>,
+        checkouts: RefCell<Vec<String>>,
+    }
+
+    impl FakeBackend {
+        fn new(branches: &[&str]) -> Self {
+            FakeBackend {
+                branches: RefCell::new(branches.iter().map(|b| b.to_string()).collect()),
+                deleted: Vec::new(),
+                current: RefCell::new("main".to_string()),
+                detached: RefCell::new(None),
+                checkouts: RefCell::new(Vec::new()),
+            }
+        }
+    }
+
+    impl GitBackend for FakeBackend {
+        fn local_branches(&self) -> Result<Vec<String>> {
+            Ok(self.branches.borrow().clone())
+        }
+
+        fn branch_exists(&self, branch: &str) -> Result<bool> {
+            Ok(self.branches.borrow().iter().any(|b| b == branch)
+                && !self.deleted.iter().any(|b| b == branch))
+        }
+
+        fn refs_fingerprint(&self) -> Result<Option<String>> {
+            Ok(None)
+        }
+
+        fn branch_descriptions(&self) -> Result<HashMap<String, String>> {
+            Ok(HashMap::new())
+        }
+
+        fn current_branch(&self) -> Result<String> {
+            match &*self.detached.borrow() {
+                Some(head) => Err(GgoError::DetachedHead(head.name.clone())),
+                None => Ok(self.current.borrow().clone()),
+            }
+        }
+
+        fn detached_head(&self) -> Result<Option<git::DetachTarget>> {
+            Ok(self.detached.borrow().clone())
+        }
+
+        fn repo_root(&self) -> Result<String> {
+            Ok("/nonexistent/ggo-fake-repo".to_string())
+        }
+
+        fn has_uncommitted_changes(&self) -> Result<bool> {
+            Ok(false)
+        }
+
+        fn unpushed_commits(&self, _branch: &str) -> Result<usize> {
+            Ok(0)
+        }
+
+        fn worktree_for_branch(&self, _branch: &str) -> Result<Option<PathBuf>> {
+            Ok(None)
+        }
+
+        fn checkout(&self, branch: &str) -> Result<()> {
+            self.checkouts.borrow_mut().push(branch.to_string());
+            *self.current.borrow_mut() = branch.to_string();
+            *self.detached.borrow_mut() = None;
+            Ok(())
+        }
+
+        fn checkout_detached(&self, branch: &str) -> Result<()> {
+            self.checkouts.borrow_mut().push(branch.to_string());
+            Ok(())
+        }
+
+        fn create_branch(&self, branch: &str) -> Result<()> {
+            self.branches.borrow_mut().push(branch.to_string());
+            Ok(())
+        }
+    }
+
+    const FAKE_REPO: &str = "/fake/repo";
+
+    fn checkout_options(backend: &dyn GitBackend) -> CheckoutOptions<'_> {
+        CheckoutOptions {
+            ignore_case: false,
+            use_fuzzy: true,
+            interactive: false,
+            create: false,
+            switch: SwitchOptions {
+                backend,
+                force_detach: false,
+                force: false,
+                can_prompt: false,
+                confirm: None,
+                quiet: true,
+                warn: print_warning,
+                repo_hooks: true,
+            },
+            suggest: false,
+            notes: false,
+            print: false,
+            picker: config::Picker::Select,
+            auto_select: AutoSelect::Smart,
+        }
+    }
+
+    fn checkout_with(
+        backend: &FakeBackend,
+        storage: &storage::Storage,
+        pattern: &str,
+        print: bool,
+    ) -> Result<()> {
+        let options = CheckoutOptions {
+            print,
+            ..checkout_options(backend)
+        };
+        let config = config::Config::default();
+        find_and_checkout_branch(storage, FAKE_REPO, pattern, &options, &config)
+    }
+
+    #[test]
+    fn test_checkout_auto_selects_single_match() {
+        let backend = FakeBackend::new(&["main", "feature/auth", "bugfix/login"]);
+        let storage = storage::Storage::open_in_memory().unwrap();
+
+        checkout_with(&backend, &storage, "auth", false).unwrap();
+
+        assert_eq!(*backend.checkouts.borrow(), vec!["feature/auth"]);
+        assert_eq!(
+            storage.previous_branch(FAKE_REPO).unwrap(),
+            Some("main".to_string())
+        );
+        let records = storage.branch_records(FAKE_REPO).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].branch_name, "feature/auth");
+    }
+
+    #[test]
+    fn test_checkout_without_terminal_picks_top_match() {
+        let backend = FakeBackend::new(&["main", "feature/auth-api", "feature/auth-ui"]);
+        let storage = storage::Storage::open_in_memory().unwrap();
+        // Frecent enough to rank first, too close to auto-select
+        storage
+            .record_checkout(FAKE_REPO, None, "feature/auth-ui")
+            .unwrap();
+
+        checkout_with(&backend, &storage, "auth", false).unwrap();
+
+        assert_eq!(*backend.checkouts.borrow(), vec!["feature/auth-ui"]);
+    }
+
+    #[test]
+    fn test_checkout_stale_alias_falls_back_to_pattern() {
+        let backend = FakeBackend::new(&["main", "feature/auth"]);
+        let storage = storage::Storage::open_in_memory().unwrap();
+        storage
+            .create_alias(FAKE_REPO, "auth", "feature/old-auth")
+            .unwrap();
+
+        checkout_with(&backend, &storage, "auth", false).unwrap();
+
+        assert_eq!(*backend.checkouts.borrow(), vec!["feature/auth"]);
+    }
+
+    #[test]
+    fn test_checkout_branch_deleted_after_listing() {
+        let mut backend = FakeBackend::new(&["main", "feature/auth"]);
+        backend.deleted.push("feature/auth".to_string());
+        let storage = storage::Storage::open_in_memory().unwrap();
+
+        let result = checkout_with(&backend, &storage, "auth", false);
+
+        assert!(matches!(result, Err(GgoError::BranchNotFound(b)) if b == "feature/auth"));
+        assert!(backend.checkouts.borrow().is_empty());
+        assert!(storage.branch_records(FAKE_REPO).unwrap().is_empty());
+        assert_eq!(storage.previous_branch(FAKE_REPO).unwrap(), None);
+    }
+
+    #[test]
+    fn test_checkout_from_detached_head_saves_commit() {
+        let backend = FakeBackend::new(&["main", "feature/auth"]);
+        let commit = "0123456789abcdef0123456789abcdef01234567";
+        *backend.detached.borrow_mut() = Some(git::DetachTarget {
+            name: commit[..7].to_string(),
+            commit: commit.to_string(),
+            summary: "Initial".to_string(),
+            commit_time: 0,
+            is_tag: false,
+        });
+        let storage = storage::Storage::open_in_memory().unwrap();
+
+        checkout_with(&backend, &storage, "auth", false).unwrap();
+
+        assert_eq!(*backend.checkouts.borrow(), vec!["feature/auth"]);
+        assert_eq!(
+            storage.previous_branch(FAKE_REPO).unwrap(),
+            Some(commit.to_string())
+        );
+        // The commit is only for 'ggo -', not the branch history
+        assert!(storage.checkout_history(FAKE_REPO).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_checkout_current_branch_is_not_recorded() {
+        let backend = FakeBackend::new(&["main", "feature/auth"]);
+        let storage = storage::Storage::open_in_memory().unwrap();
+
+        checkout_with(&backend, &storage, "main", false).unwrap();
+
+        assert!(backend.checkouts.borrow().is_empty());
+        assert!(storage.branch_records(FAKE_REPO).unwrap().is_empty());
+        assert_eq!(storage.previous_branch(FAKE_REPO).unwrap(), None);
+    }
+
+    #[test]
+    fn test_checkout_skip_current() {
+        let backend = FakeBackend::new(&["main", "maintenance"]);
+        let storage = storage::Storage::open_in_memory().unwrap();
+        let options = checkout_options(&backend);
+        let mut config = config::Config::default();
+        config.behavior.skip_current = true;
+
+        // 'main' ranks first, but it's where we are
+        find_and_checkout_branch(&storage, FAKE_REPO, "main", &options, &config).unwrap();
+        assert_eq!(*backend.checkouts.borrow(), vec!["maintenance"]);
+
+        // Alone, the current branch is still the answer
+        let backend = FakeBackend::new(&["main", "develop"]);
+        let options = checkout_options(&backend);
+        find_and_checkout_branch(&storage, FAKE_REPO, "main", &options, &config).unwrap();
+        assert!(backend.checkouts.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_checkout_print_does_not_switch() {
+        let backend = FakeBackend::new(&["main", "feature/auth"]);
+        let storage = storage::Storage::open_in_memory().unwrap();
+
+        checkout_with(&backend, &storage, "auth", true).unwrap();
+
+        assert!(backend.checkouts.borrow().is_empty());
+        assert!(storage.branch_records(FAKE_REPO).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_match_detach_targets() {
+        let target = |name: &str, commit: &str, summary: &str, is_tag: bool| git::DetachTarget {
+            name: name.to_string(),
+            commit: commit.to_string(),
+            summary: summary.to_string(),
+            commit_time: 0,
+            is_tag,
+        };
+        let targets = vec![
+            target("v1.2.0", "aaaa1111", "Release 1.2", true),
+            target("v1.2.0-rc1", "bbbb2222", "Release candidate", true),
+            target("cccc333", "cccc3333", "Fix v1.2 regression", false),
+            target("dddd444", "dddd4444", "Add login", false),
+        ];
+
+        // An exact tag name wins outright
+        let matched = match_detach_targets(targets.clone(), "v1.2.0", false, true);
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].name, "v1.2.0");
+
+        // Tags first, then commits by subject
+        let names: Vec<String> = match_detach_targets(targets.clone(), "v1.2", false, false)
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(names, vec!["v1.2.0", "v1.2.0-rc1", "cccc333"]);
+
+        // Commit id prefixes
+        let matched = match_detach_targets(targets.clone(), "DDDD4", true, true);
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].commit, "dddd4444");
+
+        assert!(match_detach_targets(targets, "nothing", false, false).is_empty());
+    }
+
+    #[test]
+    fn test_should_auto_select_clear_winner() {
+        // Test that 2x score ratio triggers auto-select
+        let top_score = 400.0;
+        let second_score = 150.0;
+
+        let should_auto_select = top_score / second_score >= 2.0;
+        assert!(should_auto_select);
+    }
+
+    #[test]
+    fn test_should_not_auto_select_close_scores() {
+        // Test that close scores (< 2x) trigger interactive menu
+        let top_score = 250.0;
+        let second_score = 200.0;
+
+        let should_auto_select = top_score / second_score >= 2.0;
+        assert!(!should_auto_select);
+    }
+
+    #[test]
+    fn test_should_auto_select_exact_2x() {
+        // Test boundary condition: exactly 2x should auto-select
+        let top_score = 200.0;
+        let second_score = 100.0;
+
+        let should_auto_select = top_score / second_score >= 2.0;
+        assert!(should_auto_select);
+    }
+
+    #[test]
+    fn test_should_auto_select_zero_second_score() {
+        // Test edge case: second score is 0, should always auto-select
+        let second_score = 0.0;
+
+        let should_auto_select = second_score == 0.0;
+        assert!(should_auto_select);
+    }
+
+    #[test]
+    fn test_should_not_auto_select_near_2x() {
+        // Test just under 2x threshold
+        let top_score = 199.0;
+        let second_score = 100.0;
+
+        let should_auto_select = top_score / second_score >= 2.0;
+        assert!(!should_auto_select);
+    }
+
+    #[test]
+    fn test_high_ratio_auto_selects() {
+        // Test very clear winner (5x)
+        let top_score = 500.0;
+        let second_score = 100.0;
+
+        let should_auto_select = top_score / second_score >= 2.0;
+        assert!(should_auto_select);
+    }
+}
@@ -0,0 +1,245 @@
+//! 'ggo cleanup' and the retention limits behind it, plus the maintenance
+//! run quietly after ordinary commands.
+
+use tracing::{debug, warn};
+
+use crate::error::Result;
+use crate::switch::repo_key;
+use crate::{config, frecency, storage};
+
+/// Handle cleanup subcommand operations
+pub fn handle_cleanup_command(
+    storage: &storage::Storage,
+    options: &CleanupOptions,
+    scope: Option<&str>,
+    config: &config::Config,
+) -> Result<()> {
+    let dry_run = options.dry_run;
+
+    if options.show_size {
+        let size = storage.database_size()?;
+        let size_kb = size as f64 / 1024.0;
+        let size_mb = size_kb / 1024.0;
+
+        if size_mb > 1.0 {
+            println!("Database size: {:.2} MB", size_mb);
+        } else {
+            println!("Database size: {:.2} KB", size_kb);
+        }
+    }
+
+    if let Some(repo) = scope {
+        println!("Only cleaning up records of '{}'", repo);
+    }
+
+    if options.deleted {
+        println!("Cleaning up deleted branches...");
+        let stale = if dry_run {
+            storage.deleted_branch_records(scope)?
+        } else {
+            storage.cleanup_deleted_branches(scope)?
+        };
+        let total: usize = stale.iter().map(|repo| repo.records.len()).sum();
+        let renamed: usize = stale.iter().map(|repo| repo.renamed.len()).sum();
+
+        println!(
+            "{} {} stale branch records",
+            if dry_run { "Would remove" } else { "Removed" },
+            total
+        );
+        if renamed > 0 {
+            println!(
+                "{} the history of {} renamed branch(es)",
+                if dry_run {
+                    "Would carry over"
+                } else {
+                    "Carried over"
+                },
+                renamed
+            );
+        }
+        for repo in &stale {
+            println!(
+                "  {}: {} record(s){}",
+                repo.repo_path,
+                repo.records.len(),
+                if repo.repo_gone {
+                    " (repository no longer exists)"
+                } else {
+                    ""
+                }
+            );
+            for (old, new) in &repo.renamed {
+                println!("    {} → {}", old, new);
+            }
+            if dry_run {
+                for record in &repo.records {
+                    println!(
+                        "    {}  (last used {})",
+                        record.branch_name,
+                        frecency::format_relative_time(record.last_used)
+                    );
+                }
+            }
+        }
+    }
+
+    // Cleanup old records (always run if a custom age is specified, or if --optimize is used)
+    if options.older_than_days < 365 || options.optimize {
+        println!(
+            "Cleaning up branches older than {} days...",
+            options.older_than_days
+        );
+        if dry_run {
+            let old = storage.old_records(options.older_than_days, scope)?;
+            println!("Would remove {} old branch records", old.len());
+            print_cleanup_candidates(&old, scope.is_none());
+        } else {
+            let deleted = storage.cleanup_old_records(options.older_than_days, scope)?;
+            println!("Removed {} old branch records", deleted);
+        }
+    }
+
+    // Compact the event log (also part of --optimize)
+    if options.compact || options.optimize {
+        let retention_days = config.history.retention_days;
+        if retention_days == 0 {
+            println!("Keeping all checkout events (history.retention_days = 0)");
+        } else {
+            println!(
+                "Compacting checkout events older than {} days...",
+                retention_days
+            );
+            if dry_run {
+                let (count, oldest) = storage.old_events(retention_days, scope)?;
+                match oldest {
+                    Some(oldest) => println!(
+                        "Would remove {} old checkout events (oldest {})",
+                        count,
+                        frecency::format_relative_time(oldest)
+                    ),
+                    None => println!("Would remove 0 old checkout events"),
+                }
+            } else {
+                let deleted = storage.compact_events(retention_days, scope)?;
+                println!("Removed {} old checkout events", deleted);
+            }
+        }
+    }
+
+    if options.optimize {
+        if dry_run {
+            println!("Would optimize the database (VACUUM and ANALYZE)");
+        } else {
+            println!("Optimizing database...");
+            storage.optimize()?;
+            println!("Database optimized (VACUUM and ANALYZE complete)");
+        }
+    }
+
+    if !options.show_size
+        && !options.deleted
+        && !options.compact
+        && !options.optimize
+        && options.older_than_days == 365
+    {
+        // No flags specified, show help
+        println!("Database cleanup options:");
+        println!("  --deleted          Remove records for deleted branches");
+        println!("  --older-than N     Remove branches not used in N days");
+        println!("  --compact          Drop checkout events past history.retention_days");
+        println!("  --optimize         Run VACUUM and ANALYZE");
+        println!("  --size             Show database size");
+        println!("  --dry-run          Show what would be removed");
+        println!("  --current          Only this repository (--repo <path> for another)");
+        println!("\nExample: ggo cleanup --deleted --optimize");
+    }
+
+    Ok(())
+}
+
+/// Automatic maintenance, every `maintenance.every` invocations: prune old
+/// records and events, drop records of deleted branches in the current
+/// repository, and refresh SQLite's statistics. Failures are only logged so
+/// they never get in the way of the actual command.
+pub fn auto_maintain(storage: &storage::Storage, config: &config::Config) {
+    match storage.maintenance_due(config.maintenance.every) {
+        Ok(true) => debug!("Running automatic maintenance"),
+        Ok(false) => return,
+        Err(e) => {
+            debug!("Skipping automatic maintenance: {}", e);
+            return;
+        }
+    }
+
+    if config.maintenance.max_age_days > 0 {
+        match storage.cleanup_old_records(config.maintenance.max_age_days, None) {
+            Ok(removed) => debug!("Maintenance removed {} old branch records", removed),
+            Err(e) => warn!("Maintenance failed to remove old records: {}", e),
+        }
+    }
+
+    match storage.compact_events(config.history.retention_days, None) {
+        Ok(removed) => debug!("Maintenance removed {} old checkout events", removed),
+        Err(e) => warn!("Maintenance failed to compact events: {}", e),
+    }
+
+    // Outside a repository there is nothing to check branches against
+    if let Ok(repo_path) = repo_key(storage, config) {
+        match storage.cleanup_deleted_branches(Some(&repo_path)) {
+            Ok(stale) => debug!(
+                "Maintenance removed {} stale branch records",
+                stale.iter().map(|repo| repo.records.len()).sum::<usize>()
+            ),
+            Err(e) => warn!("Maintenance failed to remove stale records: {}", e),
+        }
+    }
+
+    if let Err(e) = storage.quick_optimize() {
+        warn!("Maintenance failed to optimize the database: {}", e);
+    }
+}
+
+/// Keep the history within `storage.max_records` and `storage.max_age_days`,
+/// dropping the lowest-frecency records first
+pub fn enforce_retention(storage: &storage::Storage, config: &config::Config) {
+    let score = |record: &storage::BranchRecord| {
+        frecency::calculate_score(record, config.scoring_for(&record.repo_path).half_life_days)
+    };
+    match storage.trim_records(
+        config.storage.max_records,
+        config.storage.max_age_days,
+        &score,
+    ) {
+        Ok(0) => {}
+        Ok(removed) => debug!("Retention removed {} branch records", removed),
+        Err(e) => warn!("Failed to apply the retention policy: {}", e),
+    }
+}
+
+/// List records a cleanup would remove, with their age (and repository when
+/// the cleanup spans all of them)
+fn print_cleanup_candidates(records: &[storage::BranchRecord], show_repo: bool) {
+    for record in records {
+        let age = frecency::format_relative_time(record.last_used);
+        if show_repo {
+            println!(
+                "  {}  {}  (last used {})",
+                record.branch_name, record.repo_path, age
+            );
+        } else {
+            println!("  {}  (last used {})", record.branch_name, age);
+        }
+    }
+}
+
+/// What 'ggo cleanup' should do
+pub struct CleanupOptions {
+    pub older_than_days: i64,
+    pub deleted: bool,
+    pub compact: bool,
+    pub optimize: bool,
+    pub show_size: bool,
+    /// Report what would be removed instead of removing it
+    pub dry_run: bool,
+}
@@ -0,0 +1,109 @@
+//! 'ggo config' and 'ggo ignore'. They read the config file directly
+//! (instead of falling back to defaults) so a broken file is reported, not
+//! overwritten.
+
+use crate::config::Config;
+use crate::error::{GgoError, Result};
+use crate::{config, validation};
+
+/// Handle 'ggo config get': print a setting
+pub fn config_get(key: &str) -> Result<()> {
+    println!("{}", Config::load()?.get(key)?);
+    Ok(())
+}
+
+/// Handle 'ggo config set': change a setting in the config file
+pub fn config_set(key: &str, value: &str) -> Result<()> {
+    let mut config = Config::load()?;
+    config.set(key, value)?;
+    config.save_keys(&[key])?;
+    println!("Set {} = {}", key, config.get(key)?);
+    Ok(())
+}
+
+/// Handle 'ggo config list': print every setting, then the per-repository
+/// overrides
+pub fn config_list() -> Result<()> {
+    let config = Config::load()?;
+    for key in config::KEYS {
+        println!("{} = {}", key, config.get(key)?);
+    }
+    for key in config.repo_override_keys() {
+        println!("{} = {}", key, config.get(&key)?);
+    }
+    Ok(())
+}
+
+/// Handle 'ggo config edit': open the config file (created with every
+/// default, commented, if missing) in $VISUAL or $EDITOR, then check it
+pub fn config_edit() -> Result<()> {
+    let path = Config::config_path()?;
+    if !path.exists() {
+        Config::create_config_dir(&path)?;
+        std::fs::write(&path, Config::commented_defaults()?)?;
+    }
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+
+    let status = std::process::Command::new(program)
+        .args(parts)
+        .arg(&path)
+        .status()
+        .map_err(|e| GgoError::Other(format!("Failed to launch editor '{}': {}", editor, e)))?;
+
+    if !status.success() {
+        return Err(GgoError::Other(format!(
+            "Editor '{}' exited with {}",
+            editor, status
+        )));
+    }
+
+    // Report problems now rather than silently using defaults later
+    Config::load()?;
+    Ok(())
+}
+
+/// Handle 'ggo config path': print where the config file is
+pub fn config_path() -> Result<()> {
+    println!("{}", Config::config_path()?.display());
+    Ok(())
+}
+
+/// Handle 'ggo ignore': add or remove an exclude pattern, or list them
+pub fn handle_ignore_command(pattern: Option<&str>, remove: bool) -> Result<()> {
+    let mut config = Config::load()?;
+
+    let Some(pattern) = pattern else {
+        if config.behavior.exclude.is_empty() {
+            println!("No excluded branches. Add a pattern with 'ggo ignore <pattern>'.");
+        } else {
+            println!("Excluded branch patterns:\n");
+            for pattern in &config.behavior.exclude {
+                println!("  {}", pattern);
+            }
+        }
+        return Ok(());
+    };
+
+    validation::validate_pattern(pattern)?;
+
+    if remove {
+        if config.remove_exclude(pattern) {
+            config.save_keys(&["behavior.exclude"])?;
+            println!("No longer excluding '{}'", pattern);
+        } else {
+            println!("'{}' is not excluded", pattern);
+        }
+    } else if config.add_exclude(pattern) {
+        config.save_keys(&["behavior.exclude"])?;
+        println!("Excluding branches matching '{}'", pattern);
+    } else {
+        println!("'{}' is already excluded", pattern);
+    }
+
+    Ok(())
+}
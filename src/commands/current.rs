@@ -0,0 +1,72 @@
+//! 'ggo current': the current branch and its frecency, for shell prompts.
+
+use tracing::debug;
+
+use crate::error::Result;
+use crate::{config, frecency, git, storage};
+
+/// Handle 'ggo current': the checked-out branch, the previous one and the
+/// current branch's frecency rank among this repository's branches. The
+/// database is opened read-only and nothing is recorded, so it's cheap
+/// enough for a prompt. With `porcelain`, one line of tab-separated fields
+/// (empty when unknown) and no output at all outside a repository.
+pub fn show_current(config: &config::Config, porcelain: bool) -> Result<()> {
+    let (repo_path, branch) = match git::prompt_head() {
+        Ok(head) => head,
+        Err(_) if porcelain => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    let repo_path = match config.history.repo_identity {
+        config::RepoIdentity::Remote => git::repo_remote_key()?.unwrap_or(repo_path),
+        config::RepoIdentity::Path => repo_path,
+    };
+
+    // A missing or unreadable history leaves the fields empty: a prompt
+    // shouldn't break over it
+    let storage = storage::Storage::open_read_only(config.storage.backend)
+        .unwrap_or_else(|e| {
+            debug!("History unavailable: {}", e);
+            None
+        })
+        .map(|storage| storage.with_exclude(config.behavior.exclude.clone()));
+    let previous = storage
+        .as_ref()
+        .and_then(|storage| storage.previous_branch(&repo_path).ok().flatten());
+    let records = storage
+        .as_ref()
+        .and_then(|storage| storage.branch_records(&repo_path).ok())
+        .unwrap_or_default();
+    let names: Vec<String> = records.iter().map(|r| r.branch_name.clone()).collect();
+    let ranked = frecency::sort_branches_by_frecency(
+        &names,
+        &records,
+        config.scoring_for(&repo_path).half_life_days,
+    );
+    let rank = branch
+        .as_ref()
+        .and_then(|branch| ranked.iter().position(|(name, _)| name == branch))
+        .map(|i| i + 1);
+
+    if porcelain {
+        println!(
+            "{}\t{}\t{}",
+            branch.as_deref().unwrap_or(DETACHED),
+            previous.unwrap_or_default(),
+            rank.map(|rank| rank.to_string()).unwrap_or_default()
+        );
+        return Ok(());
+    }
+
+    println!("{}", branch.as_deref().unwrap_or(DETACHED));
+    if let Some(previous) = previous {
+        println!("  previous: {}", previous);
+    }
+    match rank {
+        Some(rank) => println!("  frecency rank: {} of {}", rank, ranked.len()),
+        None => println!("  frecency rank: not recorded yet"),
+    }
+    Ok(())
+}
+
+/// What 'ggo current' shows for the branch when HEAD is detached
+const DETACHED: &str = "(detached)";
@@ -0,0 +1,264 @@
+//! Moving the history around: 'ggo export', 'ggo import', 'ggo sync',
+//! 'ggo forget', 'ggo rename-record' and 'ggo migrate-storage'.
+
+use crate::error::{GgoError, Result};
+use crate::export::{ExportFormat, HistoryExport};
+use crate::switch::repo_key;
+use crate::{config, git, matcher, storage, sync, validation};
+
+/// Handle 'ggo forget': remove what ggo stored about the branches matching a
+/// name or glob in the current repository, or about a whole repository
+pub fn handle_forget_command(
+    storage: &storage::Storage,
+    pattern: Option<&str>,
+    repo: Option<&std::path::Path>,
+    dry_run: bool,
+    config: &config::Config,
+) -> Result<()> {
+    let pattern = match (pattern, repo) {
+        (Some(pattern), _) => pattern,
+        (None, Some(repo)) => return forget_repository(storage, repo, dry_run),
+        (None, None) => {
+            return Err(GgoError::Other(
+                "Nothing to forget\n\nUsage:\n  ggo forget <branch-or-glob>\n  ggo forget --repo <path>"
+                    .to_string(),
+            ))
+        }
+    };
+    validation::validate_pattern(pattern)?;
+
+    let repo_path = repo_key(storage, config)?;
+    let recorded = storage.recorded_branches(&repo_path)?;
+
+    // An exact name wins, so branch names containing glob characters still work
+    let matched: Vec<&str> = if recorded.iter().any(|b| b == pattern) {
+        vec![pattern]
+    } else {
+        recorded
+            .iter()
+            .filter(|b| matcher::glob_matches(pattern, b))
+            .map(String::as_str)
+            .collect()
+    };
+
+    if matched.is_empty() {
+        println!("Nothing stored about branches matching '{}'", pattern);
+        return Ok(());
+    }
+
+    let records = storage.branch_records(&repo_path).unwrap_or_default();
+    println!(
+        "{} {} branch(es):",
+        if dry_run {
+            "Would forget"
+        } else {
+            "Forgetting"
+        },
+        matched.len()
+    );
+    for branch in &matched {
+        let switches = records
+            .iter()
+            .find(|r| r.branch_name == *branch)
+            .map_or(0, |r| r.switch_count);
+        println!("  {} ({} switches)", branch, switches);
+    }
+
+    if !dry_run {
+        storage.forget_branches(&repo_path, &matched)?;
+    }
+
+    Ok(())
+}
+
+/// Handle 'ggo export': write the history of all repositories as JSON or CSV,
+/// to a file or standard output
+pub fn handle_export_command(
+    storage: &storage::Storage,
+    format: &str,
+    path: Option<&std::path::Path>,
+) -> Result<()> {
+    let format = ExportFormat::from_name(format).unwrap_or(ExportFormat::Json);
+    let export = storage.export_history()?;
+    let content = export.render(format)?;
+
+    match path {
+        Some(path) => {
+            std::fs::write(path, content)?;
+            println!("Exported {} records to {}", export.len(), path.display());
+        }
+        None => print!("{}", content),
+    }
+
+    Ok(())
+}
+
+/// Handle 'ggo import': load an export from a file or standard input
+pub fn handle_import_command(
+    storage: &storage::Storage,
+    path: Option<&std::path::Path>,
+    merge: bool,
+) -> Result<()> {
+    let content = match path.filter(|p| p.as_os_str() != "-") {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => std::io::read_to_string(std::io::stdin())?,
+    };
+    let export = HistoryExport::parse(&content)?;
+
+    let imported = storage.import_history(&export, merge)?;
+    println!(
+        "{} {} records",
+        if merge { "Merged" } else { "Imported" },
+        imported
+    );
+
+    Ok(())
+}
+
+/// Handle 'ggo sync': exchange history with other machines through the
+/// configured git repository
+pub fn handle_sync_command(storage: &storage::Storage, config: &config::Config) -> Result<()> {
+    let report = sync::sync(storage, &config.sync)?;
+
+    for (machine, changed) in &report.merged {
+        println!("Merged {} changed records from {}", changed, machine);
+    }
+    if report.merged.is_empty() {
+        println!("No other machines have synced yet");
+    }
+
+    let action = match (report.committed, report.pushed) {
+        (true, true) => "Pushed",
+        (true, false) => "Committed",
+        (false, _) => "Up to date:",
+    };
+    println!(
+        "{} history of {} in {}",
+        action,
+        report.machine,
+        report.checkout.display()
+    );
+
+    Ok(())
+}
+
+/// Handle 'ggo migrate-storage': copy the whole history to another backend
+/// and make it the configured one. The old backend's file is left in place.
+pub fn handle_migrate_storage_command(
+    config: &config::Config,
+    to: &str,
+    force: bool,
+) -> Result<()> {
+    let from = config.storage.backend;
+    let to = config::StorageBackend::from_name(to).expect("clap validates the backend");
+    if from == to {
+        println!("History is already kept in the {} backend", to.as_str());
+        return Ok(());
+    }
+
+    let source = storage::Storage::open_backend(from)?;
+    let target = storage::Storage::open_backend(to)?;
+    if !force && !target.snapshot()?.is_empty() {
+        return Err(GgoError::StorageNotEmpty(to.as_str().to_string()));
+    }
+    let snapshot = source.snapshot()?;
+    target.restore_snapshot(&snapshot)?;
+
+    let mut file_config = config::Config::load()?;
+    file_config.storage.backend = to;
+    file_config.save_keys(&["storage.backend"])?;
+
+    println!(
+        "Moved {} branch records and {} checkouts from {} to {} ({})",
+        snapshot.branches.len(),
+        snapshot.events.len(),
+        from.as_str(),
+        to.as_str(),
+        target.db_path().display()
+    );
+    println!(
+        "storage.backend is now {}; delete {} once you no longer need it",
+        to.as_str(),
+        source.db_path().display()
+    );
+    Ok(())
+}
+
+/// Forget everything stored under a repository, given by its path (which may
+/// no longer exist) or its remote key (`github.com/user/repo`)
+fn forget_repository(
+    storage: &storage::Storage,
+    repo: &std::path::Path,
+    dry_run: bool,
+) -> Result<()> {
+    let key = match stored_repo_key(storage, repo)? {
+        Some(key) => key,
+        None => {
+            println!("Nothing stored about repository '{}'", repo.display());
+            return Ok(());
+        }
+    };
+
+    if dry_run {
+        let rows = storage.repo_row_count(&key)?;
+        println!("Would forget {} rows stored for '{}'", rows, key);
+    } else {
+        let removed = storage.forget_repo(&key)?;
+        println!("Forgot {} rows stored for '{}'", removed, key);
+    }
+
+    Ok(())
+}
+
+/// The key history is stored under for the repository at `repo` (which may
+/// no longer exist), or a remote key given as is (`github.com/user/repo`).
+/// None if nothing is stored for it.
+pub fn stored_repo_key(
+    storage: &storage::Storage,
+    repo: &std::path::Path,
+) -> Result<Option<String>> {
+    let absolute = std::env::current_dir()
+        .map(|cwd| cwd.join(repo))
+        .unwrap_or_else(|_| repo.to_path_buf());
+
+    // Keys are stored with a trailing slash, canonicalised while the repository exists
+    let candidates = [
+        git::repo_path_key_for(repo),
+        Some(format!(
+            "{}/",
+            absolute.to_string_lossy().trim_end_matches('/')
+        )),
+        Some(repo.to_string_lossy().to_string()),
+    ];
+
+    for key in candidates.into_iter().flatten() {
+        if storage.repo_row_count(&key)? > 0 {
+            return Ok(Some(key));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Handle 'ggo rename-record': move the history of `old` to `new`, which has
+/// to exist
+pub fn handle_rename_record_command(
+    storage: &storage::Storage,
+    old: &str,
+    new: &str,
+    config: &config::Config,
+) -> Result<()> {
+    validation::validate_branch_name(old)?;
+    validation::validate_branch_name(new)?;
+    let repo_path = repo_key(storage, config)?;
+    if !git::get_branches()?.contains(&new.to_string()) {
+        return Err(GgoError::BranchNotFound(new.to_string()));
+    }
+
+    if storage.rename_branch_data(&repo_path, old, new)? {
+        println!("Moved the history of '{}' to '{}'", old, new);
+    } else {
+        println!("Nothing stored about '{}' in this repository", old);
+    }
+    Ok(())
+}
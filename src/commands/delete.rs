@@ -0,0 +1,475 @@
+//! Removing branches: 'ggo delete', 'ggo prune' and 'ggo stale'.
+
+use serde::Serialize;
+
+use crate::error::{GgoError, Result};
+use crate::ranking::rank_matching_branches;
+use crate::switch::repo_key;
+use crate::{config, constants, frecency, git, interactive, matcher, storage, style, validation};
+
+use super::batch::run_batch;
+use super::{display_columns, print_json};
+
+/// Handle the delete subcommand: pick matching branches, delete them, and drop
+/// their usage records and aliases. Frequently used branches need extra confirmation.
+///
+/// With `yes` nothing is picked: the pattern has to name one branch (see
+/// `unattended_target`).
+pub fn handle_delete_command(
+    storage: &storage::Storage,
+    pattern: &str,
+    force: bool,
+    yes: bool,
+    can_prompt: bool,
+    config: &config::Config,
+) -> Result<()> {
+    validation::validate_pattern(pattern)?;
+    if !yes && !can_prompt {
+        return Err(GgoError::NotATerminal(
+            "Passing -y to delete the branch named exactly, or the only one containing the pattern",
+        ));
+    }
+
+    let repo_path = repo_key(storage, config)?;
+    let current_branch = git::get_current_branch().ok();
+    let scoring = config.scoring_for(&repo_path);
+    let half_life_days = scoring.half_life_days;
+
+    // The current branch can't be deleted, so don't offer it
+    let branches: Vec<String> = git::get_branches()?
+        .into_iter()
+        .filter(|b| Some(b) != current_branch.as_ref())
+        .collect();
+    let records = storage.branch_records(&repo_path).unwrap_or_default();
+
+    let selected = if yes {
+        let branch =
+            unattended_target(&branches, pattern, config.behavior.ignore_case_for(pattern))?;
+        println!("Deleting '{}'", branch);
+        vec![branch]
+    } else {
+        let ranked = rank_matching_branches(
+            &branches,
+            pattern,
+            config.behavior.ignore_case_for(pattern),
+            config.behavior.default_fuzzy,
+            &records,
+            &scoring,
+            &config.matching.bonuses(),
+        );
+        if ranked.is_empty() {
+            return Err(GgoError::NoMatchingBranches(pattern.to_string()));
+        }
+
+        let candidates: Vec<String> = ranked.into_iter().map(|(b, _)| b).collect();
+        interactive::select_branches(
+            "Select branches to delete:",
+            &candidates,
+            &records,
+            display_columns(config),
+            half_life_days,
+        )?
+    };
+
+    if selected.is_empty() {
+        println!("No branches selected.");
+        return Ok(());
+    }
+
+    delete_branches(
+        storage,
+        &repo_path,
+        &selected,
+        &records,
+        if force { Force::All } else { Force::No },
+        yes,
+        half_life_days,
+    )
+}
+
+/// Handle the prune subcommand: list merged and upstream-gone branches with
+/// their frecency, then delete the ones the user picks
+pub fn handle_prune_command(
+    storage: &storage::Storage,
+    fetch: bool,
+    dry_run: bool,
+    yes: bool,
+    can_prompt: bool,
+    config: &config::Config,
+) -> Result<()> {
+    if fetch {
+        git::fetch_prune()?;
+    }
+
+    let repo_path = repo_key(storage, config)?;
+    let half_life_days = config.scoring_for(&repo_path).half_life_days;
+    let prunable = git::prunable_branches()?;
+
+    if prunable.is_empty() {
+        println!("No merged or gone branches to prune.");
+        return Ok(());
+    }
+    if !yes && !dry_run && !can_prompt {
+        return Err(GgoError::NotATerminal(
+            "Passing --dry-run to only list the branches, or -y to prune them all",
+        ));
+    }
+
+    let records = storage.branch_records(&repo_path).unwrap_or_default();
+
+    println!("Branches that can be pruned:\n");
+    for branch in &prunable {
+        let usage = match records.iter().find(|r| r.branch_name == branch.name) {
+            Some(record) => format!(
+                "score {:.1}, {} switches, last used {}",
+                frecency::calculate_score(record, half_life_days),
+                record.switch_count,
+                frecency::format_relative_time(record.last_used)
+            ),
+            None => "never used".to_string(),
+        };
+        println!(
+            "  {} {:<32} {}",
+            interactive::pad(&branch.name, 40),
+            branch.reason,
+            usage
+        );
+    }
+
+    if dry_run {
+        return Ok(());
+    }
+
+    let names: Vec<String> = prunable.iter().map(|b| b.name.clone()).collect();
+    let merged: Vec<String> = prunable
+        .into_iter()
+        .filter(|b| matches!(b.reason, git::PruneReason::Merged(_)))
+        .map(|b| b.name)
+        .collect();
+    let selected = if yes {
+        names
+    } else {
+        interactive::select_branches(
+            "Select branches to prune:",
+            &names,
+            &records,
+            display_columns(config),
+            half_life_days,
+        )?
+    };
+
+    if selected.is_empty() {
+        println!("No branches selected.");
+        return Ok(());
+    }
+
+    // Merged branches may not be merged into HEAD, so skip git's merge check
+    // for them; a branch whose upstream is gone may still hold the only copy
+    // of its commits, so it keeps the check
+    delete_branches(
+        storage,
+        &repo_path,
+        &selected,
+        &records,
+        Force::Merged(&merged),
+        yes,
+        half_life_days,
+    )
+}
+
+/// What 'ggo stale' was asked for
+pub struct StaleOptions {
+    pub days: u32,
+    pub mine: bool,
+    pub force: bool,
+    pub dry_run: bool,
+    pub yes: bool,
+    pub json: bool,
+}
+
+/// One 'ggo stale --json' entry
+#[derive(Serialize)]
+struct StaleBranch {
+    branch: String,
+    last_commit: i64,
+    /// Last checkout through ggo, if any
+    last_used: Option<i64>,
+    author_email: String,
+    /// The default branch, when the branch is merged into it
+    merged_into: Option<String>,
+}
+
+/// Handle the stale subcommand: list branches with neither a commit nor a
+/// checkout in the last `days` days, oldest first, then delete the ones the
+/// user picks. Unmerged branches are only deleted with `force`. Without a
+/// terminal (and without `yes`) it only lists them.
+pub fn handle_stale_command(
+    storage: &storage::Storage,
+    options: &StaleOptions,
+    can_prompt: bool,
+    config: &config::Config,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+    let half_life_days = config.scoring_for(&repo_path).half_life_days;
+    let records = storage.branch_records(&repo_path).unwrap_or_default();
+    let email = options.mine.then(git::user_email).transpose()?;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    let cutoff = now - i64::from(options.days) * constants::frecency::DAY_SECONDS;
+
+    let mut stale: Vec<StaleBranch> = git::branch_ages()?
+        .into_iter()
+        .filter(|age| {
+            email
+                .as_deref()
+                .is_none_or(|email| age.author_email.eq_ignore_ascii_case(email))
+        })
+        .map(|age| StaleBranch {
+            last_used: records
+                .iter()
+                .find(|r| r.branch_name == age.name)
+                .map(|r| r.last_used),
+            branch: age.name,
+            last_commit: age.last_commit,
+            author_email: age.author_email,
+            merged_into: age.merged_into,
+        })
+        .filter(|b| b.last_commit < cutoff && b.last_used.is_none_or(|used| used < cutoff))
+        .collect();
+    stale.sort_by_key(|b| b.last_commit.max(b.last_used.unwrap_or(0)));
+
+    if options.json {
+        return print_json(&stale);
+    }
+
+    if stale.is_empty() {
+        println!(
+            "No branches without a commit or checkout in the last {} days.",
+            options.days
+        );
+        return Ok(());
+    }
+
+    println!("Stale branches (oldest first):\n");
+    for branch in &stale {
+        let merged = match &branch.merged_into {
+            Some(into) => format!("merged into {}", into),
+            None => "not merged".to_string(),
+        };
+        let last_used = match branch.last_used {
+            Some(used) => format!("last used {}", frecency::format_relative_time(used)),
+            None => "never used".to_string(),
+        };
+        println!(
+            "  {} {:<24} last commit {:<10} {}",
+            interactive::pad(&branch.branch, 40),
+            merged,
+            frecency::format_relative_time(branch.last_commit),
+            last_used
+        );
+    }
+
+    if options.dry_run {
+        return Ok(());
+    }
+    // From a script or cron there is no menu to pick from: the listing is
+    // the report, and deleting takes an explicit --yes
+    if !options.yes && !can_prompt {
+        eprintln!(
+            "\nNo terminal to pick branches in, so nothing was deleted (-y deletes them all)."
+        );
+        return Ok(());
+    }
+
+    let names: Vec<String> = stale.iter().map(|b| b.branch.clone()).collect();
+    let selected = if options.yes {
+        names
+    } else {
+        interactive::select_branches(
+            "Select stale branches to delete:",
+            &names,
+            &records,
+            display_columns(config),
+            half_life_days,
+        )?
+    };
+
+    // Without --force, unmerged branches stay; merged ones may not be merged
+    // into HEAD, so git's own merge check is skipped for them
+    let (selected, unmerged): (Vec<String>, Vec<String>) = selected.into_iter().partition(|name| {
+        options.force
+            || stale
+                .iter()
+                .any(|b| &b.branch == name && b.merged_into.is_some())
+    });
+    for name in &unmerged {
+        style::warn(format!(
+            "Kept '{}': it is not merged (use -D to delete it anyway)",
+            name
+        ));
+    }
+
+    if selected.is_empty() {
+        println!("No branches selected.");
+        return Ok(());
+    }
+
+    let merged: Vec<String> = stale
+        .into_iter()
+        .filter(|b| b.merged_into.is_some())
+        .map(|b| b.branch)
+        .collect();
+    delete_branches(
+        storage,
+        &repo_path,
+        &selected,
+        &records,
+        if options.force {
+            Force::All
+        } else {
+            Force::Merged(&merged)
+        },
+        options.yes,
+        half_life_days,
+    )
+}
+
+/// The one branch `pattern` names when acting without prompting (`-y`): the
+/// branch of that exact name, or else the only one containing it. A fuzzy
+/// match, or several branches containing it, is too loose to delete on.
+pub(crate) fn unattended_target(
+    branches: &[String],
+    pattern: &str,
+    ignore_case: bool,
+) -> Result<String> {
+    if let Some(branch) = branches.iter().find(|b| *b == pattern) {
+        return Ok(branch.clone());
+    }
+
+    match matcher::filter_branches(branches, pattern, ignore_case).as_slice() {
+        [] => Err(GgoError::NoMatchingBranches(pattern.to_string())),
+        [branch] => Ok((*branch).clone()),
+        several => Err(GgoError::AmbiguousPattern(
+            pattern.to_string(),
+            several
+                .iter()
+                .map(|b| b.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        )),
+    }
+}
+
+/// Which branches [`delete_branches`] deletes without git's merge check
+#[derive(Clone, Copy)]
+pub(crate) enum Force<'a> {
+    /// None: unmerged branches are refused
+    No,
+    /// Every branch, as asked for with -D; under `yes` this also lets
+    /// frequently used branches go
+    All,
+    /// Only these, known to be merged into the default branch rather than HEAD
+    Merged(&'a [String]),
+}
+
+impl Force<'_> {
+    fn skips_merge_check(self, branch: &str) -> bool {
+        match self {
+            Force::No => false,
+            Force::All => true,
+            Force::Merged(merged) => merged.iter().any(|m| m == branch),
+        }
+    }
+}
+
+/// Delete branches and everything stored about them. Frequently used branches
+/// get a loud warning and need an extra confirmation; with `yes` there is no
+/// one to ask, so they're refused unless forced with -D.
+pub(crate) fn delete_branches(
+    storage: &storage::Storage,
+    repo_path: &str,
+    branches: &[String],
+    records: &[storage::BranchRecord],
+    force: Force,
+    yes: bool,
+    half_life_days: f64,
+) -> Result<()> {
+    use constants::scoring::HIGH_FRECENCY_SCORE;
+
+    run_batch(branches, "delete", |branch| {
+        if let Some(record) = records.iter().find(|r| r.branch_name == branch) {
+            let score = frecency::calculate_score(record, half_life_days);
+            if score >= HIGH_FRECENCY_SCORE {
+                eprintln!(
+                    "⚠️  WARNING: '{}' is one of your most used branches (score {:.1}, {} switches, last used {})",
+                    branch,
+                    score,
+                    record.switch_count,
+                    frecency::format_relative_time(record.last_used)
+                );
+                if yes && !matches!(force, Force::All) {
+                    return Err(GgoError::FrequentBranch(branch.to_string()));
+                }
+                if !yes && !interactive::confirm_delete_frequent_branch(branch)? {
+                    println!("Skipped '{}'", branch);
+                    return Ok(());
+                }
+            }
+        }
+
+        git::delete_branch(branch, force.skips_merge_check(branch))?;
+
+        if let Err(e) = storage.delete_branch_data(repo_path, branch) {
+            style::warn(format!(
+                "Could not remove stored data for '{}': {}",
+                branch, e
+            ));
+        }
+
+        println!("Deleted branch '{}'", branch);
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unattended_target_needs_one_branch() {
+        let branches: Vec<String> = [
+            "a-u-t-h",
+            "add-unit-tests-handler",
+            "auth",
+            "auth-api",
+            "Billing",
+        ]
+        .iter()
+        .map(|b| b.to_string())
+        .collect();
+
+        // An exact name wins over the other branches containing it
+        assert_eq!(unattended_target(&branches, "auth", false).unwrap(), "auth");
+        assert_eq!(
+            unattended_target(&branches, "api", false).unwrap(),
+            "auth-api"
+        );
+        assert_eq!(
+            unattended_target(&branches, "billing", true).unwrap(),
+            "Billing"
+        );
+
+        assert!(matches!(
+            unattended_target(&branches, "aut", false),
+            Err(GgoError::AmbiguousPattern(_, list)) if list == "auth, auth-api"
+        ));
+        // Fuzzy matches don't count
+        assert!(matches!(
+            unattended_target(&branches, "auh", false),
+            Err(GgoError::NoMatchingBranches(_))
+        ));
+    }
+}
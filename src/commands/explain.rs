@@ -0,0 +1,357 @@
+//! 'ggo explain' and 'ggo suggest': why branches rank where they do, and
+//! what usually comes next.
+
+use serde::Serialize;
+use tabled::{
+    settings::{object::Rows, Alignment, Modify, Style},
+    Table, Tabled,
+};
+
+use crate::config::AutoSelect;
+use crate::error::Result;
+use crate::switch::repo_key;
+use crate::{backend, config, frecency, git, interactive, ranking, storage};
+
+use super::checkout::load_session_weights;
+use super::{load_pinned_branches, print_json};
+
+/// JSON form of one `ggo suggest` entry
+#[derive(Serialize)]
+struct SuggestionJson {
+    branch: String,
+    probability: f64,
+    count: i64,
+}
+
+/// Show the branches most likely to be checked out next from the current one
+pub fn handle_suggest_command(
+    storage: &storage::Storage,
+    limit: usize,
+    json: bool,
+    config: &config::Config,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+    let branches = git::get_branches()?;
+    let current = git::get_current_branch()?;
+
+    let transitions = storage.transitions_from(&repo_path, &current)?;
+    let suggestions: Vec<ranking::Suggestion> =
+        ranking::suggest_next_branches(&transitions, &branches)
+            .into_iter()
+            .filter(|s| s.branch != current)
+            .take(limit)
+            .collect();
+
+    if json {
+        let output: Vec<SuggestionJson> = suggestions
+            .into_iter()
+            .map(|s| SuggestionJson {
+                branch: s.branch,
+                probability: s.probability,
+                count: s.count,
+            })
+            .collect();
+        return print_json(&output);
+    }
+
+    if suggestions.is_empty() {
+        println!(
+            "No switches from '{}' recorded yet. ggo learns where you go next as you switch branches.",
+            current
+        );
+        return Ok(());
+    }
+
+    println!("After '{}' you usually switch to:\n", current);
+    for (i, suggestion) in suggestions.iter().enumerate() {
+        println!(
+            "  {:2}. {} {:>4.0}%  ({} times)",
+            i + 1,
+            interactive::pad(&suggestion.branch, 40),
+            suggestion.probability * 100.0,
+            suggestion.count
+        );
+    }
+
+    Ok(())
+}
+
+/// JSON form of one `ggo explain` candidate
+#[derive(Serialize)]
+struct ExplainedBranchJson {
+    branch: String,
+    fuzzy_score: Option<i64>,
+    switch_count: i64,
+    last_used: Option<i64>,
+    decay_weight: f64,
+    frecency_score: f64,
+    frecency_component: f64,
+    session_component: f64,
+    score: f64,
+    pinned: bool,
+}
+
+/// JSON form of `ggo explain`
+#[derive(Serialize)]
+struct ExplainJson {
+    pattern: String,
+    /// Rule that decides: alias, create, single, auto_select or interactive
+    decision: &'static str,
+    /// Branch the rule picks; None when the menu decides or nothing matches
+    selected: Option<String>,
+    auto_select_threshold: f64,
+    half_life_days: f64,
+    frecency_multiplier: f64,
+    candidates: Vec<ExplainedBranchJson>,
+}
+
+/// Handle 'ggo explain': show how every candidate for a pattern is scored and
+/// which rule would decide the checkout, without checking anything out
+pub fn handle_explain_command(
+    storage: &storage::Storage,
+    pattern: &str,
+    ignore_case: bool,
+    use_fuzzy: bool,
+    json: bool,
+    config: &config::Config,
+) -> Result<()> {
+    let branches = git::get_branches()?;
+    let repo_path = repo_key(storage, config)?;
+    let scoring = config.scoring_for(&repo_path);
+    let half_life_days = scoring.half_life_days;
+    let threshold = scoring.auto_select_threshold;
+    let min_score = config.behavior.auto_select_min_score;
+    let records = storage.branch_records(&repo_path)?;
+    let pinned = load_pinned_branches(storage, &repo_path);
+
+    let matchable = config.behavior.offered_branches(branches.clone());
+    let mut candidates = ranking::explain_matching_branches(
+        &matchable,
+        pattern,
+        ignore_case,
+        use_fuzzy,
+        &records,
+        &scoring,
+        &config.matching.bonuses(),
+    );
+
+    let ranked: Vec<(String, f64)> = candidates
+        .iter()
+        .map(|c| (c.branch.clone(), c.score))
+        .collect();
+    let session = load_session_weights(backend::backend(), storage, &repo_path, config);
+    let boosts = ranking::session_boosts(&ranked, &session, threshold);
+    for candidate in candidates.iter_mut() {
+        candidate.session_component = boosts.get(&candidate.branch).copied().unwrap_or(0.0);
+        candidate.score += candidate.session_component;
+    }
+    candidates.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    // Same order as the checkout: pinned branches first
+    candidates.sort_by_key(|c| !pinned.contains(&c.branch));
+
+    let ranked: Vec<(String, f64)> = candidates
+        .iter()
+        .map(|c| (c.branch.clone(), c.score))
+        .collect();
+    let alias = storage
+        .alias(&repo_path, pattern)?
+        .filter(|branch| branches.contains(branch));
+
+    // Mirrors find_and_checkout_branch
+    let (decision, selected, reason) = if let Some(branch) = &alias {
+        (
+            "alias",
+            Some(branch.clone()),
+            format!(
+                "alias '{}' points to '{}', so matching is skipped",
+                pattern, branch
+            ),
+        )
+    } else if ranked.is_empty() {
+        (
+            "create",
+            None,
+            format!(
+                "ggo offers to create '{}' (-c creates it without asking)",
+                pattern
+            ),
+        )
+    } else if ranked.len() == 1 {
+        (
+            "single",
+            Some(ranked[0].0.clone()),
+            format!("'{}' is the only match", ranked[0].0),
+        )
+    } else if config.behavior.auto_select == AutoSelect::Always {
+        (
+            "auto_select",
+            Some(ranked[0].0.clone()),
+            format!(
+                "behavior.auto_select is 'always', so the top match '{}' is taken",
+                ranked[0].0
+            ),
+        )
+    } else if config.behavior.auto_select == AutoSelect::Never {
+        (
+            "interactive",
+            None,
+            format!(
+                "behavior.auto_select is 'never', so ggo shows the menu \
+                 (without a terminal it takes '{}')",
+                ranked[0].0
+            ),
+        )
+    } else if ranking::is_clear_winner(&ranked, threshold) && ranked[0].1 < min_score {
+        (
+            "interactive",
+            None,
+            format!(
+                "'{}' leads but scores {:.1}, below behavior.auto_select_min_score {}, \
+                 so ggo shows the menu (without a terminal it takes it)",
+                ranked[0].0, ranked[0].1, min_score
+            ),
+        )
+    } else if ranking::is_clear_winner(&ranked, threshold) {
+        let reason = if ranked[1].1 == 0.0 {
+            format!("'{}' wins: the runner-up scores 0", ranked[0].0)
+        } else {
+            format!(
+                "'{}' scores {:.2}× the runner-up, at least the auto-select threshold of {}",
+                ranked[0].0,
+                ranked[0].1 / ranked[1].1,
+                threshold
+            )
+        };
+        ("auto_select", Some(ranked[0].0.clone()), reason)
+    } else {
+        (
+            "interactive",
+            None,
+            format!(
+                "the top two scores are within {:.2}× (threshold {}), so ggo shows the menu \
+                 (without a terminal it takes '{}')",
+                ranked[0].1 / ranked[1].1,
+                threshold,
+                ranked[0].0
+            ),
+        )
+    };
+
+    if json {
+        let output = ExplainJson {
+            pattern: pattern.to_string(),
+            decision,
+            selected,
+            auto_select_threshold: threshold,
+            half_life_days,
+            frecency_multiplier: scoring.frecency_multiplier,
+            candidates: candidates
+                .into_iter()
+                .map(|c| ExplainedBranchJson {
+                    pinned: pinned.contains(&c.branch),
+                    branch: c.branch,
+                    fuzzy_score: c.fuzzy_score,
+                    switch_count: c.switch_count,
+                    last_used: c.last_used,
+                    decay_weight: c.decay_weight,
+                    frecency_score: c.frecency_score,
+                    frecency_component: c.frecency_component,
+                    session_component: c.session_component,
+                    score: c.score,
+                })
+                .collect(),
+        };
+        return print_json(&output);
+    }
+
+    println!(
+        "Scoring for '{}' ({} matching, {}; half-life {} days{})\n",
+        pattern,
+        if use_fuzzy { "fuzzy" } else { "substring" },
+        if ignore_case {
+            "ignoring case"
+        } else {
+            "case-sensitive"
+        },
+        half_life_days,
+        if use_fuzzy {
+            format!(
+                ", score = fuzzy + frecency × {} + session",
+                scoring.frecency_multiplier
+            )
+        } else {
+            ", score = frecency + session".to_string()
+        }
+    );
+
+    if !candidates.is_empty() {
+        #[derive(Tabled)]
+        struct CandidateRow {
+            #[tabled(rename = "Branch")]
+            branch: String,
+            #[tabled(rename = "Fuzzy")]
+            fuzzy: String,
+            #[tabled(rename = "Switches")]
+            switches: i64,
+            #[tabled(rename = "Last used")]
+            age: String,
+            #[tabled(rename = "Decay")]
+            decay: String,
+            #[tabled(rename = "Frecency")]
+            frecency: String,
+            #[tabled(rename = "+Frecency")]
+            component: String,
+            #[tabled(rename = "+Session")]
+            session: String,
+            #[tabled(rename = "Score")]
+            score: String,
+        }
+
+        let rows: Vec<CandidateRow> = candidates
+            .iter()
+            .map(|c| CandidateRow {
+                branch: if pinned.contains(&c.branch) {
+                    format!("★ {}", c.branch)
+                } else {
+                    c.branch.clone()
+                },
+                fuzzy: c
+                    .fuzzy_score
+                    .map(|f| f.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                switches: c.switch_count,
+                age: c
+                    .last_used
+                    .map(frecency::format_relative_time)
+                    .unwrap_or_else(|| "never".to_string()),
+                decay: format!("{:.3}", c.decay_weight),
+                frecency: format!("{:.2}", c.frecency_score),
+                component: format!("{:.1}", c.frecency_component),
+                session: format!("{:.1}", c.session_component),
+                score: format!("{:.1}", c.score),
+            })
+            .collect();
+
+        let mut table = Table::new(rows);
+        table
+            .with(Style::rounded())
+            .with(Modify::new(Rows::first()).with(Alignment::center()));
+        println!("{}\n", table);
+    }
+
+    let rule = match decision {
+        "alias" => "Alias",
+        "create" => "No match",
+        "single" => "Single match",
+        "auto_select" => "Auto-select",
+        _ => "Interactive",
+    };
+    println!("Decision: {}: {}", rule, reason);
+
+    Ok(())
+}
@@ -0,0 +1,157 @@
+//! 'ggo find' (branches by commit message) and 'ggo contains' (branches by
+//! commit).
+
+use std::io::IsTerminal;
+
+use crate::config::AutoSelect;
+use crate::error::{GgoError, Result};
+use crate::switch::{repo_key, SwitchOptions};
+use crate::{config, frecency, git, interactive, ranking, storage, style, validation};
+
+use super::checkout::{pick_branch, switch_to_branch};
+use super::load_pinned_branches;
+
+/// Handle 'ggo find': search the branches for commits matching the query,
+/// then list them or check out the one holding the newest match (asking when
+/// several branches match and a terminal is available)
+pub fn handle_find_command(
+    storage: &storage::Storage,
+    query: &git::CommitQuery,
+    unmerged: bool,
+    list: bool,
+    switch: &SwitchOptions,
+    config: &config::Config,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+
+    // Progress on stderr, only where someone is watching
+    let show_progress = std::io::stderr().is_terminal();
+    let hits = git::find_commits(query, unmerged, |done, total| {
+        if show_progress {
+            eprint!("\rSearching branches… {}/{}", done, total);
+        }
+    })?;
+    if show_progress {
+        eprint!("\r\x1b[2K");
+    }
+
+    let hits: Vec<git::CommitHit> = hits
+        .into_iter()
+        .filter(|hit| config.behavior.offers(&hit.branch))
+        .collect();
+
+    if hits.is_empty() {
+        let criteria: Vec<String> = [
+            query.message.as_ref().map(|m| format!("message '{}'", m)),
+            query.author.as_ref().map(|a| format!("author '{}'", a)),
+            query
+                .touches
+                .as_ref()
+                .map(|t| format!("changes to '{}'", t)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        return Err(GgoError::NoMatchingCommits(criteria.join(", ")));
+    }
+
+    if list {
+        for hit in &hits {
+            let more = if hit.count > 1 {
+                format!(" (+{} more)", hit.count - 1)
+            } else {
+                String::new()
+            };
+            println!(
+                "  {} {} {:>10}  {}{}",
+                interactive::pad(&hit.branch, 40),
+                hit.commit,
+                frecency::format_relative_time(hit.time),
+                interactive::truncate(&hit.summary, 60),
+                more
+            );
+        }
+        return Ok(());
+    }
+
+    let hit = match hits.len() {
+        1 => &hits[0],
+        _ if switch.can_prompt => &hits[interactive::select_commit_hit(&hits)?],
+        _ => {
+            if !switch.quiet {
+                eprintln!(
+                    "{} branches have matching commits; picking '{}', which has the newest (no interactive terminal)",
+                    hits.len(),
+                    hits[0].branch
+                );
+            }
+            &hits[0]
+        }
+    };
+
+    println!("Found in '{}': {} {}", hit.branch, hit.commit, hit.summary);
+    switch_to_branch(storage, config, &repo_path, &hit.branch, switch)
+}
+
+/// Handle 'ggo contains': check out the branch containing a commit. The
+/// branches are ranked by frecency (pinned first); the top one is taken when
+/// it clearly wins, otherwise the picker is shown.
+pub fn checkout_branch_containing(
+    storage: &storage::Storage,
+    rev: &str,
+    switch: &SwitchOptions,
+    config: &config::Config,
+) -> Result<()> {
+    validation::validate_pattern(rev)?;
+    let repo_path = repo_key(storage, config)?;
+
+    let branches =
+        git::branches_containing(rev)?.ok_or_else(|| GgoError::UnknownCommit(rev.to_string()))?;
+    let branches = config.behavior.offered_branches(branches);
+    if branches.is_empty() {
+        return Err(GgoError::NoBranchContains(rev.to_string()));
+    }
+
+    let records = storage.branch_records(&repo_path).unwrap_or_else(|e| {
+        style::warn(format!("Could not load branch history: {}", e));
+        vec![]
+    });
+    let scoring = config.scoring_for(&repo_path);
+    let ranked = frecency::sort_branches_by_frecency(&branches, &records, scoring.half_life_days);
+    let ranked = ranking::float_pinned(ranked, &load_pinned_branches(storage, &repo_path));
+
+    // Without any history there is no winner to pick, unless told to take the top
+    let clear_winner = (ranked[0].1 > 0.0 || config.behavior.auto_select == AutoSelect::Always)
+        && ranking::auto_selects(
+            &ranked,
+            config.behavior.auto_select,
+            scoring.auto_select_threshold,
+            config.behavior.auto_select_min_score,
+        );
+    let branch = if ranked.len() == 1 || clear_winner {
+        ranked[0].0.clone()
+    } else if switch.can_prompt {
+        let branch_list: Vec<String> = ranked.iter().map(|(b, _)| b.clone()).collect();
+        pick_branch(
+            storage,
+            &repo_path,
+            &branch_list,
+            &records,
+            config.behavior.picker,
+            config,
+            scoring.half_life_days,
+        )?
+    } else {
+        if !switch.quiet {
+            eprintln!(
+                "{} branches contain '{}'; picking '{}' (no interactive terminal)",
+                ranked.len(),
+                rev,
+                ranked[0].0
+            );
+        }
+        ranked[0].0.clone()
+    };
+
+    switch_to_branch(storage, config, &repo_path, &branch, switch)
+}
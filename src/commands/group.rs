@@ -0,0 +1,172 @@
+//! 'ggo group': named sets of branches and patterns, and picking within one.
+
+use std::collections::BTreeMap;
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::error::{GgoError, Result};
+use crate::style::{self, Role};
+use crate::switch::{repo_key, SwitchOptions};
+use crate::{config, frecency, git, groups, interactive, ranking, storage, validation};
+
+use super::checkout::{pick_branch, switch_to_branch};
+use super::load_pinned_branches;
+
+/// Groups of the current repository: the stored ones and those in its
+/// `.ggo.toml`
+fn all_groups(
+    storage: &storage::Storage,
+    repo_path: &str,
+) -> Result<BTreeMap<String, Vec<String>>> {
+    // A bare repository has no checked-out .ggo.toml
+    let repo_groups = match git::get_repo_root() {
+        Ok(repo_root) => groups::repo_groups(std::path::Path::new(&repo_root))?,
+        Err(GgoError::BareRepository) => BTreeMap::new(),
+        Err(e) => return Err(e),
+    };
+    Ok(groups::merge(
+        storage.branch_groups(repo_path)?,
+        repo_groups,
+    ))
+}
+
+/// Branch names and patterns of a group in the current repository
+pub fn group_members(
+    storage: &storage::Storage,
+    group: &str,
+    config: &config::Config,
+) -> Result<Vec<String>> {
+    let repo_path = repo_key(storage, config)?;
+    all_groups(storage, &repo_path)?
+        .remove(group)
+        .ok_or_else(|| GgoError::GroupNotFound(group.to_string()))
+}
+
+/// Handle 'ggo group add': add branches or patterns to a group, creating it
+pub fn add_to_group(
+    storage: &storage::Storage,
+    name: &str,
+    branches: &[String],
+    config: &config::Config,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+    validation::validate_group_name(name)?;
+    let local = git::get_branches()?;
+    for branch in branches {
+        // Names must exist; patterns are for branches to come
+        if branch.contains(['*', '?']) {
+            validation::validate_pattern(branch)?;
+        } else {
+            validation::validate_branch_name(branch)?;
+            if !local.contains(branch) {
+                return Err(GgoError::BranchNotFound(branch.clone()));
+            }
+        }
+    }
+
+    let members: Vec<&str> = branches.iter().map(String::as_str).collect();
+    let added = storage.add_to_group(&repo_path, name, &members)?;
+    println!("Added {} to '{}'", added, name);
+    Ok(())
+}
+
+/// Handle 'ggo group remove': take branches or patterns out of a group, or
+/// remove the whole group when none are given
+pub fn remove_from_group(
+    storage: &storage::Storage,
+    name: &str,
+    branches: &[String],
+    config: &config::Config,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+    let members: Vec<&str> = branches.iter().map(String::as_str).collect();
+    let removed = storage.remove_from_group(&repo_path, name, &members)?;
+
+    if removed == 0 && !all_groups(storage, &repo_path)?.contains_key(name) {
+        return Err(GgoError::GroupNotFound(name.to_string()));
+    }
+    if members.is_empty() {
+        println!("Removed group '{}'", name);
+    } else {
+        println!("Removed {} from '{}'", removed, name);
+    }
+    Ok(())
+}
+
+/// Handle 'ggo group': list the groups with their members and how many
+/// branches they hold
+pub fn list_groups(storage: &storage::Storage, config: &config::Config) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+    let groups = all_groups(storage, &repo_path)?;
+    if groups.is_empty() {
+        println!(
+            "No groups in this repository. Create one with 'ggo group add <group> <branch>...'."
+        );
+        return Ok(());
+    }
+
+    let branches = git::get_branches()?;
+    let width = groups.keys().map(|g| g.width()).max().unwrap_or(0);
+    println!("Groups:\n");
+    for (group, members) in &groups {
+        let count = branches
+            .iter()
+            .filter(|b| groups::contains(members, b))
+            .count();
+        println!(
+            "  {}  {}  {}",
+            style::paint(Role::Header, interactive::pad(group, width)),
+            members.join(", "),
+            style::paint(Role::Dim, format!("({} branches)", count))
+        );
+    }
+    Ok(())
+}
+
+/// Handle `ggo group <name>`: pick a branch from the group and switch to
+/// it, or list its branches when there is no terminal to pick in
+pub fn pick_from_group(
+    storage: &storage::Storage,
+    name: &str,
+    switch: &SwitchOptions,
+    config: &config::Config,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+    let members = group_members(storage, name, config)?;
+    let branches: Vec<String> = config
+        .behavior
+        .offered_branches(git::get_branches()?)
+        .into_iter()
+        .filter(|b| groups::contains(&members, b))
+        .collect();
+    if branches.is_empty() {
+        return Err(GgoError::Other(format!(
+            "No local branches in group '{}'",
+            name
+        )));
+    }
+
+    let records = storage.branch_records(&repo_path).unwrap_or_default();
+    let half_life_days = config.scoring_for(&repo_path).half_life_days;
+    let ranked = frecency::sort_branches_by_frecency(&branches, &records, half_life_days);
+    let ranked = ranking::float_pinned(ranked, &load_pinned_branches(storage, &repo_path));
+    let branch_list: Vec<String> = ranked.into_iter().map(|(b, _)| b).collect();
+
+    if !switch.can_prompt {
+        for branch in &branch_list {
+            println!("{}", branch);
+        }
+        return Ok(());
+    }
+
+    let branch = pick_branch(
+        storage,
+        &repo_path,
+        &branch_list,
+        &records,
+        config.behavior.picker,
+        config,
+        half_life_days,
+    )?;
+    switch_to_branch(storage, config, &repo_path, &branch, switch)
+}
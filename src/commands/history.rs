@@ -0,0 +1,198 @@
+//! Going back: 'ggo -' and 'ggo -N', 'ggo --history' and 'ggo undo'.
+
+use crate::error::{GgoError, Result};
+use crate::switch::{
+    branch_last_used, check_pre_checkout, repo_key, run_post_checkout_hooks, SwitchOptions,
+};
+use crate::{config, constants, frecency, git, interactive, storage};
+
+use super::checkout::{switch_or_print, switch_to_detach_target};
+
+pub fn checkout_previous_branch(
+    storage: &storage::Storage,
+    config: &config::Config,
+    switch: &SwitchOptions,
+    print: bool,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+
+    let previous_branch = storage
+        .previous_branch(&repo_path)?
+        .ok_or(GgoError::NoPreviousBranch)?;
+
+    // The last switch left a detached HEAD: go back to its commit
+    if git::is_commit_id(&previous_branch) && !switch.backend.branch_exists(&previous_branch)? {
+        let target = git::resolve_commit(&previous_branch)?.ok_or(GgoError::NoPreviousBranch)?;
+        if print {
+            println!("{}", target.commit);
+            return Ok(());
+        }
+        return switch_to_detach_target(storage, config, &repo_path, &target, switch);
+    }
+
+    switch_or_print(storage, config, &repo_path, &previous_branch, switch, print)
+}
+
+/// Parse a '-N' pattern (N >= 1) into a history position
+pub fn parse_history_position(pattern: &str) -> Option<usize> {
+    let digits = pattern.strip_prefix('-')?;
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok().filter(|&n| n >= 1)
+}
+
+/// Get the checkout history stack, skipping the current branch and branches
+/// that no longer exist, so that position 1 is the last branch switched away from
+fn history_stack(
+    storage: &storage::Storage,
+    repo_path: &str,
+) -> Result<Vec<storage::HistoryEntry>> {
+    let branches = git::get_branches()?;
+    let current_branch = git::get_current_branch().ok();
+
+    Ok(storage
+        .checkout_history(repo_path)?
+        .into_iter()
+        .filter(|e| Some(&e.branch_name) != current_branch.as_ref())
+        .filter(|e| branches.contains(&e.branch_name))
+        .collect())
+}
+
+pub fn checkout_history_entry(
+    storage: &storage::Storage,
+    config: &config::Config,
+    position: usize,
+    switch: &SwitchOptions,
+    print: bool,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+
+    let entry = history_stack(storage, &repo_path)?
+        .into_iter()
+        .nth(position - 1)
+        .ok_or(GgoError::NoHistoryEntry(position))?;
+
+    switch_or_print(
+        storage,
+        config,
+        &repo_path,
+        &entry.branch_name,
+        switch,
+        print,
+    )
+}
+
+/// Show the checkout history stack for the current repository
+pub fn show_history(storage: &storage::Storage, config: &config::Config) -> Result<()> {
+    use constants::history::HISTORY_DISPLAY_LIMIT;
+
+    let repo_path = repo_key(storage, config)?;
+    let history = history_stack(storage, &repo_path)?;
+
+    if history.is_empty() {
+        println!(
+            "No checkout history for this repository yet. Switch branches with ggo to build it!"
+        );
+        return Ok(());
+    }
+
+    println!("Checkout history (use 'ggo -N' to jump back):");
+    for (i, entry) in history.iter().take(HISTORY_DISPLAY_LIMIT).enumerate() {
+        println!(
+            "  -{:<3} {} ({})",
+            i + 1,
+            interactive::pad(&entry.branch_name, 40),
+            frecency::format_relative_time(entry.switched_at)
+        );
+    }
+
+    Ok(())
+}
+
+/// Handle 'ggo undo': switch back from the last recorded checkout and remove
+/// every trace of it from the history, so a mistaken switch doesn't skew ranking
+pub fn handle_undo_command(
+    storage: &storage::Storage,
+    config: &config::Config,
+    switch: &SwitchOptions,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+
+    let last = storage.last_checkout(&repo_path)?.ok_or_else(|| {
+        GgoError::NothingToUndo("no switches recorded in this repository".to_string())
+    })?;
+
+    let from = last.from_branch.clone().ok_or_else(|| {
+        GgoError::NothingToUndo(format!(
+            "the switch to '{}' didn't record where it came from",
+            last.to_branch
+        ))
+    })?;
+
+    // Only the switch that left us here can be undone
+    let backend = switch.backend;
+    let current = backend.current_branch()?;
+    if current != last.to_branch {
+        return Err(GgoError::NothingToUndo(format!(
+            "the last switch went to '{}', but '{}' is checked out now",
+            last.to_branch, current
+        )));
+    }
+
+    // Going back is a checkout like any other: the branch has to be there and
+    // free to check out here, and the pre-checkout guards and hooks apply
+    if !backend.branch_exists(&from)? {
+        return Err(GgoError::BranchNotFound(from));
+    }
+    if let Some(worktree) = backend.worktree_for_branch(&from)? {
+        return Err(GgoError::BranchInWorktree(
+            from,
+            worktree.display().to_string(),
+        ));
+    }
+    backend.repo_root()?;
+    let last_used = branch_last_used(storage, &repo_path, &from);
+    check_pre_checkout(
+        storage,
+        config,
+        switch,
+        Some(&current),
+        &from,
+        last_used,
+        false,
+    )?;
+
+    backend.checkout(&from)?;
+    // With --no-record the history is left as it is
+    let undone = storage.undo_last_checkout(&repo_path)?;
+
+    if undone.is_some() {
+        println!(
+            "Switched back to branch '{}' (undid the switch to '{}')",
+            from, last.to_branch
+        );
+    } else {
+        println!(
+            "Switched back to branch '{}' (history unchanged: recording is off)",
+            from
+        );
+    }
+    run_post_checkout_hooks(storage, config, switch, Some(&current), &from, false);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_history_position() {
+        assert_eq!(parse_history_position("-1"), Some(1));
+        assert_eq!(parse_history_position("-12"), Some(12));
+        assert_eq!(parse_history_position("-"), None);
+        assert_eq!(parse_history_position("-0"), None);
+        assert_eq!(parse_history_position("-2a"), None);
+        assert_eq!(parse_history_position("feature-2"), None);
+    }
+}
@@ -0,0 +1,57 @@
+//! 'ggo hooks': showing, trusting and untrusting a repository's own hooks.
+
+use crate::backend::GitBackend;
+use crate::error::Result;
+use crate::{constants, hooks, storage};
+
+/// Handle 'ggo hooks': show the hooks in this repository's `.ggo.toml` and
+/// whether they run, or with `trust`, trust (`true`) or stop trusting
+/// (`false`) the file as it is now
+pub fn handle_hooks_command(
+    storage: &storage::Storage,
+    trust: Option<bool>,
+    backend: &dyn GitBackend,
+) -> Result<()> {
+    let repo_root = backend.repo_root()?;
+    let repo_hooks = hooks::repo_hooks(std::path::Path::new(&repo_root))?;
+    let file = constants::hooks::REPO_HOOKS_FILE;
+
+    if trust == Some(false) {
+        storage.set_trusted_hooks(&repo_root, None)?;
+        println!("The hooks in {} no longer run in this repository", file);
+        return Ok(());
+    }
+    if repo_hooks.is_empty() {
+        println!("No hooks in {} in this repository", file);
+        return Ok(());
+    }
+
+    for command in &repo_hooks.pre_checkout {
+        println!("pre_checkout:  {}", command);
+    }
+    for command in &repo_hooks.post_checkout {
+        println!("post_checkout: {}", command);
+    }
+
+    if trust == Some(true) {
+        storage.set_trusted_hooks(&repo_root, Some(&repo_hooks.digest))?;
+        println!(
+            "Trusted: these hooks run on switches here until {} changes",
+            file
+        );
+        return Ok(());
+    }
+
+    let trusted_digest = storage.trusted_hooks(&repo_root)?;
+    match repo_hooks.trust(trusted_digest.as_deref()) {
+        hooks::Trust::Trusted => println!("Trusted: these hooks run on switches here"),
+        hooks::Trust::Untrusted => {
+            println!("Not trusted: review them, then allow them with 'ggo hooks trust'")
+        }
+        hooks::Trust::Changed => println!(
+            "Not trusted: {} changed since it was trusted; review it, then run 'ggo hooks trust' again",
+            file
+        ),
+    }
+    Ok(())
+}
@@ -0,0 +1,435 @@
+//! 'ggo -l' and 'ggo query': printing the ranked branches instead of
+//! switching, and checking out by the number a listing showed.
+
+use std::collections::HashMap;
+use std::io::IsTerminal;
+
+use serde::Serialize;
+use tracing::debug;
+
+use crate::config::MenuColumn;
+use crate::error::{GgoError, Result};
+use crate::query::{cached_branches, query_branches};
+use crate::style::{self, Role};
+use crate::switch::{repo_key, SwitchOptions};
+use crate::{
+    backend, config, constants, frecency, git, interactive, logfile, matcher, ranking, storage,
+    template,
+};
+
+use super::checkout::{boost_likely_next, boost_session, switch_or_print};
+use super::{display_columns, load_branch_notes, load_pinned_branches, print_json, print_lines};
+
+/// One `ggo --list` entry, for `--json` and `--format` templates
+#[derive(Serialize)]
+struct ListedBranch {
+    branch: String,
+    score: f64,
+    /// None when substring matching is used
+    fuzzy_score: Option<i64>,
+    frecency_score: f64,
+    aliases: Vec<String>,
+    /// None when the branch has never been switched to with ggo
+    last_used: Option<i64>,
+    pinned: bool,
+    note: Option<String>,
+    /// Set with 'git branch --edit-description'
+    description: Option<String>,
+    /// Remote-tracking branch, None when there is no upstream
+    upstream: Option<String>,
+    /// Commits ahead of / behind the upstream
+    ahead: Option<usize>,
+    behind: Option<usize>,
+    last_commit: Option<i64>,
+    last_commit_summary: Option<String>,
+    switch_count: i64,
+    /// Checked out in the current worktree
+    current: bool,
+}
+
+impl ListedBranch {
+    /// Value of a template placeholder (see [`template::PLACEHOLDERS`]);
+    /// empty when unknown
+    fn field(&self, placeholder: &str) -> String {
+        let or_empty = |value: Option<String>| value.unwrap_or_default();
+        match placeholder {
+            "name" => self.branch.clone(),
+            "score" => format!("{:.1}", self.score),
+            "fuzzy_score" => or_empty(self.fuzzy_score.map(|s| s.to_string())),
+            "frecency" => format!("{:.1}", self.frecency_score),
+            "switches" => self.switch_count.to_string(),
+            "last_used" => self
+                .last_used
+                .map(frecency::format_relative_time)
+                .unwrap_or_else(|| "never".to_string()),
+            "last_used_ts" => or_empty(self.last_used.map(|t| t.to_string())),
+            "aliases" => self.aliases.join(","),
+            "pinned" => self.pinned.to_string(),
+            "current" => self.current.to_string(),
+            "note" => or_empty(self.note.clone()),
+            "description" => or_empty(self.description.clone()),
+            "upstream" => or_empty(self.upstream.clone()),
+            "ahead" => or_empty(self.ahead.map(|n| n.to_string())),
+            "behind" => or_empty(self.behind.map(|n| n.to_string())),
+            "last_commit" => or_empty(self.last_commit.map(frecency::format_relative_time)),
+            "subject" => or_empty(self.last_commit_summary.clone()),
+            _ => String::new(),
+        }
+    }
+}
+
+/// Handle 'ggo query': print the ranked candidates for an editor's branch
+/// picker, one `branch<TAB>score` line each or as JSON. Nothing matching is
+/// an empty list, not an error.
+pub fn handle_query_command(
+    storage: &storage::Storage,
+    pattern: &str,
+    limit: usize,
+    json: bool,
+    config: &config::Config,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+    let queried = query_branches(storage, config, &repo_path, pattern, limit)?;
+    if json {
+        return print_json(&queried);
+    }
+    for branch in &queried {
+        println!("{}\t{:.1}", branch.branch, branch.score);
+    }
+    Ok(())
+}
+
+/// How 'ggo --list' matches and prints branches
+pub struct ListOptions<'a> {
+    pub ignore_case: bool,
+    pub use_fuzzy: bool,
+    pub suggest: bool,
+    /// Also match the pattern against branch notes
+    pub notes: bool,
+    pub json: bool,
+    /// One line per branch from this template (--format)
+    pub template: Option<&'a template::Template>,
+    /// Show only this many of the best-ranked branches (0 for all)
+    pub limit: usize,
+}
+
+pub fn list_matching_branches(
+    storage: &storage::Storage,
+    pattern: &str,
+    options: &ListOptions,
+    config: &config::Config,
+) -> Result<()> {
+    let ListOptions {
+        ignore_case,
+        use_fuzzy,
+        suggest,
+        notes: search_notes,
+        json,
+        template,
+        limit,
+    } = *options;
+    let repo_path = repo_key(storage, config)?;
+    let branches =
+        config
+            .behavior
+            .offered_branches(cached_branches(backend::backend(), storage, &repo_path)?);
+    let scoring = config.scoring_for(&repo_path);
+    let half_life_days = scoring.half_life_days;
+
+    // Try to load branch history, but continue without it if it fails
+    let records = match storage.branch_records(&repo_path) {
+        Ok(r) => r,
+        Err(e) => {
+            style::warn(format!(
+                "Could not load branch history: {}\n   Frecency ranking will not be available.",
+                e
+            ));
+            vec![]
+        }
+    };
+
+    // Put in order only once the best --limit are known
+    let mut ranked = ranking::score_matching_branches(
+        &branches,
+        pattern,
+        ignore_case,
+        use_fuzzy,
+        &records,
+        &scoring,
+        &config.matching.bonuses(),
+    );
+
+    let descriptions = backend::backend().branch_descriptions()?;
+    ranked = ranking::add_description_matches(
+        ranked,
+        &matcher::filter_by_text(&branches, &descriptions, pattern, ignore_case),
+        use_fuzzy,
+        &records,
+        &scoring,
+    );
+
+    let notes = load_branch_notes(storage, &repo_path);
+    if search_notes {
+        ranked = ranking::add_note_matches(
+            ranked,
+            &branches,
+            &notes,
+            pattern,
+            ignore_case,
+            &records,
+            half_life_days,
+        );
+    }
+
+    ranked = boost_session(backend::backend(), storage, &repo_path, ranked, config);
+    if suggest {
+        ranked = boost_likely_next(backend::backend(), storage, &repo_path, ranked);
+    }
+
+    // Everything below (branch details, aliases, notes, output) is per listed
+    // branch, so --limit caps it
+    let pinned = load_pinned_branches(storage, &repo_path);
+    let matched = ranked.len();
+    let ranked = ranking::top_ranked(ranked, &pinned, limit);
+    logfile::record_candidates(pattern, &ranked);
+
+    if ranked.is_empty() {
+        return Err(GgoError::NoMatchingBranches(pattern.to_string()));
+    }
+
+    let names: Vec<String> = ranked.iter().map(|(branch, _)| branch.clone()).collect();
+    let branch_infos = git::branch_infos(&names)?;
+    let current_branch = backend::backend().current_branch().ok();
+    if let Err(e) = storage.save_listing(&repo_path, current_branch.as_deref(), &names) {
+        debug!("Could not save the listing for --nth: {}", e);
+    }
+
+    if json || template.is_some() {
+        let fuzzy_scores: HashMap<String, i64> = if use_fuzzy {
+            matcher::fuzzy_filter_branches_with_bonuses(
+                &names,
+                pattern,
+                ignore_case,
+                &config.matching.bonuses(),
+            )
+            .into_iter()
+            .map(|m| (m.branch, m.score))
+            .collect()
+        } else {
+            HashMap::new()
+        };
+
+        let listed: Vec<ListedBranch> = ranked
+            .iter()
+            .map(|(branch, score)| {
+                let record = records.iter().find(|r| &r.branch_name == branch);
+                let info = branch_infos.iter().find(|info| &info.name == branch);
+                ListedBranch {
+                    branch: branch.clone(),
+                    score: *score,
+                    fuzzy_score: fuzzy_scores.get(branch).copied(),
+                    frecency_score: record
+                        .map(|r| frecency::calculate_score(r, half_life_days))
+                        .unwrap_or(0.0),
+                    aliases: storage
+                        .aliases_for_branch(&repo_path, branch)
+                        .unwrap_or_default(),
+                    last_used: record.map(|r| r.last_used),
+                    pinned: pinned.contains(branch),
+                    note: notes.get(branch).cloned(),
+                    description: descriptions.get(branch).cloned(),
+                    upstream: info.and_then(|info| info.upstream.clone()),
+                    ahead: info
+                        .and_then(|info| info.ahead_behind)
+                        .map(|(ahead, _)| ahead),
+                    behind: info
+                        .and_then(|info| info.ahead_behind)
+                        .map(|(_, behind)| behind),
+                    last_commit: info.map(|info| info.last_commit_time),
+                    last_commit_summary: info.map(|info| info.last_commit_summary.clone()),
+                    switch_count: record.map(|r| r.switch_count).unwrap_or(0),
+                    current: info.is_some_and(|info| info.is_head),
+                }
+            })
+            .collect();
+
+        if let Some(template) = template {
+            return print_lines(
+                listed
+                    .iter()
+                    .map(|branch| template.render(|placeholder| branch.field(placeholder))),
+            );
+        }
+        return print_json(&listed);
+    }
+
+    let match_type = if use_fuzzy {
+        "fuzzy matching"
+    } else {
+        "substring matching"
+    };
+    let heading = format!(
+        "Branches matching '{}' ({}+ frecency):",
+        pattern, match_type
+    );
+    println!("{}\n", style::paint(Role::Header, heading));
+
+    // Drop columns like the menus do rather than wrapping; piped output
+    // keeps them all unless COLUMNS asks for a width
+    let columns = if std::io::stdout().is_terminal() || interactive::columns_override().is_some() {
+        display_columns(config)
+    } else {
+        interactive::Columns {
+            subject: config.display.subject_width,
+            ..interactive::Columns::default()
+        }
+    };
+
+    for (i, (branch, score)) in ranked.iter().enumerate() {
+        let number = style::paint(Role::Dim, format!("{:>3}", i + 1));
+        let marker = if i == 0 {
+            style::paint(Role::Target, "→")
+        } else {
+            " ".to_string()
+        };
+        let pin = if pinned.contains(branch) {
+            format!("{} ", style::paint(Role::Pinned, "★"))
+        } else {
+            String::new()
+        };
+        let name = if current_branch.as_ref() == Some(branch) {
+            style::paint(Role::Current, format!("* {}", branch))
+        } else {
+            branch.clone()
+        };
+        if columns.plain {
+            println!("{} {} {}{}", number, marker, pin, name);
+            continue;
+        }
+        let score_display = if *score > 0.0 && columns.shows(MenuColumn::Score) {
+            format!(" ({})", style::paint(Role::Score, format!("{:.1}", score)))
+        } else {
+            String::new()
+        };
+
+        // Get aliases for this branch
+        let aliases = storage
+            .aliases_for_branch(&repo_path, branch)
+            .unwrap_or_default();
+        let alias_display = if !aliases.is_empty() {
+            format!(
+                " [alias: {}]",
+                style::paint(Role::Alias, aliases.join(", "))
+            )
+        } else {
+            String::new()
+        };
+        let note_display = notes
+            .get(branch)
+            .map(|note| format!(" [note: {}]", note))
+            .unwrap_or_default();
+        let description_display = descriptions
+            .get(branch)
+            .map(|description| {
+                let snippet = interactive::description_snippet(description);
+                format!(" ({})", style::paint(Role::Dim, snippet))
+            })
+            .unwrap_or_default();
+
+        // Upstream sync status and last commit, when known
+        let info = branch_infos.iter().find(|info| &info.name == branch);
+        let sync = info
+            .map(|info| interactive::format_ahead_behind(info.ahead_behind))
+            .filter(|sync| !sync.is_empty() && columns.shows(MenuColumn::Sync))
+            .map(|sync| format!(" {}", sync))
+            .unwrap_or_default();
+        let subject = match info {
+            Some(info) if columns.subject > 0 => {
+                let summary = interactive::truncate(&info.last_commit_summary, columns.subject);
+                format!(" — {}", style::paint(Role::Dim, summary))
+            }
+            _ => String::new(),
+        };
+
+        println!(
+            "{} {} {}{}{}{}{}{}{}{}",
+            number,
+            marker,
+            pin,
+            name,
+            score_display,
+            description_display,
+            alias_display,
+            note_display,
+            sync,
+            subject
+        );
+    }
+
+    if ranked.len() < matched {
+        println!(
+            "\n(top {} of {} matches, --limit 0 lists all; → indicates checkout target, * the current branch; 'ggo --nth N' checks out entry N)",
+            ranked.len(),
+            matched
+        );
+    } else if ranked.len() > 1 {
+        println!(
+            "\n({} matches, → indicates checkout target, * the current branch; 'ggo --nth N' checks out entry N)",
+            ranked.len()
+        );
+    }
+    // No branch to mark: say where HEAD is instead
+    if current_branch.is_none() {
+        if let Ok(Some(head)) = backend::backend().detached_head() {
+            println!(
+                "{}",
+                style::paint(Role::Dim, format!("(detached at {})", head.name))
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// The last 'ggo -l' listing of a repository, if it was shown less than
+/// [`LISTING_TTL_SECS`] ago. With `unswitched`, also only if the same branch
+/// is still checked out, i.e. nothing was switched to since.
+///
+/// [`LISTING_TTL_SECS`]: constants::picker::LISTING_TTL_SECS
+pub fn recent_listing(
+    storage: &storage::Storage,
+    repo_path: &str,
+    unswitched: bool,
+) -> Result<Option<Vec<String>>> {
+    let Some(listing) = storage.listing(repo_path)? else {
+        return Ok(None);
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    if now - listing.listed_at > constants::picker::LISTING_TTL_SECS {
+        return Ok(None);
+    }
+    if unswitched && backend::backend().current_branch().ok() != listing.head {
+        return Ok(None);
+    }
+    Ok(Some(listing.branches))
+}
+
+/// Handle 'ggo --nth N': check out entry N (from 1) of the last 'ggo -l'
+/// listing in this repository
+pub fn checkout_listed_branch(
+    storage: &storage::Storage,
+    config: &config::Config,
+    n: usize,
+    switch: &SwitchOptions,
+    print: bool,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+    let branches = recent_listing(storage, &repo_path, false)?.ok_or(GgoError::NoRecentListing)?;
+    let branch = branches
+        .get(n - 1)
+        .ok_or(GgoError::NoListingEntry(n, branches.len()))?;
+    switch_or_print(storage, config, &repo_path, branch, switch, print)
+}
@@ -0,0 +1,140 @@
+//! The `ggo` subcommands and flags behind the binary: each handler takes
+//! what was parsed from the command line, does the work through the rest of
+//! the library, and prints the result. The binary only parses arguments and
+//! dispatches here.
+
+pub mod alias;
+pub mod batch;
+pub mod checkout;
+pub mod cleanup;
+pub mod config;
+pub mod current;
+pub mod data;
+pub mod delete;
+pub mod explain;
+pub mod find;
+pub mod group;
+pub mod history;
+pub mod hooks;
+pub mod list;
+pub mod note;
+pub mod open;
+pub mod pin;
+pub mod recent;
+pub mod repo;
+pub mod setup;
+pub mod stack;
+pub mod stats;
+pub mod worktree;
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::config::{AutoSelect, Config};
+use crate::error::{GgoError, Result};
+use crate::{interactive, ranking, storage, style};
+
+/// How 'ggo repo' and `ggo --stats <pattern>` match repositories and pick
+/// among them
+pub struct RepoMatchOptions {
+    pub ignore_case: bool,
+    pub use_fuzzy: bool,
+    pub can_prompt: bool,
+    /// behavior.auto_select, or what --pick/--first set for this run
+    pub auto_select: AutoSelect,
+}
+
+/// The name to use from a non-empty ranking: the top one when it is the only
+/// match, there is no terminal for a menu, or [`ranking::auto_selects`] takes
+/// it under `auto_select`; otherwise what `pick` returns from the menu
+pub fn top_or_pick(
+    ranked: &[(String, f64)],
+    can_prompt: bool,
+    auto_select: AutoSelect,
+    threshold: f64,
+    config: &Config,
+    pick: impl FnOnce() -> Result<String>,
+) -> Result<String> {
+    if ranked.len() == 1
+        || !can_prompt
+        || ranking::auto_selects(
+            ranked,
+            auto_select,
+            threshold,
+            config.behavior.auto_select_min_score,
+        )
+    {
+        Ok(ranked[0].0.clone())
+    } else {
+        pick()
+    }
+}
+
+/// Print a value as pretty JSON on stdout. A reader that stops early
+/// (`ggo -l --json | head -1`) is not an error.
+pub(crate) fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| GgoError::Other(format!("Failed to serialize JSON: {}", e)))?;
+
+    let mut stdout = std::io::stdout().lock();
+    match writeln!(stdout, "{}", json).and_then(|()| stdout.flush()) {
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+/// Print one line per item on stdout, stopping quietly once the reader has
+/// gone away (`ggo -l --format '{name}' | head -1`)
+pub(crate) fn print_lines(lines: impl IntoIterator<Item = String>) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    let written = lines
+        .into_iter()
+        .try_for_each(|line| writeln!(stdout, "{}", line))
+        .and_then(|()| stdout.flush());
+    match written {
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+/// Menu column widths from the `[display]` config, fitted to the terminal
+pub(crate) fn display_columns(config: &Config) -> interactive::Columns {
+    interactive::Columns {
+        branch: config.display.branch_width,
+        subject: config.display.subject_width,
+        page_size: config.display.page_size,
+        ..interactive::Columns::default()
+    }
+    .fit(
+        interactive::terminal_width(),
+        &config.display.column_priority,
+    )
+}
+
+/// Pinned branches for the repository, warning (and treating none as pinned)
+/// if they can't be loaded
+pub(crate) fn load_pinned_branches(storage: &storage::Storage, repo_path: &str) -> Vec<String> {
+    storage.pinned_branches(repo_path).unwrap_or_else(|e| {
+        style::warn(format!("Could not load pinned branches: {}", e));
+        vec![]
+    })
+}
+
+/// Notes on the repository's branches, warning (and treating none as noted)
+/// if they can't be read
+pub(crate) fn load_branch_notes(
+    storage: &storage::Storage,
+    repo_path: &str,
+) -> HashMap<String, String> {
+    storage.branch_notes(repo_path).unwrap_or_else(|e| {
+        style::warn(format!("Could not load branch notes: {}", e));
+        HashMap::new()
+    })
+}
+
+/// How the library's warnings reach the terminal
+pub fn print_warning(message: &str) {
+    style::warn(message);
+}
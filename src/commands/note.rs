@@ -0,0 +1,67 @@
+//! 'ggo note': a line of text kept with a branch and shown in the menus.
+
+use std::collections::BTreeMap;
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::error::{GgoError, Result};
+use crate::switch::repo_key;
+use crate::{config, git, interactive, storage, validation};
+
+/// Handle 'ggo note': set, show or remove the note on a branch, or list the
+/// notes in the repository
+pub fn handle_note_command(
+    storage: &storage::Storage,
+    branch: Option<&str>,
+    text: Option<&str>,
+    remove: bool,
+    config: &config::Config,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+
+    let Some(branch) = branch else {
+        let notes: BTreeMap<String, String> =
+            storage.branch_notes(&repo_path)?.into_iter().collect();
+        if notes.is_empty() {
+            println!(
+                "No branch notes in this repository. Add one with 'ggo note <branch> \"text\"'."
+            );
+        } else {
+            println!("Branch notes:\n");
+            let width = notes.keys().map(|b| b.width()).max().unwrap_or(0);
+            for (branch, note) in notes {
+                println!("  {}  {}", interactive::pad(&branch, width), note);
+            }
+        }
+        return Ok(());
+    };
+
+    validation::validate_branch_name(branch)?;
+
+    if remove {
+        if storage.remove_branch_note(&repo_path, branch)? {
+            println!("Removed the note on '{}'", branch);
+        } else {
+            println!("'{}' has no note", branch);
+        }
+        return Ok(());
+    }
+
+    let Some(text) = text else {
+        match storage.branch_notes(&repo_path)?.get(branch) {
+            Some(note) => println!("{}", note),
+            None => println!("'{}' has no note", branch),
+        }
+        return Ok(());
+    };
+
+    let text = text.trim();
+    validation::validate_note(text)?;
+    if !git::get_branches()?.contains(&branch.to_string()) {
+        return Err(GgoError::BranchNotFound(branch.to_string()));
+    }
+
+    storage.set_branch_note(&repo_path, branch, text)?;
+    println!("Noted on '{}': {}", branch, text);
+    Ok(())
+}
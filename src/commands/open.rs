@@ -0,0 +1,158 @@
+//! The forge side: 'ggo open' in the browser and 'ggo pr N' checkouts.
+
+use tracing::debug;
+
+use crate::error::{GgoError, Result};
+use crate::switch::{repo_key, SwitchOptions};
+use crate::{config, forge, git, ranking, storage, validation};
+
+use super::checkout::switch_or_print;
+
+/// Handle 'ggo open': show the branch (or its pull request) on the forge `origin` points to
+pub fn handle_open_command(
+    storage: &storage::Storage,
+    pattern: Option<&str>,
+    pr: bool,
+    print_url: bool,
+    config: &config::Config,
+) -> Result<()> {
+    let branch = match pattern {
+        Some(pattern) => {
+            let repo_path = repo_key(storage, config)?;
+            resolve_branch(storage, &repo_path, pattern, config)?
+        }
+        None => git::get_current_branch()?,
+    };
+
+    let remote = git::origin_url()?
+        .ok_or_else(|| GgoError::Other("This repository has no 'origin' remote".to_string()))?;
+    let web = forge::WebRepo::from_remote_url(&remote)
+        .ok_or_else(|| GgoError::UnsupportedForge(remote.clone()))?;
+
+    let url = if pr {
+        web.pull_request_url(&branch)
+    } else {
+        web.branch_url(&branch)
+    };
+
+    if print_url {
+        println!("{}", url);
+        return Ok(());
+    }
+
+    println!("Opening {}", url);
+    open_in_browser(&url)
+}
+
+/// Handle 'ggo pr N' and 'ggo #N': fetch the pull request's head from `origin`
+/// into `pr/N` (updating it if it already exists) and check it out
+pub fn checkout_pull_request(
+    storage: &storage::Storage,
+    number: u64,
+    print: bool,
+    switch: &SwitchOptions,
+    config: &config::Config,
+) -> Result<()> {
+    let branch = forge::pull_request_branch(number);
+    let repo_path = repo_key(storage, config)?;
+
+    // Fetching into the checked-out branch would leave the worktree behind
+    if git::get_current_branch().ok().as_deref() == Some(branch.as_str()) {
+        if print {
+            println!("{}", branch);
+        } else {
+            println!("Already on '{}'", branch);
+        }
+        return Ok(());
+    }
+
+    let remote = git::origin_url()?
+        .ok_or_else(|| GgoError::Other("This repository has no 'origin' remote".to_string()))?;
+    let forge = forge::WebRepo::from_remote_url(&remote).map(|web| web.forge);
+
+    let mut head_ref = None;
+    for candidate in forge::pull_request_refs(forge, number) {
+        if git::remote_ref_exists("origin", &candidate)? {
+            head_ref = Some(candidate);
+            break;
+        }
+    }
+    let head_ref = head_ref.ok_or(GgoError::PullRequestNotFound(number))?;
+
+    debug!("Fetching {} into {}", head_ref, branch);
+    git::fetch_into_branch("origin", &head_ref, &branch)?;
+
+    // Its '.ggo.toml' is the pull request author's, not this repository's
+    let switch = SwitchOptions {
+        repo_hooks: false,
+        ..*switch
+    };
+    switch_or_print(storage, config, &repo_path, &branch, &switch, print)
+}
+
+/// Resolve a branch name, alias or pattern to a single branch without
+/// prompting: an exact name wins, then an alias, then the best match
+fn resolve_branch(
+    storage: &storage::Storage,
+    repo_path: &str,
+    pattern: &str,
+    config: &config::Config,
+) -> Result<String> {
+    validation::validate_pattern(pattern)?;
+    let branches = git::get_branches()?;
+
+    if branches.iter().any(|b| b == pattern) {
+        return Ok(pattern.to_string());
+    }
+
+    if let Ok(Some(branch)) = storage.alias(repo_path, pattern) {
+        if branches.contains(&branch) {
+            return Ok(branch);
+        }
+    }
+
+    let branches = config.behavior.offered_branches(branches);
+    let records = storage.branch_records(repo_path).unwrap_or_default();
+    let scored = ranking::score_matching_branches(
+        &branches,
+        pattern,
+        config.behavior.ignore_case_for(pattern),
+        config.behavior.default_fuzzy,
+        &records,
+        &config.scoring_for(repo_path),
+        &config.matching.bonuses(),
+    );
+    ranking::top_ranked(scored, &[], 1)
+        .into_iter()
+        .next()
+        .map(|(branch, _)| branch)
+        .ok_or_else(|| GgoError::NoMatchingBranches(pattern.to_string()))
+}
+
+/// Open a URL with $BROWSER, or the platform's default handler
+fn open_in_browser(url: &str) -> Result<()> {
+    let mut command = match std::env::var("BROWSER") {
+        Ok(browser) if !browser.trim().is_empty() => std::process::Command::new(browser.trim()),
+        _ if cfg!(target_os = "macos") => std::process::Command::new("open"),
+        _ if cfg!(windows) => {
+            let mut command = std::process::Command::new("cmd");
+            command.args(["/C", "start", ""]);
+            command
+        }
+        _ => std::process::Command::new("xdg-open"),
+    };
+
+    let status = command
+        .arg(url)
+        .status()
+        .map_err(|e| GgoError::Other(format!("Failed to open a browser: {}", e)))?;
+
+    if !status.success() {
+        return Err(GgoError::Other(format!(
+            "Browser command exited with {}\n\nUse 'ggo open --url' to print the URL instead.",
+            status
+        )));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+//! 'ggo pin' and 'ggo unpin': branches kept at the top of the ranking.
+
+use crate::error::{GgoError, Result};
+use crate::switch::repo_key;
+use crate::{config, git, storage, validation};
+
+/// Handle 'ggo pin': pin a branch, or list pinned branches
+pub fn handle_pin_command(
+    storage: &storage::Storage,
+    branch: Option<&str>,
+    config: &config::Config,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+
+    let Some(branch) = branch else {
+        let pinned = storage.pinned_branches(&repo_path)?;
+        if pinned.is_empty() {
+            println!("No pinned branches in this repository. Pin one with 'ggo pin <branch>'.");
+        } else {
+            println!("Pinned branches:\n");
+            for branch in pinned {
+                println!("  ★ {}", branch);
+            }
+        }
+        return Ok(());
+    };
+
+    validation::validate_branch_name(branch)?;
+    if !git::get_branches()?.contains(&branch.to_string()) {
+        return Err(GgoError::BranchNotFound(branch.to_string()));
+    }
+
+    storage.pin_branch(&repo_path, branch)?;
+    println!("Pinned '{}'", branch);
+    Ok(())
+}
+
+/// Handle 'ggo unpin'
+pub fn handle_unpin_command(
+    storage: &storage::Storage,
+    branch: &str,
+    config: &config::Config,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+
+    if storage.unpin_branch(&repo_path, branch)? {
+        println!("Unpinned '{}'", branch);
+    } else {
+        println!("'{}' is not pinned", branch);
+    }
+    Ok(())
+}
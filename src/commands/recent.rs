@@ -0,0 +1,200 @@
+//! 'ggo recent' and 'ggo switch-last-modified': branches by when they were used
+//! or committed to.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::error::{GgoError, Result};
+use crate::style::{self, Role};
+use crate::switch::{repo_key, SwitchOptions};
+use crate::{config, frecency, git, interactive, ranking, storage};
+
+use super::checkout::{pick_branch, switch_to_branch};
+use super::print_json;
+
+/// Handle the recent subcommand: show (or pick from) the most recently used branches
+pub fn handle_recent_command(
+    storage: &storage::Storage,
+    limit: usize,
+    interactive: bool,
+    switch: &SwitchOptions,
+    config: &config::Config,
+) -> Result<()> {
+    if interactive && !switch.can_prompt {
+        return Err(GgoError::NotATerminal(
+            "Dropping -i to list the branches instead",
+        ));
+    }
+    let repo_path = repo_key(storage, config)?;
+    let branches = config.behavior.offered_branches(git::get_branches()?);
+
+    // Records are ordered by last_used; skip branches that no longer exist
+    let recent: Vec<storage::BranchRecord> = storage
+        .branch_records(&repo_path)?
+        .into_iter()
+        .filter(|r| branches.contains(&r.branch_name))
+        .take(limit)
+        .collect();
+
+    if recent.is_empty() {
+        println!(
+            "No branch history for this repository yet. Switch branches with ggo to build it!"
+        );
+        return Ok(());
+    }
+
+    if interactive {
+        let branch_list: Vec<String> = recent.iter().map(|r| r.branch_name.clone()).collect();
+        let branch = pick_branch(
+            storage,
+            &repo_path,
+            &branch_list,
+            &recent,
+            config.behavior.picker,
+            config,
+            config.scoring_for(&repo_path).half_life_days,
+        )?;
+        return switch_to_branch(storage, config, &repo_path, &branch, switch);
+    }
+
+    println!("Recently used branches:\n");
+    for (i, record) in recent.iter().enumerate() {
+        println!(
+            "  {:2}. {} {:>10}  ({} switches)",
+            i + 1,
+            interactive::pad(&record.branch_name, 40),
+            frecency::format_relative_time(record.last_used),
+            record.switch_count
+        );
+    }
+
+    Ok(())
+}
+
+/// One 'ggo switch-last-modified --json' entry
+#[derive(Serialize)]
+struct ActiveBranch {
+    branch: String,
+    /// Blend of commit recency and frecency, 0 to 1
+    score: f64,
+    /// Your newest commit on the branch
+    last_commit: i64,
+    summary: String,
+    /// Remote-tracking branch, when there is no local one yet
+    remote: Option<String>,
+}
+
+/// Handle 'ggo switch-last-modified': rank the branches by how recently you
+/// committed on them, blended with frecency, then switch to the top one,
+/// pick one, or list them. A branch only on a remote gets a local branch
+/// tracking it first.
+pub fn handle_switch_last_modified_command(
+    storage: &storage::Storage,
+    list: bool,
+    interactive: bool,
+    json: bool,
+    switch: &SwitchOptions,
+    config: &config::Config,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+    let half_life_days = config.scoring_for(&repo_path).half_life_days;
+
+    let (email, activity) = git::my_branch_activity()?;
+    let offered: HashSet<String> = config
+        .behavior
+        .offered_branches(activity.iter().map(|a| a.branch.clone()).collect())
+        .into_iter()
+        .collect();
+    let activity: Vec<git::BranchActivity> = activity
+        .into_iter()
+        .filter(|a| offered.contains(&a.branch))
+        .collect();
+    if activity.is_empty() {
+        return Err(GgoError::NoOwnCommits(email));
+    }
+
+    let records = storage.branch_records(&repo_path).unwrap_or_default();
+    let times: Vec<(String, i64)> = activity
+        .iter()
+        .map(|a| (a.branch.clone(), a.last_commit))
+        .collect();
+    let ranked = ranking::blend_activity(
+        &times,
+        &records,
+        half_life_days,
+        config.frecency.activity_weight,
+    );
+    let activity_of = |branch: &str| {
+        activity
+            .iter()
+            .find(|a| a.branch == branch)
+            .expect("ranked branches come from the activity")
+    };
+
+    if json {
+        let active: Vec<ActiveBranch> = ranked
+            .iter()
+            .map(|(branch, score)| {
+                let a = activity_of(branch);
+                ActiveBranch {
+                    branch: branch.clone(),
+                    score: *score,
+                    last_commit: a.last_commit,
+                    summary: a.summary.clone(),
+                    remote: a.remote.clone(),
+                }
+            })
+            .collect();
+        return print_json(&active);
+    }
+
+    if list {
+        println!("Branches with your recent commits:\n");
+        for (i, (branch, score)) in ranked.iter().enumerate() {
+            let a = activity_of(branch);
+            let remote = a
+                .remote
+                .as_ref()
+                .map(|remote| format!(" [{}]", style::paint(Role::Dim, remote)))
+                .unwrap_or_default();
+            let subject = if config.display.subject_width > 0 {
+                let summary = interactive::truncate(&a.summary, config.display.subject_width);
+                format!(" — {}", style::paint(Role::Dim, summary))
+            } else {
+                String::new()
+            };
+            println!(
+                "  {:2}. {} {:>10}  ({}){}{}",
+                i + 1,
+                interactive::pad(branch, 40),
+                frecency::format_relative_time(a.last_commit),
+                style::paint(Role::Score, format!("{:.2}", score)),
+                remote,
+                subject
+            );
+        }
+        return Ok(());
+    }
+
+    let branch = if interactive {
+        let branches: Vec<String> = ranked.into_iter().map(|(branch, _)| branch).collect();
+        pick_branch(
+            storage,
+            &repo_path,
+            &branches,
+            &records,
+            config.behavior.picker,
+            config,
+            half_life_days,
+        )?
+    } else {
+        ranked[0].0.clone()
+    };
+
+    if let Some(remote) = &activity_of(&branch).remote {
+        git::create_tracking_branch(&branch, remote)?;
+        println!("Created '{}' tracking '{}'", branch, remote);
+    }
+    switch_to_branch(storage, config, &repo_path, &branch, switch)
+}
@@ -0,0 +1,82 @@
+//! 'ggo repo': jumping to another repository by frecency.
+
+use crate::error::{GgoError, Result};
+use crate::switch::request_directory_change;
+use crate::{config, constants, git, interactive, ranking, storage, style};
+
+use super::{top_or_pick, RepoMatchOptions};
+
+/// Handle 'ggo repo': rank the repositories in the history and under
+/// search.roots, then change to the best match (or print its path without
+/// the shell integration). Repositories without a directory, such as remote
+/// keys from `history.repo_identity = "remote"`, are skipped.
+pub fn jump_to_repo(
+    storage: &storage::Storage,
+    pattern: &str,
+    options: &RepoMatchOptions,
+    config: &config::Config,
+) -> Result<()> {
+    let records = storage.all_records().unwrap_or_else(|e| {
+        style::warn(format!("Could not load branch history: {}", e));
+        Vec::new()
+    });
+
+    let mut repos: Vec<String> = records
+        .iter()
+        .map(|record| record.repo_path.clone())
+        .filter(|repo| std::path::Path::new(repo).is_dir())
+        .collect();
+    for root in config.search_roots() {
+        for path in git::find_repositories(&root, constants::repos::SEARCH_DEPTH) {
+            let path = std::fs::canonicalize(&path).unwrap_or(path);
+            repos.push(format!("{}/", path.to_string_lossy().trim_end_matches('/')));
+        }
+    }
+    repos.sort();
+    repos.dedup();
+
+    // Ranked across repositories, so no single repository's overrides apply
+    let scoring = config.scoring();
+    let ranked = ranking::rank_repos(
+        &repos,
+        pattern,
+        options.ignore_case,
+        options.use_fuzzy,
+        &records,
+        &scoring,
+        &config.matching.bonuses(),
+    );
+    if ranked.is_empty() {
+        return Err(GgoError::NoMatchingRepos(pattern.to_string()));
+    }
+
+    let repo = top_or_pick(
+        &ranked,
+        options.can_prompt,
+        options.auto_select,
+        scoring.auto_select_threshold,
+        config,
+        || {
+            let choices: Vec<String> = ranked
+                .iter()
+                .map(|(repo, _)| repo.trim_end_matches('/').to_string())
+                .collect();
+            Ok(ranked[interactive::select_repository(&choices)?].0.clone())
+        },
+    )?;
+
+    let path = std::path::PathBuf::from(repo.trim_end_matches('/'));
+    let here = std::env::current_dir().and_then(std::fs::canonicalize).ok();
+    if here.as_deref() == Some(path.as_path()) {
+        println!("Already in '{}'", path.display());
+        return Ok(());
+    }
+
+    if request_directory_change(&path)? {
+        println!("Jumped to '{}'", path.display());
+    } else {
+        // Without the shell integration, print the path for cd "$(ggo repo ...)"
+        println!("{}", path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,239 @@
+//! 'ggo setup' (shell integration and completions) and 'ggo self-update'.
+
+use clap_complete::{generate, Shell};
+
+use crate::error::{GgoError, Result};
+use crate::switch::repo_key;
+use crate::{config, frecency, git, interactive, shell, storage};
+
+/// Handle 'ggo self-update': install the latest release over this binary,
+/// or with --check only say whether there is one
+#[cfg(feature = "self-update")]
+pub fn handle_self_update(check: bool) -> Result<()> {
+    use crate::update;
+
+    let current = update::current_version();
+    let release = update::latest_release()?;
+    if !update::is_newer(release.version(), current) {
+        println!("ggo {} is up to date", current);
+        return Ok(());
+    }
+    if check {
+        println!(
+            "ggo {} is available (you have {})\nRun 'ggo self-update' to install it.",
+            release.version(),
+            current
+        );
+        return Ok(());
+    }
+
+    let asset = update::platform_asset().ok_or_else(|| {
+        GgoError::UpdateFailed(format!(
+            "no release binary is built for {} {}",
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        ))
+    })?;
+    println!("Downloading {} from {}...", asset, release.tag);
+    let bytes = update::download_verified(&release, asset)?;
+    let path = update::replace_executable(&bytes)?;
+    println!(
+        "Updated ggo {} → {} ({})",
+        current,
+        release.version(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Handle 'ggo setup': install the shell integration and completions, write
+/// a config file and import the current repository's reflog, asking before
+/// each step (all of them with --yes). Completions are generated from `cli`,
+/// the command line being set up.
+pub fn handle_setup_command(
+    storage: &storage::Storage,
+    config: &config::Config,
+    shell_name: Option<&str>,
+    yes: bool,
+    can_prompt: bool,
+    mut cli: clap::Command,
+) -> Result<()> {
+    if !yes && !can_prompt {
+        return Err(GgoError::NotATerminal(
+            "Passing --yes to run every step without asking",
+        ));
+    }
+    let ask =
+        |question: &str| -> Result<bool> { Ok(yes || interactive::confirm_setup_step(question)?) };
+
+    let shell = match shell_name {
+        Some(name) => shell::Shell::from_name(name),
+        None => shell::Shell::detect(),
+    };
+    let mut reload = None;
+    match shell {
+        Some(shell) => {
+            if setup_shell_integration(shell, &ask)? {
+                reload = shell.rc_file();
+            }
+            setup_completions(shell, &mut cli, &ask)?;
+        }
+        None => println!(
+            "Couldn't tell your shell from $SHELL; run 'ggo setup --shell <bash|zsh|fish>' for the shell steps"
+        ),
+    }
+    setup_config_file(&ask)?;
+    setup_reflog_import(storage, config, &ask)?;
+
+    println!("\nSetup done.");
+    if let Some(rc) = reload {
+        println!(
+            "Open a new terminal (or 'source {}') to use the shell integration.",
+            rc.display()
+        );
+    }
+    Ok(())
+}
+
+/// Add the init line to the shell's rc file, unless it is there already.
+/// Returns whether it was added.
+fn setup_shell_integration(
+    shell: shell::Shell,
+    ask: &dyn Fn(&str) -> Result<bool>,
+) -> Result<bool> {
+    let Some(rc) = shell.rc_file() else {
+        println!(
+            "Couldn't find your home directory; add '{}' to your {} startup file",
+            shell.init_line(),
+            shell.name()
+        );
+        return Ok(false);
+    };
+
+    let existing = std::fs::read_to_string(&rc).unwrap_or_default();
+    if existing.contains(&format!("ggo init {}", shell.name())) {
+        println!("✓ Shell integration already in {}", rc.display());
+        return Ok(false);
+    }
+    if !ask(&format!(
+        "Add the {} integration to {}?",
+        shell.name(),
+        rc.display()
+    ))? {
+        return Ok(false);
+    }
+
+    let mut addition = String::new();
+    if !existing.is_empty() && !existing.ends_with('\n') {
+        addition.push('\n');
+    }
+    addition.push_str(&format!(
+        "\n# ggo: cd for 'ggo wt', Ctrl-G picker, prompt hook\n{}\n",
+        shell.init_line()
+    ));
+    if let Some(dir) = rc.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&rc)?;
+    std::io::Write::write_all(&mut file, addition.as_bytes())?;
+
+    println!("✓ Added '{}' to {}", shell.init_line(), rc.display());
+    Ok(true)
+}
+
+/// Write the shell's completion script where it looks for one
+fn setup_completions(
+    shell: shell::Shell,
+    cli: &mut clap::Command,
+    ask: &dyn Fn(&str) -> Result<bool>,
+) -> Result<()> {
+    let Some(path) = shell.completion_file() else {
+        return Ok(());
+    };
+    if !ask(&format!(
+        "Install {} completions to {}?",
+        shell.name(),
+        path.display()
+    ))? {
+        return Ok(());
+    }
+
+    let generator = match shell {
+        shell::Shell::Bash => Shell::Bash,
+        shell::Shell::Zsh => Shell::Zsh,
+        shell::Shell::Fish => Shell::Fish,
+    };
+    let mut script = Vec::new();
+    generate(generator, cli, "ggo", &mut script);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, script)?;
+
+    println!("✓ Installed completions to {}", path.display());
+    if shell == shell::Shell::Zsh {
+        println!("  zsh loads them with 'fpath=(~/.zfunc $fpath)' before compinit in ~/.zshrc");
+    }
+    Ok(())
+}
+
+/// Write a config file listing every setting at its default, unless there
+/// is one already
+fn setup_config_file(ask: &dyn Fn(&str) -> Result<bool>) -> Result<()> {
+    let path = config::Config::config_path()?;
+    if path.exists() {
+        println!("✓ Keeping your config file {}", path.display());
+        return Ok(());
+    }
+    if !ask(&format!(
+        "Write a config file with the defaults to {}?",
+        path.display()
+    ))? {
+        return Ok(());
+    }
+
+    config::Config::create_config_dir(&path)?;
+    std::fs::write(&path, config::Config::commented_defaults()?)?;
+    println!("✓ Wrote {}", path.display());
+    Ok(())
+}
+
+/// Seed frecency with the branch switches in the current repository's reflog
+fn setup_reflog_import(
+    storage: &storage::Storage,
+    config: &config::Config,
+    ask: &dyn Fn(&str) -> Result<bool>,
+) -> Result<()> {
+    // Outside a repository there is nothing to import
+    let Ok(checkouts) = git::reflog_checkouts() else {
+        return Ok(());
+    };
+    let Some(oldest) = checkouts.first() else {
+        return Ok(());
+    };
+    let question = format!(
+        "Import {} branch switches from this repository's reflog (since {})?",
+        checkouts.len(),
+        frecency::format_relative_time(oldest.time)
+    );
+    if !ask(&question)? {
+        return Ok(());
+    }
+
+    let repo_path = repo_key(storage, config)?;
+    let events: Vec<storage::CheckoutEvent> = checkouts
+        .into_iter()
+        .map(|checkout| storage::CheckoutEvent {
+            repo_path: repo_path.clone(),
+            from_branch: checkout.from,
+            to_branch: checkout.to,
+            switched_at: checkout.time,
+        })
+        .collect();
+    let imported = storage.import_checkout_events(&events)?;
+    println!("✓ Imported {} branch switches", imported);
+    Ok(())
+}
@@ -0,0 +1,191 @@
+//! 'ggo stack', 'ggo up' and 'ggo down': branches built on one another.
+
+use std::collections::{BTreeSet, HashSet};
+
+use crate::error::{GgoError, Result};
+use crate::switch::{repo_key, SwitchOptions};
+use crate::{config, git, interactive, stack, storage};
+
+use super::checkout::switch_to_branch;
+
+/// The repository's stacks, first brought up to date with git: branches
+/// renamed with 'git branch -m' keep their place, and deleted ones are taken
+/// out, the branches stacked on them moving down
+fn load_stacks(storage: &storage::Storage, repo_path: &str) -> Result<stack::Stacks> {
+    let names = |links: &[storage::StackLink]| -> BTreeSet<String> {
+        links
+            .iter()
+            .flat_map(|link| [link.branch.clone(), link.parent.clone()])
+            .collect()
+    };
+
+    let links = storage.stack_links(repo_path)?;
+    let branches: HashSet<String> = git::get_branches()?.into_iter().collect();
+    if names(&links).iter().all(|name| branches.contains(name)) {
+        return Ok(stack::Stacks::new(&links));
+    }
+
+    for (old, new) in git::branch_renames()? {
+        if !branches.contains(&old) {
+            storage.rename_stack_branch(repo_path, &old, &new)?;
+        }
+    }
+    for name in names(&storage.stack_links(repo_path)?) {
+        if !branches.contains(&name) {
+            storage.unstack_branch(repo_path, &name)?;
+        }
+    }
+
+    Ok(stack::Stacks::new(&storage.stack_links(repo_path)?))
+}
+
+/// `branch`, or the current branch when not given
+fn branch_or_current(branch: Option<&str>) -> Result<String> {
+    match branch {
+        Some(branch) => Ok(branch.to_string()),
+        None => git::get_current_branch(),
+    }
+}
+
+/// Handle 'ggo stack on': stack a branch (default: the current one) on
+/// `parent`
+pub fn stack_on(
+    storage: &storage::Storage,
+    parent: &str,
+    branch: Option<&str>,
+    config: &config::Config,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+    let stacks = load_stacks(storage, &repo_path)?;
+    let branch = branch_or_current(branch)?;
+    let branches = git::get_branches()?;
+    for name in [branch.as_str(), parent] {
+        if !branches.iter().any(|b| b == name) {
+            return Err(GgoError::BranchNotFound(name.to_string()));
+        }
+    }
+    if stacks.would_cycle(&branch, parent) {
+        return Err(GgoError::StackCycle(branch, parent.to_string()));
+    }
+
+    storage.set_stack_parent(&repo_path, &branch, parent)?;
+    println!("Stacked '{}' on '{}'", branch, parent);
+    Ok(())
+}
+
+/// Handle 'ggo stack remove': take a branch (default: the current one) out
+/// of its stack
+pub fn unstack(
+    storage: &storage::Storage,
+    branch: Option<&str>,
+    config: &config::Config,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+    load_stacks(storage, &repo_path)?;
+    let branch = branch_or_current(branch)?;
+    if storage.unstack_branch(&repo_path, &branch)? {
+        println!("Took '{}' out of its stack", branch);
+    } else {
+        println!("'{}' is not in a stack", branch);
+    }
+    Ok(())
+}
+
+/// Handle 'ggo stack': show the current branch's stack (every stack with
+/// `all`)
+pub fn show_stacks(storage: &storage::Storage, all: bool, config: &config::Config) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+    let stacks = load_stacks(storage, &repo_path)?;
+    let current = git::get_current_branch().ok();
+
+    let roots = if all {
+        stacks.roots()
+    } else {
+        match current.as_deref().filter(|branch| stacks.contains(branch)) {
+            Some(branch) => vec![stacks.root(branch)],
+            None if current.is_none() => {
+                let at = git::detached_head()?
+                    .map(|head| format!(" at {}", head.name))
+                    .unwrap_or_default();
+                println!("Not on a branch (detached{}), so not in a stack.", at);
+                return Ok(());
+            }
+            None => {
+                println!(
+                    "The current branch is not in a stack. Stack it with 'ggo stack on <parent>'."
+                );
+                return Ok(());
+            }
+        }
+    };
+
+    if roots.is_empty() {
+        println!("No stacks in this repository. Start one with 'ggo stack on <parent>'.");
+    }
+    for (i, root) in roots.into_iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        for line in stacks.render(root, current.as_deref()) {
+            println!("{}", line);
+        }
+    }
+    Ok(())
+}
+
+/// Handle 'ggo up' and 'ggo down': check out the branch `steps` levels up
+/// the stack (stacked on the current one) or down (what it is stacked on),
+/// stopping early at either end
+pub fn walk_stack(
+    storage: &storage::Storage,
+    config: &config::Config,
+    up: bool,
+    steps: usize,
+    switch: &SwitchOptions,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+    let stacks = load_stacks(storage, &repo_path)?;
+    let current = git::get_current_branch()?;
+
+    let mut target = current.clone();
+    let mut moved = 0;
+    while moved < steps {
+        let next = if up {
+            match stacks.children(&target) {
+                [] => None,
+                [only] => Some(only.clone()),
+                several if switch.can_prompt => {
+                    Some(several[interactive::select_stacked_branch(&target, several)?].clone())
+                }
+                several => {
+                    return Err(GgoError::StackFork(target, several.join(", ")));
+                }
+            }
+        } else {
+            stacks.parent(&target).map(String::from)
+        };
+        let Some(next) = next else {
+            break;
+        };
+        target = next;
+        moved += 1;
+    }
+
+    if moved == 0 {
+        return Err(if up {
+            GgoError::StackTop(current)
+        } else {
+            GgoError::NotStacked(current)
+        });
+    }
+    if moved < steps && !switch.quiet {
+        eprintln!(
+            "Reached the {} of the stack after {} of {} levels",
+            if up { "top" } else { "bottom" },
+            moved,
+            steps
+        );
+    }
+
+    switch_to_branch(storage, config, &repo_path, &target, switch)
+}
@@ -0,0 +1,497 @@
+//! 'ggo --stats': usage statistics, the heatmap and the slow-run report.
+
+use serde::Serialize;
+use tabled::{
+    settings::{object::Rows, Alignment, Modify, Style},
+    Table, Tabled,
+};
+
+use crate::error::{GgoError, Result};
+use crate::export::{self, ExportFormat, ScoredRecord};
+use crate::style::{self, Role};
+use crate::{config, frecency, interactive, logfile, ranking, storage};
+
+use super::{print_json, top_or_pick, RepoMatchOptions};
+
+/// JSON form of `ggo --stats`
+#[derive(Serialize)]
+struct StatsJson {
+    total_switches: i64,
+    unique_branches: i64,
+    unique_repos: i64,
+    db_path: String,
+    data_dir: String,
+    /// What chose the data directory: flag, env, config or default
+    data_dir_source: &'static str,
+    top_branches: Vec<TopBranchJson>,
+    repositories: Vec<RepoJson>,
+    /// Only with --heatmap
+    #[serde(skip_serializing_if = "Option::is_none")]
+    heatmap: Option<Vec<WeekdayJson>>,
+}
+
+/// Switches on one weekday, per hour of the day (local time)
+#[derive(Serialize)]
+struct WeekdayJson {
+    weekday: &'static str,
+    hours: Vec<i64>,
+    total: i64,
+}
+
+#[derive(Serialize)]
+struct TopBranchJson {
+    branch: String,
+    frecency_score: f64,
+    switch_count: i64,
+    last_used: i64,
+}
+
+#[derive(Serialize)]
+struct RepoJson {
+    repo_path: String,
+    branches: usize,
+    switches: i64,
+    last_used: i64,
+}
+
+/// The first `limit` records matching `filter` in `sort` order, with their
+/// frecency (using each repository's own half-life)
+fn scored_records(
+    storage: &storage::Storage,
+    filter: &storage::StatsFilter,
+    sort: storage::StatsSort,
+    limit: usize,
+    config: &config::Config,
+) -> Result<Vec<ScoredRecord>> {
+    let mut scored: Vec<ScoredRecord> = storage
+        .top_records(filter, sort, limit)?
+        .into_iter()
+        .map(|r| ScoredRecord {
+            frecency_score: frecency::calculate_score(
+                &r,
+                config.scoring_for(&r.repo_path).half_life_days,
+            ),
+            repo_path: r.repo_path,
+            branch_name: r.branch_name,
+            switch_count: r.switch_count,
+            last_used: r.last_used,
+        })
+        .collect();
+
+    // Other orders come back from the query already sorted
+    if sort == storage::StatsSort::Score {
+        scored.sort_by(|a, b| {
+            b.frecency_score
+                .partial_cmp(&a.frecency_score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+
+    scored.truncate(limit);
+    Ok(scored)
+}
+
+/// Weekday names in display order (Monday first), with their index in
+/// [`storage::Storage::switch_heatmap`] (Sunday = 0)
+const WEEKDAYS: [(&str, usize); 7] = [
+    ("Mon", 1),
+    ("Tue", 2),
+    ("Wed", 3),
+    ("Thu", 4),
+    ("Fri", 5),
+    ("Sat", 6),
+    ("Sun", 0),
+];
+
+/// Shading for a heatmap cell holding `count` out of a busiest cell of `max`
+fn heat_shade(count: i64, max: i64) -> char {
+    const SHADES: [char; 5] = ['·', '░', '▒', '▓', '█'];
+    if count <= 0 || max <= 0 {
+        return SHADES[0];
+    }
+    // Any activity gets at least the lightest shade
+    let level = ((count as f64 / max as f64) * 4.0).ceil() as usize;
+    SHADES[level.clamp(1, 4)]
+}
+
+fn print_heatmap(heatmap: &[[i64; 24]; 7]) {
+    let max = heatmap.iter().flatten().copied().max().unwrap_or(0);
+
+    println!("\n🗓️  Switches by Weekday and Hour:\n");
+    if max == 0 {
+        println!("  No checkout events recorded yet.");
+        return;
+    }
+
+    let hours: String = (0..24)
+        .map(|h| {
+            if h % 3 == 0 {
+                format!("{:<3}", h)
+            } else {
+                String::new()
+            }
+        })
+        .collect();
+    println!("       {}  Total", hours);
+
+    for (name, index) in WEEKDAYS {
+        let row = &heatmap[index];
+        let cells: String = row.iter().map(|&c| heat_shade(c, max)).collect();
+        println!("  {}  {}  {:>5}", name, cells, row.iter().sum::<i64>());
+    }
+
+    let (busiest_day, busiest_hour) = WEEKDAYS
+        .iter()
+        .flat_map(|&(name, index)| (0..24).map(move |h| (name, index, h)))
+        .max_by_key(|&(_, index, h)| heatmap[index][h])
+        .map(|(name, _, h)| (name, h))
+        .unwrap_or(("Mon", 0));
+    println!(
+        "\n  Busiest: {} {:02}:00-{:02}:00 ({} switches)",
+        busiest_day,
+        busiest_hour,
+        busiest_hour + 1,
+        max
+    );
+}
+
+/// What 'ggo --stats' should show
+pub struct StatsOptions {
+    pub filter: storage::StatsFilter,
+    pub sort: storage::StatsSort,
+    /// Number of top branches
+    pub top: usize,
+    pub heatmap: bool,
+    pub json: bool,
+    /// Dump every matching record with its score instead of the summary
+    pub format: Option<ExportFormat>,
+}
+
+pub fn show_stats(
+    storage: &storage::Storage,
+    options: &StatsOptions,
+    config: &config::Config,
+) -> Result<()> {
+    let filter = &options.filter;
+    let sort = options.sort;
+    let stats = storage.stats(filter)?;
+    let (data_dir, data_dir_source) = storage::data_dir_location()?;
+
+    // The full table goes straight out for spreadsheets and dashboards
+    if let Some(format) = options.format {
+        let rows = scored_records(storage, filter, sort, usize::MAX, config)?;
+        print!("{}", export::render_scores(&rows, format)?);
+        return Ok(());
+    }
+
+    let top_branches = scored_records(storage, filter, sort, options.top, config)?;
+    let repositories = storage.repo_summaries(filter)?;
+    let heatmap = if options.heatmap {
+        Some(storage.switch_heatmap(filter)?)
+    } else {
+        None
+    };
+
+    if options.json {
+        let output = StatsJson {
+            total_switches: stats.total_switches,
+            unique_branches: stats.unique_branches,
+            unique_repos: stats.unique_repos,
+            db_path: stats.db_path.display().to_string(),
+            data_dir: data_dir.display().to_string(),
+            data_dir_source: data_dir_source.as_str(),
+            top_branches: top_branches
+                .into_iter()
+                .map(|b| TopBranchJson {
+                    branch: b.branch_name,
+                    frecency_score: b.frecency_score,
+                    switch_count: b.switch_count,
+                    last_used: b.last_used,
+                })
+                .collect(),
+            repositories: repositories
+                .into_iter()
+                .map(|repo| RepoJson {
+                    repo_path: repo.repo_path,
+                    branches: repo.branches as usize,
+                    switches: repo.switches,
+                    last_used: repo.last_used,
+                })
+                .collect(),
+            heatmap: heatmap.map(|heatmap| {
+                WEEKDAYS
+                    .iter()
+                    .map(|&(weekday, index)| WeekdayJson {
+                        weekday,
+                        hours: heatmap[index].to_vec(),
+                        total: heatmap[index].iter().sum(),
+                    })
+                    .collect()
+            }),
+        };
+        return print_json(&output);
+    }
+
+    // Summary Section
+    println!("{}\n", style::paint(Role::Header, "📊 ggo Statistics"));
+    println!("Total branch switches: {}", stats.total_switches);
+    println!("Unique branches tracked: {}", stats.unique_branches);
+    match &filter.repo_path {
+        Some(repo) => println!("Repository: {}", repo),
+        None => println!("Repositories: {}", stats.unique_repos),
+    }
+    println!("Database location: {}", stats.db_path.display());
+    println!(
+        "Data directory: {} ({})",
+        data_dir.display(),
+        data_dir_source.describe()
+    );
+
+    if stats.unique_branches == 0 {
+        if filter.repo_path.is_some() || filter.max_age_seconds.is_some() {
+            println!("\nNo branch usage matches these filters.");
+        } else {
+            println!("\nNo branch usage data yet. Start using ggo to build your history!");
+        }
+        return Ok(());
+    }
+
+    // Top Branches with Bar Charts
+    let order = match sort {
+        storage::StatsSort::Switches => "Switches",
+        storage::StatsSort::Recent => "Recent Use",
+        storage::StatsSort::Score => "Frecency",
+    };
+    let heading = format!("🔥 Top Branches by {}:", order);
+    println!("\n{}\n", style::paint(Role::Header, heading));
+
+    if !top_branches.is_empty() {
+        let max_score = top_branches
+            .iter()
+            .map(|b| b.frecency_score)
+            .fold(1.0_f64, f64::max);
+
+        for (i, branch) in top_branches.iter().enumerate() {
+            let time_ago = frecency::format_relative_time(branch.last_used);
+            let bar_width = (branch.frecency_score / max_score * 40.0) as usize;
+            let bar = "█".repeat(bar_width);
+
+            // Pad before coloring: escape codes would count toward the width
+            let score = format!("{:>5.1}", branch.frecency_score);
+            println!(
+                "  {:2}. {:<30} {} {} {}",
+                i + 1,
+                truncate_string(&branch.branch_name, 30),
+                style::paint(Role::Score, score),
+                style::paint(Role::Score, bar),
+                style::paint(
+                    Role::Dim,
+                    format!("({} switches, {})", branch.switch_count, time_ago)
+                )
+            );
+        }
+    }
+
+    // Repository Breakdown
+    if stats.unique_repos > 1 {
+        println!(
+            "\n{}\n",
+            style::paint(Role::Header, "📁 Repository Breakdown:")
+        );
+
+        #[derive(Tabled)]
+        struct RepoStats {
+            #[tabled(rename = "Repository")]
+            name: String,
+            #[tabled(rename = "Branches")]
+            branches: i64,
+            #[tabled(rename = "Switches")]
+            switches: i64,
+            #[tabled(rename = "Last activity")]
+            last_activity: String,
+        }
+
+        let repo_stats: Vec<RepoStats> = repositories
+            .into_iter()
+            .map(|repo| {
+                let name = std::path::Path::new(&repo.repo_path)
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or(&repo.repo_path)
+                    .to_string();
+                RepoStats {
+                    name,
+                    branches: repo.branches,
+                    switches: repo.switches,
+                    last_activity: frecency::format_relative_time(repo.last_used),
+                }
+            })
+            .collect();
+
+        let mut table = Table::new(repo_stats);
+        table
+            .with(Style::rounded())
+            .with(Modify::new(Rows::first()).with(Alignment::center()));
+
+        println!("{}", table);
+    }
+
+    if let Some(heatmap) = &heatmap {
+        print_heatmap(heatmap);
+    }
+
+    Ok(())
+}
+
+/// The repository `ggo --stats <pattern>` shows: the best match of
+/// `pattern` among the repositories with history (by name, or by path when
+/// the pattern has a '/'), or the one picked from a menu when several match
+/// about as well. Only the grouped per-repository counts are read.
+pub fn stats_repo(
+    storage: &storage::Storage,
+    pattern: &str,
+    options: &RepoMatchOptions,
+    max_age_seconds: Option<i64>,
+    config: &config::Config,
+) -> Result<String> {
+    let filter = storage::StatsFilter {
+        repo_path: None,
+        max_age_seconds,
+    };
+    // Busiest first, which also breaks ties between equal matches
+    let summaries = storage.repo_summaries(&filter)?;
+    let repos: Vec<String> = summaries.iter().map(|s| s.repo_path.clone()).collect();
+
+    let scoring = config.scoring();
+    let mut ranked = ranking::rank_repos(
+        &repos,
+        pattern,
+        options.ignore_case,
+        options.use_fuzzy,
+        &[],
+        &scoring,
+        &config.matching.bonuses(),
+    );
+    let busyness = |repo: &str| repos.iter().position(|r| r == repo);
+    ranked.sort_by(|a, b| {
+        b.1.total_cmp(&a.1)
+            .then(busyness(&a.0).cmp(&busyness(&b.0)))
+    });
+
+    if ranked.is_empty() {
+        return Err(GgoError::NoMatchingRepos(pattern.to_string()));
+    }
+
+    top_or_pick(
+        &ranked,
+        options.can_prompt,
+        options.auto_select,
+        scoring.auto_select_threshold,
+        config,
+        || {
+            let choices: Vec<String> = ranked
+                .iter()
+                .map(|(repo, _)| {
+                    let summary = &summaries[busyness(repo).expect("ranked from the summaries")];
+                    format!(
+                        "{}  ({} switches, {})",
+                        repo,
+                        summary.switches,
+                        frecency::format_relative_time(summary.last_used)
+                    )
+                })
+                .collect();
+            let index = interactive::select_repository(&choices)?;
+            Ok(ranked[index].0.clone())
+        },
+    )
+}
+
+/// Which logged runs `ggo --stats --slow` shows
+pub struct SlowRunsOptions {
+    /// Only runs in this repository (--this-repo)
+    pub repo_path: Option<String>,
+    /// Only runs started within this many seconds (--since)
+    pub max_age_seconds: Option<i64>,
+    pub top: usize,
+    pub json: bool,
+}
+
+/// Handle 'ggo --stats --slow': the slowest runs in the invocation log
+pub fn show_slow_runs(path: &std::path::Path, options: &SlowRunsOptions) -> Result<()> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    let entries: Vec<logfile::Entry> = logfile::read_entries(path)?
+        .into_iter()
+        .filter(|entry| {
+            options.repo_path.is_none() || entry.repo.as_deref() == options.repo_path.as_deref()
+        })
+        .filter(|entry| {
+            options
+                .max_age_seconds
+                .is_none_or(|max_age| now - entry.timestamp <= max_age)
+        })
+        .collect();
+    let slowest = logfile::slowest(entries, options.top);
+
+    if options.json {
+        return print_json(&slowest);
+    }
+
+    if slowest.is_empty() {
+        println!("No logged runs in {}.", path.display());
+        println!("Set log.enabled = true (or pass --log-file) to log every run.");
+        return Ok(());
+    }
+
+    println!(
+        "{}\n",
+        style::paint(Role::Header, "🐢 Slowest Recent Runs:")
+    );
+    for (i, entry) in slowest.iter().enumerate() {
+        let outcome = match (&entry.selected, &entry.error) {
+            (_, Some(error)) => format!("failed: {}", error),
+            (Some(branch), None) => format!("→ {}", branch),
+            (None, None) => String::new(),
+        };
+        let duration = format!("{:>7} ms", entry.duration_ms);
+        println!(
+            "  {:2}. {} {:<30} {} {}",
+            i + 1,
+            style::paint(Role::Score, duration),
+            truncate_string(&format!("ggo {}", entry.args.join(" ")), 30),
+            outcome,
+            style::paint(
+                Role::Dim,
+                format!("({})", frecency::format_relative_time(entry.timestamp))
+            )
+        );
+    }
+
+    Ok(())
+}
+
+/// Truncate string to max length with ellipsis
+fn truncate_string(s: &str, max_len: usize) -> String {
+    if s.len() <= max_len {
+        s.to_string()
+    } else {
+        format!("{}...", &s[..max_len - 3])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heat_shade_scales_to_busiest_cell() {
+        assert_eq!(heat_shade(0, 10), '·');
+        assert_eq!(heat_shade(1, 10), '░');
+        assert_eq!(heat_shade(5, 10), '▒');
+        assert_eq!(heat_shade(10, 10), '█');
+        assert_eq!(heat_shade(3, 0), '·');
+    }
+}
@@ -0,0 +1,129 @@
+//! 'ggo wt': jumping to the worktree a branch is checked out in.
+
+use crate::config::AutoSelect;
+use crate::error::{GgoError, Result};
+use crate::switch::{
+    record_branch_usage, repo_key, request_directory_change, save_previous_branch,
+};
+use crate::{backend, config, git, interactive, ranking, storage, style};
+
+use super::checkout::{boost_session, pick_branch};
+use super::{load_pinned_branches, print_warning, top_or_pick};
+
+/// What 'ggo wt' should do
+pub struct WorktreeOptions {
+    pub ignore_case: bool,
+    pub use_fuzzy: bool,
+    /// Create a missing worktree without asking
+    pub create: bool,
+    /// Directory for a new worktree (default: next to the main worktree)
+    pub path: Option<std::path::PathBuf>,
+    pub can_prompt: bool,
+    /// behavior.auto_select, or what --pick/--first set for this run
+    pub auto_select: AutoSelect,
+}
+
+/// Handle 'ggo wt': rank branches exactly like a checkout, then go to the
+/// worktree the pick is checked out in, or create one for it. Jumps are
+/// recorded like switches, so frecency ranks worktrees the same way.
+pub fn handle_wt_command(
+    storage: &storage::Storage,
+    pattern: &str,
+    options: &WorktreeOptions,
+    config: &config::Config,
+) -> Result<()> {
+    let repo_path = repo_key(storage, config)?;
+    let branches = config.behavior.offered_branches(git::get_branches()?);
+
+    let records = storage.branch_records(&repo_path).unwrap_or_else(|e| {
+        style::warn(format!("Could not load branch history: {}", e));
+        Vec::new()
+    });
+    let scoring = config.scoring_for(&repo_path);
+    let ranked = ranking::score_matching_branches(
+        &branches,
+        pattern,
+        options.ignore_case,
+        options.use_fuzzy,
+        &records,
+        &scoring,
+        &config.matching.bonuses(),
+    );
+    let ranked = boost_session(backend::backend(), storage, &repo_path, ranked, config);
+    let ranked = ranking::float_pinned(ranked, &load_pinned_branches(storage, &repo_path));
+
+    if ranked.is_empty() {
+        return Err(GgoError::NoMatchingBranches(pattern.to_string()));
+    }
+
+    let branch = top_or_pick(
+        &ranked,
+        options.can_prompt,
+        options.auto_select,
+        scoring.auto_select_threshold,
+        config,
+        || {
+            let branch_list: Vec<String> = ranked.iter().map(|(b, _)| b.clone()).collect();
+            pick_branch(
+                storage,
+                &repo_path,
+                &branch_list,
+                &records,
+                config.behavior.picker,
+                config,
+                scoring.half_life_days,
+            )
+        },
+    )?;
+
+    // A branch is checked out in at most one worktree
+    let current_branch = git::get_current_branch().ok();
+    if current_branch.as_deref() == Some(branch.as_str()) {
+        println!("Already in the worktree for '{}'", branch);
+        return Ok(());
+    }
+
+    let existing = git::worktrees()?
+        .into_iter()
+        .find(|worktree| worktree.branch.as_deref() == Some(branch.as_str()));
+    let path = match existing {
+        Some(worktree) => worktree.path,
+        None => {
+            let path = match &options.path {
+                Some(path) => path.clone(),
+                None => git::default_worktree_path(&branch)?,
+            };
+            let create = options.create
+                || (options.can_prompt && interactive::confirm_create_worktree(&branch, &path)?);
+            if !create {
+                return Err(GgoError::NoWorktree(branch));
+            }
+            git::create_worktree(&branch, &path)?;
+            eprintln!("Created worktree for '{}' at '{}'", branch, path.display());
+            path
+        }
+    };
+
+    save_previous_branch(
+        storage,
+        &repo_path,
+        current_branch.as_deref(),
+        &branch,
+        print_warning,
+    );
+    record_branch_usage(
+        storage,
+        &repo_path,
+        current_branch.as_deref(),
+        &branch,
+        print_warning,
+    );
+
+    if request_directory_change(&path)? {
+        println!("Switched to worktree '{}' ({})", path.display(), branch);
+    } else {
+        // Without the shell integration, print the path for cd "$(ggo wt ...)"
+        println!("{}", path.display());
+    }
+    Ok(())
+}
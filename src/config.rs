@@ -1,7 +1,25 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
+use crate::constants::database::MAX_RECORDS;
+use crate::constants::frecency::DEFAULT_HALF_LIFE_DAYS;
+use crate::constants::history::DEFAULT_PASSIVE_WINDOW_MINUTES;
+use crate::constants::hooks::DEFAULT_STALE_DAYS;
+use crate::constants::log::{DEFAULT_KEEP, DEFAULT_MAX_SIZE_KB};
+use crate::constants::maintenance::{MAINTENANCE_EVERY, MAINTENANCE_MAX_AGE_DAYS};
+use crate::constants::picker::DEFAULT_PAGE_SIZE;
+use crate::constants::scoring::{
+    ACRONYM_BONUS, DEFAULT_ACTIVITY_WEIGHT, DEFAULT_AUTO_SELECT_MIN_SCORE,
+    DEFAULT_AUTO_SELECT_THRESHOLD, DEFAULT_SESSION_MINUTES, DEFAULT_TICKET_PATTERN,
+    FRECENCY_MULTIPLIER, SEGMENT_START_BONUS, TICKET_BONUS,
+};
+use crate::constants::sync::DEFAULT_SYNC_DIR;
 use crate::error::{GgoError, Result};
+use crate::frecency::ScoringParams;
+use crate::groups;
+use crate::matcher;
+use tracing::warn;
 
 /// Configuration for ggo behavior
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,6 +29,38 @@ pub struct Config {
 
     #[serde(default)]
     pub behavior: BehaviorConfig,
+
+    #[serde(default)]
+    pub history: HistoryConfig,
+
+    #[serde(default)]
+    pub matching: MatchingConfig,
+
+    #[serde(default)]
+    pub display: DisplayConfig,
+
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+
+    #[serde(default)]
+    pub sync: SyncConfig,
+
+    #[serde(default)]
+    pub hooks: HooksConfig,
+
+    #[serde(default)]
+    pub search: SearchConfig,
+
+    #[serde(default)]
+    pub log: LogConfig,
+
+    #[serde(default)]
+    pub storage: StorageConfig,
+
+    /// Scoring overrides per repository, keyed by its path (or
+    /// `host/owner/repo` with `history.repo_identity = "remote"`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub repos: BTreeMap<String, RepoScoringConfig>,
 }
 
 /// Frecency algorithm configuration
@@ -20,6 +70,36 @@ pub struct FrecencyConfig {
     /// After this duration, a branch's recency weight is halved
     #[serde(default = "default_half_life_days")]
     pub half_life_days: f64,
+
+    /// Weight of frecency against fuzzy match quality (default: 10)
+    #[serde(default = "default_frecency_multiplier")]
+    pub multiplier: f64,
+
+    /// Minutes without a checkout that end a session (default: 120, 0 turns
+    /// session ranking off). Branches switched to in the current session rank
+    /// above ones with more lifetime use.
+    #[serde(default = "default_session_minutes")]
+    pub session_minutes: u32,
+
+    /// Weight of your own recent commits against frecency in 'ggo
+    /// switch-last-modified' (default: 0.7; 1 ranks by commits only, 0 by
+    /// frecency only)
+    #[serde(default = "default_activity_weight")]
+    pub activity_weight: f64,
+}
+
+/// Scoring settings for one repository (`[repos."<repository>"]`); unset
+/// fields fall back to the global ones
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RepoScoringConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub half_life_days: Option<f64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multiplier: Option<f64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_select_threshold: Option<f64>,
 }
 
 /// Behavior configuration
@@ -29,6 +109,15 @@ pub struct BehaviorConfig {
     #[serde(default = "default_auto_select_threshold")]
     pub auto_select_threshold: f64,
 
+    /// When the top match is checked out without showing the menu
+    #[serde(default)]
+    pub auto_select: AutoSelect,
+
+    /// Score the top match needs, besides the ratio, to be auto-selected
+    /// in `smart` mode (0 = no minimum)
+    #[serde(default = "default_auto_select_min_score")]
+    pub auto_select_min_score: f64,
+
     /// Enable fuzzy matching by default
     #[serde(default = "default_fuzzy")]
     pub default_fuzzy: bool,
@@ -36,23 +125,442 @@ pub struct BehaviorConfig {
     /// Case-insensitive matching by default
     #[serde(default)]
     pub default_ignore_case: bool,
+
+    /// Smart-case: all-lowercase patterns match case-insensitively, patterns
+    /// with an uppercase letter match case-sensitively
+    #[serde(default = "default_smart_case")]
+    pub smart_case: bool,
+
+    /// Picker used when a choice between branches is needed
+    #[serde(default)]
+    pub picker: Picker,
+
+    /// Command run for the fzf picker; it reads `branch<TAB>score<TAB>meta`
+    /// lines on stdin and prints the chosen line (skim works too)
+    #[serde(default = "default_fzf_command")]
+    pub fzf_command: String,
+
+    /// Glob patterns (e.g. "release/*") for branches that never show up in
+    /// matches and are never recorded
+    #[serde(default)]
+    pub exclude: Vec<String>,
+
+    /// Leave the checked-out branch out of the candidates when other
+    /// branches match too
+    #[serde(default)]
+    pub skip_current: bool,
+
+    /// Names and glob patterns of the `--group` given for this run; only
+    /// branches matching one of them are offered. Never saved.
+    #[serde(skip)]
+    pub only: Option<Vec<String>>,
+}
+
+/// Checkout event history configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryConfig {
+    /// Days of individual checkout events to keep when compacting (0 = forever).
+    /// Aggregated switch counts are kept regardless.
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u32,
+
+    /// What a repository's history is keyed by
+    #[serde(default)]
+    pub repo_identity: RepoIdentity,
+
+    /// Minutes within which the shell hook doesn't count landing on a branch
+    /// again with plain git as another switch (default: 10, 0 counts every
+    /// one); 'ggo -' still follows it
+    #[serde(default = "default_passive_window_minutes")]
+    pub passive_window_minutes: u32,
+}
+
+/// Branch listing layout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisplayConfig {
+    /// Width of the branch name column in the selection menu
+    #[serde(default = "default_branch_width")]
+    pub branch_width: usize,
+
+    /// Width of the last-commit subject in listings (0 hides it)
+    #[serde(default = "default_subject_width")]
+    pub subject_width: usize,
+
+    /// Branches shown at once in the selection menus
+    #[serde(default = "default_page_size")]
+    pub page_size: usize,
+
+    /// Colors used when output is colored (see `--color`)
+    #[serde(default)]
+    pub theme: Theme,
+
+    /// Menu columns, most important first: in a narrow terminal the last
+    /// one is dropped first. Columns left out are dropped before any listed.
+    #[serde(default = "default_column_priority")]
+    pub column_priority: Vec<MenuColumn>,
+}
+
+/// Automatic database maintenance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceConfig {
+    /// Tidy the database in passing every `every` invocations (opt-in)
+    #[serde(default)]
+    pub auto: bool,
+
+    /// Invocations between maintenance runs
+    #[serde(default = "default_maintenance_every")]
+    pub every: u32,
+
+    /// Branch records unused for this many days are pruned (0 keeps them)
+    #[serde(default = "default_maintenance_max_age_days")]
+    pub max_age_days: i64,
+}
+
+/// Sharing usage history between machines (`ggo sync`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncConfig {
+    /// Git remote URL, or the path of a local clone (e.g. a dotfiles repository).
+    /// Empty disables sync.
+    #[serde(default)]
+    pub repo: String,
+
+    /// Directory inside the repository for the per-machine history files
+    #[serde(default = "default_sync_dir")]
+    pub dir: String,
+}
+
+/// How a repository is identified in the usage history
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RepoIdentity {
+    /// Canonical path of the main worktree (shared by all its worktrees)
+    #[default]
+    Path,
+    /// The `origin` URL, so history follows the project across clones and
+    /// moves; falls back to the path without an `origin` remote
+    Remote,
+}
+
+/// Commands run around a branch switch (a repository's `.ggo.toml` can add more)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HooksConfig {
+    /// Run hooks at all (`--no-hooks` turns them off for one run)
+    #[serde(default = "default_hooks_enabled")]
+    pub enabled: bool,
+
+    /// Shell commands run before a switch; one failing cancels it
+    #[serde(default)]
+    pub pre_checkout: Vec<String>,
+
+    /// Shell commands run after a successful switch
+    #[serde(default)]
+    pub post_checkout: Vec<String>,
+
+    /// Guard against switching with uncommitted changes
+    #[serde(default)]
+    pub guard_dirty: GuardAction,
+
+    /// Guard against leaving a branch with commits on no remote
+    #[serde(default)]
+    pub guard_unpushed: GuardAction,
+
+    /// Guard against switching to a protected branch
+    #[serde(default = "default_guard_protected")]
+    pub guard_protected: GuardAction,
+
+    /// Guard against switching to a branch not used in `stale_days`
+    #[serde(default)]
+    pub guard_stale: GuardAction,
+
+    /// Days without a switch after which a branch counts as stale
+    #[serde(default = "default_stale_days")]
+    pub stale_days: u32,
+
+    /// Glob patterns of protected branches (e.g. `main`, `release/*`)
+    #[serde(default)]
+    pub protected: Vec<String>,
+}
+
+/// Where `ggo repo` looks for repositories besides the usage history
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchConfig {
+    /// Directories searched a few levels deep for repositories (`~/` expands)
+    #[serde(default)]
+    pub roots: Vec<String>,
+}
+
+/// The invocation log (see `ggo::logfile`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogConfig {
+    /// Log every run to `logs/ggo.jsonl` in the data directory (opt-in)
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Size in KiB past which the log file is rotated
+    #[serde(default = "default_log_max_size_kb")]
+    pub max_size_kb: u64,
+
+    /// Rotated files kept besides the current one (0 keeps none)
+    #[serde(default = "default_log_keep")]
+    pub keep: u32,
+}
+
+/// Where the usage history lives, and how much of it is kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageConfig {
+    /// Directory for the database, its backups and the invocation log, e.g. in
+    /// a dotfiles repository or a synced folder (`~/` expands). Empty uses the
+    /// default; `--data-dir` and `GGO_DATA_DIR` take precedence.
+    #[serde(default)]
+    pub data_dir: String,
+
+    /// Where the history is kept in the data directory: an SQLite database
+    /// or a plain JSON file
+    #[serde(default)]
+    pub backend: StorageBackend,
+
+    /// Most branch records kept; beyond it the lowest-frecency ones are
+    /// trimmed (0: no limit)
+    #[serde(default = "default_max_records")]
+    pub max_records: usize,
+
+    /// Branch records unused for this many days are trimmed (0 keeps them)
+    #[serde(default)]
+    pub max_age_days: i64,
+}
+
+/// Storage backend for the usage history (`storage.backend`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// `data.db`, the default when built with the `sqlite` feature
+    #[cfg_attr(feature = "sqlite", default)]
+    Sqlite,
+    /// `history.json`, the default without the `sqlite` feature
+    #[cfg_attr(not(feature = "sqlite"), default)]
+    Json,
+    /// `history.json.enc`, keyed from the OS keychain (`encrypted` feature)
+    Encrypted,
+}
+
+impl StorageConfig {
+    /// The configured data directory, if any, with `~/` expanded
+    pub fn data_dir_path(&self) -> Option<PathBuf> {
+        (!self.data_dir.is_empty()).then(|| expand_home(&self.data_dir))
+    }
+}
+
+/// What a pre-checkout guard does when it trips (`--force` skips all guards),
+/// from least to most strict
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GuardAction {
+    /// Don't check
+    #[default]
+    Off,
+    /// Print a warning and switch anyway
+    Warn,
+    /// Ask before switching (refuses without a terminal)
+    Confirm,
+    /// Refuse to switch
+    Abort,
+}
+
+/// Fuzzy match scoring configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchingConfig {
+    /// Bonus per matched character at the start of a path segment or word
+    /// (after `/`, `-`, `_`)
+    #[serde(default = "default_segment_bonus")]
+    pub segment_bonus: i64,
+
+    /// Bonus per pattern character when the pattern matches segment initials
+    /// (`fab` → `feature/add-button`)
+    #[serde(default = "default_acronym_bonus")]
+    pub acronym_bonus: i64,
+
+    /// Bonus per pattern term that names a ticket ID in the branch name
+    /// (`1234` → `feature/PROJ-1234-fix-login`)
+    #[serde(default = "default_ticket_bonus")]
+    pub ticket_bonus: i64,
+
+    /// Regex for ticket IDs in branch names (empty disables ticket matching)
+    #[serde(default = "default_ticket_pattern")]
+    pub ticket_pattern: String,
+}
+
+/// Interactive picker style
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Picker {
+    /// Inline select menu
+    #[default]
+    Select,
+    /// Full-screen picker with live filtering and a commit preview
+    Tui,
+    /// External fzf (or skim) process, see `behavior.fzf_command`
+    Fzf,
+}
+
+/// When several branches match, whether the top one is checked out without
+/// showing the menu
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AutoSelect {
+    /// Always take the top match
+    Always,
+    /// Always show the menu
+    Never,
+    /// Take the top match when it clearly wins (see
+    /// `behavior.auto_select_threshold` and `behavior.auto_select_min_score`)
+    #[default]
+    Smart,
+}
+
+/// A selection menu column that can be dropped to fit a narrow terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MenuColumn {
+    /// Frecency score
+    Score,
+    /// Number of switches
+    Usage,
+    /// When the branch was last switched to
+    LastUsed,
+    /// Ahead/behind counts against the upstream
+    Sync,
+}
+
+impl MenuColumn {
+    pub const ALL: [MenuColumn; 4] = [
+        MenuColumn::LastUsed,
+        MenuColumn::Sync,
+        MenuColumn::Score,
+        MenuColumn::Usage,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MenuColumn::Score => "score",
+            MenuColumn::Usage => "usage",
+            MenuColumn::LastUsed => "last_used",
+            MenuColumn::Sync => "sync",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|column| column.as_str() == name)
+    }
+}
+
+/// Color theme for terminal output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Bright colors for dark terminal backgrounds
+    #[default]
+    Default,
+    /// Darker colors that stay readable on light backgrounds
+    Light,
+    /// No colors, only bold and dim text
+    Mono,
 }
 
 // Default value functions
 fn default_half_life_days() -> f64 {
-    7.0 // 1 week
+    DEFAULT_HALF_LIFE_DAYS // 1 week
+}
+fn default_frecency_multiplier() -> f64 {
+    FRECENCY_MULTIPLIER
+}
+fn default_session_minutes() -> u32 {
+    DEFAULT_SESSION_MINUTES
+}
+fn default_activity_weight() -> f64 {
+    DEFAULT_ACTIVITY_WEIGHT
 }
 fn default_auto_select_threshold() -> f64 {
-    2.0
+    DEFAULT_AUTO_SELECT_THRESHOLD
+}
+fn default_auto_select_min_score() -> f64 {
+    DEFAULT_AUTO_SELECT_MIN_SCORE
+}
+fn default_fzf_command() -> String {
+    // fzf and skim take a regex delimiter, so "\t" is a tab; matching only
+    // the branch column keeps the score and meta out of the query
+    r#"fzf --delimiter "\t" --nth 1"#.to_string()
 }
 fn default_fuzzy() -> bool {
     true
 }
+fn default_smart_case() -> bool {
+    true
+}
+fn default_retention_days() -> u32 {
+    180
+}
+fn default_passive_window_minutes() -> u32 {
+    DEFAULT_PASSIVE_WINDOW_MINUTES
+}
+fn default_branch_width() -> usize {
+    38
+}
+fn default_subject_width() -> usize {
+    40
+}
+fn default_page_size() -> usize {
+    DEFAULT_PAGE_SIZE
+}
+fn default_column_priority() -> Vec<MenuColumn> {
+    MenuColumn::ALL.to_vec()
+}
+fn default_segment_bonus() -> i64 {
+    SEGMENT_START_BONUS
+}
+fn default_acronym_bonus() -> i64 {
+    ACRONYM_BONUS
+}
+fn default_ticket_bonus() -> i64 {
+    TICKET_BONUS
+}
+fn default_ticket_pattern() -> String {
+    DEFAULT_TICKET_PATTERN.to_string()
+}
+fn default_maintenance_every() -> u32 {
+    MAINTENANCE_EVERY
+}
+fn default_maintenance_max_age_days() -> i64 {
+    MAINTENANCE_MAX_AGE_DAYS
+}
+fn default_max_records() -> usize {
+    MAX_RECORDS
+}
+fn default_sync_dir() -> String {
+    DEFAULT_SYNC_DIR.to_string()
+}
+fn default_guard_protected() -> GuardAction {
+    GuardAction::Confirm
+}
+fn default_stale_days() -> u32 {
+    DEFAULT_STALE_DAYS
+}
+fn default_log_max_size_kb() -> u64 {
+    DEFAULT_MAX_SIZE_KB
+}
+fn default_log_keep() -> u32 {
+    DEFAULT_KEEP
+}
+fn default_hooks_enabled() -> bool {
+    true
+}
 
 impl Default for FrecencyConfig {
     fn default() -> Self {
         Self {
             half_life_days: default_half_life_days(),
+            multiplier: default_frecency_multiplier(),
+            session_minutes: default_session_minutes(),
+            activity_weight: default_activity_weight(),
         }
     }
 }
@@ -61,8 +569,156 @@ impl Default for BehaviorConfig {
     fn default() -> Self {
         Self {
             auto_select_threshold: default_auto_select_threshold(),
+            auto_select: AutoSelect::default(),
+            auto_select_min_score: default_auto_select_min_score(),
             default_fuzzy: default_fuzzy(),
             default_ignore_case: false,
+            smart_case: default_smart_case(),
+            picker: Picker::default(),
+            fzf_command: default_fzf_command(),
+            exclude: Vec::new(),
+            skip_current: false,
+            only: None,
+        }
+    }
+}
+
+impl BehaviorConfig {
+    /// Whether `pattern` should match case-insensitively when no `-i` or
+    /// `--case-sensitive` flag was given
+    pub fn ignore_case_for(&self, pattern: &str) -> bool {
+        self.default_ignore_case || (self.smart_case && matcher::smart_case_ignores(pattern))
+    }
+
+    /// Whether `branch` can be offered: it isn't excluded and, with
+    /// `--group`, belongs to the group
+    pub fn offers(&self, branch: &str) -> bool {
+        !matcher::is_excluded(branch, &self.exclude)
+            && self
+                .only
+                .as_ref()
+                .is_none_or(|only| groups::contains(only, branch))
+    }
+
+    /// The branches that can be offered (see [`BehaviorConfig::offers`])
+    pub fn offered_branches(&self, branches: Vec<String>) -> Vec<String> {
+        if self.exclude.is_empty() && self.only.is_none() {
+            return branches;
+        }
+
+        branches
+            .into_iter()
+            .filter(|branch| self.offers(branch))
+            .collect()
+    }
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: default_retention_days(),
+            repo_identity: RepoIdentity::default(),
+            passive_window_minutes: default_passive_window_minutes(),
+        }
+    }
+}
+
+impl Default for MatchingConfig {
+    fn default() -> Self {
+        Self {
+            segment_bonus: default_segment_bonus(),
+            acronym_bonus: default_acronym_bonus(),
+            ticket_bonus: default_ticket_bonus(),
+            ticket_pattern: default_ticket_pattern(),
+        }
+    }
+}
+
+impl MatchingConfig {
+    /// The configured bonuses in the form the matcher takes. An invalid
+    /// ticket pattern (from a hand-edited file) turns ticket matching off.
+    pub fn bonuses(&self) -> matcher::MatchBonuses {
+        let tickets = if self.ticket_pattern.is_empty() {
+            None
+        } else {
+            matcher::TicketPattern::new(&self.ticket_pattern)
+                .map_err(|e| warn!("Ignoring invalid matching.ticket_pattern: {}", e))
+                .ok()
+        };
+
+        matcher::MatchBonuses {
+            segment_start: self.segment_bonus,
+            acronym: self.acronym_bonus,
+            ticket: self.ticket_bonus,
+            tickets,
+        }
+    }
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            branch_width: default_branch_width(),
+            subject_width: default_subject_width(),
+            page_size: default_page_size(),
+            theme: Theme::default(),
+            column_priority: default_column_priority(),
+        }
+    }
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            auto: false,
+            every: default_maintenance_every(),
+            max_age_days: default_maintenance_max_age_days(),
+        }
+    }
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            data_dir: String::new(),
+            backend: StorageBackend::default(),
+            max_records: default_max_records(),
+            max_age_days: 0,
+        }
+    }
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            repo: String::new(),
+            dir: default_sync_dir(),
+        }
+    }
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_hooks_enabled(),
+            pre_checkout: Vec::new(),
+            post_checkout: Vec::new(),
+            guard_dirty: GuardAction::default(),
+            guard_unpushed: GuardAction::default(),
+            guard_protected: default_guard_protected(),
+            guard_stale: GuardAction::default(),
+            stale_days: default_stale_days(),
+            protected: Vec::new(),
+        }
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_size_kb: default_log_max_size_kb(),
+            keep: default_log_keep(),
         }
     }
 }
@@ -73,11 +729,715 @@ impl Default for Config {
         Self {
             frecency: FrecencyConfig::default(),
             behavior: BehaviorConfig::default(),
+            history: HistoryConfig::default(),
+            matching: MatchingConfig::default(),
+            display: DisplayConfig::default(),
+            maintenance: MaintenanceConfig::default(),
+            sync: SyncConfig::default(),
+            hooks: HooksConfig::default(),
+            search: SearchConfig::default(),
+            log: LogConfig::default(),
+            storage: StorageConfig::default(),
+            repos: BTreeMap::new(),
+        }
+    }
+}
+
+/// Keys accepted by `ggo config get/set`, in `section.field` form
+pub const KEYS: &[&str] = &[
+    "frecency.half_life_days",
+    "frecency.multiplier",
+    "frecency.session_minutes",
+    "frecency.activity_weight",
+    "behavior.auto_select_threshold",
+    "behavior.auto_select",
+    "behavior.auto_select_min_score",
+    "behavior.default_fuzzy",
+    "behavior.default_ignore_case",
+    "behavior.smart_case",
+    "behavior.picker",
+    "behavior.fzf_command",
+    "behavior.exclude",
+    "behavior.skip_current",
+    "history.retention_days",
+    "history.repo_identity",
+    "history.passive_window_minutes",
+    "matching.segment_bonus",
+    "matching.acronym_bonus",
+    "matching.ticket_bonus",
+    "matching.ticket_pattern",
+    "display.branch_width",
+    "display.subject_width",
+    "display.page_size",
+    "display.theme",
+    "display.column_priority",
+    "maintenance.auto",
+    "maintenance.every",
+    "maintenance.max_age_days",
+    "sync.repo",
+    "sync.dir",
+    "hooks.enabled",
+    "hooks.guard_dirty",
+    "hooks.guard_unpushed",
+    "hooks.guard_protected",
+    "hooks.guard_stale",
+    "hooks.stale_days",
+    "hooks.protected",
+    "search.roots",
+    "log.enabled",
+    "log.max_size_kb",
+    "log.keep",
+    "storage.data_dir",
+    "storage.backend",
+    "storage.max_records",
+    "storage.max_age_days",
+];
+
+impl AutoSelect {
+    fn as_str(self) -> &'static str {
+        match self {
+            AutoSelect::Always => "always",
+            AutoSelect::Never => "never",
+            AutoSelect::Smart => "smart",
+        }
+    }
+}
+
+impl Picker {
+    fn as_str(self) -> &'static str {
+        match self {
+            Picker::Select => "select",
+            Picker::Tui => "tui",
+            Picker::Fzf => "fzf",
+        }
+    }
+}
+
+impl Theme {
+    fn as_str(self) -> &'static str {
+        match self {
+            Theme::Default => "default",
+            Theme::Light => "light",
+            Theme::Mono => "mono",
+        }
+    }
+}
+
+impl GuardAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            GuardAction::Off => "off",
+            GuardAction::Warn => "warn",
+            GuardAction::Confirm => "confirm",
+            GuardAction::Abort => "abort",
+        }
+    }
+
+    fn parse(key: &str, value: &str) -> Result<Self> {
+        match value {
+            "off" => Ok(GuardAction::Off),
+            "warn" => Ok(GuardAction::Warn),
+            "confirm" => Ok(GuardAction::Confirm),
+            "abort" => Ok(GuardAction::Abort),
+            _ => Err(invalid_value(
+                key,
+                value,
+                "expected 'off', 'warn', 'confirm' or 'abort'",
+            )),
         }
     }
 }
 
+impl RepoIdentity {
+    fn as_str(self) -> &'static str {
+        match self {
+            RepoIdentity::Path => "path",
+            RepoIdentity::Remote => "remote",
+        }
+    }
+}
+
+impl StorageBackend {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sqlite" => Some(StorageBackend::Sqlite),
+            "json" => Some(StorageBackend::Json),
+            "encrypted" => Some(StorageBackend::Encrypted),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StorageBackend::Sqlite => "sqlite",
+            StorageBackend::Json => "json",
+            StorageBackend::Encrypted => "encrypted",
+        }
+    }
+}
+
+fn invalid_value(key: &str, value: &str, expected: &str) -> GgoError {
+    GgoError::InvalidConfigValue(key.to_string(), value.to_string(), expected.to_string())
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool> {
+    value
+        .parse()
+        .map_err(|_| invalid_value(key, value, "expected 'true' or 'false'"))
+}
+
+fn parse_positive(key: &str, value: &str) -> Result<f64> {
+    match value.parse::<f64>() {
+        Ok(v) if v.is_finite() && v > 0.0 => Ok(v),
+        _ => Err(invalid_value(key, value, "expected a positive number")),
+    }
+}
+
+/// Fields of a `[repos."<repository>"]` table
+const REPO_SCORING_FIELDS: [&str; 3] = ["half_life_days", "multiplier", "auto_select_threshold"];
+
+/// Split a `repos.<repository>.<field>` key. The repository part may itself
+/// contain dots (`repos.github.com/me/app.multiplier`).
+fn split_repo_key(key: &str) -> Option<(&str, &str)> {
+    let (repo, field) = key.strip_prefix("repos.")?.rsplit_once('.')?;
+    (!repo.is_empty() && REPO_SCORING_FIELDS.contains(&field)).then_some((repo, field))
+}
+
+/// A path from the config with a leading `~/` expanded
+fn expand_home(configured: &str) -> PathBuf {
+    match (configured.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(configured),
+    }
+}
+
+/// Whether a repository named in the config is the one stored under
+/// `repo_key`: `~/` is expanded and trailing slashes don't matter
+fn same_repo(configured: &str, repo_key: &str) -> bool {
+    let expanded = expand_home(configured).to_string_lossy().into_owned();
+    expanded.trim_end_matches('/') == repo_key.trim_end_matches('/')
+}
+
+fn parse_bonus(key: &str, value: &str) -> Result<i64> {
+    match value.parse::<i64>() {
+        Ok(v) if v >= 0 => Ok(v),
+        _ => Err(invalid_value(
+            key,
+            value,
+            "expected a whole number (0 disables)",
+        )),
+    }
+}
+
 impl Config {
+    /// The `search.roots` directories, with `~/` expanded
+    pub fn search_roots(&self) -> Vec<PathBuf> {
+        self.search
+            .roots
+            .iter()
+            .map(|root| expand_home(root))
+            .collect()
+    }
+
+    /// Get a configuration value by its `section.field` key
+    pub fn get(&self, key: &str) -> Result<String> {
+        let value = match key {
+            "frecency.half_life_days" => self.frecency.half_life_days.to_string(),
+            "frecency.multiplier" => self.frecency.multiplier.to_string(),
+            "frecency.session_minutes" => self.frecency.session_minutes.to_string(),
+            "frecency.activity_weight" => self.frecency.activity_weight.to_string(),
+            "behavior.auto_select_threshold" => self.behavior.auto_select_threshold.to_string(),
+            "behavior.default_fuzzy" => self.behavior.default_fuzzy.to_string(),
+            "behavior.default_ignore_case" => self.behavior.default_ignore_case.to_string(),
+            "behavior.smart_case" => self.behavior.smart_case.to_string(),
+            "behavior.skip_current" => self.behavior.skip_current.to_string(),
+            "behavior.auto_select" => self.behavior.auto_select.as_str().to_string(),
+            "behavior.auto_select_min_score" => self.behavior.auto_select_min_score.to_string(),
+            "behavior.picker" => self.behavior.picker.as_str().to_string(),
+            "behavior.fzf_command" => self.behavior.fzf_command.clone(),
+            "behavior.exclude" => self.behavior.exclude.join(","),
+            "history.retention_days" => self.history.retention_days.to_string(),
+            "history.repo_identity" => self.history.repo_identity.as_str().to_string(),
+            "history.passive_window_minutes" => self.history.passive_window_minutes.to_string(),
+            "matching.segment_bonus" => self.matching.segment_bonus.to_string(),
+            "matching.acronym_bonus" => self.matching.acronym_bonus.to_string(),
+            "matching.ticket_bonus" => self.matching.ticket_bonus.to_string(),
+            "matching.ticket_pattern" => self.matching.ticket_pattern.clone(),
+            "display.branch_width" => self.display.branch_width.to_string(),
+            "display.subject_width" => self.display.subject_width.to_string(),
+            "display.page_size" => self.display.page_size.to_string(),
+            "display.theme" => self.display.theme.as_str().to_string(),
+            "display.column_priority" => self
+                .display
+                .column_priority
+                .iter()
+                .map(|column| column.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+            "maintenance.auto" => self.maintenance.auto.to_string(),
+            "maintenance.every" => self.maintenance.every.to_string(),
+            "maintenance.max_age_days" => self.maintenance.max_age_days.to_string(),
+            "sync.repo" => self.sync.repo.clone(),
+            "sync.dir" => self.sync.dir.clone(),
+            "hooks.enabled" => self.hooks.enabled.to_string(),
+            "hooks.guard_dirty" => self.hooks.guard_dirty.as_str().to_string(),
+            "hooks.guard_unpushed" => self.hooks.guard_unpushed.as_str().to_string(),
+            "hooks.guard_protected" => self.hooks.guard_protected.as_str().to_string(),
+            "hooks.guard_stale" => self.hooks.guard_stale.as_str().to_string(),
+            "hooks.stale_days" => self.hooks.stale_days.to_string(),
+            "hooks.protected" => self.hooks.protected.join(","),
+            "search.roots" => self.search.roots.join(","),
+            "log.enabled" => self.log.enabled.to_string(),
+            "log.max_size_kb" => self.log.max_size_kb.to_string(),
+            "log.keep" => self.log.keep.to_string(),
+            "storage.data_dir" => self.storage.data_dir.clone(),
+            "storage.backend" => self.storage.backend.as_str().to_string(),
+            "storage.max_records" => self.storage.max_records.to_string(),
+            "storage.max_age_days" => self.storage.max_age_days.to_string(),
+            _ => {
+                let (repo, field) = split_repo_key(key)
+                    .ok_or_else(|| GgoError::UnknownConfigKey(key.to_string()))?;
+                let overrides = self.repos.get(repo);
+                let value = match field {
+                    "half_life_days" => overrides.and_then(|o| o.half_life_days),
+                    "multiplier" => overrides.and_then(|o| o.multiplier),
+                    _ => overrides.and_then(|o| o.auto_select_threshold),
+                };
+                // Unset overrides read as empty, like an unset sync.repo
+                value.map(|v| v.to_string()).unwrap_or_default()
+            }
+        };
+
+        Ok(value)
+    }
+
+    /// Set a configuration value by its `section.field` key, validating the value
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        match key {
+            "frecency.half_life_days" => {
+                self.frecency.half_life_days = parse_positive(key, value)?;
+            }
+            "frecency.multiplier" => {
+                self.frecency.multiplier = match value.parse::<f64>() {
+                    Ok(v) if v.is_finite() && v >= 0.0 => v,
+                    _ => {
+                        return Err(invalid_value(
+                            key,
+                            value,
+                            "expected a number (0 ranks by match quality only)",
+                        ))
+                    }
+                };
+            }
+            "behavior.auto_select_threshold" => {
+                self.behavior.auto_select_threshold = parse_positive(key, value)?;
+            }
+            "behavior.auto_select" => {
+                self.behavior.auto_select = match value {
+                    "always" => AutoSelect::Always,
+                    "never" => AutoSelect::Never,
+                    "smart" => AutoSelect::Smart,
+                    _ => {
+                        return Err(invalid_value(
+                            key,
+                            value,
+                            "expected 'always', 'never' or 'smart'",
+                        ))
+                    }
+                };
+            }
+            "behavior.auto_select_min_score" => {
+                self.behavior.auto_select_min_score = match value.parse::<f64>() {
+                    Ok(v) if v.is_finite() && v >= 0.0 => v,
+                    _ => {
+                        return Err(invalid_value(
+                            key,
+                            value,
+                            "expected a number, 0 or more (0 has no minimum)",
+                        ))
+                    }
+                };
+            }
+            "behavior.default_fuzzy" => {
+                self.behavior.default_fuzzy = parse_bool(key, value)?;
+            }
+            "behavior.default_ignore_case" => {
+                self.behavior.default_ignore_case = parse_bool(key, value)?;
+            }
+            "behavior.smart_case" => {
+                self.behavior.smart_case = parse_bool(key, value)?;
+            }
+            "behavior.skip_current" => {
+                self.behavior.skip_current = parse_bool(key, value)?;
+            }
+            "behavior.picker" => {
+                self.behavior.picker = match value {
+                    "select" => Picker::Select,
+                    "tui" => Picker::Tui,
+                    "fzf" => Picker::Fzf,
+                    _ => {
+                        return Err(invalid_value(
+                            key,
+                            value,
+                            "expected 'select', 'tui' or 'fzf'",
+                        ))
+                    }
+                };
+            }
+            "behavior.fzf_command" => {
+                if value.trim().is_empty() {
+                    return Err(invalid_value(key, value, "expected a command"));
+                }
+                self.behavior.fzf_command = value.to_string();
+            }
+            "behavior.exclude" => {
+                // Comma-separated glob patterns; an empty value clears the list
+                self.behavior.exclude = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|p| !p.is_empty())
+                    .map(String::from)
+                    .collect();
+            }
+            "frecency.session_minutes" => {
+                self.frecency.session_minutes = value.parse().map_err(|_| {
+                    invalid_value(
+                        key,
+                        value,
+                        "expected a whole number of minutes (0 turns it off)",
+                    )
+                })?;
+            }
+            "frecency.activity_weight" => {
+                self.frecency.activity_weight = match value.parse::<f64>() {
+                    Ok(v) if (0.0..=1.0).contains(&v) => v,
+                    _ => return Err(invalid_value(key, value, "expected a number from 0 to 1")),
+                };
+            }
+            "history.retention_days" => {
+                self.history.retention_days = value.parse().map_err(|_| {
+                    invalid_value(key, value, "expected a whole number of days (0 keeps all)")
+                })?;
+            }
+            "history.passive_window_minutes" => {
+                self.history.passive_window_minutes = value.parse().map_err(|_| {
+                    invalid_value(
+                        key,
+                        value,
+                        "expected a whole number of minutes (0 counts every switch)",
+                    )
+                })?;
+            }
+            "history.repo_identity" => {
+                self.history.repo_identity = match value {
+                    "path" => RepoIdentity::Path,
+                    "remote" => RepoIdentity::Remote,
+                    _ => return Err(invalid_value(key, value, "expected 'path' or 'remote'")),
+                };
+            }
+            "matching.segment_bonus" => {
+                self.matching.segment_bonus = parse_bonus(key, value)?;
+            }
+            "matching.acronym_bonus" => {
+                self.matching.acronym_bonus = parse_bonus(key, value)?;
+            }
+            "matching.ticket_bonus" => {
+                self.matching.ticket_bonus = parse_bonus(key, value)?;
+            }
+            "matching.ticket_pattern" => {
+                if !value.is_empty() {
+                    matcher::TicketPattern::new(value)
+                        .map_err(|e| invalid_value(key, value, &format!("invalid regex: {}", e)))?;
+                }
+                self.matching.ticket_pattern = value.to_string();
+            }
+            "display.branch_width" => {
+                self.display.branch_width = match value.parse::<usize>() {
+                    Ok(width) if width >= 8 => width,
+                    _ => return Err(invalid_value(key, value, "expected a width of at least 8")),
+                };
+            }
+            "display.subject_width" => {
+                self.display.subject_width = value
+                    .parse()
+                    .map_err(|_| invalid_value(key, value, "expected a width (0 hides it)"))?;
+            }
+            "display.page_size" => {
+                self.display.page_size = match value.parse::<usize>() {
+                    Ok(rows) if rows > 0 => rows,
+                    _ => {
+                        return Err(invalid_value(
+                            key,
+                            value,
+                            "expected a number of rows, 1 or more",
+                        ))
+                    }
+                };
+            }
+            "display.theme" => {
+                self.display.theme = match value {
+                    "default" => Theme::Default,
+                    "light" => Theme::Light,
+                    "mono" => Theme::Mono,
+                    _ => {
+                        return Err(invalid_value(
+                            key,
+                            value,
+                            "expected 'default', 'light' or 'mono'",
+                        ))
+                    }
+                };
+            }
+            "display.column_priority" => {
+                // Comma-separated column names, most important first
+                let mut priority = Vec::new();
+                for name in value.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+                    match MenuColumn::parse(name) {
+                        Some(column) if !priority.contains(&column) => priority.push(column),
+                        _ => {
+                            return Err(invalid_value(
+                                key,
+                                value,
+                                "expected distinct columns among 'score', 'usage', 'last_used' and 'sync'",
+                            ))
+                        }
+                    }
+                }
+                self.display.column_priority = priority;
+            }
+            "maintenance.auto" => {
+                self.maintenance.auto = parse_bool(key, value)?;
+            }
+            "maintenance.every" => {
+                self.maintenance.every = match value.parse::<u32>() {
+                    Ok(every) if every > 0 => every,
+                    _ => {
+                        return Err(invalid_value(
+                            key,
+                            value,
+                            "expected a positive number of invocations",
+                        ))
+                    }
+                };
+            }
+            "maintenance.max_age_days" => {
+                self.maintenance.max_age_days = match value.parse::<i64>() {
+                    Ok(days) if days >= 0 => days,
+                    _ => {
+                        return Err(invalid_value(
+                            key,
+                            value,
+                            "expected a whole number of days (0 keeps all)",
+                        ))
+                    }
+                };
+            }
+            "sync.repo" => {
+                self.sync.repo = value.to_string();
+            }
+            "sync.dir" => {
+                // Relative to the sync repository, and never outside it
+                if value.is_empty()
+                    || std::path::Path::new(value).is_absolute()
+                    || value.split(['/', '\\']).any(|part| part == "..")
+                {
+                    return Err(invalid_value(
+                        key,
+                        value,
+                        "expected a directory inside the sync repository",
+                    ));
+                }
+                self.sync.dir = value.to_string();
+            }
+            "hooks.enabled" => {
+                self.hooks.enabled = parse_bool(key, value)?;
+            }
+            "hooks.guard_dirty" => {
+                self.hooks.guard_dirty = GuardAction::parse(key, value)?;
+            }
+            "hooks.guard_unpushed" => {
+                self.hooks.guard_unpushed = GuardAction::parse(key, value)?;
+            }
+            "hooks.guard_protected" => {
+                self.hooks.guard_protected = GuardAction::parse(key, value)?;
+            }
+            "hooks.guard_stale" => {
+                self.hooks.guard_stale = GuardAction::parse(key, value)?;
+            }
+            "hooks.stale_days" => {
+                self.hooks.stale_days = match value.parse::<u32>() {
+                    Ok(days) if days > 0 => days,
+                    _ => {
+                        return Err(invalid_value(
+                            key,
+                            value,
+                            "expected a positive whole number of days",
+                        ))
+                    }
+                };
+            }
+            "hooks.protected" => {
+                // Comma-separated glob patterns, like behavior.exclude
+                self.hooks.protected = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|p| !p.is_empty())
+                    .map(String::from)
+                    .collect();
+            }
+            "search.roots" => {
+                self.search.roots = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|p| !p.is_empty())
+                    .map(String::from)
+                    .collect();
+            }
+            "log.enabled" => {
+                self.log.enabled = parse_bool(key, value)?;
+            }
+            "log.max_size_kb" => {
+                self.log.max_size_kb = match value.parse::<u64>() {
+                    Ok(size) if size > 0 => size,
+                    _ => return Err(invalid_value(key, value, "expected a positive size in KiB")),
+                };
+            }
+            "log.keep" => {
+                self.log.keep = value.parse().map_err(|_| {
+                    invalid_value(
+                        key,
+                        value,
+                        "expected a whole number of files (0 keeps none)",
+                    )
+                })?;
+            }
+            "storage.data_dir" => {
+                // Relative paths would move with the working directory
+                if !value.is_empty() && !expand_home(value).is_absolute() {
+                    return Err(invalid_value(
+                        key,
+                        value,
+                        "expected an absolute path or one starting with ~/ (empty for the default)",
+                    ));
+                }
+                self.storage.data_dir = value.to_string();
+            }
+            "storage.backend" => {
+                self.storage.backend = StorageBackend::from_name(value).ok_or_else(|| {
+                    invalid_value(key, value, "expected 'sqlite', 'json' or 'encrypted'")
+                })?;
+            }
+            "storage.max_records" => {
+                self.storage.max_records = value.parse().map_err(|_| {
+                    invalid_value(
+                        key,
+                        value,
+                        "expected a whole number of records (0: no limit)",
+                    )
+                })?;
+            }
+            "storage.max_age_days" => {
+                self.storage.max_age_days = match value.parse::<i64>() {
+                    Ok(days) if days >= 0 => days,
+                    _ => {
+                        return Err(invalid_value(
+                            key,
+                            value,
+                            "expected a whole number of days (0 keeps all)",
+                        ))
+                    }
+                };
+            }
+            _ => {
+                let (repo, field) = split_repo_key(key)
+                    .ok_or_else(|| GgoError::UnknownConfigKey(key.to_string()))?;
+
+                // An empty value removes the override
+                let parsed = if value.is_empty() {
+                    None
+                } else if field == "multiplier" {
+                    match value.parse::<f64>() {
+                        Ok(v) if v.is_finite() && v >= 0.0 => Some(v),
+                        _ => {
+                            return Err(invalid_value(
+                                key,
+                                value,
+                                "expected a number (empty removes the override)",
+                            ))
+                        }
+                    }
+                } else {
+                    Some(parse_positive(key, value)?)
+                };
+
+                let overrides = self.repos.entry(repo.to_string()).or_default();
+                match field {
+                    "half_life_days" => overrides.half_life_days = parsed,
+                    "multiplier" => overrides.multiplier = parsed,
+                    _ => overrides.auto_select_threshold = parsed,
+                }
+                if *overrides == RepoScoringConfig::default() {
+                    self.repos.remove(repo);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// `repos.<repository>.<field>` keys of the per-repository overrides that
+    /// are set, for `ggo config list`
+    pub fn repo_override_keys(&self) -> Vec<String> {
+        let mut keys = Vec::new();
+        for (repo, overrides) in &self.repos {
+            let set = [
+                overrides.half_life_days,
+                overrides.multiplier,
+                overrides.auto_select_threshold,
+            ];
+            for (field, value) in REPO_SCORING_FIELDS.iter().zip(set) {
+                if value.is_some() {
+                    keys.push(format!("repos.{}.{}", repo, field));
+                }
+            }
+        }
+        keys
+    }
+
+    /// The global scoring parameters, without any repository's overrides
+    pub fn scoring(&self) -> ScoringParams {
+        ScoringParams {
+            half_life_days: self.frecency.half_life_days,
+            frecency_multiplier: self.frecency.multiplier,
+            auto_select_threshold: self.behavior.auto_select_threshold,
+        }
+    }
+
+    /// Scoring parameters for the repository stored under `repo_key`: the
+    /// global settings with that repository's overrides applied
+    pub fn scoring_for(&self, repo_key: &str) -> ScoringParams {
+        let mut params = self.scoring();
+
+        let overrides = self
+            .repos
+            .iter()
+            .filter(|(repo, _)| same_repo(repo, repo_key))
+            .map(|(_, overrides)| overrides);
+        for o in overrides {
+            if let Some(v) = o.half_life_days {
+                params.half_life_days = v;
+            }
+            if let Some(v) = o.multiplier {
+                params.frecency_multiplier = v;
+            }
+            if let Some(v) = o.auto_select_threshold {
+                params.auto_select_threshold = v;
+            }
+        }
+
+        params
+    }
+
     /// Load configuration from file, or use defaults if file doesn't exist
     pub fn load() -> Result<Self> {
         let config_path = Self::config_path()?;
@@ -96,7 +1456,31 @@ impl Config {
         Ok(config)
     }
 
-    /// Get the path to the config file
+    /// A config file with every setting at its default, commented out, for
+    /// `ggo setup`: it parses to the defaults, and uncommenting a line
+    /// overrides one setting
+    pub fn commented_defaults() -> Result<String> {
+        let defaults = toml::to_string_pretty(&Self::default()).map_err(|e| {
+            GgoError::ConfigError(format!("Failed to serialize configuration: {}", e))
+        })?;
+
+        let mut content = String::from(
+            "# ggo configuration. Every setting is listed with its default; uncomment\n\
+             # a line to change it, or use 'ggo config set <key> <value>'.\n",
+        );
+        for line in defaults.lines() {
+            content.push('\n');
+            if !line.is_empty() && !line.starts_with('[') {
+                content.push_str("# ");
+            }
+            content.push_str(line);
+        }
+        content.push('\n');
+        Ok(content)
+    }
+
+    /// Get the path to the config file. Its directory is only created when
+    /// something is saved there.
     pub fn config_path() -> Result<PathBuf> {
         let config_dir = dirs::config_dir()
             .ok_or_else(|| {
@@ -104,20 +1488,54 @@ impl Config {
             })?
             .join("ggo");
 
-        std::fs::create_dir_all(&config_dir).map_err(|e| {
+        Ok(config_dir.join("config.toml"))
+    }
+
+    /// Create the directory the config file goes in
+    pub fn create_config_dir(config_path: &Path) -> Result<()> {
+        let Some(config_dir) = config_path.parent() else {
+            return Ok(());
+        };
+        std::fs::create_dir_all(config_dir).map_err(|e| {
             GgoError::ConfigError(format!("Failed to create config directory: {}", e))
         })?;
+        Ok(())
+    }
 
-        Ok(config_dir.join("config.toml"))
+    /// Add a glob pattern to the exclude list. Returns false if it was already there.
+    pub fn add_exclude(&mut self, pattern: &str) -> bool {
+        if self.behavior.exclude.iter().any(|p| p == pattern) {
+            return false;
+        }
+        self.behavior.exclude.push(pattern.to_string());
+        true
     }
 
-    /// Save configuration to file
-    #[allow(dead_code)]
-    pub fn save(&self) -> Result<()> {
+    /// Remove a glob pattern from the exclude list. Returns false if it wasn't there.
+    pub fn remove_exclude(&mut self, pattern: &str) -> bool {
+        let before = self.behavior.exclude.len();
+        self.behavior.exclude.retain(|p| p != pattern);
+        self.behavior.exclude.len() != before
+    }
+
+    /// Write the values of `keys` (as named by [`Config::set`]) to the config
+    /// file. The rest of the file, comments included, stays as it is, and
+    /// settings it leaves out keep following the defaults.
+    pub fn save_keys(&self, keys: &[&str]) -> Result<()> {
         let config_path = Self::config_path()?;
-        let content = toml::to_string_pretty(self).map_err(|e| {
-            GgoError::ConfigError(format!("Failed to serialize configuration: {}", e))
-        })?;
+        Self::create_config_dir(&config_path)?;
+
+        let existing = match std::fs::read_to_string(&config_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(GgoError::ConfigError(format!(
+                    "Failed to read configuration file: {}",
+                    e
+                )))
+            }
+        };
+        let content = self.with_keys_written(&existing, keys)?;
 
         std::fs::write(&config_path, content).map_err(|e| {
             GgoError::ConfigError(format!("Failed to write configuration file: {}", e))
@@ -125,6 +1543,75 @@ impl Config {
 
         Ok(())
     }
+
+    /// The config file `existing` with the values of `keys` taken from this
+    /// config: changed in place, added to their table, or removed when unset
+    fn with_keys_written(&self, existing: &str, keys: &[&str]) -> Result<String> {
+        let mut document: toml_edit::DocumentMut = existing
+            .parse()
+            .map_err(|e| GgoError::ConfigError(format!("Failed to parse TOML: {}", e)))?;
+        let current: toml_edit::DocumentMut = toml::to_string(self)
+            .map_err(|e| {
+                GgoError::ConfigError(format!("Failed to serialize configuration: {}", e))
+            })?
+            .parse()
+            .map_err(|e| {
+                GgoError::ConfigError(format!("Failed to serialize configuration: {}", e))
+            })?;
+
+        for key in keys {
+            let path: Vec<&str> = match split_repo_key(key) {
+                Some((repo, field)) => vec!["repos", repo, field],
+                None => key.split('.').collect(),
+            };
+            let (field, tables) = path.split_last().expect("split yields a part");
+
+            let value = tables
+                .iter()
+                .try_fold(current.as_item(), |item, name| item.get(name))
+                .and_then(|table| table.get(field))
+                .and_then(toml_edit::Item::as_value)
+                .cloned();
+
+            let mut table: &mut dyn toml_edit::TableLike = document.as_table_mut();
+            for name in tables {
+                table = table
+                    .entry(name)
+                    .or_insert_with(|| {
+                        let mut table = toml_edit::Table::new();
+                        table.set_implicit(true);
+                        toml_edit::Item::Table(table)
+                    })
+                    .as_table_like_mut()
+                    .ok_or_else(|| {
+                        GgoError::ConfigError(format!(
+                            "Can't save {}: '{}' in the config file is not a table",
+                            key, name
+                        ))
+                    })?;
+            }
+
+            match value {
+                Some(mut value) => match table.get_mut(field) {
+                    // Replaced in place, keeping the comments around it
+                    Some(old) => {
+                        if let Some(old) = old.as_value() {
+                            *value.decor_mut() = old.decor().clone();
+                        }
+                        *old = toml_edit::Item::Value(value);
+                    }
+                    None => {
+                        table.insert(field, toml_edit::Item::Value(value));
+                    }
+                },
+                None => {
+                    table.remove(field);
+                }
+            }
+        }
+
+        Ok(document.to_string())
+    }
 }
 
 #[cfg(test)]
@@ -136,9 +1623,95 @@ mod tests {
         let config = Config::default();
 
         assert_eq!(config.frecency.half_life_days, 7.0);
+        assert_eq!(config.frecency.multiplier, 10.0);
+        assert_eq!(config.frecency.session_minutes, 120);
+        assert_eq!(config.frecency.activity_weight, 0.7);
         assert_eq!(config.behavior.auto_select_threshold, 2.0);
+        assert_eq!(config.behavior.auto_select, AutoSelect::Smart);
+        assert_eq!(config.behavior.auto_select_min_score, 0.0);
         assert!(config.behavior.default_fuzzy);
         assert!(!config.behavior.default_ignore_case);
+        assert!(config.behavior.smart_case);
+        assert_eq!(config.behavior.picker, Picker::Select);
+        assert_eq!(config.display.page_size, 15);
+        assert_eq!(
+            config.display.column_priority,
+            vec![
+                MenuColumn::LastUsed,
+                MenuColumn::Sync,
+                MenuColumn::Score,
+                MenuColumn::Usage
+            ]
+        );
+        assert_eq!(config.history.retention_days, 180);
+        assert_eq!(config.history.passive_window_minutes, 10);
+        assert!(!config.maintenance.auto);
+        assert_eq!(config.maintenance.every, 50);
+        assert!(config.sync.repo.is_empty());
+        assert_eq!(config.sync.dir, "ggo");
+        assert!(config.hooks.enabled);
+        assert!(config.hooks.post_checkout.is_empty());
+        assert_eq!(config.hooks.guard_dirty, GuardAction::Off);
+        assert_eq!(config.hooks.guard_protected, GuardAction::Confirm);
+        assert_eq!(config.hooks.guard_stale, GuardAction::Off);
+        assert_eq!(config.hooks.stale_days, 90);
+        assert!(!config.log.enabled);
+        assert_eq!(config.log.max_size_kb, 1024);
+        assert_eq!(config.log.keep, 3);
+        assert!(config.storage.data_dir_path().is_none());
+        assert_eq!(config.storage.max_records, 10_000);
+        assert_eq!(config.storage.max_age_days, 0);
+        assert_eq!(
+            config.storage.backend,
+            if cfg!(feature = "sqlite") {
+                StorageBackend::Sqlite
+            } else {
+                StorageBackend::Json
+            }
+        );
+    }
+
+    #[test]
+    fn test_commented_defaults_parse_to_defaults() {
+        let content = Config::commented_defaults().unwrap();
+        assert!(content.contains("[frecency]"));
+        assert!(content.contains("# half_life_days = 7.0"));
+
+        let parsed: Config = toml::from_str(&content).unwrap();
+        assert_eq!(
+            toml::to_string(&parsed).unwrap(),
+            toml::to_string(&Config::default()).unwrap()
+        );
+
+        // Uncommenting a line changes just that setting
+        let edited = content.replace("# half_life_days = 7.0", "half_life_days = 3.0");
+        let parsed: Config = toml::from_str(&edited).unwrap();
+        assert_eq!(parsed.frecency.half_life_days, 3.0);
+        assert_eq!(parsed.hooks.stale_days, 90);
+    }
+
+    #[test]
+    fn test_picker_deserialization() {
+        let toml_str = r#"
+            [behavior]
+            picker = "tui"
+        "#;
+
+        let config: Config = toml::from_str(toml_str).expect("Failed to parse");
+        assert_eq!(config.behavior.picker, Picker::Tui);
+
+        let config: Config = toml::from_str("[behavior]\npicker = \"fzf\"").unwrap();
+        assert_eq!(config.behavior.picker, Picker::Fzf);
+        assert!(config.behavior.fzf_command.starts_with("fzf "));
+    }
+
+    #[test]
+    fn test_theme_deserialization() {
+        let config: Config = toml::from_str("[display]\ntheme = \"mono\"").unwrap();
+        assert_eq!(config.display.theme, Theme::Mono);
+
+        let config: Config = toml::from_str("[display]\nsubject_width = 20").unwrap();
+        assert_eq!(config.display.theme, Theme::Default);
     }
 
     #[test]
@@ -218,6 +1791,375 @@ mod tests {
         assert_eq!(loaded.behavior.auto_select_threshold, 3.0);
     }
 
+    #[test]
+    fn test_get_all_keys() {
+        let config = Config::default();
+
+        for key in KEYS {
+            assert!(config.get(key).is_ok(), "key {} should be readable", key);
+        }
+        assert_eq!(config.get("frecency.half_life_days").unwrap(), "7");
+        assert_eq!(config.get("behavior.picker").unwrap(), "select");
+    }
+
+    #[test]
+    fn test_scoring_for_applies_repo_overrides() {
+        let toml_str = r#"
+            [frecency]
+            half_life_days = 14.0
+            multiplier = 5.0
+
+            [repos."/home/me/monorepo"]
+            half_life_days = 2.0
+            auto_select_threshold = 3.0
+
+            [repos."github.com/me/app"]
+            multiplier = 0.0
+        "#;
+        let config: Config = toml::from_str(toml_str).expect("Failed to parse");
+
+        assert_eq!(config.scoring_for("/elsewhere/"), {
+            ScoringParams {
+                half_life_days: 14.0,
+                frecency_multiplier: 5.0,
+                auto_select_threshold: 2.0,
+            }
+        });
+        // Stored path keys end in a slash
+        assert_eq!(
+            config.scoring_for("/home/me/monorepo/"),
+            ScoringParams {
+                half_life_days: 2.0,
+                frecency_multiplier: 5.0,
+                auto_select_threshold: 3.0,
+            }
+        );
+        assert_eq!(
+            config.scoring_for("github.com/me/app").frecency_multiplier,
+            0.0
+        );
+        assert_eq!(
+            Config::default().scoring_for("/r/"),
+            ScoringParams::default()
+        );
+    }
+
+    #[test]
+    fn test_set_and_get_repo_overrides() {
+        let mut config = Config::default();
+        let key = "repos.github.com/me/app.half_life_days";
+
+        assert_eq!(config.get(key).unwrap(), "");
+        config.set(key, "3").unwrap();
+        assert_eq!(config.get(key).unwrap(), "3");
+        assert_eq!(config.scoring_for("github.com/me/app").half_life_days, 3.0);
+        assert_eq!(config.repo_override_keys(), vec![key.to_string()]);
+
+        assert!(config.set(key, "-1").is_err());
+        assert!(config.set("repos.github.com/me/app.bogus", "1").is_err());
+        assert!(config.set("repos..multiplier", "1").is_err());
+
+        // Clearing the last override drops the repository's table
+        config.set(key, "").unwrap();
+        assert!(config.repos.is_empty());
+
+        config.set("frecency.multiplier", "0").unwrap();
+        assert_eq!(config.scoring_for("/r/").frecency_multiplier, 0.0);
+        assert!(config.set("frecency.multiplier", "-2").is_err());
+    }
+
+    #[test]
+    fn test_get_unknown_key() {
+        let config = Config::default();
+        assert!(matches!(
+            config.get("behavior.nope"),
+            Err(GgoError::UnknownConfigKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_ignore_case_for_smart_case() {
+        let mut behavior = BehaviorConfig::default();
+        assert!(behavior.ignore_case_for("auth"));
+        assert!(!behavior.ignore_case_for("Auth"));
+
+        behavior.smart_case = false;
+        assert!(!behavior.ignore_case_for("auth"));
+
+        behavior.default_ignore_case = true;
+        assert!(behavior.ignore_case_for("Auth"));
+    }
+
+    #[test]
+    fn test_with_keys_written_keeps_the_rest_of_the_file() {
+        let existing = "# my settings\n\n[frecency]\n# slower decay\nhalf_life_days = 14.0 # two weeks\n\n[display]\ntheme = \"mono\"\n";
+        let mut config: Config = toml::from_str(existing).unwrap();
+        config.set("frecency.half_life_days", "3").unwrap();
+        config.set("behavior.picker", "tui").unwrap();
+        config.set("repos.~/work/app.multiplier", "2").unwrap();
+
+        let content = config
+            .with_keys_written(
+                existing,
+                &[
+                    "frecency.half_life_days",
+                    "behavior.picker",
+                    "repos.~/work/app.multiplier",
+                ],
+            )
+            .unwrap();
+
+        assert!(content.starts_with("# my settings\n"));
+        assert!(content.contains("# slower decay\nhalf_life_days = 3.0 # two weeks\n"));
+        assert!(content.contains("theme = \"mono\""));
+        // Untouched settings stay out of the file
+        assert!(!content.contains("multiplier = 10"));
+        assert!(!content.contains("page_size"));
+
+        let saved: Config = toml::from_str(&content).unwrap();
+        assert_eq!(saved.frecency.half_life_days, 3.0);
+        assert_eq!(saved.behavior.picker, Picker::Tui);
+        assert_eq!(saved.repos["~/work/app"].multiplier, Some(2.0));
+    }
+
+    #[test]
+    fn test_with_keys_written_removes_unset_values() {
+        let existing = "[repos.\"~/work/app\"]\nmultiplier = 2.0\n";
+        let mut config: Config = toml::from_str(existing).unwrap();
+        config.repos.clear();
+
+        let content = config
+            .with_keys_written(existing, &["repos.~/work/app.multiplier"])
+            .unwrap();
+
+        let saved: Config = toml::from_str(&content).unwrap();
+        assert!(saved
+            .repos
+            .get("~/work/app")
+            .is_none_or(|o| o.multiplier.is_none()));
+    }
+
+    #[test]
+    fn test_set_values() {
+        let mut config = Config::default();
+
+        config.set("frecency.half_life_days", "14").unwrap();
+        config.set("frecency.session_minutes", "45").unwrap();
+        config.set("frecency.activity_weight", "0.25").unwrap();
+        config.set("behavior.auto_select_threshold", "1.5").unwrap();
+        config.set("behavior.default_fuzzy", "false").unwrap();
+        config.set("behavior.default_ignore_case", "true").unwrap();
+        config.set("behavior.smart_case", "false").unwrap();
+        config.set("behavior.skip_current", "true").unwrap();
+        config.set("behavior.auto_select", "never").unwrap();
+        config.set("behavior.auto_select_min_score", "40").unwrap();
+        config.set("behavior.picker", "tui").unwrap();
+        config.set("behavior.fzf_command", "sk").unwrap();
+        config.set("history.retention_days", "0").unwrap();
+        config.set("history.repo_identity", "remote").unwrap();
+        config.set("history.passive_window_minutes", "0").unwrap();
+        config.set("matching.segment_bonus", "0").unwrap();
+        config.set("display.branch_width", "60").unwrap();
+        config.set("display.subject_width", "0").unwrap();
+        config.set("display.page_size", "30").unwrap();
+        config.set("display.theme", "light").unwrap();
+        config
+            .set("display.column_priority", "score, last_used")
+            .unwrap();
+        config.set("matching.acronym_bonus", "40").unwrap();
+        config.set("matching.ticket_bonus", "500").unwrap();
+        config.set("maintenance.auto", "true").unwrap();
+        config.set("maintenance.every", "10").unwrap();
+        config.set("maintenance.max_age_days", "0").unwrap();
+        config.set("sync.repo", "~/dotfiles").unwrap();
+        config.set("sync.dir", "history/ggo").unwrap();
+        config.set("hooks.enabled", "false").unwrap();
+        config.set("hooks.guard_dirty", "abort").unwrap();
+        config.set("hooks.guard_unpushed", "warn").unwrap();
+        config.set("hooks.guard_protected", "off").unwrap();
+        config.set("hooks.guard_stale", "confirm").unwrap();
+        config.set("hooks.stale_days", "30").unwrap();
+        config.set("hooks.protected", "main, release/*").unwrap();
+        config.set("search.roots", "~/src, /work").unwrap();
+        config.set("log.enabled", "true").unwrap();
+        config.set("log.keep", "0").unwrap();
+        config.set("storage.data_dir", "/srv/ggo").unwrap();
+        config.set("storage.backend", "json").unwrap();
+        config.set("storage.max_records", "500").unwrap();
+        config.set("storage.max_age_days", "180").unwrap();
+        config.set("matching.ticket_pattern", "#[0-9]+").unwrap();
+        config
+            .set("behavior.exclude", "release/*, dependabot/*")
+            .unwrap();
+
+        assert_eq!(config.frecency.half_life_days, 14.0);
+        assert_eq!(config.frecency.session_minutes, 45);
+        assert_eq!(config.get("frecency.activity_weight").unwrap(), "0.25");
+        assert_eq!(config.behavior.auto_select_threshold, 1.5);
+        assert!(!config.behavior.default_fuzzy);
+        assert!(config.behavior.default_ignore_case);
+        assert!(!config.behavior.smart_case);
+        assert!(config.behavior.skip_current);
+        assert_eq!(config.behavior.auto_select, AutoSelect::Never);
+        assert_eq!(config.get("behavior.auto_select_min_score").unwrap(), "40");
+        assert_eq!(config.behavior.picker, Picker::Tui);
+        assert_eq!(config.behavior.fzf_command, "sk");
+        assert_eq!(config.history.retention_days, 0);
+        assert_eq!(config.history.repo_identity, RepoIdentity::Remote);
+        assert_eq!(config.history.passive_window_minutes, 0);
+        assert_eq!(config.matching.segment_bonus, 0);
+        assert_eq!(config.display.branch_width, 60);
+        assert_eq!(config.display.subject_width, 0);
+        assert_eq!(config.display.page_size, 30);
+        assert_eq!(config.display.theme, Theme::Light);
+        assert_eq!(
+            config.display.column_priority,
+            vec![MenuColumn::Score, MenuColumn::LastUsed]
+        );
+        assert_eq!(
+            config.get("display.column_priority").unwrap(),
+            "score,last_used"
+        );
+        assert_eq!(config.matching.acronym_bonus, 40);
+        assert_eq!(config.matching.ticket_bonus, 500);
+        assert!(config.maintenance.auto);
+        assert_eq!(config.maintenance.every, 10);
+        assert_eq!(config.maintenance.max_age_days, 0);
+        assert_eq!(config.sync.repo, "~/dotfiles");
+        assert_eq!(config.sync.dir, "history/ggo");
+        assert!(!config.hooks.enabled);
+        assert_eq!(config.hooks.guard_dirty, GuardAction::Abort);
+        assert_eq!(config.hooks.guard_unpushed, GuardAction::Warn);
+        assert_eq!(config.hooks.guard_protected, GuardAction::Off);
+        assert_eq!(config.hooks.guard_stale, GuardAction::Confirm);
+        assert_eq!(config.hooks.stale_days, 30);
+        assert_eq!(config.hooks.protected, vec!["main", "release/*"]);
+        assert_eq!(config.search.roots, vec!["~/src", "/work"]);
+        assert_eq!(config.search_roots()[1], PathBuf::from("/work"));
+        assert!(config.log.enabled);
+        assert_eq!(config.get("log.keep").unwrap(), "0");
+        assert_eq!(
+            config.storage.data_dir_path(),
+            Some(PathBuf::from("/srv/ggo"))
+        );
+        assert_eq!(config.storage.backend, StorageBackend::Json);
+        assert_eq!(config.get("storage.backend").unwrap(), "json");
+        config.set("storage.backend", "encrypted").unwrap();
+        assert_eq!(config.storage.backend, StorageBackend::Encrypted);
+        assert_eq!(config.storage.max_records, 500);
+        assert_eq!(config.get("storage.max_age_days").unwrap(), "180");
+        assert_eq!(config.get("hooks.guard_dirty").unwrap(), "abort");
+        assert_eq!(config.matching.ticket_pattern, "#[0-9]+");
+        assert!(config.matching.bonuses().tickets.is_some());
+        assert_eq!(config.behavior.exclude, vec!["release/*", "dependabot/*"]);
+        assert_eq!(
+            config.get("behavior.exclude").unwrap(),
+            "release/*,dependabot/*"
+        );
+
+        config.set("behavior.exclude", "").unwrap();
+        assert!(config.behavior.exclude.is_empty());
+    }
+
+    #[test]
+    fn test_add_and_remove_exclude() {
+        let mut config = Config::default();
+
+        assert!(config.add_exclude("release/*"));
+        assert!(!config.add_exclude("release/*"));
+        assert_eq!(config.behavior.exclude, vec!["release/*"]);
+
+        assert!(config.remove_exclude("release/*"));
+        assert!(!config.remove_exclude("release/*"));
+        assert!(config.behavior.exclude.is_empty());
+    }
+
+    #[test]
+    fn test_exclude_deserialization() {
+        let toml_str = r#"
+            [behavior]
+            exclude = ["release/*", "dependabot/*"]
+        "#;
+
+        let config: Config = toml::from_str(toml_str).expect("Failed to parse");
+        assert_eq!(config.behavior.exclude, vec!["release/*", "dependabot/*"]);
+    }
+
+    #[test]
+    fn test_offered_branches_with_group() {
+        let mut config = Config::default();
+        config.add_exclude("feature/old*");
+        let branches = vec![
+            "main".to_string(),
+            "feature/api".to_string(),
+            "feature/old-api".to_string(),
+        ];
+
+        assert_eq!(
+            config.behavior.offered_branches(branches.clone()),
+            vec!["main", "feature/api"]
+        );
+
+        // The group narrows the choice further, and exclusions still apply
+        config.behavior.only = Some(vec!["feature/*".to_string()]);
+        assert_eq!(
+            config.behavior.offered_branches(branches),
+            vec!["feature/api"]
+        );
+        assert!(!config.behavior.offers("main"));
+
+        // Runtime only: never written to the config file
+        let saved = toml::to_string(&config).unwrap();
+        assert!(!saved.contains("only"));
+    }
+
+    #[test]
+    fn test_set_rejects_invalid_values() {
+        let mut config = Config::default();
+
+        assert!(config.set("frecency.half_life_days", "0").is_err());
+        assert!(config.set("frecency.half_life_days", "-1").is_err());
+        assert!(config.set("behavior.auto_select_threshold", "abc").is_err());
+        assert!(config.set("behavior.default_fuzzy", "yes").is_err());
+        assert!(config.set("behavior.picker", "fancy").is_err());
+        assert!(config.set("behavior.auto_select", "sometimes").is_err());
+        assert!(config.set("behavior.auto_select_min_score", "-1").is_err());
+        assert!(config.set("display.theme", "neon").is_err());
+        assert!(config
+            .set("display.column_priority", "score,branch")
+            .is_err());
+        assert!(config.set("display.column_priority", "sync,sync").is_err());
+        assert!(config.set("behavior.fzf_command", " ").is_err());
+        assert!(config.set("hooks.guard_dirty", "ask").is_err());
+        assert!(config.set("history.retention_days", "-5").is_err());
+        assert!(config.set("history.passive_window_minutes", "5m").is_err());
+        assert!(config.set("frecency.session_minutes", "2h").is_err());
+        assert!(config.set("frecency.activity_weight", "1.5").is_err());
+        assert!(config.set("frecency.activity_weight", "-0.1").is_err());
+        assert!(config.set("hooks.stale_days", "0").is_err());
+        assert!(config.set("log.max_size_kb", "0").is_err());
+        assert!(config.set("storage.data_dir", "dotfiles/ggo").is_err());
+        assert!(config.set("storage.backend", "postgres").is_err());
+        assert!(config.set("storage.max_records", "-1").is_err());
+        assert!(config.set("storage.max_age_days", "-30").is_err());
+        assert!(config.set("matching.acronym_bonus", "-1").is_err());
+        assert!(config.set("matching.ticket_pattern", "[A-Z").is_err());
+        assert!(config.set("maintenance.every", "0").is_err());
+        assert!(config.set("sync.dir", "../elsewhere").is_err());
+        assert!(config.set("sync.dir", "").is_err());
+        assert!(config.set("history.repo_identity", "url").is_err());
+        assert!(config.set("display.branch_width", "3").is_err());
+        assert!(config.set("display.page_size", "0").is_err());
+        assert!(matches!(
+            config.set("behavior.nope", "1"),
+            Err(GgoError::UnknownConfigKey(_))
+        ));
+
+        // Nothing changed
+        assert_eq!(config.frecency.half_life_days, 7.0);
+        assert!(config.behavior.default_fuzzy);
+    }
+
     #[test]
     fn test_invalid_config_returns_error() {
         let toml_str = r#"
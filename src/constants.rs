@@ -13,16 +13,211 @@ pub mod frecency {
 
     /// One month in seconds (30 days)
     pub const MONTH_SECONDS: i64 = 2592000;
+
+    /// Default half-life for frecency decay, in days
+    pub const DEFAULT_HALF_LIFE_DAYS: f64 = 7.0;
 }
 
 /// Scoring combination constants
 pub mod scoring {
-    /// Multiplier for frecency when combining with fuzzy match scores
-    /// Higher value gives more weight to frecency over fuzzy match quality
+    /// Default multiplier for frecency when combining with fuzzy match scores
+    /// (`frecency.multiplier`). Higher value gives more weight to frecency over
+    /// fuzzy match quality
     pub const FRECENCY_MULTIPLIER: f64 = 10.0;
 
-    // Note: AUTO_SELECT_THRESHOLD moved to config.behavior.auto_select_threshold
-    // for user configurability
+    /// Score added (scaled by transition probability) to branches usually
+    /// checked out next from the current branch, with `--suggest`
+    pub const TRANSITION_BOOST: f64 = 50.0;
+
+    /// Fuzzy score added per matched character that starts a path segment or
+    /// word (after `/`, `-`, `_`)
+    pub const SEGMENT_START_BONUS: i64 = 12;
+
+    /// Fuzzy score added per pattern character when the pattern matches the
+    /// initials of a branch name's segments
+    pub const ACRONYM_BONUS: i64 = 16;
+
+    /// Fuzzy score added per pattern term that names one of the branch's
+    /// ticket IDs exactly (`1234` or `proj-1234` → `feature/PROJ-1234-fix`)
+    pub const TICKET_BONUS: i64 = 200;
+
+    /// Score per pattern term found in a branch's git description, standing in
+    /// for the fuzzy score of a name match (kept well below a typical one)
+    pub const DESCRIPTION_TERM_SCORE: i64 = 30;
+
+    /// Branch count from which fuzzy matching scores branches in parallel;
+    /// below it the threads cost more than they save
+    pub const PARALLEL_MATCH_THRESHOLD: usize = 2_000;
+
+    /// Default regex for ticket IDs in branch names (`JIRA-1234`, `GH-567`)
+    pub const DEFAULT_TICKET_PATTERN: &str = r"[A-Za-z][A-Za-z0-9]*-[0-9]+";
+
+    /// Frecency score at which deleting a branch asks for extra confirmation
+    pub const HIGH_FRECENCY_SCORE: f64 = 5.0;
+
+    /// Default score ratio over the runner-up at which the top match is
+    /// checked out without asking (`behavior.auto_select_threshold`)
+    pub const DEFAULT_AUTO_SELECT_THRESHOLD: f64 = 2.0;
+
+    /// Default score the top match needs on top of the ratio to be checked
+    /// out without asking (`behavior.auto_select_min_score`); 0 has no minimum
+    pub const DEFAULT_AUTO_SELECT_MIN_SCORE: f64 = 0.0;
+
+    /// Default session length in minutes (`frecency.session_minutes`): the
+    /// current session is the checkouts since the last gap at least this long
+    pub const DEFAULT_SESSION_MINUTES: u32 = 120;
+
+    /// Default weight of your own recent commits against frecency in
+    /// 'ggo switch-last-modified' (`frecency.activity_weight`)
+    pub const DEFAULT_ACTIVITY_WEIGHT: f64 = 0.7;
+
+    /// Session weights halve every this fraction of the session length, so
+    /// they fade far faster than lifetime frecency
+    pub const SESSION_HALF_LIFE_FRACTION: f64 = 0.25;
+
+    /// Sessions older than this many session lengths are not looked up; their
+    /// weights have decayed to nothing
+    pub const SESSION_LOOKBACK: i64 = 8;
+}
+
+/// Checkout history stack limits
+pub mod history {
+    /// Maximum number of history entries kept per repository
+    pub const MAX_HISTORY_ENTRIES: usize = 100;
+
+    /// Number of entries shown by `ggo --history`
+    pub const HISTORY_DISPLAY_LIMIT: usize = 20;
+
+    /// Default window in minutes (`history.passive_window_minutes`) within
+    /// which landing on a branch again with plain git isn't counted again
+    pub const DEFAULT_PASSIVE_WINDOW_MINUTES: u32 = 10;
+}
+
+/// Automatic maintenance defaults
+pub mod maintenance {
+    /// Invocations between automatic maintenance runs
+    pub const MAINTENANCE_EVERY: u32 = 50;
+
+    /// Days without use after which automatic maintenance prunes a branch record
+    pub const MAINTENANCE_MAX_AGE_DAYS: i64 = 365;
+}
+
+/// History sync defaults
+pub mod sync {
+    /// Directory inside the sync repository holding one history file per machine
+    pub const DEFAULT_SYNC_DIR: &str = "ggo";
+}
+
+/// Git interaction
+pub mod git {
+    /// First git release with `git switch` (2.23)
+    pub const GIT_SWITCH_MIN_VERSION: (u32, u32) = (2, 23);
+
+    /// Recent commits `ggo --detach` matches against, besides tags
+    pub const DETACH_RECENT_COMMITS: usize = 200;
+
+    /// Unmerged commits per branch `ggo find --unmerged` looks at, newest first
+    pub const FIND_MAX_COMMITS: usize = 1000;
+
+    /// Commits per branch 'ggo switch-last-modified' looks through for your
+    /// own, newest first
+    pub const ACTIVITY_MAX_COMMITS: usize = 200;
+
+    /// A `packed-refs` written this recently may change again without its
+    /// timestamp moving, so the branch list isn't cached from it yet
+    pub const REFS_RACY_WINDOW_SECS: u64 = 2;
+}
+
+/// Interactive branch selection
+pub mod picker {
+    /// Longest list the inline menu shows; bigger ones open the full-screen
+    /// finder, which filters as you type
+    pub const MENU_MAX_BRANCHES: usize = 500;
+
+    /// Default number of branches shown at once in the menus
+    /// (`display.page_size`)
+    pub const DEFAULT_PAGE_SIZE: usize = 15;
+
+    /// Seconds the numbered entries of a 'ggo -l' listing can be checked out
+    /// with 'ggo --nth N'
+    pub const LISTING_TTL_SECS: i64 = 10 * 60;
+
+    /// Terminal widths below which the menu drops its least important
+    /// column, then the next one (`display.column_priority`)
+    pub const DROP_COLUMN_BELOW: [usize; 2] = [100, 70];
+
+    /// Terminal width below which the menu shows plain branch names
+    pub const PLAIN_NAMES_BELOW: usize = 50;
+}
+
+/// Checkout hooks
+pub mod repos {
+    /// Levels below each `search.roots` directory searched for repositories
+    /// (`~/src/github.com/owner/repo` is three)
+    pub const SEARCH_DEPTH: usize = 3;
+}
+
+pub mod hooks {
+    /// File in a repository's root with hooks for that repository
+    pub const REPO_HOOKS_FILE: &str = ".ggo.toml";
+
+    /// Days without a switch after which the stale guard trips
+    pub const DEFAULT_STALE_DAYS: u32 = 90;
+}
+
+/// The invocation log (`[log]`, `--log-file`)
+pub mod log {
+    /// Directory in the data directory holding the log files
+    pub const LOG_DIR: &str = "logs";
+
+    /// Name of the current log file; rotated files get `.1`, `.2`, ... appended
+    pub const LOG_FILE: &str = "ggo.jsonl";
+
+    /// Size in KiB past which the log file is rotated
+    pub const DEFAULT_MAX_SIZE_KB: u64 = 1024;
+
+    /// Rotated log files kept besides the current one
+    pub const DEFAULT_KEEP: u32 = 3;
+
+    /// Most ranked candidates written per invocation
+    pub const MAX_LOGGED_CANDIDATES: usize = 20;
+}
+
+/// `ggo self-update`
+pub mod update {
+    /// GitHub API endpoint describing the latest release
+    pub const LATEST_RELEASE_URL: &str =
+        "https://api.github.com/repos/XavierFabregat/ggo/releases/latest";
+
+    /// Where releases can be downloaded by hand
+    pub const RELEASES_PAGE: &str = "https://github.com/XavierFabregat/ggo/releases";
+
+    /// Suffix of the checksum file published next to each release binary
+    pub const CHECKSUM_SUFFIX: &str = ".sha256";
+
+    /// Seconds a request may take before the update gives up
+    pub const TIMEOUT_SECS: u64 = 60;
+}
+
+/// `ggo serve`
+pub mod server {
+    /// Socket in the data directory listened on without --socket
+    pub const SOCKET_FILE: &str = "ggo.sock";
+}
+
+/// Database contention handling
+pub mod database {
+    /// How long SQLite waits on a locked database before reporting busy
+    pub const BUSY_TIMEOUT_MS: u64 = 2000;
+
+    /// Attempts made for a write that keeps failing with SQLITE_BUSY
+    pub const MAX_WRITE_ATTEMPTS: u32 = 5;
+
+    /// Delay before the first retry; doubled after every failed attempt
+    pub const RETRY_BASE_DELAY_MS: u64 = 25;
+
+    /// Branch records kept before the lowest-frecency ones are trimmed
+    pub const MAX_RECORDS: usize = 10_000;
 }
 
 /// Validation limits
@@ -36,6 +231,27 @@ pub mod validation {
     /// Maximum length for alias names
     pub const MAX_ALIAS_LENGTH: usize = 50;
 
+    /// Maximum length for branch notes
+    pub const MAX_NOTE_LENGTH: usize = 200;
+
     /// Maximum length for repository paths
     pub const MAX_REPO_PATH_LENGTH: usize = 4096;
 }
+
+/// Process exit codes, part of the documented CLI interface for scripts
+pub mod exit {
+    /// Any error without a more specific code
+    pub const FAILURE: i32 = 1;
+
+    /// Nothing matched the pattern (branches, tags, commits, repositories)
+    pub const NO_MATCH: i32 = 2;
+
+    /// Not run inside a git repository
+    pub const NOT_A_REPO: i32 = 3;
+
+    /// The user cancelled a menu or prompt
+    pub const CANCELLED: i32 = 4;
+
+    /// The branch was found but switching to it failed or was refused
+    pub const CHECKOUT_FAILED: i32 = 5;
+}
@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::constants::exit;
+
 /// Custom error types for ggo
 #[derive(Error, Debug)]
 pub enum GgoError {
@@ -12,15 +14,91 @@ pub enum GgoError {
     #[error("No branches match pattern '{0}'\n\nTry:\n  • Using a shorter pattern\n  • Running 'ggo --list \"\"' to see all branches\n  • Using case-insensitive mode with '-i'")]
     NoMatchingBranches(String),
 
+    /// Nothing to check out for the pattern, which could name a new branch
+    #[error("No branches match pattern '{0}'\n\nTry:\n  • Using a shorter pattern\n  • Running 'ggo --list \"\"' to see all branches\n  • Using case-insensitive mode with '-i'\n  • Creating it with 'ggo -c {0}'")]
+    NoBranchToCheckOut(String),
+
     #[error("Failed to checkout branch '{0}': {1}")]
     CheckoutFailed(String, String),
 
+    #[error("No tags or recent commits match '{0}'\n\nTry:\n  • Running 'git tag' to see all tags\n  • A commit id or a revision like 'HEAD~3'")]
+    NoDetachTarget(String),
+
+    #[error("No tags match '{0}'\n\nRun 'git tag' to see all tags.")]
+    NoMatchingTags(String),
+
+    #[error("Can't stack '{0}' on '{1}': '{1}' is '{0}' or stacked on it")]
+    StackCycle(String, String),
+
+    #[error("'{0}' is not stacked on another branch\n\nStack it with 'ggo stack on <parent>'")]
+    NotStacked(String),
+
+    #[error("No branch is stacked on '{0}'\n\nStack one on it with 'ggo stack on {0}'")]
+    StackTop(String),
+
+    #[error("Several branches are stacked on '{0}': {1}\n\nRun in a terminal to pick one, or check one out by name")]
+    StackFork(String, String),
+
+    #[error("No branch has a commit with {0}\n\nTry:\n  • --unmerged to search every commit not yet on the default branch, not only branch tips\n  • A shorter --message or --author")]
+    NoMatchingCommits(String),
+
+    #[error("'{0}' is not a commit in this repository\n\nFetch first if it comes from CI or another clone: git fetch")]
+    UnknownCommit(String),
+
+    #[error("No local branch contains '{0}'\n\nTry:\n  • ggo --detach {0} to check it out with a detached HEAD\n  • Fetching the branch it was committed on")]
+    NoBranchContains(String),
+
+    #[error("No repositories match '{0}'\n\nggo knows the repositories you've switched branches in, and the ones under search.roots:\n  ggo config set search.roots ~/src")]
+    NoMatchingRepos(String),
+
+    #[error("Failed to create branch '{0}': {1}")]
+    CreateBranchFailed(String, String),
+
+    #[error("Not switching to '{0}': {1}\n\nRun again with --force to switch anyway.")]
+    CheckoutBlocked(String, String),
+
+    #[error("Branch '{0}' is already checked out in another worktree: {1}\n\nTry:\n  • cd {1}\n  • ggo --force-detach {0} to check out its commit here with a detached HEAD")]
+    BranchInWorktree(String, String),
+
+    #[error("Branch '{0}' has no worktree\n\nCreate one with 'ggo wt -c {0}'")]
+    NoWorktree(String),
+
+    #[error("Failed to create a worktree for '{0}': {1}")]
+    WorktreeFailed(String, String),
+
+    #[error("Failed to delete branch '{0}': {1}")]
+    DeleteBranchFailed(String, String),
+
+    #[error("'{0}' matches more than one branch: {1}\n\nWithout prompting, only the branch named exactly or the only one containing the pattern is used. Give its full name, or drop -y to pick.")]
+    AmbiguousPattern(String, String),
+
+    #[error("Not deleting '{0}': it is one of your most used branches\n\nRun again without -y to confirm, or with --force to delete it anyway.")]
+    FrequentBranch(String),
+
+    #[error("Branch '{0}' is not fully merged\n\nIf you are sure you want to delete it, run 'ggo delete -D {0}'.")]
+    BranchNotMerged(String),
+
     #[error("Invalid branch name: {0}\n\n{1}")]
     InvalidBranchName(String, String),
 
     #[error("Invalid pattern: {0}\n\n{1}")]
     InvalidPattern(String, String),
 
+    #[error("Invalid --format '{0}': {1}\n\nWith --list it takes a template such as '{{name}}\\t{{score}}\\t{{last_used}}'; placeholders include {{switches}}, {{aliases}}, {{upstream}} and {{subject}}.\nWith --stats it takes 'json' or 'csv'.")]
+    InvalidFormat(String, String),
+
+    #[error("Invalid alias name: {0}\n\n{1}")]
+    InvalidAliasName(String, String),
+
+    #[error("Invalid group name '{0}': {1}")]
+    InvalidGroupName(String, String),
+
+    #[error("Group '{0}' not found in this repository\n\nTry:\n  • Running 'ggo group' to see all groups\n  • Creating it with 'ggo group add {0} <branch>...'")]
+    GroupNotFound(String),
+
+    #[error("Invalid note: {0}")]
+    InvalidNote(String),
+
     #[error("Invalid repository path: {0}\n\n{1}")]
     InvalidRepoPath(String, String),
 
@@ -30,32 +108,154 @@ pub enum GgoError {
     #[error("Configuration error: {0}\n\nCheck your config file at ~/.config/ggo/config.toml")]
     ConfigError(String),
 
+    #[error("Unknown config key '{0}'\n\nRun 'ggo config list' to see available keys.")]
+    UnknownConfigKey(String),
+
+    #[error("Invalid value '{1}' for config key '{0}': {2}")]
+    InvalidConfigValue(String, String, String),
+
     #[error("No previous branch found\n\nYou need to switch branches at least once before using 'ggo -'")]
     NoPreviousBranch,
 
+    #[error("No branch at position -{0} in checkout history\n\nRun 'ggo --history' to see available entries.")]
+    NoHistoryEntry(usize),
+
+    #[error("Nothing to undo: {0}")]
+    NothingToUndo(String),
+
+    #[error("Not on a branch: HEAD is detached at {0}\n\nSwitch to a branch with 'ggo <pattern>' (and back here with 'ggo -').")]
+    DetachedHead(String),
+
+    #[error("This is a bare repository: there is no working tree to check branches out in\n\nListing, stats and history work here. To work on a branch:\n  • Open it in a worktree: ggo wt <pattern>\n  • Or run ggo in a clone")]
+    BareRepository,
+
     #[error("User cancelled operation")]
     UserCancelled,
 
-    #[allow(dead_code)]
+    /// With the command's way of running without prompts, such as
+    /// [`PICK_TOP_MATCH_HINT`]
+    #[error("Interactive selection needs a terminal\n\nTry:\n  • Running ggo from an interactive shell\n  • {0}")]
+    NotATerminal(&'static str),
+
+    #[error("Couldn't run the picker '{command}': {reason}\n\nTry:\n  • Installing fzf (https://github.com/junegunn/fzf) or skim\n  • Pointing ggo at it: ggo config set behavior.fzf_command 'sk --delimiter \"\\t\" --nth 1'")]
+    PickerFailed { command: String, reason: String },
+
     #[error(
         "Alias '{0}' not found in this repository\n\nRun 'ggo alias --list' to see all aliases."
     )]
     AliasNotFound(String),
 
+    #[error(
+        "Alias '{0}' already exists in this repository\n\nRemove it first: ggo alias --remove {0}"
+    )]
+    AliasExists(String),
+
+    #[error("Can't import aliases: {0}\n\nAlias files are TOML with an [aliases] table of name = \"branch\", as written by 'ggo alias export'.")]
+    InvalidAliasFile(String),
+
+    #[error("Invalid alias template '{0}': {1}\n\nTemplates mark each argument with '{{}}', e.g. ggo alias rel \"release/{{}}\" for 'ggo rel 1.42'.")]
+    InvalidAliasTemplate(String, String),
+
+    #[error("Alias '{alias}' → '{template}' takes {expected} argument(s), but {given} were given\n\nExample: ggo {alias} {}", vec!["<value>"; *expected].join(" "))]
+    AliasArguments {
+        alias: String,
+        template: String,
+        expected: usize,
+        given: usize,
+    },
+
+    #[error("Can't build a web URL for remote '{0}'\n\nSupported forges:\n  • GitHub\n  • GitLab\n  • Bitbucket")]
+    UnsupportedForge(String),
+
+    #[error("Pull request #{0} not found on 'origin'\n\nCheck the number, or fetch it yourself if the forge doesn't publish pull request refs (Bitbucket).")]
+    PullRequestNotFound(u64),
+
+    #[error("Can't import history: {0}\n\nImports take the JSON or CSV written by 'ggo export'.")]
+    InvalidImport(String),
+
+    #[error("History sync is not set up\n\nPoint it at a git remote or a local clone (e.g. your dotfiles):\n  ggo config set sync.repo git@github.com:you/ggo-history.git")]
+    SyncNotConfigured,
+
+    #[error("The history database uses schema v{found}, but this ggo only knows up to v{supported}\n\nIt was upgraded by a newer ggo. Either:\n  • Upgrade ggo again\n  • Restore the backup taken before the upgrade: ggo cleanup --restore-backup")]
+    SchemaTooNew { found: i32, supported: i32 },
+
+    #[error("This ggo was built without the '{0}' storage backend\n\nEither:\n  • Keep the history in a JSON file: ggo config set storage.backend json\n  • Rebuild with the '{0}' feature")]
+    BackendUnavailable(String),
+
+    #[error("The history encryption key is not available: {0}\n\nEither:\n  • Unlock the keychain holding it (service 'ggo', account 'history-key')\n  • Set GGO_STORAGE_KEY to the key (64 hex digits)")]
+    EncryptionKeyUnavailable(String),
+
+    #[error("The {0} backend already holds history\n\nRun again with --force to replace it.")]
+    StorageNotEmpty(String),
+
+    #[error("Can't tell which commits are yours: git's user.email is not set\n\nSet it with 'git config --global user.email you@example.com'.")]
+    UnknownAuthor,
+
+    #[error("No branch has recent commits by {0}\n\nCheck that git's user.email is the address you commit with.")]
+    NoOwnCommits(String),
+
+    #[error("No recent branch listing in this repository\n\nRun 'ggo -l <pattern>' first, then 'ggo --nth N' to check out entry N.")]
+    NoRecentListing,
+
+    #[error("The last listing has {1} branches, so there is no entry {0}")]
+    NoListingEntry(usize, usize),
+
+    #[error("Another 'ggo serve' is listening on {0}\n\nStop it, or pass another --socket.")]
+    ServerRunning(String),
+
     #[error("Unsupported shell: '{0}'\n\nSupported shells:\n  • bash\n  • zsh\n  • fish\n  • powershell\n  • elvish\n\nExample: ggo --generate-completion bash")]
     InvalidShell(String),
 
+    #[error("Self-update failed: {0}\n\nDownload the release for your platform from https://github.com/XavierFabregat/ggo/releases instead.")]
+    UpdateFailed(String),
+
     #[error("{0}")]
     Other(String),
 }
 
+impl GgoError {
+    /// The process exit code for this error (see [`crate::constants::exit`])
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            GgoError::NoMatchingBranches(_)
+            | GgoError::NoBranchToCheckOut(_)
+            | GgoError::BranchNotFound(_)
+            | GgoError::NoDetachTarget(_)
+            | GgoError::NoMatchingTags(_)
+            | GgoError::NoMatchingCommits(_)
+            | GgoError::NoBranchContains(_)
+            | GgoError::NoMatchingRepos(_)
+            | GgoError::NoOwnCommits(_)
+            | GgoError::NoRecentListing
+            | GgoError::NoListingEntry(..) => exit::NO_MATCH,
+            GgoError::NotGitRepository => exit::NOT_A_REPO,
+            GgoError::UserCancelled => exit::CANCELLED,
+            GgoError::CheckoutFailed(..)
+            | GgoError::CheckoutBlocked(..)
+            | GgoError::BranchInWorktree(..) => exit::CHECKOUT_FAILED,
+            _ => exit::FAILURE,
+        }
+    }
+
+    /// The message without the hints that follow it
+    pub fn summary(&self) -> String {
+        let message = self.to_string();
+        match message.split_once("\n\n") {
+            Some((summary, _hints)) => summary.to_string(),
+            None => message,
+        }
+    }
+}
+
 // Implement conversions from other error types
+#[cfg(feature = "sqlite")]
 impl From<rusqlite::Error> for GgoError {
     fn from(err: rusqlite::Error) -> Self {
         GgoError::DatabaseError(err.to_string())
     }
 }
 
+#[cfg(feature = "libgit2")]
 impl From<git2::Error> for GgoError {
     fn from(err: git2::Error) -> Self {
         match err.code() {
@@ -89,6 +289,9 @@ impl From<anyhow::Error> for GgoError {
 
 pub type Result<T> = std::result::Result<T, GgoError>;
 
+/// [`GgoError::NotATerminal`] hint for the menus a checkout opens
+pub const PICK_TOP_MATCH_HINT: &str = "Dropping --interactive/--tui/--fzf to let ggo pick the top match\n  • Using 'ggo -p <pattern>' in scripts";
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -117,6 +320,13 @@ mod tests {
         assert!(msg.contains("No branches match pattern 'xyz'"));
         assert!(msg.contains("Try:"));
         assert!(msg.contains("shorter pattern"));
+        // Creating a branch is only suggested when checking out
+        assert!(!msg.contains("ggo -c"));
+
+        let err = GgoError::NoBranchToCheckOut("xyz".to_string());
+        let msg = err.to_string();
+        assert!(msg.contains("No branches match pattern 'xyz'"));
+        assert!(msg.contains("ggo -c xyz"));
     }
 
     #[test]
@@ -127,6 +337,35 @@ mod tests {
         assert!(msg.contains("uncommitted changes"));
     }
 
+    #[test]
+    fn test_create_branch_failed_error() {
+        let err =
+            GgoError::CreateBranchFailed("feature/x".to_string(), "already exists".to_string());
+        let msg = err.to_string();
+        assert!(msg.contains("Failed to create branch 'feature/x'"));
+        assert!(msg.contains("already exists"));
+    }
+
+    #[test]
+    fn test_branch_in_worktree_error() {
+        let err = GgoError::BranchInWorktree("feature/x".to_string(), "/work/x".to_string());
+        let msg = err.to_string();
+        assert!(msg.contains("already checked out in another worktree: /work/x"));
+        assert!(msg.contains("cd /work/x"));
+        assert!(msg.contains("--force-detach"));
+    }
+
+    #[test]
+    fn test_checkout_blocked_error() {
+        let err = GgoError::CheckoutBlocked(
+            "main".to_string(),
+            "you have uncommitted changes".to_string(),
+        );
+        let msg = err.to_string();
+        assert!(msg.contains("Not switching to 'main': you have uncommitted changes"));
+        assert!(msg.contains("--force"));
+    }
+
     #[test]
     fn test_invalid_branch_name_error() {
         let err = GgoError::InvalidBranchName(
@@ -162,6 +401,55 @@ mod tests {
         assert!(msg.contains("ggo -"));
     }
 
+    #[test]
+    fn test_unattended_delete_errors() {
+        let err = GgoError::AmbiguousPattern("auth".to_string(), "a-u-t-h, auth-api".to_string());
+        let msg = err.to_string();
+        assert!(msg.contains("'auth' matches more than one branch: a-u-t-h, auth-api"));
+        assert!(msg.contains("drop -y"));
+
+        let err = GgoError::FrequentBranch("main".to_string());
+        assert!(err.to_string().contains("most used"));
+        assert!(err.to_string().contains("--force"));
+    }
+
+    #[test]
+    fn test_branch_not_merged_error() {
+        let err = GgoError::BranchNotMerged("feature/wip".to_string());
+        let msg = err.to_string();
+        assert!(msg.contains("not fully merged"));
+        assert!(msg.contains("ggo delete -D feature/wip"));
+    }
+
+    #[test]
+    fn test_unknown_config_key_error() {
+        let err = GgoError::UnknownConfigKey("frecency.foo".to_string());
+        let msg = err.to_string();
+        assert!(msg.contains("frecency.foo"));
+        assert!(msg.contains("ggo config list"));
+    }
+
+    #[test]
+    fn test_invalid_config_value_error() {
+        let err = GgoError::InvalidConfigValue(
+            "behavior.picker".to_string(),
+            "fancy".to_string(),
+            "expected 'select' or 'tui'".to_string(),
+        );
+        let msg = err.to_string();
+        assert!(msg.contains("'fancy'"));
+        assert!(msg.contains("behavior.picker"));
+        assert!(msg.contains("expected 'select' or 'tui'"));
+    }
+
+    #[test]
+    fn test_no_history_entry_error() {
+        let err = GgoError::NoHistoryEntry(3);
+        let msg = err.to_string();
+        assert!(msg.contains("position -3"));
+        assert!(msg.contains("ggo --history"));
+    }
+
     #[test]
     fn test_user_cancelled_error() {
         let err = GgoError::UserCancelled;
@@ -169,6 +457,27 @@ mod tests {
         assert!(msg.contains("User cancelled"));
     }
 
+    #[test]
+    fn test_not_a_terminal_error() {
+        let msg = GgoError::NotATerminal(PICK_TOP_MATCH_HINT).to_string();
+        assert!(msg.contains("needs a terminal"));
+        assert!(msg.contains("ggo -p"));
+
+        let msg = GgoError::NotATerminal("Passing -y").to_string();
+        assert!(msg.ends_with("  • Passing -y"));
+    }
+
+    #[test]
+    fn test_picker_failed_error() {
+        let err = GgoError::PickerFailed {
+            command: "fzf".to_string(),
+            reason: "command not found".to_string(),
+        };
+        let msg = err.to_string();
+        assert!(msg.contains("'fzf'"));
+        assert!(msg.contains("behavior.fzf_command"));
+    }
+
     #[test]
     fn test_alias_not_found_error() {
         let err = GgoError::AliasNotFound("m".to_string());
@@ -177,6 +486,155 @@ mod tests {
         assert!(msg.contains("ggo alias --list"));
     }
 
+    #[test]
+    fn test_group_not_found_error() {
+        let err = GgoError::GroupNotFound("backend".to_string());
+        let msg = err.to_string();
+        assert!(msg.contains("Group 'backend' not found"));
+        assert!(msg.contains("ggo group add backend"));
+    }
+
+    #[test]
+    fn test_unsupported_forge_error() {
+        let err = GgoError::UnsupportedForge("git@example.com:o/r.git".to_string());
+        let msg = err.to_string();
+        assert!(msg.contains("git@example.com:o/r.git"));
+        assert!(msg.contains("GitLab"));
+    }
+
+    #[test]
+    fn test_alias_file_errors() {
+        let err = GgoError::AliasExists("m".to_string());
+        assert!(err.to_string().contains("ggo alias --remove m"));
+
+        let err = GgoError::InvalidAliasFile("expected a table".to_string());
+        assert!(err.summary().contains("expected a table"));
+        assert!(err.to_string().contains("ggo alias export"));
+    }
+
+    #[test]
+    fn test_alias_template_errors() {
+        let err = GgoError::InvalidAliasTemplate(
+            "release/{v}".to_string(),
+            "braces are only allowed as '{}'".to_string(),
+        );
+        assert!(err.to_string().contains("ggo alias rel \"release/{}\""));
+
+        let err = GgoError::AliasArguments {
+            alias: "fix".to_string(),
+            template: "{}/fix-{}".to_string(),
+            expected: 2,
+            given: 0,
+        };
+        let msg = err.to_string();
+        assert!(msg.contains("takes 2 argument(s), but 0 were given"));
+        assert!(msg.contains("ggo fix <value> <value>"));
+    }
+
+    #[test]
+    fn test_detached_head_error() {
+        let err = GgoError::DetachedHead("abc1234".to_string());
+        assert_eq!(
+            err.summary(),
+            "Not on a branch: HEAD is detached at abc1234"
+        );
+        assert!(err.to_string().contains("ggo -"));
+    }
+
+    #[test]
+    fn test_invalid_import_error() {
+        let err = GgoError::InvalidImport("line 3: expected 6 columns".to_string());
+        let msg = err.to_string();
+        assert!(msg.contains("line 3"));
+        assert!(msg.contains("ggo export"));
+    }
+
+    #[test]
+    fn test_sync_not_configured_error() {
+        let msg = GgoError::SyncNotConfigured.to_string();
+        assert!(msg.contains("sync.repo"));
+    }
+
+    #[test]
+    fn test_schema_too_new_error() {
+        let msg = GgoError::SchemaTooNew {
+            found: 9,
+            supported: 7,
+        }
+        .to_string();
+        assert!(msg.contains("v9"));
+        assert!(msg.contains("v7"));
+        assert!(msg.contains("--restore-backup"));
+    }
+
+    #[test]
+    fn test_own_commit_errors() {
+        let msg = GgoError::UnknownAuthor.to_string();
+        assert!(msg.contains("user.email is not set"));
+        assert!(msg.contains("git config --global user.email"));
+
+        let err = GgoError::NoOwnCommits("me@example.com".to_string());
+        assert_eq!(
+            err.summary(),
+            "No branch has recent commits by me@example.com"
+        );
+        assert_eq!(err.exit_code(), exit::NO_MATCH);
+    }
+
+    #[test]
+    fn test_listing_errors() {
+        let err = GgoError::NoRecentListing;
+        assert!(err.to_string().contains("ggo -l <pattern>"));
+        assert_eq!(err.exit_code(), exit::NO_MATCH);
+
+        let err = GgoError::NoListingEntry(7, 3);
+        assert_eq!(
+            err.to_string(),
+            "The last listing has 3 branches, so there is no entry 7"
+        );
+        assert_eq!(err.exit_code(), exit::NO_MATCH);
+    }
+
+    #[test]
+    fn test_server_running_error() {
+        let err = GgoError::ServerRunning("/tmp/ggo.sock".to_string());
+        let msg = err.to_string();
+        assert!(msg.contains("listening on /tmp/ggo.sock"));
+        assert!(msg.contains("--socket"));
+    }
+
+    #[test]
+    fn test_encryption_key_unavailable_error() {
+        let err = GgoError::EncryptionKeyUnavailable("no keychain".to_string());
+        assert_eq!(
+            err.summary(),
+            "The history encryption key is not available: no keychain"
+        );
+        assert!(err.to_string().contains("GGO_STORAGE_KEY"));
+    }
+
+    #[test]
+    fn test_backend_unavailable_error() {
+        let err = GgoError::BackendUnavailable("sqlite".to_string());
+        assert_eq!(
+            err.summary(),
+            "This ggo was built without the 'sqlite' storage backend"
+        );
+        assert!(err.to_string().contains("storage.backend json"));
+    }
+
+    #[test]
+    fn test_nothing_to_undo_error() {
+        let err = GgoError::NothingToUndo("no recorded switches".to_string());
+        assert_eq!(err.to_string(), "Nothing to undo: no recorded switches");
+    }
+
+    #[test]
+    fn test_pull_request_not_found_error() {
+        let err = GgoError::PullRequestNotFound(42);
+        assert!(err.to_string().contains("#42"));
+    }
+
     #[test]
     fn test_invalid_shell_error() {
         let err = GgoError::InvalidShell("invalid".to_string());
@@ -188,12 +646,56 @@ mod tests {
     }
 
     #[test]
+    fn test_invalid_format_error() {
+        let err =
+            GgoError::InvalidFormat("{branch}".to_string(), "unknown placeholder".to_string());
+        let msg = err.to_string();
+        assert!(msg.contains("Invalid --format '{branch}': unknown placeholder"));
+        assert!(msg.contains(r"'{name}\t{score}\t{last_used}'"));
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(
+            GgoError::NoMatchingBranches("xyz".to_string()).exit_code(),
+            2
+        );
+        assert_eq!(
+            GgoError::NoBranchToCheckOut("xyz".to_string()).exit_code(),
+            2
+        );
+        assert_eq!(GgoError::BranchNotFound("gone".to_string()).exit_code(), 2);
+        assert_eq!(GgoError::NotGitRepository.exit_code(), 3);
+        assert_eq!(GgoError::UserCancelled.exit_code(), 4);
+        assert_eq!(
+            GgoError::CheckoutFailed("main".to_string(), "conflict".to_string()).exit_code(),
+            5
+        );
+        assert_eq!(
+            GgoError::CheckoutBlocked("main".to_string(), "dirty".to_string()).exit_code(),
+            5
+        );
+        assert_eq!(GgoError::Other("boom".to_string()).exit_code(), 1);
+    }
+
+    #[test]
+    fn test_summary_drops_hints() {
+        let err = GgoError::NoMatchingBranches("xyz".to_string());
+        assert_eq!(err.summary(), "No branches match pattern 'xyz'");
+
+        let err = GgoError::CheckoutFailed("main".to_string(), "conflict".to_string());
+        assert_eq!(err.summary(), err.to_string());
+    }
+
+    #[test]
+    #[cfg(feature = "sqlite")]
     fn test_from_rusqlite_error() {
         let sqlite_err = rusqlite::Error::InvalidQuery;
         let ggo_err: GgoError = sqlite_err.into();
         assert!(matches!(ggo_err, GgoError::DatabaseError(_)));
     }
 
+    #[cfg(feature = "libgit2")]
     #[test]
     fn test_from_git2_not_found() {
         let git_err = git2::Error::from_str("repository not found");
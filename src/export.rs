@@ -0,0 +1,413 @@
+//! Portable form of the usage history, for backups and for moving frecency
+//! between machines (`ggo export` / `ggo import`), one repository's aliases
+//! (`ggo alias export` / `ggo alias import`), and the scored branch table of
+//! `ggo --stats --format`.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{GgoError, Result};
+
+/// Version of the export format, bumped on incompatible changes
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Column header of the CSV form
+const CSV_HEADER: &str = "table,repo_path,branch_name,alias,switch_count,timestamp";
+
+/// Column header of the CSV form of the scored branch table
+const SCORES_CSV_HEADER: &str = "repo_path,branch_name,switch_count,last_used,frecency_score";
+
+/// Everything `ggo export` writes: usage records, aliases and previous branches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryExport {
+    pub version: u32,
+    #[serde(default)]
+    pub branches: Vec<ExportedBranch>,
+    #[serde(default)]
+    pub aliases: Vec<ExportedAlias>,
+    #[serde(default)]
+    pub previous_branches: Vec<ExportedPreviousBranch>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedBranch {
+    pub repo_path: String,
+    pub branch_name: String,
+    pub switch_count: i64,
+    pub last_used: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedAlias {
+    pub repo_path: String,
+    pub alias: String,
+    pub branch_name: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedPreviousBranch {
+    pub repo_path: String,
+    pub branch_name: String,
+    pub updated_at: i64,
+}
+
+/// One repository's aliases as TOML, for sharing a team's standard aliases
+/// or moving them to another machine:
+///
+/// ```toml
+/// [aliases]
+/// m = "main"
+/// rel = "release/1.42"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AliasFile {
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+}
+
+impl AliasFile {
+    pub fn render(&self) -> Result<String> {
+        toml::to_string(self)
+            .map_err(|e| GgoError::Other(format!("Failed to serialize aliases: {}", e)))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        toml::from_str(content).map_err(|e| GgoError::InvalidAliasFile(e.to_string()))
+    }
+}
+
+/// A usage record with its frecency score, one row of `ggo --stats --format`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScoredRecord {
+    pub repo_path: String,
+    pub branch_name: String,
+    pub switch_count: i64,
+    pub last_used: i64,
+    pub frecency_score: f64,
+}
+
+/// Serialization of an export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    /// The format named on the command line (`json` or `csv`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+}
+
+impl Default for HistoryExport {
+    fn default() -> Self {
+        Self {
+            version: EXPORT_VERSION,
+            branches: Vec::new(),
+            aliases: Vec::new(),
+            previous_branches: Vec::new(),
+        }
+    }
+}
+
+impl HistoryExport {
+    /// Number of rows across all tables
+    pub fn len(&self) -> usize {
+        self.branches.len() + self.aliases.len() + self.previous_branches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Serialize in the given format
+    pub fn render(&self, format: ExportFormat) -> Result<String> {
+        match format {
+            ExportFormat::Json => serde_json::to_string_pretty(self)
+                .map(|json| json + "\n")
+                .map_err(|e| GgoError::Other(format!("Failed to serialize export: {}", e))),
+            ExportFormat::Csv => Ok(self.to_csv()),
+        }
+    }
+
+    /// One row per record, with a `table` column saying which kind it is
+    fn to_csv(&self) -> String {
+        let mut out = String::from(CSV_HEADER);
+        out.push('\n');
+
+        let mut row = |fields: [&str; 6]| out.push_str(&csv_row(&fields));
+
+        for b in &self.branches {
+            row([
+                "branch",
+                &b.repo_path,
+                &b.branch_name,
+                "",
+                &b.switch_count.to_string(),
+                &b.last_used.to_string(),
+            ]);
+        }
+        for a in &self.aliases {
+            row([
+                "alias",
+                &a.repo_path,
+                &a.branch_name,
+                &a.alias,
+                "",
+                &a.created_at.to_string(),
+            ]);
+        }
+        for p in &self.previous_branches {
+            row([
+                "previous_branch",
+                &p.repo_path,
+                &p.branch_name,
+                "",
+                "",
+                &p.updated_at.to_string(),
+            ]);
+        }
+
+        out
+    }
+
+    /// Parse an export in either format (JSON is recognised by its leading `{`)
+    pub fn parse(content: &str) -> Result<Self> {
+        let trimmed = content.trim_start();
+        if trimmed.starts_with('{') {
+            let export: Self = serde_json::from_str(trimmed)
+                .map_err(|e| GgoError::InvalidImport(e.to_string()))?;
+            if export.version > EXPORT_VERSION {
+                return Err(GgoError::InvalidImport(format!(
+                    "export format version {} is newer than this ggo supports ({})",
+                    export.version, EXPORT_VERSION
+                )));
+            }
+            return Ok(export);
+        }
+
+        Self::from_csv(trimmed)
+    }
+
+    fn from_csv(content: &str) -> Result<Self> {
+        let mut lines = content.lines().enumerate();
+        match lines.next() {
+            Some((_, header)) if header.trim() == CSV_HEADER => {}
+            _ => {
+                return Err(GgoError::InvalidImport(format!(
+                    "expected JSON or CSV starting with '{}'",
+                    CSV_HEADER
+                )))
+            }
+        }
+
+        let mut export = Self::default();
+
+        for (index, line) in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let invalid =
+                |reason: &str| GgoError::InvalidImport(format!("line {}: {}", index + 1, reason));
+
+            let fields = parse_csv_line(line).ok_or_else(|| invalid("unbalanced quotes"))?;
+            let [table, repo_path, branch_name, alias, switch_count, timestamp]: [String; 6] =
+                fields
+                    .try_into()
+                    .map_err(|_| invalid("expected 6 columns"))?;
+            let timestamp: i64 = timestamp
+                .parse()
+                .map_err(|_| invalid("timestamp is not a number"))?;
+
+            match table.as_str() {
+                "branch" => export.branches.push(ExportedBranch {
+                    repo_path,
+                    branch_name,
+                    switch_count: switch_count
+                        .parse()
+                        .map_err(|_| invalid("switch_count is not a number"))?,
+                    last_used: timestamp,
+                }),
+                "alias" => export.aliases.push(ExportedAlias {
+                    repo_path,
+                    alias,
+                    branch_name,
+                    created_at: timestamp,
+                }),
+                "previous_branch" => export.previous_branches.push(ExportedPreviousBranch {
+                    repo_path,
+                    branch_name,
+                    updated_at: timestamp,
+                }),
+                other => return Err(invalid(&format!("unknown table '{}'", other))),
+            }
+        }
+
+        Ok(export)
+    }
+}
+
+/// Serialize the scored branch table in the given format
+pub fn render_scores(rows: &[ScoredRecord], format: ExportFormat) -> Result<String> {
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(rows)
+            .map(|json| json + "\n")
+            .map_err(|e| GgoError::Other(format!("Failed to serialize scores: {}", e))),
+        ExportFormat::Csv => {
+            let mut out = String::from(SCORES_CSV_HEADER);
+            out.push('\n');
+            for r in rows {
+                out.push_str(&csv_row(&[
+                    &r.repo_path,
+                    &r.branch_name,
+                    &r.switch_count.to_string(),
+                    &r.last_used.to_string(),
+                    &format!("{:.4}", r.frecency_score),
+                ]));
+            }
+            Ok(out)
+        }
+    }
+}
+
+/// One CSV line (with its line break) from unquoted fields
+fn csv_row(fields: &[&str]) -> String {
+    let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+    fields.join(",") + "\n"
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Split a CSV line into fields, undoing [`csv_field`]'s quoting.
+/// None if a quoted field is not closed.
+fn parse_csv_line(line: &str) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+
+    if quoted {
+        return None;
+    }
+    fields.push(field);
+    Some(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> HistoryExport {
+        HistoryExport {
+            version: EXPORT_VERSION,
+            branches: vec![ExportedBranch {
+                repo_path: "/home/me/app/".to_string(),
+                branch_name: "feature/a,b \"quoted\"".to_string(),
+                switch_count: 3,
+                last_used: 1_700_000_000,
+            }],
+            aliases: vec![ExportedAlias {
+                repo_path: "github.com/o/r".to_string(),
+                alias: "m".to_string(),
+                branch_name: "main".to_string(),
+                created_at: 1_690_000_000,
+            }],
+            previous_branches: vec![ExportedPreviousBranch {
+                repo_path: "/home/me/app/".to_string(),
+                branch_name: "main".to_string(),
+                updated_at: 1_700_000_001,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_round_trip_json_and_csv() {
+        let export = sample();
+        for format in [ExportFormat::Json, ExportFormat::Csv] {
+            let rendered = export.render(format).unwrap();
+            assert_eq!(HistoryExport::parse(&rendered).unwrap(), export);
+        }
+        assert_eq!(export.len(), 3);
+    }
+
+    #[test]
+    fn test_parse_rejects_garbage() {
+        assert!(matches!(
+            HistoryExport::parse("hello"),
+            Err(GgoError::InvalidImport(_))
+        ));
+        assert!(HistoryExport::parse(&format!("{}\nbranch,/r/,x,,nope,1\n", CSV_HEADER)).is_err());
+        assert!(HistoryExport::parse(&format!("{}\nbranch,\"/r/,x\n", CSV_HEADER)).is_err());
+        assert!(HistoryExport::parse(r#"{"version": 99}"#).is_err());
+    }
+
+    #[test]
+    fn test_alias_file_round_trip() {
+        let mut file = AliasFile::default();
+        file.aliases.insert("m".to_string(), "main".to_string());
+        file.aliases
+            .insert("rel".to_string(), "release/1.42".to_string());
+
+        let toml = file.render().unwrap();
+        assert!(toml.starts_with("[aliases]"));
+        assert_eq!(AliasFile::parse(&toml).unwrap(), file);
+
+        assert_eq!(AliasFile::parse("").unwrap(), AliasFile::default());
+        assert!(AliasFile::parse("[aliases]\nm = 3").is_err());
+    }
+
+    #[test]
+    fn test_render_scores() {
+        let rows = vec![ScoredRecord {
+            repo_path: "/home/me/app/".to_string(),
+            branch_name: "fix,comma".to_string(),
+            switch_count: 4,
+            last_used: 1_700_000_000,
+            frecency_score: 2.5,
+        }];
+
+        let csv = render_scores(&rows, ExportFormat::Csv).unwrap();
+        assert_eq!(
+            csv,
+            format!(
+                "{}\n/home/me/app/,\"fix,comma\",4,1700000000,2.5000\n",
+                SCORES_CSV_HEADER
+            )
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render_scores(&rows, ExportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json[0]["branch_name"], "fix,comma");
+        assert_eq!(json[0]["frecency_score"], 2.5);
+
+        assert_eq!(ExportFormat::from_name("csv"), Some(ExportFormat::Csv));
+        assert_eq!(ExportFormat::from_name("xml"), None);
+    }
+}
@@ -0,0 +1,213 @@
+//! Web URLs for repositories hosted on GitHub, GitLab and Bitbucket.
+
+use crate::git::normalize_remote_url;
+
+/// A code hosting service ggo knows the URL layout of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Forge {
+    GitHub,
+    GitLab,
+    Bitbucket,
+}
+
+impl Forge {
+    /// Ref the forge publishes a pull/merge request's head commit under.
+    /// Bitbucket doesn't expose one.
+    pub fn pull_request_ref(self, number: u64) -> Option<String> {
+        match self {
+            Forge::GitHub => Some(format!("refs/pull/{}/head", number)),
+            Forge::GitLab => Some(format!("refs/merge-requests/{}/head", number)),
+            Forge::Bitbucket => None,
+        }
+    }
+}
+
+/// Refs pull request `number` may live under on a remote. For hosts ggo
+/// doesn't recognise (mirrors, self-hosted instances under another name)
+/// both the GitHub and GitLab layouts are candidates.
+pub fn pull_request_refs(forge: Option<Forge>, number: u64) -> Vec<String> {
+    match forge {
+        Some(forge) => forge.pull_request_ref(number).into_iter().collect(),
+        None => [Forge::GitHub, Forge::GitLab]
+            .iter()
+            .filter_map(|forge| forge.pull_request_ref(number))
+            .collect(),
+    }
+}
+
+/// Parse a pull request number written as `1234` or `#1234`
+pub fn parse_pull_request_number(value: &str) -> Option<u64> {
+    let digits = value.strip_prefix('#').unwrap_or(value);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok().filter(|&n| n > 0)
+}
+
+/// Local branch a pull request is checked out as
+pub fn pull_request_branch(number: u64) -> String {
+    format!("pr/{}", number)
+}
+
+/// A repository's web location, derived from its remote URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebRepo {
+    pub forge: Forge,
+    /// `https://host/owner/repo`
+    pub base_url: String,
+}
+
+impl WebRepo {
+    /// Recognise the forge from a remote URL in any of git's spellings
+    /// (`git@github.com:user/repo.git`, `https://gitlab.com/group/repo`, ...).
+    /// Self-hosted instances are recognised by the forge's name in the host.
+    pub fn from_remote_url(url: &str) -> Option<Self> {
+        let normalized = normalize_remote_url(url);
+        let (host, path) = normalized.split_once('/')?;
+        if path.is_empty() {
+            return None;
+        }
+
+        let forge = if host.contains("github") {
+            Forge::GitHub
+        } else if host.contains("gitlab") {
+            Forge::GitLab
+        } else if host.contains("bitbucket") {
+            Forge::Bitbucket
+        } else {
+            return None;
+        };
+
+        Some(Self {
+            forge,
+            base_url: format!("https://{}/{}", host, path),
+        })
+    }
+
+    /// Page showing the branch's files
+    pub fn branch_url(&self, branch: &str) -> String {
+        let branch = encode_path(branch);
+        match self.forge {
+            Forge::GitHub => format!("{}/tree/{}", self.base_url, branch),
+            Forge::GitLab => format!("{}/-/tree/{}", self.base_url, branch),
+            Forge::Bitbucket => format!("{}/branch/{}", self.base_url, branch),
+        }
+    }
+
+    /// Page for the branch's pull/merge request. The forges redirect to an
+    /// existing request or offer to open a new one.
+    pub fn pull_request_url(&self, branch: &str) -> String {
+        match self.forge {
+            Forge::GitHub => format!("{}/compare/{}?expand=1", self.base_url, encode_path(branch)),
+            Forge::GitLab => format!(
+                "{}/-/merge_requests/new?merge_request%5Bsource_branch%5D={}",
+                self.base_url,
+                encode_query(branch)
+            ),
+            Forge::Bitbucket => format!(
+                "{}/pull-requests/new?source={}",
+                self.base_url,
+                encode_query(branch)
+            ),
+        }
+    }
+}
+
+/// Percent-encode a branch name for a URL path, keeping `/` separators
+fn encode_path(branch: &str) -> String {
+    branch
+        .split('/')
+        .map(encode_query)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Percent-encode everything but unreserved characters
+fn encode_query(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_remote_url() {
+        let repo = WebRepo::from_remote_url("git@github.com:XavierFabregat/ggo.git").unwrap();
+        assert_eq!(repo.forge, Forge::GitHub);
+        assert_eq!(repo.base_url, "https://github.com/XavierFabregat/ggo");
+
+        let repo = WebRepo::from_remote_url("https://gitlab.example.com/group/sub/app").unwrap();
+        assert_eq!(repo.forge, Forge::GitLab);
+        assert_eq!(repo.base_url, "https://gitlab.example.com/group/sub/app");
+
+        let repo = WebRepo::from_remote_url("git@bitbucket.org:team/app.git").unwrap();
+        assert_eq!(repo.forge, Forge::Bitbucket);
+
+        assert!(WebRepo::from_remote_url("git@example.com:team/app.git").is_none());
+        assert!(WebRepo::from_remote_url("/srv/git/app.git").is_none());
+    }
+
+    #[test]
+    fn test_pull_request_refs() {
+        assert_eq!(
+            pull_request_refs(Some(Forge::GitHub), 12),
+            vec!["refs/pull/12/head"]
+        );
+        assert_eq!(
+            pull_request_refs(Some(Forge::GitLab), 12),
+            vec!["refs/merge-requests/12/head"]
+        );
+        assert!(pull_request_refs(Some(Forge::Bitbucket), 12).is_empty());
+        assert_eq!(pull_request_refs(None, 12).len(), 2);
+
+        assert_eq!(parse_pull_request_number("1234"), Some(1234));
+        assert_eq!(parse_pull_request_number("#1234"), Some(1234));
+        assert_eq!(parse_pull_request_number("#"), None);
+        assert_eq!(parse_pull_request_number("#0"), None);
+        assert_eq!(parse_pull_request_number("+12"), None);
+        assert_eq!(parse_pull_request_number("#12a"), None);
+        assert_eq!(pull_request_branch(1234), "pr/1234");
+    }
+
+    #[test]
+    fn test_branch_and_pull_request_urls() {
+        let github = WebRepo::from_remote_url("git@github.com:o/r.git").unwrap();
+        assert_eq!(
+            github.branch_url("feature/auth"),
+            "https://github.com/o/r/tree/feature/auth"
+        );
+        assert_eq!(
+            github.pull_request_url("feature/auth"),
+            "https://github.com/o/r/compare/feature/auth?expand=1"
+        );
+
+        let gitlab = WebRepo::from_remote_url("https://gitlab.com/o/r").unwrap();
+        assert_eq!(
+            gitlab.branch_url("fix/a#1"),
+            "https://gitlab.com/o/r/-/tree/fix/a%231"
+        );
+        assert_eq!(
+            gitlab.pull_request_url("fix/a"),
+            "https://gitlab.com/o/r/-/merge_requests/new?merge_request%5Bsource_branch%5D=fix%2Fa"
+        );
+
+        let bitbucket = WebRepo::from_remote_url("git@bitbucket.org:o/r.git").unwrap();
+        assert_eq!(
+            bitbucket.branch_url("main"),
+            "https://bitbucket.org/o/r/branch/main"
+        );
+        assert_eq!(
+            bitbucket.pull_request_url("feature/x"),
+            "https://bitbucket.org/o/r/pull-requests/new?source=feature%2Fx"
+        );
+    }
+}
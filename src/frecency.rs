@@ -1,10 +1,35 @@
-use crate::constants::frecency::{DAY_SECONDS, HOUR_SECONDS, MONTH_SECONDS, WEEK_SECONDS};
-use crate::storage::BranchRecord;
+use crate::constants::frecency::{
+    DAY_SECONDS, DEFAULT_HALF_LIFE_DAYS, HOUR_SECONDS, MONTH_SECONDS, WEEK_SECONDS,
+};
+use crate::constants::scoring::{
+    DEFAULT_AUTO_SELECT_THRESHOLD, FRECENCY_MULTIPLIER, SESSION_HALF_LIFE_FRACTION,
+};
+use crate::storage::{BranchRecord, CheckoutEvent};
+use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-/// Half-life for exponential decay (1 week in seconds)
-/// After this duration, a branch's recency weight is halved
-const HALF_LIFE_SECONDS: f64 = 604800.0; // 1 week
+/// The scoring knobs in effect for one repository, resolved from the config
+/// (see `Config::scoring_for`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoringParams {
+    /// Days after which a branch's recency weight halves
+    pub half_life_days: f64,
+    /// Weight of frecency against fuzzy match quality in the combined score
+    pub frecency_multiplier: f64,
+    /// Score ratio over the runner-up at which the top match is picked
+    /// without asking
+    pub auto_select_threshold: f64,
+}
+
+impl Default for ScoringParams {
+    fn default() -> Self {
+        Self {
+            half_life_days: DEFAULT_HALF_LIFE_DAYS,
+            frecency_multiplier: FRECENCY_MULTIPLIER,
+            auto_select_threshold: DEFAULT_AUTO_SELECT_THRESHOLD,
+        }
+    }
+}
 
 /// Calculate the frecency score for a branch record using exponential decay.
 ///
@@ -12,24 +37,29 @@ const HALF_LIFE_SECONDS: f64 = 604800.0; // 1 week
 /// where λ = ln(2) / half_life
 ///
 /// This provides smooth decay instead of stepped tiers, more similar to zoxide's algorithm.
-/// The half-life is 1 week, meaning a branch's recency weight halves each week.
-pub fn calculate_score(record: &BranchRecord) -> f64 {
+/// `half_life_days` comes from the config (default 1 week): a branch's recency
+/// weight halves every `half_life_days`.
+pub fn calculate_score(record: &BranchRecord, half_life_days: f64) -> f64 {
+    // Multiply frequency by decayed recency weight
+    record.switch_count as f64 * recency_weight(record.last_used, half_life_days)
+}
+
+/// Decay weight of a branch last used at `last_used`: 1.0 right now, 0.5 one
+/// half-life ago, 0.25 two half-lives ago, and so on
+pub fn recency_weight(last_used: i64, half_life_days: f64) -> f64 {
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
         .as_secs() as f64;
 
-    let age_seconds = now - record.last_used as f64;
+    let age_seconds = now - last_used as f64;
 
     // Decay constant (lambda) = ln(2) / half_life
-    let lambda = 2.0_f64.ln() / HALF_LIFE_SECONDS;
+    let half_life_seconds = half_life_days * DAY_SECONDS as f64;
+    let lambda = 2.0_f64.ln() / half_life_seconds;
 
     // Exponential decay: e^(-λt)
-    // This gives smooth decay: 1.0 at t=0, 0.5 at t=half_life, 0.25 at t=2*half_life, etc.
-    let recency_weight = (-lambda * age_seconds).exp();
-
-    // Multiply frequency by decayed recency weight
-    record.switch_count as f64 * recency_weight
+    (-lambda * age_seconds).exp()
 }
 
 /// A branch with its calculated frecency score
@@ -42,12 +72,12 @@ pub struct ScoredBranch {
 }
 
 /// Score and sort branches by frecency
-pub fn rank_branches(records: &[BranchRecord]) -> Vec<ScoredBranch> {
+pub fn rank_branches(records: &[BranchRecord], half_life_days: f64) -> Vec<ScoredBranch> {
     let mut scored: Vec<ScoredBranch> = records
         .iter()
         .map(|r| ScoredBranch {
             name: r.branch_name.clone(),
-            score: calculate_score(r),
+            score: calculate_score(r, half_life_days),
             switch_count: r.switch_count,
             last_used: r.last_used,
         })
@@ -68,8 +98,9 @@ pub fn rank_branches(records: &[BranchRecord]) -> Vec<ScoredBranch> {
 pub fn sort_branches_by_frecency(
     branches: &[String],
     records: &[BranchRecord],
+    half_life_days: f64,
 ) -> Vec<(String, f64)> {
-    let scored = rank_branches(records);
+    let scored = rank_branches(records, half_life_days);
 
     let mut result: Vec<(String, f64)> = branches
         .iter()
@@ -89,6 +120,59 @@ pub fn sort_branches_by_frecency(
     result
 }
 
+/// Per-visit frecency from individual checkout events: each visit to `branch`
+/// contributes its own decayed weight, so a burst of old visits fades instead
+/// of being carried by a single recent one.
+///
+/// Score = Σ exp(-λ × age_i) over the events that switched to `branch`
+pub fn calculate_visit_score(events: &[CheckoutEvent], branch: &str, half_life_days: f64) -> f64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as f64;
+
+    let half_life_seconds = half_life_days * DAY_SECONDS as f64;
+    let lambda = 2.0_f64.ln() / half_life_seconds;
+
+    events
+        .iter()
+        .filter(|e| e.to_branch == branch)
+        .map(|e| (-lambda * (now - e.switched_at as f64).max(0.0)).exp())
+        .sum()
+}
+
+/// How much each branch was used in the current session: the checkouts in
+/// `events` (most recent first) back to the first gap of `window_seconds` or
+/// more, counting from now. A checkout weighs 1.0 when it just happened and
+/// halves every quarter window; a branch's weight is capped at 1.0.
+pub fn session_weights(events: &[CheckoutEvent], window_seconds: i64) -> HashMap<String, f64> {
+    let mut weights = HashMap::new();
+    if window_seconds <= 0 {
+        return weights;
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    let half_life_seconds = window_seconds as f64 * SESSION_HALF_LIFE_FRACTION;
+    let lambda = 2.0_f64.ln() / half_life_seconds;
+
+    let mut previous = now;
+    for event in events {
+        if previous - event.switched_at >= window_seconds {
+            break;
+        }
+        previous = event.switched_at;
+
+        let age_seconds = (now - event.switched_at).max(0) as f64;
+        let weight = weights.entry(event.to_branch.clone()).or_insert(0.0);
+        *weight = (*weight + (-lambda * age_seconds).exp()).min(1.0);
+    }
+
+    weights
+}
+
 /// Format a timestamp as a human-readable relative time
 pub fn format_relative_time(timestamp: i64) -> String {
     let now = SystemTime::now()
@@ -121,6 +205,90 @@ pub fn format_relative_time(timestamp: i64) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::constants::frecency::DEFAULT_HALF_LIFE_DAYS;
+
+    fn event(to_branch: &str, age_seconds: i64) -> CheckoutEvent {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        CheckoutEvent {
+            repo_path: "/repo".to_string(),
+            from_branch: None,
+            to_branch: to_branch.to_string(),
+            switched_at: now - age_seconds,
+        }
+    }
+
+    #[test]
+    fn test_visit_score_sums_decayed_visits() {
+        let half_life = 7.0;
+        let events = vec![
+            event("main", 0),
+            event("main", 7 * DAY_SECONDS),
+            event("feature", 0),
+        ];
+
+        let score = calculate_visit_score(&events, "main", half_life);
+        assert!((score - 1.5).abs() < 0.01);
+        assert_eq!(calculate_visit_score(&events, "missing", half_life), 0.0);
+    }
+
+    #[test]
+    fn test_visit_score_old_bursts_fade() {
+        // Ten visits a month ago rank below two visits today
+        let mut events: Vec<CheckoutEvent> =
+            (0..10).map(|_| event("old", 30 * DAY_SECONDS)).collect();
+        events.push(event("new", 0));
+        events.push(event("new", HOUR_SECONDS));
+
+        let old = calculate_visit_score(&events, "old", DEFAULT_HALF_LIFE_DAYS);
+        let new = calculate_visit_score(&events, "new", DEFAULT_HALF_LIFE_DAYS);
+        assert!(new > old);
+    }
+
+    #[test]
+    fn test_session_weights() {
+        let window = 2 * HOUR_SECONDS;
+        let events = vec![
+            event("feature", 0),
+            event("main", 30 * 60),
+            event("feature", 90 * 60),
+            // Gap of two hours: an earlier session
+            event("old", 210 * 60),
+        ];
+
+        let weights = session_weights(&events, window);
+        assert_eq!(weights["feature"], 1.0);
+        // Half an hour is a quarter of the window: one half-life
+        assert!((weights["main"] - 0.5).abs() < 0.01);
+        assert!(!weights.contains_key("old"));
+
+        // A session that ended a window ago is over
+        assert!(session_weights(&events[3..], window).is_empty());
+        assert!(session_weights(&events, 0).is_empty());
+    }
+
+    #[test]
+    fn test_shorter_half_life_decays_faster() {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let record = BranchRecord {
+            repo_path: "/test".to_string(),
+            branch_name: "main".to_string(),
+            switch_count: 10,
+            last_used: now - DAY_SECONDS, // 1 day ago
+        };
+
+        // One day old with a one day half-life: weight is halved
+        let score = calculate_score(&record, 1.0);
+        assert!((score - 5.0).abs() < 0.1);
+        assert!(score < calculate_score(&record, DEFAULT_HALF_LIFE_DAYS));
+    }
 
     #[test]
     fn test_calculate_score_recent() {
@@ -136,7 +304,7 @@ mod tests {
             last_used: now - 60, // 1 minute ago
         };
 
-        let score = calculate_score(&record);
+        let score = calculate_score(&record, DEFAULT_HALF_LIFE_DAYS);
         // With exponential decay, very recent items have weight ~1.0
         // 10 * ~1.0 ≈ 10.0
         assert!(score > 9.9 && score < 10.1);
@@ -156,7 +324,7 @@ mod tests {
             last_used: now - 3599, // Just under 1 hour ago
         };
 
-        let score = calculate_score(&record);
+        let score = calculate_score(&record, DEFAULT_HALF_LIFE_DAYS);
         // With exponential decay, 1 hour old has weight ~0.999
         // 5 * ~0.999 ≈ 5.0
         assert!(score > 4.9 && score < 5.1);
@@ -176,7 +344,7 @@ mod tests {
             last_used: now - 43200, // 12 hours ago
         };
 
-        let score = calculate_score(&record);
+        let score = calculate_score(&record, DEFAULT_HALF_LIFE_DAYS);
         // With exponential decay, 12 hours (~7% of half-life) has weight ~0.95
         // 8 * ~0.95 ≈ 7.6
         assert!(score > 7.5 && score < 7.7);
@@ -196,7 +364,7 @@ mod tests {
             last_used: now - 259200, // 3 days ago
         };
 
-        let score = calculate_score(&record);
+        let score = calculate_score(&record, DEFAULT_HALF_LIFE_DAYS);
         // 3 days is ~0.43 of half-life, weight ≈ 0.75
         // 6 * ~0.75 ≈ 4.5
         assert!(score > 4.4 && score < 4.7);
@@ -216,7 +384,7 @@ mod tests {
             last_used: now - 1209600, // 14 days ago (2 weeks = 2 half-lives)
         };
 
-        let score = calculate_score(&record);
+        let score = calculate_score(&record, DEFAULT_HALF_LIFE_DAYS);
         // 2 half-lives means weight = 0.25
         // 4 * 0.25 = 1.0
         assert!(score > 0.9 && score < 1.1);
@@ -236,7 +404,7 @@ mod tests {
             last_used: now - 3000000, // ~35 days ago (~5 half-lives)
         };
 
-        let score = calculate_score(&record);
+        let score = calculate_score(&record, DEFAULT_HALF_LIFE_DAYS);
         // 5 half-lives: weight ≈ 0.03125 (1/32)
         // 10 * 0.03125 ≈ 0.31
         assert!(score > 0.3 && score < 0.35);
@@ -256,14 +424,14 @@ mod tests {
             last_used: now - 60,
         };
 
-        let score = calculate_score(&record);
+        let score = calculate_score(&record, DEFAULT_HALF_LIFE_DAYS);
         assert_eq!(score, 0.0); // 0 * any_weight = 0
     }
 
     #[test]
     fn test_rank_branches_empty() {
         let records: Vec<BranchRecord> = vec![];
-        let ranked = rank_branches(&records);
+        let ranked = rank_branches(&records, DEFAULT_HALF_LIFE_DAYS);
         assert!(ranked.is_empty());
     }
 
@@ -281,7 +449,7 @@ mod tests {
             last_used: now - 60,
         }];
 
-        let ranked = rank_branches(&records);
+        let ranked = rank_branches(&records, DEFAULT_HALF_LIFE_DAYS);
         assert_eq!(ranked.len(), 1);
         assert_eq!(ranked[0].name, "main");
         // Score should be ~5.0 (5 switches * ~1.0 weight for very recent)
@@ -317,7 +485,7 @@ mod tests {
             },
         ];
 
-        let ranked = rank_branches(&records);
+        let ranked = rank_branches(&records, DEFAULT_HALF_LIFE_DAYS);
         assert_eq!(ranked.len(), 3);
         // Should be sorted by score (highest first)
         assert_eq!(ranked[0].name, "recent");
@@ -332,7 +500,7 @@ mod tests {
     fn test_sort_branches_by_frecency_empty_branches() {
         let branches: Vec<String> = vec![];
         let records: Vec<BranchRecord> = vec![];
-        let sorted = sort_branches_by_frecency(&branches, &records);
+        let sorted = sort_branches_by_frecency(&branches, &records, DEFAULT_HALF_LIFE_DAYS);
         assert!(sorted.is_empty());
     }
 
@@ -345,7 +513,7 @@ mod tests {
         ];
         let records: Vec<BranchRecord> = vec![];
 
-        let sorted = sort_branches_by_frecency(&branches, &records);
+        let sorted = sort_branches_by_frecency(&branches, &records, DEFAULT_HALF_LIFE_DAYS);
         assert_eq!(sorted.len(), 3);
 
         // All should have score 0.0
@@ -382,7 +550,7 @@ mod tests {
             },
         ];
 
-        let sorted = sort_branches_by_frecency(&branches, &records);
+        let sorted = sort_branches_by_frecency(&branches, &records, DEFAULT_HALF_LIFE_DAYS);
         assert_eq!(sorted.len(), 3);
         assert_eq!(sorted[0].0, "develop");
         assert!(sorted[0].1 > 9.9 && sorted[0].1 < 10.1);
@@ -412,7 +580,7 @@ mod tests {
             last_used: now - 60,
         }];
 
-        let sorted = sort_branches_by_frecency(&branches, &records);
+        let sorted = sort_branches_by_frecency(&branches, &records, DEFAULT_HALF_LIFE_DAYS);
         assert_eq!(sorted[0].0, "branch-b");
         assert!(sorted[0].1 > 0.0);
         assert_eq!(sorted[1].1, 0.0);
@@ -0,0 +1,172 @@
+use std::io::{self, Write};
+use std::process::Stdio;
+
+use crate::error::{GgoError, Result};
+use crate::frecency;
+use crate::hooks;
+use crate::storage::BranchRecord;
+
+/// Exit code fzf and skim use when the user aborts with Esc or Ctrl-C
+const EXIT_INTERRUPTED: i32 = 130;
+/// Exit code fzf uses when the query matched nothing
+const EXIT_NO_MATCH: i32 = 1;
+/// Exit code `sh` uses when the command doesn't exist
+const EXIT_NOT_FOUND: i32 = 127;
+
+/// One `branch<TAB>score<TAB>meta` line per branch, in the given (ranked) order
+fn candidate_lines(
+    branches: &[String],
+    records: &[BranchRecord],
+    pinned: &[String],
+    half_life_days: f64,
+) -> Vec<String> {
+    branches
+        .iter()
+        .map(|branch| {
+            let record = records.iter().find(|r| &r.branch_name == branch);
+            let score = record
+                .map(|r| frecency::calculate_score(r, half_life_days))
+                .unwrap_or(0.0);
+
+            let mut meta = match record {
+                Some(r) if r.switch_count > 0 => format!(
+                    "{} switches, {}",
+                    r.switch_count,
+                    frecency::format_relative_time(r.last_used)
+                ),
+                _ => "never used".to_string(),
+            };
+            if pinned.contains(branch) {
+                meta.push_str(" ★");
+            }
+
+            format!("{}\t{:.1}\t{}", branch, score, meta)
+        })
+        .collect()
+}
+
+/// Branch name from the line the picker printed (its first tab-separated field)
+fn parse_selection(output: &str) -> Option<String> {
+    output
+        .lines()
+        .map(|line| line.split('\t').next().unwrap_or_default().trim())
+        .find(|branch| !branch.is_empty())
+        .map(str::to_string)
+}
+
+/// Pipe `input` into `command` and return what it printed on stdout
+fn run_picker(command: &str, input: &str) -> Result<String> {
+    let failed = |reason: String| GgoError::PickerFailed {
+        command: command.to_string(),
+        reason,
+    };
+
+    // stderr stays attached: fzf draws its interface there (and on /dev/tty)
+    let mut child = hooks::shell(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| failed(e.to_string()))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // The picker may exit before reading everything (e.g. Esc right away)
+        match stdin.write_all(input.as_bytes()) {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => return Err(e.into()),
+            _ => {}
+        }
+    }
+
+    let output = child.wait_with_output()?;
+    match output.status.code() {
+        Some(0) => Ok(String::from_utf8_lossy(&output.stdout).into_owned()),
+        Some(EXIT_NO_MATCH) | Some(EXIT_INTERRUPTED) => Err(GgoError::UserCancelled),
+        Some(EXIT_NOT_FOUND) => Err(failed("command not found".to_string())),
+        _ => Err(failed(format!("exited with {}", output.status))),
+    }
+}
+
+/// Let an external fzf/skim process pick one of `branches`
+pub fn select_branch(
+    command: &str,
+    branches: &[String],
+    records: &[BranchRecord],
+    pinned: &[String],
+    half_life_days: f64,
+) -> Result<String> {
+    if branches.is_empty() {
+        return Err(GgoError::Other(
+            "No branches available for selection".to_string(),
+        ));
+    }
+
+    let mut input = candidate_lines(branches, records, pinned, half_life_days).join("\n");
+    input.push('\n');
+
+    let output = run_picker(command, &input)?;
+    parse_selection(&output).ok_or(GgoError::UserCancelled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(name: &str, switch_count: i64) -> BranchRecord {
+        BranchRecord {
+            repo_path: "/repo".to_string(),
+            branch_name: name.to_string(),
+            switch_count,
+            last_used: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as i64,
+        }
+    }
+
+    #[test]
+    fn test_candidate_lines() {
+        let branches = vec!["feature/auth".to_string(), "main".to_string()];
+        let records = vec![record("feature/auth", 4)];
+        let pinned = vec!["main".to_string()];
+
+        let lines = candidate_lines(&branches, &records, &pinned, 7.0);
+        assert_eq!(lines.len(), 2);
+
+        let fields: Vec<&str> = lines[0].split('\t').collect();
+        assert_eq!(fields[0], "feature/auth");
+        assert!(fields[1].parse::<f64>().unwrap() > 0.0);
+        assert!(fields[2].starts_with("4 switches"));
+
+        assert_eq!(lines[1], "main\t0.0\tnever used ★");
+    }
+
+    #[test]
+    fn test_parse_selection() {
+        assert_eq!(
+            parse_selection("feature/auth\t12.5\t4 switches, 2h ago\n"),
+            Some("feature/auth".to_string())
+        );
+        assert_eq!(parse_selection("main\n"), Some("main".to_string()));
+        assert_eq!(parse_selection(""), None);
+        assert_eq!(parse_selection("\n"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_picker() {
+        let branches = vec!["main".to_string(), "develop".to_string()];
+
+        // Any filter that prints one of its input lines can stand in for fzf
+        assert_eq!(
+            select_branch("sed -n 2p", &branches, &[], &[], 7.0).unwrap(),
+            "develop"
+        );
+        assert!(matches!(
+            select_branch("exit 130", &branches, &[], &[], 7.0),
+            Err(GgoError::UserCancelled)
+        ));
+        assert!(matches!(
+            select_branch("ggo-no-such-picker", &branches, &[], &[], 7.0),
+            Err(GgoError::PickerFailed { .. })
+        ));
+    }
+}
@@ -0,0 +1,1556 @@
+//! [`crate::git`] with gitoxide, checkouts included: the trees are compared
+//! and the changed files written by gix, and git's refusals (local changes or
+//! untracked files that would be overwritten, a branch checked out in another
+//! worktree) are checked here. Unlike `git switch`, no post-checkout hook of
+//! git's own runs.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+
+use gix::bstr::{BStr, BString, ByteSlice};
+use gix::object::tree::diff::ChangeDetached;
+use gix::refs::transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog};
+use gix::refs::{FullName, FullNameRef, Target};
+use gix::revision::walk::Sorting;
+use gix::ObjectId;
+
+pub(crate) use gix::Repository;
+
+use super::{
+    author_matches, bare_worktree_path, match_renamed_branches, merge_activity,
+    other_worktree_with, parse_checkout, parse_rename, reflog_checkout, same_path_key,
+    short_upstream, worktree_name, BranchActivity, BranchAge, BranchInfo, CommitHit, CommitQuery,
+    DetachTarget, PrunableBranch, PruneReason, ReflogCheckout, Worktree,
+};
+use crate::constants::git::{ACTIVITY_MAX_COMMITS, FIND_MAX_COMMITS};
+use crate::error::{GgoError, Result};
+
+/// gix errors have no variant of their own
+fn other(err: impl std::fmt::Display) -> GgoError {
+    GgoError::Other(err.to_string())
+}
+
+/// Opens the repository like git does: `GIT_DIR` if set, else the first one
+/// found walking up from the current directory
+pub(crate) fn open() -> Result<Repository> {
+    // From an absolute path, so the paths gix hands back are absolute too
+    let cwd = std::env::current_dir()?;
+    gix::discover_with_environment_overrides(cwd).map_err(|_| GgoError::NotGitRepository)
+}
+
+/// The repository at `path`, if there is one
+pub(crate) fn open_at(path: &Path) -> Option<Repository> {
+    gix::open(path).ok()
+}
+
+/// Git directory shared by all worktrees of a repository
+pub(crate) fn common_dir(repo: &Repository) -> PathBuf {
+    repo.common_dir().to_path_buf()
+}
+
+/// The working tree, None for a bare repository
+pub(crate) fn workdir(repo: &Repository) -> Option<&Path> {
+    repo.workdir()
+}
+
+/// Local branches with their names; like libgit2, names that aren't valid
+/// UTF-8 are skipped
+fn branches(repo: &Repository) -> Result<Vec<(String, gix::Reference<'_>)>> {
+    let references = repo.references().map_err(other)?;
+    let mut branches = Vec::new();
+
+    for reference in references.local_branches().map_err(other)? {
+        let reference = reference.map_err(other)?;
+        if let Ok(name) = reference.name().shorten().to_str() {
+            branches.push((name.to_string(), reference));
+        }
+    }
+
+    Ok(branches)
+}
+
+fn find_branch<'repo>(repo: &'repo Repository, branch: &str) -> Option<gix::Reference<'repo>> {
+    // Invalid names can't exist
+    repo.try_find_reference(format!("refs/heads/{}", branch).as_str())
+        .ok()
+        .flatten()
+}
+
+pub(crate) fn local_branches_in(repo: &Repository) -> Result<Vec<String>> {
+    Ok(branches(repo)?.into_iter().map(|(name, _)| name).collect())
+}
+
+pub(crate) fn branch_exists_in(repo: &Repository, branch: &str) -> bool {
+    find_branch(repo, branch).is_some()
+}
+
+pub(crate) fn list_branches_in(repo: &Repository) -> Result<Vec<BranchInfo>> {
+    let mut descriptions = branch_descriptions_in(repo)?;
+    let head = head_ref_name(repo);
+
+    branches(repo)?
+        .into_iter()
+        .map(|(name, reference)| branch_info(repo, name, reference, &head, &mut descriptions))
+        .collect()
+}
+
+pub(crate) fn branch_infos_in(repo: &Repository, names: &[String]) -> Result<Vec<BranchInfo>> {
+    let mut descriptions = branch_descriptions_in(repo)?;
+    let head = head_ref_name(repo);
+    let mut branches = Vec::with_capacity(names.len());
+
+    for name in names {
+        let Some(reference) = find_branch(repo, name) else {
+            continue;
+        };
+        branches.push(branch_info(
+            repo,
+            name.clone(),
+            reference,
+            &head,
+            &mut descriptions,
+        )?);
+    }
+
+    Ok(branches)
+}
+
+/// Upstream, tip commit and description of one branch
+fn branch_info(
+    repo: &Repository,
+    name: String,
+    mut reference: gix::Reference<'_>,
+    head: &Option<FullName>,
+    descriptions: &mut HashMap<String, String>,
+) -> Result<BranchInfo> {
+    let tip = reference.peel_to_commit().map_err(other)?;
+
+    // No upstream configured, or its remote-tracking ref is gone
+    let upstream = upstream_ref(repo, reference.name())
+        .and_then(|upstream| repo.try_find_reference(upstream.as_ref()).ok().flatten());
+    let (upstream, ahead_behind) = match upstream {
+        Some(mut upstream) => {
+            let ahead_behind = upstream
+                .peel_to_id()
+                .ok()
+                .and_then(|upstream_tip| ahead_behind(repo, tip.id, upstream_tip.detach()).ok());
+            (Some(upstream.name().shorten().to_string()), ahead_behind)
+        }
+        None => (None, None),
+    };
+
+    Ok(BranchInfo {
+        description: descriptions.remove(&name),
+        is_head: head
+            .as_ref()
+            .is_some_and(|head| head.as_ref() == reference.name()),
+        name,
+        upstream,
+        ahead_behind,
+        last_commit_time: tip.time().map_err(other)?.seconds,
+        last_commit_summary: summary(&tip)?,
+    })
+}
+
+/// The ref a local branch's upstream is: the remote-tracking branch for
+/// `branch.<name>.merge` on `branch.<name>.remote`, or a local branch when
+/// that remote is `.`. Whether the ref exists isn't checked.
+fn upstream_ref(repo: &Repository, branch: &FullNameRef) -> Option<FullName> {
+    let config = repo.config_snapshot();
+    let remote = config
+        .plumbing()
+        .string_by("branch", Some(branch.shorten()), "remote")?;
+    let direction = gix::remote::Direction::Fetch;
+    let upstream = if remote.as_ref() == "." {
+        repo.branch_remote_ref_name(branch, direction)?.ok()
+    } else {
+        repo.branch_remote_tracking_ref_name(branch, direction)?
+            .ok()
+    };
+    upstream.map(Cow::into_owned)
+}
+
+/// Commits (ahead, behind) of `local` relative to `upstream`
+fn ahead_behind(repo: &Repository, local: ObjectId, upstream: ObjectId) -> Result<(usize, usize)> {
+    let count = |tip: ObjectId, hidden: ObjectId| -> Result<usize> {
+        let walk = repo
+            .rev_walk([tip])
+            .with_hidden([hidden])
+            .all()
+            .map_err(other)?;
+        walk.into_iter()
+            .try_fold(0, |count, info| info.map(|_| count + 1))
+            .map_err(other)
+    };
+    Ok((count(local, upstream)?, count(upstream, local)?))
+}
+
+/// First line of the commit message
+fn summary(commit: &gix::Commit<'_>) -> Result<String> {
+    Ok(commit.message().map_err(other)?.summary().to_string())
+}
+
+/// Full name of the branch HEAD points to, born or not
+fn head_ref_name(repo: &Repository) -> Option<FullName> {
+    repo.head_name().ok().flatten()
+}
+
+pub(crate) fn branch_descriptions_in(repo: &Repository) -> Result<HashMap<String, String>> {
+    let config = repo.config_snapshot();
+    let mut descriptions = HashMap::new();
+
+    let Some(sections) = config.plumbing().sections_by_name("branch") else {
+        return Ok(descriptions);
+    };
+    for section in sections {
+        let branch = section.header().subsection_name();
+        let description = section.value("description");
+        let description = description.as_ref().map(|d| d.to_str_lossy());
+        let description = description
+            .as_deref()
+            .map(str::trim)
+            .filter(|d| !d.is_empty());
+
+        if let (Some(branch), Some(description)) = (branch, description) {
+            descriptions.insert(branch.to_string(), description.to_string());
+        }
+    }
+
+    Ok(descriptions)
+}
+
+/// Check out a local branch: the changed files are written, HEAD points to
+/// the branch and the switch is logged in the HEAD reflog
+pub(crate) fn checkout(branch: &str) -> Result<()> {
+    checkout_in(&open()?, branch)
+}
+
+fn checkout_in(repo: &Repository, branch: &str) -> Result<()> {
+    let failed = |message: String| GgoError::CheckoutFailed(branch.to_string(), message);
+
+    let mut reference =
+        find_branch(repo, branch).ok_or_else(|| GgoError::BranchNotFound(branch.to_string()))?;
+
+    if let Some(path) = worktree_for_branch_in(repo, branch)? {
+        return Err(failed(format!(
+            "'{}' is already used by worktree at '{}'",
+            branch,
+            path.display()
+        )));
+    }
+
+    // Already on it
+    if head_ref_name(repo).is_some_and(|head| head.as_ref() == reference.name()) {
+        return Ok(());
+    }
+
+    let tree = tree_of(&mut reference).map_err(|e| failed(e.to_string()))?;
+    let target = Target::Symbolic(reference.name().to_owned());
+    switch_to(repo, tree, target, branch).map_err(|e| failed(e.to_string()))
+}
+
+/// The tree of the commit a ref points to
+fn tree_of(reference: &mut gix::Reference<'_>) -> Result<ObjectId> {
+    let commit = reference.peel_to_commit().map_err(other)?;
+    Ok(commit.tree_id().map_err(other)?.detach())
+}
+
+pub(crate) fn checkout_detached(branch: &str) -> Result<()> {
+    let repo = open()?;
+
+    let commit = find_branch(&repo, branch)
+        .and_then(|mut reference| reference.peel_to_commit().ok())
+        .ok_or_else(|| GgoError::BranchNotFound(branch.to_string()))?;
+
+    detach_at(&repo, &commit, branch)
+}
+
+pub(crate) fn checkout_commit(commit_id: &str) -> Result<()> {
+    let repo = open()?;
+
+    let commit = ObjectId::from_hex(commit_id.as_bytes())
+        .map_err(other)
+        .and_then(|id| repo.find_commit(id).map_err(other))
+        .map_err(|e| GgoError::CheckoutFailed(commit_id.to_string(), e.to_string()))?;
+
+    detach_at(&repo, &commit, commit_id)
+}
+
+fn detach_at(repo: &Repository, commit: &gix::Commit<'_>, target: &str) -> Result<()> {
+    let id = commit.id;
+    let checkout = || -> Result<()> {
+        let tree = commit.tree_id().map_err(other)?.detach();
+        switch_to(repo, tree, Target::Object(id), &id.to_string())
+    };
+    checkout().map_err(|e| GgoError::CheckoutFailed(target.to_string(), e.to_string()))
+}
+
+/// Make the working tree and index go from HEAD's tree to `tree`, then point
+/// HEAD at `target` (`to` in the reflog message). Files that don't differ
+/// between the two trees are left alone, along with any local changes to
+/// them; local changes to files that do differ stop the switch before
+/// anything is written, as `git switch` does.
+fn switch_to(repo: &Repository, tree: ObjectId, target: Target, to: &str) -> Result<()> {
+    let workdir = repo.workdir().ok_or(GgoError::BareRepository)?;
+
+    let head = repo.head().map_err(other)?;
+    let old_tree = if head.is_unborn() {
+        repo.empty_tree()
+    } else {
+        repo.head_commit().map_err(other)?.tree().map_err(other)?
+    };
+    let new_tree = repo.find_tree(tree).map_err(other)?;
+
+    let mut removed: HashSet<BString> = HashSet::new();
+    let mut written: HashSet<BString> = HashSet::new();
+    let mut added: Vec<BString> = Vec::new();
+    let changes = repo
+        .diff_tree_to_tree(&old_tree, &new_tree, gix::diff::Options::default())
+        .map_err(other)?;
+    for change in changes {
+        match change {
+            ChangeDetached::Addition {
+                location,
+                entry_mode,
+                ..
+            } if !entry_mode.is_tree() => {
+                added.push(location.clone());
+                written.insert(location);
+            }
+            ChangeDetached::Deletion {
+                location,
+                entry_mode,
+                ..
+            } if !entry_mode.is_tree() => {
+                removed.insert(location);
+            }
+            ChangeDetached::Modification {
+                location,
+                entry_mode,
+                ..
+            } if !entry_mode.is_tree() => {
+                written.insert(location);
+            }
+            ChangeDetached::Rewrite {
+                source_location,
+                location,
+                entry_mode,
+                ..
+            } if !entry_mode.is_tree() => {
+                removed.insert(source_location);
+                written.insert(location);
+            }
+            _ => {}
+        }
+    }
+
+    let dirty = dirty_paths(repo)?;
+    let mut overwritten: Vec<String> = dirty
+        .iter()
+        .filter(|path| removed.contains(*path) || written.contains(*path))
+        .map(ToString::to_string)
+        .collect();
+    if !overwritten.is_empty() {
+        overwritten.sort();
+        return Err(GgoError::Other(format!(
+            "Your local changes to the following files would be overwritten by checkout: {}",
+            overwritten.join(", ")
+        )));
+    }
+
+    let mut untracked: Vec<String> = added
+        .iter()
+        .filter(|path| !dirty.contains(*path))
+        .filter(|path| {
+            workdir
+                .join(path.to_path_lossy())
+                .symlink_metadata()
+                .is_ok()
+        })
+        .map(ToString::to_string)
+        .collect();
+    if !untracked.is_empty() {
+        untracked.sort();
+        return Err(GgoError::Other(format!(
+            "The following untracked working tree files would be overwritten by checkout: {}",
+            untracked.join(", ")
+        )));
+    }
+
+    for path in &removed {
+        remove_file(workdir, &path.to_path_lossy())?;
+    }
+
+    // The new tree's version of the files that change, with the stat data
+    // of the files as written
+    let (mut entries, _) = repo.index_from_tree(&tree).map_err(other)?.into_parts();
+    entries.remove_entries(|_, path, _| !written.contains(path));
+    let mut options = repo
+        .checkout_options(gix::worktree::stack::state::attributes::Source::IdMapping)
+        .map_err(other)?;
+    options.overwrite_existing = true;
+    let outcome = gix::worktree::state::checkout(
+        &mut entries,
+        workdir,
+        repo.objects.clone().into_arc().map_err(other)?,
+        &gix::progress::Discard,
+        &gix::progress::Discard,
+        &AtomicBool::new(false),
+        options,
+    )
+    .map_err(other)?;
+    if let Some(error) = outcome.errors.first() {
+        return Err(GgoError::Other(format!("{}: {}", error.path, error.error)));
+    }
+
+    // Staged changes to the files that don't change are kept
+    let old_index = repo.index_or_empty().map_err(other)?;
+    let mut index = gix::index::File::clone(&old_index);
+    index.remove_entries(|_, path, _| removed.contains(path) || written.contains(path));
+    for entry in entries.entries() {
+        index.dangerously_push_entry(
+            entry.stat,
+            entry.id,
+            entry.flags,
+            entry.mode,
+            entry.path(&entries),
+        );
+    }
+    index.sort_entries();
+    // The cached trees no longer match the entries
+    index.remove_tree();
+    index.write(Default::default()).map_err(other)?;
+
+    set_head(repo, target, to)
+}
+
+/// Paths of tracked files with changes, staged or not
+fn dirty_paths(repo: &Repository) -> Result<HashSet<BString>> {
+    let status = repo
+        .status(gix::progress::Discard)
+        .map_err(other)?
+        .untracked_files(gix::status::UntrackedFiles::None)
+        .index_worktree_submodules(None)
+        .index_worktree_options_mut(|options| options.dirwalk_options = None)
+        .into_iter(Vec::new())
+        .map_err(other)?;
+
+    let mut dirty = HashSet::new();
+    for item in status {
+        dirty.insert(item.map_err(other)?.location().to_owned());
+    }
+    Ok(dirty)
+}
+
+/// Remove a file of the working tree and the directories it leaves empty
+fn remove_file(workdir: &Path, path: &Path) -> Result<()> {
+    let path = workdir.join(path);
+    match fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+        _ => {}
+    }
+
+    let mut dir = path.parent();
+    while let Some(parent) = dir.filter(|dir| *dir != workdir) {
+        if fs::remove_dir(parent).is_err() {
+            break;
+        }
+        dir = parent.parent();
+    }
+    Ok(())
+}
+
+/// Point HEAD at `target` and log it as "checkout: moving from <old> to
+/// `to`", the entry [`super::reflog_checkouts`] reads
+fn set_head(repo: &Repository, target: Target, to: &str) -> Result<()> {
+    let head = repo.head().map_err(other)?;
+    let old = head
+        .id()
+        .map(|id| id.detach())
+        .unwrap_or_else(|| repo.object_hash().null());
+    let from = match head.referent_name() {
+        Some(name) => name.shorten().to_string(),
+        None => old.to_string(),
+    };
+    let new = match &target {
+        Target::Object(id) => *id,
+        Target::Symbolic(name) => repo
+            .find_reference(name.as_ref())
+            .map_err(other)?
+            .peel_to_id()
+            .map_err(other)?
+            .detach(),
+    };
+    let message = format!("checkout: moving from {} to {}", from, to);
+
+    // gix logs no update of a symbolic ref, so the entry is written below
+    let mut refs = repo.refs.clone();
+    let write_reflog = refs.write_reflog;
+    refs.write_reflog = gix::refs::store::WriteReflog::Disable;
+    refs.transaction()
+        .prepare(
+            [RefEdit {
+                change: Change::Update {
+                    log: LogChange {
+                        mode: RefLog::AndReference,
+                        force_create_reflog: false,
+                        message: message.clone().into(),
+                    },
+                    expected: PreviousValue::Any,
+                    new: target,
+                },
+                name: "HEAD".try_into().map_err(other)?,
+                deref: false,
+            }],
+            gix::lock::acquire::Fail::Immediately,
+            gix::lock::acquire::Fail::Immediately,
+        )
+        .map_err(other)?
+        .commit(None)
+        .map_err(other)?;
+
+    if write_reflog == gix::refs::store::WriteReflog::Disable {
+        return Ok(());
+    }
+    let mut repo = repo.clone();
+    let mut line = format!("{} {} ", old, new).into_bytes();
+    repo.committer_or_set_generic_fallback()
+        .map_err(other)?
+        .write_to(&mut line)?;
+    line.extend_from_slice(format!("\t{}\n", message).as_bytes());
+
+    let log = repo.git_dir().join("logs").join("HEAD");
+    if let Some(dir) = log.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log)?
+        .write_all(&line)?;
+    Ok(())
+}
+
+pub(crate) fn detach_targets_in(repo: &Repository, limit: usize) -> Result<Vec<DetachTarget>> {
+    let mut targets = list_tags_in(repo)?;
+
+    let mut tips = Vec::new();
+    for (_, mut reference) in branches(repo)? {
+        tips.push(reference.peel_to_id().map_err(other)?.detach());
+    }
+    let walk = repo
+        .rev_walk(tips)
+        .sorting(Sorting::ByCommitTime(Default::default()))
+        .all()
+        .map_err(other)?;
+    for info in walk.take(limit) {
+        let commit = info.map_err(other)?.object().map_err(other)?;
+        let name = short_id(&commit)?;
+        targets.push(detach_target(&commit, name, false)?);
+    }
+
+    Ok(targets)
+}
+
+pub(crate) fn list_tags_in(repo: &Repository) -> Result<Vec<DetachTarget>> {
+    let references = repo.references().map_err(other)?;
+    let mut tags = Vec::new();
+
+    for reference in references.tags().map_err(other)? {
+        let mut reference = reference.map_err(other)?;
+        let Ok(name) = reference.name().shorten().to_str().map(str::to_string) else {
+            continue;
+        };
+        // Tags of trees or blobs can't be checked out
+        let Ok(commit) = reference.peel_to_commit() else {
+            continue;
+        };
+        tags.push(detach_target(&commit, name, true)?);
+    }
+    tags.sort_by_key(|t| std::cmp::Reverse(t.commit_time));
+
+    Ok(tags)
+}
+
+/// The commit `rev` names, if it names one
+fn resolve<'repo>(repo: &'repo Repository, rev: &str) -> Option<gix::Commit<'repo>> {
+    repo.rev_parse_single(rev)
+        .ok()?
+        .object()
+        .ok()?
+        .peel_to_commit()
+        .ok()
+}
+
+pub(crate) fn resolve_commit_in(repo: &Repository, rev: &str) -> Result<Option<DetachTarget>> {
+    let Some(commit) = resolve(repo, rev) else {
+        return Ok(None);
+    };
+    let name = short_id(&commit)?;
+    Ok(Some(detach_target(&commit, name, false)?))
+}
+
+pub(crate) fn branches_containing_in(repo: &Repository, rev: &str) -> Result<Option<Vec<String>>> {
+    let Some(commit) = resolve(repo, rev) else {
+        return Ok(None);
+    };
+
+    let mut branches = Vec::new();
+    for (name, mut reference) in self::branches(repo)? {
+        let tip = reference.peel_to_id().map_err(other)?.detach();
+        if is_merged_into(repo, commit.id, tip) {
+            branches.push(name);
+        }
+    }
+
+    Ok(Some(branches))
+}
+
+pub(crate) fn detached_head_in(repo: &Repository) -> Result<Option<DetachTarget>> {
+    let head = repo.head().map_err(other)?;
+    if !head.is_detached() {
+        return Ok(None);
+    }
+
+    let commit = repo.head_commit().map_err(other)?;
+    let name = short_id(&commit)?;
+    Ok(Some(detach_target(&commit, name, false)?))
+}
+
+/// The commit's id abbreviated like git does
+fn short_id(commit: &gix::Commit<'_>) -> Result<String> {
+    Ok(commit.short_id().map_err(other)?.to_string())
+}
+
+fn detach_target(commit: &gix::Commit<'_>, name: String, is_tag: bool) -> Result<DetachTarget> {
+    Ok(DetachTarget {
+        name,
+        commit: commit.id.to_string(),
+        summary: summary(commit)?,
+        commit_time: commit.time().map_err(other)?.seconds,
+        is_tag,
+    })
+}
+
+pub(crate) fn has_uncommitted_changes_in(repo: &Repository) -> Result<bool> {
+    Ok(!dirty_paths(repo)?.is_empty())
+}
+
+pub(crate) fn unpushed_commits_in(repo: &Repository, branch: &str) -> Result<usize> {
+    let tip = find_branch(repo, branch)
+        .ok_or_else(|| GgoError::BranchNotFound(branch.to_string()))?
+        .peel_to_id()
+        .map_err(other)?
+        .detach();
+
+    let references = repo.references().map_err(other)?;
+    let mut remote_tips = Vec::new();
+    for reference in references.remote_branches().map_err(other)? {
+        if let Ok(id) = reference.map_err(other)?.peel_to_id() {
+            remote_tips.push(id.detach());
+        }
+    }
+
+    if remote_tips.is_empty() {
+        return Ok(0);
+    }
+    let walk = repo
+        .rev_walk([tip])
+        .with_hidden(remote_tips)
+        .all()
+        .map_err(other)?;
+    walk.into_iter()
+        .try_fold(0, |count, info| info.map(|_| count + 1))
+        .map_err(other)
+}
+
+pub(crate) fn worktrees_in(repo: &Repository) -> Result<Vec<Worktree>> {
+    // Linked worktrees are registered in the main repository
+    let main_repo;
+    let main = if repo.git_dir() != repo.common_dir() {
+        main_repo = repo.main_repo().map_err(other)?;
+        &main_repo
+    } else {
+        repo
+    };
+
+    let mut worktrees = Vec::new();
+
+    if let Some(workdir) = main.workdir() {
+        worktrees.push(Worktree {
+            path: workdir.to_path_buf(),
+            branch: head_branch(main),
+        });
+    }
+
+    for proxy in main.worktrees()? {
+        let Ok(path) = proxy.base() else {
+            continue;
+        };
+        // Skip stale entries whose directory was removed without pruning
+        let Ok(worktree_repo) = proxy.into_repo() else {
+            continue;
+        };
+        worktrees.push(Worktree {
+            path,
+            branch: head_branch(&worktree_repo),
+        });
+    }
+
+    Ok(worktrees)
+}
+
+/// Name of the branch HEAD points to, if any
+pub(crate) fn head_branch(repo: &Repository) -> Option<String> {
+    let head = repo.head().ok()?;
+    if head.is_unborn() {
+        return None;
+    }
+    let name = head
+        .referent_name()?
+        .as_bstr()
+        .strip_prefix(b"refs/heads/")?;
+    name.to_str().ok().map(str::to_string)
+}
+
+pub(crate) fn create_worktree_in(repo: &Repository, branch: &str, path: &Path) -> Result<()> {
+    let failed = |message: String| GgoError::WorktreeFailed(branch.to_string(), message);
+
+    let mut reference =
+        find_branch(repo, branch).ok_or_else(|| GgoError::BranchNotFound(branch.to_string()))?;
+    let tree = tree_of(&mut reference).map_err(|e| failed(e.to_string()))?;
+
+    if let Some(worktree) = worktrees_in(repo)?
+        .into_iter()
+        .find(|wt| wt.branch.as_deref() == Some(branch))
+    {
+        return Err(failed(format!(
+            "'{}' is already checked out at '{}'",
+            branch,
+            worktree.path.display()
+        )));
+    }
+    if fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_some()) || path.is_file() {
+        return Err(failed(format!("'{}' already exists", path.display())));
+    }
+
+    // Worktree names are directory names under .git/worktrees
+    let common = same_path_key(repo.common_dir());
+    let name = worktree_name(branch, |name| common.join("worktrees").join(name).exists());
+    let git_dir = common.join("worktrees").join(name);
+
+    let add = || -> Result<()> {
+        fs::create_dir_all(path)?;
+        let path = path.canonicalize()?;
+        fs::create_dir_all(&git_dir)?;
+        fs::write(
+            path.join(".git"),
+            format!("gitdir: {}\n", git_dir.display()),
+        )?;
+        fs::write(
+            git_dir.join("gitdir"),
+            format!("{}\n", path.join(".git").display()),
+        )?;
+        fs::write(git_dir.join("commondir"), "../..\n")?;
+        fs::write(
+            git_dir.join("HEAD"),
+            format!("ref: refs/heads/{}\n", branch),
+        )?;
+
+        let worktree = gix::open(&path).map_err(other)?;
+        let mut index = worktree.index_from_tree(&tree).map_err(other)?;
+        let mut options = worktree
+            .checkout_options(gix::worktree::stack::state::attributes::Source::IdMapping)
+            .map_err(other)?;
+        options.destination_is_initially_empty = true;
+        let outcome = gix::worktree::state::checkout(
+            &mut index,
+            &path,
+            worktree.objects.clone().into_arc().map_err(other)?,
+            &gix::progress::Discard,
+            &gix::progress::Discard,
+            &AtomicBool::new(false),
+            options,
+        )
+        .map_err(other)?;
+        if let Some(error) = outcome.errors.first() {
+            return Err(other(format!("{}: {}", error.path, error.error)));
+        }
+        index.write(Default::default()).map_err(other)?;
+        Ok(())
+    };
+    add().map_err(|e| failed(e.to_string()))
+}
+
+/// Path of the main worktree; for a bare repository, where its worktree
+/// would be
+pub(crate) fn main_worktree_path(repo: &Repository) -> Result<PathBuf> {
+    let main = repo.main_repo().map_err(other)?;
+    match main.workdir() {
+        Some(workdir) => Ok(workdir.to_path_buf()),
+        None => Ok(bare_worktree_path(main.common_dir())),
+    }
+}
+
+/// Entries of a ref's reflog, newest first; none when it has no reflog
+fn reflog(reference: &gix::Reference<'_>) -> Vec<gix::refs::log::Line> {
+    let mut log = reference.log_iter();
+    let Ok(Some(lines)) = log.rev() else {
+        return Vec::new();
+    };
+    lines.filter_map(|line| line.ok()).collect()
+}
+
+/// The HEAD reflog of the current worktree, newest first
+fn head_reflog(repo: &Repository) -> Vec<gix::refs::log::Line> {
+    match repo.find_reference("HEAD") {
+        Ok(head) => reflog(&head),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub(crate) fn branch_renames_in(repo: &Repository) -> Result<Vec<(String, String)>> {
+    let mut renames = Vec::new();
+    for (_, reference) in branches(repo)? {
+        for line in reflog(&reference) {
+            let Some((old, new)) = line.message.to_str().ok().and_then(parse_rename) else {
+                continue;
+            };
+            renames.push((
+                line.signature.time.seconds,
+                old.to_string(),
+                new.to_string(),
+            ));
+        }
+    }
+
+    renames.sort_by_key(|(time, _, _)| *time);
+    Ok(renames
+        .into_iter()
+        .map(|(_, old, new)| (old, new))
+        .collect())
+}
+
+pub(crate) fn reflog_checkouts_in(repo: &Repository) -> Result<Vec<ReflogCheckout>> {
+    let branches = local_branch_names(repo)?;
+
+    let mut checkouts: Vec<ReflogCheckout> = head_reflog(repo)
+        .iter()
+        .filter_map(|line| {
+            let time = line.signature.time.seconds;
+            reflog_checkout(line.message.to_str().ok()?, time, &branches)
+        })
+        .collect();
+    // Entries come newest first
+    checkouts.reverse();
+    Ok(checkouts)
+}
+
+/// The commit each branch was at when it was last checked out or left,
+/// from the HEAD reflog ("checkout: moving from <old> to <new>")
+fn last_checked_out_commits(repo: &Repository) -> HashMap<String, ObjectId> {
+    let mut commits = HashMap::new();
+
+    // Entries come newest first, so the first mention of a branch wins
+    for line in head_reflog(repo) {
+        let Some((from, to)) = line.message.to_str().ok().and_then(parse_checkout) else {
+            continue;
+        };
+        commits.entry(from.to_string()).or_insert(line.previous_oid);
+        commits.entry(to.to_string()).or_insert(line.new_oid);
+    }
+    commits
+}
+
+/// Names of the local branches in a repository
+pub(crate) fn local_branch_names(repo: &Repository) -> Result<HashSet<String>> {
+    Ok(branches(repo)?.into_iter().map(|(name, _)| name).collect())
+}
+
+pub(crate) fn renamed_branches_in(
+    repo: &Repository,
+    missing: &[String],
+    recorded: &[String],
+) -> Vec<(String, String)> {
+    let mut tips: HashMap<String, ObjectId> = HashMap::new();
+    for (name, reference) in branches(repo).unwrap_or_default() {
+        if let Some(tip) = reference.target().try_id() {
+            tips.insert(name, tip.to_owned());
+        }
+    }
+    let renames = branch_renames_in(repo).unwrap_or_default();
+    let last_commits = last_checked_out_commits(repo);
+
+    match_renamed_branches(missing, recorded, &tips, &renames, &last_commits)
+}
+
+pub(crate) fn worktree_for_branch_in(repo: &Repository, branch: &str) -> Result<Option<PathBuf>> {
+    Ok(other_worktree_with(
+        worktrees_in(repo)?,
+        repo.workdir(),
+        branch,
+    ))
+}
+
+/// Create `refs/heads/<branch>` at `id`, failing if it exists
+fn new_branch(repo: &Repository, branch: &str, id: ObjectId, log_message: &str) -> Result<()> {
+    repo.reference(
+        format!("refs/heads/{}", branch).as_str(),
+        id,
+        PreviousValue::MustNotExist,
+        log_message,
+    )
+    .map_err(|e| GgoError::CreateBranchFailed(branch.to_string(), e.to_string()))?;
+    Ok(())
+}
+
+pub(crate) fn create_branch_in(repo: &Repository, branch: &str) -> Result<()> {
+    let commit = repo
+        .head_commit()
+        .map_err(|e| GgoError::CreateBranchFailed(branch.to_string(), e.to_string()))?;
+
+    new_branch(repo, branch, commit.id, "branch: Created from HEAD")
+}
+
+pub(crate) fn create_branch_at_in(repo: &Repository, branch: &str, commit_id: &str) -> Result<()> {
+    let commit = ObjectId::from_hex(commit_id.as_bytes())
+        .map_err(other)
+        .and_then(|id| repo.find_commit(id).map_err(other))
+        .map_err(|e| GgoError::CreateBranchFailed(branch.to_string(), e.to_string()))?;
+
+    new_branch(
+        repo,
+        branch,
+        commit.id,
+        &format!("branch: Created from {}", commit_id),
+    )
+}
+
+pub(crate) fn create_tracking_branch_in(
+    repo: &Repository,
+    branch: &str,
+    remote_branch: &str,
+) -> Result<()> {
+    let failed = |e: GgoError| GgoError::CreateBranchFailed(branch.to_string(), e.to_string());
+
+    let mut tracking = repo
+        .find_reference(format!("refs/remotes/{}", remote_branch).as_str())
+        .map_err(|e| failed(other(e)))?;
+    let commit = tracking.peel_to_commit().map_err(|e| failed(other(e)))?;
+    let (merge, remote) = repo
+        .upstream_branch_and_remote_for_tracking_branch(tracking.name())
+        .map_err(|e| failed(other(e)))?
+        .ok_or_else(|| failed(other(format!("no remote fetches into '{}'", remote_branch))))?;
+    let remote = remote
+        .name()
+        .map(|name| name.as_bstr().to_owned())
+        .ok_or_else(|| failed(other(format!("no remote fetches into '{}'", remote_branch))))?;
+
+    new_branch(
+        repo,
+        branch,
+        commit.id,
+        &format!("branch: Created from {}", remote_branch),
+    )?;
+    edit_config(repo, |config| {
+        let section = Some(BStr::new(branch));
+        config.set_raw_value_by("branch", section, "remote", remote.as_bstr())?;
+        config.set_raw_value_by("branch", section, "merge", merge.as_bstr())?;
+        Ok(())
+    })
+    .map_err(failed)
+}
+
+/// Change the repository's own config file (`.git/config`)
+fn edit_config(
+    repo: &Repository,
+    edit: impl FnOnce(
+        &mut gix::config::File<'static>,
+    ) -> std::result::Result<(), gix::config::file::set_raw_value::Error>,
+) -> Result<()> {
+    let path = repo.common_dir().join("config");
+    let mut config =
+        gix::config::File::from_path_no_includes(path.clone(), gix::config::Source::Local)
+            .map_err(other)?;
+    edit(&mut config).map_err(other)?;
+
+    let mut file = gix::lock::File::acquire_to_update_resource(
+        &path,
+        gix::lock::acquire::Fail::Immediately,
+        None,
+    )
+    .map_err(other)?;
+    config.write_to(&mut file)?;
+    file.commit().map_err(|e| other(e.error))?;
+    Ok(())
+}
+
+pub(crate) fn delete_branch_in(repo: &Repository, branch: &str, force: bool) -> Result<()> {
+    let mut local =
+        find_branch(repo, branch).ok_or_else(|| GgoError::BranchNotFound(branch.to_string()))?;
+
+    if head_ref_name(repo).is_some_and(|head| head.as_ref() == local.name()) {
+        return Err(GgoError::DeleteBranchFailed(
+            branch.to_string(),
+            "it is the current branch".to_string(),
+        ));
+    }
+
+    if !force {
+        let tip = local.peel_to_id().map_err(other)?.detach();
+        let upstream = upstream_ref(repo, local.name())
+            .and_then(|upstream| repo.try_find_reference(upstream.as_ref()).ok().flatten());
+        let target = match upstream {
+            Some(mut upstream) => upstream.peel_to_id().map_err(other)?.detach(),
+            None => repo.head_id().map_err(other)?.detach(),
+        };
+
+        if !is_merged_into(repo, tip, target) {
+            return Err(GgoError::BranchNotMerged(branch.to_string()));
+        }
+    }
+
+    local
+        .delete()
+        .map_err(|e| GgoError::DeleteBranchFailed(branch.to_string(), e.to_string()))?;
+    // Its upstream and description go with it, as with 'git branch -d'
+    edit_config(repo, |config| {
+        config.remove_section("branch", Some(BStr::new(branch)));
+        Ok(())
+    })
+}
+
+/// Whether every commit of `tip` is reachable from `target`
+fn is_merged_into(repo: &Repository, tip: ObjectId, target: ObjectId) -> bool {
+    tip == target || repo.merge_base(tip, target).is_ok_and(|base| base == tip)
+}
+
+pub(crate) fn prunable_branches_in(repo: &Repository) -> Result<Vec<PrunableBranch>> {
+    let (default_name, default_tip) = default_branch(repo)?;
+    let head = head_ref_name(repo);
+
+    let mut prunable = Vec::new();
+    for (name, mut reference) in branches(repo)? {
+        if head
+            .as_ref()
+            .is_some_and(|head| head.as_ref() == reference.name())
+            || name == default_name
+        {
+            continue;
+        }
+
+        let tip = reference.peel_to_id().map_err(other)?.detach();
+        if is_merged_into(repo, tip, default_tip) {
+            prunable.push(PrunableBranch {
+                name,
+                reason: PruneReason::Merged(default_name.clone()),
+            });
+            continue;
+        }
+
+        // An upstream configured for the branch whose remote-tracking ref no longer exists
+        if let Some(upstream) = upstream_ref(repo, reference.name()) {
+            if repo
+                .try_find_reference(upstream.as_ref())
+                .ok()
+                .flatten()
+                .is_none()
+            {
+                prunable.push(PrunableBranch {
+                    name,
+                    reason: PruneReason::UpstreamGone(short_upstream(
+                        &upstream.as_bstr().to_string(),
+                    )),
+                });
+            }
+        }
+    }
+
+    Ok(prunable)
+}
+
+/// The default branch and its tip: what origin/HEAD points to, else `main` or
+/// `master`, else the current branch
+fn default_branch(repo: &Repository) -> Result<(String, ObjectId)> {
+    if let Some(mut origin_head) = repo
+        .try_find_reference("refs/remotes/origin/HEAD")
+        .ok()
+        .flatten()
+    {
+        let name = origin_head
+            .target()
+            .try_name()
+            .and_then(|target| target.as_bstr().strip_prefix(b"refs/remotes/origin/"))
+            .and_then(|name| name.to_str().ok())
+            .map(str::to_string);
+        if let Some(name) = name {
+            let tip = match find_branch(repo, &name) {
+                Some(mut local) => local.peel_to_id().map_err(other)?.detach(),
+                None => origin_head.peel_to_id().map_err(other)?.detach(),
+            };
+            return Ok((name, tip));
+        }
+    }
+
+    for name in ["main", "master"] {
+        if let Some(mut local) = find_branch(repo, name) {
+            return Ok((
+                name.to_string(),
+                local.peel_to_id().map_err(other)?.detach(),
+            ));
+        }
+    }
+
+    let name = head_branch(repo).unwrap_or_else(|| "HEAD".to_string());
+    Ok((name, repo.head_id().map_err(other)?.detach()))
+}
+
+pub(crate) fn branch_ages_in(repo: &Repository) -> Result<Vec<BranchAge>> {
+    let (default_name, default_tip) = default_branch(repo)?;
+    let head = head_ref_name(repo);
+
+    let mut ages = Vec::new();
+    for (name, mut reference) in branches(repo)? {
+        if head
+            .as_ref()
+            .is_some_and(|head| head.as_ref() == reference.name())
+            || name == default_name
+        {
+            continue;
+        }
+        let Ok(tip) = reference.peel_to_commit() else {
+            continue;
+        };
+
+        let merged = is_merged_into(repo, tip.id, default_tip);
+        ages.push(BranchAge {
+            name,
+            last_commit: tip.committer().map_err(other)?.seconds(),
+            author_email: tip.author().map_err(other)?.email.to_string(),
+            merged_into: merged.then(|| default_name.clone()),
+        });
+    }
+
+    Ok(ages)
+}
+
+pub(crate) fn find_commits_in(
+    repo: &Repository,
+    query: &CommitQuery,
+    unmerged: bool,
+    mut progress: impl FnMut(usize, usize),
+) -> Result<Vec<CommitHit>> {
+    let mut branches = Vec::new();
+    for (name, mut reference) in self::branches(repo)? {
+        branches.push((name, reference.peel_to_id().map_err(other)?.detach()));
+    }
+
+    let base = if unmerged {
+        Some(default_branch(repo)?.1)
+    } else {
+        None
+    };
+    let message = query.message.as_deref().map(str::to_lowercase);
+    let author = query.author.as_deref().map(str::to_lowercase);
+
+    let mut hits = Vec::new();
+    for (done, (name, tip)) in branches.iter().enumerate() {
+        let mut candidates = vec![*tip];
+        if let Some(base) = base {
+            let walk = repo
+                .rev_walk([*tip])
+                .sorting(Sorting::ByCommitTime(Default::default()))
+                .with_hidden([base])
+                .all()
+                .map_err(other)?;
+            for info in walk.take(FIND_MAX_COMMITS) {
+                let id = info.map_err(other)?.id;
+                if id != *tip {
+                    candidates.push(id);
+                }
+            }
+        }
+
+        let mut newest: Option<gix::Commit<'_>> = None;
+        let mut count = 0;
+        for id in candidates {
+            let commit = repo.find_commit(id).map_err(other)?;
+            if commit_matches(
+                repo,
+                &commit,
+                message.as_deref(),
+                author.as_deref(),
+                query.touches.as_deref(),
+            )? {
+                count += 1;
+                if newest.is_none() {
+                    newest = Some(commit);
+                }
+            }
+        }
+
+        if let Some(commit) = newest {
+            hits.push(CommitHit {
+                branch: name.clone(),
+                commit: short_id(&commit)?,
+                summary: summary(&commit)?,
+                author: commit.author().map_err(other)?.name.to_string(),
+                time: commit.time().map_err(other)?.seconds,
+                count,
+            });
+        }
+        progress(done + 1, branches.len());
+    }
+
+    hits.sort_by_key(|hit| std::cmp::Reverse(hit.time));
+    Ok(hits)
+}
+
+pub(crate) fn user_email_in(repo: &Repository) -> Result<String> {
+    repo.config_snapshot()
+        .string("user.email")
+        .map(|email| email.to_string())
+        .ok_or(GgoError::UnknownAuthor)
+}
+
+pub(crate) fn branch_activity_in(repo: &Repository, email: &str) -> Result<Vec<BranchActivity>> {
+    let mut tips = Vec::new();
+    let local = local_branch_names(repo)?;
+    for (name, mut reference) in branches(repo)? {
+        if let Ok(tip) = reference.peel_to_id() {
+            tips.push((name, None, tip.detach()));
+        }
+    }
+    let references = repo.references().map_err(other)?;
+    for reference in references.remote_branches().map_err(other)? {
+        let mut reference = reference.map_err(other)?;
+        let Ok(name) = reference.name().shorten().to_str().map(str::to_string) else {
+            continue;
+        };
+        let Some((_, short)) = name.split_once('/') else {
+            continue;
+        };
+        if short == "HEAD" || local.contains(short) {
+            continue;
+        }
+        if let Ok(tip) = reference.peel_to_id() {
+            tips.push((short.to_string(), Some(name.clone()), tip.detach()));
+        }
+    }
+
+    let mut activity: Vec<BranchActivity> = Vec::new();
+    for (branch, remote, tip) in tips {
+        let walk = repo
+            .rev_walk([tip])
+            .sorting(Sorting::ByCommitTime(Default::default()))
+            .all()
+            .map_err(other)?;
+
+        let mut newest: Option<(i64, gix::Commit<'_>)> = None;
+        for info in walk.take(ACTIVITY_MAX_COMMITS) {
+            let commit = info.map_err(other)?.object().map_err(other)?;
+            let author = commit.author().map_err(other)?;
+            let mine = author
+                .email
+                .to_str()
+                .is_ok_and(|author| author.eq_ignore_ascii_case(email));
+            let time = author.seconds();
+            if mine && newest.as_ref().is_none_or(|(newest, _)| time > *newest) {
+                newest = Some((time, commit));
+            }
+        }
+        let Some((last_commit, commit)) = newest else {
+            continue;
+        };
+
+        merge_activity(
+            &mut activity,
+            BranchActivity {
+                branch,
+                remote,
+                last_commit,
+                summary: summary(&commit)?,
+            },
+        );
+    }
+
+    activity.sort_by_key(|a| std::cmp::Reverse(a.last_commit));
+    Ok(activity)
+}
+
+/// Whether a commit matches every given criterion; `message` and `author`
+/// are lowercase, `touches` is relative to the repository root
+fn commit_matches(
+    repo: &Repository,
+    commit: &gix::Commit<'_>,
+    message: Option<&str>,
+    author: Option<&str>,
+    touches: Option<&str>,
+) -> Result<bool> {
+    if let Some(author) = author {
+        let signature = commit.author().map_err(other)?;
+        let name = signature.name.to_str_lossy();
+        let email = signature.email.to_str_lossy();
+        if !author_matches(&name, &email, author) {
+            return Ok(false);
+        }
+    }
+
+    if let Some(message) = message {
+        let text = commit.message_raw().map_err(other)?.to_str_lossy();
+        if !text.to_lowercase().contains(message) {
+            return Ok(false);
+        }
+    }
+
+    if let Some(path) = touches {
+        let parent_tree = match commit.parent_ids().next() {
+            Some(parent) => Some(
+                parent
+                    .object()
+                    .map_err(other)?
+                    .peel_to_tree()
+                    .map_err(other)?,
+            ),
+            None => None,
+        };
+        let tree = commit.tree().map_err(other)?;
+        let changes = repo
+            .diff_tree_to_tree(parent_tree.as_ref(), &tree, gix::diff::Options::default())
+            .map_err(other)?;
+        if !changes
+            .iter()
+            .any(|change| path_touches(change.location(), path))
+        {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Whether a changed path is `path` or inside it, as libgit2's pathspecs
+/// match a file or directory
+fn path_touches(location: &BStr, path: &str) -> bool {
+    let path = path.trim_end_matches('/');
+    path.is_empty()
+        || location == path
+        || location
+            .strip_prefix(path.as_bytes())
+            .is_some_and(|rest| rest.starts_with(b"/"))
+}
+
+pub(crate) fn recent_commits_in(
+    repo: &Repository,
+    branch: &str,
+    limit: usize,
+) -> Result<Vec<String>> {
+    let tip = find_branch(repo, branch)
+        .ok_or_else(|| GgoError::BranchNotFound(branch.to_string()))?
+        .peel_to_id()
+        .map_err(other)?
+        .detach();
+
+    let walk = repo
+        .rev_walk([tip])
+        .sorting(Sorting::ByCommitTime(Default::default()))
+        .all()
+        .map_err(other)?;
+
+    let mut commits = Vec::new();
+    for info in walk.take(limit) {
+        let commit = info.map_err(other)?.object().map_err(other)?;
+        commits.push(format!("{} {}", short_id(&commit)?, summary(&commit)?));
+    }
+
+    Ok(commits)
+}
+
+pub(crate) fn origin_url_in(repo: &Repository) -> Result<Option<String>> {
+    // As configured, without the insteadOf rewrites gix would apply
+    Ok(repo
+        .config_snapshot()
+        .string("remote.origin.url")
+        .map(|url| url.to_string()))
+}
+
+pub(crate) fn current_branch_in(repo: &Repository) -> Result<String> {
+    // A bare repository's HEAD names a branch, but nothing is checked out
+    if repo.is_bare() {
+        return Err(GgoError::BareRepository);
+    }
+
+    let head = repo.head().map_err(|_| GgoError::NotGitRepository)?;
+    if head.is_unborn() {
+        return Err(GgoError::NotGitRepository);
+    }
+
+    if head.is_detached() {
+        let at = detached_head_in(repo)?
+            .map(|target| target.name)
+            .unwrap_or_default();
+        return Err(GgoError::DetachedHead(at));
+    }
+
+    head_branch(repo).ok_or_else(|| GgoError::Other("Invalid branch name".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_test_repo() -> (tempfile::TempDir, Repository) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(temp_dir.path()).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test User").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+
+        commit_files(
+            &repo,
+            "Initial",
+            &[("a.txt", Some("a")), ("dir/b.txt", Some("b"))],
+        );
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("feature", &head, false).unwrap();
+        repo.set_head("refs/heads/feature").unwrap();
+        commit_files(
+            &repo,
+            "Feature",
+            &[
+                ("a.txt", Some("a2")),
+                ("dir/b.txt", None),
+                ("c.txt", Some("c")),
+            ],
+        );
+        repo.set_head("refs/heads/master").unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+
+        let gix_repo = gix::open(temp_dir.path()).unwrap();
+        (temp_dir, gix_repo)
+    }
+
+    /// Commit on the branch HEAD points to, writing (Some) or deleting
+    /// (None) the files in the working tree and the index
+    fn commit_files(repo: &git2::Repository, message: &str, files: &[(&str, Option<&str>)]) {
+        let workdir = repo.workdir().unwrap();
+        let mut index = repo.index().unwrap();
+        for (path, content) in files {
+            match content {
+                Some(content) => {
+                    let file = workdir.join(path);
+                    fs::create_dir_all(file.parent().unwrap()).unwrap();
+                    fs::write(file, content).unwrap();
+                    index.add_path(Path::new(path)).unwrap();
+                }
+                None => {
+                    fs::remove_file(workdir.join(path)).unwrap();
+                    index.remove_path(Path::new(path)).unwrap();
+                }
+            }
+        }
+        index.write().unwrap();
+
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = repo.signature().unwrap();
+        let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+            .unwrap();
+    }
+
+    fn git_status(path: &Path) -> String {
+        let output = std::process::Command::new("git")
+            .args(["status", "--porcelain"])
+            .current_dir(path)
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn test_checkout_switches_files_index_and_head() {
+        let (temp_dir, repo) = setup_test_repo();
+        let path = temp_dir.path();
+
+        checkout_in(&repo, "feature").unwrap();
+
+        assert_eq!(fs::read_to_string(path.join("a.txt")).unwrap(), "a2");
+        assert_eq!(fs::read_to_string(path.join("c.txt")).unwrap(), "c");
+        assert!(!path.join("dir").exists());
+        assert_eq!(git_status(path), "");
+
+        let repo = gix::open(path).unwrap();
+        assert_eq!(head_branch(&repo).as_deref(), Some("feature"));
+        let checkouts = reflog_checkouts_in(&repo).unwrap();
+        let last = checkouts.last().unwrap();
+        assert_eq!(
+            (last.from.as_deref(), last.to.as_str()),
+            (Some("master"), "feature")
+        );
+    }
+
+    #[test]
+    fn test_checkout_keeps_unrelated_local_changes() {
+        let (temp_dir, repo) = setup_test_repo();
+        let path = temp_dir.path();
+        fs::write(path.join("untracked.txt"), "mine").unwrap();
+
+        checkout_in(&repo, "feature").unwrap();
+        checkout_in(&gix::open(path).unwrap(), "master").unwrap();
+
+        assert_eq!(fs::read_to_string(path.join("dir/b.txt")).unwrap(), "b");
+        assert_eq!(git_status(path), "?? untracked.txt\n");
+    }
+
+    #[test]
+    fn test_checkout_refuses_to_overwrite_local_changes() {
+        let (temp_dir, repo) = setup_test_repo();
+        let path = temp_dir.path();
+        fs::write(path.join("a.txt"), "edited").unwrap();
+
+        let result = checkout_in(&repo, "feature");
+        assert!(
+            matches!(&result, Err(GgoError::CheckoutFailed(_, message)) if message.contains("a.txt")),
+            "{:?}",
+            result
+        );
+        assert_eq!(fs::read_to_string(path.join("a.txt")).unwrap(), "edited");
+        assert_eq!(head_branch(&repo).as_deref(), Some("master"));
+
+        fs::write(path.join("a.txt"), "a").unwrap();
+        fs::write(path.join("c.txt"), "untracked").unwrap();
+        assert!(checkout_in(&repo, "feature").is_err());
+        assert_eq!(fs::read_to_string(path.join("c.txt")).unwrap(), "untracked");
+    }
+
+    #[test]
+    fn test_detach_at_commit() {
+        let (temp_dir, repo) = setup_test_repo();
+        let commit = find_branch(&repo, "feature")
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+
+        detach_at(&repo, &commit, "feature").unwrap();
+
+        let repo = gix::open(temp_dir.path()).unwrap();
+        let detached = detached_head_in(&repo).unwrap().unwrap();
+        assert_eq!(detached.commit, commit.id.to_string());
+        assert_eq!(git_status(temp_dir.path()), "");
+    }
+
+    #[test]
+    fn test_path_touches() {
+        assert!(path_touches("src/git.rs".into(), "src/git.rs"));
+        assert!(path_touches("src/git.rs".into(), "src"));
+        assert!(path_touches("src/git.rs".into(), "src/"));
+        assert!(!path_touches("src/git.rs".into(), "sr"));
+        assert!(!path_touches("src-old/git.rs".into(), "src"));
+    }
+}
@@ -0,0 +1,997 @@
+//! [`crate::git`] with libgit2, plus the `git switch` checkouts
+//! [`CliBackend`](crate::backend::CliBackend) makes.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+pub(crate) use git2::Repository;
+
+use super::{
+    author_matches, bare_worktree_path, common_dir_of, description_branch, match_renamed_branches,
+    merge_activity, other_worktree_with, parse_checkout, parse_rename, reflog_checkout,
+    short_upstream, worktree_name, BranchActivity, BranchAge, BranchInfo, CommitHit, CommitQuery,
+    DetachTarget, PrunableBranch, PruneReason, ReflogCheckout, Worktree,
+};
+use crate::constants::git::{ACTIVITY_MAX_COMMITS, FIND_MAX_COMMITS, GIT_SWITCH_MIN_VERSION};
+use crate::error::{GgoError, Result};
+
+/// Opens the repository like git does: `GIT_DIR` if set, else the first one
+/// found walking up from the current directory
+pub(crate) fn open() -> Result<Repository> {
+    Repository::open_from_env().map_err(|_| GgoError::NotGitRepository)
+}
+
+/// The repository at `path`, if there is one
+pub(crate) fn open_at(path: &Path) -> Option<Repository> {
+    Repository::open(path).ok()
+}
+
+/// Git directory shared by all worktrees of a repository
+pub(crate) fn common_dir(repo: &Repository) -> PathBuf {
+    common_dir_of(repo.path())
+}
+
+/// The working tree, None for a bare repository
+pub(crate) fn workdir(repo: &Repository) -> Option<&Path> {
+    repo.workdir()
+}
+
+pub(crate) fn local_branches_in(repo: &Repository) -> Result<Vec<String>> {
+    let mut branches = Vec::new();
+
+    for branch in repo.branches(Some(git2::BranchType::Local))? {
+        let (branch, _) = branch?;
+        if let Some(name) = branch.name()? {
+            branches.push(name.to_string());
+        }
+    }
+
+    Ok(branches)
+}
+
+pub(crate) fn branch_exists_in(repo: &Repository, branch: &str) -> bool {
+    repo.find_branch(branch, git2::BranchType::Local).is_ok()
+}
+
+pub(crate) fn list_branches_in(repo: &Repository) -> Result<Vec<BranchInfo>> {
+    let mut branches = Vec::new();
+    let mut descriptions = branch_descriptions_in(repo)?;
+
+    for branch in repo.branches(Some(git2::BranchType::Local))? {
+        let (branch, _) = branch?;
+        if let Some(info) = branch_info(repo, &branch, &mut descriptions)? {
+            branches.push(info);
+        }
+    }
+
+    Ok(branches)
+}
+
+pub(crate) fn branch_infos_in(repo: &Repository, names: &[String]) -> Result<Vec<BranchInfo>> {
+    let mut descriptions = branch_descriptions_in(repo)?;
+    let mut branches = Vec::with_capacity(names.len());
+
+    for name in names {
+        let Ok(branch) = repo.find_branch(name, git2::BranchType::Local) else {
+            continue;
+        };
+        if let Some(info) = branch_info(repo, &branch, &mut descriptions)? {
+            branches.push(info);
+        }
+    }
+
+    Ok(branches)
+}
+
+/// Upstream, tip commit and description of one branch; None when its name
+/// isn't UTF-8
+fn branch_info(
+    repo: &Repository,
+    branch: &git2::Branch,
+    descriptions: &mut HashMap<String, String>,
+) -> Result<Option<BranchInfo>> {
+    let name = match branch.name()? {
+        Some(name) => name.to_string(),
+        None => return Ok(None),
+    };
+
+    let tip = branch.get().peel_to_commit()?;
+
+    // No upstream configured, or its remote-tracking ref is gone
+    let (upstream, ahead_behind) = match branch.upstream() {
+        Ok(upstream) => {
+            let ahead_behind = upstream
+                .get()
+                .target()
+                .and_then(|upstream_tip| repo.graph_ahead_behind(tip.id(), upstream_tip).ok());
+            (upstream.name()?.map(String::from), ahead_behind)
+        }
+        Err(_) => (None, None),
+    };
+
+    Ok(Some(BranchInfo {
+        description: descriptions.remove(&name),
+        name,
+        upstream,
+        ahead_behind,
+        last_commit_time: tip.time().seconds(),
+        last_commit_summary: tip.summary().unwrap_or_default().to_string(),
+        is_head: branch.is_head(),
+    }))
+}
+
+pub(crate) fn branch_descriptions_in(repo: &Repository) -> Result<HashMap<String, String>> {
+    let config = repo.config()?;
+    let mut descriptions = HashMap::new();
+
+    let mut entries = config.entries(Some(r"^branch\..*\.description$"))?;
+    while let Some(entry) = entries.next() {
+        let entry = entry?;
+        let branch = entry.name().and_then(description_branch);
+        let description = entry.value().map(str::trim).filter(|d| !d.is_empty());
+
+        if let (Some(branch), Some(description)) = (branch, description) {
+            descriptions.insert(branch.to_string(), description.to_string());
+        }
+    }
+
+    Ok(descriptions)
+}
+
+/// Check out a local branch with `git switch` when the installed git has it,
+/// else with libgit2
+pub(crate) fn checkout(branch: &str) -> Result<()> {
+    if has_git_switch() {
+        return git_switch(&["--no-guess", branch], branch);
+    }
+
+    let repo = open()?;
+
+    // Find the branch reference
+    let refname = format!("refs/heads/{}", branch);
+    let obj = repo
+        .revparse_single(&refname)
+        .map_err(|_| GgoError::BranchNotFound(branch.to_string()))?;
+
+    // Checkout the branch
+    repo.checkout_tree(&obj, None)
+        .map_err(|e| GgoError::CheckoutFailed(branch.to_string(), e.to_string()))?;
+
+    // Update HEAD to point to the branch
+    repo.set_head(&refname)
+        .map_err(|e| GgoError::CheckoutFailed(branch.to_string(), e.to_string()))?;
+
+    Ok(())
+}
+
+pub(crate) fn checkout_detached(branch: &str) -> Result<()> {
+    let repo = open()?;
+
+    let refname = format!("refs/heads/{}", branch);
+    let commit = repo
+        .revparse_single(&refname)
+        .and_then(|obj| obj.peel_to_commit())
+        .map_err(|_| GgoError::BranchNotFound(branch.to_string()))?;
+
+    detach_at(&repo, &commit, branch)
+}
+
+pub(crate) fn checkout_commit(commit_id: &str) -> Result<()> {
+    let repo = open()?;
+
+    let commit = git2::Oid::from_str(commit_id)
+        .and_then(|oid| repo.find_commit(oid))
+        .map_err(|e| GgoError::CheckoutFailed(commit_id.to_string(), e.to_string()))?;
+
+    detach_at(&repo, &commit, commit_id)
+}
+
+fn detach_at(repo: &Repository, commit: &git2::Commit, target: &str) -> Result<()> {
+    if has_git_switch() {
+        return git_switch(&["--detach", &commit.id().to_string()], target);
+    }
+
+    repo.checkout_tree(commit.as_object(), None)
+        .map_err(|e| GgoError::CheckoutFailed(target.to_string(), e.to_string()))?;
+
+    repo.set_head_detached(commit.id())
+        .map_err(|e| GgoError::CheckoutFailed(target.to_string(), e.to_string()))?;
+
+    Ok(())
+}
+
+/// Run `git switch --quiet` with `args`, reporting a failure against `target`
+fn git_switch(args: &[&str], target: &str) -> Result<()> {
+    let output = Command::new("git")
+        .args(["switch", "--quiet"])
+        .args(args)
+        .output()
+        .map_err(|e| GgoError::CheckoutFailed(target.to_string(), e.to_string()))?;
+
+    if !output.status.success() {
+        return Err(GgoError::CheckoutFailed(
+            target.to_string(),
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Whether the `git` on PATH has `git switch`; asked once per run
+fn has_git_switch() -> bool {
+    static HAS_SWITCH: OnceLock<bool> = OnceLock::new();
+
+    *HAS_SWITCH.get_or_init(|| {
+        Command::new("git")
+            .arg("version")
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| parse_git_version(&String::from_utf8_lossy(&output.stdout)))
+            .is_some_and(|version| version >= GIT_SWITCH_MIN_VERSION)
+    })
+}
+
+/// Major and minor version from `git version` output
+/// (`git version 2.39.3 (Apple Git-146)`, `git version 2.41.0.windows.1`)
+fn parse_git_version(output: &str) -> Option<(u32, u32)> {
+    let version = output.trim().strip_prefix("git version ")?;
+    let mut parts = version.split(|c: char| !c.is_ascii_digit());
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+pub(crate) fn detach_targets_in(repo: &Repository, limit: usize) -> Result<Vec<DetachTarget>> {
+    let mut targets = list_tags_in(repo)?;
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(git2::Sort::TIME)?;
+    revwalk.push_glob("refs/heads/*")?;
+    for oid in revwalk.take(limit) {
+        let commit = repo.find_commit(oid?)?;
+        let name = short_id(&commit)?;
+        targets.push(detach_target(&commit, name, false));
+    }
+
+    Ok(targets)
+}
+
+pub(crate) fn list_tags_in(repo: &Repository) -> Result<Vec<DetachTarget>> {
+    let mut tags = Vec::new();
+
+    for name in repo.tag_names(None)?.iter().flatten() {
+        // Tags of trees or blobs can't be checked out
+        let commit = match repo
+            .revparse_single(&format!("refs/tags/{}", name))
+            .and_then(|obj| obj.peel_to_commit())
+        {
+            Ok(commit) => commit,
+            Err(_) => continue,
+        };
+        tags.push(detach_target(&commit, name.to_string(), true));
+    }
+    tags.sort_by_key(|t| std::cmp::Reverse(t.commit_time));
+
+    Ok(tags)
+}
+
+pub(crate) fn resolve_commit_in(repo: &Repository, rev: &str) -> Result<Option<DetachTarget>> {
+    let commit = match repo
+        .revparse_single(rev)
+        .and_then(|obj| obj.peel_to_commit())
+    {
+        Ok(commit) => commit,
+        Err(_) => return Ok(None),
+    };
+    let name = short_id(&commit)?;
+    Ok(Some(detach_target(&commit, name, false)))
+}
+
+pub(crate) fn branches_containing_in(repo: &Repository, rev: &str) -> Result<Option<Vec<String>>> {
+    let commit = match repo
+        .revparse_single(rev)
+        .and_then(|obj| obj.peel_to_commit())
+    {
+        Ok(commit) => commit.id(),
+        Err(_) => return Ok(None),
+    };
+
+    let mut branches = Vec::new();
+    for branch in repo.branches(Some(git2::BranchType::Local))? {
+        let (branch, _) = branch?;
+        let Some(name) = branch.name()? else {
+            continue;
+        };
+        if is_merged_into(repo, commit, branch.get().peel_to_commit()?.id())? {
+            branches.push(name.to_string());
+        }
+    }
+
+    Ok(Some(branches))
+}
+
+pub(crate) fn detached_head_in(repo: &Repository) -> Result<Option<DetachTarget>> {
+    if !repo.head_detached()? {
+        return Ok(None);
+    }
+
+    let commit = repo.head()?.peel_to_commit()?;
+    let name = short_id(&commit)?;
+    Ok(Some(detach_target(&commit, name, false)))
+}
+
+/// The commit's id abbreviated like git does
+fn short_id(commit: &git2::Commit) -> Result<String> {
+    let short_id = commit.as_object().short_id()?;
+    Ok(short_id.as_str().unwrap_or_default().to_string())
+}
+
+fn detach_target(commit: &git2::Commit, name: String, is_tag: bool) -> DetachTarget {
+    DetachTarget {
+        name,
+        commit: commit.id().to_string(),
+        summary: commit.summary().unwrap_or_default().to_string(),
+        commit_time: commit.time().seconds(),
+        is_tag,
+    }
+}
+
+pub(crate) fn has_uncommitted_changes_in(repo: &Repository) -> Result<bool> {
+    let mut options = git2::StatusOptions::new();
+    options
+        .include_untracked(false)
+        .include_ignored(false)
+        .exclude_submodules(true);
+    Ok(!repo.statuses(Some(&mut options))?.is_empty())
+}
+
+pub(crate) fn unpushed_commits_in(repo: &Repository, branch: &str) -> Result<usize> {
+    let tip = repo
+        .find_branch(branch, git2::BranchType::Local)
+        .map_err(|_| GgoError::BranchNotFound(branch.to_string()))?
+        .get()
+        .peel_to_commit()?
+        .id();
+
+    let mut walk = repo.revwalk()?;
+    walk.push(tip)?;
+
+    let mut remote_tips = 0;
+    for reference in repo.references_glob("refs/remotes/*")? {
+        if let Ok(commit) = reference?.peel_to_commit() {
+            walk.hide(commit.id())?;
+            remote_tips += 1;
+        }
+    }
+
+    if remote_tips == 0 {
+        return Ok(0);
+    }
+    Ok(walk.count())
+}
+
+pub(crate) fn worktrees_in(repo: &Repository) -> Result<Vec<Worktree>> {
+    // Linked worktrees are registered in the main repository
+    let main_repo;
+    let main = if repo.is_worktree() {
+        main_repo = Repository::open(common_dir(repo))?;
+        &main_repo
+    } else {
+        repo
+    };
+
+    let mut worktrees = Vec::new();
+
+    if let Some(workdir) = main.workdir() {
+        worktrees.push(Worktree {
+            path: workdir.to_path_buf(),
+            branch: head_branch(main),
+        });
+    }
+
+    for name in main.worktrees()?.iter().flatten() {
+        let worktree = main.find_worktree(name)?;
+
+        // Skip stale entries whose directory was removed without pruning
+        if worktree.validate().is_err() {
+            continue;
+        }
+
+        let worktree_repo = Repository::open_from_worktree(&worktree)?;
+        worktrees.push(Worktree {
+            path: worktree.path().to_path_buf(),
+            branch: head_branch(&worktree_repo),
+        });
+    }
+
+    Ok(worktrees)
+}
+
+/// Name of the branch HEAD points to, if any
+pub(crate) fn head_branch(repo: &Repository) -> Option<String> {
+    let head = repo.head().ok()?;
+    if head.is_branch() {
+        head.shorthand().map(|s| s.to_string())
+    } else {
+        None
+    }
+}
+
+pub(crate) fn create_worktree_in(repo: &Repository, branch: &str, path: &Path) -> Result<()> {
+    let reference = repo
+        .find_branch(branch, git2::BranchType::Local)
+        .map_err(|_| GgoError::BranchNotFound(branch.to_string()))?
+        .into_reference();
+
+    // Worktree names are directory names under .git/worktrees
+    let name = worktree_name(branch, |name| repo.find_worktree(name).is_ok());
+
+    let mut opts = git2::WorktreeAddOptions::new();
+    opts.reference(Some(&reference));
+    repo.worktree(&name, path, Some(&opts))
+        .map_err(|e| GgoError::WorktreeFailed(branch.to_string(), e.message().to_string()))?;
+
+    Ok(())
+}
+
+/// Path of the main worktree; for a bare repository, where its worktree
+/// would be
+pub(crate) fn main_worktree_path(repo: &Repository) -> Result<PathBuf> {
+    let common = common_dir(repo);
+    let main = Repository::open(&common)?;
+    match main.workdir() {
+        Some(workdir) => Ok(workdir.to_path_buf()),
+        None => Ok(bare_worktree_path(&common)),
+    }
+}
+
+pub(crate) fn branch_renames_in(repo: &Repository) -> Result<Vec<(String, String)>> {
+    Ok(reflog_renames(repo)?)
+}
+
+fn reflog_renames(repo: &Repository) -> std::result::Result<Vec<(String, String)>, git2::Error> {
+    let mut renames = Vec::new();
+    for branch in repo.branches(Some(git2::BranchType::Local))? {
+        let (branch, _) = branch?;
+        let Some(refname) = branch.get().name() else {
+            continue;
+        };
+        let Ok(reflog) = repo.reflog(refname) else {
+            continue;
+        };
+        for entry in reflog.iter() {
+            let Some((old, new)) = entry.message().and_then(parse_rename) else {
+                continue;
+            };
+            renames.push((
+                entry.committer().when().seconds(),
+                old.to_string(),
+                new.to_string(),
+            ));
+        }
+    }
+
+    renames.sort_by_key(|(time, _, _)| *time);
+    Ok(renames
+        .into_iter()
+        .map(|(_, old, new)| (old, new))
+        .collect())
+}
+
+pub(crate) fn reflog_checkouts_in(repo: &Repository) -> Result<Vec<ReflogCheckout>> {
+    let branches = local_branch_names(repo)?;
+    let Ok(reflog) = repo.reflog("HEAD") else {
+        return Ok(Vec::new());
+    };
+
+    let mut checkouts: Vec<ReflogCheckout> = reflog
+        .iter()
+        .filter_map(|entry| {
+            let time = entry.committer().when().seconds();
+            reflog_checkout(entry.message()?, time, &branches)
+        })
+        .collect();
+    // Entries come newest first
+    checkouts.reverse();
+    Ok(checkouts)
+}
+
+/// The commit each branch was at when it was last checked out or left,
+/// from the HEAD reflog ("checkout: moving from <old> to <new>")
+fn last_checked_out_commits(repo: &Repository) -> HashMap<String, git2::Oid> {
+    let mut commits = HashMap::new();
+    let Ok(reflog) = repo.reflog("HEAD") else {
+        return commits;
+    };
+
+    // Entries come newest first, so the first mention of a branch wins
+    for entry in reflog.iter() {
+        let Some((from, to)) = entry.message().and_then(parse_checkout) else {
+            continue;
+        };
+        commits.entry(from.to_string()).or_insert(entry.id_old());
+        commits.entry(to.to_string()).or_insert(entry.id_new());
+    }
+    commits
+}
+
+/// Names of the local branches in a repository
+pub(crate) fn local_branch_names(repo: &Repository) -> Result<HashSet<String>> {
+    let mut names = HashSet::new();
+    for branch in repo.branches(Some(git2::BranchType::Local))? {
+        let (branch, _) = branch?;
+        if let Some(name) = branch.name()? {
+            names.insert(name.to_string());
+        }
+    }
+    Ok(names)
+}
+
+pub(crate) fn renamed_branches_in(
+    repo: &Repository,
+    missing: &[String],
+    recorded: &[String],
+) -> Vec<(String, String)> {
+    let mut tips: HashMap<String, git2::Oid> = HashMap::new();
+    if let Ok(branches) = repo.branches(Some(git2::BranchType::Local)) {
+        for (branch, _) in branches.flatten() {
+            if let (Ok(Some(name)), Some(tip)) = (branch.name(), branch.get().target()) {
+                tips.insert(name.to_string(), tip);
+            }
+        }
+    }
+    let renames = reflog_renames(repo).unwrap_or_default();
+    let last_commits = last_checked_out_commits(repo);
+
+    match_renamed_branches(missing, recorded, &tips, &renames, &last_commits)
+}
+
+pub(crate) fn worktree_for_branch_in(repo: &Repository, branch: &str) -> Result<Option<PathBuf>> {
+    Ok(other_worktree_with(
+        worktrees_in(repo)?,
+        repo.workdir(),
+        branch,
+    ))
+}
+
+pub(crate) fn create_branch_in(repo: &Repository, branch: &str) -> Result<()> {
+    let commit = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .map_err(|e| GgoError::CreateBranchFailed(branch.to_string(), e.message().to_string()))?;
+
+    repo.branch(branch, &commit, false)
+        .map_err(|e| GgoError::CreateBranchFailed(branch.to_string(), e.message().to_string()))?;
+
+    Ok(())
+}
+
+pub(crate) fn create_branch_at_in(repo: &Repository, branch: &str, commit_id: &str) -> Result<()> {
+    let commit = git2::Oid::from_str(commit_id)
+        .and_then(|oid| repo.find_commit(oid))
+        .map_err(|e| GgoError::CreateBranchFailed(branch.to_string(), e.message().to_string()))?;
+
+    repo.branch(branch, &commit, false)
+        .map_err(|e| GgoError::CreateBranchFailed(branch.to_string(), e.message().to_string()))?;
+
+    Ok(())
+}
+
+pub(crate) fn create_tracking_branch_in(
+    repo: &Repository,
+    branch: &str,
+    remote_branch: &str,
+) -> Result<()> {
+    let failed =
+        |e: git2::Error| GgoError::CreateBranchFailed(branch.to_string(), e.message().to_string());
+
+    let commit = repo
+        .find_branch(remote_branch, git2::BranchType::Remote)
+        .and_then(|remote| remote.get().peel_to_commit())
+        .map_err(failed)?;
+    repo.branch(branch, &commit, false)
+        .and_then(|mut local| local.set_upstream(Some(remote_branch)))
+        .map_err(failed)?;
+
+    Ok(())
+}
+
+pub(crate) fn delete_branch_in(repo: &Repository, branch: &str, force: bool) -> Result<()> {
+    let mut local = repo
+        .find_branch(branch, git2::BranchType::Local)
+        .map_err(|_| GgoError::BranchNotFound(branch.to_string()))?;
+
+    if local.is_head() {
+        return Err(GgoError::DeleteBranchFailed(
+            branch.to_string(),
+            "it is the current branch".to_string(),
+        ));
+    }
+
+    if !force {
+        let tip = local.get().peel_to_commit()?.id();
+        let target = match local.upstream() {
+            Ok(upstream) => upstream.get().peel_to_commit()?.id(),
+            Err(_) => repo.head()?.peel_to_commit()?.id(),
+        };
+
+        if !is_merged_into(repo, tip, target)? {
+            return Err(GgoError::BranchNotMerged(branch.to_string()));
+        }
+    }
+
+    local
+        .delete()
+        .map_err(|e| GgoError::DeleteBranchFailed(branch.to_string(), e.message().to_string()))?;
+
+    Ok(())
+}
+
+/// Whether every commit of `tip` is reachable from `target`
+fn is_merged_into(repo: &Repository, tip: git2::Oid, target: git2::Oid) -> Result<bool> {
+    Ok(tip == target || repo.graph_descendant_of(target, tip)?)
+}
+
+pub(crate) fn prunable_branches_in(repo: &Repository) -> Result<Vec<PrunableBranch>> {
+    let (default_name, default_tip) = default_branch(repo)?;
+
+    let mut prunable = Vec::new();
+    for branch in repo.branches(Some(git2::BranchType::Local))? {
+        let (branch, _) = branch?;
+        let name = match branch.name()? {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+
+        if branch.is_head() || name == default_name {
+            continue;
+        }
+
+        let tip = branch.get().peel_to_commit()?.id();
+        if is_merged_into(repo, tip, default_tip)? {
+            prunable.push(PrunableBranch {
+                name,
+                reason: PruneReason::Merged(default_name.clone()),
+            });
+            continue;
+        }
+
+        // An upstream configured for the branch whose remote-tracking ref no longer exists
+        if let Some(refname) = branch.get().name() {
+            if let Ok(upstream) = repo.branch_upstream_name(refname) {
+                let upstream = upstream.as_str().unwrap_or_default().to_string();
+                if repo.find_reference(&upstream).is_err() {
+                    prunable.push(PrunableBranch {
+                        name,
+                        reason: PruneReason::UpstreamGone(short_upstream(&upstream)),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(prunable)
+}
+
+/// The default branch and its tip: what origin/HEAD points to, else `main` or
+/// `master`, else the current branch
+fn default_branch(repo: &Repository) -> Result<(String, git2::Oid)> {
+    if let Ok(origin_head) = repo.find_reference("refs/remotes/origin/HEAD") {
+        if let Some(target) = origin_head.symbolic_target() {
+            if let Some(name) = target.strip_prefix("refs/remotes/origin/") {
+                let tip = match repo.find_branch(name, git2::BranchType::Local) {
+                    Ok(local) => local.get().peel_to_commit()?.id(),
+                    Err(_) => origin_head.peel_to_commit()?.id(),
+                };
+                return Ok((name.to_string(), tip));
+            }
+        }
+    }
+
+    for name in ["main", "master"] {
+        if let Ok(local) = repo.find_branch(name, git2::BranchType::Local) {
+            return Ok((name.to_string(), local.get().peel_to_commit()?.id()));
+        }
+    }
+
+    let head = repo.head()?;
+    let name = head.shorthand().unwrap_or("HEAD").to_string();
+    Ok((name, head.peel_to_commit()?.id()))
+}
+
+pub(crate) fn branch_ages_in(repo: &Repository) -> Result<Vec<BranchAge>> {
+    let (default_name, default_tip) = default_branch(repo)?;
+
+    let mut ages = Vec::new();
+    for branch in repo.branches(Some(git2::BranchType::Local))? {
+        let (branch, _) = branch?;
+        let (Some(name), Ok(tip)) = (branch.name()?, branch.get().peel_to_commit()) else {
+            continue;
+        };
+        if branch.is_head() || name == default_name {
+            continue;
+        }
+
+        let merged = is_merged_into(repo, tip.id(), default_tip)?;
+        ages.push(BranchAge {
+            name: name.to_string(),
+            last_commit: tip.committer().when().seconds(),
+            author_email: tip.author().email().unwrap_or_default().to_string(),
+            merged_into: merged.then(|| default_name.clone()),
+        });
+    }
+
+    Ok(ages)
+}
+
+pub(crate) fn find_commits_in(
+    repo: &Repository,
+    query: &CommitQuery,
+    unmerged: bool,
+    mut progress: impl FnMut(usize, usize),
+) -> Result<Vec<CommitHit>> {
+    let mut branches = Vec::new();
+    for branch in repo.branches(Some(git2::BranchType::Local))? {
+        let (branch, _) = branch?;
+        if let Some(name) = branch.name()? {
+            branches.push((name.to_string(), branch.get().peel_to_commit()?.id()));
+        }
+    }
+
+    let base = if unmerged {
+        Some(default_branch(repo)?.1)
+    } else {
+        None
+    };
+    let message = query.message.as_deref().map(str::to_lowercase);
+    let author = query.author.as_deref().map(str::to_lowercase);
+
+    let mut hits = Vec::new();
+    for (done, (name, tip)) in branches.iter().enumerate() {
+        let mut candidates = vec![*tip];
+        if let Some(base) = base {
+            let mut revwalk = repo.revwalk()?;
+            revwalk.set_sorting(git2::Sort::TIME)?;
+            revwalk.push(*tip)?;
+            revwalk.hide(base)?;
+            for oid in revwalk.take(FIND_MAX_COMMITS) {
+                let oid = oid?;
+                if oid != *tip {
+                    candidates.push(oid);
+                }
+            }
+        }
+
+        let mut newest: Option<git2::Commit> = None;
+        let mut count = 0;
+        for oid in candidates {
+            let commit = repo.find_commit(oid)?;
+            if commit_matches(
+                repo,
+                &commit,
+                message.as_deref(),
+                author.as_deref(),
+                query.touches.as_deref(),
+            )? {
+                count += 1;
+                if newest.is_none() {
+                    newest = Some(commit);
+                }
+            }
+        }
+
+        if let Some(commit) = newest {
+            hits.push(CommitHit {
+                branch: name.clone(),
+                commit: short_id(&commit)?,
+                summary: commit.summary().unwrap_or_default().to_string(),
+                author: commit.author().name().unwrap_or_default().to_string(),
+                time: commit.time().seconds(),
+                count,
+            });
+        }
+        progress(done + 1, branches.len());
+    }
+
+    hits.sort_by_key(|hit| std::cmp::Reverse(hit.time));
+    Ok(hits)
+}
+
+pub(crate) fn user_email_in(repo: &Repository) -> Result<String> {
+    repo.config()?
+        .get_string("user.email")
+        .map_err(|_| GgoError::UnknownAuthor)
+}
+
+pub(crate) fn branch_activity_in(repo: &Repository, email: &str) -> Result<Vec<BranchActivity>> {
+    let local = local_branch_names(repo)?;
+    let mut tips = Vec::new();
+    for branch in repo.branches(None)? {
+        let (branch, kind) = branch?;
+        let (Some(name), Ok(tip)) = (branch.name()?, branch.get().peel_to_commit()) else {
+            continue;
+        };
+        match kind {
+            git2::BranchType::Local => tips.push((name.to_string(), None, tip.id())),
+            git2::BranchType::Remote => {
+                let Some((_, short)) = name.split_once('/') else {
+                    continue;
+                };
+                if short != "HEAD" && !local.contains(short) {
+                    tips.push((short.to_string(), Some(name.to_string()), tip.id()));
+                }
+            }
+        }
+    }
+
+    let mut activity: Vec<BranchActivity> = Vec::new();
+    for (branch, remote, tip) in tips {
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TIME)?;
+        revwalk.push(tip)?;
+
+        let mut newest: Option<git2::Commit> = None;
+        for oid in revwalk.take(ACTIVITY_MAX_COMMITS) {
+            let commit = repo.find_commit(oid?)?;
+            let mine = commit
+                .author()
+                .email()
+                .is_some_and(|author| author.eq_ignore_ascii_case(email));
+            let time = commit.author().when().seconds();
+            if mine
+                && newest
+                    .as_ref()
+                    .is_none_or(|newest| time > newest.author().when().seconds())
+            {
+                newest = Some(commit);
+            }
+        }
+        let Some(commit) = newest else {
+            continue;
+        };
+
+        merge_activity(
+            &mut activity,
+            BranchActivity {
+                branch,
+                remote,
+                last_commit: commit.author().when().seconds(),
+                summary: commit.summary().unwrap_or_default().to_string(),
+            },
+        );
+    }
+
+    activity.sort_by_key(|a| std::cmp::Reverse(a.last_commit));
+    Ok(activity)
+}
+
+/// Whether a commit matches every given criterion; `message` and `author`
+/// are lowercase, `touches` is relative to the repository root
+fn commit_matches(
+    repo: &Repository,
+    commit: &git2::Commit,
+    message: Option<&str>,
+    author: Option<&str>,
+    touches: Option<&str>,
+) -> Result<bool> {
+    if let Some(author) = author {
+        let signature = commit.author();
+        let name = signature.name().unwrap_or_default();
+        let email = signature.email().unwrap_or_default();
+        if !author_matches(name, email, author) {
+            return Ok(false);
+        }
+    }
+
+    if let Some(message) = message {
+        if !commit
+            .message()
+            .unwrap_or_default()
+            .to_lowercase()
+            .contains(message)
+        {
+            return Ok(false);
+        }
+    }
+
+    if let Some(path) = touches {
+        let parent_tree = match commit.parents().next() {
+            Some(parent) => Some(parent.tree()?),
+            None => None,
+        };
+        let mut options = git2::DiffOptions::new();
+        options.pathspec(path);
+        let diff = repo.diff_tree_to_tree(
+            parent_tree.as_ref(),
+            Some(&commit.tree()?),
+            Some(&mut options),
+        )?;
+        if diff.deltas().len() == 0 {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+pub(crate) fn recent_commits_in(
+    repo: &Repository,
+    branch: &str,
+    limit: usize,
+) -> Result<Vec<String>> {
+    let tip = repo
+        .find_branch(branch, git2::BranchType::Local)
+        .map_err(|_| GgoError::BranchNotFound(branch.to_string()))?
+        .get()
+        .peel_to_commit()?;
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(tip.id())?;
+
+    let mut commits = Vec::new();
+    for oid in revwalk.take(limit) {
+        let commit = repo.find_commit(oid?)?;
+        commits.push(format!(
+            "{} {}",
+            short_id(&commit)?,
+            commit.summary().unwrap_or_default()
+        ));
+    }
+
+    Ok(commits)
+}
+
+pub(crate) fn origin_url_in(repo: &Repository) -> Result<Option<String>> {
+    let remote = match repo.find_remote("origin") {
+        Ok(remote) => remote,
+        Err(_) => return Ok(None),
+    };
+
+    Ok(remote.url().map(String::from))
+}
+
+pub(crate) fn current_branch_in(repo: &Repository) -> Result<String> {
+    // A bare repository's HEAD names a branch, but nothing is checked out
+    if repo.is_bare() {
+        return Err(GgoError::BareRepository);
+    }
+
+    let head = repo.head().map_err(|_| GgoError::NotGitRepository)?;
+
+    if !head.is_branch() {
+        let at = detached_head_in(repo)?
+            .map(|target| target.name)
+            .unwrap_or_default();
+        return Err(GgoError::DetachedHead(at));
+    }
+
+    let branch_name = head
+        .shorthand()
+        .ok_or_else(|| GgoError::Other("Invalid branch name".to_string()))?;
+
+    Ok(branch_name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_git_version() {
+        assert_eq!(parse_git_version("git version 2.43.0\n"), Some((2, 43)));
+        assert_eq!(
+            parse_git_version("git version 2.39.3 (Apple Git-146)"),
+            Some((2, 39))
+        );
+        assert_eq!(
+            parse_git_version("git version 2.41.0.windows.1"),
+            Some((2, 41))
+        );
+        assert_eq!(parse_git_version("hub version 2.14.2"), None);
+        assert!(parse_git_version("git version 2.22.5").unwrap() < GIT_SWITCH_MIN_VERSION);
+    }
+}
@@ -0,0 +1,108 @@
+//! Named groups of branches. `ggo group add` stores them per repository, and
+//! a repository's `.ggo.toml` can define more for everyone working on it:
+//!
+//! ```toml
+//! [groups]
+//! backend = ["feature/api*", "svc/*"]
+//! ```
+//!
+//! Members are branch names or glob patterns; git doesn't allow `*` or `?` in
+//! branch names, so a name only ever matches itself.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::constants::hooks::REPO_HOOKS_FILE;
+use crate::error::{GgoError, Result};
+use crate::matcher;
+
+/// Groups section of a repository's `.ggo.toml`
+#[derive(Debug, Default, Deserialize)]
+struct RepoGroupsFile {
+    #[serde(default)]
+    groups: BTreeMap<String, Vec<String>>,
+}
+
+/// Groups from `.ggo.toml` in `repo_root` (none without the file)
+pub fn repo_groups(repo_root: &Path) -> Result<BTreeMap<String, Vec<String>>> {
+    let path = repo_root.join(REPO_HOOKS_FILE);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let file: RepoGroupsFile = toml::from_str(&content)
+        .map_err(|e| GgoError::ConfigError(format!("Failed to parse {}: {}", path.display(), e)))?;
+    Ok(file.groups)
+}
+
+/// Combine the stored groups with the repository's; a group defined in both
+/// gets the members of each
+pub fn merge(
+    mut stored: BTreeMap<String, Vec<String>>,
+    repo: BTreeMap<String, Vec<String>>,
+) -> BTreeMap<String, Vec<String>> {
+    for (group, patterns) in repo {
+        let members = stored.entry(group).or_default();
+        for pattern in patterns {
+            if !members.contains(&pattern) {
+                members.push(pattern);
+            }
+        }
+    }
+    stored
+}
+
+/// Whether `branch` is one of the members, by name or glob pattern
+pub fn contains(members: &[String], branch: &str) -> bool {
+    members
+        .iter()
+        .any(|pattern| matcher::glob_matches(pattern, branch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(members: &[&str]) -> Vec<String> {
+        members.iter().map(|m| m.to_string()).collect()
+    }
+
+    #[test]
+    fn test_repo_groups() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(repo_groups(dir.path()).unwrap().is_empty());
+
+        std::fs::write(
+            dir.path().join(REPO_HOOKS_FILE),
+            "[hooks]\npost_checkout = [\"make\"]\n\n[groups]\nbackend = [\"feature/api*\", \"svc/*\"]\n",
+        )
+        .unwrap();
+        let groups = repo_groups(dir.path()).unwrap();
+        assert_eq!(groups["backend"], group(&["feature/api*", "svc/*"]));
+
+        std::fs::write(dir.path().join(REPO_HOOKS_FILE), "[groups]\nbackend = 1\n").unwrap();
+        assert!(repo_groups(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_merge_and_contains() {
+        let stored = BTreeMap::from([("backend".to_string(), group(&["feature/db", "svc/*"]))]);
+        let repo = BTreeMap::from([
+            ("backend".to_string(), group(&["svc/*", "feature/api*"])),
+            ("ui".to_string(), group(&["web/*"])),
+        ]);
+
+        let groups = merge(stored, repo);
+        let backend = &groups["backend"];
+        assert_eq!(backend, &group(&["feature/db", "svc/*", "feature/api*"]));
+        assert_eq!(groups["ui"], group(&["web/*"]));
+
+        assert!(contains(backend, "feature/db"));
+        assert!(contains(backend, "feature/api-v2"));
+        assert!(!contains(backend, "feature/dbx"));
+        assert!(!contains(backend, "main"));
+    }
+}
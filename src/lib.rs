@@ -0,0 +1,73 @@
+//! ggo's branch matching, frecency ranking, switching, and usage history.
+//!
+//! Editor plugins and other tools can use it to rank branches ([`query`])
+//! and switch to them ([`switch`]) the way the `ggo` binary does, without
+//! shelling out. Those don't print: a switch returns what it did
+//! ([`switch::Switched`]) and its warnings go to a callback
+//! ([`switch::SwitchOptions::warn`]). The terminal side lives here too,
+//! apart from them: the selection menus ([`interactive`], [`tui`],
+//! [`fzf`]), `--format` templates ([`template`]), `ggo serve`
+//! ([`server`]) and the command handlers ([`commands`]); the binary only
+//! parses arguments and dispatches to them.
+//!
+//! ```no_run
+//! use ggo::{fuzzy_filter_branches, Config, Storage};
+//!
+//! let config = Config::load().unwrap_or_default();
+//! let storage = Storage::open()?;
+//! let records = storage.branch_records("/path/to/repo/")?;
+//!
+//! let branches = vec!["main".to_string(), "feature/auth".to_string()];
+//! let ranked = ggo::rank_matching_branches(
+//!     &branches,
+//!     "auth",
+//!     false,
+//!     true,
+//!     &records,
+//!     &config.scoring_for("/path/to/repo/"),
+//!     &config.matching.bonuses(),
+//! );
+//! # let _ = fuzzy_filter_branches(&branches, "auth", false);
+//! # Ok::<(), ggo::GgoError>(())
+//! ```
+
+#[cfg(not(any(feature = "libgit2", feature = "gix")))]
+compile_error!("ggo needs a git backend: enable the `libgit2` feature, `gix`, or both");
+
+pub mod alias;
+pub mod backend;
+pub mod commands;
+pub mod config;
+pub mod constants;
+pub mod error;
+pub mod export;
+pub mod forge;
+pub mod frecency;
+pub mod fzf;
+pub mod git;
+pub mod groups;
+pub mod hooks;
+pub mod interactive;
+pub mod logfile;
+pub mod matcher;
+pub mod query;
+pub mod ranking;
+pub mod server;
+pub mod shell;
+pub mod stack;
+pub mod storage;
+pub mod style;
+pub mod switch;
+pub mod sync;
+pub mod template;
+pub mod tui;
+#[cfg(feature = "self-update")]
+pub mod update;
+pub mod validation;
+
+pub use config::Config;
+pub use error::{GgoError, Result};
+pub use frecency::rank_branches;
+pub use matcher::fuzzy_filter_branches;
+pub use ranking::rank_matching_branches;
+pub use storage::{HistoryStore, Storage};
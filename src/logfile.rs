@@ -0,0 +1,312 @@
+//! The invocation log: one JSON line per ggo run with the decisions it made
+//! (arguments, repository, ranked candidates with their scores, the branch
+//! picked, any error) and how long it took, for digging into "why did ggo
+//! pick that branch?" after the fact.
+//!
+//! Off by default; `log.enabled = true` writes to `<data dir>/logs/ggo.jsonl`,
+//! `--log-file <path>` logs one run anywhere. The file is rotated to
+//! `ggo.jsonl.1`, `ggo.jsonl.2`, ... once it grows past `log.max_size_kb`.
+
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::config::LogConfig;
+use crate::constants::log::{LOG_DIR, LOG_FILE, MAX_LOGGED_CANDIDATES};
+use crate::error::{GgoError, Result};
+use crate::storage;
+
+/// One logged invocation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Unix timestamp of the start of the run
+    pub timestamp: i64,
+    /// Command-line arguments, without the program name
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Best-ranked candidates first, as they were scored
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<Candidate>,
+    /// Branch switched to (or printed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selected: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// A ranked branch and its combined score
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candidate {
+    pub branch: String,
+    pub score: f64,
+}
+
+/// Where and how the current run is logged
+struct Target {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: u32,
+}
+
+/// The run being logged, if logging is on
+struct Session {
+    target: Target,
+    started: Instant,
+    entry: Entry,
+}
+
+static SESSION: Mutex<Option<Session>> = Mutex::new(None);
+
+/// The log file used with `log.enabled` (its directory is created on the
+/// first write)
+pub fn default_path() -> Result<PathBuf> {
+    Ok(storage::data_dir_location()?.0.join(LOG_DIR).join(LOG_FILE))
+}
+
+/// Start logging this run to `path`. Until then (and without it) the
+/// `record_*` calls do nothing.
+pub fn start(path: PathBuf, config: &LogConfig, args: Vec<String>) {
+    let session = Session {
+        target: Target {
+            path,
+            max_bytes: config.max_size_kb.saturating_mul(1024),
+            keep: config.keep,
+        },
+        started: Instant::now(),
+        entry: Entry {
+            timestamp: now(),
+            args,
+            repo: None,
+            pattern: None,
+            candidates: Vec::new(),
+            selected: None,
+            error: None,
+            duration_ms: 0,
+        },
+    };
+    *lock() = Some(session);
+}
+
+/// Note the repository the run works in
+pub fn record_repo(repo: &str) {
+    with_entry(|entry| entry.repo = Some(repo.to_string()));
+}
+
+/// Note the pattern and the candidates it ranked, best first
+pub fn record_candidates(pattern: &str, ranked: &[(String, f64)]) {
+    with_entry(|entry| {
+        entry.pattern = Some(pattern.to_string());
+        entry.candidates = ranked
+            .iter()
+            .take(MAX_LOGGED_CANDIDATES)
+            .map(|(branch, score)| Candidate {
+                branch: branch.clone(),
+                score: *score,
+            })
+            .collect();
+    });
+}
+
+/// Note the branch the run settled on
+pub fn record_selection(branch: &str) {
+    with_entry(|entry| entry.selected = Some(branch.to_string()));
+}
+
+/// Write the run's entry with its duration and outcome, rotating the file if
+/// it has grown too big. Does nothing if logging was never started.
+pub fn finish(error: Option<String>) -> Result<()> {
+    let Some(mut session) = lock().take() else {
+        return Ok(());
+    };
+    session.entry.error = error;
+    session.entry.duration_ms = session.started.elapsed().as_millis() as u64;
+
+    let mut line = serde_json::to_string(&session.entry)
+        .map_err(|e| GgoError::Other(format!("Failed to serialize log entry: {}", e)))?;
+    line.push('\n');
+    append(&session.target, &line)
+}
+
+/// Every entry in `path` and its rotated files, oldest first. Lines that
+/// don't parse (a run killed mid-write, an older format) are skipped.
+pub fn read_entries(path: &Path) -> Result<Vec<Entry>> {
+    let mut files: Vec<PathBuf> = (1..)
+        .map(|n| rotated_path(path, n))
+        .take_while(|rotated| rotated.exists())
+        .collect();
+    files.reverse();
+    files.push(path.to_path_buf());
+
+    let mut entries = Vec::new();
+    for file in files.iter().filter(|file| file.exists()) {
+        let content = std::fs::read_to_string(file)?;
+        entries.extend(
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str::<Entry>(line).ok()),
+        );
+    }
+    Ok(entries)
+}
+
+/// The `n` slowest entries, slowest first
+pub fn slowest(mut entries: Vec<Entry>, n: usize) -> Vec<Entry> {
+    entries.sort_by(|a, b| {
+        b.duration_ms
+            .cmp(&a.duration_ms)
+            .then(b.timestamp.cmp(&a.timestamp))
+    });
+    entries.truncate(n);
+    entries
+}
+
+fn append(target: &Target, line: &str) -> Result<()> {
+    if let Some(dir) = target.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let size = std::fs::metadata(&target.path)
+        .map(|m| m.len())
+        .unwrap_or(0);
+    if size > 0 && size + line.len() as u64 > target.max_bytes {
+        rotate(&target.path, target.keep)?;
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&target.path)?;
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Shift `ggo.jsonl` to `ggo.jsonl.1`, `.1` to `.2` and so on, dropping
+/// whatever would end up past `keep`
+fn rotate(path: &Path, keep: u32) -> Result<()> {
+    if keep == 0 {
+        std::fs::remove_file(path)?;
+        return Ok(());
+    }
+
+    let oldest = rotated_path(path, keep);
+    if oldest.exists() {
+        std::fs::remove_file(&oldest)?;
+    }
+    for n in (1..keep).rev() {
+        let from = rotated_path(path, n);
+        if from.exists() {
+            std::fs::rename(&from, rotated_path(path, n + 1))?;
+        }
+    }
+    std::fs::rename(path, rotated_path(path, 1))?;
+    Ok(())
+}
+
+fn rotated_path(path: &Path, n: u32) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+fn with_entry(update: impl FnOnce(&mut Entry)) {
+    if let Some(session) = lock().as_mut() {
+        update(&mut session.entry);
+    }
+}
+
+/// A panic elsewhere while holding the lock leaves a usable session behind
+fn lock() -> std::sync::MutexGuard<'static, Option<Session>> {
+    SESSION.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(timestamp: i64, duration_ms: u64) -> Entry {
+        Entry {
+            timestamp,
+            args: vec!["feat".to_string()],
+            repo: None,
+            pattern: Some("feat".to_string()),
+            candidates: vec![Candidate {
+                branch: "feature/login".to_string(),
+                score: 42.5,
+            }],
+            selected: Some("feature/login".to_string()),
+            error: None,
+            duration_ms,
+        }
+    }
+
+    fn write(target: &Target, entry: &Entry) {
+        let mut line = serde_json::to_string(entry).unwrap();
+        line.push('\n');
+        append(target, &line).unwrap();
+    }
+
+    #[test]
+    fn test_rotation_keeps_the_newest_entries() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("logs").join(LOG_FILE);
+        let line_len = serde_json::to_string(&entry(0, 0)).unwrap().len() as u64 + 1;
+        // Room for two entries per file
+        let target = Target {
+            path: path.clone(),
+            max_bytes: line_len * 2,
+            keep: 2,
+        };
+
+        for timestamp in 0..7 {
+            write(&target, &entry(timestamp, 0));
+        }
+
+        assert!(rotated_path(&path, 2).exists());
+        assert!(!rotated_path(&path, 3).exists());
+        let timestamps: Vec<i64> = read_entries(&path)
+            .unwrap()
+            .iter()
+            .map(|e| e.timestamp)
+            .collect();
+        assert_eq!(timestamps, vec![2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_read_entries_skips_bad_lines() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(LOG_FILE);
+        let good = serde_json::to_string(&entry(1, 5)).unwrap();
+        std::fs::write(&path, format!("{}\nnot json\n{{\"trunc", good)).unwrap();
+
+        assert_eq!(read_entries(&path).unwrap(), vec![entry(1, 5)]);
+        assert!(read_entries(&dir.path().join("missing.jsonl"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_slowest() {
+        let entries = vec![entry(1, 30), entry(2, 500), entry(3, 30), entry(4, 7)];
+        let slowest: Vec<(i64, u64)> = slowest(entries, 3)
+            .iter()
+            .map(|e| (e.timestamp, e.duration_ms))
+            .collect();
+        // Ties go to the most recent run
+        assert_eq!(slowest, vec![(2, 500), (3, 30), (1, 30)]);
+    }
+}
@@ -1,60 +1,114 @@
 mod cli;
-mod config;
-mod constants;
-mod error;
-mod frecency;
-mod git;
-mod interactive;
-mod matcher;
-mod storage;
-mod validation;
 
 use clap::{CommandFactory, Parser};
 use clap_complete::{generate, Shell};
-use std::collections::HashMap;
-use tabled::{
-    settings::{object::Rows, Alignment, Modify, Style},
-    Table, Tabled,
-};
 use tracing::{debug, warn};
 
-use cli::{Cli, Commands};
-use constants::scoring::FRECENCY_MULTIPLIER;
-use error::{GgoError, Result};
+use cli::{AliasCommand, Cli, Commands, ConfigCommand, GroupCommand, HooksCommand, StackCommand};
+use ggo::commands::{
+    alias::{
+        expand_alias_template, handle_alias_command, handle_alias_export, handle_alias_import,
+        list_all_aliases,
+    },
+    batch::{handle_batch_command, BatchAction, BatchOptions},
+    checkout::{
+        checkout_detach_target, checkout_tag, find_and_checkout_branch, switch_or_print,
+        CheckoutOptions,
+    },
+    cleanup::{auto_maintain, enforce_retention, handle_cleanup_command, CleanupOptions},
+    config::{
+        config_edit, config_get, config_list, config_path, config_set, handle_ignore_command,
+    },
+    current::show_current,
+    data::{
+        handle_export_command, handle_forget_command, handle_import_command,
+        handle_migrate_storage_command, handle_rename_record_command, handle_sync_command,
+        stored_repo_key,
+    },
+    delete::{handle_delete_command, handle_prune_command, handle_stale_command, StaleOptions},
+    explain::{handle_explain_command, handle_suggest_command},
+    find::{checkout_branch_containing, handle_find_command},
+    group::{add_to_group, group_members, list_groups, pick_from_group, remove_from_group},
+    history::{
+        checkout_history_entry, checkout_previous_branch, handle_undo_command,
+        parse_history_position, show_history,
+    },
+    hooks::handle_hooks_command,
+    list::{
+        checkout_listed_branch, handle_query_command, list_matching_branches, recent_listing,
+        ListOptions,
+    },
+    note::handle_note_command,
+    open::{checkout_pull_request, handle_open_command},
+    pin::{handle_pin_command, handle_unpin_command},
+    print_warning,
+    recent::{handle_recent_command, handle_switch_last_modified_command},
+    repo::jump_to_repo,
+    setup::{handle_self_update, handle_setup_command},
+    stack::{show_stacks, stack_on, unstack, walk_stack},
+    stats::{show_slow_runs, show_stats, stats_repo, SlowRunsOptions, StatsOptions},
+    worktree::{handle_wt_command, WorktreeOptions},
+    RepoMatchOptions,
+};
+use ggo::config::AutoSelect;
+use ggo::error::{GgoError, Result};
+use ggo::export::ExportFormat;
+use ggo::style::{self, Role};
+use ggo::switch::{record_current_branch, repo_key, SwitchOptions};
+use ggo::{
+    backend, config, constants, forge, git, interactive, logfile, server, shell, storage, template,
+    validation,
+};
 
 fn main() {
-    // Initialize tracing for structured logging
-    // Set RUST_LOG=debug for verbose output, or RUST_LOG=trace for very verbose
+    let cli = Cli::parse();
+    init_tracing(cli.verbose, cli.quiet);
+    style::set_quiet(cli.quiet);
+    let quiet = cli.quiet;
+    let result = run(cli);
+
+    // A log that can't be written never fails the run it describes
+    if let Err(e) = logfile::finish(result.as_ref().err().map(|e| e.summary())) {
+        warn!("Failed to write the invocation log: {}", e);
+    }
+
+    if let Err(e) = result {
+        let label = style::paint_err(Role::Error, "Error:");
+        if quiet {
+            eprintln!("{} {}", label, e.summary());
+        } else {
+            eprintln!("{} {}", label, e);
+        }
+        std::process::exit(e.exit_code());
+    }
+}
+
+/// Send tracing output to stderr: -v shows ggo's info messages, -vv debug,
+/// -vvv trace, and --quiet only errors. Without either, RUST_LOG decides
+/// (warnings by default).
+fn init_tracing(verbose: u8, quiet: bool) {
+    use tracing_subscriber::EnvFilter;
+
+    let filter = match (quiet, verbose) {
+        (true, _) => EnvFilter::new("error"),
+        (false, 0) => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+        (false, 1) => EnvFilter::new("warn,ggo=info"),
+        (false, 2) => EnvFilter::new("warn,ggo=debug"),
+        (false, _) => EnvFilter::new("warn,ggo=trace"),
+    };
     tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn")),
-        )
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
         .with_target(false)
         .with_level(true)
         .init();
-
-    if let Err(e) = run() {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
-    }
 }
 
-fn run() -> Result<()> {
-    let cli = Cli::parse();
+fn run(mut cli: Cli) -> Result<()> {
     debug!("CLI arguments: {:?}", cli);
 
-    // Load configuration (use defaults if config file doesn't exist or is invalid)
-    let config = match config::Config::load() {
-        Ok(c) => c,
-        Err(e) => {
-            warn!("Failed to load config, using defaults: {}", e);
-            config::Config::default()
-        }
-    };
-    debug!("Configuration: {:?}", config);
-
-    // Handle version flag
+    // These print something fixed, so they don't touch the config, the
+    // invocation log or the database
     if cli.version {
         println!("ggo {}", env!("CARGO_PKG_VERSION"));
         return Ok(());
@@ -65,934 +119,938 @@ fn run() -> Result<()> {
         generate_completion(&shell)?;
         return Ok(());
     }
-
-    // Handle subcommands first
-    if let Some(command) = cli.command {
-        match command {
-            Commands::Alias {
-                alias,
-                branch,
-                list,
-                remove,
-            } => {
-                handle_alias_command(alias.as_deref(), branch.as_deref(), list, remove)?;
-                return Ok(());
-            }
-            Commands::Cleanup {
-                older_than,
-                deleted,
-                optimize,
-                size,
-            } => {
-                handle_cleanup_command(older_than, deleted, optimize, size)?;
-                return Ok(());
-            }
-        }
+    if let Some(dir) = &cli.generate_assets {
+        return generate_assets(dir);
     }
 
-    if cli.stats {
-        show_stats()?;
+    // The shell integration script needs neither a repository nor the database
+    if let Some(Commands::Init { shell: name }) = &cli.command {
+        let shell =
+            shell::Shell::from_name(name).ok_or_else(|| GgoError::InvalidShell(name.clone()))?;
+        print!("{}", shell.init_script());
         return Ok(());
     }
 
-    // Pattern is required if no subcommand and no stats
-    let pattern = cli
-        .pattern
-        .as_deref()
-        .ok_or_else(|| GgoError::Other("Pattern argument is required\n\nUsage: ggo <pattern>\nTry 'ggo --help' for more information".to_string()))?;
-
-    // Handle the special '-' pattern to go back to previous branch
-    if pattern == "-" {
-        checkout_previous_branch()?;
-        return Ok(());
+    // --log-file and --data-dir name paths relative to where ggo was started,
+    // not -C
+    let log_file = cli.log_file.as_deref().map(from_current_dir);
+    let data_dir = cli.data_dir.as_deref().map(from_current_dir);
+
+    // -C: every git lookup and the repository key for storage follow the
+    // working directory, so switching it is enough (same as 'git -C')
+    // 'ggo forget --repo <path>' and 'ggo cleanup --repo <path>' name a
+    // repository to act on instead, which may not exist anymore
+    let repo_is_target = matches!(
+        cli.command,
+        Some(Commands::Forget { pattern: None, .. } | Commands::Cleanup { .. })
+    );
+    if let Some(path) = cli.repo.as_ref().filter(|_| !repo_is_target) {
+        std::env::set_current_dir(path).map_err(|e| {
+            GgoError::InvalidRepoPath(
+                path.display().to_string(),
+                format!("Cannot change to this directory: {}", e),
+            )
+        })?;
     }
 
-    // Validate search pattern
-    validation::validate_pattern(pattern)?;
-
-    if cli.list {
-        list_matching_branches(pattern, cli.ignore_case, !cli.no_fuzzy)?;
-    } else {
-        let branch = find_and_checkout_branch(
-            pattern,
-            cli.ignore_case,
-            !cli.no_fuzzy,
-            cli.interactive,
-            &config,
-        )?;
-        println!("Switched to branch '{}'", branch);
+    // Load configuration (use defaults if config file doesn't exist or is invalid)
+    let mut config = match config::Config::load() {
+        Ok(c) => c,
+        Err(e) => {
+            warn!("Failed to load config, using defaults: {}", e);
+            config::Config::default()
+        }
+    };
+    if cli.no_hooks || env_flag("GGO_NO_HOOKS") {
+        config.hooks.enabled = false;
     }
+    debug!("Configuration: {:?}", config);
+    storage::use_data_dir(data_dir, config.storage.data_dir_path())?;
 
-    Ok(())
-}
-
-fn show_stats() -> Result<()> {
-    let stats = storage::get_stats()?;
-    let records = storage::get_all_records()?;
-
-    // Summary Section
-    println!("📊 ggo Statistics\n");
-    println!("Total branch switches: {}", stats.total_switches);
-    println!("Unique branches tracked: {}", stats.unique_branches);
-    println!("Repositories: {}", stats.unique_repos);
-    println!("Database location: {}", stats.db_path.display());
-
-    if records.is_empty() {
-        println!("\nNo branch usage data yet. Start using ggo to build your history!");
-        return Ok(());
+    // Shell prompts run this on every render, so it skips the invocation
+    // log, migrations and maintenance and only reads the history
+    if let Some(Commands::Current { porcelain }) = cli.command {
+        return show_current(&config, porcelain);
     }
 
-    // Top Branches with Bar Charts
-    println!("\n🔥 Top Branches by Frecency:\n");
-
-    let scored = frecency::rank_branches(&records);
-    let top_branches = scored.iter().take(10).collect::<Vec<_>>();
-
-    if !top_branches.is_empty() {
-        let max_score = top_branches[0].score.max(1.0);
-
-        for (i, branch) in top_branches.iter().enumerate() {
-            let time_ago = frecency::format_relative_time(branch.last_used);
-            let bar_width = (branch.score / max_score * 40.0) as usize;
-            let bar = "█".repeat(bar_width);
-
-            println!(
-                "  {:2}. {:<30} {:>5.1} {} ({} switches, {})",
-                i + 1,
-                truncate_string(&branch.name, 30),
-                branch.score,
-                bar,
-                branch.switch_count,
-                time_ago
-            );
+    // The prompt hook runs this on every branch change, so keep it cheap and
+    // quiet: no invocation log or maintenance, and nothing to report outside
+    // a repository
+    if cli.record_current || matches!(cli.command, Some(Commands::RecordCurrent)) {
+        if cli.no_record || env_flag("GGO_NO_RECORD") {
+            return Ok(());
         }
+        if let Ok(storage) = open_storage(&config, true) {
+            record_current_branch(&storage, &config, print_warning);
+        }
+        return Ok(());
     }
 
-    // Repository Breakdown
-    if stats.unique_repos > 1 {
-        println!("\n📁 Repository Breakdown:\n");
-
-        #[derive(Tabled)]
-        struct RepoStats {
-            #[tabled(rename = "Repository")]
-            name: String,
-            #[tabled(rename = "Branches")]
-            branches: usize,
-            #[tabled(rename = "Switches")]
-            switches: i64,
-        }
-
-        let mut repo_map: HashMap<String, (usize, i64)> = HashMap::new();
-        for record in &records {
-            let entry = repo_map.entry(record.repo_path.clone()).or_insert((0, 0));
-            entry.0 += 1; // branch count
-            entry.1 += record.switch_count; // total switches
-        }
-
-        let mut repo_stats: Vec<RepoStats> = repo_map
-            .into_iter()
-            .map(|(path, (branches, switches))| {
-                let name = std::path::Path::new(&path)
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or(&path)
-                    .to_string();
-                RepoStats {
-                    name,
-                    branches,
-                    switches,
-                }
-            })
-            .collect();
-
-        // Sort by switches descending
-        repo_stats.sort_by(|a, b| b.switches.cmp(&a.switches));
-
-        let mut table = Table::new(repo_stats);
-        table
-            .with(Style::rounded())
-            .with(Modify::new(Rows::first()).with(Alignment::center()));
-
-        println!("{}", table);
+    // The invocation log, if on; main() writes the entry once the run is over
+    let log_path = match &log_file {
+        Some(path) => Some(path.clone()),
+        None if config.log.enabled => logfile::default_path()
+            .map_err(|e| warn!("Invocation log unavailable: {}", e))
+            .ok(),
+        None => None,
+    };
+    if let Some(path) = log_path {
+        logfile::start(path, &config.log, std::env::args().skip(1).collect());
     }
+    style::init(&cli.color, config.display.theme);
 
-    Ok(())
-}
+    // Updating ggo needs neither a repository nor the database
+    #[cfg(feature = "self-update")]
+    if let Some(Commands::SelfUpdate { check }) = cli.command {
+        return handle_self_update(check);
+    }
 
-/// Truncate string to max length with ellipsis
-fn truncate_string(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
-        s.to_string()
-    } else {
-        format!("{}...", &s[..max_len - 3])
+    // Restoring has to work on a database this ggo refuses to open
+    if let Some(Commands::Cleanup {
+        restore_backup: true,
+        ..
+    }) = cli.command
+    {
+        let (backup, version) = storage::restore_backup()?;
+        println!(
+            "Restored the schema v{} database backup {}",
+            version,
+            backup.display()
+        );
+        return Ok(());
     }
-}
 
-fn list_matching_branches(pattern: &str, ignore_case: bool, use_fuzzy: bool) -> Result<()> {
-    let branches = git::get_branches()?;
-    let repo_path = git::get_repo_root()?;
+    // Migrating opens both backends itself
+    if let Some(Commands::MigrateStorage { to, force }) = &cli.command {
+        return handle_migrate_storage_command(&config, to, *force);
+    }
 
-    // Try to load branch history, but continue without it if it fails
-    let records = match storage::get_branch_records(&repo_path) {
-        Ok(r) => r,
-        Err(e) => {
-            eprintln!("⚠️  Warning: Could not load branch history: {}", e);
-            eprintln!("   Frecency ranking will not be available.");
-            vec![]
-        }
+    // How resolved branches are switched to, for every command that checks out
+    let switch = SwitchOptions {
+        backend: backend::backend(),
+        force_detach: cli.force_detach,
+        force: cli.force,
+        can_prompt: !cli.no_interactive && interactive::is_terminal(),
+        confirm: Some(interactive::confirm_guarded_switch),
+        quiet: cli.quiet,
+        warn: print_warning,
+        repo_hooks: true,
     };
 
-    let ranked = if use_fuzzy {
-        // Use fuzzy matching and combine with frecency
-        let fuzzy_matches = matcher::fuzzy_filter_branches(&branches, pattern, ignore_case);
+    let record = !(cli.no_record || env_flag("GGO_NO_RECORD"));
 
-        if fuzzy_matches.is_empty() {
-            return Err(GgoError::NoMatchingBranches(pattern.to_string()));
-        }
+    // --stats --slow reads the invocation log, not the history
+    if cli.command.is_none() && cli.stats && cli.slow {
+        let path = match log_file {
+            Some(path) => path,
+            None => logfile::default_path()?,
+        };
+        let options = SlowRunsOptions {
+            repo_path: if cli.this_repo {
+                Some(repo_key(&open_storage(&config, record)?, &config)?)
+            } else {
+                None
+            },
+            max_age_seconds: cli
+                .since
+                .as_deref()
+                .map(validation::parse_duration)
+                .transpose()?,
+            top: cli.top,
+            json: cli.json,
+        };
+        show_slow_runs(&path, &options)?;
+        return Ok(());
+    }
 
-        combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records)
+    // One database connection for the rest of the invocation. --stats and
+    // --history only read it, so before anything has been recorded they get
+    // an empty store instead of creating the database.
+    let reads_only = cli.command.is_none() && (cli.stats || cli.history);
+    let storage = if reads_only && !storage::Storage::exists(config.storage.backend)? {
+        storage::Storage::open_in_memory()
     } else {
-        // Use exact substring matching
-        let matches = matcher::filter_branches(&branches, pattern, ignore_case);
+        open_storage(&config, record)
+    };
 
-        if matches.is_empty() {
-            return Err(GgoError::NoMatchingBranches(pattern.to_string()));
+    // Opt-in housekeeping, but never behind the back of an explicit cleanup
+    if let Ok(storage) = &storage {
+        let cleaning_up = matches!(
+            cli.command,
+            Some(Commands::Cleanup { .. } | Commands::Forget { .. })
+        );
+        if config.maintenance.auto && !cli.no_auto_maintenance && !cleaning_up {
+            auto_maintain(storage, &config);
         }
+        enforce_retention(storage, &config);
+    }
 
-        let match_strings: Vec<String> = matches.iter().map(|s| s.to_string()).collect();
-        frecency::sort_branches_by_frecency(&match_strings, &records)
-    };
+    // --group narrows every branch list to the group's members
+    if let (Some(group), Ok(storage)) = (&cli.group, &storage) {
+        config.behavior.only = Some(group_members(storage, group, &config)?);
+    }
 
-    let match_type = if use_fuzzy {
-        "fuzzy matching"
-    } else {
-        "substring matching"
-    };
-    println!(
-        "Branches matching '{}' ({}+ frecency):\n",
-        pattern, match_type
-    );
+    // Handle subcommands first
+    if let Some(command) = cli.command.take() {
+        return run_command(command, &cli, &storage?, &config, &switch);
+    }
 
-    for (i, (branch, score)) in ranked.iter().enumerate() {
-        let marker = if i == 0 { "→" } else { " " };
-        let score_display = if *score > 0.0 {
-            format!(" ({:.1})", score)
-        } else {
-            String::new()
+    if cli.stats {
+        let storage = storage?;
+        let max_age_seconds = cli
+            .since
+            .as_deref()
+            .map(validation::parse_duration)
+            .transpose()?;
+        let repo_path = match &cli.pattern {
+            Some(pattern) => {
+                validation::validate_pattern(pattern)?;
+                let (ignore_case, use_fuzzy) = matching_mode(
+                    cli.ignore_case,
+                    cli.case_sensitive,
+                    cli.no_fuzzy,
+                    pattern,
+                    &config,
+                );
+                let options = RepoMatchOptions {
+                    ignore_case,
+                    use_fuzzy,
+                    can_prompt: switch.can_prompt && !cli.json,
+                    auto_select: auto_select_mode(&cli, &config),
+                };
+                Some(stats_repo(
+                    &storage,
+                    pattern,
+                    &options,
+                    max_age_seconds,
+                    &config,
+                )?)
+            }
+            None if cli.this_repo => Some(repo_key(&storage, &config)?),
+            None => None,
         };
-
-        // Get aliases for this branch
-        let aliases = storage::get_aliases_for_branch(&repo_path, branch).unwrap_or_default();
-        let alias_display = if !aliases.is_empty() {
-            format!(" [alias: {}]", aliases.join(", "))
-        } else {
-            String::new()
+        let filter = storage::StatsFilter {
+            repo_path,
+            max_age_seconds,
         };
-
-        println!("  {} {}{}{}", marker, branch, score_display, alias_display);
+        let sort = match cli.sort.as_str() {
+            "switches" => storage::StatsSort::Switches,
+            "recent" => storage::StatsSort::Recent,
+            _ => storage::StatsSort::Score,
+        };
+        let options = StatsOptions {
+            filter,
+            sort,
+            top: cli.top,
+            heatmap: cli.heatmap,
+            json: cli.json,
+            format: cli
+                .format
+                .as_deref()
+                .map(|format| {
+                    ExportFormat::from_name(format).ok_or_else(|| {
+                        GgoError::InvalidFormat(
+                            format.to_string(),
+                            "--stats takes json or csv".to_string(),
+                        )
+                    })
+                })
+                .transpose()?,
+        };
+        show_stats(&storage, &options, &config)?;
+        return Ok(());
     }
 
-    if ranked.len() > 1 {
-        println!("\n({} matches, → indicates checkout target)", ranked.len());
+    if cli.history {
+        show_history(&storage?, &config)?;
+        return Ok(());
     }
 
-    Ok(())
-}
-
-fn checkout_previous_branch() -> Result<()> {
-    let repo_path = git::get_repo_root()?;
-
-    let previous_branch =
-        storage::get_previous_branch(&repo_path)?.ok_or(GgoError::NoPreviousBranch)?;
-
-    // Re-verify branch exists before checkout (prevent race condition)
-    let current_branches = git::get_branches()?;
-
-    if !current_branches.contains(&previous_branch) {
-        return Err(GgoError::BranchNotFound(previous_branch));
+    // Switching branches still works when the database is unavailable, just
+    // without history
+    let storage = storage.or_else(|e| {
+        style::warn(format!(
+            "Could not open ggo database: {}\n   Branch history will not be available or saved for this run.",
+            e
+        ));
+        storage::Storage::open_in_memory()
+    })?;
+
+    if let Some(n) = cli.nth {
+        checkout_listed_branch(&storage, &config, n as usize, &switch, cli.print)?;
+        return Ok(());
     }
 
-    // Save current branch before switching
-    if let Ok(current_branch) = git::get_current_branch() {
-        if let Err(e) = storage::save_previous_branch(&repo_path, &current_branch) {
-            eprintln!("⚠️  Warning: Could not save previous branch: {}", e);
-            eprintln!("   The 'ggo -' command may not work correctly.");
-        }
-    }
+    // Pattern is required if no subcommand and no stats
+    let pattern = cli
+        .pattern
+        .as_deref()
+        .ok_or_else(|| GgoError::Other("Pattern argument is required\n\nUsage: ggo <pattern>\nTry 'ggo --help' for more information".to_string()))?;
 
-    // Checkout the previous branch
-    git::checkout(&previous_branch)?;
+    // A template alias takes the words after it: 'ggo rel 1.42'
+    let expanded = expand_alias_template(&storage, pattern, &cli.args, &config)?;
+    let pattern = expanded.as_deref().unwrap_or(pattern);
 
-    // Record the checkout for frecency tracking
-    if let Err(e) = storage::record_checkout(&repo_path, &previous_branch) {
-        eprintln!("⚠️  Warning: Could not save branch usage: {}", e);
-        eprintln!(
-            "   This won't affect future checkouts, but frecency tracking may be incomplete."
+    // --tags is 'ggo tag <pattern>'
+    if cli.tags {
+        validation::validate_pattern(pattern)?;
+        let (ignore_case, use_fuzzy) = matching_mode(
+            cli.ignore_case,
+            cli.case_sensitive,
+            cli.no_fuzzy,
+            pattern,
+            &config,
         );
+        checkout_tag(
+            &storage,
+            &config,
+            Some(pattern),
+            None,
+            ignore_case,
+            use_fuzzy,
+            &switch,
+            cli.print,
+        )?;
+        return Ok(());
     }
 
-    println!("Switched to branch '{}'", previous_branch);
-    Ok(())
-}
-
-/// Handle cleanup subcommand operations
-fn handle_cleanup_command(
-    older_than_days: i64,
-    cleanup_deleted: bool,
-    optimize: bool,
-    show_size: bool,
-) -> Result<()> {
-    if show_size {
-        let size = storage::get_database_size()?;
-        let size_kb = size as f64 / 1024.0;
-        let size_mb = size_kb / 1024.0;
-
-        if size_mb > 1.0 {
-            println!("Database size: {:.2} MB", size_mb);
-        } else {
-            println!("Database size: {:.2} KB", size_kb);
-        }
-    }
-
-    if cleanup_deleted {
-        println!("Cleaning up deleted branches...");
-        let deleted = storage::cleanup_deleted_branches()?;
-        println!("Removed {} stale branch records", deleted);
-    }
-
-    // Cleanup old records (always run if a custom age is specified, or if --optimize is used)
-    if older_than_days < 365 || optimize {
-        println!(
-            "Cleaning up branches older than {} days...",
-            older_than_days
+    // --detach matches tags and recent commits instead of branches
+    if cli.detach {
+        validation::validate_pattern(pattern)?;
+        let (ignore_case, use_fuzzy) = matching_mode(
+            cli.ignore_case,
+            cli.case_sensitive,
+            cli.no_fuzzy,
+            pattern,
+            &config,
         );
-        let deleted = storage::cleanup_old_records(older_than_days)?;
-        println!("Removed {} old branch records", deleted);
+        checkout_detach_target(
+            &storage,
+            &config,
+            pattern,
+            ignore_case,
+            use_fuzzy,
+            &switch,
+            cli.print,
+        )?;
+        return Ok(());
     }
 
-    if optimize {
-        println!("Optimizing database...");
-        storage::optimize_database()?;
-        println!("Database optimized (VACUUM and ANALYZE complete)");
+    // Handle the special '-' pattern to go back to previous branch
+    if pattern == "-" {
+        checkout_previous_branch(&storage, &config, &switch, cli.print)?;
+        return Ok(());
     }
 
-    if !show_size && !cleanup_deleted && !optimize && older_than_days == 365 {
-        // No flags specified, show help
-        println!("Database cleanup options:");
-        println!("  --deleted          Remove records for deleted branches");
-        println!("  --older-than N     Remove branches not used in N days");
-        println!("  --optimize         Run VACUUM and ANALYZE");
-        println!("  --size             Show database size");
-        println!("\nExample: ggo cleanup --deleted --optimize");
+    // Handle '-N' to go back N switches in the checkout history
+    if let Some(position) = parse_history_position(pattern) {
+        checkout_history_entry(&storage, &config, position, &switch, cli.print)?;
+        return Ok(());
     }
 
-    Ok(())
-}
-
-/// Generate shell completion script
-fn generate_completion(shell_name: &str) -> Result<()> {
-    let shell = match shell_name.to_lowercase().as_str() {
-        "bash" => Shell::Bash,
-        "zsh" => Shell::Zsh,
-        "fish" => Shell::Fish,
-        "powershell" | "pwsh" => Shell::PowerShell,
-        "elvish" => Shell::Elvish,
-        _ => return Err(GgoError::InvalidShell(shell_name.to_string())),
-    };
-
-    let mut cmd = Cli::command();
-    generate(shell, &mut cmd, "ggo", &mut std::io::stdout());
-
-    Ok(())
-}
-
-/// Handle alias subcommand operations
-fn handle_alias_command(
-    alias: Option<&str>,
-    branch: Option<&str>,
-    list: bool,
-    remove: bool,
-) -> Result<()> {
-    let repo_path = git::get_repo_root()?;
-
-    // Handle --list flag
-    if list {
-        let aliases = storage::list_aliases(&repo_path)?;
-        if aliases.is_empty() {
-            println!("No aliases defined for this repository");
-        } else {
-            println!("Aliases for this repository:\n");
-            for a in aliases {
-                println!("  {} → {}", a.alias, a.branch_name);
-            }
+    // '#1234' checks out a pull request rather than matching branch names
+    if pattern.starts_with('#') {
+        if let Some(number) = forge::parse_pull_request_number(pattern) {
+            checkout_pull_request(&storage, number, cli.print, &switch, &config)?;
+            return Ok(());
         }
-        return Ok(());
     }
 
-    // Alias is required for other operations
-    let alias = alias.ok_or_else(|| GgoError::Other("Alias name is required".to_string()))?;
-
-    // Handle --remove flag
-    if remove {
-        storage::delete_alias(&repo_path, alias)?;
-        println!("Removed alias '{}'", alias);
-        return Ok(());
+    // A bare number right after 'ggo -l' picks from that listing; past its
+    // end it's a pattern again (e.g. a ticket number)
+    if !cli.list {
+        if let Some(n) = pattern.parse::<usize>().ok().filter(|&n| n > 0) {
+            let repo_path = repo_key(&storage, &config)?;
+            let listing = recent_listing(&storage, &repo_path, true)?.unwrap_or_default();
+            if let Some(branch) = listing.get(n - 1) {
+                switch_or_print(&storage, &config, &repo_path, branch, &switch, cli.print)?;
+                return Ok(());
+            }
+        }
     }
 
-    // If branch is provided, create/update alias
-    if let Some(branch_name) = branch {
-        // Validate alias name
-        validation::validate_alias_name(alias)?;
-
-        // Validate branch name
-        validation::validate_branch_name(branch_name)?;
+    // Validate search pattern
+    validation::validate_pattern(pattern)?;
 
-        // Validate that branch exists
-        let branches = git::get_branches()?;
-        if !branches.contains(&branch_name.to_string()) {
-            return Err(GgoError::BranchNotFound(branch_name.to_string()));
-        }
+    let (ignore_case, use_fuzzy) = matching_mode(
+        cli.ignore_case,
+        cli.case_sensitive,
+        cli.no_fuzzy,
+        pattern,
+        &config,
+    );
 
-        // Create/update the alias
-        storage::create_alias(&repo_path, alias, branch_name)?;
-        println!("Created alias '{}' → '{}'", alias, branch_name);
-        return Ok(());
-    }
+    if cli.list {
+        let template = cli
+            .format
+            .as_deref()
+            .map(template::Template::parse)
+            .transpose()?;
+        let options = ListOptions {
+            ignore_case,
+            use_fuzzy,
+            suggest: cli.suggest,
+            notes: cli.notes,
+            json: cli.json,
+            template: template.as_ref(),
+            limit: cli.limit,
+        };
+        list_matching_branches(&storage, pattern, &options, &config)?;
+    } else {
+        let options = CheckoutOptions {
+            ignore_case,
+            use_fuzzy,
+            interactive: cli.interactive || cli.tui || cli.fzf,
+            create: cli.create,
+            switch,
+            suggest: cli.suggest,
+            notes: cli.notes,
+            print: cli.print,
+            // --tui/--fzf force their picker; otherwise use the configured one
+            picker: if cli.tui {
+                config::Picker::Tui
+            } else if cli.fzf {
+                config::Picker::Fzf
+            } else {
+                config.behavior.picker
+            },
+            auto_select: auto_select_mode(&cli, &config),
+        };
 
-    // No branch provided: show what alias points to
-    match storage::get_alias(&repo_path, alias)? {
-        Some(branch_name) => {
-            println!("{} → {}", alias, branch_name);
-        }
-        None => {
-            println!("Alias '{}' not found", alias);
-        }
+        let repo_path = repo_key(&storage, &config)?;
+        find_and_checkout_branch(&storage, &repo_path, pattern, &options, &config)?;
     }
 
     Ok(())
 }
 
-/// Combine fuzzy match scores with frecency scores for final ranking
-/// Formula: combined_score = fuzzy_score + (frecency_score * 10)
-/// This gives weight to both good fuzzy matches and frequently-used branches
-fn combine_fuzzy_and_frecency_scores(
-    fuzzy_matches: &[matcher::ScoredMatch],
-    records: &[storage::BranchRecord],
-) -> Vec<(String, f64)> {
-    use std::collections::HashMap;
-
-    // Build a map of branch -> frecency score
-    let frecency_map: HashMap<&str, f64> = records
-        .iter()
-        .map(|r| (r.branch_name.as_str(), frecency::calculate_score(r)))
-        .collect();
-
-    let mut combined: Vec<(String, f64)> = fuzzy_matches
-        .iter()
-        .map(|m| {
-            let fuzzy_score = m.score as f64;
-            let frecency_score = frecency_map.get(m.branch.as_str()).copied().unwrap_or(0.0);
-
-            // Combine scores: fuzzy match quality + (frecency * weight)
-            // Frecency gets a multiplier to give it significant weight
-            let combined_score = fuzzy_score + (frecency_score * FRECENCY_MULTIPLIER);
-
-            (m.branch.clone(), combined_score)
-        })
-        .collect();
-
-    // Sort by combined score descending
-    combined.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-
-    combined
+/// `path` made absolute against the current directory
+fn from_current_dir(path: &std::path::Path) -> std::path::PathBuf {
+    std::env::current_dir()
+        .map(|dir| dir.join(path))
+        .unwrap_or_else(|_| path.to_path_buf())
 }
 
-fn find_and_checkout_branch(
-    pattern: &str,
-    ignore_case: bool,
-    use_fuzzy: bool,
-    interactive: bool,
+/// Run a subcommand
+fn run_command(
+    command: Commands,
+    cli: &Cli,
+    storage: &storage::Storage,
     config: &config::Config,
-) -> Result<String> {
-    let branches = git::get_branches()?;
-    let repo_path = git::get_repo_root()?;
-
-    // Try to load branch history, but continue without it if it fails
-    let records = match storage::get_branch_records(&repo_path) {
-        Ok(r) => r,
-        Err(e) => {
-            eprintln!("⚠️  Warning: Could not load branch history: {}", e);
-            eprintln!("   Frecency ranking will not be available.");
-            vec![]
+    switch: &SwitchOptions,
+) -> Result<()> {
+    match command {
+        Commands::Alias {
+            action: Some(action),
+            ..
+        } => match action {
+            AliasCommand::Export => handle_alias_export(storage, config)?,
+            AliasCommand::Import { path, force } => {
+                handle_alias_import(storage, path.as_deref(), force, config)?
+            }
+        },
+        Commands::Alias {
+            action: None,
+            list: true,
+            all: true,
+            ..
+        } => {
+            list_all_aliases(storage, config)?;
         }
-    };
-
-    // Check if pattern is an exact alias match (highest priority)
-    // Note: get_alias() only returns aliases for the current repo (scoped by repo_path)
-    // This ensures we never try to use an alias from a different repository
-    if let Ok(Some(branch_name)) = storage::get_alias(&repo_path, pattern) {
-        // Verify the aliased branch exists in the current repository
-        // This protects against stale aliases pointing to deleted branches
-        if branches.contains(&branch_name) {
-            println!("Using alias '{}' → '{}'", pattern, branch_name);
-
-            // Re-verify branch exists before checkout (prevent race condition)
-            let current_branches = git::get_branches()?;
-
-            if !current_branches.contains(&branch_name) {
-                return Err(GgoError::BranchNotFound(branch_name));
+        Commands::Alias {
+            action: None,
+            alias,
+            branch,
+            list,
+            remove,
+            rename,
+            ..
+        } => {
+            handle_alias_command(
+                storage,
+                alias.as_deref(),
+                branch.as_deref(),
+                list,
+                remove,
+                rename,
+                config,
+            )?;
+        }
+        Commands::Undo => {
+            handle_undo_command(storage, config, switch)?;
+        }
+        Commands::Query { pattern, limit } => {
+            handle_query_command(
+                storage,
+                pattern.as_deref().unwrap_or_default(),
+                limit,
+                cli.json,
+                config,
+            )?;
+        }
+        Commands::Recent { limit, interactive } => {
+            handle_recent_command(storage, limit, interactive, switch, config)?;
+        }
+        Commands::Current { .. } | Commands::RecordCurrent => {
+            unreachable!("handled before opening the database")
+        }
+        Commands::SwitchLastModified { list, interactive } => {
+            handle_switch_last_modified_command(
+                storage,
+                list || cli.json,
+                interactive,
+                cli.json,
+                switch,
+                config,
+            )?;
+        }
+        Commands::Ignore { pattern, remove } => {
+            handle_ignore_command(pattern.as_deref(), remove)?;
+        }
+        Commands::Pin { branch } => {
+            handle_pin_command(storage, branch.as_deref(), config)?;
+        }
+        Commands::Unpin { branch } => {
+            handle_unpin_command(storage, &branch, config)?;
+        }
+        Commands::Group { action, name } => match (action, name) {
+            (Some(GroupCommand::Add { name, branches }), _) => {
+                add_to_group(storage, &name, &branches, config)?;
             }
-
-            // Checkout the aliased branch directly
-            let current_branch = git::get_current_branch().ok();
-            if let Some(ref current) = current_branch {
-                if current != &branch_name {
-                    if let Err(e) = storage::save_previous_branch(&repo_path, current) {
-                        warn!("Failed to save previous branch: {}", e);
-                        eprintln!("⚠️  Warning: 'ggo -' may not work correctly");
-                    } else {
-                        debug!("Saved previous branch: {}", current);
-                    }
-                }
+            (Some(GroupCommand::Remove { name, branches }), _) => {
+                remove_from_group(storage, &name, &branches, config)?;
             }
-
-            git::checkout(&branch_name)?;
-
-            if let Err(e) = storage::record_checkout(&repo_path, &branch_name) {
-                eprintln!("⚠️  Warning: Could not save branch usage: {}", e);
-                eprintln!("   This won't affect future checkouts, but frecency tracking may be incomplete.");
+            (None, None) => list_groups(storage, config)?,
+            (None, Some(name)) => pick_from_group(storage, &name, switch, config)?,
+        },
+        Commands::Note {
+            branch,
+            text,
+            remove,
+        } => {
+            handle_note_command(storage, branch.as_deref(), text.as_deref(), remove, config)?;
+        }
+        Commands::RenameRecord { old, new } => {
+            handle_rename_record_command(storage, &old, &new, config)?;
+        }
+        Commands::Stack { action, all } => match action {
+            Some(StackCommand::On { parent, branch }) => {
+                stack_on(storage, &parent, branch.as_deref(), config)?;
             }
-
-            return Ok(branch_name);
-        } else {
-            eprintln!(
-                "Warning: Alias '{}' points to non-existent branch '{}'. Falling back to pattern matching.",
-                pattern, branch_name
+            Some(StackCommand::Remove { branch }) => {
+                unstack(storage, branch.as_deref(), config)?;
+            }
+            None => show_stacks(storage, all, config)?,
+        },
+        Commands::Up { steps } => {
+            walk_stack(storage, config, true, steps, switch)?;
+        }
+        Commands::Down { steps } => {
+            walk_stack(storage, config, false, steps, switch)?;
+        }
+        Commands::Open { pattern, pr, url } => {
+            handle_open_command(storage, pattern.as_deref(), pr, url, config)?;
+        }
+        Commands::Init { .. } => unreachable!("handled before opening the database"),
+        Commands::MigrateStorage { .. } => {
+            unreachable!("handled before opening the database")
+        }
+        #[cfg(feature = "self-update")]
+        Commands::SelfUpdate { .. } => unreachable!("handled before opening the database"),
+        Commands::Repo { pattern } => {
+            let pattern = pattern.unwrap_or_default();
+            validation::validate_pattern(&pattern)?;
+            let (ignore_case, use_fuzzy) = matching_mode(
+                cli.ignore_case,
+                cli.case_sensitive,
+                cli.no_fuzzy,
+                &pattern,
+                config,
             );
+            let options = RepoMatchOptions {
+                ignore_case,
+                use_fuzzy,
+                can_prompt: switch.can_prompt,
+                auto_select: auto_select_mode(cli, config),
+            };
+            jump_to_repo(storage, &pattern, &options, config)?;
         }
-    }
-
-    let ranked = if use_fuzzy {
-        // Use fuzzy matching and combine with frecency
-        let fuzzy_matches = matcher::fuzzy_filter_branches(&branches, pattern, ignore_case);
-
-        if fuzzy_matches.is_empty() {
-            return Err(GgoError::NoMatchingBranches(pattern.to_string()));
+        Commands::Wt {
+            pattern,
+            create,
+            path,
+        } => {
+            let pattern = pattern.unwrap_or_default();
+            validation::validate_pattern(&pattern)?;
+            let (ignore_case, use_fuzzy) = matching_mode(
+                cli.ignore_case,
+                cli.case_sensitive,
+                cli.no_fuzzy,
+                &pattern,
+                config,
+            );
+            let options = WorktreeOptions {
+                ignore_case,
+                use_fuzzy,
+                create,
+                path,
+                can_prompt: switch.can_prompt,
+                auto_select: auto_select_mode(cli, config),
+            };
+            handle_wt_command(storage, &pattern, &options, config)?;
         }
-
-        combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records)
-    } else {
-        // Use exact substring matching
-        let matches = matcher::filter_branches(&branches, pattern, ignore_case);
-
-        if matches.is_empty() {
-            return Err(GgoError::NoMatchingBranches(pattern.to_string()));
+        Commands::Tag { pattern, branch } => {
+            if let Some(pattern) = pattern.as_deref() {
+                validation::validate_pattern(pattern)?;
+            }
+            let (ignore_case, use_fuzzy) = matching_mode(
+                cli.ignore_case,
+                cli.case_sensitive,
+                cli.no_fuzzy,
+                pattern.as_deref().unwrap_or_default(),
+                config,
+            );
+            checkout_tag(
+                storage,
+                config,
+                pattern.as_deref(),
+                branch.as_deref(),
+                ignore_case,
+                use_fuzzy,
+                switch,
+                false,
+            )?;
+        }
+        Commands::Pr { number } => {
+            checkout_pull_request(storage, number, false, switch, config)?;
+        }
+        Commands::Suggest { limit } => {
+            handle_suggest_command(storage, limit, cli.json, config)?;
+        }
+        Commands::Find {
+            message,
+            author,
+            touches,
+            unmerged,
+            list,
+        } => {
+            let query = git::CommitQuery {
+                message,
+                author,
+                touches,
+            };
+            handle_find_command(storage, &query, unmerged, list, switch, config)?;
+        }
+        Commands::Contains { commit } => {
+            checkout_branch_containing(storage, &commit, switch, config)?;
+        }
+        Commands::Explain { pattern } => {
+            validation::validate_pattern(&pattern)?;
+            let (ignore_case, use_fuzzy) = matching_mode(
+                cli.ignore_case,
+                cli.case_sensitive,
+                cli.no_fuzzy,
+                &pattern,
+                config,
+            );
+            handle_explain_command(storage, &pattern, ignore_case, use_fuzzy, cli.json, config)?;
+        }
+        Commands::Forget { pattern, dry_run } => {
+            handle_forget_command(
+                storage,
+                pattern.as_deref(),
+                cli.repo.as_deref(),
+                dry_run,
+                config,
+            )?;
+        }
+        Commands::Export { format, path } => {
+            handle_export_command(storage, &format, path.as_deref())?;
+        }
+        Commands::Import { path, merge } => {
+            handle_import_command(storage, path.as_deref(), merge)?;
         }
+        Commands::Sync => {
+            handle_sync_command(storage, config)?;
+        }
+        Commands::Serve { socket } => {
+            let socket = match socket {
+                Some(path) => from_current_dir(&path),
+                None => storage::get_data_dir()?.join(constants::server::SOCKET_FILE),
+            };
+            server::serve(storage, config, &socket)?;
+        }
+        Commands::Delete {
+            pattern,
+            force,
+            yes,
+        } => {
+            handle_delete_command(storage, &pattern, force, yes, switch.can_prompt, config)?;
+        }
+        Commands::Batch {
+            pattern,
+            action,
+            into,
+            force,
+            yes,
+        } => {
+            let action = action.as_deref().and_then(BatchAction::from_name);
+            let options = BatchOptions {
+                action,
+                into: into.as_deref(),
+                force,
+                yes,
+            };
+            handle_batch_command(storage, &pattern, &options, switch.can_prompt, config)?;
+        }
+        Commands::Prune {
+            fetch,
+            dry_run,
+            yes,
+        } => {
+            handle_prune_command(storage, fetch, dry_run, yes, switch.can_prompt, config)?;
+        }
+        Commands::Stale {
+            days,
+            mine,
+            force,
+            dry_run,
+            yes,
+        } => {
+            let options = StaleOptions {
+                days,
+                mine,
+                force,
+                dry_run: dry_run || cli.json,
+                yes,
+                json: cli.json,
+            };
+            handle_stale_command(storage, &options, switch.can_prompt, config)?;
+        }
+        Commands::Hooks { action } => {
+            let trust = action.map(|action| action == HooksCommand::Trust);
+            handle_hooks_command(storage, trust, switch.backend)?;
+        }
+        Commands::Config { action } => match action {
+            ConfigCommand::Get { key } => config_get(&key)?,
+            ConfigCommand::Set { key, value } => config_set(&key, &value)?,
+            ConfigCommand::List => config_list()?,
+            ConfigCommand::Edit => config_edit()?,
+            ConfigCommand::Path => config_path()?,
+        },
+        Commands::Setup { shell, yes } => {
+            handle_setup_command(
+                storage,
+                config,
+                shell.as_deref(),
+                yes,
+                switch.can_prompt,
+                Cli::command(),
+            )?;
+        }
+        Commands::Cleanup {
+            older_than,
+            deleted,
+            compact,
+            optimize,
+            size,
+            dry_run,
+            current,
+            restore_backup: _,
+        } => {
+            let scope = if let Some(path) = cli.repo.as_deref() {
+                match stored_repo_key(storage, path)? {
+                    Some(key) => Some(key),
+                    None => {
+                        println!("Nothing stored about repository '{}'", path.display());
+                        return Ok(());
+                    }
+                }
+            } else if current {
+                Some(repo_key(storage, config)?)
+            } else {
+                None
+            };
+
+            let options = CleanupOptions {
+                older_than_days: older_than,
+                deleted,
+                compact,
+                optimize,
+                show_size: size,
+                dry_run,
+            };
+            handle_cleanup_command(storage, &options, scope.as_deref(), config)?;
+        }
+    }
+    Ok(())
+}
 
-        let match_strings: Vec<String> = matches.iter().map(|s| s.to_string()).collect();
-        frecency::sort_branches_by_frecency(&match_strings, &records)
-    };
+/// Open the configured history store for this run, creating and migrating it
+/// if needed
+fn open_storage(config: &config::Config, record: bool) -> anyhow::Result<storage::Storage> {
+    let storage = storage::Storage::open_backend(config.storage.backend)?;
+    Ok(storage
+        .with_exclude(config.behavior.exclude.clone())
+        .with_recording(record))
+}
 
-    // Determine which branch to checkout
-    let branch_to_checkout = if interactive {
-        // Always use interactive mode if explicitly requested
-        let branch_list: Vec<String> = ranked.iter().map(|(b, _)| b.clone()).collect();
-        interactive::select_branch(&branch_list, &records)?
-    } else if ranked.len() == 1 {
-        // Single match: use it
-        ranked[0].0.clone()
+/// Case-insensitivity and fuzzy matching for `pattern`: the command-line flags
+/// override the configured matching defaults (smart-case unless turned off)
+fn matching_mode(
+    ignore_case: bool,
+    case_sensitive: bool,
+    no_fuzzy: bool,
+    pattern: &str,
+    config: &config::Config,
+) -> (bool, bool) {
+    let ignore_case = if ignore_case {
+        true
+    } else if case_sensitive {
+        false
     } else {
-        // Multiple matches: check if there's a clear winner
-        let top_score = ranked[0].1;
-        let second_score = ranked[1].1;
-
-        // If top score is above threshold compared to second, auto-select
-        // Handle edge case where second_score is 0
-        let should_auto_select = if second_score == 0.0 {
-            true
-        } else {
-            top_score / second_score >= config.behavior.auto_select_threshold
-        };
-
-        if should_auto_select {
-            ranked[0].0.clone()
-        } else {
-            // Scores are close, show interactive menu
-            let branch_list: Vec<String> = ranked.iter().map(|(b, _)| b.clone()).collect();
-            interactive::select_branch(&branch_list, &records)?
-        }
+        config.behavior.ignore_case_for(pattern)
     };
+    (ignore_case, config.behavior.default_fuzzy && !no_fuzzy)
+}
 
-    // Re-verify branch exists before checkout (prevent race condition)
-    let current_branches = git::get_branches()?;
+/// Whether a boolean environment variable is switched on (`1`, `true`, `yes`)
+fn env_flag(name: &str) -> bool {
+    std::env::var(name)
+        .map(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
 
-    if !current_branches.contains(&branch_to_checkout) {
-        return Err(GgoError::BranchNotFound(branch_to_checkout));
+/// behavior.auto_select for this run: --pick and --first override it
+fn auto_select_mode(cli: &Cli, config: &config::Config) -> AutoSelect {
+    if cli.pick {
+        AutoSelect::Never
+    } else if cli.first {
+        AutoSelect::Always
+    } else {
+        config.behavior.auto_select
     }
+}
 
-    // Save current branch as previous before switching
-    if let Ok(current_branch) = git::get_current_branch() {
-        // Only save if we're switching to a different branch
-        if current_branch != branch_to_checkout {
-            if let Err(e) = storage::save_previous_branch(&repo_path, &current_branch) {
-                eprintln!("⚠️  Warning: Could not save previous branch: {}", e);
-                eprintln!("   The 'ggo -' command may not work correctly.");
-            }
-        }
-    }
+/// Generate shell completion script
+fn generate_completion(shell_name: &str) -> Result<()> {
+    let shell = match shell_name.to_lowercase().as_str() {
+        "bash" => Shell::Bash,
+        "zsh" => Shell::Zsh,
+        "fish" => Shell::Fish,
+        "powershell" | "pwsh" => Shell::PowerShell,
+        "elvish" => Shell::Elvish,
+        _ => return Err(GgoError::InvalidShell(shell_name.to_string())),
+    };
 
-    // Checkout the branch
-    git::checkout(&branch_to_checkout)?;
+    let mut cmd = Cli::command();
+    generate(shell, &mut cmd, "ggo", &mut std::io::stdout());
 
-    // Record the checkout for frecency tracking
-    if let Err(e) = storage::record_checkout(&repo_path, &branch_to_checkout) {
-        // Don't fail the checkout if recording fails, just warn
-        eprintln!("⚠️  Warning: Could not save branch usage: {}", e);
-        eprintln!(
-            "   This won't affect future checkouts, but frecency tracking may be incomplete."
-        );
-    }
+    Ok(())
+}
 
-    Ok(branch_to_checkout)
+/// Write the man page to `dir/ggo.1` and completion scripts for every shell
+/// clap supports to `dir/completions/`
+fn generate_assets(dir: &std::path::Path) -> Result<()> {
+    let completions = dir.join("completions");
+    std::fs::create_dir_all(&completions)?;
+
+    let mut cmd = Cli::command().version(env!("CARGO_PKG_VERSION"));
+    cmd.build();
+
+    let mut page = Vec::new();
+    clap_mangen::Man::new(cmd.clone()).render(&mut page)?;
+    std::fs::write(dir.join("ggo.1"), page)?;
+
+    for shell in [
+        Shell::Bash,
+        Shell::Zsh,
+        Shell::Fish,
+        Shell::PowerShell,
+        Shell::Elvish,
+    ] {
+        clap_complete::generate_to(shell, &mut cmd, "ggo", &completions)?;
+    }
+    println!("Wrote the man page and completions to {}", dir.display());
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::matcher::ScoredMatch;
-    use crate::storage::BranchRecord;
-
-    #[test]
-    fn test_combine_fuzzy_and_frecency_scores_empty() {
-        let fuzzy_matches: Vec<ScoredMatch> = vec![];
-        let records: Vec<BranchRecord> = vec![];
-
-        let result = combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records);
-        assert_eq!(result.len(), 0);
-    }
-
-    #[test]
-    fn test_combine_fuzzy_and_frecency_scores_no_records() {
-        let fuzzy_matches = vec![
-            ScoredMatch {
-                branch: "feature/auth".to_string(),
-                score: 100,
-            },
-            ScoredMatch {
-                branch: "feature/dashboard".to_string(),
-                score: 80,
-            },
-        ];
-        let records: Vec<BranchRecord> = vec![];
-
-        let result = combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records);
-
-        assert_eq!(result.len(), 2);
-        // Without frecency, should sort by fuzzy score only
-        assert_eq!(result[0].0, "feature/auth");
-        assert_eq!(result[0].1, 100.0);
-        assert_eq!(result[1].0, "feature/dashboard");
-        assert_eq!(result[1].1, 80.0);
-    }
-
-    #[test]
-    fn test_combine_fuzzy_and_frecency_scores_with_records() {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
-
-        let fuzzy_matches = vec![
-            ScoredMatch {
-                branch: "feature/auth".to_string(),
-                score: 80,
-            },
-            ScoredMatch {
-                branch: "feature/dashboard".to_string(),
-                score: 100,
-            },
-        ];
-
-        let records = vec![BranchRecord {
-            repo_path: "/test".to_string(),
-            branch_name: "feature/auth".to_string(),
-            switch_count: 10,
-            last_used: now - 60, // Recent: frecency score ≈ 10.0 (10 * ~1.0)
-        }];
-
-        let result = combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records);
-
-        assert_eq!(result.len(), 2);
-        // feature/auth should rank higher due to frecency
-        // auth: 80 + (10.0 * 10) = 180
-        // dashboard: 100 + (0 * 10) = 100
-        assert_eq!(result[0].0, "feature/auth");
-        assert!(result[0].1 > 179.0 && result[0].1 < 181.0);
-        assert_eq!(result[1].0, "feature/dashboard");
-        assert_eq!(result[1].1, 100.0);
-    }
-
-    #[test]
-    fn test_combine_fuzzy_and_frecency_scores_balanced() {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
-
-        let fuzzy_matches = vec![
-            ScoredMatch {
-                branch: "branch-a".to_string(),
-                score: 100,
-            },
-            ScoredMatch {
-                branch: "branch-b".to_string(),
-                score: 50,
-            },
-        ];
-
-        let records = vec![
-            BranchRecord {
-                repo_path: "/test".to_string(),
-                branch_name: "branch-a".to_string(),
-                switch_count: 1,
-                last_used: now - 3000000, // Old: frecency ≈ 0.03 (1 * 0.03)
-            },
-            BranchRecord {
-                repo_path: "/test".to_string(),
-                branch_name: "branch-b".to_string(),
-                switch_count: 5,
-                last_used: now - 60, // Recent: frecency ≈ 5.0 (5 * 1.0)
-            },
-        ];
-
-        let result = combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records);
-
-        assert_eq!(result.len(), 2);
-        // branch-a: 100 + (0.03 * 10) ≈ 100.3
-        // branch-b: 50 + (5.0 * 10) = 100.0
-        // branch-a wins slightly (better fuzzy match despite lower frecency)
-        assert_eq!(result[0].0, "branch-a");
-        assert_eq!(result[1].0, "branch-b");
-    }
-
-    #[test]
-    fn test_combine_fuzzy_and_frecency_scores_high_frecency() {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
-
-        let fuzzy_matches = vec![
-            ScoredMatch {
-                branch: "new-branch".to_string(),
-                score: 100,
-            },
-            ScoredMatch {
-                branch: "popular-branch".to_string(),
-                score: 60,
-            },
-        ];
-
-        let records = vec![BranchRecord {
-            repo_path: "/test".to_string(),
-            branch_name: "popular-branch".to_string(),
-            switch_count: 20,
-            last_used: now - 60, // Recent: frecency ≈ 20.0 (20 * ~1.0)
-        }];
-
-        let result = combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records);
-
-        assert_eq!(result.len(), 2);
-        // popular-branch: 60 + (20.0 * 10) = 260.0
-        // new-branch: 100 + (0 * 10) = 100.0
-        assert_eq!(result[0].0, "popular-branch");
-        assert!(result[0].1 > 259.0 && result[0].1 < 261.0);
-    }
-
-    #[test]
-    fn test_combine_fuzzy_and_frecency_scores_single_match() {
-        let fuzzy_matches = vec![ScoredMatch {
-            branch: "only-match".to_string(),
-            score: 75,
-        }];
-        let records: Vec<BranchRecord> = vec![];
-
-        let result = combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records);
-
-        assert_eq!(result.len(), 1);
-        assert_eq!(result[0].0, "only-match");
-        assert_eq!(result[0].1, 75.0);
-    }
-
-    #[test]
-    fn test_combine_fuzzy_and_frecency_scores_all_same_fuzzy() {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
-
-        let fuzzy_matches = vec![
-            ScoredMatch {
-                branch: "branch-a".to_string(),
-                score: 100,
-            },
-            ScoredMatch {
-                branch: "branch-b".to_string(),
-                score: 100,
-            },
-        ];
-
-        let records = vec![BranchRecord {
-            repo_path: "/test".to_string(),
-            branch_name: "branch-b".to_string(),
-            switch_count: 5,
-            last_used: now - 60, // Recent
-        }];
-
-        let result = combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records);
-
-        // branch-b should rank higher due to frecency
-        assert_eq!(result[0].0, "branch-b");
-        assert!(result[0].1 > result[1].1);
+    use ggo::commands::top_or_pick;
+
+    /// What 'ggo repo' / 'ggo wt' would use from `ranked`, or "menu"
+    fn jump_or_menu(args: &[&str], config: &config::Config, ranked: &[(String, f64)]) -> String {
+        let cli = Cli::parse_from(args);
+        top_or_pick(
+            ranked,
+            true,
+            auto_select_mode(&cli, config),
+            config.scoring().auto_select_threshold,
+            config,
+            || Ok("menu".to_string()),
+        )
+        .unwrap()
     }
 
     #[test]
-    fn test_combine_fuzzy_and_frecency_scores_partial_overlap() {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
-
-        let fuzzy_matches = vec![
-            ScoredMatch {
-                branch: "branch-a".to_string(),
-                score: 90,
-            },
-            ScoredMatch {
-                branch: "branch-b".to_string(),
-                score: 85,
-            },
-            ScoredMatch {
-                branch: "branch-c".to_string(),
-                score: 80,
-            },
+    fn test_auto_select_never_shows_the_menu_for_repo_and_wt() {
+        // A clear winner
+        let repos = vec![
+            ("/src/api/".to_string(), 400.0),
+            ("/src/apps/".to_string(), 20.0),
         ];
-
-        let records = vec![BranchRecord {
-            repo_path: "/test".to_string(),
-            branch_name: "branch-b".to_string(),
-            switch_count: 3,
-            last_used: now - 60,
-        }];
-
-        let result = combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records);
-
-        assert_eq!(result.len(), 3);
-        // branch-b should be first due to frecency boost
-        assert_eq!(result[0].0, "branch-b");
-    }
-
-    #[test]
-    fn test_combine_fuzzy_and_frecency_scores_zero_fuzzy_score() {
-        let fuzzy_matches = vec![ScoredMatch {
-            branch: "branch-a".to_string(),
-            score: 0,
-        }];
-        let records: Vec<BranchRecord> = vec![];
-        let result = combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records);
-
-        assert_eq!(result.len(), 1);
-        assert_eq!(result[0].1, 0.0);
-    }
-
-    #[test]
-    fn test_combine_fuzzy_and_frecency_ordering_consistency() {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
-
-        let fuzzy_matches = vec![
-            ScoredMatch {
-                branch: "high-fuzzy-low-frecency".to_string(),
-                score: 100,
-            },
-            ScoredMatch {
-                branch: "low-fuzzy-high-frecency".to_string(),
-                score: 20,
-            },
+        let branches = vec![
+            ("feature/api".to_string(), 400.0),
+            ("feature/apps".to_string(), 20.0),
         ];
+        let mut config = config::Config::default();
 
-        let records = vec![BranchRecord {
-            repo_path: "/test".to_string(),
-            branch_name: "low-fuzzy-high-frecency".to_string(),
-            switch_count: 50,
-            last_used: now - 60, // Recent, high frecency
-        }];
-
-        let result = combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records);
-
-        // Low fuzzy but high frecency should win
-        assert_eq!(result[0].0, "low-fuzzy-high-frecency");
-        assert!(result[0].1 > result[1].1);
-    }
-
-    #[test]
-    fn test_should_auto_select_clear_winner() {
-        // Test that 2x score ratio triggers auto-select
-        let top_score = 400.0;
-        let second_score = 150.0;
-
-        let should_auto_select = top_score / second_score >= 2.0;
-        assert!(should_auto_select);
-    }
-
-    #[test]
-    fn test_should_not_auto_select_close_scores() {
-        // Test that close scores (< 2x) trigger interactive menu
-        let top_score = 250.0;
-        let second_score = 200.0;
-
-        let should_auto_select = top_score / second_score >= 2.0;
-        assert!(!should_auto_select);
-    }
-
-    #[test]
-    fn test_should_auto_select_exact_2x() {
-        // Test boundary condition: exactly 2x should auto-select
-        let top_score = 200.0;
-        let second_score = 100.0;
-
-        let should_auto_select = top_score / second_score >= 2.0;
-        assert!(should_auto_select);
-    }
-
-    #[test]
-    fn test_should_auto_select_zero_second_score() {
-        // Test edge case: second score is 0, should always auto-select
-        let second_score = 0.0;
-
-        let should_auto_select = second_score == 0.0;
-        assert!(should_auto_select);
-    }
-
-    #[test]
-    fn test_should_not_auto_select_near_2x() {
-        // Test just under 2x threshold
-        let top_score = 199.0;
-        let second_score = 100.0;
+        assert_eq!(
+            jump_or_menu(&["ggo", "repo", "api"], &config, &repos),
+            "/src/api/"
+        );
+        assert_eq!(
+            jump_or_menu(&["ggo", "wt", "api"], &config, &branches),
+            "feature/api"
+        );
+        assert_eq!(
+            jump_or_menu(&["ggo", "--pick", "repo", "api"], &config, &repos),
+            "menu"
+        );
+        assert_eq!(
+            jump_or_menu(&["ggo", "--pick", "wt", "api"], &config, &branches),
+            "menu"
+        );
 
-        let should_auto_select = top_score / second_score >= 2.0;
-        assert!(!should_auto_select);
-    }
+        config.behavior.auto_select = AutoSelect::Never;
+        assert_eq!(
+            jump_or_menu(&["ggo", "repo", "api"], &config, &repos),
+            "menu"
+        );
+        assert_eq!(
+            jump_or_menu(&["ggo", "wt", "api"], &config, &branches),
+            "menu"
+        );
+        assert_eq!(
+            jump_or_menu(&["ggo", "--first", "wt", "api"], &config, &branches),
+            "feature/api"
+        );
 
-    #[test]
-    fn test_high_ratio_auto_selects() {
-        // Test very clear winner (5x)
-        let top_score = 500.0;
-        let second_score = 100.0;
+        // The minimum score applies too
+        config.behavior.auto_select = AutoSelect::Smart;
+        config.behavior.auto_select_min_score = 1000.0;
+        assert_eq!(
+            jump_or_menu(&["ggo", "repo", "api"], &config, &repos),
+            "menu"
+        );
 
-        let should_auto_select = top_score / second_score >= 2.0;
-        assert!(should_auto_select);
+        // A single match needs no menu
+        config.behavior.auto_select = AutoSelect::Never;
+        assert_eq!(
+            jump_or_menu(&["ggo", "wt", "api"], &config, &branches[..1]),
+            "feature/api"
+        );
     }
 }
@@ -1,5 +1,106 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
 use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
+use nucleo_matcher::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo_matcher::Utf32String;
+
+use rayon::prelude::*;
+use regex::{Regex, RegexBuilder};
+
+use crate::constants::scoring::{
+    ACRONYM_BONUS, DEFAULT_TICKET_PATTERN, DESCRIPTION_TERM_SCORE, PARALLEL_MATCH_THRESHOLD,
+    SEGMENT_START_BONUS, TICKET_BONUS,
+};
+
+/// Characters that start a new segment of a branch name (`feature/add-button`)
+const SEGMENT_SEPARATORS: [char; 3] = ['/', '-', '_'];
+
+/// The default ticket regex, compiled on first use rather than per call
+static DEFAULT_TICKETS: OnceLock<Option<TicketPattern>> = OnceLock::new();
+
+/// Extra fuzzy score for matches that line up with the structure of a branch name
+#[derive(Debug, Clone)]
+pub struct MatchBonuses {
+    /// Added per matched character that starts a path segment or word
+    pub segment_start: i64,
+    /// Added per pattern character when the pattern matches segment initials
+    /// (`fab` → `feature/add-button`)
+    pub acronym: i64,
+    /// Added per pattern term that names one of the branch's ticket IDs
+    pub ticket: i64,
+    /// Finds ticket IDs in branch names; None turns the ticket bonus off
+    pub tickets: Option<TicketPattern>,
+}
+
+impl Default for MatchBonuses {
+    fn default() -> Self {
+        Self {
+            segment_start: SEGMENT_START_BONUS,
+            acronym: ACRONYM_BONUS,
+            ticket: TICKET_BONUS,
+            tickets: DEFAULT_TICKETS
+                .get_or_init(|| TicketPattern::new(DEFAULT_TICKET_PATTERN).ok())
+                .clone(),
+        }
+    }
+}
+
+/// Regex for the ticket IDs teams put in branch names (`PROJ-1234`)
+#[derive(Debug, Clone)]
+pub struct TicketPattern {
+    /// As configured, for branch names
+    branches: Regex,
+    /// The same ignoring case, for pattern terms, which may have been
+    /// lowercased
+    terms: Regex,
+}
+
+impl TicketPattern {
+    pub fn new(pattern: &str) -> std::result::Result<Self, regex::Error> {
+        Ok(Self {
+            branches: Regex::new(pattern)?,
+            terms: RegexBuilder::new(pattern).case_insensitive(true).build()?,
+        })
+    }
+
+    /// Whether a pattern term names one of the tickets in `branch`: the term
+    /// contains the same ticket ID (case-insensitively), or is just digits
+    /// equal to a ticket's number (`1234` for `PROJ-1234`). `branch` is the
+    /// name as it is, not case-folded.
+    pub fn names_ticket(&self, term: &str, branch: &str) -> bool {
+        let mut wanted: Vec<&str> = self.terms.find_iter(term).map(|m| m.as_str()).collect();
+        let bare_number = term.bytes().all(|b| b.is_ascii_digit());
+        if bare_number && !term.is_empty() {
+            wanted.push(term);
+        }
+        if wanted.is_empty() {
+            return false;
+        }
+
+        self.branches.find_iter(branch).any(|ticket| {
+            let ticket = ticket.as_str();
+            wanted.iter().any(|&token| {
+                token.eq_ignore_ascii_case(ticket)
+                    || (bare_number && token == ticket_number(ticket))
+            })
+        })
+    }
+}
+
+/// The last run of digits in a ticket ID (`1234` in `PROJ-1234`)
+fn ticket_number(ticket: &str) -> &str {
+    let end = match ticket.rfind(|c: char| c.is_ascii_digit()) {
+        Some(i) => i + 1,
+        None => return "",
+    };
+    let start = ticket[..end]
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map_or(0, |i| i + 1);
+    &ticket[start..end]
+}
 
 /// A branch with its fuzzy match score
 #[derive(Debug, Clone)]
@@ -8,81 +109,519 @@ pub struct ScoredMatch {
     pub score: i64,
 }
 
+/// A fuzzy match borrowed from [`Candidates`]: nothing is copied until a
+/// caller keeps it ([`MatchRef::to_scored`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchRef<'a> {
+    /// Position of the branch in the candidate list
+    pub index: usize,
+    pub branch: &'a str,
+    pub score: i64,
+}
+
+impl MatchRef<'_> {
+    /// The match as an owned [`ScoredMatch`]
+    pub fn to_scored(self) -> ScoredMatch {
+        ScoredMatch {
+            branch: self.branch.to_string(),
+            score: self.score,
+        }
+    }
+}
+
+/// Branch names prepared for fuzzy matching: each name is case-folded once,
+/// when the set is built, so matching patterns against it neither folds nor
+/// allocates per branch. Keep one for as long as the branch list is in use
+/// (a ranking, a menu being filtered as you type).
+#[derive(Debug, Clone)]
+pub struct Candidates<'a> {
+    names: Cow<'a, [String]>,
+    /// Lowercased names, for the names lowercasing changes
+    folded: Vec<Option<Box<str>>>,
+}
+
+impl<'a> Candidates<'a> {
+    pub fn new(names: impl Into<Cow<'a, [String]>>) -> Self {
+        let names = names.into();
+        let folded = names
+            .iter()
+            .map(|name| match fold_case(name, true) {
+                Cow::Owned(folded) => Some(folded.into_boxed_str()),
+                Cow::Borrowed(_) => None,
+            })
+            .collect();
+        Self { names, folded }
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// The name matched against: lowercased with `ignore_case`
+    fn text(&self, index: usize, ignore_case: bool) -> &str {
+        match &self.folded[index] {
+            Some(folded) if ignore_case => folded,
+            _ => &self.names[index],
+        }
+    }
+
+    /// Score of the branch at `index`, like [`fuzzy_filter_branches`]; None
+    /// when some term of the pattern doesn't match it
+    pub fn fuzzy_score(
+        &self,
+        index: usize,
+        pattern: &str,
+        ignore_case: bool,
+        bonuses: &MatchBonuses,
+    ) -> Option<i64> {
+        let terms = pattern_terms(pattern, ignore_case);
+        if terms.is_empty() {
+            return Some(0);
+        }
+        score_terms(
+            &skim_matcher(),
+            self.text(index, ignore_case),
+            &self.names[index],
+            &terms,
+            bonuses,
+        )
+    }
+
+    /// Every branch matching `pattern`, like [`fuzzy_filter_branches`] but
+    /// borrowed and in list order rather than sorted: callers that keep only
+    /// the best few pick those out instead of sorting everything.
+    ///
+    /// Branches are scored in parallel from [`PARALLEL_MATCH_THRESHOLD`] on,
+    /// when there is more than one core; the result is the same either way.
+    pub fn fuzzy_matches(
+        &self,
+        pattern: &str,
+        ignore_case: bool,
+        bonuses: &MatchBonuses,
+    ) -> Vec<MatchRef<'_>> {
+        let terms = pattern_terms(pattern, ignore_case);
+        let matcher = skim_matcher();
+        let score = |index: usize| {
+            // An empty pattern matches everything
+            let score = if terms.is_empty() {
+                0
+            } else {
+                score_terms(
+                    &matcher,
+                    self.text(index, ignore_case),
+                    &self.names[index],
+                    &terms,
+                    bonuses,
+                )?
+            };
+            Some(MatchRef {
+                index,
+                branch: &self.names[index],
+                score,
+            })
+        };
+
+        // Collecting keeps the list order in both cases
+        let parallel = self.len() >= PARALLEL_MATCH_THRESHOLD && rayon::current_num_threads() > 1;
+        if parallel {
+            (0..self.len()).into_par_iter().filter_map(score).collect()
+        } else {
+            (0..self.len()).filter_map(score).collect()
+        }
+    }
+}
+
 /// Check if a branch name matches the given pattern (substring match)
 pub fn matches(branch: &str, pattern: &str, ignore_case: bool) -> bool {
-    if ignore_case {
-        branch.to_lowercase().contains(&pattern.to_lowercase())
+    fold_case(branch, ignore_case).contains(fold_case(pattern, ignore_case).as_ref())
+}
+
+/// `text` lowercased with `ignore_case`. Borrowed when there is nothing to
+/// fold, which is most branch names, so matching doesn't allocate per branch.
+fn fold_case(text: &str, ignore_case: bool) -> Cow<'_, str> {
+    let folds = |c: char| {
+        let mut lower = c.to_lowercase();
+        lower.next() != Some(c) || lower.next().is_some()
+    };
+    if !ignore_case {
+        Cow::Borrowed(text)
+    } else if text.is_ascii() {
+        if text.bytes().any(|b| b.is_ascii_uppercase()) {
+            Cow::Owned(text.to_ascii_lowercase())
+        } else {
+            Cow::Borrowed(text)
+        }
+    } else if text.chars().any(folds) {
+        Cow::Owned(text.to_lowercase())
     } else {
-        branch.contains(pattern)
+        Cow::Borrowed(text)
     }
 }
 
+/// Smart-case: a pattern without uppercase letters matches case-insensitively
+pub fn smart_case_ignores(pattern: &str) -> bool {
+    !pattern.chars().any(char::is_uppercase)
+}
+
+/// Check if a branch name matches a glob pattern from the exclude list.
+/// `*` matches any run of characters (including `/`), `?` matches one character.
+pub fn glob_matches(pattern: &str, branch: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let branch: Vec<char> = branch.chars().collect();
+
+    let (mut p, mut b) = (0, 0);
+    // Position of the last '*' in the pattern and the branch index it matched up to
+    let mut star: Option<(usize, usize)> = None;
+
+    while b < branch.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == branch[b]) {
+            p += 1;
+            b += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, b));
+            p += 1;
+        } else if let Some((star_p, star_b)) = star {
+            // Let the last '*' swallow one more character and retry
+            p = star_p + 1;
+            b = star_b + 1;
+            star = Some((star_p, star_b + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Check if a branch is excluded by any of the glob patterns
+pub fn is_excluded(branch: &str, exclude: &[String]) -> bool {
+    exclude.iter().any(|pattern| glob_matches(pattern, branch))
+}
+
+/// Drop branches excluded by any of the glob patterns
+pub fn exclude_branches(branches: Vec<String>, exclude: &[String]) -> Vec<String> {
+    if exclude.is_empty() {
+        return branches;
+    }
+
+    branches
+        .into_iter()
+        .filter(|branch| !is_excluded(branch, exclude))
+        .collect()
+}
+
 /// Filter branches by pattern using substring matching
 pub fn filter_branches<'a>(
     branches: &'a [String],
     pattern: &str,
     ignore_case: bool,
 ) -> Vec<&'a String> {
+    let pattern = fold_case(pattern, ignore_case);
     branches
         .iter()
-        .filter(|branch| matches(branch, pattern, ignore_case))
+        .filter(|branch| fold_case(branch, ignore_case).contains(pattern.as_ref()))
         .collect()
 }
 
+/// A tag with its fuzzy match score. Tags get their own match type so they
+/// never end up ranked among branches.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagMatch {
+    pub tag: String,
+    pub score: i64,
+}
+
+/// Filter and score tags with the same matching as branches: fuzzy, or
+/// substring (every score 0) without `fuzzy`
+pub fn filter_tags(
+    tags: &[String],
+    pattern: &str,
+    ignore_case: bool,
+    fuzzy: bool,
+) -> Vec<TagMatch> {
+    if fuzzy {
+        fuzzy_filter_branches(tags, pattern, ignore_case)
+            .into_iter()
+            .map(|m| TagMatch {
+                tag: m.branch,
+                score: m.score,
+            })
+            .collect()
+    } else {
+        filter_branches(tags, pattern, ignore_case)
+            .into_iter()
+            .map(|tag| TagMatch {
+                tag: tag.clone(),
+                score: 0,
+            })
+            .collect()
+    }
+}
+
 /// Filter and score branches using fuzzy matching
 /// Returns branches with their fuzzy match scores, sorted by score (highest first)
+///
+/// A pattern with several whitespace-separated terms (`"auth api"`) only
+/// matches branches where every term fuzzy-matches, in any order; the
+/// branch's score is the sum of the per-term scores.
 pub fn fuzzy_filter_branches(
     branches: &[String],
     pattern: &str,
     ignore_case: bool,
 ) -> Vec<ScoredMatch> {
-    if pattern.is_empty() {
-        // If no pattern, return all branches with zero score
-        return branches
-            .iter()
-            .map(|b| ScoredMatch {
-                branch: b.clone(),
-                score: 0,
-            })
-            .collect();
+    fuzzy_filter_branches_with_bonuses(branches, pattern, ignore_case, &MatchBonuses::default())
+}
+
+/// Like [`fuzzy_filter_branches`], with custom segment-start and acronym
+/// bonuses. Matching the same list more than once, or keeping only the best
+/// matches, is cheaper through [`Candidates`].
+pub fn fuzzy_filter_branches_with_bonuses(
+    branches: &[String],
+    pattern: &str,
+    ignore_case: bool,
+    bonuses: &MatchBonuses,
+) -> Vec<ScoredMatch> {
+    let candidates = Candidates::new(branches);
+    let mut matches = candidates.fuzzy_matches(pattern, ignore_case, bonuses);
+
+    // Stable, so ties keep the input order
+    matches.sort_by_key(|m| std::cmp::Reverse(m.score));
+    matches.into_iter().map(MatchRef::to_scored).collect()
+}
+
+/// Fuzzy score of one branch name, like [`fuzzy_filter_branches`]; None when
+/// it doesn't match
+pub fn fuzzy_score(
+    branch: &str,
+    pattern: &str,
+    ignore_case: bool,
+    bonuses: &MatchBonuses,
+) -> Option<i64> {
+    let terms = pattern_terms(pattern, ignore_case);
+    if terms.is_empty() {
+        return Some(0);
     }
+    score_terms(
+        &skim_matcher(),
+        &fold_case(branch, ignore_case),
+        branch,
+        &terms,
+        bonuses,
+    )
+}
+
+/// skim's matcher, case-sensitive: by default it ignores case for an
+/// all-lowercase pattern, but ignoring case is already done by folding both
+/// sides, so otherwise case has to count
+fn skim_matcher() -> SkimMatcherV2 {
+    SkimMatcherV2::default().respect_case()
+}
 
-    let matcher = SkimMatcherV2::default();
+/// The whitespace-separated terms of a pattern, lowercased with `ignore_case`
+fn pattern_terms(pattern: &str, ignore_case: bool) -> Vec<Cow<'_, str>> {
+    pattern
+        .split_whitespace()
+        .map(|term| fold_case(term, ignore_case))
+        .collect()
+}
 
-    let mut scored: Vec<ScoredMatch> = branches
+/// Sum of the per-term scores; every term has to match, so a single miss
+/// drops the branch. `text` is the branch name as matched (case-folded with
+/// `ignore_case`), `branch` the name as it is.
+fn score_terms(
+    matcher: &SkimMatcherV2,
+    text: &str,
+    branch: &str,
+    terms: &[Cow<str>],
+    bonuses: &MatchBonuses,
+) -> Option<i64> {
+    terms
         .iter()
-        .filter_map(|branch| {
-            let search_text = if ignore_case {
-                branch.to_lowercase()
-            } else {
-                branch.clone()
-            };
+        .map(|term| score_term(matcher, text, branch, term, bonuses))
+        .sum()
+}
 
-            let search_pattern = if ignore_case {
-                pattern.to_lowercase()
-            } else {
-                pattern.to_string()
-            };
+/// Match branches by a secondary text, such as their git description or ggo
+/// note: a branch matches when every term of the pattern appears in its
+/// text, scoring [`DESCRIPTION_TERM_SCORE`] per term. An empty pattern
+/// matches nothing (every branch already matches it by name).
+pub fn filter_by_text(
+    branches: &[String],
+    texts: &HashMap<String, String>,
+    pattern: &str,
+    ignore_case: bool,
+) -> Vec<ScoredMatch> {
+    let terms: Vec<&str> = pattern.split_whitespace().collect();
+    if terms.is_empty() {
+        return Vec::new();
+    }
 
-            matcher
-                .fuzzy_match(&search_text, &search_pattern)
-                .map(|score| ScoredMatch {
-                    branch: branch.clone(),
-                    score,
-                })
+    branches
+        .iter()
+        .filter(|branch| {
+            texts
+                .get(*branch)
+                .is_some_and(|text| terms.iter().all(|term| matches(text, term, ignore_case)))
         })
-        .collect();
+        .map(|branch| ScoredMatch {
+            branch: branch.clone(),
+            score: DESCRIPTION_TERM_SCORE * terms.len() as i64,
+        })
+        .collect()
+}
+
+/// Fuzzy score of a single term plus the segment-start, acronym and ticket
+/// bonuses; tickets are looked for in `branch`, the name before case-folding,
+/// so a configured pattern with uppercase letters still finds them
+fn score_term(
+    matcher: &SkimMatcherV2,
+    text: &str,
+    branch: &str,
+    term: &str,
+    bonuses: &MatchBonuses,
+) -> Option<i64> {
+    let (score, indices) = matcher.fuzzy_indices(text, term)?;
+
+    // Both are in ascending order, so one pass over each finds the overlap
+    let mut indices = indices.into_iter().peekable();
+    let segment_hits = segment_starts(text)
+        .filter(|&(start, _)| {
+            while indices.next_if(|&i| i < start).is_some() {}
+            indices.next_if_eq(&start).is_some()
+        })
+        .count() as i64;
+
+    let term_len = term.chars().count() as i64;
+    let acronym_bonus = if term_len > 1 && is_acronym_of(term, text) {
+        term_len * bonuses.acronym
+    } else {
+        0
+    };
+
+    let ticket_bonus = match &bonuses.tickets {
+        Some(tickets) if tickets.names_ticket(term, branch) => bonuses.ticket,
+        _ => 0,
+    };
+
+    Some(score + segment_hits * bonuses.segment_start + acronym_bonus + ticket_bonus)
+}
+
+/// Char indices and characters where a segment starts: the first character
+/// and every character following a separator
+fn segment_starts(text: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut at_start = true;
+    text.chars().enumerate().filter(move |&(_, c)| {
+        if SEGMENT_SEPARATORS.contains(&c) {
+            at_start = true;
+            false
+        } else {
+            std::mem::replace(&mut at_start, false)
+        }
+    })
+}
 
-    // Sort by score descending (higher scores = better matches)
-    scored.sort_by(|a, b| b.score.cmp(&a.score));
+/// Whether every character of the term matches, in order, the first
+/// character of some segment of the text
+fn is_acronym_of(term: &str, text: &str) -> bool {
+    let mut initials = segment_starts(text).map(|(_, initial)| initial);
 
-    scored
+    term.chars().all(|c| initials.any(|initial| initial == c))
+}
+
+/// Fuzzy matcher behind the `--tui` finder, over names converted once so
+/// that each keystroke only pays for scoring (thousands of branches stay
+/// interactive)
+pub struct Finder {
+    matcher: nucleo_matcher::Matcher,
+    haystacks: Vec<Utf32String>,
+}
+
+impl Finder {
+    pub fn new<'a>(names: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            matcher: nucleo_matcher::Matcher::new(nucleo_matcher::Config::DEFAULT),
+            haystacks: names.into_iter().map(Utf32String::from).collect(),
+        }
+    }
+
+    /// Indices of the names matching the query with their scores, best
+    /// first and ties in the names' order. Matching is fuzzy and
+    /// case-insensitive, with fzf syntax (`^main`, `!wip`, `'exact`).
+    pub fn matches(&mut self, query: &str) -> Vec<(usize, u32)> {
+        let pattern = Pattern::parse(query, CaseMatching::Ignore, Normalization::Smart);
+        let mut matches: Vec<(usize, u32)> = self
+            .haystacks
+            .iter()
+            .enumerate()
+            .filter_map(|(i, haystack)| {
+                pattern
+                    .score(haystack.slice(..), &mut self.matcher)
+                    .map(|score| (i, score))
+            })
+            .collect();
+        matches.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        matches
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_filter_by_text() {
+        let branches = vec![
+            "feature/svc-2231".to_string(),
+            "feature/svc-2232".to_string(),
+            "main".to_string(),
+        ];
+        let texts: HashMap<String, String> = [
+            ("feature/svc-2231", "Payments retry queue"),
+            ("feature/svc-2232", "Invoices"),
+        ]
+        .into_iter()
+        .map(|(b, t)| (b.to_string(), t.to_string()))
+        .collect();
+
+        let matched = filter_by_text(&branches, &texts, "payments retry", true);
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].branch, "feature/svc-2231");
+        assert_eq!(matched[0].score, 2 * DESCRIPTION_TERM_SCORE);
+
+        assert!(filter_by_text(&branches, &texts, "payments", false).is_empty());
+        assert!(filter_by_text(&branches, &texts, "payments invoices", true).is_empty());
+        assert!(filter_by_text(&branches, &texts, "", true).is_empty());
+    }
+
+    #[test]
+    fn test_filter_tags() {
+        let tags = vec![
+            "v1.2.0".to_string(),
+            "v2.0.0".to_string(),
+            "nightly".to_string(),
+        ];
+
+        let fuzzy = filter_tags(&tags, "v12", false, true);
+        assert_eq!(fuzzy.len(), 1);
+        assert_eq!(fuzzy[0].tag, "v1.2.0");
+        assert!(fuzzy[0].score > 0);
+
+        let substring = filter_tags(&tags, ".0.0", false, false);
+        assert_eq!(substring.len(), 1);
+        assert_eq!(substring[0].tag, "v2.0.0");
+        assert_eq!(substring[0].score, 0);
+
+        assert!(filter_tags(&tags, "v12", false, false).is_empty());
+    }
+
     #[test]
     fn test_matches_case_sensitive() {
         assert!(matches("feature/auth", "feat", false));
@@ -139,6 +678,15 @@ mod tests {
         assert!(matches("café-feature", "café", false));
     }
 
+    #[test]
+    fn test_smart_case_ignores() {
+        assert!(smart_case_ignores("auth"));
+        assert!(smart_case_ignores("feature/123"));
+        assert!(smart_case_ignores(""));
+        assert!(!smart_case_ignores("Auth"));
+        assert!(!smart_case_ignores("JIRA-12"));
+    }
+
     #[test]
     fn test_filter_branches() {
         let branches = vec![
@@ -294,6 +842,26 @@ mod tests {
             .any(|m| m.branch.contains("auth") || m.branch.contains("Auth")));
     }
 
+    #[test]
+    fn test_fuzzy_case_sensitive_lowercase_pattern_skips_mixed_case() {
+        let branches = vec!["Feature-Login".to_string(), "feature-logout".to_string()];
+
+        let matches = fuzzy_filter_branches(&branches, "feat", false);
+        let names: Vec<&str> = matches.iter().map(|m| m.branch.as_str()).collect();
+        assert_eq!(names, vec!["feature-logout"]);
+        assert_eq!(
+            fuzzy_score("Feature-Login", "feat", false, &MatchBonuses::default()),
+            None
+        );
+
+        let candidates = Candidates::new(&branches);
+        assert_eq!(
+            candidates.fuzzy_score(0, "feat", false, &MatchBonuses::default()),
+            None
+        );
+        assert_eq!(fuzzy_filter_branches(&branches, "feat", true).len(), 2);
+    }
+
     #[test]
     fn test_fuzzy_filter_complex_pattern() {
         let branches = vec![
@@ -324,6 +892,216 @@ mod tests {
         assert!(matches[1].score >= matches[2].score);
     }
 
+    #[test]
+    fn test_fuzzy_filter_multiple_terms() {
+        let branches = vec![
+            "feature/auth-api".to_string(),
+            "feature/api-auth-refactor".to_string(),
+            "feature/auth-ui".to_string(),
+            "fix/api-timeout".to_string(),
+        ];
+
+        let matches = fuzzy_filter_branches(&branches, "auth api", false);
+        let names: Vec<&str> = matches.iter().map(|m| m.branch.as_str()).collect();
+
+        // Both terms must match, in any order
+        assert_eq!(matches.len(), 2);
+        assert!(names.contains(&"feature/auth-api"));
+        assert!(names.contains(&"feature/api-auth-refactor"));
+
+        // The score is the sum of the per-term scores
+        let single = |term| fuzzy_filter_branches(&branches[..1], term, false)[0].score;
+        let expected = single("auth") + single("api");
+        let auth_api = matches
+            .iter()
+            .find(|m| m.branch == "feature/auth-api")
+            .unwrap();
+        assert_eq!(auth_api.score, expected);
+    }
+
+    #[test]
+    fn test_fuzzy_filter_parallel_matches_sequential() {
+        let branches: Vec<String> = (0..PARALLEL_MATCH_THRESHOLD + 100)
+            .map(|i| format!("feature/PROJ-{}-{}", i, ["auth", "api", "ui"][i % 3]))
+            .collect();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        let parallel = pool.install(|| fuzzy_filter_branches(&branches, "auth", true));
+
+        // Chunks below the threshold are scored one by one; a stable sort of
+        // their concatenation keeps ties in input order, as the parallel run must
+        let mut sequential: Vec<ScoredMatch> = branches
+            .chunks(100)
+            .flat_map(|chunk| fuzzy_filter_branches(chunk, "auth", true))
+            .collect();
+        sequential.sort_by_key(|m| std::cmp::Reverse(m.score));
+
+        let pairs = |matches: &[ScoredMatch]| -> Vec<(String, i64)> {
+            matches
+                .iter()
+                .map(|m| (m.branch.clone(), m.score))
+                .collect()
+        };
+        assert!(!parallel.is_empty());
+        assert_eq!(pairs(&parallel), pairs(&sequential));
+    }
+
+    #[test]
+    fn test_fold_case_borrows_unless_it_folds() {
+        assert!(matches!(fold_case("feature/x", true), Cow::Borrowed(_)));
+        assert!(matches!(fold_case("Feature/X", false), Cow::Borrowed(_)));
+        assert_eq!(fold_case("Feature/X", true), "feature/x");
+        assert_eq!(fold_case("fix/ÉTÉ", true), "fix/été");
+        assert!(matches!(fold_case("fix/été", true), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_candidates_match_like_fuzzy_filter() {
+        let branches: Vec<String> = ["Feature/Auth", "fix/login", "feature/api", "main"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let candidates = Candidates::new(&branches[..]);
+        let bonuses = MatchBonuses::default();
+
+        for (pattern, ignore_case) in [
+            ("feat", true),
+            ("Feat", false),
+            ("fix log", true),
+            ("", true),
+        ] {
+            let mut matches = candidates.fuzzy_matches(pattern, ignore_case, &bonuses);
+            for m in &matches {
+                assert_eq!(m.branch, branches[m.index]);
+                assert_eq!(
+                    candidates.fuzzy_score(m.index, pattern, ignore_case, &bonuses),
+                    Some(m.score)
+                );
+            }
+            matches.sort_by_key(|m| std::cmp::Reverse(m.score));
+
+            let expected = fuzzy_filter_branches(&branches, pattern, ignore_case);
+            let got: Vec<(&str, i64)> = matches.iter().map(|m| (m.branch, m.score)).collect();
+            let expected: Vec<(&str, i64)> = expected
+                .iter()
+                .map(|m| (m.branch.as_str(), m.score))
+                .collect();
+            assert_eq!(got, expected, "pattern {:?}", pattern);
+        }
+        assert_eq!(candidates.fuzzy_score(3, "feat", true, &bonuses), None);
+    }
+
+    #[test]
+    fn test_fuzzy_filter_whitespace_only_pattern() {
+        let branches = vec!["main".to_string(), "develop".to_string()];
+
+        let matches = fuzzy_filter_branches(&branches, "   ", false);
+        assert_eq!(matches.len(), 2);
+        assert!(matches.iter().all(|m| m.score == 0));
+    }
+
+    #[test]
+    fn test_fuzzy_filter_prefers_segment_starts() {
+        let branches = vec![
+            "refactor-about-page".to_string(),
+            "feature/add-button".to_string(),
+        ];
+
+        let matches = fuzzy_filter_branches(&branches, "fab", false);
+        assert_eq!(matches[0].branch, "feature/add-button");
+
+        // The acronym alone carries it: without bonuses the order is decided
+        // by the raw fuzzy scores only
+        let none = MatchBonuses {
+            segment_start: 0,
+            acronym: 0,
+            ticket: 0,
+            tickets: None,
+        };
+        let plain = fuzzy_filter_branches_with_bonuses(&branches, "fab", false, &none);
+        let boosted = matches
+            .iter()
+            .find(|m| m.branch == "feature/add-button")
+            .unwrap();
+        let unboosted = plain
+            .iter()
+            .find(|m| m.branch == "feature/add-button")
+            .unwrap();
+        assert!(boosted.score - unboosted.score >= 3 * ACRONYM_BONUS);
+    }
+
+    #[test]
+    fn test_ticket_number_beats_fuzzy_noise() {
+        let branches = vec![
+            "fix/1-2-3-4-layout".to_string(),
+            "release/2024-12-34".to_string(),
+            "feature/PROJ-1234-fix-login".to_string(),
+        ];
+
+        let matches = fuzzy_filter_branches(&branches, "1234", true);
+        assert_eq!(matches[0].branch, "feature/PROJ-1234-fix-login");
+        assert!(matches[0].score - matches[1].score >= TICKET_BONUS / 2);
+
+        let matches = fuzzy_filter_branches(&branches, "proj-1234", true);
+        assert_eq!(matches[0].branch, "feature/PROJ-1234-fix-login");
+    }
+
+    #[test]
+    fn test_ticket_pattern_names_ticket() {
+        let tickets = TicketPattern::new(DEFAULT_TICKET_PATTERN).unwrap();
+        assert!(tickets.names_ticket("1234", "feature/PROJ-1234-fix"));
+        assert!(tickets.names_ticket("proj-1234", "feature/PROJ-1234-fix"));
+        assert!(tickets.names_ticket("GH-567", "gh-567"));
+        assert!(!tickets.names_ticket("123", "feature/PROJ-1234-fix"));
+        assert!(!tickets.names_ticket("OTHER-1234", "feature/PROJ-1234-fix"));
+        assert!(!tickets.names_ticket("login", "feature/PROJ-1234-login"));
+
+        // Custom patterns: GitHub issue references
+        let tickets = TicketPattern::new(r"#[0-9]+").unwrap();
+        assert!(tickets.names_ticket("42", "fix/#42-crash"));
+        assert!(!tickets.names_ticket("42", "fix/PROJ-42"));
+    }
+
+    #[test]
+    fn test_uppercase_ticket_pattern_scores_when_ignoring_case() {
+        let bonuses = MatchBonuses {
+            tickets: Some(TicketPattern::new(r"PROJ-[0-9]+").unwrap()),
+            ..MatchBonuses::default()
+        };
+        let no_tickets = MatchBonuses {
+            tickets: None,
+            ..MatchBonuses::default()
+        };
+        let branch = "feature/PROJ-1234-login";
+        let text = fold_case(branch, true);
+        let matcher = skim_matcher();
+
+        for term in ["1234", "proj-1234"] {
+            let with = score_term(&matcher, &text, branch, term, &bonuses).unwrap();
+            let without = score_term(&matcher, &text, branch, term, &no_tickets).unwrap();
+            assert_eq!(with - without, TICKET_BONUS, "{}", term);
+        }
+
+        let scored = fuzzy_score(branch, "1234", true, &bonuses).unwrap();
+        let plain = fuzzy_score(branch, "1234", true, &no_tickets).unwrap();
+        assert_eq!(scored - plain, TICKET_BONUS);
+    }
+
+    #[test]
+    fn test_segment_starts_and_acronyms() {
+        let starts = |text| segment_starts(text).map(|(i, _)| i).collect::<Vec<_>>();
+        assert_eq!(starts("feature/add-button"), vec![0, 8, 12]);
+        assert_eq!(starts("a__b"), vec![0, 3]);
+        assert!(starts("").is_empty());
+
+        assert!(is_acronym_of("fab", "feature/add-button"));
+        assert!(is_acronym_of("fb", "feature/add-button"));
+        assert!(!is_acronym_of("bf", "feature/add-button"));
+        assert!(!is_acronym_of("fe", "feature/add-button"));
+    }
+
     #[test]
     fn test_scored_match_clone() {
         let original = ScoredMatch {
@@ -345,4 +1123,52 @@ mod tests {
         assert!(debug_str.contains("test"));
         assert!(debug_str.contains("100"));
     }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("release/*", "release/1.0"));
+        assert!(glob_matches("release/*", "release/1.0/hotfix"));
+        assert!(!glob_matches("release/*", "prerelease/1.0"));
+        assert!(glob_matches("dependabot/*", "dependabot/npm/lodash-4.17"));
+        assert!(glob_matches("*-wip", "feature/auth-wip"));
+        assert!(glob_matches("v?.?", "v1.2"));
+        assert!(!glob_matches("v?.?", "v1.20"));
+        assert!(glob_matches("main", "main"));
+        assert!(!glob_matches("main", "main2"));
+        assert!(glob_matches("*", "anything"));
+        assert!(glob_matches("a*b*c", "a-x-b-y-c"));
+        assert!(!glob_matches("a*b*c", "a-x-c-y-b"));
+    }
+
+    #[test]
+    fn test_exclude_branches() {
+        let branches = vec![
+            "main".to_string(),
+            "release/1.0".to_string(),
+            "dependabot/npm/lodash".to_string(),
+            "feature/auth".to_string(),
+        ];
+        let exclude = vec!["release/*".to_string(), "dependabot/*".to_string()];
+
+        assert_eq!(
+            exclude_branches(branches.clone(), &exclude),
+            vec!["main".to_string(), "feature/auth".to_string()]
+        );
+        assert_eq!(exclude_branches(branches.clone(), &[]), branches);
+        assert!(is_excluded("release/2.0", &exclude));
+        assert!(!is_excluded("main", &exclude));
+    }
+
+    #[test]
+    fn test_finder_matches_best_first() {
+        let names = ["main", "feature/auth", "fix/author-name"];
+        let mut finder = Finder::new(names);
+
+        let matches: Vec<usize> = finder.matches("AUTH").iter().map(|&(i, _)| i).collect();
+        assert_eq!(matches.len(), 2);
+        assert!(!matches.contains(&0));
+        assert_eq!(finder.matches("")[0].0, 0);
+        assert_eq!(finder.matches("!auth").len(), 1);
+        assert_eq!(finder.matches("^fix")[0].0, 2);
+    }
 }
@@ -0,0 +1,112 @@
+//! Ranked branch candidates for pickers outside the terminal: 'ggo query'
+//! for editor plugins and the 'list-ranked' request of 'ggo serve'.
+
+use serde::Serialize;
+use tracing::debug;
+
+use crate::backend::{self, GitBackend};
+use crate::error::Result;
+use crate::ranking;
+use crate::{config, frecency, storage, validation};
+
+/// One 'ggo query' (and 'ggo serve' list-ranked) entry
+#[derive(Serialize)]
+pub struct QueriedBranch {
+    pub branch: String,
+    pub score: f64,
+    pub frecency_score: f64,
+    pub switch_count: i64,
+    /// None when the branch has never been switched to with ggo
+    pub last_used: Option<i64>,
+    pub aliases: Vec<String>,
+    pub pinned: bool,
+    pub note: Option<String>,
+    /// Checked out in the current worktree
+    pub current: bool,
+}
+
+/// The branches matching `pattern` (all of them when it is empty) ranked
+/// like 'ggo -l', pinned ones first, without the slower per-branch git
+/// lookups. `limit` 0 keeps them all.
+pub fn query_branches(
+    storage: &storage::Storage,
+    config: &config::Config,
+    repo_path: &str,
+    pattern: &str,
+    limit: usize,
+) -> Result<Vec<QueriedBranch>> {
+    let backend = backend::backend();
+    let branches = config
+        .behavior
+        .offered_branches(cached_branches(backend, storage, repo_path)?);
+    let records = storage.branch_records(repo_path)?;
+    let scoring = config.scoring_for(repo_path);
+
+    let ranked = if pattern.is_empty() {
+        frecency::sort_branches_by_frecency(&branches, &records, scoring.half_life_days)
+    } else {
+        validation::validate_pattern(pattern)?;
+        ranking::score_matching_branches(
+            &branches,
+            pattern,
+            config.behavior.ignore_case_for(pattern),
+            config.behavior.default_fuzzy,
+            &records,
+            &scoring,
+            &config.matching.bonuses(),
+        )
+    };
+    let pinned = storage.pinned_branches(repo_path)?;
+    let aliases = storage.aliases(repo_path)?;
+    let notes = storage.branch_notes(repo_path)?;
+    let current = backend.current_branch().ok();
+
+    Ok(ranking::top_ranked(ranked, &pinned, limit)
+        .into_iter()
+        .map(|(branch, score)| {
+            let record = records.iter().find(|r| r.branch_name == branch);
+            QueriedBranch {
+                score,
+                frecency_score: record
+                    .map(|r| frecency::calculate_score(r, scoring.half_life_days))
+                    .unwrap_or(0.0),
+                switch_count: record.map(|r| r.switch_count).unwrap_or(0),
+                last_used: record.map(|r| r.last_used),
+                aliases: aliases
+                    .iter()
+                    .filter(|alias| alias.branch_name == branch)
+                    .map(|alias| alias.alias.clone())
+                    .collect(),
+                pinned: pinned.contains(&branch),
+                note: notes.get(&branch).cloned(),
+                current: current.as_ref() == Some(&branch),
+                branch,
+            }
+        })
+        .collect())
+}
+
+/// Local branch names, served from the database while the refs fingerprint
+/// is unchanged (listing thousands of refs is the slow part of a checkout)
+pub fn cached_branches(
+    backend: &dyn GitBackend,
+    storage: &storage::Storage,
+    repo_path: &str,
+) -> Result<Vec<String>> {
+    let fingerprint = backend.refs_fingerprint()?;
+    if let Some(fingerprint) = &fingerprint {
+        match storage.cached_branches(repo_path, fingerprint) {
+            Ok(Some(branches)) => return Ok(branches),
+            Ok(None) => {}
+            Err(e) => debug!("Branch cache unavailable: {}", e),
+        }
+    }
+
+    let branches = backend.local_branches()?;
+    if let Some(fingerprint) = fingerprint {
+        if let Err(e) = storage.cache_branches(repo_path, &fingerprint, &branches) {
+            debug!("Failed to cache the branch list: {}", e);
+        }
+    }
+    Ok(branches)
+}
@@ -0,0 +1,1315 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::config::AutoSelect;
+use crate::constants::scoring::TRANSITION_BOOST;
+use crate::frecency::{self, ScoringParams};
+use crate::matcher::{self, Candidates, MatchBonuses, ScoredMatch, TagMatch};
+use crate::storage::{BranchRecord, Transition};
+
+/// Combine fuzzy match scores with frecency scores for final ranking
+/// Formula: combined_score = fuzzy_score + (frecency_score * frecency_multiplier)
+/// This gives weight to both good fuzzy matches and frequently-used branches
+pub fn combine_fuzzy_and_frecency_scores(
+    fuzzy_matches: &[ScoredMatch],
+    records: &[BranchRecord],
+    params: &ScoringParams,
+) -> Vec<(String, f64)> {
+    let matches = fuzzy_matches.iter().map(|m| (m.branch.as_str(), m.score));
+    sorted_owned(with_frecency(matches, records, params))
+}
+
+/// Add each match's weighted frecency to its fuzzy score, still borrowing
+/// the branch names
+fn with_frecency<'a>(
+    matches: impl Iterator<Item = (&'a str, i64)>,
+    records: &[BranchRecord],
+    params: &ScoringParams,
+) -> Vec<(&'a str, f64)> {
+    let frecency_map = frecency_by_branch(records, params.half_life_days);
+
+    matches
+        .map(|(branch, fuzzy_score)| {
+            let frecency_score = frecency_map.get(branch).copied().unwrap_or(0.0);
+
+            // Combine scores: fuzzy match quality + (frecency * weight)
+            // Frecency gets a multiplier to give it significant weight
+            (
+                branch,
+                fuzzy_score as f64 + (frecency_score * params.frecency_multiplier),
+            )
+        })
+        .collect()
+}
+
+/// Sort by combined score descending, copying the names only now
+fn sorted_owned(mut combined: Vec<(&str, f64)>) -> Vec<(String, f64)> {
+    combined.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+    combined
+        .into_iter()
+        .map(|(branch, score)| (branch.to_string(), score))
+        .collect()
+}
+
+/// Match branches against the pattern and rank them by match quality and frecency.
+/// Returns an empty list when nothing matches.
+pub fn rank_matching_branches(
+    branches: &[String],
+    pattern: &str,
+    ignore_case: bool,
+    use_fuzzy: bool,
+    records: &[BranchRecord],
+    params: &ScoringParams,
+    bonuses: &MatchBonuses,
+) -> Vec<(String, f64)> {
+    let mut ranked = score_matching_branches(
+        branches,
+        pattern,
+        ignore_case,
+        use_fuzzy,
+        records,
+        params,
+        bonuses,
+    );
+    ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+    ranked
+}
+
+/// The scores [`rank_matching_branches`] ranks by, in branch list order.
+/// Pipelines that end in [`float_pinned`] or [`top_ranked`] start here, so
+/// the branches are only put in order once, and with a limit only the
+/// branches kept are.
+pub fn score_matching_branches(
+    branches: &[String],
+    pattern: &str,
+    ignore_case: bool,
+    use_fuzzy: bool,
+    records: &[BranchRecord],
+    params: &ScoringParams,
+    bonuses: &MatchBonuses,
+) -> Vec<(String, f64)> {
+    if use_fuzzy {
+        // Use fuzzy matching and combine with frecency
+        let candidates = Candidates::new(branches);
+        let matches = candidates
+            .fuzzy_matches(pattern, ignore_case, bonuses)
+            .into_iter()
+            .map(|m| (m.branch, m.score));
+        with_frecency(matches, records, params)
+            .into_iter()
+            .map(|(branch, score)| (branch.to_string(), score))
+            .collect()
+    } else {
+        // Use exact substring matching; only frecency counts
+        let frecency_map = frecency_by_branch(records, params.half_life_days);
+        matcher::filter_branches(branches, pattern, ignore_case)
+            .into_iter()
+            .map(|branch| {
+                let score = frecency_map.get(branch.as_str()).copied().unwrap_or(0.0);
+                (branch.clone(), score)
+            })
+            .collect()
+    }
+}
+
+/// Frecency score of each recorded branch
+fn frecency_by_branch(records: &[BranchRecord], half_life_days: f64) -> HashMap<&str, f64> {
+    records
+        .iter()
+        .map(|r| {
+            (
+                r.branch_name.as_str(),
+                frecency::calculate_score(r, half_life_days),
+            )
+        })
+        .collect()
+}
+
+/// How one candidate's score was put together, for `ggo explain`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreBreakdown {
+    pub branch: String,
+    /// None with substring matching
+    pub fuzzy_score: Option<i64>,
+    pub switch_count: i64,
+    /// None when the branch has never been switched to with ggo
+    pub last_used: Option<i64>,
+    /// Recency weight applied to the switch count
+    pub decay_weight: f64,
+    pub frecency_score: f64,
+    /// What frecency adds to the combined score
+    pub frecency_component: f64,
+    /// What use in the current session adds (see [`session_boosts`])
+    pub session_component: f64,
+    pub score: f64,
+}
+
+/// Like [`rank_matching_branches`], but keeping every part of each score
+pub fn explain_matching_branches(
+    branches: &[String],
+    pattern: &str,
+    ignore_case: bool,
+    use_fuzzy: bool,
+    records: &[BranchRecord],
+    params: &ScoringParams,
+    bonuses: &MatchBonuses,
+) -> Vec<ScoreBreakdown> {
+    let candidates: Vec<(String, Option<i64>)> = if use_fuzzy {
+        matcher::fuzzy_filter_branches_with_bonuses(branches, pattern, ignore_case, bonuses)
+            .into_iter()
+            .map(|m| (m.branch, Some(m.score)))
+            .collect()
+    } else {
+        matcher::filter_branches(branches, pattern, ignore_case)
+            .into_iter()
+            .map(|b| (b.clone(), None))
+            .collect()
+    };
+
+    let mut explained: Vec<ScoreBreakdown> = candidates
+        .into_iter()
+        .map(|(branch, fuzzy_score)| {
+            let record = records.iter().find(|r| r.branch_name == branch);
+            let decay_weight = record
+                .map(|r| frecency::recency_weight(r.last_used, params.half_life_days))
+                .unwrap_or(0.0);
+            let switch_count = record.map(|r| r.switch_count).unwrap_or(0);
+            let frecency_score = switch_count as f64 * decay_weight;
+
+            // Same formulas as rank_matching_branches
+            let (frecency_component, score) = match fuzzy_score {
+                Some(fuzzy) => {
+                    let component = frecency_score * params.frecency_multiplier;
+                    (component, fuzzy as f64 + component)
+                }
+                None => (frecency_score, frecency_score),
+            };
+
+            ScoreBreakdown {
+                branch,
+                fuzzy_score,
+                switch_count,
+                last_used: record.map(|r| r.last_used),
+                decay_weight,
+                frecency_score,
+                frecency_component,
+                session_component: 0.0,
+                score,
+            }
+        })
+        .collect();
+
+    explained.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    explained
+}
+
+/// Whether the top of a ranking is picked without asking: it's the only
+/// match, the runner-up scores 0, or it scores at least `threshold` times
+/// the runner-up
+pub fn is_clear_winner(ranked: &[(String, f64)], threshold: f64) -> bool {
+    match ranked {
+        [] => false,
+        [_] => true,
+        [(_, top), (_, second), ..] => *second == 0.0 || top / second >= threshold,
+    }
+}
+
+/// Whether the top of a ranking is picked without asking under `mode`.
+/// `Smart` needs a clear winner (see [`is_clear_winner`]) that also scores
+/// at least `min_score`.
+pub fn auto_selects(
+    ranked: &[(String, f64)],
+    mode: AutoSelect,
+    threshold: f64,
+    min_score: f64,
+) -> bool {
+    match mode {
+        AutoSelect::Always => !ranked.is_empty(),
+        AutoSelect::Never => false,
+        AutoSelect::Smart => is_clear_winner(ranked, threshold) && ranked[0].1 >= min_score,
+    }
+}
+
+/// Put scored branches in their final order: pinned branches first, then by
+/// score, highest first. Equal entries keep their order.
+pub fn float_pinned(ranked: Vec<(String, f64)>, pinned: &[String]) -> Vec<(String, f64)> {
+    top_ranked(ranked, pinned, 0)
+}
+
+/// The first `limit` entries [`float_pinned`] would return. Only those are
+/// sorted: the rest are set aside by a selection pass, so listing the best
+/// few of thousands of branches doesn't sort thousands. A `limit` of 0 keeps
+/// every entry.
+pub fn top_ranked(
+    ranked: Vec<(String, f64)>,
+    pinned: &[String],
+    limit: usize,
+) -> Vec<(String, f64)> {
+    // The position breaks ties, which makes the unstable sorts stable
+    let mut keyed: Vec<(bool, usize, (String, f64))> = ranked
+        .into_iter()
+        .enumerate()
+        .map(|(position, entry)| (!pinned.contains(&entry.0), position, entry))
+        .collect();
+    let order = |a: &(bool, usize, (String, f64)), b: &(bool, usize, (String, f64))| {
+        a.0.cmp(&b.0)
+            .then_with(|| b.2 .1.total_cmp(&a.2 .1))
+            .then_with(|| a.1.cmp(&b.1))
+    };
+
+    if limit > 0 && limit < keyed.len() {
+        keyed.select_nth_unstable_by(limit - 1, order);
+        keyed.truncate(limit);
+    }
+    keyed.sort_unstable_by(order);
+    keyed.into_iter().map(|(_, _, entry)| entry).collect()
+}
+
+/// Add the branches whose note contains every term of the pattern to a
+/// ranking of name matches (`ggo --notes`). Branches matched only by their
+/// note score their frecency. Added branches go at the end: the order is
+/// left to [`float_pinned`] or [`top_ranked`].
+pub fn add_note_matches(
+    mut ranked: Vec<(String, f64)>,
+    branches: &[String],
+    notes: &HashMap<String, String>,
+    pattern: &str,
+    ignore_case: bool,
+    records: &[BranchRecord],
+    half_life_days: f64,
+) -> Vec<(String, f64)> {
+    for matched in matcher::filter_by_text(branches, notes, pattern, ignore_case) {
+        if ranked.iter().any(|(b, _)| b == &matched.branch) {
+            continue;
+        }
+        let score = records
+            .iter()
+            .find(|r| r.branch_name == matched.branch)
+            .map(|r| frecency::calculate_score(r, half_life_days))
+            .unwrap_or(0.0);
+        ranked.push((matched.branch, score));
+    }
+
+    ranked
+}
+
+/// Add the branches matched by their git description
+/// ([`matcher::filter_by_text`]) to a ranking of name matches. The
+/// description score stands in for the fuzzy score; with substring matching
+/// only frecency counts, as for name matches. Added branches go at the end,
+/// like with [`add_note_matches`].
+pub fn add_description_matches(
+    mut ranked: Vec<(String, f64)>,
+    description_matches: &[ScoredMatch],
+    use_fuzzy: bool,
+    records: &[BranchRecord],
+    params: &ScoringParams,
+) -> Vec<(String, f64)> {
+    let added: Vec<ScoredMatch> = description_matches
+        .iter()
+        .filter(|m| !ranked.iter().any(|(b, _)| b == &m.branch))
+        .cloned()
+        .collect();
+    if added.is_empty() {
+        return ranked;
+    }
+
+    if use_fuzzy {
+        let matches = added.iter().map(|m| (m.branch.as_str(), m.score));
+        ranked.extend(
+            with_frecency(matches, records, params)
+                .into_iter()
+                .map(|(branch, score)| (branch.to_string(), score)),
+        );
+    } else {
+        let frecency_map = frecency_by_branch(records, params.half_life_days);
+        ranked.extend(added.into_iter().map(|m| {
+            let score = frecency_map.get(m.branch.as_str()).copied().unwrap_or(0.0);
+            (m.branch, score)
+        }));
+    }
+
+    ranked
+}
+
+/// A predicted next branch: how likely it is to follow the current one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub branch: String,
+    pub probability: f64,
+    pub count: i64,
+}
+
+/// Turn transition counts out of the current branch into next-branch
+/// probabilities (a first-order Markov chain), most likely first.
+/// Branches not in `branches` (deleted since) are dropped before normalizing.
+pub fn suggest_next_branches(transitions: &[Transition], branches: &[String]) -> Vec<Suggestion> {
+    let live: Vec<&Transition> = transitions
+        .iter()
+        .filter(|t| branches.contains(&t.to_branch))
+        .collect();
+
+    let total: i64 = live.iter().map(|t| t.count).sum();
+    if total == 0 {
+        return Vec::new();
+    }
+
+    let mut suggestions: Vec<Suggestion> = live
+        .iter()
+        .map(|t| Suggestion {
+            branch: t.to_branch.clone(),
+            probability: t.count as f64 / total as f64,
+            count: t.count,
+        })
+        .collect();
+
+    suggestions.sort_by(|a, b| {
+        b.probability
+            .partial_cmp(&a.probability)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    suggestions
+}
+
+/// Boost ranked branches by how likely they are to follow the current branch
+/// (`probability * TRANSITION_BOOST`). The order is left to [`float_pinned`]
+/// or [`top_ranked`].
+pub fn apply_transition_boost(
+    mut ranked: Vec<(String, f64)>,
+    transitions: &[Transition],
+) -> Vec<(String, f64)> {
+    let branches: Vec<String> = ranked.iter().map(|(b, _)| b.clone()).collect();
+    let probabilities: HashMap<String, f64> = suggest_next_branches(transitions, &branches)
+        .into_iter()
+        .map(|s| (s.branch, s.probability))
+        .collect();
+
+    for (branch, score) in ranked.iter_mut() {
+        if let Some(p) = probabilities.get(branch) {
+            *score += p * TRANSITION_BOOST;
+        }
+    }
+
+    ranked
+}
+
+/// What each branch used in the current session (weights from
+/// [`frecency::session_weights`]) adds to its score: a branch switched to
+/// moments ago gains `threshold` times the top score, so it is picked without
+/// asking however much lifetime use the others have. The lift fades with its
+/// weight.
+pub fn session_boosts(
+    ranked: &[(String, f64)],
+    weights: &HashMap<String, f64>,
+    threshold: f64,
+) -> HashMap<String, f64> {
+    let top = ranked.iter().map(|(_, score)| *score).fold(0.0, f64::max);
+
+    ranked
+        .iter()
+        .filter_map(|(branch, _)| {
+            let weight = weights.get(branch)?;
+            Some((branch.clone(), weight * threshold * top))
+        })
+        .collect()
+}
+
+/// Add the [`session_boosts`] to the ranked branches, leaving the order to
+/// [`float_pinned`] or [`top_ranked`]
+pub fn apply_session_boost(
+    mut ranked: Vec<(String, f64)>,
+    weights: &HashMap<String, f64>,
+    threshold: f64,
+) -> Vec<(String, f64)> {
+    let boosts = session_boosts(&ranked, weights, threshold);
+    for (branch, score) in ranked.iter_mut() {
+        if let Some(boost) = boosts.get(branch) {
+            *score += boost;
+        }
+    }
+
+    ranked
+}
+
+/// Rank branches for 'ggo switch-last-modified' by a blend of how recently
+/// you committed on them (`activity`: branch and commit time, newest first)
+/// and their frecency. `weight` 1 ranks by your commits only, 0 by frecency
+/// only. Both parts are scaled to 0..=1 first: commit recency decays over the
+/// frecency half-life, and frecency is relative to the highest among them.
+pub fn blend_activity(
+    activity: &[(String, i64)],
+    records: &[BranchRecord],
+    half_life_days: f64,
+    weight: f64,
+) -> Vec<(String, f64)> {
+    let frecency: HashMap<&str, f64> = records
+        .iter()
+        .map(|r| {
+            (
+                r.branch_name.as_str(),
+                frecency::calculate_score(r, half_life_days),
+            )
+        })
+        .collect();
+    let top = activity
+        .iter()
+        .filter_map(|(branch, _)| frecency.get(branch.as_str()))
+        .fold(0.0, |top: f64, score| top.max(*score));
+
+    let mut ranked: Vec<(String, f64)> = activity
+        .iter()
+        .map(|(branch, last_commit)| {
+            let recency = frecency::recency_weight(*last_commit, half_life_days).min(1.0);
+            let used = match frecency.get(branch.as_str()) {
+                Some(score) if top > 0.0 => score / top,
+                _ => 0.0,
+            };
+            (branch.clone(), weight * recency + (1.0 - weight) * used)
+        })
+        .collect();
+
+    // Stable, so equal scores keep the newest commit first
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked
+}
+
+/// Match repositories (keyed like the history, `/path/to/repo/`) against the
+/// pattern and rank them by match quality plus the frecency of all their
+/// branches. A pattern without `/` only has to match the directory name.
+pub fn rank_repos(
+    repos: &[String],
+    pattern: &str,
+    ignore_case: bool,
+    use_fuzzy: bool,
+    records: &[BranchRecord],
+    params: &ScoringParams,
+    bonuses: &MatchBonuses,
+) -> Vec<(String, f64)> {
+    let mut frecency_map: HashMap<&str, f64> = HashMap::new();
+    for record in records {
+        *frecency_map.entry(record.repo_path.as_str()).or_default() +=
+            frecency::calculate_score(record, params.half_life_days);
+    }
+
+    let mut ranked: Vec<(String, f64)> = repos
+        .iter()
+        .filter_map(|repo| {
+            let path = repo.trim_end_matches('/');
+            let text = if pattern.contains('/') {
+                path
+            } else {
+                path.rsplit('/').next().unwrap_or(path)
+            };
+
+            let match_score = if use_fuzzy {
+                matcher::fuzzy_score(text, pattern, ignore_case, bonuses)?
+            } else if matcher::matches(text, pattern, ignore_case) {
+                0
+            } else {
+                return None;
+            };
+            let frecency_score = frecency_map.get(repo.as_str()).copied().unwrap_or(0.0);
+            Some((
+                repo.clone(),
+                match_score as f64 + frecency_score * params.frecency_multiplier,
+            ))
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.0.cmp(&b.0))
+    });
+    ranked
+}
+
+/// Rank the tags matching `pattern`: best match first, and among equally
+/// good matches the highest version first
+pub fn rank_tags(
+    tags: &[String],
+    pattern: &str,
+    ignore_case: bool,
+    use_fuzzy: bool,
+) -> Vec<TagMatch> {
+    let mut ranked = matcher::filter_tags(tags, pattern, ignore_case, use_fuzzy);
+    ranked.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| compare_versions(&b.tag, &a.tag))
+    });
+    ranked
+}
+
+/// The version in a tag name: its dot-separated numbers (from the first
+/// digit, so `v1.2.3` and `release-1.2.3` both work) and pre-release suffix
+struct Version<'a> {
+    numbers: Vec<u64>,
+    pre_release: Option<&'a str>,
+}
+
+fn parse_version(tag: &str) -> Option<Version<'_>> {
+    let rest = &tag[tag.find(|c: char| c.is_ascii_digit())?..];
+    let end = rest
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(rest.len());
+
+    let numbers = rest[..end]
+        .split('.')
+        .filter(|part| !part.is_empty())
+        .map(|part| part.parse().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    let pre_release = rest[end..].strip_prefix('-').filter(|pre| !pre.is_empty());
+
+    Some(Version {
+        numbers,
+        pre_release,
+    })
+}
+
+/// Order tag names semver-aware, lowest first: `v1.9.2` < `v1.10.0`, a
+/// pre-release (`v2.0.0-rc1`) before its release, and names without a
+/// version before all versions (alphabetically among themselves, reversed)
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    match (parse_version(a), parse_version(b)) {
+        (Some(x), Some(y)) => {
+            let len = x.numbers.len().max(y.numbers.len());
+            let component = |v: &Version, i: usize| v.numbers.get(i).copied().unwrap_or(0);
+            (0..len)
+                .map(|i| component(&x, i).cmp(&component(&y, i)))
+                .find(|order| order.is_ne())
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| match (x.pre_release, y.pre_release) {
+                    (None, None) => Ordering::Equal,
+                    (None, Some(_)) => Ordering::Greater,
+                    (Some(_), None) => Ordering::Less,
+                    (Some(p), Some(q)) => p.cmp(q),
+                })
+                .then_with(|| b.cmp(a))
+        }
+        (Some(_), None) => Ordering::Greater,
+        (None, Some(_)) => Ordering::Less,
+        (None, None) => b.cmp(a),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("v1.10.0", "v1.9.2"), Ordering::Greater);
+        assert_eq!(compare_versions("v2.0.0-rc1", "v2.0.0"), Ordering::Less);
+        assert_eq!(
+            compare_versions("v2.0.0-rc2", "v2.0.0-rc1"),
+            Ordering::Greater
+        );
+        assert_eq!(compare_versions("1.2", "v1.2.0"), Ordering::Greater);
+        assert_eq!(compare_versions("release-3.0", "v2.9"), Ordering::Greater);
+        assert_eq!(compare_versions("nightly", "v0.0.1"), Ordering::Less);
+
+        let mut tags = vec!["v1.9.2", "alpha", "v1.10.0", "v2.0.0-rc1", "beta", "v2.0.0"];
+        tags.sort_by(|a, b| compare_versions(b, a));
+        assert_eq!(
+            tags,
+            vec!["v2.0.0", "v2.0.0-rc1", "v1.10.0", "v1.9.2", "alpha", "beta"]
+        );
+    }
+
+    #[test]
+    fn test_blend_activity() {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let day = 86400;
+        let activity = vec![
+            ("fresh".to_string(), now - day),
+            ("busy".to_string(), now - 10 * day),
+            ("idle".to_string(), now - 10 * day),
+        ];
+        let records = vec![BranchRecord {
+            repo_path: "/repo".to_string(),
+            branch_name: "busy".to_string(),
+            switch_count: 40,
+            last_used: now,
+        }];
+        let order = |weight: f64| -> Vec<String> {
+            blend_activity(&activity, &records, 7.0, weight)
+                .into_iter()
+                .map(|(branch, _)| branch)
+                .collect()
+        };
+
+        assert_eq!(order(1.0), vec!["fresh", "busy", "idle"]);
+        assert_eq!(order(0.0), vec!["busy", "fresh", "idle"]);
+        assert_eq!(order(0.7), vec!["fresh", "busy", "idle"]);
+        assert_eq!(order(0.3), vec!["busy", "fresh", "idle"]);
+
+        let ranked = blend_activity(&activity, &records, 7.0, 0.5);
+        assert!(ranked.iter().all(|(_, score)| (0.0..=1.0).contains(score)));
+    }
+
+    #[test]
+    fn test_rank_tags_prefers_match_then_version() {
+        let tags: Vec<String> = ["v1.2.0", "v1.2.10", "v1.2.9", "v0.9.0"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+
+        let ranked: Vec<String> = rank_tags(&tags, "", false, true)
+            .into_iter()
+            .map(|m| m.tag)
+            .collect();
+        assert_eq!(ranked, vec!["v1.2.10", "v1.2.9", "v1.2.0", "v0.9.0"]);
+
+        let ranked = rank_tags(&tags, "1.2", false, false);
+        assert_eq!(ranked.len(), 3);
+        assert_eq!(ranked[0].tag, "v1.2.10");
+    }
+
+    #[test]
+    fn test_rank_repos_matches_name_and_weighs_history() {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let repos: Vec<String> = ["/src/api/", "/src/web-api/", "/api/tools/"]
+            .iter()
+            .map(|r| r.to_string())
+            .collect();
+        let records = vec![BranchRecord {
+            repo_path: "/src/web-api/".to_string(),
+            branch_name: "main".to_string(),
+            switch_count: 20,
+            last_used: now - 60,
+        }];
+        let params = ScoringParams::default();
+        let bonuses = MatchBonuses::default();
+
+        // '/api/tools' only matches as a whole path; history lifts web-api
+        let ranked = rank_repos(&repos, "api", false, true, &records, &params, &bonuses);
+        let names: Vec<&str> = ranked.iter().map(|(r, _)| r.as_str()).collect();
+        assert_eq!(names, vec!["/src/web-api/", "/src/api/"]);
+
+        let ranked = rank_repos(&repos, "/api/", false, false, &records, &params, &bonuses);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].0, "/api/tools/");
+    }
+
+    #[test]
+    fn test_combine_fuzzy_and_frecency_scores_empty() {
+        let fuzzy_matches: Vec<ScoredMatch> = vec![];
+        let records: Vec<BranchRecord> = vec![];
+
+        let result =
+            combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records, &ScoringParams::default());
+        assert_eq!(result.len(), 0);
+    }
+
+    #[test]
+    fn test_combine_fuzzy_and_frecency_scores_no_records() {
+        let fuzzy_matches = vec![
+            ScoredMatch {
+                branch: "feature/auth".to_string(),
+                score: 100,
+            },
+            ScoredMatch {
+                branch: "feature/dashboard".to_string(),
+                score: 80,
+            },
+        ];
+        let records: Vec<BranchRecord> = vec![];
+
+        let result =
+            combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records, &ScoringParams::default());
+
+        assert_eq!(result.len(), 2);
+        // Without frecency, should sort by fuzzy score only
+        assert_eq!(result[0].0, "feature/auth");
+        assert_eq!(result[0].1, 100.0);
+        assert_eq!(result[1].0, "feature/dashboard");
+        assert_eq!(result[1].1, 80.0);
+    }
+
+    #[test]
+    fn test_combine_fuzzy_and_frecency_scores_with_records() {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let fuzzy_matches = vec![
+            ScoredMatch {
+                branch: "feature/auth".to_string(),
+                score: 80,
+            },
+            ScoredMatch {
+                branch: "feature/dashboard".to_string(),
+                score: 100,
+            },
+        ];
+
+        let records = vec![BranchRecord {
+            repo_path: "/test".to_string(),
+            branch_name: "feature/auth".to_string(),
+            switch_count: 10,
+            last_used: now - 60, // Recent: frecency score ≈ 10.0 (10 * ~1.0)
+        }];
+
+        let result =
+            combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records, &ScoringParams::default());
+
+        assert_eq!(result.len(), 2);
+        // feature/auth should rank higher due to frecency
+        // auth: 80 + (10.0 * 10) = 180
+        // dashboard: 100 + (0 * 10) = 100
+        assert_eq!(result[0].0, "feature/auth");
+        assert!(result[0].1 > 179.0 && result[0].1 < 181.0);
+        assert_eq!(result[1].0, "feature/dashboard");
+        assert_eq!(result[1].1, 100.0);
+    }
+
+    #[test]
+    fn test_combine_fuzzy_and_frecency_scores_balanced() {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let fuzzy_matches = vec![
+            ScoredMatch {
+                branch: "branch-a".to_string(),
+                score: 100,
+            },
+            ScoredMatch {
+                branch: "branch-b".to_string(),
+                score: 50,
+            },
+        ];
+
+        let records = vec![
+            BranchRecord {
+                repo_path: "/test".to_string(),
+                branch_name: "branch-a".to_string(),
+                switch_count: 1,
+                last_used: now - 3000000, // Old: frecency ≈ 0.03 (1 * 0.03)
+            },
+            BranchRecord {
+                repo_path: "/test".to_string(),
+                branch_name: "branch-b".to_string(),
+                switch_count: 5,
+                last_used: now - 60, // Recent: frecency ≈ 5.0 (5 * 1.0)
+            },
+        ];
+
+        let result =
+            combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records, &ScoringParams::default());
+
+        assert_eq!(result.len(), 2);
+        // branch-a: 100 + (0.03 * 10) ≈ 100.3
+        // branch-b: 50 + (5.0 * 10) = 100.0
+        // branch-a wins slightly (better fuzzy match despite lower frecency)
+        assert_eq!(result[0].0, "branch-a");
+        assert_eq!(result[1].0, "branch-b");
+    }
+
+    #[test]
+    fn test_combine_fuzzy_and_frecency_scores_high_frecency() {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let fuzzy_matches = vec![
+            ScoredMatch {
+                branch: "new-branch".to_string(),
+                score: 100,
+            },
+            ScoredMatch {
+                branch: "popular-branch".to_string(),
+                score: 60,
+            },
+        ];
+
+        let records = vec![BranchRecord {
+            repo_path: "/test".to_string(),
+            branch_name: "popular-branch".to_string(),
+            switch_count: 20,
+            last_used: now - 60, // Recent: frecency ≈ 20.0 (20 * ~1.0)
+        }];
+
+        let result =
+            combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records, &ScoringParams::default());
+
+        assert_eq!(result.len(), 2);
+        // popular-branch: 60 + (20.0 * 10) = 260.0
+        // new-branch: 100 + (0 * 10) = 100.0
+        assert_eq!(result[0].0, "popular-branch");
+        assert!(result[0].1 > 259.0 && result[0].1 < 261.0);
+    }
+
+    #[test]
+    fn test_combine_fuzzy_and_frecency_scores_single_match() {
+        let fuzzy_matches = vec![ScoredMatch {
+            branch: "only-match".to_string(),
+            score: 75,
+        }];
+        let records: Vec<BranchRecord> = vec![];
+
+        let result =
+            combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records, &ScoringParams::default());
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].0, "only-match");
+        assert_eq!(result[0].1, 75.0);
+    }
+
+    #[test]
+    fn test_combine_fuzzy_and_frecency_scores_all_same_fuzzy() {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let fuzzy_matches = vec![
+            ScoredMatch {
+                branch: "branch-a".to_string(),
+                score: 100,
+            },
+            ScoredMatch {
+                branch: "branch-b".to_string(),
+                score: 100,
+            },
+        ];
+
+        let records = vec![BranchRecord {
+            repo_path: "/test".to_string(),
+            branch_name: "branch-b".to_string(),
+            switch_count: 5,
+            last_used: now - 60, // Recent
+        }];
+
+        let result =
+            combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records, &ScoringParams::default());
+
+        // branch-b should rank higher due to frecency
+        assert_eq!(result[0].0, "branch-b");
+        assert!(result[0].1 > result[1].1);
+    }
+
+    #[test]
+    fn test_combine_fuzzy_and_frecency_scores_partial_overlap() {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let fuzzy_matches = vec![
+            ScoredMatch {
+                branch: "branch-a".to_string(),
+                score: 90,
+            },
+            ScoredMatch {
+                branch: "branch-b".to_string(),
+                score: 85,
+            },
+            ScoredMatch {
+                branch: "branch-c".to_string(),
+                score: 80,
+            },
+        ];
+
+        let records = vec![BranchRecord {
+            repo_path: "/test".to_string(),
+            branch_name: "branch-b".to_string(),
+            switch_count: 3,
+            last_used: now - 60,
+        }];
+
+        let result =
+            combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records, &ScoringParams::default());
+
+        assert_eq!(result.len(), 3);
+        // branch-b should be first due to frecency boost
+        assert_eq!(result[0].0, "branch-b");
+    }
+
+    #[test]
+    fn test_combine_fuzzy_and_frecency_scores_zero_fuzzy_score() {
+        let fuzzy_matches = vec![ScoredMatch {
+            branch: "branch-a".to_string(),
+            score: 0,
+        }];
+        let records: Vec<BranchRecord> = vec![];
+        let result =
+            combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records, &ScoringParams::default());
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].1, 0.0);
+    }
+
+    #[test]
+    fn test_combine_fuzzy_and_frecency_ordering_consistency() {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let fuzzy_matches = vec![
+            ScoredMatch {
+                branch: "high-fuzzy-low-frecency".to_string(),
+                score: 100,
+            },
+            ScoredMatch {
+                branch: "low-fuzzy-high-frecency".to_string(),
+                score: 20,
+            },
+        ];
+
+        let records = vec![BranchRecord {
+            repo_path: "/test".to_string(),
+            branch_name: "low-fuzzy-high-frecency".to_string(),
+            switch_count: 50,
+            last_used: now - 60, // Recent, high frecency
+        }];
+
+        let result =
+            combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records, &ScoringParams::default());
+
+        // Low fuzzy but high frecency should win
+        assert_eq!(result[0].0, "low-fuzzy-high-frecency");
+        assert!(result[0].1 > result[1].1);
+    }
+
+    fn transition(to_branch: &str, count: i64) -> Transition {
+        Transition {
+            to_branch: to_branch.to_string(),
+            count,
+            last_switched: 0,
+        }
+    }
+
+    #[test]
+    fn test_suggest_next_branches_probabilities() {
+        let branches = vec!["develop".to_string(), "feature".to_string()];
+        let transitions = vec![
+            transition("develop", 3),
+            transition("feature", 1),
+            transition("deleted", 4),
+        ];
+
+        let suggestions = suggest_next_branches(&transitions, &branches);
+
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].branch, "develop");
+        assert!((suggestions[0].probability - 0.75).abs() < f64::EPSILON);
+        assert_eq!(suggestions[1].branch, "feature");
+        assert!((suggestions[1].probability - 0.25).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_suggest_next_branches_empty() {
+        let branches = vec!["main".to_string()];
+        assert!(suggest_next_branches(&[], &branches).is_empty());
+        assert!(suggest_next_branches(&[transition("gone", 2)], &branches).is_empty());
+    }
+
+    #[test]
+    fn test_apply_transition_boost_reorders() {
+        let ranked = vec![("feature".to_string(), 30.0), ("develop".to_string(), 20.0)];
+        let transitions = vec![transition("develop", 9), transition("feature", 1)];
+
+        let boosted = float_pinned(apply_transition_boost(ranked, &transitions), &[]);
+
+        assert_eq!(boosted[0].0, "develop");
+        assert!((boosted[0].1 - (20.0 + 0.9 * TRANSITION_BOOST)).abs() < 1e-9);
+        assert_eq!(boosted[1].0, "feature");
+    }
+
+    #[test]
+    fn test_apply_transition_boost_without_history() {
+        let ranked = vec![("a".to_string(), 2.0), ("b".to_string(), 1.0)];
+        assert_eq!(apply_transition_boost(ranked.clone(), &[]), ranked);
+    }
+
+    #[test]
+    fn test_session_boost_beats_lifetime_use() {
+        let ranked = vec![
+            ("develop".to_string(), 2000.0),
+            ("feature/a".to_string(), 120.0),
+            ("feature/b".to_string(), 100.0),
+        ];
+        let weights = HashMap::from([("feature/b".to_string(), 1.0)]);
+
+        let boosted = float_pinned(apply_session_boost(ranked.clone(), &weights, 2.0), &[]);
+        assert_eq!(boosted[0], ("feature/b".to_string(), 4100.0));
+        assert!(is_clear_winner(&boosted, 2.0));
+
+        // A faded session weight only nudges
+        let weights = HashMap::from([("feature/b".to_string(), 0.01)]);
+        let boosted = float_pinned(apply_session_boost(ranked, &weights, 2.0), &[]);
+        assert_eq!(boosted[0].0, "develop");
+        assert_eq!(boosted[1], ("feature/b".to_string(), 140.0));
+    }
+
+    #[test]
+    fn test_add_note_matches() {
+        let branches = vec![
+            "feat/api".to_string(),
+            "feat/ui".to_string(),
+            "fix/login".to_string(),
+        ];
+        let notes: HashMap<String, String> = [
+            ("feat/ui", "Waiting on review from design"),
+            ("fix/login", "blocked"),
+        ]
+        .into_iter()
+        .map(|(b, n)| (b.to_string(), n.to_string()))
+        .collect();
+        let ranked = vec![("feat/api".to_string(), 50.0)];
+
+        let added = add_note_matches(ranked.clone(), &branches, &notes, "review", true, &[], 7.0);
+        let order: Vec<&str> = added.iter().map(|(b, _)| b.as_str()).collect();
+        assert_eq!(order, vec!["feat/api", "feat/ui"]);
+
+        // Every term has to be in the note, with the pattern's case rules
+        assert_eq!(
+            add_note_matches(ranked.clone(), &branches, &notes, "Review", false, &[], 7.0),
+            ranked
+        );
+        assert_eq!(
+            add_note_matches(vec![], &branches, &notes, "review design", true, &[], 7.0).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_add_description_matches() {
+        let ranked = vec![("feature/pay".to_string(), 150.0)];
+        let by_description = vec![
+            ScoredMatch {
+                branch: "feature/pay".to_string(),
+                score: 30,
+            },
+            ScoredMatch {
+                branch: "feature/svc-2231".to_string(),
+                score: 30,
+            },
+        ];
+
+        let added = add_description_matches(
+            ranked.clone(),
+            &by_description,
+            true,
+            &[],
+            &ScoringParams::default(),
+        );
+        assert_eq!(
+            added,
+            vec![
+                ("feature/pay".to_string(), 150.0),
+                ("feature/svc-2231".to_string(), 30.0)
+            ]
+        );
+
+        // Substring matching ranks by frecency alone
+        let added = add_description_matches(
+            vec![],
+            &by_description[1..],
+            false,
+            &[],
+            &ScoringParams::default(),
+        );
+        assert_eq!(added, vec![("feature/svc-2231".to_string(), 0.0)]);
+    }
+
+    #[test]
+    fn test_float_pinned_moves_pinned_first() {
+        let ranked = vec![
+            ("a".to_string(), 30.0),
+            ("b".to_string(), 20.0),
+            ("c".to_string(), 10.0),
+        ];
+        let pinned = vec!["c".to_string(), "missing".to_string()];
+
+        let floated = float_pinned(ranked.clone(), &pinned);
+        let order: Vec<&str> = floated.iter().map(|(b, _)| b.as_str()).collect();
+        assert_eq!(order, vec!["c", "a", "b"]);
+        assert_eq!(floated[0].1, 10.0);
+
+        assert_eq!(float_pinned(ranked.clone(), &[]), ranked);
+
+        // Scores are put in order too; equal ones keep theirs
+        let unsorted = vec![
+            ("x".to_string(), 1.0),
+            ("y".to_string(), 5.0),
+            ("z".to_string(), 1.0),
+        ];
+        let order: Vec<String> = float_pinned(unsorted, &[])
+            .into_iter()
+            .map(|(b, _)| b)
+            .collect();
+        assert_eq!(order, vec!["y", "x", "z"]);
+    }
+
+    #[test]
+    fn test_top_ranked_agrees_with_float_pinned() {
+        let ranked: Vec<(String, f64)> = ["a", "b", "c", "d", "e"]
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (b.to_string(), 50.0 - i as f64 * 10.0))
+            .collect();
+        // Unsorted, with ties
+        let scrambled: Vec<(String, f64)> = [3.0, 9.0, 1.0, 3.0, 7.0, 3.0, 0.0, 9.0]
+            .iter()
+            .enumerate()
+            .map(|(i, score)| (format!("s{}", i), *score))
+            .collect();
+        let floated = float_pinned(scrambled.clone(), &["s6".to_string()]);
+        for limit in 1..=scrambled.len() {
+            let top = top_ranked(scrambled.clone(), &["s6".to_string()], limit);
+            assert_eq!(top, floated[..limit], "limit {}", limit);
+        }
+
+        let pinned = vec!["d".to_string(), "b".to_string()];
+
+        let floated = float_pinned(ranked.clone(), &pinned);
+        for limit in 1..=ranked.len() + 1 {
+            let top = top_ranked(ranked.clone(), &pinned, limit);
+            assert_eq!(top, floated[..limit.min(floated.len())], "limit {}", limit);
+        }
+        assert_eq!(top_ranked(ranked.clone(), &pinned, 0), floated);
+
+        let names =
+            |top: Vec<(String, f64)>| -> Vec<String> { top.into_iter().map(|(b, _)| b).collect() };
+        assert_eq!(names(top_ranked(ranked.clone(), &pinned, 1)), vec!["b"]);
+        assert_eq!(names(top_ranked(ranked, &pinned, 3)), vec!["b", "d", "a"]);
+    }
+
+    #[test]
+    fn test_explain_matches_ranking() {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let branches = vec!["feature/auth".to_string(), "feature/audit".to_string()];
+        let records = vec![BranchRecord {
+            repo_path: "/test".to_string(),
+            branch_name: "feature/audit".to_string(),
+            switch_count: 3,
+            last_used: now,
+        }];
+        let bonuses = MatchBonuses::default();
+
+        for use_fuzzy in [true, false] {
+            let ranked = rank_matching_branches(
+                &branches,
+                "au",
+                false,
+                use_fuzzy,
+                &records,
+                &ScoringParams::default(),
+                &bonuses,
+            );
+            let explained = explain_matching_branches(
+                &branches,
+                "au",
+                false,
+                use_fuzzy,
+                &records,
+                &ScoringParams::default(),
+                &bonuses,
+            );
+
+            assert_eq!(explained.len(), ranked.len());
+            for (e, (branch, score)) in explained.iter().zip(&ranked) {
+                assert_eq!(&e.branch, branch);
+                assert!((e.score - score).abs() < 1e-6);
+            }
+            assert_eq!(explained[0].branch, "feature/audit");
+            assert_eq!(explained[0].fuzzy_score.is_some(), use_fuzzy);
+            assert!((explained[0].decay_weight - 1.0).abs() < 1e-3);
+            assert_eq!(explained[1].last_used, None);
+            assert_eq!(explained[1].frecency_score, 0.0);
+        }
+    }
+
+    #[test]
+    fn test_is_clear_winner() {
+        let ranked = |scores: &[f64]| -> Vec<(String, f64)> {
+            scores.iter().map(|&s| ("b".to_string(), s)).collect()
+        };
+        assert!(!is_clear_winner(&ranked(&[]), 2.0));
+        assert!(is_clear_winner(&ranked(&[5.0]), 2.0));
+        assert!(is_clear_winner(&ranked(&[200.0, 100.0]), 2.0));
+        assert!(!is_clear_winner(&ranked(&[199.0, 100.0]), 2.0));
+        assert!(is_clear_winner(&ranked(&[1.0, 0.0]), 2.0));
+    }
+
+    #[test]
+    fn test_auto_selects_modes() {
+        let ranked = |scores: &[f64]| -> Vec<(String, f64)> {
+            scores.iter().map(|&s| ("b".to_string(), s)).collect()
+        };
+        let close = ranked(&[110.0, 100.0]);
+        let weak_winner = ranked(&[30.0, 10.0]);
+
+        assert!(auto_selects(&close, AutoSelect::Always, 2.0, 0.0));
+        assert!(!auto_selects(&ranked(&[]), AutoSelect::Always, 2.0, 0.0));
+        assert!(!auto_selects(&weak_winner, AutoSelect::Never, 2.0, 0.0));
+
+        assert!(!auto_selects(&close, AutoSelect::Smart, 2.0, 0.0));
+        assert!(auto_selects(&weak_winner, AutoSelect::Smart, 2.0, 0.0));
+        assert!(!auto_selects(&weak_winner, AutoSelect::Smart, 2.0, 40.0));
+        assert!(auto_selects(
+            &ranked(&[80.0, 0.0]),
+            AutoSelect::Smart,
+            2.0,
+            40.0
+        ));
+    }
+
+    #[test]
+    fn test_frecency_multiplier_is_configurable() {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let fuzzy_matches = vec![
+            ScoredMatch {
+                branch: "feature/auth".to_string(),
+                score: 50,
+            },
+            ScoredMatch {
+                branch: "feature/audit".to_string(),
+                score: 60,
+            },
+        ];
+        let records = vec![BranchRecord {
+            repo_path: "/test".to_string(),
+            branch_name: "feature/auth".to_string(),
+            switch_count: 2,
+            last_used: now,
+        }];
+
+        // Default weight: 2 recent switches (+20) outrank a 10 point fuzzy lead
+        let ranked =
+            combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records, &ScoringParams::default());
+        assert_eq!(ranked[0].0, "feature/auth");
+
+        // Frecency switched off: match quality alone decides
+        let params = ScoringParams {
+            frecency_multiplier: 0.0,
+            ..ScoringParams::default()
+        };
+        let ranked = combine_fuzzy_and_frecency_scores(&fuzzy_matches, &records, &params);
+        assert_eq!(ranked[0], ("feature/audit".to_string(), 60.0));
+    }
+}
@@ -0,0 +1,457 @@
+//! `ggo serve`: JSON-RPC 2.0 on a unix socket for editor plugins, one
+//! request per line and one response line per request.
+//!
+//! Connections are read on their own threads, but requests run one at a time
+//! on the thread that owns the storage: each one changes to the repository
+//! it names, and the database (with its branch cache) stays open between
+//! them.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+use crate::backend;
+use crate::config::Config;
+use crate::error::{GgoError, Result};
+use crate::query::{self, QueriedBranch};
+use crate::storage::Storage;
+use crate::switch::{self, SwitchOptions};
+
+/// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// A ggo error, with the exit code ggo would have used as `data.exit_code`
+const SERVER_ERROR: i64 = -32000;
+
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: String,
+    /// None for notifications, which get no response
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(flatten)]
+    outcome: Outcome,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Outcome {
+    Result(Value),
+    Error(RpcError),
+}
+
+impl Response {
+    fn new(id: Value, outcome: Outcome) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            id,
+            outcome,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+impl From<GgoError> for RpcError {
+    fn from(e: GgoError) -> Self {
+        Self {
+            code: SERVER_ERROR,
+            message: e.summary(),
+            data: Some(json!({ "exit_code": e.exit_code() })),
+        }
+    }
+}
+
+type Reply = std::result::Result<Value, RpcError>;
+
+/// Params of `list-ranked`
+#[derive(Deserialize)]
+struct ListParams {
+    repo: PathBuf,
+    /// Empty: every branch, by frecency
+    #[serde(default)]
+    pattern: String,
+    limit: Option<usize>,
+}
+
+/// Params of `checkout`
+#[derive(Deserialize)]
+struct CheckoutParams {
+    repo: PathBuf,
+    branch: String,
+    /// Skip the pre-checkout guards and hooks
+    #[serde(default)]
+    force: bool,
+}
+
+/// Params of `record`
+#[derive(Deserialize)]
+struct RecordParams {
+    repo: PathBuf,
+    branch: String,
+}
+
+/// Params of `aliases`
+#[derive(Deserialize)]
+struct RepoParams {
+    repo: PathBuf,
+}
+
+#[derive(Serialize)]
+struct AliasEntry {
+    alias: String,
+    branch: String,
+}
+
+/// What requests run against
+struct Server<'a> {
+    storage: &'a Storage,
+    config: &'a Config,
+}
+
+impl Server<'_> {
+    /// Answer one request line; None for notifications
+    fn handle(&self, line: &str) -> Option<Response> {
+        let value: Value = match serde_json::from_str(line) {
+            Ok(value) => value,
+            Err(e) => {
+                let error = RpcError::new(PARSE_ERROR, e.to_string());
+                return Some(Response::new(Value::Null, Outcome::Error(error)));
+            }
+        };
+
+        let id = value.get("id").cloned().unwrap_or(Value::Null);
+        let request = match serde_json::from_value::<Request>(value) {
+            Ok(request) if request.jsonrpc == "2.0" => request,
+            Ok(_) => {
+                let error = RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\"");
+                return Some(Response::new(id, Outcome::Error(error)));
+            }
+            Err(e) => {
+                let error = RpcError::new(INVALID_REQUEST, e.to_string());
+                return Some(Response::new(id, Outcome::Error(error)));
+            }
+        };
+
+        debug!("Serving '{}'", request.method);
+        let outcome = match self.call(&request.method, request.params) {
+            Ok(result) => Outcome::Result(result),
+            Err(error) => Outcome::Error(error),
+        };
+        Some(Response::new(request.id?, outcome))
+    }
+
+    fn call(&self, method: &str, params: Value) -> Reply {
+        match method {
+            "list-ranked" => reply(self.list_ranked(parse(params)?)?),
+            "checkout" => reply(self.checkout(parse(params)?)?),
+            "record" => reply(self.record(parse(params)?)?),
+            "aliases" => reply(self.aliases(parse(params)?)?),
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method '{}'", method),
+            )),
+        }
+    }
+
+    /// Change to the repository a request names and return its history key
+    fn enter(&self, repo: &Path) -> Result<String> {
+        std::env::set_current_dir(repo).map_err(|e| {
+            GgoError::InvalidRepoPath(
+                repo.display().to_string(),
+                format!("Cannot change to this directory: {}", e),
+            )
+        })?;
+        switch::repo_key(self.storage, self.config)
+    }
+
+    /// Branches ranked like 'ggo query'
+    fn list_ranked(&self, params: ListParams) -> Result<Vec<QueriedBranch>> {
+        let repo_path = self.enter(&params.repo)?;
+        query::query_branches(
+            self.storage,
+            self.config,
+            &repo_path,
+            &params.pattern,
+            params.limit.unwrap_or(0),
+        )
+    }
+
+    /// Switch like 'ggo <branch>' would, without prompting: guards that ask
+    /// for confirmation refuse instead
+    fn checkout(&self, params: CheckoutParams) -> Result<Value> {
+        let repo_path = self.enter(&params.repo)?;
+        let backend = backend::backend();
+        let previous = backend.current_branch().ok();
+        let switch = SwitchOptions {
+            backend,
+            force_detach: false,
+            force: params.force,
+            can_prompt: false,
+            confirm: None,
+            quiet: true,
+            warn: log_warning,
+            repo_hooks: true,
+        };
+        let switched = switch::switch_to_branch(
+            self.storage,
+            self.config,
+            &repo_path,
+            &params.branch,
+            &switch,
+        )?;
+        debug!("{}", switched);
+        Ok(json!({ "branch": params.branch, "previous": previous }))
+    }
+
+    /// Count a switch the plugin made itself
+    fn record(&self, params: RecordParams) -> Result<Value> {
+        let repo_path = self.enter(&params.repo)?;
+        if !backend::backend().branch_exists(&params.branch)? {
+            return Err(GgoError::BranchNotFound(params.branch));
+        }
+        // The plugin switched on purpose, so it always counts
+        let recorded =
+            switch::record_switch_to(self.storage, &repo_path, &params.branch, 0, log_warning);
+        Ok(json!({ "recorded": recorded }))
+    }
+
+    fn aliases(&self, params: RepoParams) -> Result<Vec<AliasEntry>> {
+        let repo_path = self.enter(&params.repo)?;
+        Ok(self
+            .storage
+            .aliases(&repo_path)?
+            .into_iter()
+            .map(|alias| AliasEntry {
+                alias: alias.alias,
+                branch: alias.branch_name,
+            })
+            .collect())
+    }
+}
+
+fn parse<T: DeserializeOwned>(params: Value) -> std::result::Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn reply<T: Serialize>(result: T) -> Reply {
+    serde_json::to_value(result).map_err(|e| RpcError::new(SERVER_ERROR, e.to_string()))
+}
+
+/// Warnings from a switch go to the log: the socket carries responses only
+fn log_warning(message: &str) {
+    warn!("{}", message);
+}
+
+/// Answer requests on `socket` until the process is stopped
+#[cfg(unix)]
+pub fn serve(storage: &Storage, config: &Config, socket: &Path) -> Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::UnixStream;
+    use std::sync::mpsc;
+
+    // A socket nobody answers on was left behind by a server that died
+    if let Ok(metadata) = std::fs::symlink_metadata(socket) {
+        if !metadata.file_type().is_socket() {
+            return Err(GgoError::Other(format!(
+                "{} exists and is not a socket",
+                socket.display()
+            )));
+        }
+        if UnixStream::connect(socket).is_ok() {
+            return Err(GgoError::ServerRunning(socket.display().to_string()));
+        }
+        std::fs::remove_file(socket)?;
+    }
+
+    let listener = bind_private(socket)?;
+    println!("Listening on {}", socket.display());
+
+    let (sender, requests) = mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let sender = sender.clone();
+                    std::thread::spawn(move || read_requests(stream, sender));
+                }
+                Err(e) => tracing::warn!("Failed to accept a connection: {}", e),
+            }
+        }
+    });
+
+    let server = Server { storage, config };
+    for (line, answer) in requests {
+        let _ = answer.send(server.handle(&line));
+    }
+    Ok(())
+}
+
+/// Listen on a socket only its owner can connect to: whoever can connect can
+/// check out branches. The socket is bound and restricted inside a fresh
+/// 0700 directory, where nobody else can reach it, then moved into place, so
+/// it is never reachable with the process umask's permissions.
+#[cfg(unix)]
+fn bind_private(socket: &Path) -> Result<std::os::unix::net::UnixListener> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::os::unix::net::UnixListener;
+
+    let parent = match socket.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let private = parent.join(format!(".ggo-serve-{}", std::process::id()));
+    // Left behind by a server with the same pid that died while binding
+    if private.exists() {
+        std::fs::remove_dir_all(&private)?;
+    }
+    std::fs::DirBuilder::new().mode(0o700).create(&private)?;
+
+    let bound = private.join("socket");
+    let listener = UnixListener::bind(&bound)
+        .and_then(|listener| {
+            std::fs::set_permissions(&bound, std::fs::Permissions::from_mode(0o600))?;
+            std::fs::rename(&bound, socket)?;
+            Ok(listener)
+        })
+        .map_err(GgoError::from);
+    let _ = std::fs::remove_dir_all(&private);
+    listener
+}
+
+#[cfg(not(unix))]
+pub fn serve(_storage: &Storage, _config: &Config, _socket: &Path) -> Result<()> {
+    Err(GgoError::Other(
+        "'ggo serve' needs unix domain sockets, which this platform doesn't have".to_string(),
+    ))
+}
+
+/// A request line, and where its response goes
+#[cfg(unix)]
+type Job = (String, std::sync::mpsc::Sender<Option<Response>>);
+
+/// Hand a connection's requests to the server thread one by one and write
+/// back the responses
+#[cfg(unix)]
+fn read_requests(stream: std::os::unix::net::UnixStream, requests: std::sync::mpsc::Sender<Job>) {
+    use std::io::{BufRead, BufReader, Write};
+
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(e) => {
+            tracing::warn!("Failed to answer a connection: {}", e);
+            return;
+        }
+    };
+    let (answer, answers) = std::sync::mpsc::channel();
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else { break };
+        if line.trim().is_empty() {
+            continue;
+        }
+        if requests.send((line, answer.clone())).is_err() {
+            break;
+        }
+        let Ok(response) = answers.recv() else { break };
+        let Some(response) = response else { continue };
+        let Ok(json) = serde_json::to_string(&response) else {
+            continue;
+        };
+        if writeln!(writer, "{}", json).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn respond(line: &str) -> Option<Value> {
+        let storage = Storage::open_in_memory().unwrap();
+        let config = Config::default();
+        let server = Server {
+            storage: &storage,
+            config: &config,
+        };
+        server
+            .handle(line)
+            .map(|response| serde_json::to_value(response).unwrap())
+    }
+
+    #[test]
+    fn test_malformed_requests() {
+        let response = respond("{not json").unwrap();
+        assert_eq!(response["id"], Value::Null);
+        assert_eq!(response["error"]["code"], PARSE_ERROR);
+
+        let response = respond(r#"{"jsonrpc": "1.0", "id": 1, "method": "aliases"}"#).unwrap();
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["error"]["code"], INVALID_REQUEST);
+
+        let response = respond(r#"{"jsonrpc": "2.0", "id": 2}"#).unwrap();
+        assert_eq!(response["error"]["code"], INVALID_REQUEST);
+    }
+
+    #[test]
+    fn test_method_and_params_errors() {
+        let response = respond(r#"{"jsonrpc": "2.0", "id": "a", "method": "rebase"}"#).unwrap();
+        assert_eq!(response["id"], "a");
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+        assert!(response.get("result").is_none());
+
+        let response = respond(r#"{"jsonrpc": "2.0", "id": 3, "method": "checkout"}"#).unwrap();
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_ggo_errors_carry_exit_code() {
+        let response = respond(
+            r#"{"jsonrpc": "2.0", "id": 4, "method": "aliases",
+                "params": {"repo": "/nonexistent/ggo-serve-test"}}"#,
+        )
+        .unwrap();
+        assert_eq!(response["error"]["code"], SERVER_ERROR);
+        assert!(response["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("/nonexistent/ggo-serve-test"));
+        assert_eq!(
+            response["error"]["data"]["exit_code"],
+            GgoError::InvalidRepoPath(String::new(), String::new()).exit_code()
+        );
+    }
+
+    #[test]
+    fn test_notifications_get_no_response() {
+        assert!(respond(r#"{"jsonrpc": "2.0", "method": "rebase"}"#).is_none());
+    }
+}
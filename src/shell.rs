@@ -0,0 +1,149 @@
+//! Shell integration printed by `ggo init <shell>`: a `ggo` wrapper function
+//! that can change the shell's directory, a Ctrl-G binding for the picker, and
+//! a prompt hook that records branches switched to outside ggo.
+//!
+//! `ggo setup` installs it, and completions, where each shell looks for them.
+
+use std::path::{Path, PathBuf};
+
+/// A shell `ggo init` has a script for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    pub const NAMES: [&'static str; 3] = ["bash", "zsh", "fish"];
+
+    /// The shell a `$SHELL` path names (`/usr/bin/zsh` is zsh)
+    pub fn from_path(path: &str) -> Option<Self> {
+        Self::from_name(Path::new(path).file_name()?.to_str()?)
+    }
+
+    /// The user's login shell, from `$SHELL`
+    pub fn detect() -> Option<Self> {
+        Self::from_path(&std::env::var("SHELL").ok()?)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "bash" => Some(Shell::Bash),
+            "zsh" => Some(Shell::Zsh),
+            "fish" => Some(Shell::Fish),
+            _ => None,
+        }
+    }
+
+    /// The script to `eval` (or `source` in fish) from the shell's rc file
+    pub fn init_script(self) -> &'static str {
+        match self {
+            Shell::Bash => include_str!("shell/ggo.bash"),
+            Shell::Zsh => include_str!("shell/ggo.zsh"),
+            Shell::Fish => include_str!("shell/ggo.fish"),
+        }
+    }
+
+    /// The rc file line that loads the integration
+    pub fn init_line(self) -> &'static str {
+        match self {
+            Shell::Bash => "eval \"$(ggo init bash)\"",
+            Shell::Zsh => "eval \"$(ggo init zsh)\"",
+            Shell::Fish => "ggo init fish | source",
+        }
+    }
+
+    /// The file the shell reads at startup, where the init line goes
+    pub fn rc_file(self) -> Option<PathBuf> {
+        let home = dirs::home_dir()?;
+        Some(match self {
+            Shell::Bash => home.join(".bashrc"),
+            Shell::Zsh => env_dir("ZDOTDIR").unwrap_or(home).join(".zshrc"),
+            Shell::Fish => xdg_dir("XDG_CONFIG_HOME", ".config")?
+                .join("fish")
+                .join("config.fish"),
+        })
+    }
+
+    /// Where the shell finds completions for `ggo` on its own (zsh needs
+    /// `~/.zfunc` in its `fpath`)
+    pub fn completion_file(self) -> Option<PathBuf> {
+        Some(match self {
+            Shell::Bash => xdg_dir("XDG_DATA_HOME", ".local/share")?
+                .join("bash-completion")
+                .join("completions")
+                .join("ggo"),
+            Shell::Zsh => dirs::home_dir()?.join(".zfunc").join("_ggo"),
+            Shell::Fish => xdg_dir("XDG_CONFIG_HOME", ".config")?
+                .join("fish")
+                .join("completions")
+                .join("ggo.fish"),
+        })
+    }
+}
+
+/// A directory named by an environment variable, if set
+fn env_dir(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// An XDG base directory: the variable if set, `~/<default>` otherwise (also
+/// on macOS, where shells follow XDG rather than `~/Library`)
+fn xdg_dir(var: &str, default: &str) -> Option<PathBuf> {
+    env_dir(var).or_else(|| Some(dirs::home_dir()?.join(default)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(Shell::from_name("bash"), Some(Shell::Bash));
+        assert_eq!(Shell::from_name("ZSH"), Some(Shell::Zsh));
+        assert_eq!(Shell::from_name("fish"), Some(Shell::Fish));
+        assert_eq!(Shell::from_name("powershell"), None);
+    }
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(Shell::from_path("/bin/bash"), Some(Shell::Bash));
+        assert_eq!(Shell::from_path("/usr/local/bin/fish"), Some(Shell::Fish));
+        assert_eq!(Shell::from_path("zsh"), Some(Shell::Zsh));
+        assert_eq!(Shell::from_path("/bin/tcsh"), None);
+        assert_eq!(Shell::from_path(""), None);
+    }
+
+    #[test]
+    fn test_init_line_names_the_shell() {
+        for name in Shell::NAMES {
+            let shell = Shell::from_name(name).unwrap();
+            assert_eq!(shell.name(), name);
+            assert!(shell.init_line().contains(&format!("ggo init {}", name)));
+        }
+    }
+
+    #[test]
+    fn test_every_script_wraps_binds_and_hooks() {
+        for name in Shell::NAMES {
+            let script = Shell::from_name(name).unwrap().init_script();
+            assert!(script.contains("GGO_CD_FILE"), "{name}: no cd wrapper");
+            assert!(script.contains("__ggo_pick"), "{name}: no key binding");
+            assert!(
+                script.contains("ggo record-current"),
+                "{name}: no prompt hook"
+            );
+        }
+    }
+}
@@ -0,0 +1,39 @@
+# ggo shell integration for bash
+# Add to ~/.bashrc:  eval "$(ggo init bash)"
+
+# Run ggo, then follow it into another directory (worktrees, repositories)
+ggo() {
+    local cd_file ret
+    cd_file="$(mktemp -t ggo-cd.XXXXXX)" || { command ggo "$@"; return; }
+    GGO_CD_FILE="$cd_file" command ggo "$@"
+    ret=$?
+    if [ -s "$cd_file" ]; then
+        builtin cd -- "$(cat "$cd_file")" || ret=$?
+    fi
+    rm -f "$cd_file"
+    return "$ret"
+}
+
+# Ctrl-G: pick a branch from the menu
+__ggo_pick() {
+    ggo --interactive ''
+}
+
+# Record branches switched to with plain git, once per repository and branch
+__ggo_hook() {
+    local ret=$? state
+    state="$(command git rev-parse --show-toplevel --abbrev-ref HEAD 2>/dev/null)" || return "$ret"
+    if [ "$state" != "${__GGO_LAST_STATE-}" ]; then
+        __GGO_LAST_STATE="$state"
+        (command ggo record-current >/dev/null 2>&1 &)
+    fi
+    return "$ret"
+}
+
+if [[ $- == *i* ]]; then
+    bind -x '"\C-g": __ggo_pick'
+fi
+
+if [[ ";${PROMPT_COMMAND:-};" != *";__ggo_hook;"* ]]; then
+    PROMPT_COMMAND="__ggo_hook;${PROMPT_COMMAND:-}"
+fi
@@ -0,0 +1,37 @@
+# ggo shell integration for fish
+# Add to ~/.config/fish/config.fish:  ggo init fish | source
+
+# Run ggo, then follow it into another directory (worktrees, repositories)
+function ggo
+    set -l cd_file (mktemp -t ggo-cd.XXXXXX)
+    or begin
+        command ggo $argv
+        return
+    end
+    GGO_CD_FILE=$cd_file command ggo $argv
+    set -l ret $status
+    if test -s $cd_file
+        builtin cd -- (cat $cd_file); or set ret $status
+    end
+    rm -f $cd_file
+    return $ret
+end
+
+# Ctrl-G: pick a branch from the menu
+function __ggo_pick
+    ggo --interactive ''
+    commandline -f repaint
+end
+bind \cg __ggo_pick
+bind -M insert \cg __ggo_pick 2>/dev/null
+
+# Record branches switched to with plain git, once per repository and branch
+function __ggo_hook --on-event fish_prompt
+    set -l state (command git rev-parse --show-toplevel --abbrev-ref HEAD 2>/dev/null)
+    or return
+    if test "$state" != "$__ggo_last_state"
+        set -g __ggo_last_state "$state"
+        command ggo record-current >/dev/null 2>&1 &
+        disown 2>/dev/null
+    end
+end
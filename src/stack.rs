@@ -0,0 +1,194 @@
+//! Branch stacks for stacked pull requests: each stacked branch is built on a
+//! parent branch (`ggo stack on <parent>`), and `ggo up`/`ggo down` walk from
+//! a branch to the ones stacked on it and back.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::storage::StackLink;
+
+/// The stacks of one repository
+#[derive(Debug, Clone, Default)]
+pub struct Stacks {
+    parents: HashMap<String, String>,
+    children: BTreeMap<String, Vec<String>>,
+}
+
+impl Stacks {
+    pub fn new(links: &[StackLink]) -> Self {
+        let mut stacks = Stacks::default();
+        for link in links {
+            stacks
+                .parents
+                .insert(link.branch.clone(), link.parent.clone());
+            stacks
+                .children
+                .entry(link.parent.clone())
+                .or_default()
+                .push(link.branch.clone());
+        }
+        for children in stacks.children.values_mut() {
+            children.sort();
+        }
+        stacks
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    /// Whether the branch is stacked on another one or has branches stacked on it
+    pub fn contains(&self, branch: &str) -> bool {
+        self.parents.contains_key(branch) || self.children.contains_key(branch)
+    }
+
+    /// The branch `branch` is stacked on
+    pub fn parent(&self, branch: &str) -> Option<&str> {
+        self.parents.get(branch).map(String::as_str)
+    }
+
+    /// The branches stacked directly on `branch`, by name
+    pub fn children(&self, branch: &str) -> &[String] {
+        self.children.get(branch).map_or(&[], Vec::as_slice)
+    }
+
+    /// The bottom of the stack `branch` is in (itself if it isn't stacked)
+    pub fn root<'a>(&'a self, branch: &'a str) -> &'a str {
+        let mut current = branch;
+        // A cycle can't be recorded, but don't trust the database blindly
+        for _ in 0..=self.parents.len() {
+            match self.parent(current) {
+                Some(parent) => current = parent,
+                None => break,
+            }
+        }
+        current
+    }
+
+    /// The bottoms of all stacks, by name
+    pub fn roots(&self) -> Vec<&str> {
+        self.children
+            .keys()
+            .filter(|branch| !self.parents.contains_key(*branch))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Whether stacking `branch` on `parent` would make a branch its own ancestor
+    pub fn would_cycle(&self, branch: &str, parent: &str) -> bool {
+        let mut ancestor = parent;
+        for _ in 0..=self.parents.len() {
+            if ancestor == branch {
+                return true;
+            }
+            match self.parent(ancestor) {
+                Some(next) => ancestor = next,
+                None => return false,
+            }
+        }
+        true
+    }
+
+    /// Draw the stack starting at `root` as a tree, one line per branch,
+    /// marking `current`
+    pub fn render(&self, root: &str, current: Option<&str>) -> Vec<String> {
+        let mut lines = vec![self.label(root, current)];
+        self.render_children(root, current, "", &mut lines, 0);
+        lines
+    }
+
+    fn render_children(
+        &self,
+        branch: &str,
+        current: Option<&str>,
+        indent: &str,
+        lines: &mut Vec<String>,
+        depth: usize,
+    ) {
+        if depth > self.parents.len() {
+            return;
+        }
+        let children = self.children(branch);
+        for (i, child) in children.iter().enumerate() {
+            let last = i + 1 == children.len();
+            let (branch_mark, next_indent) = if last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            lines.push(format!(
+                "{}{}{}",
+                indent,
+                branch_mark,
+                self.label(child, current)
+            ));
+            let indent = format!("{}{}", indent, next_indent);
+            self.render_children(child, current, &indent, lines, depth + 1);
+        }
+    }
+
+    fn label(&self, branch: &str, current: Option<&str>) -> String {
+        if current == Some(branch) {
+            format!("{}  ← current", branch)
+        } else {
+            branch.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stacks(links: &[(&str, &str)]) -> Stacks {
+        let links: Vec<StackLink> = links
+            .iter()
+            .map(|(branch, parent)| StackLink {
+                branch: branch.to_string(),
+                parent: parent.to_string(),
+            })
+            .collect();
+        Stacks::new(&links)
+    }
+
+    #[test]
+    fn test_walks_parents_and_children() {
+        let stacks = stacks(&[("a", "main"), ("b", "a"), ("c", "a"), ("d", "b")]);
+
+        assert_eq!(stacks.parent("d"), Some("b"));
+        assert_eq!(stacks.parent("main"), None);
+        assert_eq!(stacks.children("a"), ["b", "c"]);
+        assert!(stacks.children("d").is_empty());
+        assert_eq!(stacks.root("d"), "main");
+        assert_eq!(stacks.root("other"), "other");
+        assert_eq!(stacks.roots(), vec!["main"]);
+        assert!(stacks.contains("main"));
+        assert!(!stacks.contains("other"));
+    }
+
+    #[test]
+    fn test_would_cycle() {
+        let stacks = stacks(&[("a", "main"), ("b", "a")]);
+
+        assert!(stacks.would_cycle("a", "b"));
+        assert!(stacks.would_cycle("a", "a"));
+        assert!(!stacks.would_cycle("b", "main"));
+        assert!(!stacks.would_cycle("c", "b"));
+        assert!(!Stacks::default().would_cycle("a", "main"));
+    }
+
+    #[test]
+    fn test_render_marks_current() {
+        let stacks = stacks(&[("a", "main"), ("b", "a"), ("c", "a"), ("d", "b")]);
+
+        assert_eq!(
+            stacks.render("main", Some("b")),
+            vec![
+                "main",
+                "└── a",
+                "    ├── b  ← current",
+                "    │   └── d",
+                "    └── c",
+            ]
+        );
+    }
+}
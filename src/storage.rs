@@ -1,15 +1,72 @@
+//! The usage history: branch records, checkout events, aliases, pins,
+//! notes, groups and stacks, behind the [`HistoryStore`] trait.
+//!
+//! `storage.backend` picks where it's kept: an SQLite database (`data.db`,
+//! the default, behind the default `sqlite` feature) or a plain JSON file
+//! (`history.json`) for builds without a bundled SQLite, such as static musl
+//! binaries or minimal containers, or the same file encrypted
+//! (`history.json.enc`, behind the `encrypted` feature). All live in the
+//! data directory.
+
 use anyhow::{Context, Result};
-use rusqlite::Connection;
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-/// Current database schema version
-const CURRENT_SCHEMA_VERSION: i32 = 2;
+use crate::config::StorageBackend;
+#[cfg(not(all(feature = "sqlite", feature = "encrypted")))]
+use crate::error::GgoError;
+use crate::export::{ExportedAlias, ExportedBranch, ExportedPreviousBranch, HistoryExport};
+
+#[cfg(feature = "encrypted")]
+mod encrypted;
+mod json;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+#[cfg(feature = "encrypted")]
+pub use encrypted::Cipher;
+
+pub use json::JsonStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite::{restore_backup, SqliteStore};
+
+/// Meta key prefix under which the last listing of each repository is kept
+const LISTING_PREFIX: &str = "listing.";
+
+/// The branches 'ggo -l' last showed in a repository, in the order shown
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Listing {
+    pub listed_at: i64,
+    /// The branch checked out when it was shown
+    pub head: Option<String>,
+    pub branches: Vec<String>,
+}
+
+/// Meta key prefix under which the shell hook notes the branch it last found
+/// checked out in each repository without counting a switch
+const VISIT_PREFIX: &str = "visit.";
+
+/// A branch the shell hook found checked out, but didn't count as a switch
+/// because it had been switched to shortly before
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Visit {
+    pub branch: String,
+    pub visited_at: i64,
+    /// The last recorded checkout at the time: once there is a newer one,
+    /// the visit is out of date
+    pub last_checkout: Option<CheckoutEvent>,
+}
+
+/// Meta key prefix under which the digest of each repository's trusted
+/// `.ggo.toml` is kept, by the path of the repository root
+const TRUSTED_HOOKS_PREFIX: &str = "trusted_hooks.";
 
 /// Branch usage record from the database
 #[derive(Debug, Clone)]
 pub struct BranchRecord {
-    #[allow(dead_code)]
     pub repo_path: String,
     pub branch_name: String,
     pub switch_count: i64,
@@ -19,171 +76,222 @@ pub struct BranchRecord {
 /// Branch alias record from the database
 #[derive(Debug, Clone)]
 pub struct Alias {
-    #[allow(dead_code)]
     pub repo_path: String,
     pub alias: String,
     pub branch_name: String,
-    #[allow(dead_code)]
     pub created_at: i64,
 }
 
-/// Get the path to the ggo data directory (~/.config/ggo on Unix)
-/// Can be overridden with GGO_DATA_DIR environment variable (for testing)
-fn get_data_dir() -> Result<PathBuf> {
-    // Check for test/override directory first
-    if let Ok(test_dir) = std::env::var("GGO_DATA_DIR") {
-        let path = PathBuf::from(test_dir);
-        std::fs::create_dir_all(&path).context("Failed to create GGO_DATA_DIR directory")?;
-        return Ok(path);
-    }
+/// An entry in the per-repo checkout history stack
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub branch_name: String,
+    /// When ggo last switched away from this branch
+    pub switched_at: i64,
+}
 
-    // Normal production path
-    let config_dir = dirs::config_local_dir()
-        .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
+/// A single recorded checkout
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CheckoutEvent {
+    pub repo_path: String,
+    pub from_branch: Option<String>,
+    pub to_branch: String,
+    pub switched_at: i64,
+}
 
-    let ggo_dir = config_dir.join("ggo");
-    std::fs::create_dir_all(&ggo_dir).context("Failed to create ggo config directory")?;
+/// A branch stacked on another one (`ggo stack on <parent>`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackLink {
+    pub branch: String,
+    pub parent: String,
+}
 
-    Ok(ggo_dir)
+/// One repository's records whose branches no longer exist
+#[derive(Debug, Clone)]
+pub struct StaleRecords {
+    pub repo_path: String,
+    /// The repository itself can't be opened anymore
+    pub repo_gone: bool,
+    pub records: Vec<BranchRecord>,
+    /// Deleted branches that live on under a new name (see
+    /// `git::renamed_branches`) as (old, new): their history moves over
+    /// instead of being removed
+    pub renamed: Vec<(String, String)>,
 }
 
-/// Get the path to the SQLite database file
-fn get_db_path() -> Result<PathBuf> {
-    Ok(get_data_dir()?.join("data.db"))
+/// How often checkouts went from one branch to another
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    pub to_branch: String,
+    pub count: i64,
+    pub last_switched: i64,
 }
 
-/// Open a connection to the database, creating it if necessary
-pub fn open_db() -> Result<Connection> {
-    let db_path = get_db_path()?;
-    let conn = Connection::open(&db_path).context("Failed to open database")?;
+/// Everything a store holds but its branch list cache, for moving the
+/// history to another backend (`ggo migrate-storage`). Rows are in the order
+/// they were added.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Snapshot {
+    pub branches: Vec<ExportedBranch>,
+    pub events: Vec<CheckoutEvent>,
+    pub history: Vec<HistoryRow>,
+    pub previous_branches: Vec<ExportedPreviousBranch>,
+    pub aliases: Vec<ExportedAlias>,
+    pub pins: Vec<PinRow>,
+    pub notes: Vec<NoteRow>,
+    pub groups: Vec<GroupRow>,
+    pub stacks: Vec<StackRow>,
+    pub meta: BTreeMap<String, String>,
+}
 
-    initialize_tables(&conn)?;
-    Ok(conn)
+impl Snapshot {
+    /// Whether there's nothing but metadata in it
+    pub fn is_empty(&self) -> bool {
+        self.branches.is_empty()
+            && self.events.is_empty()
+            && self.history.is_empty()
+            && self.previous_branches.is_empty()
+            && self.aliases.is_empty()
+            && self.pins.is_empty()
+            && self.notes.is_empty()
+            && self.groups.is_empty()
+            && self.stacks.is_empty()
+    }
 }
 
-/// Initialize database tables and run migrations
-fn initialize_tables(conn: &Connection) -> Result<()> {
-    // Create schema version table first
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS schema_version (
-            version INTEGER PRIMARY KEY,
-            applied_at INTEGER NOT NULL
-        )",
-        [],
-    )
-    .context("Failed to create schema_version table")?;
-
-    // Get current schema version
-    let current_version: i32 = conn
-        .query_row(
-            "SELECT version FROM schema_version ORDER BY version DESC LIMIT 1",
-            [],
-            |row| row.get(0),
-        )
-        .unwrap_or(0);
+/// A row of the checkout history stack
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryRow {
+    pub repo_path: String,
+    pub branch_name: String,
+    pub switched_at: i64,
+}
 
-    // Run migrations if needed
-    if current_version < CURRENT_SCHEMA_VERSION {
-        run_migrations(conn, current_version)?;
-    }
+/// A pinned branch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PinRow {
+    pub repo_path: String,
+    pub branch_name: String,
+    pub pinned_at: i64,
+}
 
-    Ok(())
+/// A branch note
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoteRow {
+    pub repo_path: String,
+    pub branch_name: String,
+    pub note: String,
+    pub updated_at: i64,
 }
 
-/// Run database migrations from one version to another
-fn run_migrations(conn: &Connection, from_version: i32) -> Result<()> {
-    let now = now_timestamp();
-
-    // Apply migrations incrementally
-    for version in (from_version + 1)..=CURRENT_SCHEMA_VERSION {
-        match version {
-            1 => {
-                // Version 1: Initial schema with branches table
-                conn.execute(
-                    "CREATE TABLE IF NOT EXISTS branches (
-                        id INTEGER PRIMARY KEY,
-                        repo_path TEXT NOT NULL,
-                        branch_name TEXT NOT NULL,
-                        switch_count INTEGER DEFAULT 1,
-                        last_used INTEGER NOT NULL,
-                        UNIQUE(repo_path, branch_name)
-                    )",
-                    [],
-                )
-                .context("Failed to create branches table in migration v1")?;
-
-                // Add indices for branches
-                conn.execute(
-                    "CREATE INDEX IF NOT EXISTS idx_branches_repo_last_used
-                     ON branches(repo_path, last_used DESC)",
-                    [],
-                )
-                .context("Failed to create branches repo index in migration v1")?;
-
-                conn.execute(
-                    "CREATE INDEX IF NOT EXISTS idx_branches_last_used
-                     ON branches(last_used DESC)",
-                    [],
-                )
-                .context("Failed to create branches last_used index in migration v1")?;
-
-                // Create previous_branch table
-                conn.execute(
-                    "CREATE TABLE IF NOT EXISTS previous_branch (
-                        repo_path TEXT PRIMARY KEY,
-                        branch_name TEXT NOT NULL,
-                        updated_at INTEGER NOT NULL
-                    )",
-                    [],
-                )
-                .context("Failed to create previous_branch table in migration v1")?;
-            }
-            2 => {
-                // Version 2: Add aliases table
-                conn.execute(
-                    "CREATE TABLE IF NOT EXISTS aliases (
-                        repo_path TEXT NOT NULL,
-                        alias TEXT NOT NULL,
-                        branch_name TEXT NOT NULL,
-                        created_at INTEGER NOT NULL,
-                        PRIMARY KEY (repo_path, alias)
-                    )",
-                    [],
-                )
-                .context("Failed to create aliases table in migration v2")?;
-
-                // Add index for aliases
-                conn.execute(
-                    "CREATE INDEX IF NOT EXISTS idx_aliases_branch
-                     ON aliases(repo_path, branch_name)",
-                    [],
-                )
-                .context("Failed to create aliases branch index in migration v2")?;
-            }
-            _ => {
-                // Unknown version - should never happen
-                anyhow::bail!("Unknown migration version: {}", version);
-            }
+/// A branch's membership of a group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupRow {
+    pub repo_path: String,
+    pub group_name: String,
+    pub branch_name: String,
+    pub added_at: i64,
+}
+
+/// A branch stacked on a parent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StackRow {
+    pub repo_path: String,
+    pub branch_name: String,
+    pub parent_branch: String,
+    pub created_at: i64,
+}
+
+/// Where the data directory was taken from, highest precedence first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataDirSource {
+    /// `--data-dir`
+    Flag,
+    /// The `GGO_DATA_DIR` environment variable
+    Env,
+    /// `storage.data_dir` in the config file
+    Config,
+    /// The platform config directory (~/.config/ggo on Linux)
+    Default,
+}
+
+impl DataDirSource {
+    /// Name used in JSON output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DataDirSource::Flag => "flag",
+            DataDirSource::Env => "env",
+            DataDirSource::Config => "config",
+            DataDirSource::Default => "default",
         }
+    }
 
-        // Record that this migration was applied
-        conn.execute(
-            "INSERT INTO schema_version (version, applied_at) VALUES (?1, ?2)",
-            [&version.to_string(), &now.to_string()],
-        )
-        .context(format!("Failed to record migration version {}", version))?;
+    /// How the source is named to users
+    pub fn describe(self) -> &'static str {
+        match self {
+            DataDirSource::Flag => "--data-dir",
+            DataDirSource::Env => "GGO_DATA_DIR",
+            DataDirSource::Config => "storage.data_dir in the config file",
+            DataDirSource::Default => "default",
+        }
     }
+}
 
+/// The data directory chosen for this process with [`use_data_dir`]
+static DATA_DIR: Mutex<Option<(PathBuf, DataDirSource)>> = Mutex::new(None);
+
+/// Choose the data directory from `--data-dir` and `storage.data_dir`, which
+/// only the caller knows: the flag wins over `GGO_DATA_DIR`, which wins over
+/// the config, which wins over the default. [`get_data_dir`] (and so
+/// [`Storage::open`]) use the choice from then on.
+pub fn use_data_dir(flag: Option<PathBuf>, configured: Option<PathBuf>) -> Result<()> {
+    let env = std::env::var_os("GGO_DATA_DIR").map(PathBuf::from);
+    let chosen = pick_data_dir(flag, env, configured)?;
+    *DATA_DIR.lock().unwrap_or_else(|e| e.into_inner()) = Some(chosen);
     Ok(())
 }
 
-#[cfg(test)]
-fn open_test_db() -> Result<Connection> {
-    // Use in-memory database for tests to ensure isolation
-    let conn = Connection::open_in_memory().context("Failed to open in-memory database")?;
+/// The data directory and where it was taken from, without creating it.
+/// Without [`use_data_dir`] only `GGO_DATA_DIR` and the default count.
+pub fn data_dir_location() -> Result<(PathBuf, DataDirSource)> {
+    if let Some(chosen) = DATA_DIR.lock().unwrap_or_else(|e| e.into_inner()).clone() {
+        return Ok(chosen);
+    }
+    let env = std::env::var_os("GGO_DATA_DIR").map(PathBuf::from);
+    pick_data_dir(None, env, None)
+}
+
+fn pick_data_dir(
+    flag: Option<PathBuf>,
+    env: Option<PathBuf>,
+    configured: Option<PathBuf>,
+) -> Result<(PathBuf, DataDirSource)> {
+    let chosen = flag
+        .map(|path| (path, DataDirSource::Flag))
+        .or_else(|| env.map(|path| (path, DataDirSource::Env)))
+        .or_else(|| configured.map(|path| (path, DataDirSource::Config)));
+    if let Some(chosen) = chosen {
+        return Ok(chosen);
+    }
 
-    initialize_tables(&conn)?;
-    Ok(conn)
+    let config_dir = dirs::config_local_dir()
+        .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
+    Ok((config_dir.join("ggo"), DataDirSource::Default))
+}
+
+/// Get the path to the ggo data directory (~/.config/ggo on Unix), creating
+/// it if needed. See [`use_data_dir`] for what overrides it.
+pub fn get_data_dir() -> Result<PathBuf> {
+    let (path, source) = data_dir_location()?;
+    std::fs::create_dir_all(&path).with_context(|| {
+        format!(
+            "Failed to create the data directory {} (from {})",
+            path.display(),
+            source.describe()
+        )
+    })?;
+    Ok(path)
 }
 
 /// Get current Unix timestamp in seconds
@@ -194,79 +302,46 @@ fn now_timestamp() -> i64 {
         .as_secs() as i64
 }
 
-/// Record a branch checkout, updating or inserting the usage record
-pub fn record_checkout(repo_path: &str, branch_name: &str) -> Result<()> {
-    let conn = open_db()?;
-    let now = now_timestamp();
-
-    conn.execute(
-        "INSERT INTO branches (repo_path, branch_name, switch_count, last_used)
-         VALUES (?1, ?2, 1, ?3)
-         ON CONFLICT(repo_path, branch_name) DO UPDATE SET
-            switch_count = switch_count + 1,
-            last_used = ?3",
-        [repo_path, branch_name, &now.to_string()],
-    )
-    .context("Failed to record checkout")?;
-
-    Ok(())
-}
-
-/// Get all branch records for a specific repository
-pub fn get_branch_records(repo_path: &str) -> Result<Vec<BranchRecord>> {
-    let conn = open_db()?;
-
-    let mut stmt = conn
-        .prepare(
-            "SELECT repo_path, branch_name, switch_count, last_used
-             FROM branches
-             WHERE repo_path = ?1
-             ORDER BY last_used DESC",
-        )
-        .context("Failed to prepare query")?;
-
-    let records = stmt
-        .query_map([repo_path], |row| {
-            Ok(BranchRecord {
-                repo_path: row.get(0)?,
-                branch_name: row.get(1)?,
-                switch_count: row.get(2)?,
-                last_used: row.get(3)?,
-            })
-        })
-        .context("Failed to query branches")?
-        .map_while(Result::ok)
-        .collect();
-
-    Ok(records)
+/// Whether a history of `count` records, the oldest last used at `oldest`,
+/// is over the retention policy
+fn over_retention(
+    count: usize,
+    oldest: Option<i64>,
+    max_records: usize,
+    max_age_days: i64,
+) -> bool {
+    let too_old = max_age_days > 0
+        && oldest.is_some_and(|oldest| oldest < now_timestamp() - max_age_days * 86400);
+    too_old || (max_records > 0 && count > max_records)
 }
 
-/// Get all branch records across all repositories
-pub fn get_all_records() -> Result<Vec<BranchRecord>> {
-    let conn = open_db()?;
-
-    let mut stmt = conn
-        .prepare(
-            "SELECT repo_path, branch_name, switch_count, last_used
-             FROM branches
-             ORDER BY last_used DESC",
-        )
-        .context("Failed to prepare query")?;
-
-    let records = stmt
-        .query_map([], |row| {
-            Ok(BranchRecord {
-                repo_path: row.get(0)?,
-                branch_name: row.get(1)?,
-                switch_count: row.get(2)?,
-                last_used: row.get(3)?,
-            })
-        })
-        .context("Failed to query branches")?
-        .map_while(Result::ok)
-        .collect();
-
-    Ok(records)
+/// The records [`HistoryStore::trim_records`] removes: the ones unused for
+/// `max_age_days`, then the lowest-scoring beyond `max_records` (the most
+/// recently used win ties)
+fn records_to_trim(
+    records: Vec<BranchRecord>,
+    max_records: usize,
+    max_age_days: i64,
+    score: &dyn Fn(&BranchRecord) -> f64,
+) -> Vec<BranchRecord> {
+    let cutoff = now_timestamp() - max_age_days * 86400;
+    let (mut trimmed, kept): (Vec<_>, Vec<_>) = records
+        .into_iter()
+        .partition(|record| max_age_days > 0 && record.last_used < cutoff);
+
+    if max_records > 0 && kept.len() > max_records {
+        let mut scored: Vec<(f64, BranchRecord)> = kept
+            .into_iter()
+            .map(|record| (score(&record), record))
+            .collect();
+        scored.sort_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .total_cmp(a_score)
+                .then(b.last_used.cmp(&a.last_used))
+        });
+        trimmed.extend(scored.drain(max_records..).map(|(_, record)| record));
+    }
+    trimmed
 }
 
 /// Get statistics summary
@@ -277,865 +352,698 @@ pub struct Stats {
     pub db_path: PathBuf,
 }
 
-pub fn get_stats() -> Result<Stats> {
-    let conn = open_db()?;
-    let db_path = get_db_path()?;
-
-    let total_switches: i64 = conn
-        .query_row(
-            "SELECT COALESCE(SUM(switch_count), 0) FROM branches",
-            [],
-            |row| row.get(0),
-        )
-        .unwrap_or(0);
-
-    let unique_branches: i64 = conn
-        .query_row("SELECT COUNT(*) FROM branches", [], |row| row.get(0))
-        .unwrap_or(0);
-
-    let unique_repos: i64 = conn
-        .query_row(
-            "SELECT COUNT(DISTINCT repo_path) FROM branches",
-            [],
-            |row| row.get(0),
-        )
-        .unwrap_or(0);
-
-    Ok(Stats {
-        total_switches,
-        unique_branches,
-        unique_repos,
-        db_path,
-    })
+/// Which records `ggo --stats` covers
+#[derive(Debug, Clone, Default)]
+pub struct StatsFilter {
+    /// Only this repository
+    pub repo_path: Option<String>,
+    /// Only branches used within this many seconds
+    pub max_age_seconds: Option<i64>,
 }
 
-/// Save the previous branch for quick access (like cd -)
-pub fn save_previous_branch(repo_path: &str, branch_name: &str) -> Result<()> {
-    let conn = open_db()?;
-
-    // Create the previous_branch table if it doesn't exist
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS previous_branch (
-            repo_path TEXT PRIMARY KEY,
-            branch_name TEXT NOT NULL,
-            updated_at INTEGER NOT NULL
-        )",
-        [],
-    )
-    .context("Failed to create previous_branch table")?;
-
-    let now = now_timestamp();
-
-    conn.execute(
-        "INSERT OR REPLACE INTO previous_branch (repo_path, branch_name, updated_at)
-         VALUES (?1, ?2, ?3)",
-        [repo_path, branch_name, &now.to_string()],
-    )
-    .context("Failed to save previous branch")?;
-
-    Ok(())
-}
-
-/// Get the previous branch for the given repository
-pub fn get_previous_branch(repo_path: &str) -> Result<Option<String>> {
-    let conn = open_db()?;
-
-    // Make sure the table exists
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS previous_branch (
-            repo_path TEXT PRIMARY KEY,
-            branch_name TEXT NOT NULL,
-            updated_at INTEGER NOT NULL
-        )",
-        [],
-    )
-    .ok();
-
-    let result = conn.query_row(
-        "SELECT branch_name FROM previous_branch WHERE repo_path = ?1",
-        [repo_path],
-        |row| row.get::<_, String>(0),
-    );
-
-    match result {
-        Ok(branch) => Ok(Some(branch)),
-        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-        Err(e) => Err(e).context("Failed to get previous branch"),
-    }
-}
-
-/// Create or update an alias for a branch
-pub fn create_alias(repo_path: &str, alias: &str, branch_name: &str) -> Result<()> {
-    let conn = open_db()?;
-    let now = now_timestamp();
-
-    conn.execute(
-        "INSERT OR REPLACE INTO aliases (repo_path, alias, branch_name, created_at)
-         VALUES (?1, ?2, ?3, ?4)",
-        [repo_path, alias, branch_name, &now.to_string()],
-    )
-    .context("Failed to create alias")?;
-
-    Ok(())
-}
-
-/// Get the branch name for an alias
-pub fn get_alias(repo_path: &str, alias: &str) -> Result<Option<String>> {
-    let conn = open_db()?;
-
-    let result = conn.query_row(
-        "SELECT branch_name FROM aliases WHERE repo_path = ?1 AND alias = ?2",
-        [repo_path, alias],
-        |row| row.get::<_, String>(0),
-    );
-
-    match result {
-        Ok(branch) => Ok(Some(branch)),
-        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-        Err(e) => Err(e).context("Failed to get alias"),
+impl StatsFilter {
+    /// Oldest `last_used` timestamp still included
+    fn cutoff(&self) -> Option<i64> {
+        self.max_age_seconds.map(|age| now_timestamp() - age)
     }
 }
 
-/// Delete an alias
-pub fn delete_alias(repo_path: &str, alias: &str) -> Result<()> {
-    let conn = open_db()?;
-
-    conn.execute(
-        "DELETE FROM aliases WHERE repo_path = ?1 AND alias = ?2",
-        [repo_path, alias],
-    )
-    .context("Failed to delete alias")?;
-
-    Ok(())
-}
-
-/// List all aliases for a repository
-pub fn list_aliases(repo_path: &str) -> Result<Vec<Alias>> {
-    let conn = open_db()?;
-
-    let mut stmt = conn
-        .prepare(
-            "SELECT repo_path, alias, branch_name, created_at
-             FROM aliases
-             WHERE repo_path = ?1
-             ORDER BY alias",
-        )
-        .context("Failed to prepare query")?;
-
-    let aliases = stmt
-        .query_map([repo_path], |row| {
-            Ok(Alias {
-                repo_path: row.get(0)?,
-                alias: row.get(1)?,
-                branch_name: row.get(2)?,
-                created_at: row.get(3)?,
-            })
-        })
-        .context("Failed to query aliases")?
-        .map_while(Result::ok)
-        .collect();
-
-    Ok(aliases)
+/// Order of the top branches in `ggo --stats`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StatsSort {
+    /// Most switches first
+    Switches,
+    /// Most recently used first
+    Recent,
+    /// Highest frecency first
+    #[default]
+    Score,
 }
 
-/// Get all aliases pointing to a specific branch
-pub fn get_aliases_for_branch(repo_path: &str, branch_name: &str) -> Result<Vec<String>> {
-    let conn = open_db()?;
-
-    let mut stmt = conn
-        .prepare(
-            "SELECT alias
-             FROM aliases
-             WHERE repo_path = ?1 AND branch_name = ?2
-             ORDER BY alias",
-        )
-        .context("Failed to prepare query")?;
-
-    let aliases = stmt
-        .query_map([repo_path, branch_name], |row| row.get::<_, String>(0))
-        .context("Failed to query aliases")?
-        .map_while(Result::ok)
-        .collect();
-
-    Ok(aliases)
+/// Branch and switch counts of one repository
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepoSummary {
+    pub repo_path: String,
+    pub branches: i64,
+    pub switches: i64,
+    /// Latest `last_used` among its branches
+    pub last_used: i64,
 }
 
-/// Remove branch records older than the specified age (in days)
-pub fn cleanup_old_records(max_age_days: i64) -> Result<usize> {
-    let conn = open_db()?;
-    let now = now_timestamp();
-    let cutoff = now - (max_age_days * 86400);
-
-    let deleted = conn
-        .execute("DELETE FROM branches WHERE last_used < ?1", [cutoff])
-        .context("Failed to cleanup old branch records")?;
-
-    Ok(deleted)
+/// Filtering every backend applies to what is recorded and returned
+#[derive(Debug, Clone)]
+pub struct StoreOptions {
+    /// Glob patterns of branches whose checkouts are not recorded and whose
+    /// existing usage records are not returned
+    pub exclude: Vec<String>,
+    /// With `false`, checkouts leave no trace: usage records, checkout
+    /// events, the previous branch and the history stack are left untouched
+    pub record: bool,
 }
 
-/// Remove branches and aliases that no longer exist in their repositories
-/// Returns the number of records cleaned up
-pub fn cleanup_deleted_branches() -> Result<usize> {
-    let conn = open_db()?;
-    let records = get_all_records()?;
-
-    let mut deleted = 0;
-
-    for record in records {
-        // Try to open the repository
-        if let Ok(repo) = git2::Repository::open(&record.repo_path) {
-            // Check if branch still exists
-            if repo
-                .find_branch(&record.branch_name, git2::BranchType::Local)
-                .is_err()
-            {
-                // Branch doesn't exist anymore, delete it
-                conn.execute(
-                    "DELETE FROM branches WHERE repo_path = ?1 AND branch_name = ?2",
-                    [&record.repo_path, &record.branch_name],
-                )
-                .ok();
-
-                // Also delete any aliases pointing to this branch
-                conn.execute(
-                    "DELETE FROM aliases WHERE repo_path = ?1 AND branch_name = ?2",
-                    [&record.repo_path, &record.branch_name],
-                )
-                .ok();
-
-                deleted += 1;
-            }
-        } else {
-            // Repository doesn't exist anymore, delete all its records
-            let branch_count: i64 = conn
-                .query_row(
-                    "SELECT COUNT(*) FROM branches WHERE repo_path = ?1",
-                    [&record.repo_path],
-                    |row| row.get(0),
-                )
-                .unwrap_or(0);
-
-            conn.execute(
-                "DELETE FROM branches WHERE repo_path = ?1",
-                [&record.repo_path],
-            )
-            .ok();
-
-            conn.execute(
-                "DELETE FROM aliases WHERE repo_path = ?1",
-                [&record.repo_path],
-            )
-            .ok();
-
-            deleted += branch_count as usize;
+impl Default for StoreOptions {
+    fn default() -> Self {
+        Self {
+            exclude: Vec::new(),
+            record: true,
         }
     }
-
-    Ok(deleted)
 }
 
-/// Optimize database with VACUUM and ANALYZE
-pub fn optimize_database() -> Result<()> {
-    let conn = open_db()?;
-    conn.execute("VACUUM", []).context("Failed to run VACUUM")?;
-    conn.execute("ANALYZE", [])
-        .context("Failed to run ANALYZE")?;
-    Ok(())
-}
+/// A place ggo keeps its usage history. Repositories are identified by the
+/// key `git::repo_path_key` gives them; timestamps are Unix seconds.
+pub trait HistoryStore {
+    /// The filtering this store applies (see [`Storage::with_exclude`] and
+    /// [`Storage::with_recording`])
+    fn options_mut(&mut self) -> &mut StoreOptions;
 
-/// Get database file size in bytes
-pub fn get_database_size() -> Result<u64> {
-    let db_path = get_db_path()?;
-    let metadata = std::fs::metadata(db_path).context("Failed to get database metadata")?;
-    Ok(metadata.len())
-}
+    /// Path of the file the history is kept in
+    fn db_path(&self) -> &Path;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Record a branch checkout: bump the usage record and log the event
+    /// (`from_branch` is the branch that was checked out before, if known)
+    fn record_checkout(
+        &self,
+        repo_path: &str,
+        from_branch: Option<&str>,
+        branch_name: &str,
+    ) -> Result<()>;
+
+    /// Add checkout events from elsewhere (e.g. a git reflog), counting each
+    /// as a switch. Events already recorded (same branches, same second) and
+    /// excluded branches are skipped, so importing twice adds nothing.
+    /// Returns how many were added.
+    fn import_checkout_events(&self, events: &[CheckoutEvent]) -> Result<usize>;
+
+    /// Get the checkout events for a repository, most recent first
+    fn checkout_events(&self, repo_path: &str) -> Result<Vec<CheckoutEvent>>;
 
-    // Generate a unique repo path for testing to avoid conflicts
-    fn unique_repo_path() -> String {
-        use std::sync::atomic::{AtomicU64, Ordering};
-        static COUNTER: AtomicU64 = AtomicU64::new(0);
-        let id = COUNTER.fetch_add(1, Ordering::SeqCst);
-        format!("/test/repo/{}", id)
-    }
+    /// Checkout events of a repository from the last `seconds`, most recent first
+    fn recent_checkout_events(&self, repo_path: &str, seconds: i64) -> Result<Vec<CheckoutEvent>>;
 
-    // Test-specific versions that use a provided connection
-    fn do_record_checkout(conn: &Connection, repo_path: &str, branch_name: &str) -> Result<()> {
-        let now = now_timestamp();
+    /// The most recent checkout recorded for a repository
+    fn last_checkout(&self, repo_path: &str) -> Result<Option<CheckoutEvent>>;
 
-        conn.execute(
-            "INSERT INTO branches (repo_path, branch_name, switch_count, last_used)
-             VALUES (?1, ?2, 1, ?3)
-             ON CONFLICT(repo_path, branch_name) DO UPDATE SET
-                switch_count = switch_count + 1,
-                last_used = ?3",
-            [repo_path, branch_name, &now.to_string()],
-        )
-        .context("Failed to record checkout")?;
+    /// Take back the most recent checkout in a repository, as if it never
+    /// happened: drop its event, decrement the target's switch count (removing
+    /// the record once it reaches zero, otherwise restoring its last use from
+    /// the remaining events), pop the branch it pushed onto the history stack,
+    /// and point the previous branch at the new top of the stack.
+    /// Returns the checkout that was removed; with recording off (see
+    /// [`Storage::with_recording`]) nothing is, and it returns None.
+    fn undo_last_checkout(&self, repo_path: &str) -> Result<Option<CheckoutEvent>>;
 
-        Ok(())
-    }
+    /// Count the branches checked out right after `from_branch`, most frequent first
+    fn transitions_from(&self, repo_path: &str, from_branch: &str) -> Result<Vec<Transition>>;
 
-    fn do_get_branch_records(conn: &Connection, repo_path: &str) -> Result<Vec<BranchRecord>> {
-        let mut stmt = conn
-            .prepare(
-                "SELECT repo_path, branch_name, switch_count, last_used
-                 FROM branches
-                 WHERE repo_path = ?1
-                 ORDER BY last_used DESC",
-            )
-            .context("Failed to prepare query")?;
-
-        let records = stmt
-            .query_map([repo_path], |row| {
-                Ok(BranchRecord {
-                    repo_path: row.get(0)?,
-                    branch_name: row.get(1)?,
-                    switch_count: row.get(2)?,
-                    last_used: row.get(3)?,
-                })
-            })
-            .context("Failed to query branches")?
-            .map_while(Result::ok)
-            .collect();
+    /// Number of checkout events older than `retention_days` and the time of
+    /// the oldest one, optionally only for one repository
+    fn old_events(
+        &self,
+        retention_days: u32,
+        repo_path: Option<&str>,
+    ) -> Result<(usize, Option<i64>)>;
 
-        Ok(records)
-    }
+    /// Drop checkout events older than `retention_days` (0 keeps everything),
+    /// optionally only for one repository.
+    /// Switch counts in the aggregated branch records are unaffected.
+    /// Returns the number of events removed.
+    fn compact_events(&self, retention_days: u32, repo_path: Option<&str>) -> Result<usize>;
 
-    fn do_get_all_records(conn: &Connection) -> Result<Vec<BranchRecord>> {
-        let mut stmt = conn
-            .prepare(
-                "SELECT repo_path, branch_name, switch_count, last_used
-                 FROM branches
-                 ORDER BY last_used DESC",
-            )
-            .context("Failed to prepare query")?;
-
-        let records = stmt
-            .query_map([], |row| {
-                Ok(BranchRecord {
-                    repo_path: row.get(0)?,
-                    branch_name: row.get(1)?,
-                    switch_count: row.get(2)?,
-                    last_used: row.get(3)?,
-                })
-            })
-            .context("Failed to query branches")?
-            .map_while(Result::ok)
-            .collect();
+    /// Get all branch records for a repository, most recently used first
+    fn branch_records(&self, repo_path: &str) -> Result<Vec<BranchRecord>>;
 
-        Ok(records)
-    }
+    /// Get all branch records across all repositories
+    fn all_records(&self) -> Result<Vec<BranchRecord>>;
 
-    fn do_save_previous_branch(
-        conn: &Connection,
-        repo_path: &str,
-        branch_name: &str,
-    ) -> Result<()> {
-        // Create the previous_branch table if it doesn't exist
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS previous_branch (
-                repo_path TEXT PRIMARY KEY,
-                branch_name TEXT NOT NULL,
-                updated_at INTEGER NOT NULL
-            )",
-            [],
-        )
-        .context("Failed to create previous_branch table")?;
+    /// Get statistics summary for the records matching `filter`
+    fn stats(&self, filter: &StatsFilter) -> Result<Stats>;
 
-        let now = now_timestamp();
+    /// The first `limit` records matching `filter` in `sort` order.
+    ///
+    /// Frecency decay isn't computed by the store, so for [`StatsSort::Score`] this
+    /// returns every matching record (most recent first) for the caller to rank.
+    fn top_records(
+        &self,
+        filter: &StatsFilter,
+        sort: StatsSort,
+        limit: usize,
+    ) -> Result<Vec<BranchRecord>>;
 
-        conn.execute(
-            "INSERT OR REPLACE INTO previous_branch (repo_path, branch_name, updated_at)
-             VALUES (?1, ?2, ?3)",
-            [repo_path, branch_name, &now.to_string()],
-        )
-        .context("Failed to save previous branch")?;
+    /// Branch and switch counts per repository for the records matching
+    /// `filter`, most switches first
+    fn repo_summaries(&self, filter: &StatsFilter) -> Result<Vec<RepoSummary>>;
 
-        Ok(())
-    }
+    /// Checkout events matching `filter` counted by local weekday (0 = Sunday)
+    /// and hour of day
+    fn switch_heatmap(&self, filter: &StatsFilter) -> Result<[[i64; 24]; 7]>;
 
-    fn do_get_previous_branch(conn: &Connection, repo_path: &str) -> Result<Option<String>> {
-        // Make sure the table exists
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS previous_branch (
-                repo_path TEXT PRIMARY KEY,
-                branch_name TEXT NOT NULL,
-                updated_at INTEGER NOT NULL
-            )",
-            [],
-        )
-        .ok();
+    /// Save the previous branch for quick access (like cd -)
+    fn save_previous_branch(&self, repo_path: &str, branch_name: &str) -> Result<()>;
 
-        let result = conn.query_row(
-            "SELECT branch_name FROM previous_branch WHERE repo_path = ?1",
-            [repo_path],
-            |row| row.get::<_, String>(0),
-        );
+    /// Get the previous branch for the given repository
+    fn previous_branch(&self, repo_path: &str) -> Result<Option<String>>;
 
-        match result {
-            Ok(branch) => Ok(Some(branch)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(e).context("Failed to get previous branch"),
-        }
-    }
+    /// Push a branch onto the checkout history stack for the repository.
+    /// Only the most recent `MAX_HISTORY_ENTRIES` entries per repository are kept.
+    fn push_checkout_history(&self, repo_path: &str, branch_name: &str) -> Result<()>;
 
-    #[test]
-    fn test_open_db_creates_table() {
-        let result = open_test_db();
-        assert!(result.is_ok());
+    /// Get the checkout history stack for the repository, most recent first.
+    /// Each branch appears once, at the position of its latest entry.
+    fn checkout_history(&self, repo_path: &str) -> Result<Vec<HistoryEntry>>;
 
-        let conn = result.unwrap();
+    /// Create or update an alias for a branch
+    fn create_alias(&self, repo_path: &str, alias: &str, branch_name: &str) -> Result<()>;
 
-        // Verify table exists
-        let table_check: Result<i64, _> = conn.query_row(
-            "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='branches'",
-            [],
-            |row| row.get(0),
-        );
+    /// Get the branch an alias points to in a repository
+    fn alias(&self, repo_path: &str, alias: &str) -> Result<Option<String>>;
 
-        assert!(table_check.is_ok());
-        assert_eq!(table_check.unwrap(), 1);
-    }
+    /// Delete an alias
+    fn delete_alias(&self, repo_path: &str, alias: &str) -> Result<()>;
 
-    #[test]
-    fn do_record_checkout_new_branch() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
+    /// Give an alias a new name, keeping its branch. Returns false if there
+    /// is no alias `old`.
+    fn rename_alias(&self, repo_path: &str, old: &str, new: &str) -> Result<bool>;
 
-        let result = do_record_checkout(&conn, &repo_path, "main");
-        assert!(result.is_ok());
+    /// List all aliases for a repository
+    fn aliases(&self, repo_path: &str) -> Result<Vec<Alias>>;
 
-        // Verify the record was created
-        let count: i64 = conn
-            .query_row(
-                "SELECT COUNT(*) FROM branches WHERE repo_path = ?1 AND branch_name = ?2",
-                [&repo_path, "main"],
-                |row| row.get(0),
-            )
-            .unwrap();
+    /// Aliases of every repository, by repository path
+    fn all_aliases(&self) -> Result<BTreeMap<String, Vec<Alias>>>;
 
-        assert_eq!(count, 1);
+    /// Get all aliases pointing to a specific branch
+    fn aliases_for_branch(&self, repo_path: &str, branch_name: &str) -> Result<Vec<String>>;
 
-        // Verify switch_count is 1
-        let switch_count: i64 = conn
-            .query_row(
-                "SELECT switch_count FROM branches WHERE repo_path = ?1 AND branch_name = ?2",
-                [&repo_path, "main"],
-                |row| row.get(0),
-            )
-            .unwrap();
+    /// Pin a branch in a repository (pinning twice is a no-op)
+    fn pin_branch(&self, repo_path: &str, branch_name: &str) -> Result<()>;
 
-        assert_eq!(switch_count, 1);
-    }
+    /// Unpin a branch. Returns false if it wasn't pinned.
+    fn unpin_branch(&self, repo_path: &str, branch_name: &str) -> Result<bool>;
 
-    #[test]
-    fn do_record_checkout_existing_branch() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
+    /// Pinned branches in a repository, in the order they were pinned
+    fn pinned_branches(&self, repo_path: &str) -> Result<Vec<String>>;
 
-        // Record first checkout
-        do_record_checkout(&conn, &repo_path, "main").unwrap();
+    /// Set the note on a branch, replacing the one it had
+    fn set_branch_note(&self, repo_path: &str, branch_name: &str, note: &str) -> Result<()>;
 
-        // Record second checkout
-        let result = do_record_checkout(&conn, &repo_path, "main");
-        assert!(result.is_ok());
+    /// Remove the note on a branch. Returns false if it had none.
+    fn remove_branch_note(&self, repo_path: &str, branch_name: &str) -> Result<bool>;
 
-        // Verify switch_count was incremented
-        let switch_count: i64 = conn
-            .query_row(
-                "SELECT switch_count FROM branches WHERE repo_path = ?1 AND branch_name = ?2",
-                [&repo_path, "main"],
-                |row| row.get(0),
-            )
-            .unwrap();
+    /// Notes on the branches of a repository, by branch name
+    fn branch_notes(&self, repo_path: &str) -> Result<HashMap<String, String>>;
 
-        assert_eq!(switch_count, 2);
-    }
+    /// Add branches (or glob patterns) to a group, creating it if needed.
+    /// Returns how many weren't in it yet.
+    fn add_to_group(
+        &self,
+        repo_path: &str,
+        group_name: &str,
+        branch_names: &[&str],
+    ) -> Result<usize>;
+
+    /// Remove branches from a group, or the whole group when `branch_names`
+    /// is empty. Returns the number of members removed.
+    fn remove_from_group(
+        &self,
+        repo_path: &str,
+        group_name: &str,
+        branch_names: &[&str],
+    ) -> Result<usize>;
+
+    /// The groups of a repository with their members (in the order they
+    /// were added), by group name
+    fn branch_groups(&self, repo_path: &str) -> Result<BTreeMap<String, Vec<String>>>;
+
+    /// The cached branch list of a repository, if it was stored under the
+    /// same refs `fingerprint`
+    fn cached_branches(&self, repo_path: &str, fingerprint: &str) -> Result<Option<Vec<String>>>;
+
+    /// Cache the branch list of a repository under its refs `fingerprint`
+    fn cache_branches(&self, repo_path: &str, fingerprint: &str, branches: &[String])
+        -> Result<()>;
+
+    /// Stack `branch` on `parent`, replacing the parent it had
+    fn set_stack_parent(&self, repo_path: &str, branch_name: &str, parent: &str) -> Result<()>;
+
+    /// Every stacked branch of a repository with its parent, by branch name
+    fn stack_links(&self, repo_path: &str) -> Result<Vec<StackLink>>;
+
+    /// Take a branch out of its stack. Branches stacked on it move down to
+    /// its parent (or start their own stacks if it had none). Returns false
+    /// if the branch wasn't in a stack.
+    fn unstack_branch(&self, repo_path: &str, branch_name: &str) -> Result<bool>;
+
+    /// Carry a branch's place in its stack over to a new name
+    fn rename_stack_branch(&self, repo_path: &str, old: &str, new: &str) -> Result<()>;
+
+    /// Move everything stored about a branch to its new name (`ggo
+    /// rename-record`): usage counts add up with any the new name already
+    /// has, and aliases, pins, history and stacks follow. Returns false if
+    /// nothing was stored about the old name.
+    fn rename_branch_data(&self, repo_path: &str, old: &str, new: &str) -> Result<bool>;
+
+    /// Move all history stored under the repository key `from` to `to`
+    /// (e.g. when switching to remote-URL keys). Returns the number of rows moved.
+    fn merge_repo(&self, from: &str, to: &str) -> Result<usize>;
+
+    /// Snapshot the branch records, aliases and previous branches of every
+    /// repository for `ggo export`
+    fn export_history(&self) -> Result<HistoryExport>;
+
+    /// Load an export in a single transaction. With `merge`, switch counts of
+    /// branches known on both sides are summed and the most recent timestamp,
+    /// alias target and previous branch win; without it imported rows replace
+    /// existing ones. Rows for other branches are left alone either way.
+    /// Returns the number of rows imported.
+    fn import_history(&self, export: &HistoryExport, merge: bool) -> Result<usize>;
+
+    /// Merge the changes seen in another machine's synced history and record
+    /// its new baseline under `baseline_key`, in a single transaction so a
+    /// change is never merged twice. Switch counts may be negative (the other
+    /// machine forgot or cleaned up branches); records left without switches
+    /// are dropped.
+    fn import_synced(
+        &self,
+        delta: &HistoryExport,
+        baseline_key: &str,
+        baseline: &str,
+    ) -> Result<()>;
+
+    /// Everything stored but the branch list cache
+    fn snapshot(&self) -> Result<Snapshot>;
+
+    /// Replace everything stored with `snapshot`, in a single transaction
+    fn restore_snapshot(&self, snapshot: &Snapshot) -> Result<()>;
+
+    /// Remove everything stored about a branch (usage record, aliases, checkout
+    /// history, previous-branch slot, pin, note) in a single transaction
+    fn delete_branch_data(&self, repo_path: &str, branch_name: &str) -> Result<()> {
+        self.forget_branches(repo_path, &[branch_name])
+    }
+
+    /// Like [`HistoryStore::delete_branch_data`] for several branches at once, in
+    /// a single transaction
+    fn forget_branches(&self, repo_path: &str, branch_names: &[&str]) -> Result<()>;
+
+    /// Every branch name ggo has stored anything about in a repository
+    /// (usage records, aliases, pins, notes, history), sorted
+    fn recorded_branches(&self, repo_path: &str) -> Result<Vec<String>>;
+
+    /// Number of rows stored under a repository key, across all kinds of data
+    fn repo_row_count(&self, repo_path: &str) -> Result<usize>;
+
+    /// Remove everything stored under a repository key. Returns the number of
+    /// rows removed.
+    fn forget_repo(&self, repo_path: &str) -> Result<usize>;
+
+    /// Branch records not used in the last `max_age_days` days, oldest first,
+    /// optionally only for one repository
+    fn old_records(&self, max_age_days: i64, repo_path: Option<&str>) -> Result<Vec<BranchRecord>>;
+
+    /// Remove branch records not used in the last `max_age_days` days,
+    /// optionally only for one repository.
+    /// Returns the number of records removed.
+    fn cleanup_old_records(&self, max_age_days: i64, repo_path: Option<&str>) -> Result<usize>;
+
+    /// Trim branch records to the retention policy (`storage.max_records`,
+    /// `storage.max_age_days`) in a single transaction: those unused for
+    /// `max_age_days` days (0 keeps them), then the lowest `score`s beyond
+    /// `max_records` (0: no limit). Cheap when there's nothing to trim.
+    /// Returns the number of records removed.
+    fn trim_records(
+        &self,
+        max_records: usize,
+        max_age_days: i64,
+        score: &dyn Fn(&BranchRecord) -> f64,
+    ) -> Result<usize>;
+
+    /// Branch records whose branch (or whole repository) no longer exists,
+    /// grouped by repository, optionally only for one repository. Each
+    /// repository is opened once and its live branches read in one pass.
+    fn deleted_branch_records(&self, repo_path: Option<&str>) -> Result<Vec<StaleRecords>> {
+        let records = match repo_path {
+            Some(repo_path) => self.branch_records(repo_path)?,
+            None => self.all_records()?,
+        };
 
-    #[test]
-    fn do_record_checkout_multiple_repos() {
-        let conn = open_test_db().unwrap();
-        let repo_path1 = unique_repo_path();
-        let repo_path2 = unique_repo_path();
+        let mut by_repo: BTreeMap<String, Vec<BranchRecord>> = BTreeMap::new();
+        for record in records {
+            // Repositories keyed by remote URL have no path to check
+            if Path::new(&record.repo_path).is_absolute() {
+                by_repo
+                    .entry(record.repo_path.clone())
+                    .or_default()
+                    .push(record);
+            }
+        }
 
-        do_record_checkout(&conn, &repo_path1, "main").unwrap();
-        do_record_checkout(&conn, &repo_path2, "main").unwrap();
+        let mut stale = Vec::new();
+
+        for (repo_path, records) in by_repo {
+            // A repository that can't be opened anymore takes all its records with it
+            let Some(live) = crate::git::branches_at(Path::new(&repo_path)) else {
+                stale.push(StaleRecords {
+                    repo_path,
+                    repo_gone: true,
+                    records,
+                    renamed: Vec::new(),
+                });
+                continue;
+            };
+
+            let recorded: Vec<String> = records.iter().map(|r| r.branch_name.clone()).collect();
+            let missing: Vec<String> = recorded
+                .iter()
+                .filter(|branch| !live.contains(*branch))
+                .cloned()
+                .collect();
+            if missing.is_empty() {
+                continue;
+            }
 
-        let records1 = do_get_branch_records(&conn, &repo_path1).unwrap();
-        let records2 = do_get_branch_records(&conn, &repo_path2).unwrap();
+            let renamed = crate::git::renamed_branches(Path::new(&repo_path), &missing, &recorded);
+            let records: Vec<BranchRecord> = records
+                .into_iter()
+                .filter(|r| missing.contains(&r.branch_name))
+                .filter(|r| !renamed.iter().any(|(old, _)| *old == r.branch_name))
+                .collect();
+
+            stale.push(StaleRecords {
+                repo_path,
+                repo_gone: false,
+                records,
+                renamed,
+            });
+        }
 
-        assert_eq!(records1.len(), 1);
-        assert_eq!(records2.len(), 1);
-        assert_eq!(records1[0].repo_path, repo_path1);
-        assert_eq!(records2[0].repo_path, repo_path2);
+        Ok(stale)
     }
 
-    #[test]
-    fn do_record_checkout_updates_timestamp() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        do_record_checkout(&conn, &repo_path, "main").unwrap();
-
-        let first_timestamp: i64 = conn
-            .query_row(
-                "SELECT last_used FROM branches WHERE repo_path = ?1 AND branch_name = ?2",
-                [&repo_path, "main"],
-                |row| row.get(0),
-            )
-            .unwrap();
+    /// Remove records and aliases of branches that no longer exist in their
+    /// repositories (all aliases of repositories that are gone), optionally
+    /// only for one repository, in a single transaction.
+    /// Returns what was removed, per repository.
+    fn cleanup_deleted_branches(&self, repo_path: Option<&str>) -> Result<Vec<StaleRecords>>;
 
-        // Wait a bit and record again
-        std::thread::sleep(std::time::Duration::from_millis(100));
-        do_record_checkout(&conn, &repo_path, "main").unwrap();
+    /// Count this invocation and report whether automatic maintenance is due,
+    /// which it is on every `every`-th invocation (never with 0)
+    fn maintenance_due(&self, every: u32) -> Result<bool>;
 
-        let second_timestamp: i64 = conn
-            .query_row(
-                "SELECT last_used FROM branches WHERE repo_path = ?1 AND branch_name = ?2",
-                [&repo_path, "main"],
-                |row| row.get(0),
-            )
-            .unwrap();
+    /// Value stored under `key` in ggo's own bookkeeping (the meta table)
+    fn meta(&self, key: &str) -> Result<Option<String>>;
 
-        assert!(second_timestamp >= first_timestamp);
-    }
+    /// Store `value` under `key` in ggo's own bookkeeping
+    fn set_meta(&self, key: &str, value: &str) -> Result<()>;
 
-    #[test]
-    fn do_get_branch_records_empty() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
+    /// Meta entries whose key starts with `prefix`, ordered by key
+    fn meta_with_prefix(&self, prefix: &str) -> Result<Vec<(String, String)>>;
 
-        let result = do_get_branch_records(&conn, &repo_path);
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap().len(), 0);
+    /// Remember the branches 'ggo -l' showed in a repository, in order, so
+    /// 'ggo --nth' can pick one
+    fn save_listing(&self, repo_path: &str, head: Option<&str>, branches: &[String]) -> Result<()> {
+        let listing = Listing {
+            listed_at: now_timestamp(),
+            head: head.map(str::to_string),
+            branches: branches.to_vec(),
+        };
+        self.set_meta(
+            &format!("{}{}", LISTING_PREFIX, repo_path),
+            &serde_json::to_string(&listing)?,
+        )
     }
 
-    #[test]
-    fn do_get_branch_records_single() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        do_record_checkout(&conn, &repo_path, "main").unwrap();
-
-        let records = do_get_branch_records(&conn, &repo_path).unwrap();
-        assert_eq!(records.len(), 1);
-        assert_eq!(records[0].repo_path, repo_path);
-        assert_eq!(records[0].branch_name, "main");
-        assert_eq!(records[0].switch_count, 1);
+    /// The branches 'ggo -l' last showed in a repository
+    fn listing(&self, repo_path: &str) -> Result<Option<Listing>> {
+        Ok(self
+            .meta(&format!("{}{}", LISTING_PREFIX, repo_path))?
+            .and_then(|json| serde_json::from_str(&json).ok()))
     }
 
-    #[test]
-    fn do_get_branch_records_multiple() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        do_record_checkout(&conn, &repo_path, "main").unwrap();
-        do_record_checkout(&conn, &repo_path, "develop").unwrap();
-        do_record_checkout(&conn, &repo_path, "feature").unwrap();
-
-        let records = do_get_branch_records(&conn, &repo_path).unwrap();
-        assert_eq!(records.len(), 3);
-
-        let branch_names: Vec<&str> = records.iter().map(|r| r.branch_name.as_str()).collect();
-        assert!(branch_names.contains(&"main"));
-        assert!(branch_names.contains(&"develop"));
-        assert!(branch_names.contains(&"feature"));
+    /// Remember that the shell hook found `branch` checked out in a repository
+    /// without recording a checkout, after `last_checkout`
+    fn save_visit(
+        &self,
+        repo_path: &str,
+        branch: &str,
+        last_checkout: Option<&CheckoutEvent>,
+    ) -> Result<()> {
+        let visit = Visit {
+            branch: branch.to_string(),
+            visited_at: now_timestamp(),
+            last_checkout: last_checkout.cloned(),
+        };
+        self.set_meta(
+            &format!("{}{}", VISIT_PREFIX, repo_path),
+            &serde_json::to_string(&visit)?,
+        )
     }
 
-    #[test]
-    fn do_get_branch_records_ordered_by_last_used() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        do_record_checkout(&conn, &repo_path, "first").unwrap();
-        std::thread::sleep(std::time::Duration::from_secs(1));
-        do_record_checkout(&conn, &repo_path, "second").unwrap();
-        std::thread::sleep(std::time::Duration::from_secs(1));
-        do_record_checkout(&conn, &repo_path, "third").unwrap();
-
-        let records = do_get_branch_records(&conn, &repo_path).unwrap();
-        assert_eq!(records.len(), 3);
-
-        // Should be ordered by last_used DESC
-        assert_eq!(records[0].branch_name, "third");
-        assert_eq!(records[1].branch_name, "second");
-        assert_eq!(records[2].branch_name, "first");
+    /// The branch the shell hook last found checked out in a repository
+    /// without recording a checkout
+    fn visit(&self, repo_path: &str) -> Result<Option<Visit>> {
+        Ok(self
+            .meta(&format!("{}{}", VISIT_PREFIX, repo_path))?
+            .and_then(|json| serde_json::from_str(&json).ok()))
     }
 
-    #[test]
-    fn do_get_branch_records_filters_by_repo() {
-        let conn = open_test_db().unwrap();
-        let repo_path1 = unique_repo_path();
-        let repo_path2 = unique_repo_path();
-
-        do_record_checkout(&conn, &repo_path1, "main").unwrap();
-        do_record_checkout(&conn, &repo_path2, "main").unwrap();
-        do_record_checkout(&conn, &repo_path2, "develop").unwrap();
-
-        let records1 = do_get_branch_records(&conn, &repo_path1).unwrap();
-        let records2 = do_get_branch_records(&conn, &repo_path2).unwrap();
-
-        assert_eq!(records1.len(), 1);
-        assert_eq!(records2.len(), 2);
+    /// Allow the hooks in the `.ggo.toml` at `repo_root` to run while the file's
+    /// SHA-256 is `digest`; None withdraws the trust
+    fn set_trusted_hooks(&self, repo_root: &str, digest: Option<&str>) -> Result<()> {
+        self.set_meta(
+            &format!("{}{}", TRUSTED_HOOKS_PREFIX, repo_root),
+            digest.unwrap_or_default(),
+        )
     }
 
-    #[test]
-    fn do_get_all_records_empty() {
-        let conn = open_test_db().unwrap();
-        let result = do_get_all_records(&conn);
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap().len(), 0);
+    /// SHA-256 of the `.ggo.toml` last trusted at `repo_root`, if any
+    fn trusted_hooks(&self, repo_root: &str) -> Result<Option<String>> {
+        Ok(self
+            .meta(&format!("{}{}", TRUSTED_HOOKS_PREFIX, repo_root))?
+            .filter(|digest| !digest.is_empty()))
     }
 
-    #[test]
-    fn do_get_all_records_multiple_repos() {
-        let conn = open_test_db().unwrap();
-        let repo_path1 = unique_repo_path();
-        let repo_path2 = unique_repo_path();
-
-        do_record_checkout(&conn, &repo_path1, "main").unwrap();
-        do_record_checkout(&conn, &repo_path1, "develop").unwrap();
-        do_record_checkout(&conn, &repo_path2, "main").unwrap();
+    /// Housekeeping cheap enough to run in passing (SQLite refreshes its
+    /// query planner statistics where they're stale)
+    fn quick_optimize(&self) -> Result<()>;
 
-        let records = do_get_all_records(&conn).unwrap();
-        assert_eq!(records.len(), 3);
-    }
+    /// Compact the stored history (VACUUM and ANALYZE for SQLite)
+    fn optimize(&self) -> Result<()>;
 
-    #[test]
-    fn do_get_all_records_ordered() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        do_record_checkout(&conn, &repo_path, "first").unwrap();
-        std::thread::sleep(std::time::Duration::from_secs(1));
-        do_record_checkout(&conn, &repo_path, "second").unwrap();
+    /// Size of the stored history on disk in bytes
+    fn database_size(&self) -> Result<u64>;
+}
 
-        let records = do_get_all_records(&conn).unwrap();
+/// A handle to the usage history, in whichever backend `storage.backend`
+/// selects; everything else goes through [`HistoryStore`].
+///
+/// Open it once per invocation and pass it down.
+pub struct Storage {
+    store: Box<dyn HistoryStore>,
+}
 
-        assert_eq!(records.len(), 2);
-        // Should be ordered by last_used DESC
-        assert_eq!(records[0].branch_name, "second");
-        assert_eq!(records[1].branch_name, "first");
+impl Storage {
+    /// Wrap a store, e.g. one a library user implements themselves
+    pub fn new(store: impl HistoryStore + 'static) -> Self {
+        Self {
+            store: Box::new(store),
+        }
     }
 
-    #[test]
-    fn test_get_stats_empty() {
-        let conn = open_test_db().unwrap();
-
-        // Query stats from test database
-        let total_switches: i64 = conn
-            .query_row(
-                "SELECT COALESCE(SUM(switch_count), 0) FROM branches",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap();
-
-        let unique_branches: i64 = conn
-            .query_row("SELECT COUNT(*) FROM branches", [], |row| row.get(0))
-            .unwrap();
-
-        let unique_repos: i64 = conn
-            .query_row(
-                "SELECT COUNT(DISTINCT repo_path) FROM branches",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap();
-
-        assert_eq!(total_switches, 0);
-        assert_eq!(unique_branches, 0);
-        assert_eq!(unique_repos, 0);
+    /// Open the default backend's store in the ggo data directory
+    pub fn open() -> Result<Self> {
+        Self::open_backend(StorageBackend::default())
     }
 
-    #[test]
-    fn test_get_stats_single_branch() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        do_record_checkout(&conn, &repo_path, "main").unwrap();
-
-        // Count stats from our test connection
-        let total: i64 = conn
-            .query_row(
-                "SELECT COALESCE(SUM(switch_count), 0) FROM branches",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap();
-        let unique_branches: i64 = conn
-            .query_row("SELECT COUNT(*) FROM branches", [], |row| row.get(0))
-            .unwrap();
-        let unique_repos: i64 = conn
-            .query_row(
-                "SELECT COUNT(DISTINCT repo_path) FROM branches",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap();
-
-        assert_eq!(total, 1);
-        assert_eq!(unique_branches, 1);
-        assert_eq!(unique_repos, 1);
+    /// Open `backend`'s store in the ggo data directory, creating it (and
+    /// migrating an SQLite database) if needed
+    pub fn open_backend(backend: StorageBackend) -> Result<Self> {
+        match backend {
+            #[cfg(feature = "sqlite")]
+            StorageBackend::Sqlite => Ok(Self::new(SqliteStore::open()?)),
+            #[cfg(not(feature = "sqlite"))]
+            StorageBackend::Sqlite => {
+                Err(GgoError::BackendUnavailable("sqlite".to_string()).into())
+            }
+            StorageBackend::Json => Ok(Self::new(JsonStore::open()?)),
+            #[cfg(feature = "encrypted")]
+            StorageBackend::Encrypted => Ok(Self::new(JsonStore::open_encrypted()?)),
+            #[cfg(not(feature = "encrypted"))]
+            StorageBackend::Encrypted => {
+                Err(GgoError::BackendUnavailable("encrypted".to_string()).into())
+            }
+        }
     }
 
-    #[test]
-    fn test_get_stats_multiple_switches() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        do_record_checkout(&conn, &repo_path, "main").unwrap();
-        do_record_checkout(&conn, &repo_path, "main").unwrap();
-        do_record_checkout(&conn, &repo_path, "main").unwrap();
-
-        let total: i64 = conn
-            .query_row(
-                "SELECT COALESCE(SUM(switch_count), 0) FROM branches",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap();
-        assert_eq!(total, 3);
+    /// Open `backend`'s store for a quick lookup, without creating, migrating
+    /// or writing anything (the SQLite database is opened read-only). None
+    /// when there is no history yet.
+    pub fn open_read_only(backend: StorageBackend) -> Result<Option<Self>> {
+        let store = match backend {
+            #[cfg(feature = "sqlite")]
+            StorageBackend::Sqlite => SqliteStore::open_read_only()?.map(Self::new),
+            // Opening only reads the file
+            StorageBackend::Json => Some(Self::new(JsonStore::open()?)),
+            #[cfg(feature = "encrypted")]
+            StorageBackend::Encrypted => JsonStore::open_encrypted_existing()?.map(Self::new),
+            #[allow(unreachable_patterns)]
+            other => Some(Self::open_backend(other)?),
+        };
+        Ok(store.map(|store| store.with_recording(false)))
+    }
+
+    /// Whether `backend` has stored anything in the ggo data directory yet,
+    /// checked without creating or opening anything
+    pub fn exists(backend: StorageBackend) -> Result<bool> {
+        let file_name = match backend {
+            #[cfg(feature = "sqlite")]
+            StorageBackend::Sqlite => sqlite::DB_FILE_NAME,
+            StorageBackend::Json => json::FILE_NAME,
+            #[cfg(feature = "encrypted")]
+            StorageBackend::Encrypted => json::ENCRYPTED_FILE_NAME,
+            // Opening it reports that the backend isn't built in
+            #[allow(unreachable_patterns)]
+            _ => return Ok(true),
+        };
+        Ok(data_dir_location()?.0.join(file_name).exists())
     }
 
-    #[test]
-    fn test_get_stats_multiple_branches_and_repos() {
-        let conn = open_test_db().unwrap();
-        let repo_path1 = unique_repo_path();
-        let repo_path2 = unique_repo_path();
-
-        do_record_checkout(&conn, &repo_path1, "main").unwrap();
-        do_record_checkout(&conn, &repo_path1, "develop").unwrap();
-        do_record_checkout(&conn, &repo_path2, "main").unwrap();
-        do_record_checkout(&conn, &repo_path2, "main").unwrap();
-
-        let total: i64 = conn
-            .query_row(
-                "SELECT COALESCE(SUM(switch_count), 0) FROM branches",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap();
-        let unique_branches: i64 = conn
-            .query_row("SELECT COUNT(*) FROM branches", [], |row| row.get(0))
-            .unwrap();
-        let unique_repos: i64 = conn
-            .query_row(
-                "SELECT COUNT(DISTINCT repo_path) FROM branches",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap();
-
-        assert_eq!(total, 4);
-        assert_eq!(unique_branches, 3);
-        assert_eq!(unique_repos, 2);
+    /// Open a throwaway in-memory store of the default backend (nothing is
+    /// persisted)
+    pub fn open_in_memory() -> Result<Self> {
+        #[cfg(feature = "sqlite")]
+        return Ok(Self::new(SqliteStore::open_in_memory()?));
+        #[cfg(not(feature = "sqlite"))]
+        return Ok(Self::new(JsonStore::open_in_memory()));
     }
 
-    #[test]
-    fn test_save_previous_branch() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        let result = do_save_previous_branch(&conn, &repo_path, "main");
-        assert!(result.is_ok());
-
-        let branch: String = conn
-            .query_row(
-                "SELECT branch_name FROM previous_branch WHERE repo_path = ?1",
-                [&repo_path],
-                |row| row.get(0),
-            )
-            .unwrap();
+    /// Ignore branches matching these glob patterns: their checkouts are not
+    /// recorded and existing usage records for them are not returned
+    pub fn with_exclude(mut self, patterns: Vec<String>) -> Self {
+        self.store.options_mut().exclude = patterns;
+        self
+    }
 
-        assert_eq!(branch, "main");
+    /// With `false`, checkouts leave no trace: usage records, checkout
+    /// events, the previous branch and the history stack are left untouched
+    pub fn with_recording(mut self, record: bool) -> Self {
+        self.store.options_mut().record = record;
+        self
     }
+}
 
-    #[test]
-    fn test_save_previous_branch_updates() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        do_save_previous_branch(&conn, &repo_path, "main").unwrap();
-        do_save_previous_branch(&conn, &repo_path, "develop").unwrap();
-
-        let branch: String = conn
-            .query_row(
-                "SELECT branch_name FROM previous_branch WHERE repo_path = ?1",
-                [&repo_path],
-                |row| row.get(0),
-            )
-            .unwrap();
+impl std::ops::Deref for Storage {
+    type Target = dyn HistoryStore;
 
-        assert_eq!(branch, "develop");
+    fn deref(&self) -> &Self::Target {
+        self.store.as_ref()
+    }
+}
 
-        // Verify only one record exists
-        let count: i64 = conn
-            .query_row(
-                "SELECT COUNT(*) FROM previous_branch WHERE repo_path = ?1",
-                [&repo_path],
-                |row| row.get(0),
-            )
-            .unwrap();
+/// Without SQLite there are no schema migrations, so no backups either
+#[cfg(not(feature = "sqlite"))]
+pub fn restore_backup() -> Result<(PathBuf, i32)> {
+    Err(GgoError::BackendUnavailable("sqlite".to_string()).into())
+}
 
-        assert_eq!(count, 1);
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
     #[test]
-    fn test_save_previous_branch_multiple_repos() {
-        let conn = open_test_db().unwrap();
-        let repo_path1 = unique_repo_path();
-        let repo_path2 = unique_repo_path();
+    fn test_listing_round_trip() {
+        let storage = Storage::open_in_memory().unwrap();
+        assert_eq!(storage.listing("/repo").unwrap(), None);
 
-        do_save_previous_branch(&conn, &repo_path1, "main").unwrap();
-        do_save_previous_branch(&conn, &repo_path2, "develop").unwrap();
-
-        let branch1 = do_get_previous_branch(&conn, &repo_path1).unwrap();
-        let branch2 = do_get_previous_branch(&conn, &repo_path2).unwrap();
+        let branches = vec!["feature/b".to_string(), "feature/a".to_string()];
+        storage
+            .save_listing("/repo", Some("main"), &branches)
+            .unwrap();
+        storage.save_listing("/other", None, &[]).unwrap();
 
-        assert_eq!(branch1, Some("main".to_string()));
-        assert_eq!(branch2, Some("develop".to_string()));
+        let listing = storage.listing("/repo").unwrap().unwrap();
+        assert_eq!(listing.branches, branches);
+        assert_eq!(listing.head.as_deref(), Some("main"));
+        assert!(now_timestamp() - listing.listed_at < 5);
     }
 
     #[test]
-    fn do_get_previous_branch_not_found() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
+    fn test_visit_round_trip() {
+        let storage = Storage::open_in_memory().unwrap();
+        assert_eq!(storage.visit("/repo").unwrap(), None);
+
+        storage.save_visit("/repo", "feature/a", None).unwrap();
+        storage.record_checkout("/repo", None, "main").unwrap();
+        let last = storage.last_checkout("/repo").unwrap();
+        storage
+            .save_visit("/repo", "feature/b", last.as_ref())
+            .unwrap();
 
-        let result = do_get_previous_branch(&conn, &repo_path);
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), None);
+        let visit = storage.visit("/repo").unwrap().unwrap();
+        assert_eq!(visit.branch, "feature/b");
+        assert!(now_timestamp() - visit.visited_at < 5);
+        assert_eq!(visit.last_checkout, last);
+        assert_eq!(storage.visit("/other").unwrap(), None);
     }
 
     #[test]
-    fn do_get_previous_branch_exists() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
+    fn test_trusted_hooks_round_trip() {
+        let storage = Storage::open_in_memory().unwrap();
+        assert_eq!(storage.trusted_hooks("/repo").unwrap(), None);
 
-        do_save_previous_branch(&conn, &repo_path, "main").unwrap();
+        storage.set_trusted_hooks("/repo", Some("abc123")).unwrap();
+        assert_eq!(
+            storage.trusted_hooks("/repo").unwrap().as_deref(),
+            Some("abc123")
+        );
+        assert_eq!(storage.trusted_hooks("/other").unwrap(), None);
 
-        let result = do_get_previous_branch(&conn, &repo_path);
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), Some("main".to_string()));
+        storage.set_trusted_hooks("/repo", None).unwrap();
+        assert_eq!(storage.trusted_hooks("/repo").unwrap(), None);
     }
 
     #[test]
-    fn do_get_previous_branch_different_repos() {
-        let conn = open_test_db().unwrap();
-        let repo_path1 = unique_repo_path();
-        let repo_path2 = unique_repo_path();
-
-        do_save_previous_branch(&conn, &repo_path1, "main").unwrap();
+    fn test_records_to_trim() {
+        let now = now_timestamp();
+        let record = |name: &str, switch_count: i64, days_ago: i64| BranchRecord {
+            repo_path: "/repo".to_string(),
+            branch_name: name.to_string(),
+            switch_count,
+            last_used: now - days_ago * 86400,
+        };
+        let records = || {
+            vec![
+                record("busy", 50, 1),
+                record("rare", 1, 2),
+                record("ancient", 90, 400),
+                record("quiet", 2, 2),
+            ]
+        };
+        let score = |r: &BranchRecord| r.switch_count as f64;
+        let names = |trimmed: Vec<BranchRecord>| {
+            let mut names: Vec<String> = trimmed.into_iter().map(|r| r.branch_name).collect();
+            names.sort();
+            names
+        };
 
-        let result1 = do_get_previous_branch(&conn, &repo_path1);
-        let result2 = do_get_previous_branch(&conn, &repo_path2);
+        assert!(records_to_trim(records(), 0, 0, &score).is_empty());
+        assert!(records_to_trim(records(), 4, 0, &score).is_empty());
+        assert_eq!(
+            names(records_to_trim(records(), 0, 365, &score)),
+            ["ancient"]
+        );
+        assert_eq!(
+            names(records_to_trim(records(), 2, 0, &score)),
+            ["quiet", "rare"]
+        );
+        assert_eq!(
+            names(records_to_trim(records(), 2, 365, &score)),
+            ["ancient", "rare"]
+        );
 
-        assert_eq!(result1.unwrap(), Some("main".to_string()));
-        assert_eq!(result2.unwrap(), None);
+        assert!(!over_retention(4, Some(now - 400 * 86400), 4, 0));
+        assert!(over_retention(4, Some(now - 400 * 86400), 4, 365));
+        assert!(over_retention(5, Some(now), 4, 365));
+        assert!(!over_retention(0, None, 4, 365));
     }
 
     #[test]
@@ -1190,319 +1098,6 @@ mod tests {
         assert!(dir.is_dir());
     }
 
-    #[test]
-    fn test_get_db_path() {
-        let result = get_db_path();
-        assert!(result.is_ok());
-
-        let path = result.unwrap();
-        assert!(path.to_string_lossy().ends_with("data.db"));
-    }
-
-    #[test]
-    fn test_special_characters_in_branch_names() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        let special_branch = "feature/issue-#123_v2.0";
-        do_record_checkout(&conn, &repo_path, special_branch).unwrap();
-
-        let records = do_get_branch_records(&conn, &repo_path).unwrap();
-        assert_eq!(records.len(), 1);
-        assert_eq!(records[0].branch_name, special_branch);
-    }
-
-    #[test]
-    fn test_unicode_in_branch_names() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        let unicode_branch = "feature/日本語-🚀";
-        do_record_checkout(&conn, &repo_path, unicode_branch).unwrap();
-
-        let records = do_get_branch_records(&conn, &repo_path).unwrap();
-        assert_eq!(records.len(), 1);
-        assert_eq!(records[0].branch_name, unicode_branch);
-    }
-
-    #[test]
-    fn test_long_branch_names() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        let long_branch = "a".repeat(200);
-        do_record_checkout(&conn, &repo_path, &long_branch).unwrap();
-
-        let records = do_get_branch_records(&conn, &repo_path).unwrap();
-        assert_eq!(records.len(), 1);
-        assert_eq!(records[0].branch_name, long_branch);
-    }
-
-    #[test]
-    fn test_long_repo_paths() {
-        let conn = open_test_db().unwrap();
-        let long_path = format!("{}/long/{}", unique_repo_path(), "repo/".repeat(50));
-        do_record_checkout(&conn, &long_path, "main").unwrap();
-
-        let records = do_get_branch_records(&conn, &long_path).unwrap();
-        assert_eq!(records.len(), 1);
-        assert_eq!(records[0].repo_path, long_path);
-    }
-
-    // Alias test helper functions
-    fn do_create_alias(
-        conn: &Connection,
-        repo_path: &str,
-        alias: &str,
-        branch_name: &str,
-    ) -> Result<()> {
-        let now = now_timestamp();
-
-        conn.execute(
-            "INSERT OR REPLACE INTO aliases (repo_path, alias, branch_name, created_at)
-             VALUES (?1, ?2, ?3, ?4)",
-            [repo_path, alias, branch_name, &now.to_string()],
-        )
-        .context("Failed to create alias")?;
-
-        Ok(())
-    }
-
-    fn do_get_alias(conn: &Connection, repo_path: &str, alias: &str) -> Result<Option<String>> {
-        let result = conn.query_row(
-            "SELECT branch_name FROM aliases WHERE repo_path = ?1 AND alias = ?2",
-            [repo_path, alias],
-            |row| row.get::<_, String>(0),
-        );
-
-        match result {
-            Ok(branch) => Ok(Some(branch)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(e).context("Failed to get alias"),
-        }
-    }
-
-    fn do_delete_alias(conn: &Connection, repo_path: &str, alias: &str) -> Result<()> {
-        conn.execute(
-            "DELETE FROM aliases WHERE repo_path = ?1 AND alias = ?2",
-            [repo_path, alias],
-        )
-        .context("Failed to delete alias")?;
-
-        Ok(())
-    }
-
-    fn do_list_aliases(conn: &Connection, repo_path: &str) -> Result<Vec<Alias>> {
-        let mut stmt = conn
-            .prepare(
-                "SELECT repo_path, alias, branch_name, created_at
-                 FROM aliases
-                 WHERE repo_path = ?1
-                 ORDER BY alias",
-            )
-            .context("Failed to prepare query")?;
-
-        let aliases = stmt
-            .query_map([repo_path], |row| {
-                Ok(Alias {
-                    repo_path: row.get(0)?,
-                    alias: row.get(1)?,
-                    branch_name: row.get(2)?,
-                    created_at: row.get(3)?,
-                })
-            })
-            .context("Failed to query aliases")?
-            .map_while(Result::ok)
-            .collect();
-
-        Ok(aliases)
-    }
-
-    fn do_get_aliases_for_branch(
-        conn: &Connection,
-        repo_path: &str,
-        branch_name: &str,
-    ) -> Result<Vec<String>> {
-        let mut stmt = conn
-            .prepare(
-                "SELECT alias
-                 FROM aliases
-                 WHERE repo_path = ?1 AND branch_name = ?2
-                 ORDER BY alias",
-            )
-            .context("Failed to prepare query")?;
-
-        let aliases = stmt
-            .query_map([repo_path, branch_name], |row| row.get::<_, String>(0))
-            .context("Failed to query aliases")?
-            .map_while(Result::ok)
-            .collect();
-
-        Ok(aliases)
-    }
-
-    #[test]
-    fn test_create_alias() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        let result = do_create_alias(&conn, &repo_path, "m", "master");
-        assert!(result.is_ok());
-
-        let branch = do_get_alias(&conn, &repo_path, "m").unwrap();
-        assert_eq!(branch, Some("master".to_string()));
-    }
-
-    #[test]
-    fn test_create_alias_updates_existing() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        do_create_alias(&conn, &repo_path, "m", "master").unwrap();
-        do_create_alias(&conn, &repo_path, "m", "main").unwrap();
-
-        let branch = do_get_alias(&conn, &repo_path, "m").unwrap();
-        assert_eq!(branch, Some("main".to_string()));
-
-        // Verify only one alias exists
-        let aliases = do_list_aliases(&conn, &repo_path).unwrap();
-        assert_eq!(aliases.len(), 1);
-    }
-
-    #[test]
-    fn test_get_alias_not_found() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        let result = do_get_alias(&conn, &repo_path, "nonexistent");
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), None);
-    }
-
-    #[test]
-    fn test_delete_alias() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        do_create_alias(&conn, &repo_path, "m", "master").unwrap();
-        let result = do_delete_alias(&conn, &repo_path, "m");
-        assert!(result.is_ok());
-
-        let branch = do_get_alias(&conn, &repo_path, "m").unwrap();
-        assert_eq!(branch, None);
-    }
-
-    #[test]
-    fn test_list_aliases_empty() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        let aliases = do_list_aliases(&conn, &repo_path).unwrap();
-        assert_eq!(aliases.len(), 0);
-    }
-
-    #[test]
-    fn test_list_aliases_multiple() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        do_create_alias(&conn, &repo_path, "m", "master").unwrap();
-        do_create_alias(&conn, &repo_path, "d", "develop").unwrap();
-        do_create_alias(&conn, &repo_path, "f", "feature/test").unwrap();
-
-        let aliases = do_list_aliases(&conn, &repo_path).unwrap();
-        assert_eq!(aliases.len(), 3);
-
-        let alias_names: Vec<&str> = aliases.iter().map(|a| a.alias.as_str()).collect();
-        assert!(alias_names.contains(&"m"));
-        assert!(alias_names.contains(&"d"));
-        assert!(alias_names.contains(&"f"));
-    }
-
-    #[test]
-    fn test_list_aliases_sorted() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        do_create_alias(&conn, &repo_path, "z", "zzz").unwrap();
-        do_create_alias(&conn, &repo_path, "a", "aaa").unwrap();
-        do_create_alias(&conn, &repo_path, "m", "mmm").unwrap();
-
-        let aliases = do_list_aliases(&conn, &repo_path).unwrap();
-        assert_eq!(aliases.len(), 3);
-
-        // Should be sorted alphabetically by alias
-        assert_eq!(aliases[0].alias, "a");
-        assert_eq!(aliases[1].alias, "m");
-        assert_eq!(aliases[2].alias, "z");
-    }
-
-    #[test]
-    fn test_list_aliases_filters_by_repo() {
-        let conn = open_test_db().unwrap();
-        let repo_path1 = unique_repo_path();
-        let repo_path2 = unique_repo_path();
-
-        do_create_alias(&conn, &repo_path1, "m", "master").unwrap();
-        do_create_alias(&conn, &repo_path2, "m", "main").unwrap();
-        do_create_alias(&conn, &repo_path2, "d", "develop").unwrap();
-
-        let aliases1 = do_list_aliases(&conn, &repo_path1).unwrap();
-        let aliases2 = do_list_aliases(&conn, &repo_path2).unwrap();
-
-        assert_eq!(aliases1.len(), 1);
-        assert_eq!(aliases2.len(), 2);
-    }
-
-    #[test]
-    fn test_get_aliases_for_branch_empty() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        let aliases = do_get_aliases_for_branch(&conn, &repo_path, "master").unwrap();
-        assert_eq!(aliases.len(), 0);
-    }
-
-    #[test]
-    fn test_get_aliases_for_branch_single() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        do_create_alias(&conn, &repo_path, "m", "master").unwrap();
-
-        let aliases = do_get_aliases_for_branch(&conn, &repo_path, "master").unwrap();
-        assert_eq!(aliases.len(), 1);
-        assert_eq!(aliases[0], "m");
-    }
-
-    #[test]
-    fn test_get_aliases_for_branch_multiple() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        do_create_alias(&conn, &repo_path, "m", "master").unwrap();
-        do_create_alias(&conn, &repo_path, "main", "master").unwrap();
-        do_create_alias(&conn, &repo_path, "prod", "master").unwrap();
-        do_create_alias(&conn, &repo_path, "d", "develop").unwrap();
-
-        let aliases = do_get_aliases_for_branch(&conn, &repo_path, "master").unwrap();
-        assert_eq!(aliases.len(), 3);
-        assert!(aliases.contains(&"m".to_string()));
-        assert!(aliases.contains(&"main".to_string()));
-        assert!(aliases.contains(&"prod".to_string()));
-    }
-
-    #[test]
-    fn test_alias_with_special_characters() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-
-        do_create_alias(&conn, &repo_path, "my-alias", "feature/test-123").unwrap();
-
-        let branch = do_get_alias(&conn, &repo_path, "my-alias").unwrap();
-        assert_eq!(branch, Some("feature/test-123".to_string()));
-    }
-
     #[test]
     fn test_alias_struct_clone() {
         let alias = Alias {
@@ -1536,276 +1131,26 @@ mod tests {
     }
 
     #[test]
-    fn test_alias_repo_isolation() {
-        let conn = open_test_db().unwrap();
-        let repo_path1 = unique_repo_path();
-        let repo_path2 = unique_repo_path();
+    fn test_data_dir_precedence() {
+        let flag = || Some(PathBuf::from("/flag"));
+        let env = || Some(PathBuf::from("/env"));
+        let configured = || Some(PathBuf::from("/config"));
 
-        // Create alias "m" → "master" in repo1
-        do_create_alias(&conn, &repo_path1, "m", "master").unwrap();
-
-        // Try to get alias "m" from repo2 - should return None
-        let result = do_get_alias(&conn, &repo_path2, "m").unwrap();
         assert_eq!(
-            result, None,
-            "Alias from repo1 should not be accessible in repo2"
+            pick_data_dir(flag(), env(), configured()).unwrap(),
+            (PathBuf::from("/flag"), DataDirSource::Flag)
         );
-
-        // Verify it still works in repo1
-        let result = do_get_alias(&conn, &repo_path1, "m").unwrap();
-        assert_eq!(result, Some("master".to_string()));
-    }
-
-    #[test]
-    fn test_alias_same_name_different_repos() {
-        let conn = open_test_db().unwrap();
-        let repo_path1 = unique_repo_path();
-        let repo_path2 = unique_repo_path();
-
-        // Create alias "m" → "master" in repo1
-        do_create_alias(&conn, &repo_path1, "m", "master").unwrap();
-
-        // Create alias "m" → "main" in repo2 (same alias name, different branch)
-        do_create_alias(&conn, &repo_path2, "m", "main").unwrap();
-
-        // Verify each repo gets its own alias
-        let result1 = do_get_alias(&conn, &repo_path1, "m").unwrap();
-        assert_eq!(result1, Some("master".to_string()));
-
-        let result2 = do_get_alias(&conn, &repo_path2, "m").unwrap();
-        assert_eq!(result2, Some("main".to_string()));
-    }
-
-    #[test]
-    fn test_delete_alias_only_affects_current_repo() {
-        let conn = open_test_db().unwrap();
-        let repo_path1 = unique_repo_path();
-        let repo_path2 = unique_repo_path();
-
-        // Create same alias in both repos
-        do_create_alias(&conn, &repo_path1, "m", "master").unwrap();
-        do_create_alias(&conn, &repo_path2, "m", "main").unwrap();
-
-        // Delete from repo1
-        do_delete_alias(&conn, &repo_path1, "m").unwrap();
-
-        // Verify deleted in repo1
-        let result1 = do_get_alias(&conn, &repo_path1, "m").unwrap();
-        assert_eq!(result1, None);
-
-        // Verify still exists in repo2
-        let result2 = do_get_alias(&conn, &repo_path2, "m").unwrap();
-        assert_eq!(result2, Some("main".to_string()));
-    }
-
-    #[test]
-    fn test_get_aliases_for_branch_repo_scoped() {
-        let conn = open_test_db().unwrap();
-        let repo_path1 = unique_repo_path();
-        let repo_path2 = unique_repo_path();
-
-        // Create aliases for "master" in both repos
-        do_create_alias(&conn, &repo_path1, "m", "master").unwrap();
-        do_create_alias(&conn, &repo_path1, "prod", "master").unwrap();
-        do_create_alias(&conn, &repo_path2, "main", "master").unwrap();
-
-        // Get aliases for "master" in repo1 - should only get repo1's aliases
-        let aliases1 = do_get_aliases_for_branch(&conn, &repo_path1, "master").unwrap();
-        assert_eq!(aliases1.len(), 2);
-        assert!(aliases1.contains(&"m".to_string()));
-        assert!(aliases1.contains(&"prod".to_string()));
-        assert!(!aliases1.contains(&"main".to_string()));
-
-        // Get aliases for "master" in repo2 - should only get repo2's aliases
-        let aliases2 = do_get_aliases_for_branch(&conn, &repo_path2, "master").unwrap();
-        assert_eq!(aliases2.len(), 1);
-        assert!(aliases2.contains(&"main".to_string()));
-        assert!(!aliases2.contains(&"m".to_string()));
-    }
-
-    // Migration tests
-    #[test]
-    fn test_schema_version_table_created() {
-        let conn = open_test_db().unwrap();
-
-        // Verify schema_version table exists
-        let table_exists: i64 = conn
-            .query_row(
-                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='schema_version'",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap();
-
-        assert_eq!(table_exists, 1);
-    }
-
-    #[test]
-    fn test_fresh_database_migrates_to_current() {
-        let conn = open_test_db().unwrap();
-
-        // Check that we're at current version
-        let version: i32 = conn
-            .query_row(
-                "SELECT version FROM schema_version ORDER BY version DESC LIMIT 1",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap();
-
-        assert_eq!(version, CURRENT_SCHEMA_VERSION);
-    }
-
-    #[test]
-    fn test_migration_creates_all_tables() {
-        let conn = open_test_db().unwrap();
-
-        // Verify branches table exists
-        let branches_exists: i64 = conn
-            .query_row(
-                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='branches'",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap();
-        assert_eq!(branches_exists, 1);
-
-        // Verify aliases table exists
-        let aliases_exists: i64 = conn
-            .query_row(
-                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='aliases'",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap();
-        assert_eq!(aliases_exists, 1);
-
-        // Verify previous_branch table exists
-        let prev_exists: i64 = conn
-            .query_row(
-                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='previous_branch'",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap();
-        assert_eq!(prev_exists, 1);
-    }
-
-    #[test]
-    fn test_migration_creates_all_indices() {
-        let conn = open_test_db().unwrap();
-
-        // Get all index names
-        let indices: Vec<String> = conn
-            .prepare("SELECT name FROM sqlite_master WHERE type='index'")
-            .unwrap()
-            .query_map([], |row| row.get(0))
-            .unwrap()
-            .map_while(Result::ok)
-            .collect();
-
-        // Check for expected indices
-        assert!(indices.contains(&"idx_branches_repo_last_used".to_string()));
-        assert!(indices.contains(&"idx_branches_last_used".to_string()));
-        assert!(indices.contains(&"idx_aliases_branch".to_string()));
-    }
-
-    #[test]
-    fn test_migration_records_versions() {
-        let conn = open_test_db().unwrap();
-
-        // Check that both migration versions are recorded
-        let versions: Vec<i32> = conn
-            .prepare("SELECT version FROM schema_version ORDER BY version")
-            .unwrap()
-            .query_map([], |row| row.get(0))
-            .unwrap()
-            .map_while(Result::ok)
-            .collect();
-
-        assert_eq!(versions.len(), 2);
-        assert_eq!(versions[0], 1);
-        assert_eq!(versions[1], 2);
-    }
-
-    #[test]
-    fn test_migration_from_v1_to_v2() {
-        // Simulate a database that only has v1 schema
-        let conn = Connection::open_in_memory().unwrap();
-
-        // Create schema_version table
-        conn.execute(
-            "CREATE TABLE schema_version (
-                version INTEGER PRIMARY KEY,
-                applied_at INTEGER NOT NULL
-            )",
-            [],
-        )
-        .unwrap();
-
-        // Manually create v1 schema
-        conn.execute(
-            "CREATE TABLE branches (
-                id INTEGER PRIMARY KEY,
-                repo_path TEXT NOT NULL,
-                branch_name TEXT NOT NULL,
-                switch_count INTEGER DEFAULT 1,
-                last_used INTEGER NOT NULL,
-                UNIQUE(repo_path, branch_name)
-            )",
-            [],
-        )
-        .unwrap();
-
-        // Record v1 migration
-        conn.execute(
-            "INSERT INTO schema_version (version, applied_at) VALUES (1, 1234567890)",
-            [],
-        )
-        .unwrap();
-
-        // Now run initialization (should migrate to v2)
-        initialize_tables(&conn).unwrap();
-
-        // Verify we're at v2
-        let version: i32 = conn
-            .query_row(
-                "SELECT version FROM schema_version ORDER BY version DESC LIMIT 1",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap();
-        assert_eq!(version, 2);
-
-        // Verify aliases table was created
-        let aliases_exists: i64 = conn
-            .query_row(
-                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='aliases'",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap();
-        assert_eq!(aliases_exists, 1);
-    }
-
-    #[test]
-    fn test_no_migration_when_current() {
-        let conn = open_test_db().unwrap();
-
-        // Get current version count
-        let version_count: i64 = conn
-            .query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0))
-            .unwrap();
-
-        // Run initialization again (should not add duplicate versions)
-        initialize_tables(&conn).unwrap();
-
-        let new_version_count: i64 = conn
-            .query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0))
-            .unwrap();
-
-        // Count should be the same (no duplicate migrations)
-        assert_eq!(version_count, new_version_count);
+        assert_eq!(
+            pick_data_dir(None, env(), configured()).unwrap(),
+            (PathBuf::from("/env"), DataDirSource::Env)
+        );
+        assert_eq!(
+            pick_data_dir(None, None, configured()).unwrap(),
+            (PathBuf::from("/config"), DataDirSource::Config)
+        );
+        let (default, source) = pick_data_dir(None, None, None).unwrap();
+        assert_eq!(source, DataDirSource::Default);
+        assert!(default.ends_with("ggo"));
     }
 
     #[test]
@@ -1830,153 +1175,4 @@ mod tests {
         assert_eq!(data_dir, test_path);
         assert!(test_path.exists());
     }
-
-    #[test]
-    fn test_env_var_isolates_database() {
-        // Create two different temp directories
-        let temp_dir1 = tempfile::tempdir().unwrap();
-        let temp_dir2 = tempfile::tempdir().unwrap();
-
-        let test_path1 = temp_dir1.path().join("db1");
-        let test_path2 = temp_dir2.path().join("db2");
-
-        // Use first database
-        std::env::set_var("GGO_DATA_DIR", &test_path1);
-        let conn1 = open_db().unwrap();
-        do_record_checkout(&conn1, "/test1", "branch1").unwrap();
-
-        // Switch to second database
-        std::env::set_var("GGO_DATA_DIR", &test_path2);
-        let conn2 = open_db().unwrap();
-        do_record_checkout(&conn2, "/test2", "branch2").unwrap();
-
-        // Verify isolation
-        let records1 = do_get_branch_records(&conn1, "/test1").unwrap();
-        let records2 = do_get_branch_records(&conn2, "/test2").unwrap();
-
-        assert_eq!(records1.len(), 1);
-        assert_eq!(records2.len(), 1);
-        assert_eq!(records1[0].branch_name, "branch1");
-        assert_eq!(records2[0].branch_name, "branch2");
-
-        // Clean up
-        std::env::remove_var("GGO_DATA_DIR");
-    }
-
-    #[test]
-    fn test_migration_preserves_existing_data() {
-        // Create a database with v1 and some data
-        let conn = Connection::open_in_memory().unwrap();
-
-        // Create schema_version table
-        conn.execute(
-            "CREATE TABLE schema_version (
-                version INTEGER PRIMARY KEY,
-                applied_at INTEGER NOT NULL
-            )",
-            [],
-        )
-        .unwrap();
-
-        // Create v1 schema
-        conn.execute(
-            "CREATE TABLE branches (
-                id INTEGER PRIMARY KEY,
-                repo_path TEXT NOT NULL,
-                branch_name TEXT NOT NULL,
-                switch_count INTEGER DEFAULT 1,
-                last_used INTEGER NOT NULL,
-                UNIQUE(repo_path, branch_name)
-            )",
-            [],
-        )
-        .unwrap();
-
-        conn.execute(
-            "INSERT INTO schema_version (version, applied_at) VALUES (1, 1234567890)",
-            [],
-        )
-        .unwrap();
-
-        // Add some test data
-        conn.execute(
-            "INSERT INTO branches (repo_path, branch_name, switch_count, last_used)
-             VALUES ('/test', 'main', 5, 1234567890)",
-            [],
-        )
-        .unwrap();
-
-        // Run migration to v2
-        initialize_tables(&conn).unwrap();
-
-        // Verify data is preserved
-        let switch_count: i64 = conn
-            .query_row(
-                "SELECT switch_count FROM branches WHERE branch_name = 'main'",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap();
-        assert_eq!(switch_count, 5);
-    }
-
-    #[test]
-    fn test_cleanup_old_records() {
-        let conn = open_test_db().unwrap();
-        let repo_path = unique_repo_path();
-        let now = now_timestamp();
-
-        // Add old and new records
-        do_record_checkout(&conn, &repo_path, "old-branch").unwrap();
-        conn.execute(
-            "UPDATE branches SET last_used = ?1 WHERE branch_name = 'old-branch'",
-            [now - (400 * 86400)], // 400 days old
-        )
-        .unwrap();
-
-        do_record_checkout(&conn, &repo_path, "recent-branch").unwrap();
-
-        // Cleanup records older than 365 days
-        let deleted = conn
-            .execute(
-                "DELETE FROM branches WHERE last_used < ?1",
-                [now - (365 * 86400)],
-            )
-            .unwrap();
-
-        assert_eq!(deleted, 1);
-
-        // Verify old branch was deleted
-        let records = do_get_branch_records(&conn, &repo_path).unwrap();
-        assert_eq!(records.len(), 1);
-        assert_eq!(records[0].branch_name, "recent-branch");
-    }
-
-    #[test]
-    fn test_optimize_database() {
-        let conn = open_test_db().unwrap();
-
-        // Add some data
-        let repo_path = unique_repo_path();
-        do_record_checkout(&conn, &repo_path, "branch1").unwrap();
-        do_record_checkout(&conn, &repo_path, "branch2").unwrap();
-
-        // Run VACUUM and ANALYZE
-        let result = conn.execute("VACUUM", []);
-        assert!(result.is_ok());
-
-        let result = conn.execute("ANALYZE", []);
-        assert!(result.is_ok());
-    }
-
-    #[test]
-    fn test_get_database_size() {
-        // Test that get_db_path works and returns a valid path
-        let result = get_db_path();
-        assert!(result.is_ok());
-
-        let db_path = result.unwrap();
-        // Path should end with data.db
-        assert!(db_path.to_string_lossy().ends_with("data.db"));
-    }
 }
@@ -0,0 +1,240 @@
+//! Encryption for the encrypted backend (`storage.backend = "encrypted"`):
+//! the JSON history sealed with ChaCha20-Poly1305, under a random key kept in
+//! the OS keychain (the macOS Keychain through Security.framework, the Secret
+//! Service through `secret-tool` elsewhere) or given in `GGO_STORAGE_KEY`.
+
+use anyhow::{Context, Result};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+#[cfg(all(unix, not(target_os = "macos")))]
+use std::io::Write;
+#[cfg(all(unix, not(target_os = "macos")))]
+use std::process::{Command, Stdio};
+
+use crate::error::GgoError;
+
+/// Start of every sealed file, also authenticated with the contents
+const MAGIC: &[u8] = b"ggo-enc1";
+
+/// Key length in bytes
+const KEY_LEN: usize = 32;
+
+/// Environment variable that supplies the key instead of the keychain
+const KEY_ENV: &str = "GGO_STORAGE_KEY";
+
+/// Where the key is kept in the keychain
+const KEYCHAIN_SERVICE: &str = "ggo";
+const KEYCHAIN_ACCOUNT: &str = "history-key";
+
+/// Seals and opens the history file
+pub struct Cipher {
+    key: LessSafeKey,
+}
+
+impl Cipher {
+    pub fn new(key: &[u8; KEY_LEN]) -> Self {
+        let key = UnboundKey::new(&CHACHA20_POLY1305, key).expect("key has the right length");
+        Self {
+            key: LessSafeKey::new(key),
+        }
+    }
+
+    /// The key from `GGO_STORAGE_KEY` or the keychain. With `create`, for a
+    /// history that doesn't exist yet, a missing key is generated and stored
+    /// in the keychain.
+    pub fn from_keychain(create: bool) -> Result<Self> {
+        if let Ok(hex) = std::env::var(KEY_ENV) {
+            return Ok(Self::new(
+                &parse_key(&hex).with_context(|| format!("{} is not 64 hex digits", KEY_ENV))?,
+            ));
+        }
+
+        let unavailable = |reason: String| GgoError::EncryptionKeyUnavailable(reason);
+        match keychain_get().map_err(|e| unavailable(format!("{:#}", e)))? {
+            Some(hex) => Ok(Self::new(&parse_key(&hex).map_err(|_| {
+                unavailable("the keychain entry is not a ggo key".to_string())
+            })?)),
+            None if create => {
+                let mut key = [0; KEY_LEN];
+                SystemRandom::new()
+                    .fill(&mut key)
+                    .map_err(|_| anyhow::anyhow!("Failed to generate an encryption key"))?;
+                keychain_set(&format_key(&key)).map_err(|e| unavailable(format!("{:#}", e)))?;
+                tracing::info!("Stored a new history encryption key in the keychain");
+                Ok(Self::new(&key))
+            }
+            None => Err(unavailable("no key in the keychain".to_string()).into()),
+        }
+    }
+
+    /// Encrypt `plain` under a fresh random nonce
+    pub fn seal(&self, plain: &[u8]) -> Result<Vec<u8>> {
+        let mut nonce = [0; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| anyhow::anyhow!("Failed to generate a nonce"))?;
+
+        let mut sealed = plain.to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(MAGIC),
+                &mut sealed,
+            )
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt history"))?;
+
+        Ok([MAGIC, &nonce, &sealed].concat())
+    }
+
+    /// Decrypt what [`Cipher::seal`] wrote, failing if it was made with
+    /// another key or changed since
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>> {
+        let rest = sealed
+            .strip_prefix(MAGIC)
+            .filter(|rest| rest.len() >= NONCE_LEN)
+            .context("not an encrypted ggo history file")?;
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).expect("nonce has the right length");
+
+        let mut plain = ciphertext.to_vec();
+        let len = self
+            .key
+            .open_in_place(nonce, Aad::from(MAGIC), &mut plain)
+            .map_err(|_| anyhow::anyhow!("wrong key, or the file was modified"))?
+            .len();
+        plain.truncate(len);
+        Ok(plain)
+    }
+}
+
+fn parse_key(hex: &str) -> Result<[u8; KEY_LEN]> {
+    let hex = hex.trim();
+    if hex.len() != KEY_LEN * 2 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        anyhow::bail!("expected {} hex digits", KEY_LEN * 2);
+    }
+
+    let mut key = [0; KEY_LEN];
+    for (byte, pair) in key.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair)?, 16)?;
+    }
+    Ok(key)
+}
+
+fn format_key(key: &[u8; KEY_LEN]) -> String {
+    key.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// errSecItemNotFound
+#[cfg(target_os = "macos")]
+const KEYCHAIN_ITEM_NOT_FOUND: i32 = -25300;
+
+#[cfg(target_os = "macos")]
+fn keychain_get() -> Result<Option<String>> {
+    match security_framework::passwords::get_generic_password(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT) {
+        Ok(key) => Ok(Some(String::from_utf8_lossy(&key).trim().to_string())),
+        Err(e) if e.code() == KEYCHAIN_ITEM_NOT_FOUND => Ok(None),
+        Err(e) => Err(e).context("Failed to read the macOS Keychain"),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn keychain_set(key: &str) -> Result<()> {
+    // Through the API rather than 'security add-generic-password -w <key>',
+    // which would show the key to anyone running ps
+    security_framework::passwords::set_generic_password(
+        KEYCHAIN_SERVICE,
+        KEYCHAIN_ACCOUNT,
+        key.as_bytes(),
+    )
+    .context("Failed to store the key in the macOS Keychain")
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn keychain_get() -> Result<Option<String>> {
+    let output = Command::new("secret-tool")
+        .args(["lookup", "service", KEYCHAIN_SERVICE])
+        .args(["account", KEYCHAIN_ACCOUNT])
+        .stderr(Stdio::null())
+        .output()
+        .context("Failed to run 'secret-tool' (libsecret)")?;
+    let key = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok((output.status.success() && !key.is_empty()).then_some(key))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn keychain_set(key: &str) -> Result<()> {
+    // The key goes through stdin, out of sight of other processes
+    let mut child = Command::new("secret-tool")
+        .args([
+            "store",
+            "--label",
+            "ggo history key",
+            "service",
+            KEYCHAIN_SERVICE,
+        ])
+        .args(["account", KEYCHAIN_ACCOUNT])
+        .stdin(Stdio::piped())
+        .spawn()
+        .context("Failed to run 'secret-tool' (libsecret)")?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(key.as_bytes())
+        .context("Failed to pass the key to 'secret-tool'")?;
+    let status = child.wait().context("Failed to run 'secret-tool'")?;
+    if !status.success() {
+        anyhow::bail!("'secret-tool store' exited with {}", status);
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn keychain_get() -> Result<Option<String>> {
+    anyhow::bail!("no supported keychain on this platform")
+}
+
+#[cfg(not(unix))]
+fn keychain_set(_key: &str) -> Result<()> {
+    anyhow::bail!("no supported keychain on this platform")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_open_round_trip() {
+        let cipher = Cipher::new(&[7; KEY_LEN]);
+        let sealed = cipher.seal(b"{\"branches\": []}").unwrap();
+
+        assert!(sealed.starts_with(MAGIC));
+        assert!(!sealed
+            .windows(b"branches".len())
+            .any(|window| window == b"branches"));
+        assert_eq!(cipher.open(&sealed).unwrap(), b"{\"branches\": []}");
+    }
+
+    #[test]
+    fn test_open_rejects_wrong_key_and_tampering() {
+        let sealed = Cipher::new(&[7; KEY_LEN]).seal(b"history").unwrap();
+
+        assert!(Cipher::new(&[8; KEY_LEN]).open(&sealed).is_err());
+
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(Cipher::new(&[7; KEY_LEN]).open(&tampered).is_err());
+
+        assert!(Cipher::new(&[7; KEY_LEN]).open(b"{}").is_err());
+    }
+
+    #[test]
+    fn test_key_hex_round_trip() {
+        let key: [u8; KEY_LEN] = std::array::from_fn(|i| i as u8 * 7);
+        assert_eq!(parse_key(&format_key(&key)).unwrap(), key);
+        assert_eq!(parse_key(&format!("{}\n", format_key(&key))).unwrap(), key);
+
+        assert!(parse_key("abcd").is_err());
+        assert!(parse_key(&"zz".repeat(KEY_LEN)).is_err());
+    }
+}
@@ -0,0 +1,1937 @@
+//! The JSON backend (`storage.backend = "json"`): the whole history in one
+//! plain `history.json`, read when opened and rewritten on every change.
+//! Needs no SQLite, so it suits static musl builds and minimal containers;
+//! every write rewrites the file, which is fine at the size a person's
+//! history grows to. The encrypted backend is this store with a [`Cipher`]
+//! sealing the file.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "encrypted")]
+use super::encrypted::Cipher;
+use super::{
+    get_data_dir, now_timestamp, over_retention, records_to_trim, Alias, BranchRecord,
+    CheckoutEvent, GroupRow, HistoryEntry, HistoryRow, HistoryStore, NoteRow, PinRow, RepoSummary,
+    Snapshot, StackLink, StackRow, StaleRecords, Stats, StatsFilter, StatsSort, StoreOptions,
+    Transition,
+};
+use crate::constants::history::MAX_HISTORY_ENTRIES;
+use crate::export::{
+    ExportedAlias, ExportedBranch, ExportedPreviousBranch, HistoryExport, EXPORT_VERSION,
+};
+use crate::matcher;
+
+/// Version of the file format, bumped on incompatible changes
+const FORMAT_VERSION: u32 = 1;
+
+/// Name of the history file in the data directory
+pub(crate) const FILE_NAME: &str = "history.json";
+
+/// Name of the encrypted backend's history file in the data directory
+#[cfg(feature = "encrypted")]
+pub(crate) const ENCRYPTED_FILE_NAME: &str = "history.json.enc";
+
+/// Everything in `history.json`. Rows keep the order they were added in,
+/// which stands in for SQLite's row ids.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct History {
+    version: u32,
+    branches: Vec<ExportedBranch>,
+    events: Vec<CheckoutEvent>,
+    history: Vec<HistoryRow>,
+    previous_branches: Vec<ExportedPreviousBranch>,
+    aliases: Vec<ExportedAlias>,
+    pins: Vec<PinRow>,
+    notes: Vec<NoteRow>,
+    groups: Vec<GroupRow>,
+    stacks: Vec<StackRow>,
+    branch_cache: Vec<CacheRow>,
+    meta: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheRow {
+    repo_path: String,
+    fingerprint: String,
+    branches: Vec<String>,
+    updated_at: i64,
+}
+
+/// History kept in a JSON file.
+///
+/// Reads are served from the copy parsed when the store was opened. Writes
+/// take a lock file and read the file again first, so concurrent ggo
+/// invocations don't lose each other's changes.
+pub struct JsonStore {
+    path: PathBuf,
+    persist: bool,
+    history: RefCell<History>,
+    options: StoreOptions,
+    /// Set for the encrypted backend: the file holds the sealed JSON
+    #[cfg(feature = "encrypted")]
+    cipher: Option<Cipher>,
+}
+
+impl JsonStore {
+    /// Open `history.json` in the ggo data directory (a missing file is an
+    /// empty history)
+    pub fn open() -> Result<Self> {
+        Self::open_at(get_data_dir()?.join(FILE_NAME))
+    }
+
+    /// Open the history file at `path`
+    pub fn open_at(path: impl Into<PathBuf>) -> Result<Self> {
+        Self {
+            path: path.into(),
+            persist: true,
+            ..Self::open_in_memory()
+        }
+        .loaded()
+    }
+
+    /// Open `history.json.enc` in the ggo data directory, with the key kept
+    /// in the OS keychain (created on first use)
+    #[cfg(feature = "encrypted")]
+    pub fn open_encrypted() -> Result<Self> {
+        let path = get_data_dir()?.join(ENCRYPTED_FILE_NAME);
+        let cipher = Cipher::from_keychain(!path.exists())?;
+        Self::open_encrypted_at(path, cipher)
+    }
+
+    /// Open `history.json.enc` only if it exists, never creating a key.
+    /// None when there is no encrypted history yet.
+    #[cfg(feature = "encrypted")]
+    pub fn open_encrypted_existing() -> Result<Option<Self>> {
+        let path = super::data_dir_location()?.0.join(ENCRYPTED_FILE_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Self::open_encrypted_at(path, Cipher::from_keychain(false)?).map(Some)
+    }
+
+    /// Open the encrypted history file at `path`
+    #[cfg(feature = "encrypted")]
+    pub fn open_encrypted_at(path: impl Into<PathBuf>, cipher: Cipher) -> Result<Self> {
+        Self {
+            path: path.into(),
+            persist: true,
+            cipher: Some(cipher),
+            ..Self::open_in_memory()
+        }
+        .loaded()
+    }
+
+    /// A throwaway in-memory store (nothing is persisted)
+    pub fn open_in_memory() -> Self {
+        Self {
+            path: PathBuf::from(":memory:"),
+            persist: false,
+            history: RefCell::new(History::default()),
+            options: StoreOptions::default(),
+            #[cfg(feature = "encrypted")]
+            cipher: None,
+        }
+    }
+
+    fn loaded(self) -> Result<Self> {
+        *self.history.borrow_mut() = self.load()?;
+        Ok(self)
+    }
+
+    fn read<T>(&self, op: impl FnOnce(&History) -> T) -> T {
+        op(&self.history.borrow())
+    }
+
+    /// Apply a change to the latest version of the file and write it back
+    fn update<T>(&self, op: impl FnOnce(&mut History) -> T) -> Result<T> {
+        let mut history = self.history.borrow_mut();
+        if !self.persist {
+            return Ok(op(&mut history));
+        }
+
+        let _lock = lock(&self.path)?;
+        *history = self.load()?;
+        let result = op(&mut history);
+        history.version = FORMAT_VERSION;
+        self.save(&history)?;
+        Ok(result)
+    }
+
+    fn is_excluded(&self, branch_name: &str) -> bool {
+        matcher::is_excluded(branch_name, &self.options.exclude)
+    }
+
+    /// Read the history file; a missing one is an empty history
+    fn load(&self) -> Result<History> {
+        let path = &self.path;
+        let content = match std::fs::read(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(History::default()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+            }
+        };
+
+        #[cfg(feature = "encrypted")]
+        let content = match &self.cipher {
+            Some(cipher) => cipher
+                .open(&content)
+                .with_context(|| format!("Failed to decrypt {}", path.display()))?,
+            None => content,
+        };
+
+        let history: History = serde_json::from_slice(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        if history.version > FORMAT_VERSION {
+            anyhow::bail!(
+                "{} was written by a newer ggo (format v{}, this ggo knows up to v{})",
+                path.display(),
+                history.version,
+                FORMAT_VERSION
+            );
+        }
+        Ok(history)
+    }
+
+    /// Write the history file through a temporary file, so a crash mid-write
+    /// never leaves it truncated
+    fn save(&self, history: &History) -> Result<()> {
+        let path = &self.path;
+        let content = serde_json::to_vec_pretty(history).context("Failed to serialize history")?;
+
+        #[cfg(feature = "encrypted")]
+        let content = match &self.cipher {
+            Some(cipher) => cipher.seal(&content)?,
+            None => content,
+        };
+
+        let mut staged = path.as_os_str().to_os_string();
+        staged.push(".tmp");
+        std::fs::write(&staged, content)
+            .and_then(|_| std::fs::rename(&staged, path))
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+impl HistoryStore for JsonStore {
+    fn options_mut(&mut self) -> &mut StoreOptions {
+        &mut self.options
+    }
+
+    fn db_path(&self) -> &Path {
+        &self.path
+    }
+
+    fn record_checkout(
+        &self,
+        repo_path: &str,
+        from_branch: Option<&str>,
+        branch_name: &str,
+    ) -> Result<()> {
+        if !self.options.record {
+            tracing::debug!("Recording disabled, not recording: {}", branch_name);
+            return Ok(());
+        }
+
+        if self.is_excluded(branch_name) {
+            tracing::debug!("Not recording excluded branch: {}", branch_name);
+            return Ok(());
+        }
+
+        let now = now_timestamp();
+        self.update(|h| {
+            let record = h.branch_mut(repo_path, branch_name);
+            record.switch_count += 1;
+            record.last_used = now;
+            h.events.push(CheckoutEvent {
+                repo_path: repo_path.to_string(),
+                from_branch: from_branch.map(str::to_string),
+                to_branch: branch_name.to_string(),
+                switched_at: now,
+            });
+        })
+        .context("Failed to record checkout")
+    }
+
+    fn import_checkout_events(&self, events: &[CheckoutEvent]) -> Result<usize> {
+        let events: Vec<&CheckoutEvent> = events
+            .iter()
+            .filter(|event| !self.is_excluded(&event.to_branch))
+            .collect();
+
+        self.update(|h| {
+            // Identical events already stored, each matching one imported copy
+            let mut stored: HashMap<&CheckoutEvent, usize> = HashMap::new();
+            let mut imported = 0;
+            for &event in &events {
+                let remaining = stored
+                    .entry(event)
+                    .or_insert_with(|| h.events.iter().filter(|e| *e == event).count());
+                if *remaining > 0 {
+                    *remaining -= 1;
+                    continue;
+                }
+
+                h.events.push(event.clone());
+                let record = h.branch_mut(&event.repo_path, &event.to_branch);
+                record.switch_count += 1;
+                record.last_used = record.last_used.max(event.switched_at);
+                imported += 1;
+            }
+            imported
+        })
+        .context("Failed to import checkout events")
+    }
+
+    fn checkout_events(&self, repo_path: &str) -> Result<Vec<CheckoutEvent>> {
+        Ok(self.read(|h| {
+            h.events
+                .iter()
+                .rev()
+                .filter(|e| e.repo_path == repo_path)
+                .cloned()
+                .collect()
+        }))
+    }
+
+    fn recent_checkout_events(&self, repo_path: &str, seconds: i64) -> Result<Vec<CheckoutEvent>> {
+        let since = now_timestamp() - seconds;
+        let mut events: Vec<CheckoutEvent> = self
+            .checkout_events(repo_path)?
+            .into_iter()
+            .filter(|e| e.switched_at >= since)
+            .collect();
+        events.sort_by_key(|e| std::cmp::Reverse(e.switched_at));
+        Ok(events)
+    }
+
+    fn last_checkout(&self, repo_path: &str) -> Result<Option<CheckoutEvent>> {
+        Ok(self.read(|h| {
+            h.events
+                .iter()
+                .rev()
+                .find(|e| e.repo_path == repo_path)
+                .cloned()
+        }))
+    }
+
+    fn undo_last_checkout(&self, repo_path: &str) -> Result<Option<CheckoutEvent>> {
+        if !self.options.record {
+            return Ok(None);
+        }
+
+        let event = match self.last_checkout(repo_path)? {
+            Some(event) => event,
+            None => return Ok(None),
+        };
+
+        self.update(|h| {
+            if let Some(last) = h.events.iter().rposition(|e| e.repo_path == repo_path) {
+                h.events.remove(last);
+            }
+
+            let last_switch = h
+                .events
+                .iter()
+                .filter(|e| e.repo_path == repo_path && e.to_branch == event.to_branch)
+                .map(|e| e.switched_at)
+                .max();
+            if let Some(record) = h
+                .branches
+                .iter_mut()
+                .find(|b| b.repo_path == repo_path && b.branch_name == event.to_branch)
+            {
+                record.switch_count -= 1;
+                record.last_used = last_switch.unwrap_or(record.last_used);
+            }
+            h.branches.retain(|b| {
+                !(b.repo_path == repo_path
+                    && b.branch_name == event.to_branch
+                    && b.switch_count <= 0)
+            });
+
+            if let Some(from) = &event.from_branch {
+                if let Some(top) = h.history.iter().rposition(|e| e.repo_path == repo_path) {
+                    if h.history[top].branch_name == *from {
+                        h.history.remove(top);
+                    }
+                }
+            }
+
+            h.previous_branches.retain(|p| p.repo_path != repo_path);
+            if let Some(top) = h.history.iter().rev().find(|e| e.repo_path == repo_path) {
+                h.previous_branches.push(ExportedPreviousBranch {
+                    repo_path: repo_path.to_string(),
+                    branch_name: top.branch_name.clone(),
+                    updated_at: top.switched_at,
+                });
+            }
+        })
+        .context("Failed to undo checkout")?;
+
+        Ok(Some(event))
+    }
+
+    fn transitions_from(&self, repo_path: &str, from_branch: &str) -> Result<Vec<Transition>> {
+        let mut by_branch: BTreeMap<&str, (i64, i64)> = BTreeMap::new();
+        let history = self.history.borrow();
+        for event in &history.events {
+            if event.repo_path == repo_path
+                && event.from_branch.as_deref() == Some(from_branch)
+                && event.to_branch != from_branch
+            {
+                let (count, last) = by_branch.entry(&event.to_branch).or_insert((0, 0));
+                *count += 1;
+                *last = (*last).max(event.switched_at);
+            }
+        }
+
+        let mut transitions: Vec<Transition> = by_branch
+            .into_iter()
+            .map(|(to_branch, (count, last_switched))| Transition {
+                to_branch: to_branch.to_string(),
+                count,
+                last_switched,
+            })
+            .collect();
+        transitions.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then(b.last_switched.cmp(&a.last_switched))
+        });
+        Ok(transitions)
+    }
+
+    fn old_events(
+        &self,
+        retention_days: u32,
+        repo_path: Option<&str>,
+    ) -> Result<(usize, Option<i64>)> {
+        if retention_days == 0 {
+            return Ok((0, None));
+        }
+
+        let cutoff = now_timestamp() - (retention_days as i64 * 86400);
+        Ok(self.read(|h| {
+            let old: Vec<i64> = h
+                .events
+                .iter()
+                .filter(|e| e.switched_at < cutoff && in_repo(&e.repo_path, repo_path))
+                .map(|e| e.switched_at)
+                .collect();
+            (old.len(), old.into_iter().min())
+        }))
+    }
+
+    fn compact_events(&self, retention_days: u32, repo_path: Option<&str>) -> Result<usize> {
+        if retention_days == 0 {
+            return Ok(0);
+        }
+
+        let cutoff = now_timestamp() - (retention_days as i64 * 86400);
+        self.update(|h| {
+            let before = h.events.len();
+            h.events
+                .retain(|e| !(e.switched_at < cutoff && in_repo(&e.repo_path, repo_path)));
+            before - h.events.len()
+        })
+        .context("Failed to compact checkout events")
+    }
+
+    fn branch_records(&self, repo_path: &str) -> Result<Vec<BranchRecord>> {
+        Ok(self.records(|b| b.repo_path == repo_path))
+    }
+
+    fn all_records(&self) -> Result<Vec<BranchRecord>> {
+        Ok(self.records(|_| true))
+    }
+
+    fn stats(&self, filter: &StatsFilter) -> Result<Stats> {
+        let (total_switches, unique_branches, repos) = self.read(|h| {
+            let mut repos = BTreeSet::new();
+            let (mut switches, mut branches) = (0, 0);
+            for b in h.branches.iter().filter(|b| matches_filter(b, filter)) {
+                switches += b.switch_count;
+                branches += 1;
+                repos.insert(b.repo_path.clone());
+            }
+            (switches, branches, repos.len() as i64)
+        });
+
+        Ok(Stats {
+            total_switches,
+            unique_branches,
+            unique_repos: repos,
+            db_path: self.path.clone(),
+        })
+    }
+
+    fn top_records(
+        &self,
+        filter: &StatsFilter,
+        sort: StatsSort,
+        limit: usize,
+    ) -> Result<Vec<BranchRecord>> {
+        let mut records = self.read(|h| {
+            h.branches
+                .iter()
+                .filter(|b| matches_filter(b, filter))
+                .map(branch_record)
+                .collect::<Vec<_>>()
+        });
+
+        let limit = match sort {
+            StatsSort::Switches => {
+                records.sort_by(|a, b| {
+                    b.switch_count
+                        .cmp(&a.switch_count)
+                        .then(b.last_used.cmp(&a.last_used))
+                });
+                limit
+            }
+            StatsSort::Recent => {
+                records.sort_by_key(|r| std::cmp::Reverse(r.last_used));
+                limit
+            }
+            StatsSort::Score => {
+                records.sort_by_key(|r| std::cmp::Reverse(r.last_used));
+                usize::MAX
+            }
+        };
+
+        // Excluded branches are skipped before the limit, as with SQLite
+        Ok(records
+            .into_iter()
+            .filter(|r| !self.is_excluded(&r.branch_name))
+            .take(limit)
+            .collect())
+    }
+
+    fn repo_summaries(&self, filter: &StatsFilter) -> Result<Vec<RepoSummary>> {
+        let mut summaries: Vec<RepoSummary> = self.read(|h| {
+            let mut by_repo: BTreeMap<&str, (i64, i64, i64)> = BTreeMap::new();
+            for b in h.branches.iter().filter(|b| matches_filter(b, filter)) {
+                let (branches, switches, last_used) =
+                    by_repo.entry(&b.repo_path).or_insert((0, 0, 0));
+                *branches += 1;
+                *switches += b.switch_count;
+                *last_used = (*last_used).max(b.last_used);
+            }
+            by_repo
+                .into_iter()
+                .map(|(repo_path, (branches, switches, last_used))| RepoSummary {
+                    repo_path: repo_path.to_string(),
+                    branches,
+                    switches,
+                    last_used,
+                })
+                .collect()
+        });
+        summaries.sort_by(|a, b| {
+            b.switches
+                .cmp(&a.switches)
+                .then_with(|| a.repo_path.cmp(&b.repo_path))
+        });
+        Ok(summaries)
+    }
+
+    fn switch_heatmap(&self, filter: &StatsFilter) -> Result<[[i64; 24]; 7]> {
+        let cutoff = filter.cutoff();
+        let tz = jiff::tz::TimeZone::system();
+        let mut heatmap = [[0; 24]; 7];
+
+        self.read(|h| {
+            for event in &h.events {
+                if !in_repo(&event.repo_path, filter.repo_path.as_deref())
+                    || cutoff.is_some_and(|cutoff| event.switched_at < cutoff)
+                {
+                    continue;
+                }
+                let Ok(at) = jiff::Timestamp::from_second(event.switched_at) else {
+                    continue;
+                };
+                let local = at.to_zoned(tz.clone());
+                let weekday = local.weekday().to_sunday_zero_offset() as usize;
+                heatmap[weekday][local.hour() as usize] += 1;
+            }
+        });
+
+        Ok(heatmap)
+    }
+
+    fn save_previous_branch(&self, repo_path: &str, branch_name: &str) -> Result<()> {
+        if !self.options.record {
+            return Ok(());
+        }
+
+        let now = now_timestamp();
+        self.update(|h| h.set_previous(repo_path, branch_name, now))
+            .context("Failed to save previous branch")
+    }
+
+    fn previous_branch(&self, repo_path: &str) -> Result<Option<String>> {
+        Ok(self.read(|h| {
+            h.previous_branches
+                .iter()
+                .find(|p| p.repo_path == repo_path)
+                .map(|p| p.branch_name.clone())
+        }))
+    }
+
+    fn push_checkout_history(&self, repo_path: &str, branch_name: &str) -> Result<()> {
+        if !self.options.record {
+            return Ok(());
+        }
+
+        let now = now_timestamp();
+        self.update(|h| {
+            h.history.push(HistoryRow {
+                repo_path: repo_path.to_string(),
+                branch_name: branch_name.to_string(),
+                switched_at: now,
+            });
+
+            let entries = h
+                .history
+                .iter()
+                .filter(|e| e.repo_path == repo_path)
+                .count();
+            let mut excess = entries.saturating_sub(MAX_HISTORY_ENTRIES);
+            h.history.retain(|e| {
+                if excess > 0 && e.repo_path == repo_path {
+                    excess -= 1;
+                    return false;
+                }
+                true
+            });
+        })
+        .context("Failed to record checkout history")
+    }
+
+    fn checkout_history(&self, repo_path: &str) -> Result<Vec<HistoryEntry>> {
+        Ok(self.read(|h| {
+            let rows: Vec<&HistoryRow> = h
+                .history
+                .iter()
+                .filter(|e| e.repo_path == repo_path)
+                .collect();
+
+            let mut seen = BTreeSet::new();
+            rows.iter()
+                .rev()
+                .filter(|row| seen.insert(row.branch_name.as_str()))
+                .map(|row| HistoryEntry {
+                    branch_name: row.branch_name.clone(),
+                    switched_at: rows
+                        .iter()
+                        .filter(|other| other.branch_name == row.branch_name)
+                        .map(|other| other.switched_at)
+                        .max()
+                        .unwrap_or(row.switched_at),
+                })
+                .collect()
+        }))
+    }
+
+    fn create_alias(&self, repo_path: &str, alias: &str, branch_name: &str) -> Result<()> {
+        let now = now_timestamp();
+        self.update(|h| {
+            h.aliases
+                .retain(|a| !(a.repo_path == repo_path && a.alias == alias));
+            h.aliases.push(ExportedAlias {
+                repo_path: repo_path.to_string(),
+                alias: alias.to_string(),
+                branch_name: branch_name.to_string(),
+                created_at: now,
+            });
+        })
+        .context("Failed to create alias")
+    }
+
+    fn alias(&self, repo_path: &str, alias: &str) -> Result<Option<String>> {
+        Ok(self.read(|h| {
+            h.aliases
+                .iter()
+                .find(|a| a.repo_path == repo_path && a.alias == alias)
+                .map(|a| a.branch_name.clone())
+        }))
+    }
+
+    fn delete_alias(&self, repo_path: &str, alias: &str) -> Result<()> {
+        self.update(|h| {
+            h.aliases
+                .retain(|a| !(a.repo_path == repo_path && a.alias == alias))
+        })
+        .context("Failed to delete alias")
+    }
+
+    fn rename_alias(&self, repo_path: &str, old: &str, new: &str) -> Result<bool> {
+        self.update(|h| {
+            if old != new
+                && h.aliases
+                    .iter()
+                    .any(|a| a.repo_path == repo_path && a.alias == new)
+            {
+                anyhow::bail!("alias '{}' already exists", new);
+            }
+            Ok(
+                match h
+                    .aliases
+                    .iter_mut()
+                    .find(|a| a.repo_path == repo_path && a.alias == old)
+                {
+                    Some(alias) => {
+                        alias.alias = new.to_string();
+                        true
+                    }
+                    None => false,
+                },
+            )
+        })?
+        .context("Failed to rename alias")
+    }
+
+    fn aliases(&self, repo_path: &str) -> Result<Vec<Alias>> {
+        Ok(self.all_aliases()?.remove(repo_path).unwrap_or_default())
+    }
+
+    fn all_aliases(&self) -> Result<BTreeMap<String, Vec<Alias>>> {
+        let mut by_repo: BTreeMap<String, Vec<Alias>> = BTreeMap::new();
+        self.read(|h| {
+            for a in &h.aliases {
+                by_repo.entry(a.repo_path.clone()).or_default().push(Alias {
+                    repo_path: a.repo_path.clone(),
+                    alias: a.alias.clone(),
+                    branch_name: a.branch_name.clone(),
+                    created_at: a.created_at,
+                });
+            }
+        });
+        for aliases in by_repo.values_mut() {
+            aliases.sort_by(|a, b| a.alias.cmp(&b.alias));
+        }
+        Ok(by_repo)
+    }
+
+    fn aliases_for_branch(&self, repo_path: &str, branch_name: &str) -> Result<Vec<String>> {
+        let mut aliases: Vec<String> = self.read(|h| {
+            h.aliases
+                .iter()
+                .filter(|a| a.repo_path == repo_path && a.branch_name == branch_name)
+                .map(|a| a.alias.clone())
+                .collect()
+        });
+        aliases.sort();
+        Ok(aliases)
+    }
+
+    fn pin_branch(&self, repo_path: &str, branch_name: &str) -> Result<()> {
+        let now = now_timestamp();
+        self.update(|h| {
+            if !h
+                .pins
+                .iter()
+                .any(|p| p.repo_path == repo_path && p.branch_name == branch_name)
+            {
+                h.pins.push(PinRow {
+                    repo_path: repo_path.to_string(),
+                    branch_name: branch_name.to_string(),
+                    pinned_at: now,
+                });
+            }
+        })
+        .context("Failed to pin branch")
+    }
+
+    fn unpin_branch(&self, repo_path: &str, branch_name: &str) -> Result<bool> {
+        self.update(|h| {
+            remove_where(&mut h.pins, |p| {
+                p.repo_path == repo_path && p.branch_name == branch_name
+            }) > 0
+        })
+        .context("Failed to unpin branch")
+    }
+
+    fn pinned_branches(&self, repo_path: &str) -> Result<Vec<String>> {
+        let mut pins: Vec<PinRow> = self.read(|h| {
+            h.pins
+                .iter()
+                .filter(|p| p.repo_path == repo_path)
+                .cloned()
+                .collect()
+        });
+        pins.sort_by_key(|p| p.pinned_at);
+        Ok(pins.into_iter().map(|p| p.branch_name).collect())
+    }
+
+    fn set_branch_note(&self, repo_path: &str, branch_name: &str, note: &str) -> Result<()> {
+        let now = now_timestamp();
+        self.update(|h| {
+            match h
+                .notes
+                .iter_mut()
+                .find(|n| n.repo_path == repo_path && n.branch_name == branch_name)
+            {
+                Some(existing) => {
+                    existing.note = note.to_string();
+                    existing.updated_at = now;
+                }
+                None => h.notes.push(NoteRow {
+                    repo_path: repo_path.to_string(),
+                    branch_name: branch_name.to_string(),
+                    note: note.to_string(),
+                    updated_at: now,
+                }),
+            }
+        })
+        .context("Failed to save branch note")
+    }
+
+    fn remove_branch_note(&self, repo_path: &str, branch_name: &str) -> Result<bool> {
+        self.update(|h| {
+            remove_where(&mut h.notes, |n| {
+                n.repo_path == repo_path && n.branch_name == branch_name
+            }) > 0
+        })
+        .context("Failed to remove branch note")
+    }
+
+    fn branch_notes(&self, repo_path: &str) -> Result<HashMap<String, String>> {
+        Ok(self.read(|h| {
+            h.notes
+                .iter()
+                .filter(|n| n.repo_path == repo_path)
+                .map(|n| (n.branch_name.clone(), n.note.clone()))
+                .collect()
+        }))
+    }
+
+    fn add_to_group(
+        &self,
+        repo_path: &str,
+        group_name: &str,
+        branch_names: &[&str],
+    ) -> Result<usize> {
+        let now = now_timestamp();
+        self.update(|h| {
+            let mut added = 0;
+            for branch_name in branch_names {
+                if !h.groups.iter().any(|g| {
+                    g.repo_path == repo_path
+                        && g.group_name == group_name
+                        && g.branch_name == *branch_name
+                }) {
+                    h.groups.push(GroupRow {
+                        repo_path: repo_path.to_string(),
+                        group_name: group_name.to_string(),
+                        branch_name: branch_name.to_string(),
+                        added_at: now,
+                    });
+                    added += 1;
+                }
+            }
+            added
+        })
+        .context("Failed to add to group")
+    }
+
+    fn remove_from_group(
+        &self,
+        repo_path: &str,
+        group_name: &str,
+        branch_names: &[&str],
+    ) -> Result<usize> {
+        self.update(|h| {
+            remove_where(&mut h.groups, |g| {
+                g.repo_path == repo_path
+                    && g.group_name == group_name
+                    && (branch_names.is_empty() || branch_names.contains(&g.branch_name.as_str()))
+            })
+        })
+        .context("Failed to remove from group")
+    }
+
+    fn branch_groups(&self, repo_path: &str) -> Result<BTreeMap<String, Vec<String>>> {
+        let mut rows: Vec<GroupRow> = self.read(|h| {
+            h.groups
+                .iter()
+                .filter(|g| g.repo_path == repo_path)
+                .cloned()
+                .collect()
+        });
+        rows.sort_by_key(|g| g.added_at);
+
+        let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for row in rows {
+            groups
+                .entry(row.group_name)
+                .or_default()
+                .push(row.branch_name);
+        }
+        Ok(groups)
+    }
+
+    fn cached_branches(&self, repo_path: &str, fingerprint: &str) -> Result<Option<Vec<String>>> {
+        Ok(self.read(|h| {
+            h.branch_cache
+                .iter()
+                .find(|c| c.repo_path == repo_path && c.fingerprint == fingerprint)
+                .map(|c| c.branches.clone())
+        }))
+    }
+
+    fn cache_branches(
+        &self,
+        repo_path: &str,
+        fingerprint: &str,
+        branches: &[String],
+    ) -> Result<()> {
+        let now = now_timestamp();
+        self.update(|h| {
+            h.branch_cache.retain(|c| c.repo_path != repo_path);
+            h.branch_cache.push(CacheRow {
+                repo_path: repo_path.to_string(),
+                fingerprint: fingerprint.to_string(),
+                branches: branches.to_vec(),
+                updated_at: now,
+            });
+        })
+        .context("Failed to cache branch list")
+    }
+
+    fn set_stack_parent(&self, repo_path: &str, branch_name: &str, parent: &str) -> Result<()> {
+        let now = now_timestamp();
+        self.update(|h| {
+            h.stacks
+                .retain(|s| !(s.repo_path == repo_path && s.branch_name == branch_name));
+            h.stacks.push(StackRow {
+                repo_path: repo_path.to_string(),
+                branch_name: branch_name.to_string(),
+                parent_branch: parent.to_string(),
+                created_at: now,
+            });
+        })
+        .context("Failed to save stack parent")
+    }
+
+    fn stack_links(&self, repo_path: &str) -> Result<Vec<StackLink>> {
+        let mut links: Vec<StackLink> = self.read(|h| {
+            h.stacks
+                .iter()
+                .filter(|s| s.repo_path == repo_path)
+                .map(|s| StackLink {
+                    branch: s.branch_name.clone(),
+                    parent: s.parent_branch.clone(),
+                })
+                .collect()
+        });
+        links.sort_by(|a, b| a.branch.cmp(&b.branch));
+        Ok(links)
+    }
+
+    fn unstack_branch(&self, repo_path: &str, branch_name: &str) -> Result<bool> {
+        self.update(|h| h.splice_out_of_stack(repo_path, branch_name) > 0)
+            .context("Failed to update stacks")
+    }
+
+    fn rename_stack_branch(&self, repo_path: &str, old: &str, new: &str) -> Result<()> {
+        self.update(|h| {
+            h.rename_in_stacks(repo_path, old, new);
+        })
+        .context("Failed to rename branch in stacks")
+    }
+
+    fn rename_branch_data(&self, repo_path: &str, old: &str, new: &str) -> Result<bool> {
+        self.update(|h| h.move_branch_rows(repo_path, old, new) > 0)
+            .context("Failed to move branch history")
+    }
+
+    fn merge_repo(&self, from: &str, to: &str) -> Result<usize> {
+        self.update(|h| h.merge_repo_rows(from, to))
+            .context("Failed to merge repository history")
+    }
+
+    fn export_history(&self) -> Result<HistoryExport> {
+        Ok(self.read(|h| {
+            let mut branches = h.branches.clone();
+            branches.sort_by(|a, b| {
+                (&a.repo_path, &a.branch_name).cmp(&(&b.repo_path, &b.branch_name))
+            });
+            let mut aliases = h.aliases.clone();
+            aliases.sort_by(|a, b| (&a.repo_path, &a.alias).cmp(&(&b.repo_path, &b.alias)));
+            let mut previous_branches = h.previous_branches.clone();
+            previous_branches.sort_by(|a, b| a.repo_path.cmp(&b.repo_path));
+
+            HistoryExport {
+                version: EXPORT_VERSION,
+                branches,
+                aliases,
+                previous_branches,
+            }
+        }))
+    }
+
+    fn import_history(&self, export: &HistoryExport, merge: bool) -> Result<usize> {
+        self.update(|h| h.import_rows(export, merge))
+            .context("Failed to import history")?;
+
+        Ok(export.len())
+    }
+
+    fn import_synced(
+        &self,
+        delta: &HistoryExport,
+        baseline_key: &str,
+        baseline: &str,
+    ) -> Result<()> {
+        self.update(|h| {
+            h.import_rows(delta, true);
+            h.branches.retain(|b| b.switch_count > 0);
+            h.meta
+                .insert(baseline_key.to_string(), baseline.to_string());
+        })
+        .context("Failed to merge synced history")
+    }
+
+    fn snapshot(&self) -> Result<Snapshot> {
+        Ok(self.read(|h| Snapshot {
+            branches: h.branches.clone(),
+            events: h.events.clone(),
+            history: h.history.clone(),
+            previous_branches: h.previous_branches.clone(),
+            aliases: h.aliases.clone(),
+            pins: h.pins.clone(),
+            notes: h.notes.clone(),
+            groups: h.groups.clone(),
+            stacks: h.stacks.clone(),
+            meta: h.meta.clone(),
+        }))
+    }
+
+    fn restore_snapshot(&self, snapshot: &Snapshot) -> Result<()> {
+        let snapshot = snapshot.clone();
+        self.update(|h| {
+            *h = History {
+                version: FORMAT_VERSION,
+                branches: snapshot.branches,
+                events: snapshot.events,
+                history: snapshot.history,
+                previous_branches: snapshot.previous_branches,
+                aliases: snapshot.aliases,
+                pins: snapshot.pins,
+                notes: snapshot.notes,
+                groups: snapshot.groups,
+                stacks: snapshot.stacks,
+                branch_cache: Vec::new(),
+                meta: snapshot.meta,
+            }
+        })
+        .context("Failed to replace history")
+    }
+
+    fn forget_branches(&self, repo_path: &str, branch_names: &[&str]) -> Result<()> {
+        self.update(|h| {
+            for branch_name in branch_names {
+                h.remove_branch_rows(repo_path, branch_name);
+            }
+        })
+        .context("Failed to remove branch data")
+    }
+
+    fn recorded_branches(&self, repo_path: &str) -> Result<Vec<String>> {
+        Ok(self.read(|h| {
+            let repo = |r: &str| r == repo_path;
+            let mut names = BTreeSet::new();
+            names.extend(
+                h.branches
+                    .iter()
+                    .filter(|b| repo(&b.repo_path))
+                    .map(|b| &b.branch_name),
+            );
+            names.extend(
+                h.aliases
+                    .iter()
+                    .filter(|a| repo(&a.repo_path))
+                    .map(|a| &a.branch_name),
+            );
+            names.extend(
+                h.pins
+                    .iter()
+                    .filter(|p| repo(&p.repo_path))
+                    .map(|p| &p.branch_name),
+            );
+            names.extend(
+                h.notes
+                    .iter()
+                    .filter(|n| repo(&n.repo_path))
+                    .map(|n| &n.branch_name),
+            );
+            names.extend(
+                h.groups
+                    .iter()
+                    .filter(|g| repo(&g.repo_path))
+                    .map(|g| &g.branch_name),
+            );
+            names.extend(
+                h.previous_branches
+                    .iter()
+                    .filter(|p| repo(&p.repo_path))
+                    .map(|p| &p.branch_name),
+            );
+            names.extend(
+                h.history
+                    .iter()
+                    .filter(|e| repo(&e.repo_path))
+                    .map(|e| &e.branch_name),
+            );
+            names.extend(
+                h.events
+                    .iter()
+                    .filter(|e| repo(&e.repo_path))
+                    .map(|e| &e.to_branch),
+            );
+            names.into_iter().cloned().collect()
+        }))
+    }
+
+    fn repo_row_count(&self, repo_path: &str) -> Result<usize> {
+        Ok(self.read(|h| h.count_repo_rows(repo_path)))
+    }
+
+    fn forget_repo(&self, repo_path: &str) -> Result<usize> {
+        self.update(|h| {
+            let removed = h.count_repo_rows(repo_path);
+            h.retain_repos(|repo| repo != repo_path);
+            h.branch_cache.retain(|c| c.repo_path != repo_path);
+            removed
+        })
+        .context("Failed to forget repository")
+    }
+
+    fn old_records(&self, max_age_days: i64, repo_path: Option<&str>) -> Result<Vec<BranchRecord>> {
+        let cutoff = now_timestamp() - (max_age_days * 86400);
+        let mut records: Vec<BranchRecord> = self.read(|h| {
+            h.branches
+                .iter()
+                .filter(|b| b.last_used < cutoff && in_repo(&b.repo_path, repo_path))
+                .map(branch_record)
+                .collect()
+        });
+        records.sort_by_key(|r| r.last_used);
+        Ok(records)
+    }
+
+    fn cleanup_old_records(&self, max_age_days: i64, repo_path: Option<&str>) -> Result<usize> {
+        let cutoff = now_timestamp() - (max_age_days * 86400);
+        self.update(|h| {
+            remove_where(&mut h.branches, |b| {
+                b.last_used < cutoff && in_repo(&b.repo_path, repo_path)
+            })
+        })
+        .context("Failed to cleanup old branch records")
+    }
+
+    fn trim_records(
+        &self,
+        max_records: usize,
+        max_age_days: i64,
+        score: &dyn Fn(&BranchRecord) -> f64,
+    ) -> Result<usize> {
+        let over = self.read(|h| {
+            let oldest = h.branches.iter().map(|b| b.last_used).min();
+            over_retention(h.branches.len(), oldest, max_records, max_age_days)
+        });
+        if !over {
+            return Ok(0);
+        }
+
+        self.update(|h| {
+            let records = h.branches.iter().map(branch_record).collect();
+            let trimmed = records_to_trim(records, max_records, max_age_days, score);
+            let trimmed: BTreeSet<(&str, &str)> = trimmed
+                .iter()
+                .map(|r| (r.repo_path.as_str(), r.branch_name.as_str()))
+                .collect();
+            remove_where(&mut h.branches, |b| {
+                trimmed.contains(&(b.repo_path.as_str(), b.branch_name.as_str()))
+            })
+        })
+        .context("Failed to trim branch records")
+    }
+
+    fn cleanup_deleted_branches(&self, repo_path: Option<&str>) -> Result<Vec<StaleRecords>> {
+        let stale = self.deleted_branch_records(repo_path)?;
+        if stale.is_empty() {
+            return Ok(stale);
+        }
+
+        self.update(|h| {
+            for repo in &stale {
+                for (old, new) in &repo.renamed {
+                    h.move_branch_rows(&repo.repo_path, old, new);
+                }
+                if repo.repo_gone {
+                    h.aliases.retain(|a| a.repo_path != repo.repo_path);
+                }
+                for record in &repo.records {
+                    let is_record = |repo_path: &str, branch_name: &str| {
+                        repo_path == record.repo_path && branch_name == record.branch_name
+                    };
+                    h.branches
+                        .retain(|b| !is_record(&b.repo_path, &b.branch_name));
+                    h.aliases
+                        .retain(|a| !is_record(&a.repo_path, &a.branch_name));
+                }
+            }
+        })
+        .context("Failed to clean up deleted branches")?;
+
+        Ok(stale)
+    }
+
+    fn maintenance_due(&self, every: u32) -> Result<bool> {
+        if every == 0 {
+            return Ok(false);
+        }
+
+        let invocations = self
+            .update(|h| {
+                let count = h
+                    .meta
+                    .get("invocations")
+                    .and_then(|value| value.parse::<i64>().ok())
+                    .unwrap_or(0)
+                    + 1;
+                h.meta.insert("invocations".to_string(), count.to_string());
+                count
+            })
+            .context("Failed to count invocation")?;
+
+        Ok(invocations % every as i64 == 0)
+    }
+
+    fn meta(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.read(|h| h.meta.get(key).cloned()))
+    }
+
+    fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+        self.update(|h| {
+            h.meta.insert(key.to_string(), value.to_string());
+        })
+        .context("Failed to write metadata")
+    }
+
+    fn meta_with_prefix(&self, prefix: &str) -> Result<Vec<(String, String)>> {
+        Ok(self.read(|h| {
+            h.meta
+                .iter()
+                .filter(|(key, _)| key.starts_with(prefix))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect()
+        }))
+    }
+
+    fn quick_optimize(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn optimize(&self) -> Result<()> {
+        // Rewriting drops nothing, but settles a file edited by hand
+        self.update(|_| ())
+            .context("Failed to rewrite history file")
+    }
+
+    fn database_size(&self) -> Result<u64> {
+        let metadata =
+            std::fs::metadata(&self.path).context("Failed to get history file metadata")?;
+        Ok(metadata.len())
+    }
+}
+
+impl JsonStore {
+    /// Usage records `keep` selects (excluded branches never), most recently
+    /// used first
+    fn records(&self, keep: impl Fn(&ExportedBranch) -> bool) -> Vec<BranchRecord> {
+        let mut records: Vec<BranchRecord> = self.read(|h| {
+            h.branches
+                .iter()
+                .filter(|b| keep(b) && !self.is_excluded(&b.branch_name))
+                .map(branch_record)
+                .collect()
+        });
+        records.sort_by_key(|r| std::cmp::Reverse(r.last_used));
+        records
+    }
+}
+
+impl History {
+    /// The usage record of a branch, created without switches if missing
+    fn branch_mut(&mut self, repo_path: &str, branch_name: &str) -> &mut ExportedBranch {
+        let index = match self
+            .branches
+            .iter()
+            .position(|b| b.repo_path == repo_path && b.branch_name == branch_name)
+        {
+            Some(index) => index,
+            None => {
+                self.branches.push(ExportedBranch {
+                    repo_path: repo_path.to_string(),
+                    branch_name: branch_name.to_string(),
+                    switch_count: 0,
+                    last_used: 0,
+                });
+                self.branches.len() - 1
+            }
+        };
+        &mut self.branches[index]
+    }
+
+    fn set_previous(&mut self, repo_path: &str, branch_name: &str, updated_at: i64) {
+        self.previous_branches.retain(|p| p.repo_path != repo_path);
+        self.previous_branches.push(ExportedPreviousBranch {
+            repo_path: repo_path.to_string(),
+            branch_name: branch_name.to_string(),
+            updated_at,
+        });
+    }
+
+    /// Rows stored under a repository key, the branch cache aside
+    fn count_repo_rows(&self, repo_path: &str) -> usize {
+        let repo = |r: &String| r == repo_path;
+        self.branches.iter().filter(|b| repo(&b.repo_path)).count()
+            + self.aliases.iter().filter(|a| repo(&a.repo_path)).count()
+            + self
+                .previous_branches
+                .iter()
+                .filter(|p| repo(&p.repo_path))
+                .count()
+            + self.history.iter().filter(|e| repo(&e.repo_path)).count()
+            + self.events.iter().filter(|e| repo(&e.repo_path)).count()
+            + self.pins.iter().filter(|p| repo(&p.repo_path)).count()
+            + self.stacks.iter().filter(|s| repo(&s.repo_path)).count()
+            + self.notes.iter().filter(|n| repo(&n.repo_path)).count()
+            + self.groups.iter().filter(|g| repo(&g.repo_path)).count()
+    }
+
+    /// Keep only the rows of repositories `keep` selects, the branch cache
+    /// aside
+    fn retain_repos(&mut self, keep: impl Fn(&str) -> bool) {
+        self.branches.retain(|b| keep(&b.repo_path));
+        self.aliases.retain(|a| keep(&a.repo_path));
+        self.previous_branches.retain(|p| keep(&p.repo_path));
+        self.history.retain(|e| keep(&e.repo_path));
+        self.events.retain(|e| keep(&e.repo_path));
+        self.pins.retain(|p| keep(&p.repo_path));
+        self.stacks.retain(|s| keep(&s.repo_path));
+        self.notes.retain(|n| keep(&n.repo_path));
+        self.groups.retain(|g| keep(&g.repo_path));
+    }
+
+    /// Move every row stored under `from` to `to`. Usage counts are added
+    /// up; on conflicting aliases, pins, stacks, notes and groups the ones
+    /// already under `to` win, and the newer previous branch wins. Returns
+    /// the number of rows moved.
+    fn merge_repo_rows(&mut self, from: &str, to: &str) -> usize {
+        if from == to {
+            return 0;
+        }
+
+        let moved = self.count_repo_rows(from);
+        if moved == 0 {
+            return 0;
+        }
+
+        for record in remove_rows(&mut self.branches, |b| b.repo_path == from) {
+            let merged = self.branch_mut(to, &record.branch_name);
+            merged.switch_count += record.switch_count;
+            merged.last_used = merged.last_used.max(record.last_used);
+        }
+        move_rows(
+            &mut self.aliases,
+            |a| a.repo_path == from,
+            |a| a.repo_path = to.to_string(),
+            |a, b| a.repo_path == b.repo_path && a.alias == b.alias,
+        );
+        move_rows(
+            &mut self.pins,
+            |p| p.repo_path == from,
+            |p| p.repo_path = to.to_string(),
+            |a, b| a.repo_path == b.repo_path && a.branch_name == b.branch_name,
+        );
+        move_rows(
+            &mut self.stacks,
+            |s| s.repo_path == from,
+            |s| s.repo_path = to.to_string(),
+            |a, b| a.repo_path == b.repo_path && a.branch_name == b.branch_name,
+        );
+        move_rows(
+            &mut self.notes,
+            |n| n.repo_path == from,
+            |n| n.repo_path = to.to_string(),
+            |a, b| a.repo_path == b.repo_path && a.branch_name == b.branch_name,
+        );
+        move_rows(
+            &mut self.groups,
+            |g| g.repo_path == from,
+            |g| g.repo_path = to.to_string(),
+            |a, b| {
+                a.repo_path == b.repo_path
+                    && a.group_name == b.group_name
+                    && a.branch_name == b.branch_name
+            },
+        );
+
+        for previous in remove_rows(&mut self.previous_branches, |p| p.repo_path == from) {
+            let newer = self
+                .previous_branches
+                .iter()
+                .find(|p| p.repo_path == to)
+                .is_none_or(|current| previous.updated_at > current.updated_at);
+            if newer {
+                self.set_previous(to, &previous.branch_name, previous.updated_at);
+            }
+        }
+
+        for entry in self.history.iter_mut().filter(|e| e.repo_path == from) {
+            entry.repo_path = to.to_string();
+        }
+        for event in self.events.iter_mut().filter(|e| e.repo_path == from) {
+            event.repo_path = to.to_string();
+        }
+        self.branch_cache.retain(|c| c.repo_path != from);
+
+        moved
+    }
+
+    /// Move every row about branch `old` to `new`: usage counts add up,
+    /// and pins, notes and group memberships `new` already has win. Returns
+    /// the number of rows changed.
+    fn move_branch_rows(&mut self, repo_path: &str, old: &str, new: &str) -> usize {
+        if old == new {
+            return 0;
+        }
+
+        let is_old = |r: &str, b: &str| r == repo_path && b == old;
+        let mut moved = 0;
+
+        for record in remove_rows(&mut self.branches, |b| is_old(&b.repo_path, &b.branch_name)) {
+            let merged = self.branch_mut(repo_path, new);
+            merged.switch_count += record.switch_count;
+            merged.last_used = merged.last_used.max(record.last_used);
+            moved += 1;
+        }
+        moved += move_rows(
+            &mut self.pins,
+            |p| is_old(&p.repo_path, &p.branch_name),
+            |p| p.branch_name = new.to_string(),
+            |a, b| a.repo_path == b.repo_path && a.branch_name == b.branch_name,
+        );
+        moved += move_rows(
+            &mut self.notes,
+            |n| is_old(&n.repo_path, &n.branch_name),
+            |n| n.branch_name = new.to_string(),
+            |a, b| a.repo_path == b.repo_path && a.branch_name == b.branch_name,
+        );
+        moved += move_rows(
+            &mut self.groups,
+            |g| is_old(&g.repo_path, &g.branch_name),
+            |g| g.branch_name = new.to_string(),
+            |a, b| {
+                a.repo_path == b.repo_path
+                    && a.group_name == b.group_name
+                    && a.branch_name == b.branch_name
+            },
+        );
+
+        for alias in self.aliases.iter_mut() {
+            if is_old(&alias.repo_path, &alias.branch_name) {
+                alias.branch_name = new.to_string();
+                moved += 1;
+            }
+        }
+        for previous in self.previous_branches.iter_mut() {
+            if is_old(&previous.repo_path, &previous.branch_name) {
+                previous.branch_name = new.to_string();
+                moved += 1;
+            }
+        }
+        for entry in self.history.iter_mut() {
+            if is_old(&entry.repo_path, &entry.branch_name) {
+                entry.branch_name = new.to_string();
+                moved += 1;
+            }
+        }
+        for event in self.events.iter_mut().filter(|e| e.repo_path == repo_path) {
+            if event.to_branch == old {
+                event.to_branch = new.to_string();
+                moved += 1;
+            }
+            if event.from_branch.as_deref() == Some(old) {
+                event.from_branch = Some(new.to_string());
+                moved += 1;
+            }
+        }
+
+        moved + self.rename_in_stacks(repo_path, old, new)
+    }
+
+    /// Rename a branch in the stacks, as the stacked branch and as a parent.
+    /// A link `new` already had gives way to the renamed one.
+    fn rename_in_stacks(&mut self, repo_path: &str, old: &str, new: &str) -> usize {
+        let mut changed = 0;
+        if self
+            .stacks
+            .iter()
+            .any(|s| s.repo_path == repo_path && s.branch_name == old)
+        {
+            self.stacks
+                .retain(|s| !(s.repo_path == repo_path && s.branch_name == new && old != new));
+            for link in self
+                .stacks
+                .iter_mut()
+                .filter(|s| s.repo_path == repo_path && s.branch_name == old)
+            {
+                link.branch_name = new.to_string();
+                changed += 1;
+            }
+        }
+        for link in self
+            .stacks
+            .iter_mut()
+            .filter(|s| s.repo_path == repo_path && s.parent_branch == old)
+        {
+            link.parent_branch = new.to_string();
+            changed += 1;
+        }
+        changed
+    }
+
+    /// Remove a branch from its stack, moving the branches stacked on it to
+    /// its parent. Returns the number of stack rows changed.
+    fn splice_out_of_stack(&mut self, repo_path: &str, branch_name: &str) -> usize {
+        let mut changed = 0;
+        let parent = self
+            .stacks
+            .iter()
+            .find(|s| s.repo_path == repo_path && s.branch_name == branch_name)
+            .map(|s| s.parent_branch.clone());
+        if let Some(parent) = parent {
+            for link in self
+                .stacks
+                .iter_mut()
+                .filter(|s| s.repo_path == repo_path && s.parent_branch == branch_name)
+            {
+                link.parent_branch = parent.clone();
+                changed += 1;
+            }
+        }
+        changed
+            + remove_where(&mut self.stacks, |s| {
+                s.repo_path == repo_path
+                    && (s.branch_name == branch_name || s.parent_branch == branch_name)
+            })
+    }
+
+    /// Remove everything stored about a branch
+    fn remove_branch_rows(&mut self, repo_path: &str, branch_name: &str) {
+        self.splice_out_of_stack(repo_path, branch_name);
+
+        let is_branch = |r: &str, b: &str| r == repo_path && b == branch_name;
+        self.branches
+            .retain(|b| !is_branch(&b.repo_path, &b.branch_name));
+        self.aliases
+            .retain(|a| !is_branch(&a.repo_path, &a.branch_name));
+        self.history
+            .retain(|e| !is_branch(&e.repo_path, &e.branch_name));
+        self.previous_branches
+            .retain(|p| !is_branch(&p.repo_path, &p.branch_name));
+        self.pins
+            .retain(|p| !is_branch(&p.repo_path, &p.branch_name));
+        self.notes
+            .retain(|n| !is_branch(&n.repo_path, &n.branch_name));
+        self.groups
+            .retain(|g| !is_branch(&g.repo_path, &g.branch_name));
+        self.events.retain(|e| {
+            !(e.repo_path == repo_path
+                && (e.to_branch == branch_name || e.from_branch.as_deref() == Some(branch_name)))
+        });
+    }
+
+    /// Load an export. With `merge`, switch counts of branches known on both
+    /// sides are summed and the most recent timestamp, alias target and
+    /// previous branch win; without it imported rows replace existing ones.
+    fn import_rows(&mut self, export: &HistoryExport, merge: bool) {
+        for b in &export.branches {
+            let record = self.branch_mut(&b.repo_path, &b.branch_name);
+            if merge {
+                record.switch_count += b.switch_count;
+                record.last_used = record.last_used.max(b.last_used);
+            } else {
+                record.switch_count = b.switch_count;
+                record.last_used = b.last_used;
+            }
+        }
+
+        for a in &export.aliases {
+            match self
+                .aliases
+                .iter_mut()
+                .find(|existing| existing.repo_path == a.repo_path && existing.alias == a.alias)
+            {
+                Some(existing) if merge && a.created_at <= existing.created_at => {}
+                Some(existing) => *existing = a.clone(),
+                None => self.aliases.push(a.clone()),
+            }
+        }
+
+        for p in &export.previous_branches {
+            match self
+                .previous_branches
+                .iter_mut()
+                .find(|existing| existing.repo_path == p.repo_path)
+            {
+                Some(existing) if merge && p.updated_at <= existing.updated_at => {}
+                Some(existing) => *existing = p.clone(),
+                None => self.previous_branches.push(p.clone()),
+            }
+        }
+    }
+}
+
+fn branch_record(b: &ExportedBranch) -> BranchRecord {
+    BranchRecord {
+        repo_path: b.repo_path.clone(),
+        branch_name: b.branch_name.clone(),
+        switch_count: b.switch_count,
+        last_used: b.last_used,
+    }
+}
+
+/// Whether `repo_path` is the repository a query is limited to, if any
+fn in_repo(repo_path: &str, only: Option<&str>) -> bool {
+    only.is_none_or(|only| only == repo_path)
+}
+
+fn matches_filter(b: &ExportedBranch, filter: &StatsFilter) -> bool {
+    in_repo(&b.repo_path, filter.repo_path.as_deref())
+        && filter.cutoff().is_none_or(|cutoff| b.last_used >= cutoff)
+}
+
+/// Remove the rows `remove` selects. Returns how many there were.
+fn remove_where<T>(rows: &mut Vec<T>, remove: impl Fn(&T) -> bool) -> usize {
+    let before = rows.len();
+    rows.retain(|row| !remove(row));
+    before - rows.len()
+}
+
+/// Take the rows `remove` selects out of `rows`
+fn remove_rows<T>(rows: &mut Vec<T>, remove: impl Fn(&T) -> bool) -> Vec<T> {
+    let (removed, kept) = std::mem::take(rows)
+        .into_iter()
+        .partition(|row| remove(row));
+    *rows = kept;
+    removed
+}
+
+/// Change the rows `moving` selects with `retarget`, in place, dropping
+/// those that would then clash with a row already there (`same` tells
+/// whether two rows have the same key). Returns how many rows were moved or
+/// dropped.
+fn move_rows<T>(
+    rows: &mut Vec<T>,
+    moving: impl Fn(&T) -> bool,
+    retarget: impl Fn(&mut T),
+    same: impl Fn(&T, &T) -> bool,
+) -> usize {
+    let mut changed = 0;
+    let mut index = 0;
+    while index < rows.len() {
+        if !moving(&rows[index]) {
+            index += 1;
+            continue;
+        }
+
+        let mut row = rows.remove(index);
+        retarget(&mut row);
+        changed += 1;
+        if !rows.iter().any(|other| same(other, &row)) {
+            rows.insert(index, row);
+            index += 1;
+        }
+    }
+    changed
+}
+
+/// Hold the history file's lock until the returned file is dropped
+fn lock(path: &Path) -> Result<File> {
+    let mut lock_path = path.as_os_str().to_os_string();
+    lock_path.push(".lock");
+    let file = File::create(&lock_path)
+        .and_then(|file| file.lock().map(|_| file))
+        .with_context(|| format!("Failed to lock {}", path.display()))?;
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_history_persists_across_opens() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(FILE_NAME);
+
+        let storage = JsonStore::open_at(&path).unwrap();
+        storage.record_checkout("/repo", None, "main").unwrap();
+        storage
+            .record_checkout("/repo", Some("main"), "feature")
+            .unwrap();
+        storage.create_alias("/repo", "f", "feature").unwrap();
+        storage.save_previous_branch("/repo", "main").unwrap();
+
+        let reopened = JsonStore::open_at(&path).unwrap();
+        let records = reopened.branch_records("/repo").unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            reopened.alias("/repo", "f").unwrap().as_deref(),
+            Some("feature")
+        );
+        assert_eq!(
+            reopened.previous_branch("/repo").unwrap().as_deref(),
+            Some("main")
+        );
+        assert_eq!(reopened.checkout_events("/repo").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_writes_keep_changes_from_other_stores() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(FILE_NAME);
+
+        // Two invocations that opened the file before either wrote to it
+        let first = JsonStore::open_at(&path).unwrap();
+        let second = JsonStore::open_at(&path).unwrap();
+        first.record_checkout("/repo", None, "main").unwrap();
+        second.record_checkout("/repo", None, "feature").unwrap();
+
+        let records = JsonStore::open_at(&path)
+            .unwrap()
+            .branch_records("/repo")
+            .unwrap();
+        assert_eq!(records.len(), 2);
+    }
+
+    #[test]
+    fn test_missing_file_is_empty_history() {
+        let dir = TempDir::new().unwrap();
+        let storage = JsonStore::open_at(dir.path().join(FILE_NAME)).unwrap();
+
+        assert!(storage.all_records().unwrap().is_empty());
+        assert!(!dir.path().join(FILE_NAME).exists());
+    }
+
+    #[test]
+    fn test_refuses_newer_format() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(FILE_NAME);
+        std::fs::write(&path, r#"{"version": 99}"#).unwrap();
+
+        let err = JsonStore::open_at(&path).err().unwrap();
+        assert!(err.to_string().contains("newer ggo"));
+    }
+
+    #[test]
+    fn test_recording_options() {
+        let storage = Storage::new(JsonStore::open_in_memory())
+            .with_exclude(vec!["dependabot/*".to_string()]);
+        storage.record_checkout("/repo", None, "main").unwrap();
+        storage
+            .record_checkout("/repo", Some("main"), "dependabot/npm")
+            .unwrap();
+        assert_eq!(storage.branch_records("/repo").unwrap().len(), 1);
+
+        let storage = Storage::new(JsonStore::open_in_memory()).with_recording(false);
+        storage.record_checkout("/repo", None, "main").unwrap();
+        storage.save_previous_branch("/repo", "main").unwrap();
+        assert!(storage.all_records().unwrap().is_empty());
+        assert_eq!(storage.previous_branch("/repo").unwrap(), None);
+    }
+
+    #[test]
+    fn test_import_checkout_events_skips_duplicates() {
+        let storage = JsonStore::open_in_memory();
+        let event = |to: &str, at: i64| CheckoutEvent {
+            repo_path: "/repo".to_string(),
+            from_branch: Some("main".to_string()),
+            to_branch: to.to_string(),
+            switched_at: at,
+        };
+        let events = vec![
+            event("feature", 100),
+            event("main", 200),
+            event("feature", 300),
+            event("feature", 300),
+        ];
+
+        assert_eq!(storage.import_checkout_events(&events).unwrap(), 4);
+        assert_eq!(storage.import_checkout_events(&events).unwrap(), 0);
+
+        let records = storage.branch_records("/repo").unwrap();
+        let feature = records.iter().find(|r| r.branch_name == "feature").unwrap();
+        assert_eq!(feature.switch_count, 3);
+        assert_eq!(feature.last_used, 300);
+    }
+
+    #[test]
+    fn test_trim_records_drops_old_and_lowest_scored() {
+        let storage = JsonStore::open_in_memory();
+        let now = now_timestamp();
+        let event = |to: &str, at: i64| CheckoutEvent {
+            repo_path: "/repo".to_string(),
+            from_branch: None,
+            to_branch: to.to_string(),
+            switched_at: at,
+        };
+        let events = vec![
+            event("ancient", 100),
+            event("main", now - 30),
+            event("main", now - 20),
+            event("feature", now - 10),
+            event("feature", now),
+            event("typo", now),
+        ];
+        storage.import_checkout_events(&events).unwrap();
+
+        let score = |r: &BranchRecord| r.switch_count as f64;
+        // Within both limits nothing is touched
+        assert_eq!(storage.trim_records(10, 0, &score).unwrap(), 0);
+        assert_eq!(storage.trim_records(2, 30, &score).unwrap(), 2);
+
+        let mut kept: Vec<_> = storage
+            .all_records()
+            .unwrap()
+            .into_iter()
+            .map(|r| r.branch_name)
+            .collect();
+        kept.sort();
+        assert_eq!(kept, ["feature", "main"]);
+    }
+
+    #[test]
+    fn test_undo_last_checkout_reverts_bookkeeping() {
+        let storage = JsonStore::open_in_memory();
+        for (from, to) in [("main", "feature"), ("feature", "main")] {
+            storage.save_previous_branch("/repo", from).unwrap();
+            storage.push_checkout_history("/repo", from).unwrap();
+            storage.record_checkout("/repo", Some(from), to).unwrap();
+        }
+
+        let undone = storage.undo_last_checkout("/repo").unwrap().unwrap();
+        assert_eq!(undone.to_branch, "main");
+        assert_eq!(
+            storage.previous_branch("/repo").unwrap().as_deref(),
+            Some("main")
+        );
+        assert!(storage
+            .branch_records("/repo")
+            .unwrap()
+            .iter()
+            .all(|r| r.branch_name != "main"));
+
+        storage.undo_last_checkout("/repo").unwrap();
+        assert!(storage.all_records().unwrap().is_empty());
+        assert_eq!(storage.previous_branch("/repo").unwrap(), None);
+        assert!(storage.undo_last_checkout("/repo").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_merge_repo_moves_all_history() {
+        let storage = JsonStore::open_in_memory();
+        storage.record_checkout("/old", None, "main").unwrap();
+        storage.record_checkout("/new", None, "main").unwrap();
+        storage.create_alias("/old", "m", "main").unwrap();
+        storage.create_alias("/new", "m", "master").unwrap();
+        storage.pin_branch("/old", "main").unwrap();
+
+        assert!(storage.merge_repo("/old", "/new").unwrap() > 0);
+
+        let records = storage.branch_records("/new").unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].switch_count, 2);
+        assert_eq!(
+            storage.alias("/new", "m").unwrap().as_deref(),
+            Some("master")
+        );
+        assert_eq!(storage.pinned_branches("/new").unwrap(), vec!["main"]);
+        assert_eq!(storage.repo_row_count("/old").unwrap(), 0);
+        assert_eq!(storage.merge_repo("/old", "/new").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_forget_branch_splices_stack() {
+        let storage = JsonStore::open_in_memory();
+        storage.set_stack_parent("/repo", "a", "main").unwrap();
+        storage.set_stack_parent("/repo", "b", "a").unwrap();
+        storage.record_checkout("/repo", None, "a").unwrap();
+
+        storage.delete_branch_data("/repo", "a").unwrap();
+
+        let links = storage.stack_links("/repo").unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(
+            (links[0].branch.as_str(), links[0].parent.as_str()),
+            ("b", "main")
+        );
+        assert!(storage.recorded_branches("/repo").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_export_import_merge() {
+        let source = JsonStore::open_in_memory();
+        source.record_checkout("/repo", None, "main").unwrap();
+        source.create_alias("/repo", "m", "main").unwrap();
+        let export = source.export_history().unwrap();
+
+        let target = JsonStore::open_in_memory();
+        target.record_checkout("/repo", None, "main").unwrap();
+        target.import_history(&export, true).unwrap();
+
+        assert_eq!(target.branch_records("/repo").unwrap()[0].switch_count, 2);
+        assert_eq!(target.alias("/repo", "m").unwrap().as_deref(), Some("main"));
+
+        target.import_history(&export, false).unwrap();
+        assert_eq!(target.branch_records("/repo").unwrap()[0].switch_count, 1);
+    }
+
+    #[cfg(feature = "encrypted")]
+    #[test]
+    fn test_encrypted_history() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(ENCRYPTED_FILE_NAME);
+
+        let storage = JsonStore::open_encrypted_at(&path, Cipher::new(&[1; 32])).unwrap();
+        storage
+            .record_checkout("/repo", None, "JIRA-123-secret-project")
+            .unwrap();
+
+        let raw = std::fs::read(&path).unwrap();
+        assert!(!String::from_utf8_lossy(&raw).contains("secret-project"));
+
+        let reopened = JsonStore::open_encrypted_at(&path, Cipher::new(&[1; 32])).unwrap();
+        assert_eq!(reopened.branch_records("/repo").unwrap().len(), 1);
+
+        let err = JsonStore::open_encrypted_at(&path, Cipher::new(&[2; 32]))
+            .err()
+            .unwrap();
+        assert!(format!("{:#}", err).contains("wrong key"));
+        assert!(JsonStore::open_at(&path).is_err());
+    }
+}
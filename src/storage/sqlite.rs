@@ -0,0 +1,5442 @@
+//! The SQLite backend (the default, behind the `sqlite` feature): one
+//! database file with schema migrations, WAL journaling and busy retries so
+//! concurrent ggo invocations don't trip over each other.
+
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::{
+    data_dir_location, get_data_dir, now_timestamp, over_retention, records_to_trim, Alias,
+    BranchRecord, CheckoutEvent, GroupRow, HistoryEntry, HistoryRow, HistoryStore, NoteRow, PinRow,
+    RepoSummary, Snapshot, StackLink, StackRow, StaleRecords, Stats, StatsFilter, StatsSort,
+    StoreOptions, Transition,
+};
+use crate::constants::database::{BUSY_TIMEOUT_MS, MAX_WRITE_ATTEMPTS, RETRY_BASE_DELAY_MS};
+use crate::constants::history::MAX_HISTORY_ENTRIES;
+use crate::error::GgoError;
+use crate::export::{
+    ExportedAlias, ExportedBranch, ExportedPreviousBranch, HistoryExport, EXPORT_VERSION,
+};
+use crate::matcher;
+
+/// Current database schema version
+const CURRENT_SCHEMA_VERSION: i32 = 11;
+
+/// Name of the database file in the data directory
+pub(crate) const DB_FILE_NAME: &str = "data.db";
+
+/// Get the path to the SQLite database file
+fn get_db_path() -> Result<PathBuf> {
+    Ok(get_data_dir()?.join(DB_FILE_NAME))
+}
+
+/// Open a connection to the database, creating it if necessary.
+/// WAL mode lets concurrent ggo invocations read while another one writes.
+fn open_db(db_path: &Path) -> Result<Connection> {
+    let conn = Connection::open(db_path).context("Failed to open database")?;
+
+    // Another ggo (or a prompt integration) may hold the write lock; wait for it
+    // instead of failing straight away with "database is locked"
+    conn.busy_timeout(Duration::from_millis(BUSY_TIMEOUT_MS))
+        .context("Failed to set busy timeout")?;
+
+    retry_on_busy(|| conn.pragma_update(None, "journal_mode", "WAL"))
+        .context("Failed to enable WAL mode")?;
+
+    // Nearly every open finds the schema current; one read settles that
+    // without taking the write lock the table setup and migration checks need
+    let version = schema_version(&conn);
+    if version == CURRENT_SCHEMA_VERSION {
+        return Ok(conn);
+    }
+
+    // Migrations only run forward, so keep a copy of what they start from
+    if version > 0 && version < CURRENT_SCHEMA_VERSION {
+        backup_database(&conn, db_path, version)?;
+    }
+
+    initialize_tables(&conn)?;
+    Ok(conn)
+}
+
+/// Latest applied schema version (0 for a new database)
+fn schema_version(conn: &Connection) -> i32 {
+    conn.query_row(
+        "SELECT version FROM schema_version ORDER BY version DESC LIMIT 1",
+        [],
+        |row| row.get(0),
+    )
+    .unwrap_or(0)
+}
+
+/// Path of the backup taken before migrating away from schema `version`
+/// (`data.db.bak-v{version}`)
+fn backup_path(db_path: &Path, version: i32) -> PathBuf {
+    let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".bak-v{}", version));
+    db_path.with_file_name(name)
+}
+
+/// Snapshot the database as it is at schema `version`. `VACUUM INTO` gives a
+/// consistent copy even with changes still in the WAL.
+fn backup_database(conn: &Connection, db_path: &Path, version: i32) -> Result<PathBuf> {
+    let backup = backup_path(db_path, version);
+    if backup.exists() {
+        std::fs::remove_file(&backup).context("Failed to replace old database backup")?;
+    }
+
+    conn.execute("VACUUM INTO ?1", [backup.to_string_lossy()])
+        .context("Failed to back up database before migrating")?;
+    tracing::info!(
+        "Backed up schema v{} database to {}",
+        version,
+        backup.display()
+    );
+
+    Ok(backup)
+}
+
+/// Put the newest backup this version of ggo can read back in place of the
+/// database, keeping the replaced file as `data.db.pre-restore`. Works without
+/// opening the database, so it also rescues one a newer ggo has migrated.
+/// Returns the backup restored and its schema version.
+pub fn restore_backup() -> Result<(PathBuf, i32)> {
+    restore_backup_at(&get_db_path()?)
+}
+
+fn restore_backup_at(db_path: &Path) -> Result<(PathBuf, i32)> {
+    let (version, backup) = (1..=CURRENT_SCHEMA_VERSION)
+        .rev()
+        .map(|version| (version, backup_path(db_path, version)))
+        .find(|(_, path)| path.exists())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "No database backup to restore (backups are taken before schema migrations)"
+            )
+        })?;
+
+    if db_path.exists() {
+        let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+        name.push(".pre-restore");
+        std::fs::copy(db_path, db_path.with_file_name(name))
+            .context("Failed to keep a copy of the current database")?;
+    }
+
+    // Stale WAL contents would be replayed on top of the restored file
+    for suffix in ["-wal", "-shm"] {
+        let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+        name.push(suffix);
+        let path = db_path.with_file_name(name);
+        if path.exists() {
+            std::fs::remove_file(&path).context("Failed to remove database journal")?;
+        }
+    }
+
+    std::fs::copy(&backup, db_path).context("Failed to restore database backup")?;
+    Ok((backup, version))
+}
+
+/// Open the database, salvaging what it can into a fresh one if the file is
+/// damaged (power loss, a full disk) so a corrupt history never blocks
+/// checkouts. The damaged file is kept as `data.db.corrupt-{timestamp}`.
+fn open_or_repair(db_path: &Path) -> Result<Connection> {
+    let err = match open_db(db_path) {
+        Ok(conn) => return Ok(conn),
+        Err(err) => err,
+    };
+    if !db_path.exists() || !is_corrupt(db_path, &err) {
+        return Err(err);
+    }
+
+    tracing::debug!("Database failed to open: {:#}", err);
+    let (conn, salvage) = repair_database(db_path).context("Failed to repair database")?;
+    tracing::warn!(
+        "{} was damaged: salvaged {} rows into a fresh database{} (the damaged file is kept as {})",
+        db_path.display(),
+        salvage.rows,
+        if salvage.incomplete.is_empty() {
+            String::new()
+        } else {
+            format!(
+                ", but could not read all of {}",
+                salvage.incomplete.join(", ")
+            )
+        },
+        salvage.damaged.display()
+    );
+    Ok(conn)
+}
+
+/// Whether opening failed because the file is damaged, rather than because
+/// it's locked, unreadable or from a newer ggo
+fn is_corrupt(db_path: &Path, err: &anyhow::Error) -> bool {
+    let corrupt_code = |e: &rusqlite::Error| {
+        matches!(
+            e.sqlite_error_code(),
+            Some(rusqlite::ErrorCode::DatabaseCorrupt | rusqlite::ErrorCode::NotADatabase)
+        )
+    };
+    if err
+        .chain()
+        .filter_map(|e| e.downcast_ref::<rusqlite::Error>())
+        .any(corrupt_code)
+    {
+        return true;
+    }
+
+    let check = Connection::open_with_flags(db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .and_then(|conn| conn.query_row("PRAGMA quick_check", [], |row| row.get::<_, String>(0)));
+    match check {
+        Ok(result) => result != "ok",
+        Err(e) => corrupt_code(&e),
+    }
+}
+
+/// What [`repair_database`] got out of a damaged database
+struct Salvage {
+    /// Where the damaged file was moved
+    damaged: PathBuf,
+    /// Rows copied into the fresh database
+    rows: usize,
+    /// Tables that could only be read in part, or not at all
+    incomplete: Vec<&'static str>,
+}
+
+/// Move the damaged database aside, create a fresh one in its place and copy
+/// over every row that can still be read
+fn repair_database(db_path: &Path) -> Result<(Connection, Salvage)> {
+    let mut damaged = db_path.file_name().unwrap_or_default().to_os_string();
+    damaged.push(format!(".corrupt-{}", now_timestamp()));
+    let damaged = db_path.with_file_name(damaged);
+
+    // The WAL holds the latest changes, so it moves along with the file
+    for suffix in ["", "-wal", "-shm"] {
+        let mut from = db_path.as_os_str().to_os_string();
+        from.push(suffix);
+        let mut to = damaged.as_os_str().to_os_string();
+        to.push(suffix);
+        if Path::new(&from).exists() {
+            std::fs::rename(&from, &to).context("Failed to move damaged database aside")?;
+        }
+    }
+
+    let conn = open_db(db_path)?;
+    let mut salvage = Salvage {
+        damaged,
+        rows: 0,
+        incomplete: Vec::new(),
+    };
+    if let Err(e) = conn.execute(
+        "ATTACH DATABASE ?1 AS damaged",
+        [salvage.damaged.to_string_lossy()],
+    ) {
+        tracing::debug!("Could not attach damaged database: {}", e);
+        salvage.incomplete.extend(REPO_TABLES);
+        return Ok((conn, salvage));
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    for table in REPO_TABLES.into_iter().chain(["meta"]) {
+        match salvage_table(&tx, table) {
+            Ok(rows) => salvage.rows += rows,
+            Err((rows, e)) => {
+                tracing::debug!("Salvaging {} stopped: {}", table, e);
+                salvage.rows += rows;
+                salvage.incomplete.push(table);
+            }
+        }
+    }
+    tx.commit()?;
+    conn.execute("DETACH DATABASE damaged", [])?;
+
+    Ok((conn, salvage))
+}
+
+/// Copy the rows of `table` from the attached damaged database until one
+/// can't be read, keeping the columns both schemas have (the damaged file may
+/// be from an older schema version). Returns the rows copied, along with the
+/// error that stopped it early.
+fn salvage_table(
+    conn: &Connection,
+    table: &str,
+) -> std::result::Result<usize, (usize, rusqlite::Error)> {
+    let columns = |schema: &str| -> rusqlite::Result<Vec<String>> {
+        conn.prepare(&format!("PRAGMA {}.table_info({})", schema, table))?
+            .query_map([], |row| row.get(1))?
+            .collect()
+    };
+    let damaged = columns("damaged").map_err(|e| (0, e))?;
+    let shared: Vec<String> = columns("main")
+        .map_err(|e| (0, e))?
+        .into_iter()
+        .filter(|column| damaged.contains(column))
+        .collect();
+    if shared.is_empty() {
+        // A table from a later schema version than the damaged file's
+        return Ok(0);
+    }
+
+    let list = shared.join(", ");
+    let placeholders = vec!["?"; shared.len()].join(", ");
+    let mut insert = conn
+        .prepare(&format!(
+            "INSERT OR IGNORE INTO main.{} ({}) VALUES ({})",
+            table, list, placeholders
+        ))
+        .map_err(|e| (0, e))?;
+    let mut select = conn
+        .prepare(&format!("SELECT {} FROM damaged.{}", list, table))
+        .map_err(|e| (0, e))?;
+    let mut rows = select.query([]).map_err(|e| (0, e))?;
+
+    let mut copied = 0;
+    loop {
+        let row = match rows.next() {
+            Ok(Some(row)) => row,
+            Ok(None) => return Ok(copied),
+            Err(e) => return Err((copied, e)),
+        };
+        let values = (0..shared.len())
+            .map(|i| row.get::<_, rusqlite::types::Value>(i))
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| (copied, e))?;
+        insert
+            .execute(rusqlite::params_from_iter(values))
+            .map_err(|e| (copied, e))?;
+        copied += 1;
+    }
+}
+
+/// Initialize database tables and run migrations
+fn initialize_tables(conn: &Connection) -> Result<()> {
+    // Create schema version table first
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            applied_at INTEGER NOT NULL
+        )",
+        [],
+    )
+    .context("Failed to create schema_version table")?;
+
+    let current_version = schema_version(conn);
+
+    // A newer ggo migrated this database; writing to it could break it for
+    // that version, and reading it may not work
+    if current_version > CURRENT_SCHEMA_VERSION {
+        return Err(GgoError::SchemaTooNew {
+            found: current_version,
+            supported: CURRENT_SCHEMA_VERSION,
+        }
+        .into());
+    }
+
+    // Run migrations if needed
+    if current_version < CURRENT_SCHEMA_VERSION {
+        run_migrations(conn, current_version)?;
+    }
+
+    Ok(())
+}
+
+/// Run database migrations from one version to another
+fn run_migrations(conn: &Connection, from_version: i32) -> Result<()> {
+    let now = now_timestamp();
+
+    // Apply migrations incrementally
+    for version in (from_version + 1)..=CURRENT_SCHEMA_VERSION {
+        match version {
+            1 => {
+                // Version 1: Initial schema with branches table
+                conn.execute(
+                    "CREATE TABLE IF NOT EXISTS branches (
+                        id INTEGER PRIMARY KEY,
+                        repo_path TEXT NOT NULL,
+                        branch_name TEXT NOT NULL,
+                        switch_count INTEGER DEFAULT 1,
+                        last_used INTEGER NOT NULL,
+                        UNIQUE(repo_path, branch_name)
+                    )",
+                    [],
+                )
+                .context("Failed to create branches table in migration v1")?;
+
+                // Add indices for branches
+                conn.execute(
+                    "CREATE INDEX IF NOT EXISTS idx_branches_repo_last_used
+                     ON branches(repo_path, last_used DESC)",
+                    [],
+                )
+                .context("Failed to create branches repo index in migration v1")?;
+
+                conn.execute(
+                    "CREATE INDEX IF NOT EXISTS idx_branches_last_used
+                     ON branches(last_used DESC)",
+                    [],
+                )
+                .context("Failed to create branches last_used index in migration v1")?;
+
+                // Create previous_branch table
+                conn.execute(
+                    "CREATE TABLE IF NOT EXISTS previous_branch (
+                        repo_path TEXT PRIMARY KEY,
+                        branch_name TEXT NOT NULL,
+                        updated_at INTEGER NOT NULL
+                    )",
+                    [],
+                )
+                .context("Failed to create previous_branch table in migration v1")?;
+            }
+            2 => {
+                // Version 2: Add aliases table
+                conn.execute(
+                    "CREATE TABLE IF NOT EXISTS aliases (
+                        repo_path TEXT NOT NULL,
+                        alias TEXT NOT NULL,
+                        branch_name TEXT NOT NULL,
+                        created_at INTEGER NOT NULL,
+                        PRIMARY KEY (repo_path, alias)
+                    )",
+                    [],
+                )
+                .context("Failed to create aliases table in migration v2")?;
+
+                // Add index for aliases
+                conn.execute(
+                    "CREATE INDEX IF NOT EXISTS idx_aliases_branch
+                     ON aliases(repo_path, branch_name)",
+                    [],
+                )
+                .context("Failed to create aliases branch index in migration v2")?;
+            }
+            3 => {
+                // Version 3: Checkout history stack (branches switched away from)
+                conn.execute(
+                    "CREATE TABLE IF NOT EXISTS checkout_history (
+                        id INTEGER PRIMARY KEY AUTOINCREMENT,
+                        repo_path TEXT NOT NULL,
+                        branch_name TEXT NOT NULL,
+                        switched_at INTEGER NOT NULL
+                    )",
+                    [],
+                )
+                .context("Failed to create checkout_history table in migration v3")?;
+
+                conn.execute(
+                    "CREATE INDEX IF NOT EXISTS idx_checkout_history_repo
+                     ON checkout_history(repo_path, id DESC)",
+                    [],
+                )
+                .context("Failed to create checkout_history index in migration v3")?;
+
+                // Seed the stack with the single-slot previous branch. Early v1
+                // databases may predate the previous_branch table.
+                conn.execute(
+                    "CREATE TABLE IF NOT EXISTS previous_branch (
+                        repo_path TEXT PRIMARY KEY,
+                        branch_name TEXT NOT NULL,
+                        updated_at INTEGER NOT NULL
+                    )",
+                    [],
+                )
+                .context("Failed to create previous_branch table in migration v3")?;
+
+                conn.execute(
+                    "INSERT INTO checkout_history (repo_path, branch_name, switched_at)
+                     SELECT repo_path, branch_name, updated_at FROM previous_branch",
+                    [],
+                )
+                .context("Failed to seed checkout_history in migration v3")?;
+            }
+            4 => {
+                // Version 4: One row per checkout, for per-visit frecency and
+                // transition analysis. Aggregates in `branches` stay authoritative
+                // for counts, so old events can be compacted away.
+                conn.execute(
+                    "CREATE TABLE IF NOT EXISTS checkout_events (
+                        id INTEGER PRIMARY KEY AUTOINCREMENT,
+                        repo_path TEXT NOT NULL,
+                        from_branch TEXT,
+                        to_branch TEXT NOT NULL,
+                        switched_at INTEGER NOT NULL
+                    )",
+                    [],
+                )
+                .context("Failed to create checkout_events table in migration v4")?;
+
+                conn.execute(
+                    "CREATE INDEX IF NOT EXISTS idx_checkout_events_repo
+                     ON checkout_events(repo_path, switched_at DESC)",
+                    [],
+                )
+                .context("Failed to create checkout_events repo index in migration v4")?;
+
+                conn.execute(
+                    "CREATE INDEX IF NOT EXISTS idx_checkout_events_switched_at
+                     ON checkout_events(switched_at)",
+                    [],
+                )
+                .context("Failed to create checkout_events time index in migration v4")?;
+            }
+            5 => {
+                // Version 5: Pinned branches
+                conn.execute(
+                    "CREATE TABLE IF NOT EXISTS pinned_branches (
+                        repo_path TEXT NOT NULL,
+                        branch_name TEXT NOT NULL,
+                        pinned_at INTEGER NOT NULL,
+                        PRIMARY KEY (repo_path, branch_name)
+                    )",
+                    [],
+                )
+                .context("Failed to create pinned_branches table in migration v5")?;
+            }
+            6 => {
+                // Version 6: Repositories are keyed by the canonical path of
+                // their main worktree. Fold rows stored under symlinked paths
+                // or linked worktrees into that key.
+                let mut stmt = conn
+                    .prepare(&format!(
+                        "SELECT repo_path FROM ({})",
+                        existing_repo_tables(conn)
+                            .context("Failed to list tables in migration v6")?
+                            .iter()
+                            .map(|table| format!("SELECT repo_path FROM {}", table))
+                            .collect::<Vec<_>>()
+                            .join(" UNION ")
+                    ))
+                    .context("Failed to list repositories in migration v6")?;
+                let repo_paths: Vec<String> = stmt
+                    .query_map([], |row| row.get(0))
+                    .context("Failed to list repositories in migration v6")?
+                    .map_while(std::result::Result::ok)
+                    .collect();
+
+                for repo_path in repo_paths {
+                    match crate::git::repo_path_key_for(Path::new(&repo_path)) {
+                        Some(key) if key != repo_path => {
+                            merge_repo_rows(conn, &repo_path, &key).context(format!(
+                                "Failed to merge history of {} in migration v6",
+                                repo_path
+                            ))?;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            7 => {
+                // Version 7: Key/value store for ggo's own bookkeeping (e.g. the
+                // invocation counter driving automatic maintenance)
+                conn.execute(
+                    "CREATE TABLE IF NOT EXISTS meta (
+                        key TEXT PRIMARY KEY,
+                        value TEXT NOT NULL
+                    )",
+                    [],
+                )
+                .context("Failed to create meta table in migration v7")?;
+            }
+            8 => {
+                // Version 8: Branch stacks, linking each stacked branch to the
+                // branch it is built on
+                conn.execute(
+                    "CREATE TABLE IF NOT EXISTS stack_parents (
+                        repo_path TEXT NOT NULL,
+                        branch_name TEXT NOT NULL,
+                        parent_branch TEXT NOT NULL,
+                        created_at INTEGER NOT NULL,
+                        PRIMARY KEY (repo_path, branch_name)
+                    )",
+                    [],
+                )
+                .context("Failed to create stack_parents table in migration v8")?;
+            }
+            9 => {
+                // Version 9: Free-text notes on branches ('ggo note')
+                conn.execute(
+                    "CREATE TABLE IF NOT EXISTS branch_notes (
+                        repo_path TEXT NOT NULL,
+                        branch_name TEXT NOT NULL,
+                        note TEXT NOT NULL,
+                        updated_at INTEGER NOT NULL,
+                        PRIMARY KEY (repo_path, branch_name)
+                    )",
+                    [],
+                )
+                .context("Failed to create branch_notes table in migration v9")?;
+            }
+            10 => {
+                // Version 10: Cached branch list per repository, valid while
+                // the refs fingerprint is unchanged
+                conn.execute(
+                    "CREATE TABLE IF NOT EXISTS branch_cache (
+                        repo_path TEXT PRIMARY KEY,
+                        fingerprint TEXT NOT NULL,
+                        branches TEXT NOT NULL,
+                        updated_at INTEGER NOT NULL
+                    )",
+                    [],
+                )
+                .context("Failed to create branch_cache table in migration v10")?;
+            }
+            11 => {
+                // Version 11: Named branch groups ('ggo group add'); members
+                // are branch names or glob patterns
+                conn.execute(
+                    "CREATE TABLE IF NOT EXISTS branch_groups (
+                        repo_path TEXT NOT NULL,
+                        group_name TEXT NOT NULL,
+                        branch_name TEXT NOT NULL,
+                        added_at INTEGER NOT NULL,
+                        PRIMARY KEY (repo_path, group_name, branch_name)
+                    )",
+                    [],
+                )
+                .context("Failed to create branch_groups table in migration v11")?;
+            }
+            _ => {
+                // Unknown version - should never happen
+                anyhow::bail!("Unknown migration version: {}", version);
+            }
+        }
+
+        // Record that this migration was applied
+        conn.execute(
+            "INSERT INTO schema_version (version, applied_at) VALUES (?1, ?2)",
+            [&version.to_string(), &now.to_string()],
+        )
+        .context(format!("Failed to record migration version {}", version))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+fn open_test_db() -> Result<Connection> {
+    // Use in-memory database for tests to ensure isolation
+    let conn = Connection::open_in_memory().context("Failed to open in-memory database")?;
+
+    initialize_tables(&conn)?;
+    Ok(conn)
+}
+
+/// Whether an error means another connection holds the database lock
+fn is_busy(err: &rusqlite::Error) -> bool {
+    matches!(
+        err.sqlite_error_code(),
+        Some(rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked)
+    )
+}
+
+/// Every row `sql` selects, in the order they were added
+fn select_all<T>(
+    conn: &Connection,
+    sql: &str,
+    map: impl FnMut(&rusqlite::Row) -> rusqlite::Result<T>,
+) -> Result<Vec<T>> {
+    conn.prepare(&format!("{} ORDER BY rowid", sql))?
+        .query_map([], map)?
+        .collect::<rusqlite::Result<_>>()
+        .context("Failed to read history")
+}
+
+/// Run a write, retrying with exponential backoff while the database is busy.
+/// The busy timeout covers most contention; this catches the cases SQLite
+/// reports immediately (e.g. a read transaction upgrading to a write).
+fn retry_on_busy<T>(mut op: impl FnMut() -> rusqlite::Result<T>) -> rusqlite::Result<T> {
+    let mut delay = Duration::from_millis(RETRY_BASE_DELAY_MS);
+    let mut attempt = 1;
+
+    loop {
+        match op() {
+            Err(e) if is_busy(&e) && attempt < MAX_WRITE_ATTEMPTS => {
+                tracing::debug!(
+                    "Database busy (attempt {}), retrying in {:?}",
+                    attempt,
+                    delay
+                );
+                std::thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Tables holding per-repository rows, keyed by `repo_path`
+const REPO_TABLES: [&str; 9] = [
+    "branches",
+    "aliases",
+    "previous_branch",
+    "checkout_history",
+    "checkout_events",
+    "pinned_branches",
+    "stack_parents",
+    "branch_notes",
+    "branch_groups",
+];
+
+/// The [`REPO_TABLES`] this database has. During a migration, the tables of
+/// later schema versions don't exist yet.
+fn existing_repo_tables(conn: &Connection) -> rusqlite::Result<Vec<&'static str>> {
+    let mut stmt = conn.prepare_cached("SELECT name FROM sqlite_master WHERE type = 'table'")?;
+    let names: Vec<String> = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    Ok(REPO_TABLES
+        .into_iter()
+        .filter(|table| names.iter().any(|name| name == table))
+        .collect())
+}
+
+/// Whether the database has `table` (tables of later schema versions don't
+/// exist yet during a migration)
+fn has_table(conn: &Connection, table: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+        [table],
+        |row| row.get(0),
+    )
+}
+
+/// Number of rows stored under a repository key, across all tables
+fn count_repo_rows(conn: &Connection, repo_path: &str) -> rusqlite::Result<usize> {
+    let count = existing_repo_tables(conn)?
+        .iter()
+        .map(|table| {
+            conn.query_row(
+                &format!("SELECT COUNT(*) FROM {} WHERE repo_path = ?1", table),
+                [repo_path],
+                |row| row.get::<_, i64>(0),
+            )
+        })
+        .sum::<rusqlite::Result<i64>>()?;
+
+    Ok(count as usize)
+}
+
+/// Move every row stored under `from` to `to`. Usage counts are added up;
+/// on conflicting aliases and pins the ones already under `to` win.
+/// Returns the number of rows moved.
+fn merge_repo_rows(conn: &Connection, from: &str, to: &str) -> rusqlite::Result<usize> {
+    if from == to {
+        return Ok(0);
+    }
+
+    let tx = conn.unchecked_transaction()?;
+
+    let moved = count_repo_rows(&tx, from)?;
+
+    if moved == 0 {
+        return Ok(0);
+    }
+
+    tx.execute(
+        "INSERT INTO branches (repo_path, branch_name, switch_count, last_used)
+         SELECT ?2, branch_name, switch_count, last_used FROM branches WHERE repo_path = ?1
+         ON CONFLICT(repo_path, branch_name) DO UPDATE SET
+            switch_count = switch_count + excluded.switch_count,
+            last_used = MAX(last_used, excluded.last_used)",
+        [from, to],
+    )?;
+    tx.execute(
+        "INSERT OR IGNORE INTO aliases (repo_path, alias, branch_name, created_at)
+         SELECT ?2, alias, branch_name, created_at FROM aliases WHERE repo_path = ?1",
+        [from, to],
+    )?;
+    tx.execute(
+        "INSERT OR IGNORE INTO pinned_branches (repo_path, branch_name, pinned_at)
+         SELECT ?2, branch_name, pinned_at FROM pinned_branches WHERE repo_path = ?1",
+        [from, to],
+    )?;
+    tx.execute(
+        "INSERT INTO previous_branch (repo_path, branch_name, updated_at)
+         SELECT ?2, branch_name, updated_at FROM previous_branch WHERE repo_path = ?1
+         ON CONFLICT(repo_path) DO UPDATE SET
+            branch_name = excluded.branch_name,
+            updated_at = excluded.updated_at
+         WHERE excluded.updated_at > previous_branch.updated_at",
+        [from, to],
+    )?;
+    for table in ["checkout_history", "checkout_events"] {
+        tx.execute(
+            &format!("UPDATE {} SET repo_path = ?2 WHERE repo_path = ?1", table),
+            [from, to],
+        )?;
+    }
+    let tables = existing_repo_tables(&tx)?;
+    if tables.contains(&"stack_parents") {
+        tx.execute(
+            "INSERT OR IGNORE INTO stack_parents
+                (repo_path, branch_name, parent_branch, created_at)
+             SELECT ?2, branch_name, parent_branch, created_at
+             FROM stack_parents WHERE repo_path = ?1",
+            [from, to],
+        )?;
+        tx.execute("DELETE FROM stack_parents WHERE repo_path = ?1", [from])?;
+    }
+    if tables.contains(&"branch_notes") {
+        tx.execute(
+            "INSERT OR IGNORE INTO branch_notes (repo_path, branch_name, note, updated_at)
+             SELECT ?2, branch_name, note, updated_at FROM branch_notes WHERE repo_path = ?1",
+            [from, to],
+        )?;
+        tx.execute("DELETE FROM branch_notes WHERE repo_path = ?1", [from])?;
+    }
+    if tables.contains(&"branch_groups") {
+        tx.execute(
+            "INSERT OR IGNORE INTO branch_groups (repo_path, group_name, branch_name, added_at)
+             SELECT ?2, group_name, branch_name, added_at FROM branch_groups WHERE repo_path = ?1",
+            [from, to],
+        )?;
+        tx.execute("DELETE FROM branch_groups WHERE repo_path = ?1", [from])?;
+    }
+    for table in ["branches", "aliases", "pinned_branches", "previous_branch"] {
+        tx.execute(
+            &format!("DELETE FROM {} WHERE repo_path = ?1", table),
+            [from],
+        )?;
+    }
+    if has_table(&tx, "branch_cache")? {
+        tx.execute("DELETE FROM branch_cache WHERE repo_path = ?1", [from])?;
+    }
+
+    tx.commit()?;
+    Ok(moved)
+}
+
+/// History kept in an SQLite database.
+///
+/// Holds a single connection for the whole invocation (statements are cached
+/// per connection), so open it once and pass it down.
+pub struct SqliteStore {
+    conn: Connection,
+    db_path: PathBuf,
+    options: StoreOptions,
+}
+
+impl SqliteStore {
+    /// Open the database in the ggo data directory, creating and migrating it if needed
+    pub fn open() -> Result<Self> {
+        let db_path = get_db_path()?;
+        let conn = open_or_repair(&db_path)?;
+        Ok(Self {
+            conn,
+            db_path,
+            options: StoreOptions::default(),
+        })
+    }
+
+    /// Open the database in the ggo data directory read-only, without
+    /// creating, migrating or repairing anything, for quick lookups such as
+    /// 'ggo current' in a shell prompt. None when there is no database yet.
+    pub fn open_read_only() -> Result<Option<Self>> {
+        let db_path = data_dir_location()?.0.join(DB_FILE_NAME);
+        if !db_path.exists() {
+            return Ok(None);
+        }
+        Self::open_read_only_at(db_path).map(Some)
+    }
+
+    /// Open the database at `db_path` read-only
+    pub fn open_read_only_at(db_path: impl Into<PathBuf>) -> Result<Self> {
+        let db_path = db_path.into();
+        let conn = Connection::open_with_flags(
+            &db_path,
+            rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .context("Failed to open database")?;
+        conn.busy_timeout(Duration::from_millis(BUSY_TIMEOUT_MS))
+            .context("Failed to set busy timeout")?;
+        Ok(Self {
+            conn,
+            db_path,
+            options: StoreOptions {
+                record: false,
+                ..StoreOptions::default()
+            },
+        })
+    }
+
+    /// Open a throwaway in-memory database (nothing is persisted)
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory().context("Failed to open in-memory database")?;
+        initialize_tables(&conn)?;
+        Ok(Self {
+            conn,
+            db_path: PathBuf::from(":memory:"),
+            options: StoreOptions::default(),
+        })
+    }
+}
+
+impl HistoryStore for SqliteStore {
+    fn options_mut(&mut self) -> &mut StoreOptions {
+        &mut self.options
+    }
+
+    fn db_path(&self) -> &Path {
+        &self.db_path
+    }
+
+    fn record_checkout(
+        &self,
+        repo_path: &str,
+        from_branch: Option<&str>,
+        branch_name: &str,
+    ) -> Result<()> {
+        if !self.options.record {
+            tracing::debug!("Recording disabled, not recording: {}", branch_name);
+            return Ok(());
+        }
+
+        if matcher::is_excluded(branch_name, &self.options.exclude) {
+            tracing::debug!("Not recording excluded branch: {}", branch_name);
+            return Ok(());
+        }
+
+        let now = now_timestamp();
+
+        retry_on_busy(|| {
+            let tx = self.conn.unchecked_transaction()?;
+
+            tx.prepare_cached(
+                "INSERT INTO branches (repo_path, branch_name, switch_count, last_used)
+                 VALUES (?1, ?2, 1, ?3)
+                 ON CONFLICT(repo_path, branch_name) DO UPDATE SET
+                    switch_count = switch_count + 1,
+                    last_used = ?3",
+            )?
+            .execute(rusqlite::params![repo_path, branch_name, now])?;
+
+            tx.prepare_cached(
+                "INSERT INTO checkout_events (repo_path, from_branch, to_branch, switched_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?
+            .execute(rusqlite::params![repo_path, from_branch, branch_name, now])?;
+
+            tx.commit()
+        })
+        .context("Failed to record checkout")?;
+
+        Ok(())
+    }
+
+    fn import_checkout_events(&self, events: &[CheckoutEvent]) -> Result<usize> {
+        let events: Vec<&CheckoutEvent> = events
+            .iter()
+            .filter(|event| !matcher::is_excluded(&event.to_branch, &self.options.exclude))
+            .collect();
+
+        let imported = retry_on_busy(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            // Identical events already stored, each matching one imported copy
+            let mut stored: HashMap<&CheckoutEvent, i64> = HashMap::new();
+            let mut imported = 0;
+            for &event in &events {
+                let remaining = match stored.get_mut(event) {
+                    Some(remaining) => remaining,
+                    None => {
+                        let count = tx
+                            .prepare_cached(
+                                "SELECT COUNT(*) FROM checkout_events
+                                 WHERE repo_path = ?1 AND from_branch IS ?2
+                                   AND to_branch = ?3 AND switched_at = ?4",
+                            )?
+                            .query_row(
+                                rusqlite::params![
+                                    event.repo_path,
+                                    event.from_branch,
+                                    event.to_branch,
+                                    event.switched_at
+                                ],
+                                |row| row.get(0),
+                            )?;
+                        stored.entry(event).or_insert(count)
+                    }
+                };
+                if *remaining > 0 {
+                    *remaining -= 1;
+                    continue;
+                }
+
+                tx.prepare_cached(
+                    "INSERT INTO checkout_events (repo_path, from_branch, to_branch, switched_at)
+                     VALUES (?1, ?2, ?3, ?4)",
+                )?
+                .execute(rusqlite::params![
+                    event.repo_path,
+                    event.from_branch,
+                    event.to_branch,
+                    event.switched_at
+                ])?;
+
+                tx.prepare_cached(
+                    "INSERT INTO branches (repo_path, branch_name, switch_count, last_used)
+                     VALUES (?1, ?2, 1, ?3)
+                     ON CONFLICT(repo_path, branch_name) DO UPDATE SET
+                        switch_count = switch_count + 1,
+                        last_used = MAX(last_used, ?3)",
+                )?
+                .execute(rusqlite::params![
+                    event.repo_path,
+                    event.to_branch,
+                    event.switched_at
+                ])?;
+                imported += 1;
+            }
+            tx.commit()?;
+            Ok(imported)
+        })
+        .context("Failed to import checkout events")?;
+
+        Ok(imported)
+    }
+
+    fn checkout_events(&self, repo_path: &str) -> Result<Vec<CheckoutEvent>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT repo_path, from_branch, to_branch, switched_at
+                 FROM checkout_events
+                 WHERE repo_path = ?1
+                 ORDER BY id DESC",
+            )
+            .context("Failed to prepare query")?;
+
+        let events = stmt
+            .query_map([repo_path], |row| {
+                Ok(CheckoutEvent {
+                    repo_path: row.get(0)?,
+                    from_branch: row.get(1)?,
+                    to_branch: row.get(2)?,
+                    switched_at: row.get(3)?,
+                })
+            })
+            .context("Failed to query checkout events")?
+            .map_while(std::result::Result::ok)
+            .collect();
+
+        Ok(events)
+    }
+
+    fn recent_checkout_events(&self, repo_path: &str, seconds: i64) -> Result<Vec<CheckoutEvent>> {
+        let since = now_timestamp() - seconds;
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT repo_path, from_branch, to_branch, switched_at
+                 FROM checkout_events
+                 WHERE repo_path = ?1 AND switched_at >= ?2
+                 ORDER BY switched_at DESC, id DESC",
+            )
+            .context("Failed to prepare query")?;
+
+        let events = stmt
+            .query_map(rusqlite::params![repo_path, since], |row| {
+                Ok(CheckoutEvent {
+                    repo_path: row.get(0)?,
+                    from_branch: row.get(1)?,
+                    to_branch: row.get(2)?,
+                    switched_at: row.get(3)?,
+                })
+            })
+            .context("Failed to query checkout events")?
+            .map_while(std::result::Result::ok)
+            .collect();
+
+        Ok(events)
+    }
+
+    fn last_checkout(&self, repo_path: &str) -> Result<Option<CheckoutEvent>> {
+        let result = self.conn.query_row(
+            "SELECT repo_path, from_branch, to_branch, switched_at
+             FROM checkout_events
+             WHERE repo_path = ?1
+             ORDER BY id DESC
+             LIMIT 1",
+            [repo_path],
+            |row| {
+                Ok(CheckoutEvent {
+                    repo_path: row.get(0)?,
+                    from_branch: row.get(1)?,
+                    to_branch: row.get(2)?,
+                    switched_at: row.get(3)?,
+                })
+            },
+        );
+
+        match result {
+            Ok(event) => Ok(Some(event)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e).context("Failed to get last checkout"),
+        }
+    }
+
+    fn undo_last_checkout(&self, repo_path: &str) -> Result<Option<CheckoutEvent>> {
+        if !self.options.record {
+            return Ok(None);
+        }
+
+        let event = match self.last_checkout(repo_path)? {
+            Some(event) => event,
+            None => return Ok(None),
+        };
+
+        retry_on_busy(|| {
+            let tx = self.conn.unchecked_transaction()?;
+
+            tx.execute(
+                "DELETE FROM checkout_events WHERE id =
+                    (SELECT MAX(id) FROM checkout_events WHERE repo_path = ?1)",
+                [repo_path],
+            )?;
+
+            tx.execute(
+                "UPDATE branches SET
+                    switch_count = switch_count - 1,
+                    last_used = COALESCE(
+                        (SELECT MAX(switched_at) FROM checkout_events
+                         WHERE repo_path = ?1 AND to_branch = ?2),
+                        last_used)
+                 WHERE repo_path = ?1 AND branch_name = ?2",
+                [repo_path, &event.to_branch],
+            )?;
+            tx.execute(
+                "DELETE FROM branches
+                 WHERE repo_path = ?1 AND branch_name = ?2 AND switch_count <= 0",
+                [repo_path, &event.to_branch],
+            )?;
+
+            if let Some(from) = &event.from_branch {
+                tx.execute(
+                    "DELETE FROM checkout_history WHERE id =
+                        (SELECT MAX(id) FROM checkout_history WHERE repo_path = ?1)
+                     AND branch_name = ?2",
+                    [repo_path, from],
+                )?;
+            }
+
+            tx.execute(
+                "DELETE FROM previous_branch WHERE repo_path = ?1",
+                [repo_path],
+            )?;
+            tx.execute(
+                "INSERT INTO previous_branch (repo_path, branch_name, updated_at)
+                 SELECT repo_path, branch_name, switched_at FROM checkout_history
+                 WHERE repo_path = ?1
+                 ORDER BY id DESC
+                 LIMIT 1",
+                [repo_path],
+            )?;
+
+            tx.commit()
+        })
+        .context("Failed to undo checkout")?;
+
+        Ok(Some(event))
+    }
+
+    fn transitions_from(&self, repo_path: &str, from_branch: &str) -> Result<Vec<Transition>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT to_branch, COUNT(*), MAX(switched_at)
+                 FROM checkout_events
+                 WHERE repo_path = ?1 AND from_branch = ?2 AND to_branch != ?2
+                 GROUP BY to_branch
+                 ORDER BY COUNT(*) DESC, MAX(switched_at) DESC",
+            )
+            .context("Failed to prepare query")?;
+
+        let transitions = stmt
+            .query_map([repo_path, from_branch], |row| {
+                Ok(Transition {
+                    to_branch: row.get(0)?,
+                    count: row.get(1)?,
+                    last_switched: row.get(2)?,
+                })
+            })
+            .context("Failed to query transitions")?
+            .map_while(std::result::Result::ok)
+            .collect();
+
+        Ok(transitions)
+    }
+
+    fn old_events(
+        &self,
+        retention_days: u32,
+        repo_path: Option<&str>,
+    ) -> Result<(usize, Option<i64>)> {
+        if retention_days == 0 {
+            return Ok((0, None));
+        }
+
+        let cutoff = now_timestamp() - (retention_days as i64 * 86400);
+
+        self.conn
+            .query_row(
+                "SELECT COUNT(*), MIN(switched_at) FROM checkout_events
+                 WHERE switched_at < ?1 AND (?2 IS NULL OR repo_path = ?2)",
+                rusqlite::params![cutoff, repo_path],
+                |row| Ok((row.get::<_, i64>(0)? as usize, row.get(1)?)),
+            )
+            .context("Failed to count old checkout events")
+    }
+
+    fn compact_events(&self, retention_days: u32, repo_path: Option<&str>) -> Result<usize> {
+        if retention_days == 0 {
+            return Ok(0);
+        }
+
+        let cutoff = now_timestamp() - (retention_days as i64 * 86400);
+
+        let deleted = retry_on_busy(|| {
+            self.conn.execute(
+                "DELETE FROM checkout_events
+                 WHERE switched_at < ?1 AND (?2 IS NULL OR repo_path = ?2)",
+                rusqlite::params![cutoff, repo_path],
+            )
+        })
+        .context("Failed to compact checkout events")?;
+
+        Ok(deleted)
+    }
+
+    fn branch_records(&self, repo_path: &str) -> Result<Vec<BranchRecord>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT repo_path, branch_name, switch_count, last_used
+                 FROM branches
+                 WHERE repo_path = ?1
+                 ORDER BY last_used DESC",
+            )
+            .context("Failed to prepare query")?;
+
+        let records = stmt
+            .query_map([repo_path], branch_record_from_row)
+            .context("Failed to query branches")?
+            .map_while(std::result::Result::ok)
+            .filter(|r: &BranchRecord| !matcher::is_excluded(&r.branch_name, &self.options.exclude))
+            .collect();
+
+        Ok(records)
+    }
+
+    fn all_records(&self) -> Result<Vec<BranchRecord>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT repo_path, branch_name, switch_count, last_used
+                 FROM branches
+                 ORDER BY last_used DESC",
+            )
+            .context("Failed to prepare query")?;
+
+        let records = stmt
+            .query_map([], branch_record_from_row)
+            .context("Failed to query branches")?
+            .map_while(std::result::Result::ok)
+            .filter(|r: &BranchRecord| !matcher::is_excluded(&r.branch_name, &self.options.exclude))
+            .collect();
+
+        Ok(records)
+    }
+
+    fn stats(&self, filter: &StatsFilter) -> Result<Stats> {
+        let (total_switches, unique_branches, unique_repos) = self
+            .conn
+            .query_row(
+                "SELECT COALESCE(SUM(switch_count), 0), COUNT(*), COUNT(DISTINCT repo_path)
+                 FROM branches
+                 WHERE (?1 IS NULL OR repo_path = ?1) AND (?2 IS NULL OR last_used >= ?2)",
+                rusqlite::params![filter.repo_path, filter.cutoff()],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap_or((0, 0, 0));
+
+        Ok(Stats {
+            total_switches,
+            unique_branches,
+            unique_repos,
+            db_path: self.db_path.clone(),
+        })
+    }
+
+    fn top_records(
+        &self,
+        filter: &StatsFilter,
+        sort: StatsSort,
+        limit: usize,
+    ) -> Result<Vec<BranchRecord>> {
+        let (order, limit) = match sort {
+            StatsSort::Switches => ("switch_count DESC, last_used DESC", limit),
+            StatsSort::Recent => ("last_used DESC", limit),
+            StatsSort::Score => ("last_used DESC", usize::MAX),
+        };
+
+        let mut stmt = self
+            .conn
+            .prepare_cached(&format!(
+                "SELECT repo_path, branch_name, switch_count, last_used
+                 FROM branches
+                 WHERE (?1 IS NULL OR repo_path = ?1) AND (?2 IS NULL OR last_used >= ?2)
+                 ORDER BY {}",
+                order
+            ))
+            .context("Failed to prepare query")?;
+
+        // Excluded branches are skipped while stepping through the rows, so
+        // the limit still yields `limit` records when some are excluded
+        let records = stmt
+            .query_map(
+                rusqlite::params![filter.repo_path, filter.cutoff()],
+                branch_record_from_row,
+            )
+            .context("Failed to query branches")?
+            .map_while(std::result::Result::ok)
+            .filter(|r: &BranchRecord| !matcher::is_excluded(&r.branch_name, &self.options.exclude))
+            .take(limit)
+            .collect();
+
+        Ok(records)
+    }
+
+    fn repo_summaries(&self, filter: &StatsFilter) -> Result<Vec<RepoSummary>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT repo_path, COUNT(*), COALESCE(SUM(switch_count), 0), MAX(last_used)
+                 FROM branches
+                 WHERE (?1 IS NULL OR repo_path = ?1) AND (?2 IS NULL OR last_used >= ?2)
+                 GROUP BY repo_path
+                 ORDER BY 3 DESC, repo_path",
+            )
+            .context("Failed to prepare query")?;
+
+        let summaries = stmt
+            .query_map(
+                rusqlite::params![filter.repo_path, filter.cutoff()],
+                |row| {
+                    Ok(RepoSummary {
+                        repo_path: row.get(0)?,
+                        branches: row.get(1)?,
+                        switches: row.get(2)?,
+                        last_used: row.get(3)?,
+                    })
+                },
+            )
+            .context("Failed to query repositories")?
+            .map_while(std::result::Result::ok)
+            .collect();
+
+        Ok(summaries)
+    }
+
+    fn switch_heatmap(&self, filter: &StatsFilter) -> Result<[[i64; 24]; 7]> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT CAST(strftime('%w', switched_at, 'unixepoch', 'localtime') AS INTEGER),
+                        CAST(strftime('%H', switched_at, 'unixepoch', 'localtime') AS INTEGER),
+                        COUNT(*)
+                 FROM checkout_events
+                 WHERE (?1 IS NULL OR repo_path = ?1) AND (?2 IS NULL OR switched_at >= ?2)
+                 GROUP BY 1, 2",
+            )
+            .context("Failed to prepare query")?;
+
+        let mut heatmap = [[0; 24]; 7];
+        let cells = stmt
+            .query_map(
+                rusqlite::params![filter.repo_path, filter.cutoff()],
+                |row| {
+                    Ok((
+                        row.get::<_, usize>(0)?,
+                        row.get::<_, usize>(1)?,
+                        row.get(2)?,
+                    ))
+                },
+            )
+            .context("Failed to query checkout events")?
+            .map_while(std::result::Result::ok);
+
+        for (weekday, hour, count) in cells {
+            if weekday < 7 && hour < 24 {
+                heatmap[weekday][hour] = count;
+            }
+        }
+
+        Ok(heatmap)
+    }
+
+    fn save_previous_branch(&self, repo_path: &str, branch_name: &str) -> Result<()> {
+        if !self.options.record {
+            return Ok(());
+        }
+
+        let now = now_timestamp();
+
+        retry_on_busy(|| {
+            self.conn
+                .prepare_cached(
+                    "INSERT OR REPLACE INTO previous_branch (repo_path, branch_name, updated_at)
+                     VALUES (?1, ?2, ?3)",
+                )
+                .and_then(|mut stmt| stmt.execute(rusqlite::params![repo_path, branch_name, now]))
+        })
+        .context("Failed to save previous branch")?;
+
+        Ok(())
+    }
+
+    fn previous_branch(&self, repo_path: &str) -> Result<Option<String>> {
+        let result = self.conn.query_row(
+            "SELECT branch_name FROM previous_branch WHERE repo_path = ?1",
+            [repo_path],
+            |row| row.get::<_, String>(0),
+        );
+
+        match result {
+            Ok(branch) => Ok(Some(branch)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e).context("Failed to get previous branch"),
+        }
+    }
+
+    fn push_checkout_history(&self, repo_path: &str, branch_name: &str) -> Result<()> {
+        if !self.options.record {
+            return Ok(());
+        }
+
+        insert_history_entry(&self.conn, repo_path, branch_name, now_timestamp())
+    }
+
+    fn checkout_history(&self, repo_path: &str) -> Result<Vec<HistoryEntry>> {
+        query_history(&self.conn, repo_path)
+    }
+
+    fn create_alias(&self, repo_path: &str, alias: &str, branch_name: &str) -> Result<()> {
+        let now = now_timestamp();
+
+        retry_on_busy(|| {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO aliases (repo_path, alias, branch_name, created_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![repo_path, alias, branch_name, now],
+            )
+        })
+        .context("Failed to create alias")?;
+
+        Ok(())
+    }
+
+    fn alias(&self, repo_path: &str, alias: &str) -> Result<Option<String>> {
+        let result = self
+            .conn
+            .prepare_cached("SELECT branch_name FROM aliases WHERE repo_path = ?1 AND alias = ?2")
+            .and_then(|mut stmt| stmt.query_row([repo_path, alias], |row| row.get::<_, String>(0)));
+
+        match result {
+            Ok(branch) => Ok(Some(branch)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e).context("Failed to get alias"),
+        }
+    }
+
+    fn delete_alias(&self, repo_path: &str, alias: &str) -> Result<()> {
+        retry_on_busy(|| {
+            self.conn.execute(
+                "DELETE FROM aliases WHERE repo_path = ?1 AND alias = ?2",
+                [repo_path, alias],
+            )
+        })
+        .context("Failed to delete alias")?;
+
+        Ok(())
+    }
+
+    fn rename_alias(&self, repo_path: &str, old: &str, new: &str) -> Result<bool> {
+        let changed = retry_on_busy(|| {
+            self.conn.execute(
+                "UPDATE aliases SET alias = ?3 WHERE repo_path = ?1 AND alias = ?2",
+                [repo_path, old, new],
+            )
+        })
+        .context("Failed to rename alias")?;
+
+        Ok(changed > 0)
+    }
+
+    fn aliases(&self, repo_path: &str) -> Result<Vec<Alias>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT repo_path, alias, branch_name, created_at
+                 FROM aliases
+                 WHERE repo_path = ?1
+                 ORDER BY alias",
+            )
+            .context("Failed to prepare query")?;
+
+        let aliases = stmt
+            .query_map([repo_path], |row| {
+                Ok(Alias {
+                    repo_path: row.get(0)?,
+                    alias: row.get(1)?,
+                    branch_name: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            })
+            .context("Failed to query aliases")?
+            .map_while(std::result::Result::ok)
+            .collect();
+
+        Ok(aliases)
+    }
+
+    fn all_aliases(&self) -> Result<BTreeMap<String, Vec<Alias>>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT repo_path, alias, branch_name, created_at
+                 FROM aliases
+                 ORDER BY repo_path, alias",
+            )
+            .context("Failed to prepare query")?;
+
+        let mut by_repo: BTreeMap<String, Vec<Alias>> = BTreeMap::new();
+        let aliases = stmt
+            .query_map([], |row| {
+                Ok(Alias {
+                    repo_path: row.get(0)?,
+                    alias: row.get(1)?,
+                    branch_name: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            })
+            .context("Failed to query aliases")?
+            .map_while(std::result::Result::ok);
+        for alias in aliases {
+            by_repo
+                .entry(alias.repo_path.clone())
+                .or_default()
+                .push(alias);
+        }
+
+        Ok(by_repo)
+    }
+
+    fn aliases_for_branch(&self, repo_path: &str, branch_name: &str) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT alias
+                 FROM aliases
+                 WHERE repo_path = ?1 AND branch_name = ?2
+                 ORDER BY alias",
+            )
+            .context("Failed to prepare query")?;
+
+        let aliases = stmt
+            .query_map([repo_path, branch_name], |row| row.get::<_, String>(0))
+            .context("Failed to query aliases")?
+            .map_while(std::result::Result::ok)
+            .collect();
+
+        Ok(aliases)
+    }
+
+    fn pin_branch(&self, repo_path: &str, branch_name: &str) -> Result<()> {
+        let now = now_timestamp();
+
+        retry_on_busy(|| {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO pinned_branches (repo_path, branch_name, pinned_at)
+                 VALUES (?1, ?2, ?3)",
+                rusqlite::params![repo_path, branch_name, now],
+            )
+        })
+        .context("Failed to pin branch")?;
+
+        Ok(())
+    }
+
+    fn unpin_branch(&self, repo_path: &str, branch_name: &str) -> Result<bool> {
+        let deleted = retry_on_busy(|| {
+            self.conn.execute(
+                "DELETE FROM pinned_branches WHERE repo_path = ?1 AND branch_name = ?2",
+                [repo_path, branch_name],
+            )
+        })
+        .context("Failed to unpin branch")?;
+
+        Ok(deleted > 0)
+    }
+
+    fn pinned_branches(&self, repo_path: &str) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT branch_name FROM pinned_branches
+                 WHERE repo_path = ?1
+                 ORDER BY pinned_at, rowid",
+            )
+            .context("Failed to prepare query")?;
+
+        let pinned = stmt
+            .query_map([repo_path], |row| row.get::<_, String>(0))
+            .context("Failed to query pinned branches")?
+            .map_while(std::result::Result::ok)
+            .collect();
+
+        Ok(pinned)
+    }
+
+    fn set_branch_note(&self, repo_path: &str, branch_name: &str, note: &str) -> Result<()> {
+        let now = now_timestamp();
+
+        retry_on_busy(|| {
+            self.conn.execute(
+                "INSERT INTO branch_notes (repo_path, branch_name, note, updated_at)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(repo_path, branch_name) DO UPDATE SET
+                    note = excluded.note,
+                    updated_at = excluded.updated_at",
+                rusqlite::params![repo_path, branch_name, note, now],
+            )
+        })
+        .context("Failed to save branch note")?;
+
+        Ok(())
+    }
+
+    fn remove_branch_note(&self, repo_path: &str, branch_name: &str) -> Result<bool> {
+        let deleted = retry_on_busy(|| {
+            self.conn.execute(
+                "DELETE FROM branch_notes WHERE repo_path = ?1 AND branch_name = ?2",
+                [repo_path, branch_name],
+            )
+        })
+        .context("Failed to remove branch note")?;
+
+        Ok(deleted > 0)
+    }
+
+    fn branch_notes(&self, repo_path: &str) -> Result<HashMap<String, String>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT branch_name, note FROM branch_notes WHERE repo_path = ?1")
+            .context("Failed to prepare query")?;
+
+        let notes = stmt
+            .query_map([repo_path], |row| Ok((row.get(0)?, row.get(1)?)))
+            .context("Failed to query branch notes")?
+            .map_while(std::result::Result::ok)
+            .collect();
+
+        Ok(notes)
+    }
+
+    fn add_to_group(
+        &self,
+        repo_path: &str,
+        group_name: &str,
+        branch_names: &[&str],
+    ) -> Result<usize> {
+        let now = now_timestamp();
+
+        let added = retry_on_busy(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            let mut added = 0;
+            for branch_name in branch_names {
+                added += tx.execute(
+                    "INSERT OR IGNORE INTO branch_groups
+                        (repo_path, group_name, branch_name, added_at)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![repo_path, group_name, branch_name, now],
+                )?;
+            }
+            tx.commit()?;
+            Ok(added)
+        })
+        .context("Failed to add to group")?;
+
+        Ok(added)
+    }
+
+    fn remove_from_group(
+        &self,
+        repo_path: &str,
+        group_name: &str,
+        branch_names: &[&str],
+    ) -> Result<usize> {
+        let removed = retry_on_busy(|| {
+            if branch_names.is_empty() {
+                return self.conn.execute(
+                    "DELETE FROM branch_groups WHERE repo_path = ?1 AND group_name = ?2",
+                    [repo_path, group_name],
+                );
+            }
+
+            let tx = self.conn.unchecked_transaction()?;
+            let mut removed = 0;
+            for branch_name in branch_names {
+                removed += tx.execute(
+                    "DELETE FROM branch_groups
+                     WHERE repo_path = ?1 AND group_name = ?2 AND branch_name = ?3",
+                    [repo_path, group_name, branch_name],
+                )?;
+            }
+            tx.commit()?;
+            Ok(removed)
+        })
+        .context("Failed to remove from group")?;
+
+        Ok(removed)
+    }
+
+    fn branch_groups(&self, repo_path: &str) -> Result<BTreeMap<String, Vec<String>>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT group_name, branch_name FROM branch_groups
+                 WHERE repo_path = ?1
+                 ORDER BY added_at, rowid",
+            )
+            .context("Failed to prepare query")?;
+
+        let rows = stmt
+            .query_map([repo_path], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .context("Failed to query branch groups")?
+            .map_while(std::result::Result::ok);
+
+        let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (group_name, branch_name) in rows {
+            groups.entry(group_name).or_default().push(branch_name);
+        }
+
+        Ok(groups)
+    }
+
+    fn cached_branches(&self, repo_path: &str, fingerprint: &str) -> Result<Option<Vec<String>>> {
+        let result = self
+            .conn
+            .prepare_cached(
+                "SELECT branches FROM branch_cache WHERE repo_path = ?1 AND fingerprint = ?2",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_row([repo_path, fingerprint], |row| row.get::<_, String>(0))
+            });
+
+        match result {
+            // Branch names can't contain a newline
+            Ok(list) => Ok(Some(
+                list.lines()
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            )),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e).context("Failed to query branch cache"),
+        }
+    }
+
+    fn cache_branches(
+        &self,
+        repo_path: &str,
+        fingerprint: &str,
+        branches: &[String],
+    ) -> Result<()> {
+        let now = now_timestamp();
+        let list = branches.join("\n");
+
+        retry_on_busy(|| {
+            self.conn.execute(
+                "INSERT INTO branch_cache (repo_path, fingerprint, branches, updated_at)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(repo_path) DO UPDATE SET
+                    fingerprint = excluded.fingerprint,
+                    branches = excluded.branches,
+                    updated_at = excluded.updated_at",
+                rusqlite::params![repo_path, fingerprint, list, now],
+            )
+        })
+        .context("Failed to cache branch list")?;
+
+        Ok(())
+    }
+
+    fn set_stack_parent(&self, repo_path: &str, branch_name: &str, parent: &str) -> Result<()> {
+        let now = now_timestamp();
+
+        retry_on_busy(|| {
+            self.conn.execute(
+                "INSERT INTO stack_parents (repo_path, branch_name, parent_branch, created_at)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(repo_path, branch_name) DO UPDATE SET
+                    parent_branch = excluded.parent_branch,
+                    created_at = excluded.created_at",
+                rusqlite::params![repo_path, branch_name, parent, now],
+            )
+        })
+        .context("Failed to save stack parent")?;
+
+        Ok(())
+    }
+
+    fn stack_links(&self, repo_path: &str) -> Result<Vec<StackLink>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT branch_name, parent_branch FROM stack_parents
+                 WHERE repo_path = ?1
+                 ORDER BY branch_name",
+            )
+            .context("Failed to prepare query")?;
+
+        let links = stmt
+            .query_map([repo_path], |row| {
+                Ok(StackLink {
+                    branch: row.get(0)?,
+                    parent: row.get(1)?,
+                })
+            })
+            .context("Failed to query stacks")?
+            .map_while(std::result::Result::ok)
+            .collect();
+
+        Ok(links)
+    }
+
+    fn unstack_branch(&self, repo_path: &str, branch_name: &str) -> Result<bool> {
+        let changed = retry_on_busy(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            let changed = splice_out_of_stack(&tx, repo_path, branch_name)?;
+            tx.commit()?;
+            Ok(changed)
+        })
+        .context("Failed to update stacks")?;
+
+        Ok(changed > 0)
+    }
+
+    fn rename_stack_branch(&self, repo_path: &str, old: &str, new: &str) -> Result<()> {
+        retry_on_busy(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            rename_in_stacks(&tx, repo_path, old, new)?;
+            tx.commit()
+        })
+        .context("Failed to rename branch in stacks")?;
+
+        Ok(())
+    }
+
+    fn rename_branch_data(&self, repo_path: &str, old: &str, new: &str) -> Result<bool> {
+        let moved = retry_on_busy(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            let moved = move_branch_rows(&tx, repo_path, old, new)?;
+            tx.commit()?;
+            Ok(moved)
+        })
+        .context("Failed to move branch history")?;
+
+        Ok(moved > 0)
+    }
+
+    fn merge_repo(&self, from: &str, to: &str) -> Result<usize> {
+        retry_on_busy(|| merge_repo_rows(&self.conn, from, to))
+            .context("Failed to merge repository history")
+    }
+
+    fn export_history(&self) -> Result<HistoryExport> {
+        let branches = self
+            .conn
+            .prepare(
+                "SELECT repo_path, branch_name, switch_count, last_used
+                 FROM branches ORDER BY repo_path, branch_name",
+            )?
+            .query_map([], |row| {
+                Ok(ExportedBranch {
+                    repo_path: row.get(0)?,
+                    branch_name: row.get(1)?,
+                    switch_count: row.get(2)?,
+                    last_used: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()
+            .context("Failed to export branches")?;
+
+        let aliases = self
+            .conn
+            .prepare(
+                "SELECT repo_path, alias, branch_name, created_at
+                 FROM aliases ORDER BY repo_path, alias",
+            )?
+            .query_map([], |row| {
+                Ok(ExportedAlias {
+                    repo_path: row.get(0)?,
+                    alias: row.get(1)?,
+                    branch_name: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()
+            .context("Failed to export aliases")?;
+
+        let previous_branches = self
+            .conn
+            .prepare(
+                "SELECT repo_path, branch_name, updated_at
+                 FROM previous_branch ORDER BY repo_path",
+            )?
+            .query_map([], |row| {
+                Ok(ExportedPreviousBranch {
+                    repo_path: row.get(0)?,
+                    branch_name: row.get(1)?,
+                    updated_at: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()
+            .context("Failed to export previous branches")?;
+
+        Ok(HistoryExport {
+            version: EXPORT_VERSION,
+            branches,
+            aliases,
+            previous_branches,
+        })
+    }
+
+    fn import_history(&self, export: &HistoryExport, merge: bool) -> Result<usize> {
+        retry_on_busy(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            import_rows(&tx, export, merge)?;
+            tx.commit()
+        })
+        .context("Failed to import history")?;
+
+        Ok(export.len())
+    }
+
+    fn import_synced(
+        &self,
+        delta: &HistoryExport,
+        baseline_key: &str,
+        baseline: &str,
+    ) -> Result<()> {
+        retry_on_busy(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            import_rows(&tx, delta, true)?;
+            tx.execute("DELETE FROM branches WHERE switch_count <= 0", [])?;
+            tx.execute(
+                "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+                [baseline_key, baseline],
+            )?;
+            tx.commit()
+        })
+        .context("Failed to merge synced history")
+    }
+
+    fn snapshot(&self) -> Result<Snapshot> {
+        let conn = &self.conn;
+        Ok(Snapshot {
+            branches: select_all(
+                conn,
+                "SELECT repo_path, branch_name, switch_count, last_used FROM branches",
+                |row| {
+                    Ok(ExportedBranch {
+                        repo_path: row.get(0)?,
+                        branch_name: row.get(1)?,
+                        switch_count: row.get(2)?,
+                        last_used: row.get(3)?,
+                    })
+                },
+            )?,
+            events: select_all(
+                conn,
+                "SELECT repo_path, from_branch, to_branch, switched_at FROM checkout_events",
+                |row| {
+                    Ok(CheckoutEvent {
+                        repo_path: row.get(0)?,
+                        from_branch: row.get(1)?,
+                        to_branch: row.get(2)?,
+                        switched_at: row.get(3)?,
+                    })
+                },
+            )?,
+            history: select_all(
+                conn,
+                "SELECT repo_path, branch_name, switched_at FROM checkout_history",
+                |row| {
+                    Ok(HistoryRow {
+                        repo_path: row.get(0)?,
+                        branch_name: row.get(1)?,
+                        switched_at: row.get(2)?,
+                    })
+                },
+            )?,
+            previous_branches: select_all(
+                conn,
+                "SELECT repo_path, branch_name, updated_at FROM previous_branch",
+                |row| {
+                    Ok(ExportedPreviousBranch {
+                        repo_path: row.get(0)?,
+                        branch_name: row.get(1)?,
+                        updated_at: row.get(2)?,
+                    })
+                },
+            )?,
+            aliases: select_all(
+                conn,
+                "SELECT repo_path, alias, branch_name, created_at FROM aliases",
+                |row| {
+                    Ok(ExportedAlias {
+                        repo_path: row.get(0)?,
+                        alias: row.get(1)?,
+                        branch_name: row.get(2)?,
+                        created_at: row.get(3)?,
+                    })
+                },
+            )?,
+            pins: select_all(
+                conn,
+                "SELECT repo_path, branch_name, pinned_at FROM pinned_branches",
+                |row| {
+                    Ok(PinRow {
+                        repo_path: row.get(0)?,
+                        branch_name: row.get(1)?,
+                        pinned_at: row.get(2)?,
+                    })
+                },
+            )?,
+            notes: select_all(
+                conn,
+                "SELECT repo_path, branch_name, note, updated_at FROM branch_notes",
+                |row| {
+                    Ok(NoteRow {
+                        repo_path: row.get(0)?,
+                        branch_name: row.get(1)?,
+                        note: row.get(2)?,
+                        updated_at: row.get(3)?,
+                    })
+                },
+            )?,
+            groups: select_all(
+                conn,
+                "SELECT repo_path, group_name, branch_name, added_at FROM branch_groups",
+                |row| {
+                    Ok(GroupRow {
+                        repo_path: row.get(0)?,
+                        group_name: row.get(1)?,
+                        branch_name: row.get(2)?,
+                        added_at: row.get(3)?,
+                    })
+                },
+            )?,
+            stacks: select_all(
+                conn,
+                "SELECT repo_path, branch_name, parent_branch, created_at FROM stack_parents",
+                |row| {
+                    Ok(StackRow {
+                        repo_path: row.get(0)?,
+                        branch_name: row.get(1)?,
+                        parent_branch: row.get(2)?,
+                        created_at: row.get(3)?,
+                    })
+                },
+            )?,
+            meta: select_all(conn, "SELECT key, value FROM meta", |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .into_iter()
+            .collect(),
+        })
+    }
+
+    fn restore_snapshot(&self, snapshot: &Snapshot) -> Result<()> {
+        retry_on_busy(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            for table in [
+                "branches",
+                "checkout_events",
+                "checkout_history",
+                "previous_branch",
+                "aliases",
+                "pinned_branches",
+                "branch_notes",
+                "branch_groups",
+                "stack_parents",
+                "branch_cache",
+                "meta",
+            ] {
+                tx.execute(&format!("DELETE FROM {}", table), [])?;
+            }
+
+            for b in &snapshot.branches {
+                tx.execute(
+                    "INSERT INTO branches (repo_path, branch_name, switch_count, last_used)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![b.repo_path, b.branch_name, b.switch_count, b.last_used],
+                )?;
+            }
+            for e in &snapshot.events {
+                tx.execute(
+                    "INSERT INTO checkout_events (repo_path, from_branch, to_branch, switched_at)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![e.repo_path, e.from_branch, e.to_branch, e.switched_at],
+                )?;
+            }
+            for h in &snapshot.history {
+                tx.execute(
+                    "INSERT INTO checkout_history (repo_path, branch_name, switched_at)
+                     VALUES (?1, ?2, ?3)",
+                    rusqlite::params![h.repo_path, h.branch_name, h.switched_at],
+                )?;
+            }
+            for p in &snapshot.previous_branches {
+                tx.execute(
+                    "INSERT INTO previous_branch (repo_path, branch_name, updated_at)
+                     VALUES (?1, ?2, ?3)",
+                    rusqlite::params![p.repo_path, p.branch_name, p.updated_at],
+                )?;
+            }
+            for a in &snapshot.aliases {
+                tx.execute(
+                    "INSERT INTO aliases (repo_path, alias, branch_name, created_at)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![a.repo_path, a.alias, a.branch_name, a.created_at],
+                )?;
+            }
+            for p in &snapshot.pins {
+                tx.execute(
+                    "INSERT INTO pinned_branches (repo_path, branch_name, pinned_at)
+                     VALUES (?1, ?2, ?3)",
+                    rusqlite::params![p.repo_path, p.branch_name, p.pinned_at],
+                )?;
+            }
+            for n in &snapshot.notes {
+                tx.execute(
+                    "INSERT INTO branch_notes (repo_path, branch_name, note, updated_at)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![n.repo_path, n.branch_name, n.note, n.updated_at],
+                )?;
+            }
+            for g in &snapshot.groups {
+                tx.execute(
+                    "INSERT INTO branch_groups (repo_path, group_name, branch_name, added_at)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![g.repo_path, g.group_name, g.branch_name, g.added_at],
+                )?;
+            }
+            for s in &snapshot.stacks {
+                tx.execute(
+                    "INSERT INTO stack_parents (repo_path, branch_name, parent_branch, created_at)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![s.repo_path, s.branch_name, s.parent_branch, s.created_at],
+                )?;
+            }
+            for (key, value) in &snapshot.meta {
+                tx.execute(
+                    "INSERT INTO meta (key, value) VALUES (?1, ?2)",
+                    [key, value],
+                )?;
+            }
+            tx.commit()
+        })
+        .context("Failed to replace history")
+    }
+
+    fn forget_branches(&self, repo_path: &str, branch_names: &[&str]) -> Result<()> {
+        remove_branch_data(&self.conn, repo_path, branch_names)
+    }
+
+    fn recorded_branches(&self, repo_path: &str) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT branch_name FROM branches WHERE repo_path = ?1
+                 UNION SELECT branch_name FROM aliases WHERE repo_path = ?1
+                 UNION SELECT branch_name FROM pinned_branches WHERE repo_path = ?1
+                 UNION SELECT branch_name FROM branch_notes WHERE repo_path = ?1
+                 UNION SELECT branch_name FROM branch_groups WHERE repo_path = ?1
+                 UNION SELECT branch_name FROM previous_branch WHERE repo_path = ?1
+                 UNION SELECT branch_name FROM checkout_history WHERE repo_path = ?1
+                 UNION SELECT to_branch FROM checkout_events WHERE repo_path = ?1
+                 ORDER BY 1",
+            )
+            .context("Failed to prepare query")?;
+
+        let branches = stmt
+            .query_map([repo_path], |row| row.get(0))
+            .context("Failed to query recorded branches")?
+            .map_while(std::result::Result::ok)
+            .collect();
+
+        Ok(branches)
+    }
+
+    fn repo_row_count(&self, repo_path: &str) -> Result<usize> {
+        count_repo_rows(&self.conn, repo_path).context("Failed to count repository rows")
+    }
+
+    fn forget_repo(&self, repo_path: &str) -> Result<usize> {
+        retry_on_busy(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            let mut removed = 0;
+            for table in REPO_TABLES {
+                removed += tx.execute(
+                    &format!("DELETE FROM {} WHERE repo_path = ?1", table),
+                    [repo_path],
+                )?;
+            }
+            tx.execute("DELETE FROM branch_cache WHERE repo_path = ?1", [repo_path])?;
+            tx.commit()?;
+            Ok(removed)
+        })
+        .context("Failed to forget repository")
+    }
+
+    fn old_records(&self, max_age_days: i64, repo_path: Option<&str>) -> Result<Vec<BranchRecord>> {
+        let cutoff = now_timestamp() - (max_age_days * 86400);
+
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT repo_path, branch_name, switch_count, last_used
+                 FROM branches
+                 WHERE last_used < ?1 AND (?2 IS NULL OR repo_path = ?2)
+                 ORDER BY last_used",
+            )
+            .context("Failed to prepare query")?;
+
+        let records = stmt
+            .query_map(rusqlite::params![cutoff, repo_path], branch_record_from_row)
+            .context("Failed to query old branch records")?
+            .map_while(std::result::Result::ok)
+            .collect();
+
+        Ok(records)
+    }
+
+    fn cleanup_old_records(&self, max_age_days: i64, repo_path: Option<&str>) -> Result<usize> {
+        let cutoff = now_timestamp() - (max_age_days * 86400);
+
+        let deleted = retry_on_busy(|| {
+            self.conn.execute(
+                "DELETE FROM branches WHERE last_used < ?1 AND (?2 IS NULL OR repo_path = ?2)",
+                rusqlite::params![cutoff, repo_path],
+            )
+        })
+        .context("Failed to cleanup old branch records")?;
+
+        Ok(deleted)
+    }
+
+    fn trim_records(
+        &self,
+        max_records: usize,
+        max_age_days: i64,
+        score: &dyn Fn(&BranchRecord) -> f64,
+    ) -> Result<usize> {
+        let (count, oldest): (i64, Option<i64>) = self
+            .conn
+            .query_row("SELECT COUNT(*), MIN(last_used) FROM branches", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .context("Failed to count branch records")?;
+        if !over_retention(count as usize, oldest, max_records, max_age_days) {
+            return Ok(0);
+        }
+
+        let records = select_all(
+            &self.conn,
+            "SELECT repo_path, branch_name, switch_count, last_used FROM branches",
+            |row| {
+                Ok(BranchRecord {
+                    repo_path: row.get(0)?,
+                    branch_name: row.get(1)?,
+                    switch_count: row.get(2)?,
+                    last_used: row.get(3)?,
+                })
+            },
+        )?;
+        let trimmed = records_to_trim(records, max_records, max_age_days, score);
+
+        retry_on_busy(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            {
+                let mut delete = tx.prepare_cached(
+                    "DELETE FROM branches WHERE repo_path = ?1 AND branch_name = ?2",
+                )?;
+                for record in &trimmed {
+                    delete.execute([&record.repo_path, &record.branch_name])?;
+                }
+            }
+            tx.commit()
+        })
+        .context("Failed to trim branch records")?;
+
+        Ok(trimmed.len())
+    }
+
+    fn cleanup_deleted_branches(&self, repo_path: Option<&str>) -> Result<Vec<StaleRecords>> {
+        let stale = self.deleted_branch_records(repo_path)?;
+        if stale.is_empty() {
+            return Ok(stale);
+        }
+
+        retry_on_busy(|| {
+            let tx = self.conn.unchecked_transaction()?;
+
+            {
+                let mut delete_record = tx.prepare_cached(
+                    "DELETE FROM branches WHERE repo_path = ?1 AND branch_name = ?2",
+                )?;
+                let mut delete_aliases = tx.prepare_cached(
+                    "DELETE FROM aliases WHERE repo_path = ?1 AND branch_name = ?2",
+                )?;
+
+                for repo in &stale {
+                    for (old, new) in &repo.renamed {
+                        move_branch_rows(&tx, &repo.repo_path, old, new)?;
+                    }
+                    if repo.repo_gone {
+                        tx.execute(
+                            "DELETE FROM aliases WHERE repo_path = ?1",
+                            [&repo.repo_path],
+                        )?;
+                    }
+                    for record in &repo.records {
+                        delete_record.execute([&record.repo_path, &record.branch_name])?;
+                        delete_aliases.execute([&record.repo_path, &record.branch_name])?;
+                    }
+                }
+            }
+
+            tx.commit()
+        })
+        .context("Failed to clean up deleted branches")?;
+
+        Ok(stale)
+    }
+
+    fn maintenance_due(&self, every: u32) -> Result<bool> {
+        if every == 0 {
+            return Ok(false);
+        }
+
+        let invocations: i64 = retry_on_busy(|| {
+            self.conn
+                .prepare_cached(
+                    "INSERT INTO meta (key, value) VALUES ('invocations', '1')
+                     ON CONFLICT(key) DO UPDATE SET value = CAST(value AS INTEGER) + 1
+                     RETURNING CAST(value AS INTEGER)",
+                )?
+                .query_row([], |row| row.get(0))
+        })
+        .context("Failed to count invocation")?;
+
+        Ok(invocations % every as i64 == 0)
+    }
+
+    fn meta(&self, key: &str) -> Result<Option<String>> {
+        let result = self
+            .conn
+            .prepare_cached("SELECT value FROM meta WHERE key = ?1")
+            .and_then(|mut stmt| stmt.query_row([key], |row| row.get::<_, String>(0)));
+
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e).context("Failed to read metadata"),
+        }
+    }
+
+    fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+        retry_on_busy(|| {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+                [key, value],
+            )
+        })
+        .context("Failed to write metadata")?;
+
+        Ok(())
+    }
+
+    fn meta_with_prefix(&self, prefix: &str) -> Result<Vec<(String, String)>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT key, value FROM meta
+                 WHERE substr(key, 1, length(?1)) = ?1
+                 ORDER BY key",
+            )
+            .context("Failed to prepare query")?;
+
+        let entries = stmt
+            .query_map([prefix], |row| Ok((row.get(0)?, row.get(1)?)))
+            .context("Failed to query metadata")?
+            .map_while(std::result::Result::ok)
+            .collect();
+
+        Ok(entries)
+    }
+
+    fn quick_optimize(&self) -> Result<()> {
+        self.conn
+            .execute_batch("PRAGMA optimize")
+            .context("Failed to run PRAGMA optimize")
+    }
+
+    fn optimize(&self) -> Result<()> {
+        self.conn
+            .execute("VACUUM", [])
+            .context("Failed to run VACUUM")?;
+        self.conn
+            .execute("ANALYZE", [])
+            .context("Failed to run ANALYZE")?;
+        Ok(())
+    }
+
+    fn database_size(&self) -> Result<u64> {
+        let metadata =
+            std::fs::metadata(&self.db_path).context("Failed to get database metadata")?;
+        Ok(metadata.len())
+    }
+}
+
+fn branch_record_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<BranchRecord> {
+    Ok(BranchRecord {
+        repo_path: row.get(0)?,
+        branch_name: row.get(1)?,
+        switch_count: row.get(2)?,
+        last_used: row.get(3)?,
+    })
+}
+
+fn insert_history_entry(
+    conn: &Connection,
+    repo_path: &str,
+    branch_name: &str,
+    switched_at: i64,
+) -> Result<()> {
+    retry_on_busy(|| {
+        conn.prepare_cached(
+            "INSERT INTO checkout_history (repo_path, branch_name, switched_at)
+             VALUES (?1, ?2, ?3)",
+        )
+        .and_then(|mut stmt| stmt.execute(rusqlite::params![repo_path, branch_name, switched_at]))
+    })
+    .context("Failed to record checkout history")?;
+
+    retry_on_busy(|| {
+        conn.prepare_cached(
+            "DELETE FROM checkout_history
+             WHERE repo_path = ?1 AND id NOT IN (
+                SELECT id FROM checkout_history WHERE repo_path = ?1
+                ORDER BY id DESC LIMIT ?2
+             )",
+        )
+        .and_then(|mut stmt| stmt.execute(rusqlite::params![repo_path, MAX_HISTORY_ENTRIES as i64]))
+    })
+    .context("Failed to trim checkout history")?;
+
+    Ok(())
+}
+
+fn query_history(conn: &Connection, repo_path: &str) -> Result<Vec<HistoryEntry>> {
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT branch_name, MAX(switched_at)
+             FROM checkout_history
+             WHERE repo_path = ?1
+             GROUP BY branch_name
+             ORDER BY MAX(id) DESC",
+        )
+        .context("Failed to prepare query")?;
+
+    let entries = stmt
+        .query_map([repo_path], |row| {
+            Ok(HistoryEntry {
+                branch_name: row.get(0)?,
+                switched_at: row.get(1)?,
+            })
+        })
+        .context("Failed to query checkout history")?
+        .map_while(std::result::Result::ok)
+        .collect();
+
+    Ok(entries)
+}
+
+fn import_rows(conn: &Connection, export: &HistoryExport, merge: bool) -> rusqlite::Result<()> {
+    let (branch_sql, alias_sql, previous_sql) = if merge {
+        (
+            "INSERT INTO branches (repo_path, branch_name, switch_count, last_used)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(repo_path, branch_name) DO UPDATE SET
+                 switch_count = switch_count + excluded.switch_count,
+                 last_used = MAX(last_used, excluded.last_used)",
+            "INSERT INTO aliases (repo_path, alias, branch_name, created_at)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(repo_path, alias) DO UPDATE SET
+                 branch_name = excluded.branch_name,
+                 created_at = excluded.created_at
+             WHERE excluded.created_at > created_at",
+            "INSERT INTO previous_branch (repo_path, branch_name, updated_at)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT(repo_path) DO UPDATE SET
+                 branch_name = excluded.branch_name,
+                 updated_at = excluded.updated_at
+             WHERE excluded.updated_at > updated_at",
+        )
+    } else {
+        (
+            "INSERT OR REPLACE INTO branches (repo_path, branch_name, switch_count, last_used)
+             VALUES (?1, ?2, ?3, ?4)",
+            "INSERT OR REPLACE INTO aliases (repo_path, alias, branch_name, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            "INSERT OR REPLACE INTO previous_branch (repo_path, branch_name, updated_at)
+             VALUES (?1, ?2, ?3)",
+        )
+    };
+
+    for b in &export.branches {
+        conn.execute(
+            branch_sql,
+            rusqlite::params![b.repo_path, b.branch_name, b.switch_count, b.last_used],
+        )?;
+    }
+    for a in &export.aliases {
+        conn.execute(
+            alias_sql,
+            rusqlite::params![a.repo_path, a.alias, a.branch_name, a.created_at],
+        )?;
+    }
+    for p in &export.previous_branches {
+        conn.execute(
+            previous_sql,
+            rusqlite::params![p.repo_path, p.branch_name, p.updated_at],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Rename a branch in the stacks, as the stacked branch and as a parent
+fn rename_in_stacks(
+    conn: &Connection,
+    repo_path: &str,
+    old: &str,
+    new: &str,
+) -> rusqlite::Result<usize> {
+    let renamed = conn.execute(
+        "UPDATE OR REPLACE stack_parents SET branch_name = ?3
+         WHERE repo_path = ?1 AND branch_name = ?2",
+        [repo_path, old, new],
+    )?;
+    let reparented = conn.execute(
+        "UPDATE stack_parents SET parent_branch = ?3
+         WHERE repo_path = ?1 AND parent_branch = ?2",
+        [repo_path, old, new],
+    )?;
+    Ok(renamed + reparented)
+}
+
+/// Move every row about branch `old` to `new`. Returns the number of rows
+/// changed.
+fn move_branch_rows(
+    conn: &Connection,
+    repo_path: &str,
+    old: &str,
+    new: &str,
+) -> rusqlite::Result<usize> {
+    if old == new {
+        return Ok(0);
+    }
+
+    let mut moved = conn.execute(
+        "INSERT INTO branches (repo_path, branch_name, switch_count, last_used)
+         SELECT repo_path, ?3, switch_count, last_used FROM branches
+         WHERE repo_path = ?1 AND branch_name = ?2
+         ON CONFLICT(repo_path, branch_name) DO UPDATE SET
+            switch_count = switch_count + excluded.switch_count,
+            last_used = MAX(last_used, excluded.last_used)",
+        [repo_path, old, new],
+    )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO pinned_branches (repo_path, branch_name, pinned_at)
+         SELECT repo_path, ?3, pinned_at FROM pinned_branches
+         WHERE repo_path = ?1 AND branch_name = ?2",
+        [repo_path, old, new],
+    )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO branch_notes (repo_path, branch_name, note, updated_at)
+         SELECT repo_path, ?3, note, updated_at FROM branch_notes
+         WHERE repo_path = ?1 AND branch_name = ?2",
+        [repo_path, old, new],
+    )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO branch_groups (repo_path, group_name, branch_name, added_at)
+         SELECT repo_path, group_name, ?3, added_at FROM branch_groups
+         WHERE repo_path = ?1 AND branch_name = ?2",
+        [repo_path, old, new],
+    )?;
+    for table in [
+        "branches",
+        "pinned_branches",
+        "branch_notes",
+        "branch_groups",
+    ] {
+        moved += conn.execute(
+            &format!(
+                "DELETE FROM {} WHERE repo_path = ?1 AND branch_name = ?2",
+                table
+            ),
+            [repo_path, old],
+        )?;
+    }
+
+    for (table, column) in [
+        ("aliases", "branch_name"),
+        ("previous_branch", "branch_name"),
+        ("checkout_history", "branch_name"),
+        ("checkout_events", "to_branch"),
+        ("checkout_events", "from_branch"),
+    ] {
+        moved += conn.execute(
+            &format!(
+                "UPDATE {0} SET {1} = ?3 WHERE repo_path = ?1 AND {1} = ?2",
+                table, column
+            ),
+            [repo_path, old, new],
+        )?;
+    }
+
+    Ok(moved + rename_in_stacks(conn, repo_path, old, new)?)
+}
+
+/// Remove a branch from its stack, moving the branches stacked on it to its
+/// parent. Returns the number of stack rows changed.
+fn splice_out_of_stack(
+    conn: &Connection,
+    repo_path: &str,
+    branch_name: &str,
+) -> rusqlite::Result<usize> {
+    let moved = conn.execute(
+        "UPDATE stack_parents SET parent_branch = (
+            SELECT parent_branch FROM stack_parents WHERE repo_path = ?1 AND branch_name = ?2
+         )
+         WHERE repo_path = ?1 AND parent_branch = ?2
+           AND EXISTS (
+            SELECT 1 FROM stack_parents WHERE repo_path = ?1 AND branch_name = ?2
+         )",
+        [repo_path, branch_name],
+    )?;
+    let removed = conn.execute(
+        "DELETE FROM stack_parents
+         WHERE repo_path = ?1 AND (branch_name = ?2 OR parent_branch = ?2)",
+        [repo_path, branch_name],
+    )?;
+    Ok(moved + removed)
+}
+
+fn remove_branch_data(conn: &Connection, repo_path: &str, branch_names: &[&str]) -> Result<()> {
+    // Retry the whole transaction: a busy error part-way through rolls it back
+    retry_on_busy(|| {
+        let tx = conn.unchecked_transaction()?;
+
+        for branch_name in branch_names {
+            splice_out_of_stack(&tx, repo_path, branch_name)?;
+
+            for table in [
+                "branches",
+                "aliases",
+                "checkout_history",
+                "previous_branch",
+                "pinned_branches",
+                "branch_notes",
+                "branch_groups",
+            ] {
+                tx.execute(
+                    &format!(
+                        "DELETE FROM {} WHERE repo_path = ?1 AND branch_name = ?2",
+                        table
+                    ),
+                    [repo_path, branch_name],
+                )?;
+            }
+
+            tx.execute(
+                "DELETE FROM checkout_events
+                 WHERE repo_path = ?1 AND (to_branch = ?2 OR from_branch = ?2)",
+                [repo_path, branch_name],
+            )?;
+        }
+
+        tx.commit()
+    })
+    .context("Failed to remove branch data")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{JsonStore, Storage};
+
+    // Generate a unique repo path for testing to avoid conflicts
+    fn unique_repo_path() -> String {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let id = COUNTER.fetch_add(1, Ordering::SeqCst);
+        format!("/test/repo/{}", id)
+    }
+
+    // Test-specific versions that use a provided connection
+    fn do_record_checkout(conn: &Connection, repo_path: &str, branch_name: &str) -> Result<()> {
+        let now = now_timestamp();
+
+        conn.execute(
+            "INSERT INTO branches (repo_path, branch_name, switch_count, last_used)
+             VALUES (?1, ?2, 1, ?3)
+             ON CONFLICT(repo_path, branch_name) DO UPDATE SET
+                switch_count = switch_count + 1,
+                last_used = ?3",
+            [repo_path, branch_name, &now.to_string()],
+        )
+        .context("Failed to record checkout")?;
+
+        Ok(())
+    }
+
+    fn do_get_branch_records(conn: &Connection, repo_path: &str) -> Result<Vec<BranchRecord>> {
+        let mut stmt = conn
+            .prepare(
+                "SELECT repo_path, branch_name, switch_count, last_used
+                 FROM branches
+                 WHERE repo_path = ?1
+                 ORDER BY last_used DESC",
+            )
+            .context("Failed to prepare query")?;
+
+        let records = stmt
+            .query_map([repo_path], |row| {
+                Ok(BranchRecord {
+                    repo_path: row.get(0)?,
+                    branch_name: row.get(1)?,
+                    switch_count: row.get(2)?,
+                    last_used: row.get(3)?,
+                })
+            })
+            .context("Failed to query branches")?
+            .map_while(Result::ok)
+            .collect();
+
+        Ok(records)
+    }
+
+    fn do_get_all_records(conn: &Connection) -> Result<Vec<BranchRecord>> {
+        let mut stmt = conn
+            .prepare(
+                "SELECT repo_path, branch_name, switch_count, last_used
+                 FROM branches
+                 ORDER BY last_used DESC",
+            )
+            .context("Failed to prepare query")?;
+
+        let records = stmt
+            .query_map([], |row| {
+                Ok(BranchRecord {
+                    repo_path: row.get(0)?,
+                    branch_name: row.get(1)?,
+                    switch_count: row.get(2)?,
+                    last_used: row.get(3)?,
+                })
+            })
+            .context("Failed to query branches")?
+            .map_while(Result::ok)
+            .collect();
+
+        Ok(records)
+    }
+
+    fn do_save_previous_branch(
+        conn: &Connection,
+        repo_path: &str,
+        branch_name: &str,
+    ) -> Result<()> {
+        // Create the previous_branch table if it doesn't exist
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS previous_branch (
+                repo_path TEXT PRIMARY KEY,
+                branch_name TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+            [],
+        )
+        .context("Failed to create previous_branch table")?;
+
+        let now = now_timestamp();
+
+        conn.execute(
+            "INSERT OR REPLACE INTO previous_branch (repo_path, branch_name, updated_at)
+             VALUES (?1, ?2, ?3)",
+            [repo_path, branch_name, &now.to_string()],
+        )
+        .context("Failed to save previous branch")?;
+
+        Ok(())
+    }
+
+    fn do_get_previous_branch(conn: &Connection, repo_path: &str) -> Result<Option<String>> {
+        // Make sure the table exists
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS previous_branch (
+                repo_path TEXT PRIMARY KEY,
+                branch_name TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+            [],
+        )
+        .ok();
+
+        let result = conn.query_row(
+            "SELECT branch_name FROM previous_branch WHERE repo_path = ?1",
+            [repo_path],
+            |row| row.get::<_, String>(0),
+        );
+
+        match result {
+            Ok(branch) => Ok(Some(branch)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e).context("Failed to get previous branch"),
+        }
+    }
+
+    #[test]
+    fn test_open_db_creates_table() {
+        let result = open_test_db();
+        assert!(result.is_ok());
+
+        let conn = result.unwrap();
+
+        // Verify table exists
+        let table_check: Result<i64, _> = conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='branches'",
+            [],
+            |row| row.get(0),
+        );
+
+        assert!(table_check.is_ok());
+        assert_eq!(table_check.unwrap(), 1);
+    }
+
+    #[test]
+    fn do_record_checkout_new_branch() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        let result = do_record_checkout(&conn, &repo_path, "main");
+        assert!(result.is_ok());
+
+        // Verify the record was created
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM branches WHERE repo_path = ?1 AND branch_name = ?2",
+                [&repo_path, "main"],
+                |row| row.get(0),
+            )
+            .unwrap();
+
+        assert_eq!(count, 1);
+
+        // Verify switch_count is 1
+        let switch_count: i64 = conn
+            .query_row(
+                "SELECT switch_count FROM branches WHERE repo_path = ?1 AND branch_name = ?2",
+                [&repo_path, "main"],
+                |row| row.get(0),
+            )
+            .unwrap();
+
+        assert_eq!(switch_count, 1);
+    }
+
+    #[test]
+    fn do_record_checkout_existing_branch() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        // Record first checkout
+        do_record_checkout(&conn, &repo_path, "main").unwrap();
+
+        // Record second checkout
+        let result = do_record_checkout(&conn, &repo_path, "main");
+        assert!(result.is_ok());
+
+        // Verify switch_count was incremented
+        let switch_count: i64 = conn
+            .query_row(
+                "SELECT switch_count FROM branches WHERE repo_path = ?1 AND branch_name = ?2",
+                [&repo_path, "main"],
+                |row| row.get(0),
+            )
+            .unwrap();
+
+        assert_eq!(switch_count, 2);
+    }
+
+    #[test]
+    fn do_record_checkout_multiple_repos() {
+        let conn = open_test_db().unwrap();
+        let repo_path1 = unique_repo_path();
+        let repo_path2 = unique_repo_path();
+
+        do_record_checkout(&conn, &repo_path1, "main").unwrap();
+        do_record_checkout(&conn, &repo_path2, "main").unwrap();
+
+        let records1 = do_get_branch_records(&conn, &repo_path1).unwrap();
+        let records2 = do_get_branch_records(&conn, &repo_path2).unwrap();
+
+        assert_eq!(records1.len(), 1);
+        assert_eq!(records2.len(), 1);
+        assert_eq!(records1[0].repo_path, repo_path1);
+        assert_eq!(records2[0].repo_path, repo_path2);
+    }
+
+    #[test]
+    fn do_record_checkout_updates_timestamp() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        do_record_checkout(&conn, &repo_path, "main").unwrap();
+
+        let first_timestamp: i64 = conn
+            .query_row(
+                "SELECT last_used FROM branches WHERE repo_path = ?1 AND branch_name = ?2",
+                [&repo_path, "main"],
+                |row| row.get(0),
+            )
+            .unwrap();
+
+        // Wait a bit and record again
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        do_record_checkout(&conn, &repo_path, "main").unwrap();
+
+        let second_timestamp: i64 = conn
+            .query_row(
+                "SELECT last_used FROM branches WHERE repo_path = ?1 AND branch_name = ?2",
+                [&repo_path, "main"],
+                |row| row.get(0),
+            )
+            .unwrap();
+
+        assert!(second_timestamp >= first_timestamp);
+    }
+
+    #[test]
+    fn do_get_branch_records_empty() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        let result = do_get_branch_records(&conn, &repo_path);
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().len(), 0);
+    }
+
+    #[test]
+    fn do_get_branch_records_single() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        do_record_checkout(&conn, &repo_path, "main").unwrap();
+
+        let records = do_get_branch_records(&conn, &repo_path).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].repo_path, repo_path);
+        assert_eq!(records[0].branch_name, "main");
+        assert_eq!(records[0].switch_count, 1);
+    }
+
+    #[test]
+    fn do_get_branch_records_multiple() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        do_record_checkout(&conn, &repo_path, "main").unwrap();
+        do_record_checkout(&conn, &repo_path, "develop").unwrap();
+        do_record_checkout(&conn, &repo_path, "feature").unwrap();
+
+        let records = do_get_branch_records(&conn, &repo_path).unwrap();
+        assert_eq!(records.len(), 3);
+
+        let branch_names: Vec<&str> = records.iter().map(|r| r.branch_name.as_str()).collect();
+        assert!(branch_names.contains(&"main"));
+        assert!(branch_names.contains(&"develop"));
+        assert!(branch_names.contains(&"feature"));
+    }
+
+    #[test]
+    fn do_get_branch_records_ordered_by_last_used() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        do_record_checkout(&conn, &repo_path, "first").unwrap();
+        std::thread::sleep(std::time::Duration::from_secs(1));
+        do_record_checkout(&conn, &repo_path, "second").unwrap();
+        std::thread::sleep(std::time::Duration::from_secs(1));
+        do_record_checkout(&conn, &repo_path, "third").unwrap();
+
+        let records = do_get_branch_records(&conn, &repo_path).unwrap();
+        assert_eq!(records.len(), 3);
+
+        // Should be ordered by last_used DESC
+        assert_eq!(records[0].branch_name, "third");
+        assert_eq!(records[1].branch_name, "second");
+        assert_eq!(records[2].branch_name, "first");
+    }
+
+    #[test]
+    fn do_get_branch_records_filters_by_repo() {
+        let conn = open_test_db().unwrap();
+        let repo_path1 = unique_repo_path();
+        let repo_path2 = unique_repo_path();
+
+        do_record_checkout(&conn, &repo_path1, "main").unwrap();
+        do_record_checkout(&conn, &repo_path2, "main").unwrap();
+        do_record_checkout(&conn, &repo_path2, "develop").unwrap();
+
+        let records1 = do_get_branch_records(&conn, &repo_path1).unwrap();
+        let records2 = do_get_branch_records(&conn, &repo_path2).unwrap();
+
+        assert_eq!(records1.len(), 1);
+        assert_eq!(records2.len(), 2);
+    }
+
+    #[test]
+    fn do_get_all_records_empty() {
+        let conn = open_test_db().unwrap();
+        let result = do_get_all_records(&conn);
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().len(), 0);
+    }
+
+    #[test]
+    fn do_get_all_records_multiple_repos() {
+        let conn = open_test_db().unwrap();
+        let repo_path1 = unique_repo_path();
+        let repo_path2 = unique_repo_path();
+
+        do_record_checkout(&conn, &repo_path1, "main").unwrap();
+        do_record_checkout(&conn, &repo_path1, "develop").unwrap();
+        do_record_checkout(&conn, &repo_path2, "main").unwrap();
+
+        let records = do_get_all_records(&conn).unwrap();
+        assert_eq!(records.len(), 3);
+    }
+
+    #[test]
+    fn do_get_all_records_ordered() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        do_record_checkout(&conn, &repo_path, "first").unwrap();
+        std::thread::sleep(std::time::Duration::from_secs(1));
+        do_record_checkout(&conn, &repo_path, "second").unwrap();
+
+        let records = do_get_all_records(&conn).unwrap();
+
+        assert_eq!(records.len(), 2);
+        // Should be ordered by last_used DESC
+        assert_eq!(records[0].branch_name, "second");
+        assert_eq!(records[1].branch_name, "first");
+    }
+
+    #[test]
+    fn test_get_stats_empty() {
+        let conn = open_test_db().unwrap();
+
+        // Query stats from test database
+        let total_switches: i64 = conn
+            .query_row(
+                "SELECT COALESCE(SUM(switch_count), 0) FROM branches",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+
+        let unique_branches: i64 = conn
+            .query_row("SELECT COUNT(*) FROM branches", [], |row| row.get(0))
+            .unwrap();
+
+        let unique_repos: i64 = conn
+            .query_row(
+                "SELECT COUNT(DISTINCT repo_path) FROM branches",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+
+        assert_eq!(total_switches, 0);
+        assert_eq!(unique_branches, 0);
+        assert_eq!(unique_repos, 0);
+    }
+
+    #[test]
+    fn test_get_stats_single_branch() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        do_record_checkout(&conn, &repo_path, "main").unwrap();
+
+        // Count stats from our test connection
+        let total: i64 = conn
+            .query_row(
+                "SELECT COALESCE(SUM(switch_count), 0) FROM branches",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let unique_branches: i64 = conn
+            .query_row("SELECT COUNT(*) FROM branches", [], |row| row.get(0))
+            .unwrap();
+        let unique_repos: i64 = conn
+            .query_row(
+                "SELECT COUNT(DISTINCT repo_path) FROM branches",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+
+        assert_eq!(total, 1);
+        assert_eq!(unique_branches, 1);
+        assert_eq!(unique_repos, 1);
+    }
+
+    #[test]
+    fn test_get_stats_multiple_switches() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        do_record_checkout(&conn, &repo_path, "main").unwrap();
+        do_record_checkout(&conn, &repo_path, "main").unwrap();
+        do_record_checkout(&conn, &repo_path, "main").unwrap();
+
+        let total: i64 = conn
+            .query_row(
+                "SELECT COALESCE(SUM(switch_count), 0) FROM branches",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(total, 3);
+    }
+
+    #[test]
+    fn test_get_stats_multiple_branches_and_repos() {
+        let conn = open_test_db().unwrap();
+        let repo_path1 = unique_repo_path();
+        let repo_path2 = unique_repo_path();
+
+        do_record_checkout(&conn, &repo_path1, "main").unwrap();
+        do_record_checkout(&conn, &repo_path1, "develop").unwrap();
+        do_record_checkout(&conn, &repo_path2, "main").unwrap();
+        do_record_checkout(&conn, &repo_path2, "main").unwrap();
+
+        let total: i64 = conn
+            .query_row(
+                "SELECT COALESCE(SUM(switch_count), 0) FROM branches",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let unique_branches: i64 = conn
+            .query_row("SELECT COUNT(*) FROM branches", [], |row| row.get(0))
+            .unwrap();
+        let unique_repos: i64 = conn
+            .query_row(
+                "SELECT COUNT(DISTINCT repo_path) FROM branches",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+
+        assert_eq!(total, 4);
+        assert_eq!(unique_branches, 3);
+        assert_eq!(unique_repos, 2);
+    }
+
+    #[test]
+    fn test_save_previous_branch() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        let result = do_save_previous_branch(&conn, &repo_path, "main");
+        assert!(result.is_ok());
+
+        let branch: String = conn
+            .query_row(
+                "SELECT branch_name FROM previous_branch WHERE repo_path = ?1",
+                [&repo_path],
+                |row| row.get(0),
+            )
+            .unwrap();
+
+        assert_eq!(branch, "main");
+    }
+
+    #[test]
+    fn test_save_previous_branch_updates() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        do_save_previous_branch(&conn, &repo_path, "main").unwrap();
+        do_save_previous_branch(&conn, &repo_path, "develop").unwrap();
+
+        let branch: String = conn
+            .query_row(
+                "SELECT branch_name FROM previous_branch WHERE repo_path = ?1",
+                [&repo_path],
+                |row| row.get(0),
+            )
+            .unwrap();
+
+        assert_eq!(branch, "develop");
+
+        // Verify only one record exists
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM previous_branch WHERE repo_path = ?1",
+                [&repo_path],
+                |row| row.get(0),
+            )
+            .unwrap();
+
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_save_previous_branch_multiple_repos() {
+        let conn = open_test_db().unwrap();
+        let repo_path1 = unique_repo_path();
+        let repo_path2 = unique_repo_path();
+
+        do_save_previous_branch(&conn, &repo_path1, "main").unwrap();
+        do_save_previous_branch(&conn, &repo_path2, "develop").unwrap();
+
+        let branch1 = do_get_previous_branch(&conn, &repo_path1).unwrap();
+        let branch2 = do_get_previous_branch(&conn, &repo_path2).unwrap();
+
+        assert_eq!(branch1, Some("main".to_string()));
+        assert_eq!(branch2, Some("develop".to_string()));
+    }
+
+    #[test]
+    fn do_get_previous_branch_not_found() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        let result = do_get_previous_branch(&conn, &repo_path);
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), None);
+    }
+
+    #[test]
+    fn do_get_previous_branch_exists() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        do_save_previous_branch(&conn, &repo_path, "main").unwrap();
+
+        let result = do_get_previous_branch(&conn, &repo_path);
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), Some("main".to_string()));
+    }
+
+    #[test]
+    fn do_get_previous_branch_different_repos() {
+        let conn = open_test_db().unwrap();
+        let repo_path1 = unique_repo_path();
+        let repo_path2 = unique_repo_path();
+
+        do_save_previous_branch(&conn, &repo_path1, "main").unwrap();
+
+        let result1 = do_get_previous_branch(&conn, &repo_path1);
+        let result2 = do_get_previous_branch(&conn, &repo_path2);
+
+        assert_eq!(result1.unwrap(), Some("main".to_string()));
+        assert_eq!(result2.unwrap(), None);
+    }
+
+    #[test]
+    fn test_get_db_path() {
+        let result = get_db_path();
+        assert!(result.is_ok());
+
+        let path = result.unwrap();
+        assert!(path.to_string_lossy().ends_with("data.db"));
+    }
+
+    #[test]
+    fn test_special_characters_in_branch_names() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        let special_branch = "feature/issue-#123_v2.0";
+        do_record_checkout(&conn, &repo_path, special_branch).unwrap();
+
+        let records = do_get_branch_records(&conn, &repo_path).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].branch_name, special_branch);
+    }
+
+    #[test]
+    fn test_unicode_in_branch_names() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        let unicode_branch = "feature/日本語-🚀";
+        do_record_checkout(&conn, &repo_path, unicode_branch).unwrap();
+
+        let records = do_get_branch_records(&conn, &repo_path).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].branch_name, unicode_branch);
+    }
+
+    #[test]
+    fn test_long_branch_names() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        let long_branch = "a".repeat(200);
+        do_record_checkout(&conn, &repo_path, &long_branch).unwrap();
+
+        let records = do_get_branch_records(&conn, &repo_path).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].branch_name, long_branch);
+    }
+
+    #[test]
+    fn test_long_repo_paths() {
+        let conn = open_test_db().unwrap();
+        let long_path = format!("{}/long/{}", unique_repo_path(), "repo/".repeat(50));
+        do_record_checkout(&conn, &long_path, "main").unwrap();
+
+        let records = do_get_branch_records(&conn, &long_path).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].repo_path, long_path);
+    }
+
+    // Alias test helper functions
+    fn do_create_alias(
+        conn: &Connection,
+        repo_path: &str,
+        alias: &str,
+        branch_name: &str,
+    ) -> Result<()> {
+        let now = now_timestamp();
+
+        conn.execute(
+            "INSERT OR REPLACE INTO aliases (repo_path, alias, branch_name, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            [repo_path, alias, branch_name, &now.to_string()],
+        )
+        .context("Failed to create alias")?;
+
+        Ok(())
+    }
+
+    fn do_get_alias(conn: &Connection, repo_path: &str, alias: &str) -> Result<Option<String>> {
+        let result = conn.query_row(
+            "SELECT branch_name FROM aliases WHERE repo_path = ?1 AND alias = ?2",
+            [repo_path, alias],
+            |row| row.get::<_, String>(0),
+        );
+
+        match result {
+            Ok(branch) => Ok(Some(branch)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e).context("Failed to get alias"),
+        }
+    }
+
+    fn do_delete_alias(conn: &Connection, repo_path: &str, alias: &str) -> Result<()> {
+        conn.execute(
+            "DELETE FROM aliases WHERE repo_path = ?1 AND alias = ?2",
+            [repo_path, alias],
+        )
+        .context("Failed to delete alias")?;
+
+        Ok(())
+    }
+
+    fn do_list_aliases(conn: &Connection, repo_path: &str) -> Result<Vec<Alias>> {
+        let mut stmt = conn
+            .prepare(
+                "SELECT repo_path, alias, branch_name, created_at
+                 FROM aliases
+                 WHERE repo_path = ?1
+                 ORDER BY alias",
+            )
+            .context("Failed to prepare query")?;
+
+        let aliases = stmt
+            .query_map([repo_path], |row| {
+                Ok(Alias {
+                    repo_path: row.get(0)?,
+                    alias: row.get(1)?,
+                    branch_name: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            })
+            .context("Failed to query aliases")?
+            .map_while(Result::ok)
+            .collect();
+
+        Ok(aliases)
+    }
+
+    fn do_get_aliases_for_branch(
+        conn: &Connection,
+        repo_path: &str,
+        branch_name: &str,
+    ) -> Result<Vec<String>> {
+        let mut stmt = conn
+            .prepare(
+                "SELECT alias
+                 FROM aliases
+                 WHERE repo_path = ?1 AND branch_name = ?2
+                 ORDER BY alias",
+            )
+            .context("Failed to prepare query")?;
+
+        let aliases = stmt
+            .query_map([repo_path, branch_name], |row| row.get::<_, String>(0))
+            .context("Failed to query aliases")?
+            .map_while(Result::ok)
+            .collect();
+
+        Ok(aliases)
+    }
+
+    #[test]
+    fn test_create_alias() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        let result = do_create_alias(&conn, &repo_path, "m", "master");
+        assert!(result.is_ok());
+
+        let branch = do_get_alias(&conn, &repo_path, "m").unwrap();
+        assert_eq!(branch, Some("master".to_string()));
+    }
+
+    #[test]
+    fn test_create_alias_updates_existing() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        do_create_alias(&conn, &repo_path, "m", "master").unwrap();
+        do_create_alias(&conn, &repo_path, "m", "main").unwrap();
+
+        let branch = do_get_alias(&conn, &repo_path, "m").unwrap();
+        assert_eq!(branch, Some("main".to_string()));
+
+        // Verify only one alias exists
+        let aliases = do_list_aliases(&conn, &repo_path).unwrap();
+        assert_eq!(aliases.len(), 1);
+    }
+
+    #[test]
+    fn test_rename_alias() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+        storage.create_alias("/repo", "m", "master").unwrap();
+
+        assert!(storage.rename_alias("/repo", "m", "main").unwrap());
+        assert_eq!(storage.alias("/repo", "m").unwrap(), None);
+        assert_eq!(
+            storage.alias("/repo", "main").unwrap(),
+            Some("master".to_string())
+        );
+        assert!(!storage.rename_alias("/repo", "m", "x").unwrap());
+        assert!(!storage.rename_alias("/other", "main", "x").unwrap());
+    }
+
+    #[test]
+    fn test_all_aliases() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+        storage.create_alias("/b", "h", "hotfix/1").unwrap();
+        storage.create_alias("/a", "m", "main").unwrap();
+        storage.create_alias("/a", "d", "develop").unwrap();
+
+        let all = storage.all_aliases().unwrap();
+        assert_eq!(all.keys().collect::<Vec<_>>(), vec!["/a", "/b"]);
+        let names: Vec<&str> = all["/a"].iter().map(|a| a.alias.as_str()).collect();
+        assert_eq!(names, vec!["d", "m"]);
+        assert_eq!(all["/b"][0].branch_name, "hotfix/1");
+    }
+
+    #[test]
+    fn test_get_alias_not_found() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        let result = do_get_alias(&conn, &repo_path, "nonexistent");
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), None);
+    }
+
+    #[test]
+    fn test_delete_alias() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        do_create_alias(&conn, &repo_path, "m", "master").unwrap();
+        let result = do_delete_alias(&conn, &repo_path, "m");
+        assert!(result.is_ok());
+
+        let branch = do_get_alias(&conn, &repo_path, "m").unwrap();
+        assert_eq!(branch, None);
+    }
+
+    #[test]
+    fn test_list_aliases_empty() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        let aliases = do_list_aliases(&conn, &repo_path).unwrap();
+        assert_eq!(aliases.len(), 0);
+    }
+
+    #[test]
+    fn test_list_aliases_multiple() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        do_create_alias(&conn, &repo_path, "m", "master").unwrap();
+        do_create_alias(&conn, &repo_path, "d", "develop").unwrap();
+        do_create_alias(&conn, &repo_path, "f", "feature/test").unwrap();
+
+        let aliases = do_list_aliases(&conn, &repo_path).unwrap();
+        assert_eq!(aliases.len(), 3);
+
+        let alias_names: Vec<&str> = aliases.iter().map(|a| a.alias.as_str()).collect();
+        assert!(alias_names.contains(&"m"));
+        assert!(alias_names.contains(&"d"));
+        assert!(alias_names.contains(&"f"));
+    }
+
+    #[test]
+    fn test_list_aliases_sorted() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        do_create_alias(&conn, &repo_path, "z", "zzz").unwrap();
+        do_create_alias(&conn, &repo_path, "a", "aaa").unwrap();
+        do_create_alias(&conn, &repo_path, "m", "mmm").unwrap();
+
+        let aliases = do_list_aliases(&conn, &repo_path).unwrap();
+        assert_eq!(aliases.len(), 3);
+
+        // Should be sorted alphabetically by alias
+        assert_eq!(aliases[0].alias, "a");
+        assert_eq!(aliases[1].alias, "m");
+        assert_eq!(aliases[2].alias, "z");
+    }
+
+    #[test]
+    fn test_list_aliases_filters_by_repo() {
+        let conn = open_test_db().unwrap();
+        let repo_path1 = unique_repo_path();
+        let repo_path2 = unique_repo_path();
+
+        do_create_alias(&conn, &repo_path1, "m", "master").unwrap();
+        do_create_alias(&conn, &repo_path2, "m", "main").unwrap();
+        do_create_alias(&conn, &repo_path2, "d", "develop").unwrap();
+
+        let aliases1 = do_list_aliases(&conn, &repo_path1).unwrap();
+        let aliases2 = do_list_aliases(&conn, &repo_path2).unwrap();
+
+        assert_eq!(aliases1.len(), 1);
+        assert_eq!(aliases2.len(), 2);
+    }
+
+    #[test]
+    fn test_get_aliases_for_branch_empty() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        let aliases = do_get_aliases_for_branch(&conn, &repo_path, "master").unwrap();
+        assert_eq!(aliases.len(), 0);
+    }
+
+    #[test]
+    fn test_get_aliases_for_branch_single() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        do_create_alias(&conn, &repo_path, "m", "master").unwrap();
+
+        let aliases = do_get_aliases_for_branch(&conn, &repo_path, "master").unwrap();
+        assert_eq!(aliases.len(), 1);
+        assert_eq!(aliases[0], "m");
+    }
+
+    #[test]
+    fn test_get_aliases_for_branch_multiple() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        do_create_alias(&conn, &repo_path, "m", "master").unwrap();
+        do_create_alias(&conn, &repo_path, "main", "master").unwrap();
+        do_create_alias(&conn, &repo_path, "prod", "master").unwrap();
+        do_create_alias(&conn, &repo_path, "d", "develop").unwrap();
+
+        let aliases = do_get_aliases_for_branch(&conn, &repo_path, "master").unwrap();
+        assert_eq!(aliases.len(), 3);
+        assert!(aliases.contains(&"m".to_string()));
+        assert!(aliases.contains(&"main".to_string()));
+        assert!(aliases.contains(&"prod".to_string()));
+    }
+
+    #[test]
+    fn test_alias_with_special_characters() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        do_create_alias(&conn, &repo_path, "my-alias", "feature/test-123").unwrap();
+
+        let branch = do_get_alias(&conn, &repo_path, "my-alias").unwrap();
+        assert_eq!(branch, Some("feature/test-123".to_string()));
+    }
+
+    #[test]
+    fn test_alias_repo_isolation() {
+        let conn = open_test_db().unwrap();
+        let repo_path1 = unique_repo_path();
+        let repo_path2 = unique_repo_path();
+
+        // Create alias "m" → "master" in repo1
+        do_create_alias(&conn, &repo_path1, "m", "master").unwrap();
+
+        // Try to get alias "m" from repo2 - should return None
+        let result = do_get_alias(&conn, &repo_path2, "m").unwrap();
+        assert_eq!(
+            result, None,
+            "Alias from repo1 should not be accessible in repo2"
+        );
+
+        // Verify it still works in repo1
+        let result = do_get_alias(&conn, &repo_path1, "m").unwrap();
+        assert_eq!(result, Some("master".to_string()));
+    }
+
+    #[test]
+    fn test_alias_same_name_different_repos() {
+        let conn = open_test_db().unwrap();
+        let repo_path1 = unique_repo_path();
+        let repo_path2 = unique_repo_path();
+
+        // Create alias "m" → "master" in repo1
+        do_create_alias(&conn, &repo_path1, "m", "master").unwrap();
+
+        // Create alias "m" → "main" in repo2 (same alias name, different branch)
+        do_create_alias(&conn, &repo_path2, "m", "main").unwrap();
+
+        // Verify each repo gets its own alias
+        let result1 = do_get_alias(&conn, &repo_path1, "m").unwrap();
+        assert_eq!(result1, Some("master".to_string()));
+
+        let result2 = do_get_alias(&conn, &repo_path2, "m").unwrap();
+        assert_eq!(result2, Some("main".to_string()));
+    }
+
+    #[test]
+    fn test_delete_alias_only_affects_current_repo() {
+        let conn = open_test_db().unwrap();
+        let repo_path1 = unique_repo_path();
+        let repo_path2 = unique_repo_path();
+
+        // Create same alias in both repos
+        do_create_alias(&conn, &repo_path1, "m", "master").unwrap();
+        do_create_alias(&conn, &repo_path2, "m", "main").unwrap();
+
+        // Delete from repo1
+        do_delete_alias(&conn, &repo_path1, "m").unwrap();
+
+        // Verify deleted in repo1
+        let result1 = do_get_alias(&conn, &repo_path1, "m").unwrap();
+        assert_eq!(result1, None);
+
+        // Verify still exists in repo2
+        let result2 = do_get_alias(&conn, &repo_path2, "m").unwrap();
+        assert_eq!(result2, Some("main".to_string()));
+    }
+
+    #[test]
+    fn test_get_aliases_for_branch_repo_scoped() {
+        let conn = open_test_db().unwrap();
+        let repo_path1 = unique_repo_path();
+        let repo_path2 = unique_repo_path();
+
+        // Create aliases for "master" in both repos
+        do_create_alias(&conn, &repo_path1, "m", "master").unwrap();
+        do_create_alias(&conn, &repo_path1, "prod", "master").unwrap();
+        do_create_alias(&conn, &repo_path2, "main", "master").unwrap();
+
+        // Get aliases for "master" in repo1 - should only get repo1's aliases
+        let aliases1 = do_get_aliases_for_branch(&conn, &repo_path1, "master").unwrap();
+        assert_eq!(aliases1.len(), 2);
+        assert!(aliases1.contains(&"m".to_string()));
+        assert!(aliases1.contains(&"prod".to_string()));
+        assert!(!aliases1.contains(&"main".to_string()));
+
+        // Get aliases for "master" in repo2 - should only get repo2's aliases
+        let aliases2 = do_get_aliases_for_branch(&conn, &repo_path2, "master").unwrap();
+        assert_eq!(aliases2.len(), 1);
+        assert!(aliases2.contains(&"main".to_string()));
+        assert!(!aliases2.contains(&"m".to_string()));
+    }
+
+    // Migration tests
+    #[test]
+    fn test_schema_version_table_created() {
+        let conn = open_test_db().unwrap();
+
+        // Verify schema_version table exists
+        let table_exists: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='schema_version'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+
+        assert_eq!(table_exists, 1);
+    }
+
+    #[test]
+    fn test_migration_backs_up_and_restores() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("data.db");
+
+        // A database one schema version behind
+        let conn = open_db(&db_path).unwrap();
+        conn.execute(
+            "INSERT INTO branches (repo_path, branch_name, switch_count, last_used)
+             VALUES ('/repo', 'main', 3, 1)",
+            [],
+        )
+        .unwrap();
+        conn.execute_batch(
+            "DROP TABLE branch_groups;
+             DELETE FROM schema_version WHERE version = 11;",
+        )
+        .unwrap();
+        drop(conn);
+        assert!(!backup_path(&db_path, 10).exists());
+
+        let conn = open_db(&db_path).unwrap();
+        assert_eq!(schema_version(&conn), CURRENT_SCHEMA_VERSION);
+        conn.execute("DELETE FROM branches", []).unwrap();
+        drop(conn);
+        assert!(backup_path(&db_path, 10).exists());
+
+        // No backup while already current
+        assert!(!backup_path(&db_path, 11).exists());
+
+        let (backup, version) = restore_backup_at(&db_path).unwrap();
+        assert_eq!(version, 10);
+        assert_eq!(backup, backup_path(&db_path, 10));
+        assert!(dir.path().join("data.db.pre-restore").exists());
+
+        let conn = Connection::open(&db_path).unwrap();
+        assert_eq!(schema_version(&conn), 10);
+        let count: i64 = conn
+            .query_row("SELECT switch_count FROM branches", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn test_newer_schema_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("data.db");
+
+        let conn = open_db(&db_path).unwrap();
+        conn.execute(
+            "INSERT INTO schema_version (version, applied_at) VALUES (99, 0)",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+
+        let err = open_db(&db_path).unwrap_err().to_string();
+        assert!(err.contains("v99"), "{}", err);
+        assert!(restore_backup_at(&db_path).is_err());
+    }
+
+    #[test]
+    fn test_repair_replaces_file_that_is_not_a_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("data.db");
+        std::fs::write(&db_path, vec![0x42; 8192]).unwrap();
+
+        let conn = open_or_repair(&db_path).unwrap();
+        assert_eq!(schema_version(&conn), CURRENT_SCHEMA_VERSION);
+
+        let damaged: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().contains(".corrupt-"))
+            .collect();
+        assert_eq!(damaged.len(), 1);
+        assert_eq!(std::fs::read(damaged[0].path()).unwrap(), vec![0x42; 8192]);
+    }
+
+    #[test]
+    fn test_repair_salvages_readable_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("data.db");
+
+        let conn = open_db(&db_path).unwrap();
+        conn.execute(
+            "INSERT INTO branches (repo_path, branch_name, switch_count, last_used)
+             VALUES ('/repo', 'main', 4, 100)",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO aliases (repo_path, alias, branch_name, created_at)
+             VALUES ('/repo', 'm', 'main', 100)",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+
+        let (conn, salvage) = repair_database(&db_path).unwrap();
+        assert_eq!(salvage.rows, 2);
+        assert!(salvage.incomplete.is_empty());
+        assert!(salvage.damaged.exists());
+
+        let count: i64 = conn
+            .query_row(
+                "SELECT switch_count FROM branches WHERE branch_name = 'main'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 4);
+        assert_eq!(schema_version(&conn), CURRENT_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_repair_keeps_what_a_truncated_file_still_has() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("data.db");
+
+        let conn = open_db(&db_path).unwrap();
+        for i in 0..2000 {
+            conn.execute(
+                "INSERT INTO checkout_events (repo_path, from_branch, to_branch, switched_at)
+                 VALUES ('/repo', 'main', ?1, ?2)",
+                rusqlite::params![format!("feature/{:04}-with-a-long-name", i), i],
+            )
+            .unwrap();
+        }
+        drop(conn);
+
+        // Lose the second half of the file, as a full disk might
+        let len = std::fs::metadata(&db_path).unwrap().len();
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .open(&db_path)
+            .unwrap();
+        file.set_len(len / 2).unwrap();
+        drop(file);
+
+        let conn = open_or_repair(&db_path).unwrap();
+        let events: i64 = conn
+            .query_row("SELECT COUNT(*) FROM checkout_events", [], |row| row.get(0))
+            .unwrap();
+        assert!(events < 2000, "{}", events);
+        conn.execute(
+            "INSERT INTO branches (repo_path, branch_name, last_used) VALUES ('/repo', 'x', 0)",
+            [],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_newer_database_is_not_repaired() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("data.db");
+
+        let conn = open_db(&db_path).unwrap();
+        conn.execute(
+            "INSERT INTO schema_version (version, applied_at) VALUES (99, 0)",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+
+        assert!(open_or_repair(&db_path).is_err());
+        assert!(std::fs::read_dir(dir.path()).unwrap().all(|entry| !entry
+            .unwrap()
+            .file_name()
+            .to_string_lossy()
+            .contains(".corrupt-")));
+    }
+
+    #[test]
+    fn test_fresh_database_migrates_to_current() {
+        let conn = open_test_db().unwrap();
+
+        // Check that we're at current version
+        let version: i32 = conn
+            .query_row(
+                "SELECT version FROM schema_version ORDER BY version DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+
+        assert_eq!(version, CURRENT_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_migration_creates_all_tables() {
+        let conn = open_test_db().unwrap();
+
+        // Verify branches table exists
+        let branches_exists: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='branches'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(branches_exists, 1);
+
+        // Verify aliases table exists
+        let aliases_exists: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='aliases'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(aliases_exists, 1);
+
+        // Verify previous_branch table exists
+        let prev_exists: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='previous_branch'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(prev_exists, 1);
+    }
+
+    #[test]
+    fn test_migration_creates_all_indices() {
+        let conn = open_test_db().unwrap();
+
+        // Get all index names
+        let indices: Vec<String> = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type='index'")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map_while(Result::ok)
+            .collect();
+
+        // Check for expected indices
+        assert!(indices.contains(&"idx_branches_repo_last_used".to_string()));
+        assert!(indices.contains(&"idx_branches_last_used".to_string()));
+        assert!(indices.contains(&"idx_aliases_branch".to_string()));
+    }
+
+    #[test]
+    fn test_migration_records_versions() {
+        let conn = open_test_db().unwrap();
+
+        // Check that both migration versions are recorded
+        let versions: Vec<i32> = conn
+            .prepare("SELECT version FROM schema_version ORDER BY version")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map_while(Result::ok)
+            .collect();
+
+        assert_eq!(versions, vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]);
+    }
+
+    #[test]
+    fn test_migration_from_v1_to_v2() {
+        // Simulate a database that only has v1 schema
+        let conn = Connection::open_in_memory().unwrap();
+
+        // Create schema_version table
+        conn.execute(
+            "CREATE TABLE schema_version (
+                version INTEGER PRIMARY KEY,
+                applied_at INTEGER NOT NULL
+            )",
+            [],
+        )
+        .unwrap();
+
+        // Manually create v1 schema
+        conn.execute(
+            "CREATE TABLE branches (
+                id INTEGER PRIMARY KEY,
+                repo_path TEXT NOT NULL,
+                branch_name TEXT NOT NULL,
+                switch_count INTEGER DEFAULT 1,
+                last_used INTEGER NOT NULL,
+                UNIQUE(repo_path, branch_name)
+            )",
+            [],
+        )
+        .unwrap();
+
+        // Record v1 migration
+        conn.execute(
+            "INSERT INTO schema_version (version, applied_at) VALUES (1, 1234567890)",
+            [],
+        )
+        .unwrap();
+
+        // Now run initialization (should migrate through v2 to current)
+        initialize_tables(&conn).unwrap();
+
+        // Verify we're at the current version
+        let version: i32 = conn
+            .query_row(
+                "SELECT version FROM schema_version ORDER BY version DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(version, CURRENT_SCHEMA_VERSION);
+
+        // Verify aliases table was created
+        let aliases_exists: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='aliases'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(aliases_exists, 1);
+    }
+
+    #[test]
+    fn test_migration_v3_seeds_history_from_previous_branch() {
+        let conn = Connection::open_in_memory().unwrap();
+
+        conn.execute(
+            "CREATE TABLE schema_version (
+                version INTEGER PRIMARY KEY,
+                applied_at INTEGER NOT NULL
+            )",
+            [],
+        )
+        .unwrap();
+
+        // Bring the database to v2 and store a previous branch
+        run_migrations(&conn, 0).unwrap();
+        conn.execute("DELETE FROM schema_version WHERE version >= 3", [])
+            .unwrap();
+        conn.execute("DROP TABLE checkout_history", []).unwrap();
+        conn.execute(
+            "INSERT INTO previous_branch (repo_path, branch_name, updated_at)
+             VALUES ('/test', 'develop', 1234567890)",
+            [],
+        )
+        .unwrap();
+
+        initialize_tables(&conn).unwrap();
+
+        let history = query_history(&conn, "/test").unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].branch_name, "develop");
+        assert_eq!(history[0].switched_at, 1234567890);
+    }
+
+    // Checkout history tests
+    #[test]
+    fn test_history_most_recent_first() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        insert_history_entry(&conn, &repo_path, "main", 100).unwrap();
+        insert_history_entry(&conn, &repo_path, "develop", 200).unwrap();
+        insert_history_entry(&conn, &repo_path, "feature", 300).unwrap();
+
+        let history = query_history(&conn, &repo_path).unwrap();
+        let names: Vec<&str> = history.iter().map(|e| e.branch_name.as_str()).collect();
+        assert_eq!(names, vec!["feature", "develop", "main"]);
+    }
+
+    #[test]
+    fn test_history_deduplicates_branches() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        insert_history_entry(&conn, &repo_path, "main", 100).unwrap();
+        insert_history_entry(&conn, &repo_path, "develop", 200).unwrap();
+        insert_history_entry(&conn, &repo_path, "main", 300).unwrap();
+
+        let history = query_history(&conn, &repo_path).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].branch_name, "main");
+        assert_eq!(history[0].switched_at, 300);
+        assert_eq!(history[1].branch_name, "develop");
+    }
+
+    #[test]
+    fn test_history_repo_isolation() {
+        let conn = open_test_db().unwrap();
+        let repo_path1 = unique_repo_path();
+        let repo_path2 = unique_repo_path();
+
+        insert_history_entry(&conn, &repo_path1, "main", 100).unwrap();
+        insert_history_entry(&conn, &repo_path2, "develop", 200).unwrap();
+
+        let history = query_history(&conn, &repo_path1).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].branch_name, "main");
+    }
+
+    #[test]
+    fn test_history_is_trimmed() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        for i in 0..(MAX_HISTORY_ENTRIES + 5) {
+            insert_history_entry(&conn, &repo_path, &format!("branch-{}", i), i as i64).unwrap();
+        }
+
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM checkout_history WHERE repo_path = ?1",
+                [&repo_path],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, MAX_HISTORY_ENTRIES as i64);
+
+        // Oldest entries are dropped first
+        let history = query_history(&conn, &repo_path).unwrap();
+        assert!(!history.iter().any(|e| e.branch_name == "branch-0"));
+    }
+
+    #[test]
+    fn test_remove_branch_data() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+
+        do_record_checkout(&conn, &repo_path, "feature").unwrap();
+        do_record_checkout(&conn, &repo_path, "main").unwrap();
+        conn.execute(
+            "INSERT INTO aliases (repo_path, alias, branch_name, created_at) VALUES (?1, 'f', 'feature', 0)",
+            [&repo_path],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO previous_branch (repo_path, branch_name, updated_at) VALUES (?1, 'feature', 0)",
+            [&repo_path],
+        )
+        .unwrap();
+        insert_history_entry(&conn, &repo_path, "feature", 100).unwrap();
+        insert_history_entry(&conn, &repo_path, "main", 200).unwrap();
+
+        remove_branch_data(&conn, &repo_path, &["feature"]).unwrap();
+
+        let records = do_get_branch_records(&conn, &repo_path).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].branch_name, "main");
+
+        let remaining: i64 = conn
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM aliases WHERE repo_path = ?1)
+                      + (SELECT COUNT(*) FROM previous_branch WHERE repo_path = ?1)",
+                [&repo_path],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(remaining, 0);
+
+        let history = query_history(&conn, &repo_path).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].branch_name, "main");
+    }
+
+    #[test]
+    fn test_no_migration_when_current() {
+        let conn = open_test_db().unwrap();
+
+        // Get current version count
+        let version_count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0))
+            .unwrap();
+
+        // Run initialization again (should not add duplicate versions)
+        initialize_tables(&conn).unwrap();
+
+        let new_version_count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0))
+            .unwrap();
+
+        // Count should be the same (no duplicate migrations)
+        assert_eq!(version_count, new_version_count);
+    }
+
+    #[test]
+    fn test_open_db_enables_wal() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let conn = open_db(&temp_dir.path().join("data.db")).unwrap();
+
+        let mode: String = conn
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+    }
+
+    #[test]
+    fn test_open_read_only_reads_without_writing() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join(DB_FILE_NAME);
+        let store = SqliteStore {
+            conn: open_db(&db_path).unwrap(),
+            db_path: db_path.clone(),
+            options: StoreOptions::default(),
+        };
+        store.record_checkout("/repo", None, "main").unwrap();
+        store.record_checkout("/repo", None, "feature").unwrap();
+        drop(store);
+
+        let reader = SqliteStore::open_read_only_at(&db_path).unwrap();
+        assert_eq!(reader.branch_records("/repo").unwrap().len(), 2);
+
+        // Recording is off, and the connection couldn't write anyway
+        reader.record_checkout("/repo", None, "other").unwrap();
+        assert!(reader.conn.execute("DELETE FROM branches", []).is_err());
+        assert_eq!(reader.branch_records("/repo").unwrap().len(), 2);
+
+        assert!(SqliteStore::open_read_only_at(temp_dir.path().join("missing.db")).is_err());
+    }
+
+    #[test]
+    fn test_retry_on_busy_retries_until_success() {
+        let mut calls = 0;
+        let result = retry_on_busy(|| {
+            calls += 1;
+            if calls < 3 {
+                Err(rusqlite::Error::SqliteFailure(
+                    rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+                    None,
+                ))
+            } else {
+                Ok(calls)
+            }
+        });
+
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[test]
+    fn test_retry_on_busy_gives_up_after_max_attempts() {
+        let mut calls = 0;
+        let result: rusqlite::Result<()> = retry_on_busy(|| {
+            calls += 1;
+            Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_LOCKED),
+                None,
+            ))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(calls, MAX_WRITE_ATTEMPTS);
+    }
+
+    #[test]
+    fn test_retry_on_busy_does_not_retry_other_errors() {
+        let mut calls = 0;
+        let result: rusqlite::Result<()> = retry_on_busy(|| {
+            calls += 1;
+            Err(rusqlite::Error::QueryReturnedNoRows)
+        });
+
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_record_checkout_waits_for_concurrent_writer() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("data.db");
+        let storage = SqliteStore {
+            conn: open_db(&db_path).unwrap(),
+            db_path: db_path.clone(),
+            options: StoreOptions::default(),
+        };
+
+        // A second connection grabs the write lock and holds it for a moment
+        let other = open_db(&db_path).unwrap();
+        other.execute_batch("BEGIN IMMEDIATE").unwrap();
+        let holder = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            other.execute_batch("COMMIT").unwrap();
+        });
+
+        storage.record_checkout("/repo", None, "main").unwrap();
+        holder.join().unwrap();
+
+        assert_eq!(storage.branch_records("/repo").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_record_checkout_logs_events() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+
+        storage.record_checkout("/repo", None, "main").unwrap();
+        storage
+            .record_checkout("/repo", Some("main"), "feature")
+            .unwrap();
+        storage
+            .record_checkout("/other", Some("main"), "develop")
+            .unwrap();
+
+        let events = storage.checkout_events("/repo").unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].from_branch.as_deref(), Some("main"));
+        assert_eq!(events[0].to_branch, "feature");
+        assert_eq!(events[1].from_branch, None);
+        assert_eq!(events[1].to_branch, "main");
+    }
+
+    #[test]
+    fn test_import_checkout_events_skips_duplicates() {
+        let storage = Storage::new(SqliteStore::open_in_memory().unwrap())
+            .with_exclude(vec!["dependabot/*".to_string()]);
+        let event = |to: &str, at: i64| CheckoutEvent {
+            repo_path: "/repo".to_string(),
+            from_branch: Some("main".to_string()),
+            to_branch: to.to_string(),
+            switched_at: at,
+        };
+        // Switches within one second look alike but still count
+        let events = vec![
+            event("feature", 100),
+            event("main", 200),
+            event("feature", 300),
+            event("feature", 300),
+            event("dependabot/npm", 400),
+        ];
+
+        assert_eq!(storage.import_checkout_events(&events).unwrap(), 4);
+        // Importing the same reflog again adds nothing
+        assert_eq!(storage.import_checkout_events(&events).unwrap(), 0);
+
+        let records = storage.branch_records("/repo").unwrap();
+        let feature = records.iter().find(|r| r.branch_name == "feature").unwrap();
+        assert_eq!(feature.switch_count, 3);
+        assert_eq!(feature.last_used, 300);
+        assert_eq!(storage.checkout_events("/repo").unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_trim_records_drops_old_and_lowest_scored() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+        let now = now_timestamp();
+        let event = |to: &str, at: i64| CheckoutEvent {
+            repo_path: "/repo".to_string(),
+            from_branch: None,
+            to_branch: to.to_string(),
+            switched_at: at,
+        };
+        let events = vec![
+            event("ancient", 100),
+            event("main", now - 30),
+            event("main", now - 20),
+            event("feature", now - 10),
+            event("feature", now),
+            event("typo", now),
+        ];
+        storage.import_checkout_events(&events).unwrap();
+
+        let score = |r: &BranchRecord| r.switch_count as f64;
+        // Within both limits nothing is touched
+        assert_eq!(storage.trim_records(10, 0, &score).unwrap(), 0);
+        assert_eq!(storage.trim_records(2, 30, &score).unwrap(), 2);
+
+        let mut kept: Vec<_> = storage
+            .all_records()
+            .unwrap()
+            .into_iter()
+            .map(|r| r.branch_name)
+            .collect();
+        kept.sort();
+        assert_eq!(kept, ["feature", "main"]);
+    }
+
+    #[test]
+    fn test_recording_disabled_writes_nothing() {
+        let storage = Storage::new(SqliteStore::open_in_memory().unwrap()).with_recording(false);
+
+        storage.save_previous_branch("/repo", "main").unwrap();
+        storage.push_checkout_history("/repo", "main").unwrap();
+        storage
+            .record_checkout("/repo", Some("main"), "feature")
+            .unwrap();
+
+        assert!(storage.branch_records("/repo").unwrap().is_empty());
+        assert!(storage.checkout_events("/repo").unwrap().is_empty());
+        assert!(storage.checkout_history("/repo").unwrap().is_empty());
+        assert_eq!(storage.previous_branch("/repo").unwrap(), None);
+
+        // Undoing doesn't rewrite the history either
+        let storage = Storage::new(SqliteStore::open_in_memory().unwrap());
+        storage
+            .record_checkout("/repo", Some("main"), "feature")
+            .unwrap();
+        let storage = storage.with_recording(false);
+        assert!(storage.undo_last_checkout("/repo").unwrap().is_none());
+        assert_eq!(storage.checkout_events("/repo").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_undo_last_checkout_reverts_bookkeeping() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+
+        // main → feature → main → feature, the way switch_to_branch records it
+        let switches = [
+            ("main", "feature"),
+            ("feature", "main"),
+            ("main", "feature"),
+        ];
+        for (from, to) in switches {
+            storage.save_previous_branch("/repo", from).unwrap();
+            storage.push_checkout_history("/repo", from).unwrap();
+            storage.record_checkout("/repo", Some(from), to).unwrap();
+        }
+
+        let undone = storage.undo_last_checkout("/repo").unwrap().unwrap();
+        assert_eq!(undone.from_branch.as_deref(), Some("main"));
+        assert_eq!(undone.to_branch, "feature");
+
+        let feature = storage
+            .branch_records("/repo")
+            .unwrap()
+            .into_iter()
+            .find(|r| r.branch_name == "feature")
+            .unwrap();
+        assert_eq!(feature.switch_count, 1);
+        assert_eq!(storage.checkout_events("/repo").unwrap().len(), 2);
+        assert_eq!(
+            storage.previous_branch("/repo").unwrap().as_deref(),
+            Some("feature")
+        );
+
+        // Undoing the first visit removes the record altogether
+        storage.undo_last_checkout("/repo").unwrap();
+        storage.undo_last_checkout("/repo").unwrap();
+        let records = storage.branch_records("/repo").unwrap();
+        assert!(records.iter().all(|r| r.branch_name != "feature"));
+        assert!(storage.checkout_history("/repo").unwrap().is_empty());
+        assert_eq!(storage.previous_branch("/repo").unwrap(), None);
+
+        assert!(storage.undo_last_checkout("/repo").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_transitions_from_counts_next_branches() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+        storage
+            .record_checkout("/repo", Some("main"), "develop")
+            .unwrap();
+        storage
+            .record_checkout("/repo", Some("develop"), "main")
+            .unwrap();
+        storage
+            .record_checkout("/repo", Some("main"), "develop")
+            .unwrap();
+        storage
+            .record_checkout("/repo", Some("main"), "feature")
+            .unwrap();
+        storage
+            .record_checkout("/other", Some("main"), "feature")
+            .unwrap();
+
+        let transitions = storage.transitions_from("/repo", "main").unwrap();
+        assert_eq!(transitions.len(), 2);
+        assert_eq!(transitions[0].to_branch, "develop");
+        assert_eq!(transitions[0].count, 2);
+        assert_eq!(transitions[1].to_branch, "feature");
+        assert_eq!(transitions[1].count, 1);
+
+        assert!(storage
+            .transitions_from("/repo", "feature")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_excluded_branches_are_not_recorded_or_returned() {
+        let storage = Storage::new(SqliteStore::open_in_memory().unwrap());
+        storage
+            .record_checkout("/repo", None, "release/1.0")
+            .unwrap();
+
+        let storage = storage.with_exclude(vec!["release/*".to_string()]);
+        storage
+            .record_checkout("/repo", None, "release/2.0")
+            .unwrap();
+        storage.record_checkout("/repo", None, "main").unwrap();
+
+        let records = storage.branch_records("/repo").unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].branch_name, "main");
+        assert_eq!(storage.all_records().unwrap().len(), 1);
+
+        // Nothing was written for the excluded checkout
+        assert_eq!(storage.checkout_events("/repo").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_branch_notes() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+
+        storage
+            .set_branch_note("/repo", "feat/api", "waiting on review")
+            .unwrap();
+        storage
+            .set_branch_note("/repo", "feat/api", "approved")
+            .unwrap();
+        storage.set_branch_note("/other", "main", "prod").unwrap();
+
+        let notes = storage.branch_notes("/repo").unwrap();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes["feat/api"], "approved");
+        assert_eq!(
+            storage.recorded_branches("/repo").unwrap(),
+            vec!["feat/api"]
+        );
+
+        // Notes follow renames and go away with the branch
+        storage
+            .rename_branch_data("/repo", "feat/api", "feat/api-v2")
+            .unwrap();
+        assert_eq!(
+            storage.branch_notes("/repo").unwrap()["feat/api-v2"],
+            "approved"
+        );
+        storage.delete_branch_data("/repo", "feat/api-v2").unwrap();
+        assert!(storage.branch_notes("/repo").unwrap().is_empty());
+
+        assert!(storage.remove_branch_note("/other", "main").unwrap());
+        assert!(!storage.remove_branch_note("/other", "main").unwrap());
+    }
+
+    #[test]
+    fn test_recent_checkout_events() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+        storage.record_checkout("/repo", None, "main").unwrap();
+        storage
+            .record_checkout("/repo", Some("main"), "feature")
+            .unwrap();
+        storage.conn
+            .execute(
+                "UPDATE checkout_events SET switched_at = switched_at - 7200 WHERE to_branch = 'main'",
+                [],
+            )
+            .unwrap();
+
+        let events = storage.recent_checkout_events("/repo", 3600).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].to_branch, "feature");
+        assert_eq!(
+            storage
+                .recent_checkout_events("/repo", 86400)
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(storage
+            .recent_checkout_events("/other", 86400)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_branch_groups() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+
+        let added = storage
+            .add_to_group("/repo", "backend", &["feature/api", "feature/db"])
+            .unwrap();
+        assert_eq!(added, 2);
+        assert_eq!(
+            storage
+                .add_to_group("/repo", "backend", &["feature/api", "svc/*"])
+                .unwrap(),
+            1
+        );
+        storage
+            .add_to_group("/repo", "ui", &["feature/api"])
+            .unwrap();
+
+        let groups = storage.branch_groups("/repo").unwrap();
+        assert_eq!(
+            groups["backend"],
+            vec!["feature/api", "feature/db", "svc/*"]
+        );
+        assert_eq!(groups["ui"], vec!["feature/api"]);
+        assert!(storage.branch_groups("/other").unwrap().is_empty());
+
+        // Members follow renames and go away with the branch
+        storage
+            .rename_branch_data("/repo", "feature/db", "feature/storage")
+            .unwrap();
+        storage.delete_branch_data("/repo", "feature/api").unwrap();
+        let groups = storage.branch_groups("/repo").unwrap();
+        assert_eq!(groups["backend"], vec!["svc/*", "feature/storage"]);
+        assert!(!groups.contains_key("ui"));
+
+        assert_eq!(
+            storage
+                .remove_from_group("/repo", "backend", &["svc/*", "missing"])
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            storage.remove_from_group("/repo", "backend", &[]).unwrap(),
+            1
+        );
+        assert!(storage.branch_groups("/repo").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_branch_cache() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+        let branches = vec!["main".to_string(), "feature/auth".to_string()];
+
+        assert_eq!(storage.cached_branches("/repo", "abc").unwrap(), None);
+        storage.cache_branches("/repo", "abc", &branches).unwrap();
+        assert_eq!(
+            storage.cached_branches("/repo", "abc").unwrap(),
+            Some(branches)
+        );
+
+        // A new fingerprint replaces the entry; the old one no longer hits
+        storage.cache_branches("/repo", "def", &[]).unwrap();
+        assert_eq!(storage.cached_branches("/repo", "abc").unwrap(), None);
+        assert_eq!(
+            storage.cached_branches("/repo", "def").unwrap(),
+            Some(vec![])
+        );
+
+        // The cache isn't history: it's dropped with the repository but not counted
+        assert_eq!(storage.repo_row_count("/repo").unwrap(), 0);
+        storage.forget_repo("/repo").unwrap();
+        assert_eq!(storage.cached_branches("/repo", "def").unwrap(), None);
+    }
+
+    #[test]
+    fn test_pin_and_unpin_branch() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+
+        storage.pin_branch("/repo", "main").unwrap();
+        storage.pin_branch("/repo", "develop").unwrap();
+        storage.pin_branch("/repo", "main").unwrap();
+        storage.pin_branch("/other", "feature").unwrap();
+
+        assert_eq!(
+            storage.pinned_branches("/repo").unwrap(),
+            vec!["main", "develop"]
+        );
+
+        assert!(storage.unpin_branch("/repo", "main").unwrap());
+        assert!(!storage.unpin_branch("/repo", "main").unwrap());
+        assert_eq!(storage.pinned_branches("/repo").unwrap(), vec!["develop"]);
+
+        storage.delete_branch_data("/repo", "develop").unwrap();
+        assert!(storage.pinned_branches("/repo").unwrap().is_empty());
+        assert_eq!(storage.pinned_branches("/other").unwrap(), vec!["feature"]);
+    }
+
+    #[test]
+    fn test_stacks_follow_renames_and_removals() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+        let links = |repo: &str| -> Vec<(String, String)> {
+            storage
+                .stack_links(repo)
+                .unwrap()
+                .into_iter()
+                .map(|link| (link.branch, link.parent))
+                .collect()
+        };
+        let link = |branch: &str, parent: &str| (branch.to_string(), parent.to_string());
+
+        storage.set_stack_parent("/repo", "a", "main").unwrap();
+        storage.set_stack_parent("/repo", "b", "a").unwrap();
+        storage.set_stack_parent("/repo", "c", "b").unwrap();
+        storage.set_stack_parent("/other", "x", "main").unwrap();
+
+        storage.rename_stack_branch("/repo", "b", "b2").unwrap();
+        assert_eq!(
+            links("/repo"),
+            vec![link("a", "main"), link("b2", "a"), link("c", "b2")]
+        );
+
+        // Branches stacked on a removed branch move down to its parent
+        assert!(storage.unstack_branch("/repo", "b2").unwrap());
+        assert!(!storage.unstack_branch("/repo", "b2").unwrap());
+        assert_eq!(links("/repo"), vec![link("a", "main"), link("c", "a")]);
+
+        // Deleting the bottom branch's data leaves its children unstacked
+        storage.delete_branch_data("/repo", "main").unwrap();
+        assert_eq!(links("/repo"), vec![link("c", "a")]);
+        assert_eq!(links("/other"), vec![link("x", "main")]);
+
+        storage.merge_repo("/other", "/repo").unwrap();
+        assert_eq!(links("/repo"), vec![link("c", "a"), link("x", "main")]);
+    }
+
+    #[test]
+    fn test_merge_repo_moves_all_history() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+
+        storage.record_checkout("/old", None, "main").unwrap();
+        storage
+            .record_checkout("/old", Some("main"), "feature")
+            .unwrap();
+        storage.record_checkout("/new", None, "main").unwrap();
+        storage.create_alias("/old", "m", "main").unwrap();
+        storage.create_alias("/old", "f", "feature").unwrap();
+        storage.create_alias("/new", "m", "master").unwrap();
+        storage.pin_branch("/old", "feature").unwrap();
+        storage.save_previous_branch("/old", "main").unwrap();
+
+        assert!(storage.merge_repo("/old", "/new").unwrap() > 0);
+
+        let records = storage.branch_records("/new").unwrap();
+        let main = records.iter().find(|r| r.branch_name == "main").unwrap();
+        assert_eq!(main.switch_count, 2);
+        assert!(records.iter().any(|r| r.branch_name == "feature"));
+
+        // Existing aliases under the new key win
+        assert_eq!(
+            storage.alias("/new", "m").unwrap().as_deref(),
+            Some("master")
+        );
+        assert_eq!(
+            storage.alias("/new", "f").unwrap().as_deref(),
+            Some("feature")
+        );
+        assert_eq!(storage.pinned_branches("/new").unwrap(), vec!["feature"]);
+        assert_eq!(
+            storage.previous_branch("/new").unwrap().as_deref(),
+            Some("main")
+        );
+        assert_eq!(storage.checkout_events("/new").unwrap().len(), 3);
+
+        assert!(storage.branch_records("/old").unwrap().is_empty());
+        assert!(storage.checkout_events("/old").unwrap().is_empty());
+        assert_eq!(storage.merge_repo("/old", "/new").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_rename_branch_data_moves_history() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+
+        storage.record_checkout("/repo", None, "feat/old").unwrap();
+        storage
+            .record_checkout("/repo", Some("feat/old"), "main")
+            .unwrap();
+        storage.record_checkout("/repo", None, "feat/new").unwrap();
+        storage.create_alias("/repo", "f", "feat/old").unwrap();
+        storage.pin_branch("/repo", "feat/old").unwrap();
+        storage.save_previous_branch("/repo", "feat/old").unwrap();
+        storage
+            .set_stack_parent("/repo", "feat/old", "main")
+            .unwrap();
+
+        assert!(storage
+            .rename_branch_data("/repo", "feat/old", "feat/new")
+            .unwrap());
+
+        let records = storage.branch_records("/repo").unwrap();
+        assert!(!records.iter().any(|r| r.branch_name == "feat/old"));
+        let new = records
+            .iter()
+            .find(|r| r.branch_name == "feat/new")
+            .unwrap();
+        assert_eq!(new.switch_count, 2);
+        assert_eq!(
+            storage.alias("/repo", "f").unwrap().as_deref(),
+            Some("feat/new")
+        );
+        assert_eq!(storage.pinned_branches("/repo").unwrap(), vec!["feat/new"]);
+        assert_eq!(
+            storage.previous_branch("/repo").unwrap().as_deref(),
+            Some("feat/new")
+        );
+        assert_eq!(
+            storage.transitions_from("/repo", "feat/new").unwrap().len(),
+            1
+        );
+        assert_eq!(storage.stack_links("/repo").unwrap()[0].branch, "feat/new");
+
+        assert!(!storage
+            .rename_branch_data("/repo", "feat/old", "feat/new")
+            .unwrap());
+    }
+
+    #[test]
+    fn test_import_history_merges_or_replaces() {
+        let laptop = SqliteStore::open_in_memory().unwrap();
+        laptop.record_checkout("/repo", None, "main").unwrap();
+        laptop.record_checkout("/repo", None, "main").unwrap();
+        laptop.record_checkout("/repo", None, "feature").unwrap();
+        laptop.create_alias("/repo", "m", "main").unwrap();
+        laptop.save_previous_branch("/repo", "main").unwrap();
+
+        let mut export = laptop.export_history().unwrap();
+        assert_eq!(export.branches.len(), 2);
+        assert_eq!(export.aliases.len(), 1);
+        assert_eq!(export.previous_branches.len(), 1);
+        // An older alias target loses against the desktop's newer one
+        export.aliases[0].created_at = 0;
+
+        let desktop = SqliteStore::open_in_memory().unwrap();
+        desktop.record_checkout("/repo", None, "main").unwrap();
+        desktop.record_checkout("/repo", None, "bugfix").unwrap();
+        desktop.create_alias("/repo", "m", "master").unwrap();
+
+        assert_eq!(desktop.import_history(&export, true).unwrap(), 4);
+        let records = desktop.branch_records("/repo").unwrap();
+        let count = |name: &str| {
+            records
+                .iter()
+                .find(|r| r.branch_name == name)
+                .map(|r| r.switch_count)
+        };
+        assert_eq!(count("main"), Some(3));
+        assert_eq!(count("feature"), Some(1));
+        assert_eq!(count("bugfix"), Some(1));
+        assert_eq!(
+            desktop.alias("/repo", "m").unwrap().as_deref(),
+            Some("master")
+        );
+        assert_eq!(
+            desktop.previous_branch("/repo").unwrap().as_deref(),
+            Some("main")
+        );
+
+        // Without merge the imported rows overwrite, other rows stay
+        desktop.import_history(&export, false).unwrap();
+        let records = desktop.branch_records("/repo").unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            records
+                .iter()
+                .find(|r| r.branch_name == "main")
+                .map(|r| r.switch_count),
+            Some(2)
+        );
+        assert_eq!(
+            desktop.alias("/repo", "m").unwrap().as_deref(),
+            Some("main")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_migration_v6_merges_symlinked_repo_paths() {
+        let repo_dir = tempfile::tempdir().unwrap();
+        git2::Repository::init(repo_dir.path()).unwrap();
+        let link_dir = tempfile::tempdir().unwrap();
+        let link = link_dir.path().join("repo-link");
+        std::os::unix::fs::symlink(repo_dir.path(), &link).unwrap();
+
+        let canonical = format!("{}/", repo_dir.path().canonicalize().unwrap().display());
+        let linked = format!("{}/", link.display());
+
+        // A v5 database with the same repository recorded under both paths
+        let conn = open_test_db().unwrap();
+        conn.execute("DELETE FROM schema_version WHERE version >= 6", [])
+            .unwrap();
+        for path in [&canonical, &linked] {
+            do_record_checkout(&conn, path, "main").unwrap();
+        }
+        do_record_checkout(&conn, "/gone/repo/", "main").unwrap();
+
+        initialize_tables(&conn).unwrap();
+
+        let count = |path: &str| -> i64 {
+            conn.query_row(
+                "SELECT COALESCE(SUM(switch_count), 0) FROM branches WHERE repo_path = ?1",
+                [path],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!(count(&canonical), 2);
+        assert_eq!(count(&linked), 0);
+        // Paths without a repository are left alone
+        assert_eq!(count("/gone/repo/"), 1);
+    }
+
+    #[test]
+    fn test_cleanup_deleted_branches_groups_by_repository() {
+        let repo_dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(repo_dir.path()).unwrap();
+        let signature = git2::Signature::now("ggo", "ggo@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let commit = repo
+            .commit(None, &signature, &signature, "init", &tree, &[])
+            .unwrap();
+        repo.branch("live", &repo.find_commit(commit).unwrap(), false)
+            .unwrap();
+        let repo_path = format!("{}/", repo_dir.path().display());
+
+        let storage = SqliteStore::open_in_memory().unwrap();
+        for branch in ["live", "dead-1", "dead-2"] {
+            storage.record_checkout(&repo_path, None, branch).unwrap();
+        }
+        storage.create_alias(&repo_path, "d", "dead-1").unwrap();
+        storage
+            .record_checkout("/gone/repo/", None, "main")
+            .unwrap();
+        storage
+            .create_alias("/gone/repo/", "x", "never-used")
+            .unwrap();
+        storage
+            .record_checkout("github.com/o/r", None, "anything")
+            .unwrap();
+
+        let stale = storage.deleted_branch_records(None).unwrap();
+        assert_eq!(stale.len(), 2);
+        assert_eq!(stale[0].repo_path, "/gone/repo/");
+        assert!(stale[0].repo_gone);
+        assert_eq!(stale[1].records.len(), 2);
+        assert!(!stale[1].repo_gone);
+
+        // Scoped to one repository
+        let scoped = storage.deleted_branch_records(Some(&repo_path)).unwrap();
+        assert_eq!(scoped.len(), 1);
+
+        let removed = storage.cleanup_deleted_branches(None).unwrap();
+        assert_eq!(removed.iter().map(|r| r.records.len()).sum::<usize>(), 3);
+        assert_eq!(storage.branch_records(&repo_path).unwrap().len(), 1);
+        assert!(storage.aliases(&repo_path).unwrap().is_empty());
+        assert!(storage.aliases("/gone/repo/").unwrap().is_empty());
+        assert_eq!(storage.branch_records("github.com/o/r").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_maintenance_due_every_n_invocations() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+
+        let due: Vec<bool> = (0..6)
+            .map(|_| storage.maintenance_due(3).unwrap())
+            .collect();
+        assert_eq!(due, vec![false, false, true, false, false, true]);
+
+        assert!(!storage.maintenance_due(0).unwrap());
+        storage.quick_optimize().unwrap();
+    }
+
+    #[test]
+    fn test_cleanup_scoped_to_repository() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+        for repo in ["/a", "/b"] {
+            storage.record_checkout(repo, None, "old").unwrap();
+            storage.record_checkout(repo, None, "new").unwrap();
+        }
+
+        let old = now_timestamp() - 100 * 86400;
+        storage
+            .conn
+            .execute(
+                "UPDATE branches SET last_used = ?1 WHERE branch_name = 'old'",
+                [old],
+            )
+            .unwrap();
+        storage
+            .conn
+            .execute(
+                "UPDATE checkout_events SET switched_at = ?1 WHERE to_branch = 'old'",
+                [old],
+            )
+            .unwrap();
+
+        // Previews report without deleting
+        assert_eq!(storage.old_records(30, None).unwrap().len(), 2);
+        let candidates = storage.old_records(30, Some("/a")).unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].repo_path, "/a");
+        assert_eq!(storage.old_events(30, Some("/a")).unwrap(), (1, Some(old)));
+
+        assert_eq!(storage.cleanup_old_records(30, Some("/a")).unwrap(), 1);
+        assert_eq!(storage.compact_events(30, Some("/a")).unwrap(), 1);
+        assert_eq!(storage.branch_records("/a").unwrap().len(), 1);
+        assert_eq!(storage.branch_records("/b").unwrap().len(), 2);
+        assert_eq!(storage.checkout_events("/b").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_switch_heatmap_counts_events() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+        storage.record_checkout("/a", None, "x").unwrap();
+        storage.record_checkout("/a", Some("x"), "y").unwrap();
+        storage.record_checkout("/b", None, "z").unwrap();
+
+        // Pin every event to one moment so they land in the same cell
+        storage
+            .conn
+            .execute("UPDATE checkout_events SET switched_at = 1700000000", [])
+            .unwrap();
+
+        let heatmap = storage.switch_heatmap(&StatsFilter::default()).unwrap();
+        let cells: Vec<i64> = heatmap
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|&c| c > 0)
+            .collect();
+        assert_eq!(cells, vec![3]);
+
+        let only_a = StatsFilter {
+            repo_path: Some("/a".to_string()),
+            max_age_seconds: None,
+        };
+        let total: i64 = storage
+            .switch_heatmap(&only_a)
+            .unwrap()
+            .iter()
+            .flatten()
+            .sum();
+        assert_eq!(total, 2);
+
+        let recent = StatsFilter {
+            repo_path: None,
+            max_age_seconds: Some(86400),
+        };
+        let total: i64 = storage
+            .switch_heatmap(&recent)
+            .unwrap()
+            .iter()
+            .flatten()
+            .sum();
+        assert_eq!(total, 0);
+    }
+
+    #[test]
+    fn test_stats_filters_and_sorting() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+        storage.record_checkout("/a", None, "busy").unwrap();
+        storage.record_checkout("/a", None, "busy").unwrap();
+        storage.record_checkout("/a", None, "busy").unwrap();
+        storage.record_checkout("/a", None, "old").unwrap();
+        storage.record_checkout("/b", None, "fresh").unwrap();
+
+        let old = now_timestamp() - 60 * 86400;
+        storage
+            .conn
+            .execute(
+                "UPDATE branches SET last_used = ?1 WHERE branch_name = 'old'",
+                [old],
+            )
+            .unwrap();
+
+        let all = StatsFilter::default();
+        let stats = storage.stats(&all).unwrap();
+        assert_eq!(
+            (
+                stats.total_switches,
+                stats.unique_branches,
+                stats.unique_repos
+            ),
+            (5, 3, 2)
+        );
+
+        let names = |records: Vec<BranchRecord>| -> Vec<String> {
+            records.into_iter().map(|r| r.branch_name).collect()
+        };
+        assert_eq!(
+            names(storage.top_records(&all, StatsSort::Switches, 1).unwrap()),
+            vec!["busy"]
+        );
+        assert_eq!(
+            storage
+                .top_records(&all, StatsSort::Recent, 10)
+                .unwrap()
+                .last()
+                .unwrap()
+                .branch_name,
+            "old"
+        );
+        // Score leaves the ranking (and the limit) to the caller
+        assert_eq!(
+            storage
+                .top_records(&all, StatsSort::Score, 1)
+                .unwrap()
+                .len(),
+            3
+        );
+
+        let recent_in_a = StatsFilter {
+            repo_path: Some("/a".to_string()),
+            max_age_seconds: Some(30 * 86400),
+        };
+        assert_eq!(storage.stats(&recent_in_a).unwrap().total_switches, 3);
+        assert_eq!(
+            names(
+                storage
+                    .top_records(&recent_in_a, StatsSort::Switches, 10)
+                    .unwrap()
+            ),
+            vec!["busy"]
+        );
+        let last_used = |repo: &str| {
+            storage
+                .branch_records(repo)
+                .unwrap()
+                .iter()
+                .map(|r| r.last_used)
+                .max()
+                .unwrap()
+        };
+        assert_eq!(
+            storage.repo_summaries(&all).unwrap(),
+            vec![
+                RepoSummary {
+                    repo_path: "/a".to_string(),
+                    branches: 2,
+                    switches: 4,
+                    last_used: last_used("/a"),
+                },
+                RepoSummary {
+                    repo_path: "/b".to_string(),
+                    branches: 1,
+                    switches: 1,
+                    last_used: last_used("/b"),
+                },
+            ]
+        );
+        assert!(last_used("/a") > old);
+    }
+
+    #[test]
+    fn test_compact_events_keeps_aggregates() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+        storage.record_checkout("/repo", None, "main").unwrap();
+        storage.record_checkout("/repo", None, "main").unwrap();
+
+        let old = now_timestamp() - 100 * 86400;
+        storage
+            .conn
+            .execute(
+                "UPDATE checkout_events SET switched_at = ?1 WHERE id = 1",
+                [old],
+            )
+            .unwrap();
+
+        assert_eq!(storage.old_events(30, None).unwrap().0, 1);
+        assert_eq!(storage.compact_events(0, None).unwrap(), 0);
+        assert_eq!(storage.compact_events(30, None).unwrap(), 1);
+        assert_eq!(storage.checkout_events("/repo").unwrap().len(), 1);
+        assert_eq!(storage.branch_records("/repo").unwrap()[0].switch_count, 2);
+    }
+
+    #[test]
+    fn test_delete_branch_data_removes_events() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+        storage.record_checkout("/repo", None, "main").unwrap();
+        storage
+            .record_checkout("/repo", Some("main"), "feature")
+            .unwrap();
+        storage
+            .record_checkout("/repo", Some("feature"), "develop")
+            .unwrap();
+
+        storage.delete_branch_data("/repo", "feature").unwrap();
+
+        let events = storage.checkout_events("/repo").unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].to_branch, "main");
+    }
+
+    #[test]
+    fn test_forget_branches_and_repo() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+        storage.record_checkout("/repo", None, "main").unwrap();
+        storage
+            .record_checkout("/repo", Some("main"), "spike/a")
+            .unwrap();
+        storage.create_alias("/repo", "b", "spike/b").unwrap();
+        storage.pin_branch("/repo", "spike/a").unwrap();
+        storage.record_checkout("/other", None, "main").unwrap();
+
+        assert_eq!(
+            storage.recorded_branches("/repo").unwrap(),
+            vec!["main", "spike/a", "spike/b"]
+        );
+
+        storage
+            .forget_branches("/repo", &["spike/a", "spike/b"])
+            .unwrap();
+        assert_eq!(storage.recorded_branches("/repo").unwrap(), vec!["main"]);
+        assert!(storage.pinned_branches("/repo").unwrap().is_empty());
+
+        // main's record and the event that checked it out
+        assert_eq!(storage.repo_row_count("/repo").unwrap(), 2);
+        assert_eq!(storage.forget_repo("/repo").unwrap(), 2);
+        assert_eq!(storage.repo_row_count("/repo").unwrap(), 0);
+        assert_eq!(storage.repo_row_count("/other").unwrap(), 2);
+    }
+
+    #[test]
+    fn test_storage_handle_round_trip() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+
+        storage.record_checkout("/repo", None, "main").unwrap();
+        storage.record_checkout("/repo", None, "main").unwrap();
+        storage.create_alias("/repo", "m", "main").unwrap();
+        storage.save_previous_branch("/repo", "develop").unwrap();
+        storage.push_checkout_history("/repo", "develop").unwrap();
+
+        let records = storage.branch_records("/repo").unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].switch_count, 2);
+        assert_eq!(
+            storage.alias("/repo", "m").unwrap(),
+            Some("main".to_string())
+        );
+        assert_eq!(
+            storage.aliases_for_branch("/repo", "main").unwrap(),
+            vec!["m"]
+        );
+        assert_eq!(
+            storage.previous_branch("/repo").unwrap(),
+            Some("develop".to_string())
+        );
+        assert_eq!(storage.checkout_history("/repo").unwrap().len(), 1);
+        assert_eq!(
+            storage
+                .stats(&StatsFilter::default())
+                .unwrap()
+                .total_switches,
+            2
+        );
+
+        storage.delete_branch_data("/repo", "main").unwrap();
+        assert!(storage.branch_records("/repo").unwrap().is_empty());
+        assert_eq!(storage.alias("/repo", "m").unwrap(), None);
+    }
+
+    #[test]
+    fn test_env_var_isolates_database() {
+        // Create two different temp directories
+        let temp_dir1 = tempfile::tempdir().unwrap();
+        let temp_dir2 = tempfile::tempdir().unwrap();
+
+        let test_path1 = temp_dir1.path().join("db1");
+        let test_path2 = temp_dir2.path().join("db2");
+
+        // Use first database
+        std::env::set_var("GGO_DATA_DIR", &test_path1);
+        let conn1 = open_db(&get_db_path().unwrap()).unwrap();
+        do_record_checkout(&conn1, "/test1", "branch1").unwrap();
+
+        // Switch to second database
+        std::env::set_var("GGO_DATA_DIR", &test_path2);
+        let conn2 = open_db(&get_db_path().unwrap()).unwrap();
+        do_record_checkout(&conn2, "/test2", "branch2").unwrap();
+
+        // Verify isolation
+        let records1 = do_get_branch_records(&conn1, "/test1").unwrap();
+        let records2 = do_get_branch_records(&conn2, "/test2").unwrap();
+
+        assert_eq!(records1.len(), 1);
+        assert_eq!(records2.len(), 1);
+        assert_eq!(records1[0].branch_name, "branch1");
+        assert_eq!(records2[0].branch_name, "branch2");
+
+        // Clean up
+        std::env::remove_var("GGO_DATA_DIR");
+    }
+
+    #[test]
+    fn test_migration_preserves_existing_data() {
+        // Create a database with v1 and some data
+        let conn = Connection::open_in_memory().unwrap();
+
+        // Create schema_version table
+        conn.execute(
+            "CREATE TABLE schema_version (
+                version INTEGER PRIMARY KEY,
+                applied_at INTEGER NOT NULL
+            )",
+            [],
+        )
+        .unwrap();
+
+        // Create v1 schema
+        conn.execute(
+            "CREATE TABLE branches (
+                id INTEGER PRIMARY KEY,
+                repo_path TEXT NOT NULL,
+                branch_name TEXT NOT NULL,
+                switch_count INTEGER DEFAULT 1,
+                last_used INTEGER NOT NULL,
+                UNIQUE(repo_path, branch_name)
+            )",
+            [],
+        )
+        .unwrap();
+
+        conn.execute(
+            "INSERT INTO schema_version (version, applied_at) VALUES (1, 1234567890)",
+            [],
+        )
+        .unwrap();
+
+        // Add some test data
+        conn.execute(
+            "INSERT INTO branches (repo_path, branch_name, switch_count, last_used)
+             VALUES ('/test', 'main', 5, 1234567890)",
+            [],
+        )
+        .unwrap();
+
+        // Run migration to v2
+        initialize_tables(&conn).unwrap();
+
+        // Verify data is preserved
+        let switch_count: i64 = conn
+            .query_row(
+                "SELECT switch_count FROM branches WHERE branch_name = 'main'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(switch_count, 5);
+    }
+
+    #[test]
+    fn test_cleanup_old_records() {
+        let conn = open_test_db().unwrap();
+        let repo_path = unique_repo_path();
+        let now = now_timestamp();
+
+        // Add old and new records
+        do_record_checkout(&conn, &repo_path, "old-branch").unwrap();
+        conn.execute(
+            "UPDATE branches SET last_used = ?1 WHERE branch_name = 'old-branch'",
+            [now - (400 * 86400)], // 400 days old
+        )
+        .unwrap();
+
+        do_record_checkout(&conn, &repo_path, "recent-branch").unwrap();
+
+        // Cleanup records older than 365 days
+        let deleted = conn
+            .execute(
+                "DELETE FROM branches WHERE last_used < ?1",
+                [now - (365 * 86400)],
+            )
+            .unwrap();
+
+        assert_eq!(deleted, 1);
+
+        // Verify old branch was deleted
+        let records = do_get_branch_records(&conn, &repo_path).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].branch_name, "recent-branch");
+    }
+
+    #[test]
+    fn test_optimize_database() {
+        let conn = open_test_db().unwrap();
+
+        // Add some data
+        let repo_path = unique_repo_path();
+        do_record_checkout(&conn, &repo_path, "branch1").unwrap();
+        do_record_checkout(&conn, &repo_path, "branch2").unwrap();
+
+        // Run VACUUM and ANALYZE
+        let result = conn.execute("VACUUM", []);
+        assert!(result.is_ok());
+
+        let result = conn.execute("ANALYZE", []);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_get_database_size() {
+        // Test that get_db_path works and returns a valid path
+        let result = get_db_path();
+        assert!(result.is_ok());
+
+        let db_path = result.unwrap();
+        // Path should end with data.db
+        assert!(db_path.to_string_lossy().ends_with("data.db"));
+    }
+
+    #[test]
+    fn test_snapshot_round_trips_through_json() {
+        let storage = SqliteStore::open_in_memory().unwrap();
+        storage.record_checkout("/repo", None, "main").unwrap();
+        storage
+            .record_checkout("/repo", Some("main"), "feature")
+            .unwrap();
+        storage.push_checkout_history("/repo", "main").unwrap();
+        storage.save_previous_branch("/repo", "main").unwrap();
+        storage.create_alias("/repo", "f", "feature").unwrap();
+        storage.pin_branch("/repo", "main").unwrap();
+        storage.set_branch_note("/repo", "feature", "WIP").unwrap();
+        storage.add_to_group("/repo", "mine", &["feature"]).unwrap();
+        storage
+            .set_stack_parent("/repo", "feature", "main")
+            .unwrap();
+        storage.set_meta("sync.baseline", "{}").unwrap();
+
+        let snapshot = storage.snapshot().unwrap();
+        assert_eq!(snapshot.events.len(), 2);
+        assert_eq!(snapshot.stacks.len(), 1);
+
+        let json = JsonStore::open_in_memory();
+        json.restore_snapshot(&snapshot).unwrap();
+        assert_eq!(json.snapshot().unwrap(), snapshot);
+
+        // Restoring replaces what was there
+        let back = SqliteStore::open_in_memory().unwrap();
+        back.record_checkout("/other", None, "main").unwrap();
+        back.restore_snapshot(&json.snapshot().unwrap()).unwrap();
+        assert_eq!(back.snapshot().unwrap(), snapshot);
+        assert!(back.branch_records("/other").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,180 @@
+//! Colors for list output, stats, menu headers and warnings.
+//!
+//! Call [`init`] once with `--color` and the configured theme; until then, and
+//! whenever colors are off, the helpers return the text unchanged.
+//! [`set_quiet`] (`--quiet`) silences [`warn`].
+
+use std::fmt::Display;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use crate::config::Theme;
+use crossterm::style::{Attribute, Color, ContentStyle};
+
+/// What a piece of output is, so the theme can color it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// Ranking and frecency scores
+    Score,
+    /// Alias names next to branches
+    Alias,
+    /// The checked-out branch
+    Current,
+    /// The branch a checkout would pick (`→`)
+    Target,
+    /// The pin marker (`★`)
+    Pinned,
+    /// Section titles and table headers
+    Header,
+    /// Secondary details: times, commit subjects, notes
+    Dim,
+    Warning,
+    Error,
+}
+
+/// Whether stdout and stderr get colors, and with which theme
+struct Palette {
+    theme: Theme,
+    stdout: bool,
+    stderr: bool,
+}
+
+static PALETTE: OnceLock<Palette> = OnceLock::new();
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Decide once per run whether to color output: `when` is the `--color`
+/// value (`auto`, `always` or `never`)
+pub fn init(when: &str, theme: Theme) {
+    // https://no-color.org: any non-empty NO_COLOR turns colors off
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let _ = PALETTE.set(Palette {
+        theme,
+        stdout: enabled(when, no_color, io::stdout().is_terminal()),
+        stderr: enabled(when, no_color, io::stderr().is_terminal()),
+    });
+}
+
+/// `--color auto` colors terminals unless NO_COLOR is set; `always` and
+/// `never` override both
+fn enabled(when: &str, no_color: bool, terminal: bool) -> bool {
+    match when {
+        "always" => true,
+        "never" => false,
+        _ => terminal && !no_color,
+    }
+}
+
+/// `text` styled for stdout
+pub fn paint(role: Role, text: impl Display) -> String {
+    match PALETTE.get() {
+        Some(palette) if palette.stdout => styled(palette.theme, role, text),
+        _ => text.to_string(),
+    }
+}
+
+/// `text` styled for stderr
+pub fn paint_err(role: Role, text: impl Display) -> String {
+    match PALETTE.get() {
+        Some(palette) if palette.stderr => styled(palette.theme, role, text),
+        _ => text.to_string(),
+    }
+}
+
+/// The label warnings on stderr start with
+pub fn warning() -> String {
+    paint_err(Role::Warning, "⚠️  Warning:")
+}
+
+/// Leave warnings out for the rest of the run (`--quiet`)
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Whether `--quiet` asked for results and errors only
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Print a warning on stderr, unless `--quiet`
+pub fn warn(message: impl Display) {
+    if !is_quiet() {
+        eprintln!("{} {}", warning(), message);
+    }
+}
+
+fn styled(theme: Theme, role: Role, text: impl Display) -> String {
+    let (color, attribute) = match theme {
+        Theme::Default => bright(role),
+        Theme::Light => dark(role),
+        Theme::Mono => (None, mono(role)),
+    };
+
+    let mut style = ContentStyle::new();
+    style.foreground_color = color;
+    if let Some(attribute) = attribute {
+        style.attributes.set(attribute);
+    }
+    style.apply(text).to_string()
+}
+
+fn bright(role: Role) -> (Option<Color>, Option<Attribute>) {
+    match role {
+        Role::Score | Role::Pinned => (Some(Color::Yellow), None),
+        Role::Alias => (Some(Color::Cyan), None),
+        Role::Current => (Some(Color::Green), Some(Attribute::Bold)),
+        Role::Target => (Some(Color::Green), None),
+        Role::Header => (Some(Color::Blue), Some(Attribute::Bold)),
+        Role::Dim => (Some(Color::DarkGrey), None),
+        Role::Warning => (Some(Color::Yellow), Some(Attribute::Bold)),
+        Role::Error => (Some(Color::Red), Some(Attribute::Bold)),
+    }
+}
+
+fn dark(role: Role) -> (Option<Color>, Option<Attribute>) {
+    match role {
+        Role::Score | Role::Pinned => (Some(Color::DarkYellow), None),
+        Role::Alias => (Some(Color::DarkCyan), None),
+        Role::Current => (Some(Color::DarkGreen), Some(Attribute::Bold)),
+        Role::Target => (Some(Color::DarkGreen), None),
+        Role::Header => (Some(Color::DarkBlue), Some(Attribute::Bold)),
+        Role::Dim => (Some(Color::DarkGrey), None),
+        Role::Warning => (Some(Color::DarkYellow), Some(Attribute::Bold)),
+        Role::Error => (Some(Color::DarkRed), Some(Attribute::Bold)),
+    }
+}
+
+fn mono(role: Role) -> Option<Attribute> {
+    match role {
+        Role::Current | Role::Header | Role::Warning | Role::Error => Some(Attribute::Bold),
+        Role::Dim => Some(Attribute::Dim),
+        Role::Score | Role::Alias | Role::Target | Role::Pinned => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enabled() {
+        assert!(enabled("auto", false, true));
+        assert!(!enabled("auto", false, false));
+        assert!(!enabled("auto", true, true));
+        assert!(enabled("always", true, false));
+        assert!(!enabled("never", false, true));
+    }
+
+    #[test]
+    fn test_styled_themes() {
+        let bright = styled(Theme::Default, Role::Score, "12.5");
+        let dark = styled(Theme::Light, Role::Score, "12.5");
+        assert!(bright.contains("12.5") && bright.starts_with('\x1b'));
+        assert_ne!(bright, dark);
+
+        // Mono only uses attributes, and leaves plain roles alone
+        assert_eq!(styled(Theme::Mono, Role::Score, "12.5"), "12.5");
+        assert!(styled(Theme::Mono, Role::Header, "Branch").starts_with('\x1b'));
+    }
+}
@@ -0,0 +1,647 @@
+//! Switching to a branch and recording it: the checkout itself, the guards
+//! and hooks around it, and the history 'ggo -' and frecency ranking read.
+//! Shared by the command line and 'ggo serve'.
+//!
+//! Nothing here prints: [`switch_to_branch`] returns what it did, and
+//! warnings go to the caller's [`Warn`].
+
+use std::fmt;
+use std::path::PathBuf;
+
+use tracing::{debug, warn};
+
+use crate::backend::GitBackend;
+use crate::config::{self, GuardAction};
+use crate::error::{GgoError, Result};
+use crate::{constants, frecency, git, hooks, logfile, matcher, storage};
+
+/// Asks whether to switch to a branch despite a guard set to `confirm`,
+/// given the branch and why the guard tripped
+pub type ConfirmGuard = fn(&str, &str) -> Result<bool>;
+
+/// Reports something that went wrong around a switch without stopping it:
+/// a guard set to `warn`, a failed hook, history that couldn't be saved
+pub type Warn = fn(&str);
+
+/// How a resolved branch is switched to
+#[derive(Clone, Copy)]
+pub struct SwitchOptions<'a> {
+    /// The repository switched in (a fake one in tests)
+    pub backend: &'a dyn GitBackend,
+    pub force_detach: bool,
+    /// Skip the pre-checkout guards and hooks
+    pub force: bool,
+    /// Menus and prompts may be shown (a terminal, and no --no-interactive)
+    pub can_prompt: bool,
+    /// How a `confirm` guard asks when prompts may be shown; without it the
+    /// guard refuses
+    pub confirm: Option<ConfirmGuard>,
+    /// Leave out advisory messages (--quiet)
+    pub quiet: bool,
+    /// Where warnings go; the command line prints them on stderr
+    pub warn: Warn,
+    /// Run the trusted hooks in the repository's `.ggo.toml` after switching;
+    /// off for branches someone else pushed ('ggo pr')
+    pub repo_hooks: bool,
+}
+
+/// Key the current repository's history is stored under (`history.repo_identity`).
+/// With remote keys, history recorded under the repository's path moves over.
+pub fn repo_key(storage: &storage::Storage, config: &config::Config) -> Result<String> {
+    let path_key = git::repo_path_key()?;
+
+    if config.history.repo_identity == config::RepoIdentity::Remote {
+        if let Some(remote_key) = git::repo_remote_key()? {
+            if let Err(e) = storage.merge_repo(&path_key, &remote_key) {
+                warn!("Failed to move history to {}: {}", remote_key, e);
+            }
+            logfile::record_repo(&remote_key);
+            return Ok(remote_key);
+        }
+    }
+
+    logfile::record_repo(&path_key);
+    Ok(path_key)
+}
+
+/// What [`switch_to_branch`] did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Switched {
+    /// The branch was checked out already; nothing was recorded
+    AlreadyOn(String),
+    /// The branch is checked out now
+    Branch(String),
+    /// The branch is checked out in another worktree, and the shell
+    /// integration was asked to change to it
+    InWorktree { branch: String, worktree: PathBuf },
+    /// The branch is checked out in another worktree, so its commit was
+    /// checked out here with a detached HEAD
+    Detached { branch: String, worktree: PathBuf },
+}
+
+impl fmt::Display for Switched {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Switched::AlreadyOn(branch) => write!(f, "Already on '{}'", branch),
+            Switched::Branch(branch) => write!(f, "Switched to branch '{}'", branch),
+            Switched::InWorktree { branch, worktree } => write!(
+                f,
+                "Branch '{}' is checked out in worktree '{}'",
+                branch,
+                worktree.display()
+            ),
+            Switched::Detached { branch, worktree } => write!(
+                f,
+                "HEAD is now detached at '{}' (branch is checked out in '{}')",
+                branch,
+                worktree.display()
+            ),
+        }
+    }
+}
+
+/// Check out a branch and update history: re-verifies the branch, saves the
+/// current branch for 'ggo -', and records the switch for frecency.
+///
+/// If the branch is checked out in another worktree, the switch is redirected:
+/// with `force_detach` its commit is checked out here with a detached HEAD,
+/// otherwise the shell integration is asked to cd into that worktree.
+///
+/// Pre-checkout guards and hooks can cancel the switch (unless `force`);
+/// post-checkout hooks run after it.
+pub fn switch_to_branch(
+    storage: &storage::Storage,
+    config: &config::Config,
+    repo_path: &str,
+    branch: &str,
+    switch: &SwitchOptions,
+) -> Result<Switched> {
+    // Re-verify the branch exists right before checkout: it may have been
+    // deleted since the (possibly cached) list was read
+    let backend = switch.backend;
+    if !backend.branch_exists(branch)? {
+        return Err(GgoError::BranchNotFound(branch.to_string()));
+    }
+
+    let current_branch = backend.current_branch().ok();
+    if current_branch.as_deref() == Some(branch) {
+        // Nothing to switch, record or hook
+        return Ok(Switched::AlreadyOn(branch.to_string()));
+    }
+
+    if let Some(worktree) = backend.worktree_for_branch(branch)? {
+        if !switch.force_detach {
+            if request_directory_change(&worktree)? {
+                return Ok(Switched::InWorktree {
+                    branch: branch.to_string(),
+                    worktree,
+                });
+            }
+
+            return Err(GgoError::BranchInWorktree(
+                branch.to_string(),
+                worktree.display().to_string(),
+            ));
+        }
+
+        backend.repo_root()?;
+        let last_used = branch_last_used(storage, repo_path, branch);
+        check_pre_checkout(
+            storage,
+            config,
+            switch,
+            current_branch.as_deref(),
+            branch,
+            last_used,
+            true,
+        )?;
+        save_previous_location(
+            storage,
+            repo_path,
+            backend,
+            current_branch.as_deref(),
+            branch,
+            switch.warn,
+        );
+        backend.checkout_detached(branch)?;
+        record_branch_usage(
+            storage,
+            repo_path,
+            current_branch.as_deref(),
+            branch,
+            switch.warn,
+        );
+        run_post_checkout_hooks(
+            storage,
+            config,
+            switch,
+            current_branch.as_deref(),
+            branch,
+            true,
+        );
+
+        return Ok(Switched::Detached {
+            branch: branch.to_string(),
+            worktree,
+        });
+    }
+
+    // Nowhere to check out in a bare repository (worktrees are handled above)
+    backend.repo_root()?;
+
+    let last_used = branch_last_used(storage, repo_path, branch);
+    check_pre_checkout(
+        storage,
+        config,
+        switch,
+        current_branch.as_deref(),
+        branch,
+        last_used,
+        false,
+    )?;
+    save_previous_location(
+        storage,
+        repo_path,
+        backend,
+        current_branch.as_deref(),
+        branch,
+        switch.warn,
+    );
+
+    // Checkout the branch
+    backend.checkout(branch)?;
+
+    record_branch_usage(
+        storage,
+        repo_path,
+        current_branch.as_deref(),
+        branch,
+        switch.warn,
+    );
+
+    run_post_checkout_hooks(
+        storage,
+        config,
+        switch,
+        current_branch.as_deref(),
+        branch,
+        false,
+    );
+    Ok(Switched::Branch(branch.to_string()))
+}
+
+/// When ggo last switched to `branch` in this repository, if ever
+pub fn branch_last_used(storage: &storage::Storage, repo_path: &str, branch: &str) -> Option<i64> {
+    storage
+        .branch_records(repo_path)
+        .ok()?
+        .into_iter()
+        .find(|record| record.branch_name == branch)
+        .map(|record| record.last_used)
+}
+
+/// Check the guards configured under `[hooks]` and run the pre-checkout
+/// hooks (the repository's only once trusted); an error cancels the switch.
+/// `--force` skips all of it.
+pub fn check_pre_checkout(
+    storage: &storage::Storage,
+    config: &config::Config,
+    switch: &SwitchOptions,
+    from_branch: Option<&str>,
+    to_branch: &str,
+    last_used: Option<i64>,
+    detached: bool,
+) -> Result<()> {
+    if switch.force || from_branch == Some(to_branch) {
+        return Ok(());
+    }
+
+    let hooks_config = &config.hooks;
+    let blocked = |reason: String| GgoError::CheckoutBlocked(to_branch.to_string(), reason);
+
+    let mut tripped = Vec::new();
+    if hooks_config.guard_dirty != GuardAction::Off && switch.backend.has_uncommitted_changes()? {
+        tripped.push((
+            hooks_config.guard_dirty,
+            "there are uncommitted changes".to_string(),
+        ));
+    }
+    if let Some(from) = from_branch.filter(|_| hooks_config.guard_unpushed != GuardAction::Off) {
+        let unpushed = switch.backend.unpushed_commits(from)?;
+        if unpushed > 0 {
+            tripped.push((
+                hooks_config.guard_unpushed,
+                format!("'{}' has {} commit(s) on no remote", from, unpushed),
+            ));
+        }
+    }
+    if hooks_config.guard_protected != GuardAction::Off
+        && hooks_config
+            .protected
+            .iter()
+            .any(|pattern| matcher::glob_matches(pattern, to_branch))
+    {
+        tripped.push((
+            hooks_config.guard_protected,
+            format!("'{}' is protected", to_branch),
+        ));
+    }
+
+    // Branches never switched to with ggo have no age to go by
+    if let Some(last_used) = last_used.filter(|_| hooks_config.guard_stale != GuardAction::Off) {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let stale_after = i64::from(hooks_config.stale_days) * constants::frecency::DAY_SECONDS;
+        if now - last_used > stale_after {
+            tripped.push((
+                hooks_config.guard_stale,
+                format!(
+                    "'{}' was last used {}",
+                    to_branch,
+                    frecency::format_relative_time(last_used)
+                ),
+            ));
+        }
+    }
+
+    // Refuse before asking, and ask before warning
+    tripped.sort_by_key(|(action, _)| std::cmp::Reverse(*action));
+    for (action, reason) in tripped {
+        match action {
+            GuardAction::Off => {}
+            GuardAction::Warn => (switch.warn)(&reason),
+            GuardAction::Confirm => {
+                let confirmed = match switch.confirm.filter(|_| switch.can_prompt) {
+                    Some(confirm) => confirm(to_branch, &reason)?,
+                    None => false,
+                };
+                if !confirmed {
+                    return Err(blocked(reason));
+                }
+            }
+            GuardAction::Abort => return Err(blocked(reason)),
+        }
+    }
+
+    if !hooks_config.enabled {
+        return Ok(());
+    }
+
+    let repo_root = std::path::PathBuf::from(switch.backend.repo_root()?);
+    let mut commands = hooks_config.pre_checkout.clone();
+    let repo_hooks = hooks::repo_hooks(&repo_root)?;
+    if !repo_hooks.pre_checkout.is_empty()
+        && trusted(
+            storage,
+            &repo_root,
+            &repo_hooks,
+            "pre-checkout",
+            switch.warn,
+        )
+    {
+        commands.extend(repo_hooks.pre_checkout);
+    }
+
+    let event = hooks::CheckoutEvent {
+        repo_root: &repo_root,
+        from_branch,
+        to_branch,
+        detached,
+    };
+    match hooks::run_until_failure(&commands, &event) {
+        Some(failure) => Err(blocked(format!(
+            "pre-checkout hook '{}' failed: {}",
+            failure.command, failure.reason
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Run the post-checkout hooks from the config and, if trusted, the
+/// repository's `.ggo.toml`. The switch already happened, so failures only warn.
+pub fn run_post_checkout_hooks(
+    storage: &storage::Storage,
+    config: &config::Config,
+    switch: &SwitchOptions,
+    from_branch: Option<&str>,
+    to_branch: &str,
+    detached: bool,
+) {
+    if !config.hooks.enabled {
+        return;
+    }
+
+    let repo_root = match switch.backend.repo_root() {
+        Ok(root) => std::path::PathBuf::from(root),
+        Err(e) => {
+            warn!("Not running post-checkout hooks: {}", e);
+            return;
+        }
+    };
+
+    let mut commands = config.hooks.post_checkout.clone();
+    if switch.repo_hooks {
+        match hooks::repo_hooks(&repo_root) {
+            Ok(repo_hooks)
+                if !repo_hooks.post_checkout.is_empty()
+                    && trusted(
+                        storage,
+                        &repo_root,
+                        &repo_hooks,
+                        "post-checkout",
+                        switch.warn,
+                    ) =>
+            {
+                commands.extend(repo_hooks.post_checkout)
+            }
+            Ok(_) => {}
+            Err(e) => (switch.warn)(&format!("Ignoring repository hooks: {}", e)),
+        }
+    }
+
+    let event = hooks::CheckoutEvent {
+        repo_root: &repo_root,
+        from_branch,
+        to_branch,
+        detached,
+    };
+    for failure in hooks::run(&commands, &event) {
+        (switch.warn)(&format!(
+            "post-checkout hook '{}' failed: {}",
+            failure.command, failure.reason
+        ));
+    }
+}
+
+/// Whether the hooks in a repository's `.ggo.toml` may run: the file as it is
+/// now was trusted with 'ggo hooks trust'. Otherwise says why its `kind`
+/// hooks are skipped.
+fn trusted(
+    storage: &storage::Storage,
+    repo_root: &std::path::Path,
+    repo_hooks: &hooks::RepoHooks,
+    kind: &str,
+    warn: Warn,
+) -> bool {
+    let trusted_digest = storage
+        .trusted_hooks(&repo_root.to_string_lossy())
+        .unwrap_or_default();
+    let file = constants::hooks::REPO_HOOKS_FILE;
+    match repo_hooks.trust(trusted_digest.as_deref()) {
+        hooks::Trust::Trusted => return true,
+        hooks::Trust::Untrusted => warn(&format!(
+            "Not running the {} hooks in {}: review them with 'ggo hooks' and allow them with 'ggo hooks trust'",
+            kind, file
+        )),
+        hooks::Trust::Changed => warn(&format!(
+            "Not running the {} hooks in {}: it changed since you trusted it; review it with 'ggo hooks' and run 'ggo hooks trust' again",
+            kind, file
+        )),
+    }
+    false
+}
+
+/// Save the current branch as previous before switching (only if it changes)
+/// and push it onto the checkout history stack
+pub fn save_previous_branch(
+    storage: &storage::Storage,
+    repo_path: &str,
+    current_branch: Option<&str>,
+    target: &str,
+    warn: Warn,
+) {
+    if let Some(current) = current_branch {
+        if current != target {
+            if let Err(e) = storage.save_previous_branch(repo_path, current) {
+                warn!("Failed to save previous branch: {}", e);
+                warn(&format!(
+                    "Could not save previous branch: {}\n   The 'ggo -' command may not work correctly.",
+                    e
+                ));
+            } else {
+                debug!("Saved previous branch: {}", current);
+            }
+
+            if let Err(e) = storage.push_checkout_history(repo_path, current) {
+                warn!("Failed to record checkout history: {}", e);
+                warn(&format!(
+                    "Could not record checkout history: {}\n   The 'ggo -N' command may not work correctly.",
+                    e
+                ));
+            }
+        }
+    }
+}
+
+/// Save where HEAD is before a switch for 'ggo -': the current branch, or
+/// the commit of a detached HEAD (which isn't pushed onto the history stack)
+pub fn save_previous_location(
+    storage: &storage::Storage,
+    repo_path: &str,
+    backend: &dyn GitBackend,
+    current_branch: Option<&str>,
+    target: &str,
+    warn: Warn,
+) {
+    if current_branch.is_some() {
+        save_previous_branch(storage, repo_path, current_branch, target, warn);
+        return;
+    }
+
+    let Ok(Some(head)) = backend.detached_head() else {
+        return;
+    };
+    if head.commit == target {
+        return;
+    }
+    if let Err(e) = storage.save_previous_branch(repo_path, &head.commit) {
+        warn!("Failed to save previous location: {}", e);
+        warn(&format!(
+            "Could not save previous location: {}\n   The 'ggo -' command may not work correctly.",
+            e
+        ));
+    } else {
+        debug!("Saved previous location: detached at {}", head.name);
+    }
+}
+
+/// Record the checkout for frecency tracking, warning instead of failing
+pub fn record_branch_usage(
+    storage: &storage::Storage,
+    repo_path: &str,
+    from_branch: Option<&str>,
+    branch: &str,
+    warn: Warn,
+) {
+    if let Err(e) = storage.record_checkout(repo_path, from_branch, branch) {
+        // Don't fail the checkout if recording fails, just warn
+        warn(&format!(
+            "Could not save branch usage: {}\n   This won't affect future checkouts, but frecency tracking may be incomplete.",
+            e
+        ));
+    }
+}
+
+/// Record the current branch as used unless ggo already knows it's checked
+/// out, so branches checked out with plain git count for frecency too.
+pub fn record_current_branch(storage: &storage::Storage, config: &config::Config, warn: Warn) {
+    let Ok(current) = git::get_current_branch() else {
+        return;
+    };
+    let Ok(repo_path) = repo_key(storage, config) else {
+        return;
+    };
+    let window_seconds = i64::from(config.history.passive_window_minutes) * 60;
+    record_switch_to(storage, &repo_path, &current, window_seconds, warn);
+}
+
+/// Record a switch to `branch` made outside ggo, from where ggo last saw the
+/// repository. Returns false when that already was `branch`.
+///
+/// Coming back to a branch switched to within the last `window_seconds` (0
+/// for none) only moves 'ggo -' along: bouncing between two branches, or
+/// between worktrees that share a history, doesn't pile up switches.
+pub fn record_switch_to(
+    storage: &storage::Storage,
+    repo_path: &str,
+    branch: &str,
+    window_seconds: i64,
+    warn: Warn,
+) -> bool {
+    let last = storage.last_checkout(repo_path).ok().flatten();
+    // A visit left uncounted since the last recorded checkout is where the
+    // repository was last seen
+    let from = match storage.visit(repo_path).ok().flatten() {
+        Some(visit) if visit.last_checkout == last => Some(visit.branch),
+        _ => last.as_ref().map(|event| event.to_branch.clone()),
+    };
+    if from.as_deref() == Some(branch) {
+        return false;
+    }
+
+    save_previous_branch(storage, repo_path, from.as_deref(), branch, warn);
+    let seen_recently = window_seconds > 0
+        && storage
+            .recent_checkout_events(repo_path, window_seconds)
+            .is_ok_and(|events| events.iter().any(|event| event.to_branch == branch));
+    if seen_recently {
+        if let Err(e) = storage.save_visit(repo_path, branch, last.as_ref()) {
+            debug!("Could not note the visit to '{}': {}", branch, e);
+        }
+    } else {
+        record_branch_usage(storage, repo_path, from.as_deref(), branch, warn);
+    }
+    true
+}
+
+/// Ask the shell integration to change directory by writing the target path to
+/// the file named by `GGO_CD_FILE`. Returns false when no integration is active.
+pub fn request_directory_change(path: &std::path::Path) -> Result<bool> {
+    match std::env::var_os("GGO_CD_FILE") {
+        Some(cd_file) => {
+            std::fs::write(cd_file, path.to_string_lossy().as_bytes())?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+
+    const REPO: &str = "/repo";
+
+    #[test]
+    fn test_switched_messages() {
+        assert_eq!(
+            Switched::Branch("feature/auth".to_string()).to_string(),
+            "Switched to branch 'feature/auth'"
+        );
+        assert_eq!(
+            Switched::AlreadyOn("main".to_string()).to_string(),
+            "Already on 'main'"
+        );
+        let detached = Switched::Detached {
+            branch: "main".to_string(),
+            worktree: PathBuf::from("/src/app"),
+        };
+        assert_eq!(
+            detached.to_string(),
+            "HEAD is now detached at 'main' (branch is checked out in '/src/app')"
+        );
+    }
+
+    #[test]
+    fn test_record_switch_to_counts_returns_within_the_window_once() {
+        let storage = Storage::open_in_memory().unwrap();
+        let switches = |branch: &str| {
+            storage
+                .branch_records(REPO)
+                .unwrap()
+                .iter()
+                .find(|record| record.branch_name == branch)
+                .map_or(0, |record| record.switch_count)
+        };
+        let previous = || storage.previous_branch(REPO).unwrap();
+
+        assert!(record_switch_to(&storage, REPO, "main", 600, |_| {}));
+        assert!(record_switch_to(&storage, REPO, "feature", 600, |_| {}));
+
+        // Back and forth within the window: 'ggo -' follows, the counts don't
+        assert!(record_switch_to(&storage, REPO, "main", 600, |_| {}));
+        assert_eq!(previous().as_deref(), Some("feature"));
+        assert!(!record_switch_to(&storage, REPO, "main", 600, |_| {}));
+        assert!(record_switch_to(&storage, REPO, "feature", 600, |_| {}));
+        assert_eq!(previous().as_deref(), Some("main"));
+        assert_eq!((switches("main"), switches("feature")), (1, 1));
+
+        // Without a window every switch counts
+        assert!(record_switch_to(&storage, REPO, "main", 0, |_| {}));
+        assert_eq!(switches("main"), 2);
+        assert_eq!(previous().as_deref(), Some("feature"));
+    }
+}
@@ -0,0 +1,366 @@
+//! `ggo sync`: share usage history between machines through a git repository.
+//!
+//! Every machine writes only its own file, `<sync.dir>/<machine>.json`, with
+//! the switches made on that machine, so pushes from different machines never
+//! conflict. Merging another machine's file adds what changed since that file
+//! was last merged (its baseline, kept in the meta table), so a switch is
+//! counted once however often you sync.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::SyncConfig;
+use crate::error::{GgoError, Result};
+use crate::export::{ExportFormat, ExportedBranch, HistoryExport};
+use crate::storage::Storage;
+
+/// Meta key of this machine's sync name
+const MACHINE_KEY: &str = "sync.machine";
+
+/// Meta key prefix of the last merged history of each other machine
+const BASELINE_PREFIX: &str = "sync.baseline.";
+
+/// What a sync did
+#[derive(Debug)]
+pub struct SyncReport {
+    /// Local clone the history files live in
+    pub checkout: PathBuf,
+    /// Name this machine's history is published under
+    pub machine: String,
+    /// Other machines merged, with the number of branch records that changed
+    pub merged: Vec<(String, usize)>,
+    /// Whether this machine's history changed and was committed
+    pub committed: bool,
+    /// Whether the repository has a remote it was pushed to
+    pub pushed: bool,
+}
+
+/// Pull the sync repository, merge the other machines' history into the
+/// database, then commit and push this machine's own history
+pub fn sync(storage: &Storage, config: &SyncConfig) -> Result<SyncReport> {
+    if config.repo.trim().is_empty() {
+        return Err(GgoError::SyncNotConfigured);
+    }
+
+    let checkout = prepare_checkout(storage, &config.repo)?;
+    let has_remote = !git(&checkout, &["remote"])?.trim().is_empty();
+    let has_upstream = git_succeeds(&checkout, &["rev-parse", "--abbrev-ref", "@{upstream}"]);
+    if has_upstream {
+        git(&checkout, &["pull", "--rebase", "--autostash"])?;
+    }
+
+    let machine = machine_name(storage)?;
+    let dir = checkout.join(&config.dir);
+    let mut merged = Vec::new();
+    for (other, path) in machine_files(&dir)? {
+        if other == machine {
+            continue;
+        }
+        let content = std::fs::read_to_string(&path)?;
+        let export = HistoryExport::parse(&content)?;
+        merged.push((other.clone(), merge_machine(storage, &other, &export)?));
+    }
+
+    std::fs::create_dir_all(&dir)?;
+    let file = Path::new(&config.dir).join(format!("{}.json", machine));
+    let own = local_contribution(storage)?;
+    std::fs::write(checkout.join(&file), own.render(ExportFormat::Json)?)?;
+
+    let file = file.to_string_lossy();
+    git(&checkout, &["add", "--", &file])?;
+    let committed = !git_succeeds(&checkout, &["diff", "--cached", "--quiet", "--", &file]);
+    if committed {
+        let message = format!("ggo sync: {}", machine);
+        git(
+            &checkout,
+            &["commit", "--quiet", "-m", &message, "--", &file],
+        )?;
+    }
+
+    if has_remote {
+        if has_upstream {
+            git(&checkout, &["push", "--quiet"])?;
+        } else {
+            git(&checkout, &["push", "--quiet", "-u", "origin", "HEAD"])?;
+        }
+    }
+
+    Ok(SyncReport {
+        checkout,
+        machine,
+        merged,
+        committed,
+        pushed: has_remote,
+    })
+}
+
+/// The local clone to sync through: `repo` itself when it names a working
+/// tree, otherwise a clone of the remote `repo` kept next to the database
+fn prepare_checkout(storage: &Storage, repo: &str) -> Result<PathBuf> {
+    let local = expand_home(repo);
+    if local.join(".git").exists() {
+        return Ok(local);
+    }
+
+    let checkout = storage.db_path().with_file_name("sync");
+    if !checkout.join(".git").exists() {
+        let output = Command::new("git")
+            // A sync.repo starting with '-' is still a repository, not an option
+            .args(["clone", "--quiet", "--", repo])
+            .arg(&checkout)
+            .output()
+            .map_err(|e| GgoError::Other(format!("Failed to run 'git clone': {}", e)))?;
+        if !output.status.success() {
+            return Err(GgoError::Other(format!(
+                "'git clone {}' failed: {}",
+                repo,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+    }
+
+    Ok(checkout)
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// History files in the sync directory, by machine name
+fn machine_files(dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut files: Vec<(String, PathBuf)> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            let machine = path.file_stem()?.to_str()?.to_string();
+            Some((machine, path))
+        })
+        .collect();
+    files.sort();
+
+    Ok(files)
+}
+
+/// Name this machine publishes its history under: the host name plus a
+/// random suffix, chosen on the first sync and kept in the database
+pub fn machine_name(storage: &Storage) -> Result<String> {
+    if let Some(name) = storage.meta(MACHINE_KEY)? {
+        return Ok(name);
+    }
+
+    let host = std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .unwrap_or_default();
+    let host: String = host
+        .trim()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        .collect();
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    let suffix = (nanos ^ std::process::id().rotate_left(16)) & 0xffff;
+    let name = if host.is_empty() {
+        format!("machine-{:04x}", suffix)
+    } else {
+        format!("{}-{:04x}", host, suffix)
+    };
+
+    storage.set_meta(MACHINE_KEY, &name)?;
+    Ok(name)
+}
+
+/// Merge another machine's published history: switch counts change by what
+/// changed since its last merge, aliases and previous branches are taken when
+/// newer. Returns the number of branch records that changed.
+pub fn merge_machine(storage: &Storage, machine: &str, export: &HistoryExport) -> Result<usize> {
+    let key = format!("{}{}", BASELINE_PREFIX, machine);
+    let baseline = match storage.meta(&key)? {
+        Some(json) => HistoryExport::parse(&json)?,
+        None => HistoryExport::default(),
+    };
+
+    let delta = HistoryExport {
+        branches: branch_delta(&baseline.branches, &export.branches),
+        aliases: export.aliases.clone(),
+        previous_branches: export.previous_branches.clone(),
+        ..HistoryExport::default()
+    };
+
+    // The baseline only needs the counts
+    let snapshot = HistoryExport {
+        branches: export.branches.clone(),
+        ..HistoryExport::default()
+    };
+    storage.import_synced(&delta, &key, &snapshot.render(ExportFormat::Json)?)?;
+
+    Ok(delta.branches.len())
+}
+
+/// Branch records of `current` minus those of `baseline`: changed counts,
+/// newer timestamps, and negative counts for records that disappeared
+fn branch_delta(baseline: &[ExportedBranch], current: &[ExportedBranch]) -> Vec<ExportedBranch> {
+    let before: HashMap<(&str, &str), &ExportedBranch> = baseline
+        .iter()
+        .map(|b| ((b.repo_path.as_str(), b.branch_name.as_str()), b))
+        .collect();
+    let now: HashMap<(&str, &str), &ExportedBranch> = current
+        .iter()
+        .map(|b| ((b.repo_path.as_str(), b.branch_name.as_str()), b))
+        .collect();
+
+    let mut delta: Vec<ExportedBranch> = current
+        .iter()
+        .filter_map(|b| {
+            let old = before.get(&(b.repo_path.as_str(), b.branch_name.as_str()));
+            let (count, last_used) = old.map_or((0, 0), |o| (o.switch_count, o.last_used));
+            (b.switch_count != count || b.last_used > last_used).then(|| ExportedBranch {
+                switch_count: b.switch_count - count,
+                ..b.clone()
+            })
+        })
+        .collect();
+
+    delta.extend(
+        baseline
+            .iter()
+            .filter(|b| !now.contains_key(&(b.repo_path.as_str(), b.branch_name.as_str())))
+            .map(|b| ExportedBranch {
+                switch_count: -b.switch_count,
+                last_used: 0,
+                ..b.clone()
+            }),
+    );
+
+    delta
+}
+
+/// This machine's own history: the database minus everything merged from
+/// other machines
+pub fn local_contribution(storage: &Storage) -> Result<HistoryExport> {
+    let mut merged: HashMap<(String, String), i64> = HashMap::new();
+    for (_, json) in storage.meta_with_prefix(BASELINE_PREFIX)? {
+        for b in HistoryExport::parse(&json)?.branches {
+            *merged.entry((b.repo_path, b.branch_name)).or_default() += b.switch_count;
+        }
+    }
+
+    let mut export = storage.export_history()?;
+    for b in &mut export.branches {
+        let key = (b.repo_path.clone(), b.branch_name.clone());
+        b.switch_count -= merged.get(&key).copied().unwrap_or(0);
+    }
+    export.branches.retain(|b| b.switch_count > 0);
+
+    Ok(export)
+}
+
+/// Run git in `dir`, returning its standard output
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| GgoError::Other(format!("Failed to run 'git {}': {}", args[0], e)))?;
+
+    if !output.status.success() {
+        return Err(GgoError::Other(format!(
+            "'git {}' in {} failed: {}",
+            args.join(" "),
+            dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn git_succeeds(dir: &Path, args: &[&str]) -> bool {
+    Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn published(storage: &Storage) -> HistoryExport {
+        local_contribution(storage).unwrap()
+    }
+
+    fn count(storage: &Storage, branch: &str) -> Option<i64> {
+        storage
+            .branch_records("/repo")
+            .unwrap()
+            .into_iter()
+            .find(|r| r.branch_name == branch)
+            .map(|r| r.switch_count)
+    }
+
+    #[test]
+    fn test_repeated_merges_count_each_switch_once() {
+        let laptop = Storage::open_in_memory().unwrap();
+        let desktop = Storage::open_in_memory().unwrap();
+        laptop.record_checkout("/repo", None, "main").unwrap();
+        laptop.record_checkout("/repo", None, "main").unwrap();
+        desktop.record_checkout("/repo", None, "main").unwrap();
+
+        for _ in 0..3 {
+            merge_machine(&desktop, "laptop", &published(&laptop)).unwrap();
+            merge_machine(&laptop, "desktop", &published(&desktop)).unwrap();
+        }
+        assert_eq!(count(&desktop, "main"), Some(3));
+        assert_eq!(count(&laptop, "main"), Some(3));
+
+        // New switches on one side arrive once
+        laptop.record_checkout("/repo", None, "feature").unwrap();
+        laptop.record_checkout("/repo", None, "main").unwrap();
+        merge_machine(&desktop, "laptop", &published(&laptop)).unwrap();
+        merge_machine(&desktop, "laptop", &published(&laptop)).unwrap();
+        assert_eq!(count(&desktop, "main"), Some(4));
+        assert_eq!(count(&desktop, "feature"), Some(1));
+
+        // Only the desktop's own switch is published by the desktop
+        let own = published(&desktop);
+        assert_eq!(own.branches.len(), 1);
+        assert_eq!(own.branches[0].switch_count, 1);
+    }
+
+    #[test]
+    fn test_forgotten_branches_are_taken_back() {
+        let laptop = Storage::open_in_memory().unwrap();
+        let desktop = Storage::open_in_memory().unwrap();
+        laptop.record_checkout("/repo", None, "spike").unwrap();
+        merge_machine(&desktop, "laptop", &published(&laptop)).unwrap();
+        assert_eq!(count(&desktop, "spike"), Some(1));
+
+        laptop.forget_branches("/repo", &["spike"]).unwrap();
+        merge_machine(&desktop, "laptop", &published(&laptop)).unwrap();
+        assert_eq!(count(&desktop, "spike"), None);
+    }
+
+    #[test]
+    fn test_machine_name_is_kept() {
+        let storage = Storage::open_in_memory().unwrap();
+        let name = machine_name(&storage).unwrap();
+        assert!(!name.is_empty());
+        assert_eq!(machine_name(&storage).unwrap(), name);
+    }
+}
@@ -0,0 +1,143 @@
+//! `--list --format` templates: text with `{placeholder}`s filled in for each
+//! branch. `\t`, `\n` and `\\` are escapes, `{{` and `}}` literal braces.
+
+use crate::error::{GgoError, Result};
+
+/// Placeholders a template may use (described in the README)
+pub const PLACEHOLDERS: &[&str] = &[
+    "name",
+    "score",
+    "fuzzy_score",
+    "frecency",
+    "switches",
+    "last_used",
+    "last_used_ts",
+    "aliases",
+    "pinned",
+    "current",
+    "note",
+    "description",
+    "upstream",
+    "ahead",
+    "behind",
+    "last_commit",
+    "subject",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Placeholder(&'static str),
+}
+
+/// A parsed `--format` template
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// Parse a template, rejecting unknown placeholders and unbalanced braces
+    pub fn parse(template: &str) -> Result<Self> {
+        let invalid = |reason: String| GgoError::InvalidFormat(template.to_string(), reason);
+
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('t') => text.push('\t'),
+                    Some('n') => text.push('\n'),
+                    Some('\\') => text.push('\\'),
+                    Some(other) => {
+                        text.push('\\');
+                        text.push(other);
+                    }
+                    None => text.push('\\'),
+                },
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(invalid("'{' is never closed".to_string())),
+                        }
+                    }
+
+                    let placeholder = PLACEHOLDERS
+                        .iter()
+                        .find(|&&known| known == name.trim())
+                        .ok_or_else(|| invalid(format!("unknown placeholder '{{{}}}'", name)))?;
+
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Placeholder(placeholder));
+                }
+                '}' => return Err(invalid("'}' without an opening '{'".to_string())),
+                c => text.push(c),
+            }
+        }
+
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        if !parts
+            .iter()
+            .any(|part| matches!(part, Part::Placeholder(_)))
+        {
+            return Err(invalid("no placeholders".to_string()));
+        }
+
+        Ok(Template { parts })
+    }
+
+    /// Fill in the template, asking `field` for each placeholder's value
+    pub fn render(&self, field: impl Fn(&str) -> String) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Placeholder(name) => field(name),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_render() {
+        let template = Template::parse(r"{name}\t{score} {{x}}").unwrap();
+        let line = template.render(|name| match name {
+            "name" => "feature/auth".to_string(),
+            "score" => "12.5".to_string(),
+            _ => unreachable!(),
+        });
+        assert_eq!(line, "feature/auth\t12.5 {x}");
+
+        let template = Template::parse("{ name }:{aliases}").unwrap();
+        assert_eq!(template.render(|name| name.to_uppercase()), "NAME:ALIASES");
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_templates() {
+        assert!(Template::parse("{branch}").is_err());
+        assert!(Template::parse("{name").is_err());
+        assert!(Template::parse("name}").is_err());
+        assert!(Template::parse("just text").is_err());
+    }
+}
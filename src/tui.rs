@@ -0,0 +1,531 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, ClearType},
+};
+
+use crate::error::{GgoError, Result, PICK_TOP_MATCH_HINT};
+use crate::frecency;
+use crate::git;
+use crate::interactive::{BranchOption, Columns, SortMode};
+use crate::matcher::Finder;
+use crate::storage::BranchRecord;
+
+/// Number of commits shown in the preview pane (like `git log --oneline -5`)
+const PREVIEW_COMMITS: usize = 5;
+
+/// Outcome of handling a single key press
+#[derive(Debug, PartialEq)]
+enum Action {
+    Continue,
+    Select(String),
+    Cancel,
+}
+
+/// State of the full-screen picker
+struct Picker {
+    options: Vec<BranchOption>,
+    finder: Finder,
+    query: String,
+    sort: SortMode,
+    selected: usize,
+    visible: Vec<usize>,
+    previews: HashMap<String, Vec<String>>,
+}
+
+impl Picker {
+    fn new(options: Vec<BranchOption>) -> Self {
+        let mut picker = Self {
+            finder: Finder::new(options.iter().map(|o| o.name.as_str())),
+            options,
+            query: String::new(),
+            sort: SortMode::Score,
+            selected: 0,
+            visible: Vec::new(),
+            previews: HashMap::new(),
+        };
+        picker.refresh();
+        picker
+    }
+
+    /// Recompute the visible rows after the query or sort mode changed
+    fn refresh(&mut self) {
+        self.visible = visible(&mut self.finder, &self.options, &self.query, self.sort);
+        self.selected = self.selected.min(self.visible.len().saturating_sub(1));
+    }
+
+    fn highlighted(&self) -> Option<&BranchOption> {
+        self.visible
+            .get(self.selected)
+            .map(|&index| &self.options[index])
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key.code {
+            KeyCode::Esc => return Action::Cancel,
+            KeyCode::Char('c') if ctrl => return Action::Cancel,
+            KeyCode::Enter => {
+                return match self.highlighted() {
+                    Some(option) => Action::Select(option.name.clone()),
+                    None => Action::Continue,
+                };
+            }
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('p') if ctrl => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.move_down(),
+            KeyCode::Char('n') if ctrl => self.move_down(),
+            KeyCode::Tab => {
+                self.sort = self.sort.next();
+                self.refresh();
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.selected = 0;
+                self.refresh();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.selected = 0;
+                self.refresh();
+            }
+            _ => {}
+        }
+
+        Action::Continue
+    }
+
+    fn move_down(&mut self) {
+        if self.selected + 1 < self.visible.len() {
+            self.selected += 1;
+        }
+    }
+
+    /// Commits for the preview pane, cached per branch
+    fn preview(&mut self, branch: &str) -> &[String] {
+        self.previews
+            .entry(branch.to_string())
+            .or_insert_with(|| {
+                git::recent_commits(branch, PREVIEW_COMMITS)
+                    .unwrap_or_else(|e| vec![format!("(no preview available: {})", e)])
+            })
+            .as_slice()
+    }
+
+    fn render(&mut self, out: &mut impl Write) -> Result<()> {
+        let (width, height) = terminal::size()?;
+        let width = width as usize;
+
+        // Query line, status line, list, separator, preview title, preview commits
+        let preview_rows = PREVIEW_COMMITS as u16 + 2;
+        let list_rows = height.saturating_sub(preview_rows + 2).max(1) as usize;
+        let offset = (self.selected + 1).saturating_sub(list_rows);
+
+        queue!(out, terminal::Clear(ClearType::All))?;
+
+        let status = format!(
+            "  {}/{} branches · sort: {} (Tab) · ↑/↓ move · Enter select · Esc cancel",
+            self.visible.len(),
+            self.options.len(),
+            self.sort.label()
+        );
+        queue!(
+            out,
+            cursor::MoveTo(0, 1),
+            SetAttribute(Attribute::Dim),
+            Print(fit(&status, width)),
+            SetAttribute(Attribute::Reset)
+        )?;
+
+        for (row, &index) in self.visible.iter().skip(offset).take(list_rows).enumerate() {
+            let line = format!("  {}", self.options[index]);
+            queue!(out, cursor::MoveTo(0, row as u16 + 2))?;
+            if offset + row == self.selected {
+                queue!(
+                    out,
+                    SetAttribute(Attribute::Reverse),
+                    Print(fit(&format!(">{}", &line[1..]), width)),
+                    SetAttribute(Attribute::Reset)
+                )?;
+            } else {
+                queue!(out, Print(fit(&line, width)))?;
+            }
+        }
+
+        let preview_top = height.saturating_sub(preview_rows);
+        queue!(
+            out,
+            cursor::MoveTo(0, preview_top),
+            Print("─".repeat(width))
+        )?;
+
+        if let Some(branch) = self.highlighted().map(|o| o.name.clone()) {
+            let title = format!("git log --oneline -{} {}", PREVIEW_COMMITS, branch);
+            queue!(
+                out,
+                cursor::MoveTo(0, preview_top + 1),
+                SetAttribute(Attribute::Bold),
+                Print(fit(&title, width)),
+                SetAttribute(Attribute::Reset)
+            )?;
+
+            let commits = self.preview(&branch).to_vec();
+            for (row, commit) in commits.iter().enumerate() {
+                queue!(
+                    out,
+                    cursor::MoveTo(0, preview_top + 2 + row as u16),
+                    Print(fit(commit, width))
+                )?;
+            }
+        }
+
+        let prompt = format!("> {}", self.query);
+        queue!(
+            out,
+            cursor::MoveTo(0, 0),
+            Print(fit(&prompt, width)),
+            cursor::MoveTo(prompt.chars().count().min(width) as u16, 0)
+        )?;
+
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Puts the terminal into raw mode on an alternate screen, restoring it on drop
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Show)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Cut a line to the terminal width (character-based)
+fn fit(s: &str, width: usize) -> String {
+    s.chars().take(width).collect()
+}
+
+/// Indices of the options matching the query, ordered by the sort mode;
+/// in score mode better matches come first (see [`Finder::matches`])
+fn visible(
+    finder: &mut Finder,
+    options: &[BranchOption],
+    query: &str,
+    sort: SortMode,
+) -> Vec<usize> {
+    let mut visible = finder.matches(query);
+
+    match sort {
+        SortMode::Score => {}
+        SortMode::Name => visible.sort_by(|a, b| options[a.0].name.cmp(&options[b.0].name)),
+        SortMode::Recent => visible.sort_by(|a, b| {
+            options[b.0]
+                .last_used
+                .cmp(&options[a.0].last_used)
+                .then(a.0.cmp(&b.0))
+        }),
+    }
+
+    // Pinned branches stay on top whatever the sort
+    visible.sort_by_key(|&(i, _)| !options[i].pinned);
+
+    visible.into_iter().map(|(i, _)| i).collect()
+}
+
+/// Build picker options, keeping the ranked order of `branches`
+fn build_options(
+    branches: &[String],
+    records: &[BranchRecord],
+    pinned: &[String],
+    current: Option<&str>,
+    half_life_days: f64,
+) -> Vec<BranchOption> {
+    branches
+        .iter()
+        .map(|branch| {
+            let record = records.iter().find(|r| &r.branch_name == branch);
+            BranchOption {
+                name: branch.clone(),
+                score: record
+                    .map(|r| frecency::calculate_score(r, half_life_days))
+                    .unwrap_or(0.0),
+                switch_count: record.map(|r| r.switch_count).unwrap_or(0),
+                last_used: record.map(|r| r.last_used),
+                pinned: pinned.contains(branch),
+                current: current == Some(branch.as_str()),
+                ahead_behind: None,
+                last_commit: None,
+                note: None,
+                description: None,
+                columns: Columns::default(),
+            }
+        })
+        .collect()
+}
+
+/// Show the full-screen picker with live filtering and a commit preview pane
+pub fn select_branch(
+    branches: &[String],
+    records: &[BranchRecord],
+    pinned: &[String],
+    current: Option<&str>,
+    half_life_days: f64,
+) -> Result<String> {
+    if branches.is_empty() {
+        return Err(GgoError::Other(
+            "No branches available for selection".to_string(),
+        ));
+    }
+
+    if !crate::interactive::is_terminal() {
+        return Err(GgoError::NotATerminal(PICK_TOP_MATCH_HINT));
+    }
+
+    let mut picker = Picker::new(build_options(
+        branches,
+        records,
+        pinned,
+        current,
+        half_life_days,
+    ));
+    let _guard = TerminalGuard::enter()?;
+    let mut stdout = io::stdout();
+
+    loop {
+        picker.render(&mut stdout)?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            match picker.handle_key(key) {
+                Action::Continue => {}
+                Action::Select(branch) => return Ok(branch),
+                Action::Cancel => return Err(GgoError::UserCancelled),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::frecency::DEFAULT_HALF_LIFE_DAYS;
+
+    fn option(name: &str, last_used: Option<i64>) -> BranchOption {
+        BranchOption {
+            name: name.to_string(),
+            score: 0.0,
+            switch_count: 0,
+            last_used,
+            pinned: false,
+            current: false,
+            ahead_behind: None,
+            last_commit: None,
+            note: None,
+            description: None,
+            columns: Columns::default(),
+        }
+    }
+
+    fn options() -> Vec<BranchOption> {
+        vec![
+            option("main", Some(100)),
+            option("feature/auth", Some(300)),
+            option("bugfix/login", Some(200)),
+        ]
+    }
+
+    fn visible_options(options: &[BranchOption], query: &str, sort: SortMode) -> Vec<usize> {
+        visible(&mut finder(options), options, query, sort)
+    }
+
+    fn finder(options: &[BranchOption]) -> Finder {
+        Finder::new(options.iter().map(|o| o.name.as_str()))
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_visible_options_empty_query_keeps_rank_order() {
+        let visible = visible_options(&options(), "", SortMode::Score);
+        assert_eq!(visible, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_visible_options_sort_by_name() {
+        let visible = visible_options(&options(), "", SortMode::Name);
+        assert_eq!(visible, vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn test_visible_options_sort_by_recent() {
+        let visible = visible_options(&options(), "", SortMode::Recent);
+        assert_eq!(visible, vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn test_visible_options_pinned_first() {
+        let mut options = options();
+        options[2].pinned = true;
+
+        assert_eq!(
+            visible_options(&options, "", SortMode::Score),
+            vec![2, 0, 1]
+        );
+        assert_eq!(visible_options(&options, "", SortMode::Name), vec![2, 1, 0]);
+        assert_eq!(
+            visible_options(&options, "", SortMode::Recent),
+            vec![2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn test_visible_options_filters_case_insensitively() {
+        let visible = visible_options(&options(), "AUTH", SortMode::Score);
+        assert_eq!(visible, vec![1]);
+    }
+
+    #[test]
+    fn test_visible_options_fzf_syntax() {
+        assert_eq!(
+            visible_options(&options(), "!main", SortMode::Score),
+            vec![1, 2]
+        );
+        assert_eq!(
+            visible_options(&options(), "^feat", SortMode::Score),
+            vec![1]
+        );
+        assert_eq!(
+            visible_options(&options(), "'login", SortMode::Score),
+            vec![2]
+        );
+    }
+
+    #[test]
+    fn test_visible_options_many_branches() {
+        let options: Vec<BranchOption> = (0..10_000)
+            .map(|i| option(&format!("feature/team-{}/ticket-{}", i % 50, i), None))
+            .collect();
+        let mut finder = finder(&options);
+
+        // The same finder answers each keystroke; timings are in
+        // benches/matcher.rs
+        let mut query = String::new();
+        for c in "tk9999".chars() {
+            query.push(c);
+            assert!(!visible(&mut finder, &options, &query, SortMode::Score).is_empty());
+        }
+        assert_eq!(
+            visible(&mut finder, &options, "ticket-9999", SortMode::Score)[0],
+            9999
+        );
+    }
+
+    #[test]
+    fn test_picker_typing_filters_and_enter_selects() {
+        let mut picker = Picker::new(options());
+
+        assert_eq!(picker.handle_key(key(KeyCode::Char('l'))), Action::Continue);
+        assert_eq!(picker.handle_key(key(KeyCode::Char('o'))), Action::Continue);
+        assert_eq!(
+            picker.handle_key(key(KeyCode::Enter)),
+            Action::Select("bugfix/login".to_string())
+        );
+    }
+
+    #[test]
+    fn test_picker_navigation_is_bounded() {
+        let mut picker = Picker::new(options());
+
+        picker.handle_key(key(KeyCode::Up));
+        assert_eq!(picker.selected, 0);
+
+        for _ in 0..10 {
+            picker.handle_key(key(KeyCode::Down));
+        }
+        assert_eq!(picker.selected, 2);
+    }
+
+    #[test]
+    fn test_picker_tab_changes_sort() {
+        let mut picker = Picker::new(options());
+        picker.handle_key(key(KeyCode::Tab));
+
+        assert_eq!(picker.sort, SortMode::Name);
+        assert_eq!(
+            picker.handle_key(key(KeyCode::Enter)),
+            Action::Select("bugfix/login".to_string())
+        );
+    }
+
+    #[test]
+    fn test_picker_escape_cancels() {
+        let mut picker = Picker::new(options());
+        assert_eq!(picker.handle_key(key(KeyCode::Esc)), Action::Cancel);
+    }
+
+    #[test]
+    fn test_picker_enter_with_no_matches_does_nothing() {
+        let mut picker = Picker::new(options());
+        for c in "zzz".chars() {
+            picker.handle_key(key(KeyCode::Char(c)));
+        }
+        assert_eq!(picker.handle_key(key(KeyCode::Enter)), Action::Continue);
+    }
+
+    #[test]
+    fn test_build_options_keeps_order() {
+        let branches = vec!["b".to_string(), "a".to_string()];
+        let records = vec![BranchRecord {
+            repo_path: "/test".to_string(),
+            branch_name: "a".to_string(),
+            switch_count: 3,
+            last_used: 1700000000,
+        }];
+
+        let pinned = vec!["a".to_string()];
+
+        let built = build_options(
+            &branches,
+            &records,
+            &pinned,
+            Some("b"),
+            DEFAULT_HALF_LIFE_DAYS,
+        );
+        assert_eq!(built[0].name, "b");
+        assert_eq!(built[0].switch_count, 0);
+        assert!(!built[0].pinned);
+        assert_eq!(built[1].name, "a");
+        assert_eq!(built[1].switch_count, 3);
+        assert!(built[1].pinned);
+
+        assert!(built[0].current);
+        assert!(!built[1].current);
+    }
+
+    #[test]
+    fn test_fit_truncates_by_chars() {
+        assert_eq!(fit("日本語ブランチ", 3), "日本語");
+        assert_eq!(fit("short", 10), "short");
+    }
+}
@@ -0,0 +1,297 @@
+//! `ggo self-update`: find the latest GitHub release, download the binary
+//! built for this platform, check it against the SHA-256 published next to
+//! it and swap it in for the running executable.
+//!
+//! Only built with the `self-update` feature (on by default). Distribution
+//! packages build without it so updates come through the package manager.
+
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::constants::update::{CHECKSUM_SUFFIX, LATEST_RELEASE_URL, TIMEOUT_SECS};
+use crate::error::{GgoError, Result};
+
+/// A published release and the files attached to it
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Release {
+    /// Release tag, e.g. `v1.2.0`
+    #[serde(rename = "tag_name")]
+    pub tag: String,
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+}
+
+/// A file attached to a release
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    #[serde(rename = "browser_download_url")]
+    pub url: String,
+}
+
+impl Release {
+    /// The version the tag names, without its `v`
+    pub fn version(&self) -> &str {
+        self.tag.strip_prefix('v').unwrap_or(&self.tag)
+    }
+
+    pub fn asset(&self, name: &str) -> Option<&Asset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+}
+
+/// The version of the running ggo
+pub fn current_version() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
+
+/// Ask GitHub for the latest release
+pub fn latest_release() -> Result<Release> {
+    agent()
+        .get(LATEST_RELEASE_URL)
+        .set("Accept", "application/vnd.github+json")
+        .call()
+        .map_err(|e| request_failed(LATEST_RELEASE_URL, e))?
+        .into_json()
+        .map_err(|e| GgoError::UpdateFailed(format!("unreadable release information: {}", e)))
+}
+
+/// Name of the release binary for this platform, if one is published
+pub fn platform_asset() -> Option<&'static str> {
+    asset_name(std::env::consts::OS, std::env::consts::ARCH)
+}
+
+/// Name of the release binary for an OS and architecture, as in
+/// `std::env::consts`
+pub fn asset_name(os: &str, arch: &str) -> Option<&'static str> {
+    match (os, arch) {
+        ("linux", "x86_64") => Some("ggo-linux-amd64"),
+        ("macos", "x86_64") => Some("ggo-macos-amd64"),
+        ("macos", "aarch64") => Some("ggo-macos-arm64"),
+        ("windows", "x86_64") => Some("ggo-windows-amd64.exe"),
+        _ => None,
+    }
+}
+
+/// Whether `latest` is a later version than `current`. Versions that don't
+/// parse as `major.minor.patch` never count as newer.
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    match (parse_version(latest), parse_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+/// `1.2.3`, `v1.2.3` or `1.2.3-rc.1` as numbers; a pre-release suffix is
+/// ignored
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let version = version.strip_prefix('v').unwrap_or(version);
+    let core = version.split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+    let version = (parts.next()??, parts.next()??, parts.next()??);
+    parts.next().is_none().then_some(version)
+}
+
+/// The hex digest for `asset` in a checksum file: either `sha256sum` output
+/// (`<hex>  <name>`, possibly listing several files) or a bare digest
+pub fn parse_checksum(content: &str, asset: &str) -> Option<String> {
+    let is_digest = |s: &str| s.len() == 64 && s.bytes().all(|b| b.is_ascii_hexdigit());
+    content.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let digest = fields.next().filter(|digest| is_digest(digest))?;
+        match fields.next() {
+            // `sha256sum -b` marks the name with '*'
+            Some(name) if name.trim_start_matches('*') != asset => None,
+            _ => Some(digest.to_ascii_lowercase()),
+        }
+    })
+}
+
+/// Lowercase hex SHA-256 of `bytes`
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Download the release's `asset` and check it against the published
+/// `<asset>.sha256`. A release without a checksum isn't installed.
+pub fn download_verified(release: &Release, asset: &str) -> Result<Vec<u8>> {
+    let binary = release.asset(asset).ok_or_else(|| {
+        GgoError::UpdateFailed(format!("release {} has no {} binary", release.tag, asset))
+    })?;
+    let checksum_name = format!("{}{}", asset, CHECKSUM_SUFFIX);
+    let checksum = release.asset(&checksum_name).ok_or_else(|| {
+        GgoError::UpdateFailed(format!(
+            "release {} publishes no checksum for {}",
+            release.tag, asset
+        ))
+    })?;
+
+    let checksum_file = String::from_utf8_lossy(&download(&checksum.url)?).into_owned();
+    let expected = parse_checksum(&checksum_file, asset).ok_or_else(|| {
+        GgoError::UpdateFailed(format!("{} has no SHA-256 for {}", checksum_name, asset))
+    })?;
+
+    let bytes = download(&binary.url)?;
+    let actual = sha256_hex(&bytes);
+    if actual != expected {
+        return Err(GgoError::UpdateFailed(format!(
+            "checksum mismatch for {}: expected {}, got {}",
+            asset, expected, actual
+        )));
+    }
+    Ok(bytes)
+}
+
+/// Put `bytes` in place of the running executable, keeping its permissions.
+/// The new binary is written next to it first so the swap is a rename.
+/// Returns the path replaced.
+pub fn replace_executable(bytes: &[u8]) -> Result<PathBuf> {
+    let exe = std::env::current_exe()?;
+    // Replace the file a symlink (e.g. ~/.local/bin/ggo) points at
+    let exe = exe.canonicalize().unwrap_or(exe);
+    let dir = exe
+        .parent()
+        .ok_or_else(|| GgoError::UpdateFailed(format!("{} has no directory", exe.display())))?;
+    let cannot_write = |e: std::io::Error| {
+        GgoError::UpdateFailed(format!("can't write to {}: {}", dir.display(), e))
+    };
+
+    let staged = dir.join(format!(".ggo-update-{}", std::process::id()));
+    std::fs::write(&staged, bytes).map_err(cannot_write)?;
+    let installed = std::fs::metadata(&exe)
+        .and_then(|metadata| std::fs::set_permissions(&staged, metadata.permissions()))
+        .and_then(|_| swap(&staged, &exe));
+    if let Err(e) = installed {
+        let _ = std::fs::remove_file(&staged);
+        return Err(cannot_write(e));
+    }
+    Ok(exe)
+}
+
+/// Rename `staged` over `exe`. Windows won't replace a running executable,
+/// but it will rename one, so the old binary is moved aside first.
+fn swap(staged: &std::path::Path, exe: &std::path::Path) -> std::io::Result<()> {
+    if cfg!(windows) {
+        let old = exe.with_extension("old.exe");
+        let _ = std::fs::remove_file(&old);
+        std::fs::rename(exe, &old)?;
+    }
+    std::fs::rename(staged, exe)
+}
+
+fn download(url: &str) -> Result<Vec<u8>> {
+    let response = agent()
+        .get(url)
+        .call()
+        .map_err(|e| request_failed(url, e))?;
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut bytes)
+        .map_err(|e| GgoError::UpdateFailed(format!("download of {} failed: {}", url, e)))?;
+    Ok(bytes)
+}
+
+fn agent() -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(TIMEOUT_SECS))
+        .user_agent(concat!("ggo/", env!("CARGO_PKG_VERSION")))
+        .build()
+}
+
+fn request_failed(url: &str, error: ureq::Error) -> GgoError {
+    match error {
+        ureq::Error::Status(code, _) => {
+            GgoError::UpdateFailed(format!("{} answered HTTP {}", url, code))
+        }
+        ureq::Error::Transport(transport) => {
+            GgoError::UpdateFailed(format!("couldn't reach {}: {}", url, transport))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_name_matches_release_workflow() {
+        assert_eq!(asset_name("linux", "x86_64"), Some("ggo-linux-amd64"));
+        assert_eq!(asset_name("macos", "aarch64"), Some("ggo-macos-arm64"));
+        assert_eq!(
+            asset_name("windows", "x86_64"),
+            Some("ggo-windows-amd64.exe")
+        );
+        assert_eq!(asset_name("linux", "aarch64"), None);
+        assert_eq!(asset_name("freebsd", "x86_64"), None);
+    }
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("v1.1.0", "1.0.1"));
+        assert!(is_newer("2.0.0", "1.10.4"));
+        assert!(is_newer("1.0.10", "1.0.9"));
+        assert!(!is_newer("v1.0.1", "1.0.1"));
+        assert!(!is_newer("1.0.0", "1.0.1"));
+        assert!(is_newer("1.2.0-rc.1", "1.1.0"));
+        assert!(!is_newer("nightly", "1.0.1"));
+        assert!(!is_newer("1.2", "1.0.1"));
+    }
+
+    #[test]
+    fn test_parse_checksum() {
+        let digest = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        let listing = format!(
+            "{}  ggo-linux-amd64\n{} *ggo-macos-arm64\n",
+            "0".repeat(64),
+            digest.to_uppercase()
+        );
+
+        assert_eq!(
+            parse_checksum(&listing, "ggo-macos-arm64").as_deref(),
+            Some(digest)
+        );
+        assert_eq!(
+            parse_checksum(&format!("{}\n", digest), "ggo-linux-amd64").as_deref(),
+            Some(digest)
+        );
+        assert_eq!(parse_checksum(&listing, "ggo-windows-amd64.exe"), None);
+        assert_eq!(parse_checksum("not a checksum", "ggo-linux-amd64"), None);
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_release_from_github_json() {
+        let json = r#"{
+            "tag_name": "v1.2.0",
+            "name": "v1.2.0",
+            "assets": [
+                {"name": "ggo-linux-amd64", "browser_download_url": "https://example.com/ggo-linux-amd64", "size": 1},
+                {"name": "ggo-linux-amd64.sha256", "browser_download_url": "https://example.com/ggo-linux-amd64.sha256"}
+            ]
+        }"#;
+        let release: Release = serde_json::from_str(json).unwrap();
+
+        assert_eq!(release.version(), "1.2.0");
+        assert_eq!(
+            release
+                .asset("ggo-linux-amd64.sha256")
+                .map(|a| a.url.as_str()),
+            Some("https://example.com/ggo-linux-amd64.sha256")
+        );
+        assert!(release.asset("ggo-macos-arm64").is_none());
+    }
+}
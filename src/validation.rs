@@ -1,7 +1,9 @@
 use std::path::Path;
 
+use crate::constants::frecency::{DAY_SECONDS, HOUR_SECONDS, WEEK_SECONDS};
 use crate::constants::validation::{
-    MAX_ALIAS_LENGTH, MAX_BRANCH_NAME_LENGTH, MAX_PATTERN_LENGTH, MAX_REPO_PATH_LENGTH,
+    MAX_ALIAS_LENGTH, MAX_BRANCH_NAME_LENGTH, MAX_NOTE_LENGTH, MAX_PATTERN_LENGTH,
+    MAX_REPO_PATH_LENGTH,
 };
 use crate::error::{GgoError, Result};
 
@@ -202,29 +204,32 @@ pub fn validate_pattern(pattern: &str) -> Result<()> {
 /// Validate alias name (more strict than branch names)
 pub fn validate_alias_name(alias: &str) -> Result<()> {
     if alias.is_empty() {
-        return Err(GgoError::InvalidBranchName(
+        return Err(GgoError::InvalidAliasName(
             alias.to_string(),
             "Alias name cannot be empty".to_string(),
         ));
     }
 
     if alias.len() > MAX_ALIAS_LENGTH {
-        return Err(GgoError::InvalidBranchName(
+        return Err(GgoError::InvalidAliasName(
             alias.to_string(),
             format!("Alias name too long (max {} characters)", MAX_ALIAS_LENGTH),
         ));
     }
 
     if alias.starts_with('-') {
-        return Err(GgoError::InvalidBranchName(
+        return Err(GgoError::InvalidAliasName(
             alias.to_string(),
             "Cannot start with '-' (conflicts with command flags)".to_string(),
         ));
     }
 
     // Check if alias is a reserved word
-    if matches!(alias, "stats" | "alias" | "list" | "remove") {
-        return Err(GgoError::InvalidBranchName(
+    if matches!(
+        alias,
+        "stats" | "alias" | "list" | "remove" | "export" | "import"
+    ) {
+        return Err(GgoError::InvalidAliasName(
             alias.to_string(),
             format!("'{}' is reserved and cannot be used as alias", alias),
         ));
@@ -235,7 +240,7 @@ pub fn validate_alias_name(alias: &str) -> Result<()> {
         .chars()
         .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
     {
-        return Err(GgoError::InvalidBranchName(
+        return Err(GgoError::InvalidAliasName(
             alias.to_string(),
             "Must contain only alphanumeric characters, dash (-), or underscore (_)".to_string(),
         ));
@@ -244,6 +249,97 @@ pub fn validate_alias_name(alias: &str) -> Result<()> {
     Ok(())
 }
 
+/// Validate a branch group name ('ggo group add <group>')
+pub fn validate_group_name(group: &str) -> Result<()> {
+    let invalid = |reason: &str| {
+        Err(GgoError::InvalidGroupName(
+            group.to_string(),
+            reason.to_string(),
+        ))
+    };
+
+    if group.is_empty() {
+        return invalid("Group name cannot be empty");
+    }
+    if group.len() > MAX_ALIAS_LENGTH {
+        return invalid(&format!(
+            "Group name too long (max {} characters)",
+            MAX_ALIAS_LENGTH
+        ));
+    }
+    if group.starts_with('-') {
+        return invalid("Cannot start with '-' (conflicts with command flags)");
+    }
+    // 'ggo group add' and 'ggo group remove' are the group subcommands
+    if matches!(group, "add" | "remove") {
+        return invalid(&format!(
+            "'{}' is reserved and cannot be used as a group",
+            group
+        ));
+    }
+    if !group
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return invalid("Must contain only alphanumeric characters, dash (-), or underscore (_)");
+    }
+
+    Ok(())
+}
+
+/// Validate a branch note: a single line of text, shown next to the branch
+pub fn validate_note(note: &str) -> Result<()> {
+    if note.trim().is_empty() {
+        return Err(GgoError::InvalidNote(
+            "Note cannot be empty (remove it with 'ggo note -r <branch>')".to_string(),
+        ));
+    }
+
+    if note.chars().count() > MAX_NOTE_LENGTH {
+        return Err(GgoError::InvalidNote(format!(
+            "Note too long (max {} characters)",
+            MAX_NOTE_LENGTH
+        )));
+    }
+
+    if note.chars().any(char::is_control) {
+        return Err(GgoError::InvalidNote(
+            "Note must be a single line without control characters".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Parse a duration such as `30d`, `12h` or `2w` into seconds (a bare
+/// number is days)
+pub fn parse_duration(value: &str) -> Result<i64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let invalid = || {
+        GgoError::Other(format!(
+            "Invalid duration '{}': use a number followed by h, d or w (e.g. 30d)",
+            value
+        ))
+    };
+
+    let unit_seconds = match unit {
+        "h" => HOUR_SECONDS,
+        "" | "d" => DAY_SECONDS,
+        "w" => WEEK_SECONDS,
+        _ => return Err(invalid()),
+    };
+
+    amount
+        .parse::<i64>()
+        .ok()
+        .and_then(|amount| amount.checked_mul(unit_seconds))
+        .ok_or_else(invalid)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -348,6 +444,25 @@ mod tests {
     }
 
     // Alias name validation tests
+    #[test]
+    fn test_validate_note() {
+        assert!(validate_note("waiting on review").is_ok());
+        assert!(validate_note("   ").is_err());
+        assert!(validate_note("two\nlines").is_err());
+        assert!(validate_note(&"x".repeat(MAX_NOTE_LENGTH)).is_ok());
+        assert!(validate_note(&"x".repeat(MAX_NOTE_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn test_validate_group_name() {
+        assert!(validate_group_name("backend").is_ok());
+        assert!(validate_group_name("team_ui-2").is_ok());
+        assert!(validate_group_name("").is_err());
+        assert!(validate_group_name("-x").is_err());
+        assert!(validate_group_name("add").is_err());
+        assert!(validate_group_name("feature/*").is_err());
+    }
+
     #[test]
     fn test_validate_alias_name_valid() {
         assert!(validate_alias_name("m").is_ok());
@@ -364,7 +479,10 @@ mod tests {
 
     #[test]
     fn test_validate_alias_name_starts_with_dash() {
-        assert!(validate_alias_name("-bad").is_err());
+        assert!(matches!(
+            validate_alias_name("-bad"),
+            Err(GgoError::InvalidAliasName(..))
+        ));
     }
 
     #[test]
@@ -373,6 +491,8 @@ mod tests {
         assert!(validate_alias_name("alias").is_err());
         assert!(validate_alias_name("list").is_err());
         assert!(validate_alias_name("remove").is_err());
+        assert!(validate_alias_name("export").is_err());
+        assert!(validate_alias_name("import").is_err());
     }
 
     #[test]
@@ -424,4 +544,16 @@ mod tests {
         let current_str = current.to_str().unwrap();
         assert!(validate_repo_path(current_str).is_ok());
     }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30d").unwrap(), 30 * DAY_SECONDS);
+        assert_eq!(parse_duration("12h").unwrap(), 12 * HOUR_SECONDS);
+        assert_eq!(parse_duration("2w").unwrap(), 2 * WEEK_SECONDS);
+        assert_eq!(parse_duration("7").unwrap(), 7 * DAY_SECONDS);
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("3 months").is_err());
+        assert!(parse_duration("-1d").is_err());
+    }
 }
@@ -304,6 +304,34 @@ fn test_fuzzy_matching_works() {
     assert!(output.status.success() || stdout.contains("expo") || stderr.contains("expo"));
 }
 
+#[test]
+fn test_multi_term_pattern_requires_every_term() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    for branch in ["feature/auth-api", "feature/auth-ui", "fix/api-timeout"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let output = Command::new(get_ggo_binary())
+        .args(["-l", "api auth"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .output()
+        .expect("Failed to run command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("feature/auth-api"));
+    assert!(!stdout.contains("feature/auth-ui"));
+    assert!(!stdout.contains("fix/api-timeout"));
+}
+
 #[test]
 fn test_empty_pattern_lists_all_branches() {
     let temp_dir = setup_test_repo().expect("Failed to create test repo");
@@ -559,6 +587,29 @@ fn test_generate_completion_powershell() {
     assert!(stdout.contains("Register-ArgumentCompleter") || stdout.contains("param"));
 }
 
+#[test]
+fn test_generate_assets_writes_man_page_and_completions() {
+    let ggo = get_ggo_binary();
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let assets = dir.path().join("assets");
+    let output = Command::new(&ggo)
+        .arg("--generate-assets")
+        .arg(&assets)
+        .output()
+        .expect("Failed to run command");
+
+    assert!(output.status.success());
+    let page = std::fs::read_to_string(assets.join("ggo.1")).unwrap();
+    assert!(page.contains(".TH ggo"));
+    for script in ["ggo.bash", "_ggo", "ggo.fish", "_ggo.ps1", "ggo.elv"] {
+        assert!(
+            assets.join("completions").join(script).exists(),
+            "{}",
+            script
+        );
+    }
+}
+
 #[test]
 fn test_stats_has_summary_section() {
     let ggo = get_ggo_binary();
@@ -673,3 +724,4385 @@ fn test_invalid_config_uses_defaults() {
 
     assert!(output.status.success());
 }
+
+#[test]
+fn test_create_flag_creates_missing_branch() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let output = Command::new(&ggo)
+        .args(["-c", "feature/brand-new"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .output()
+        .expect("Failed to run command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("Created branch 'feature/brand-new'"));
+    assert!(stdout.contains("Switched to branch 'feature/brand-new'"));
+
+    let current = Command::new("git")
+        .args(["branch", "--show-current"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&current.stdout).trim(),
+        "feature/brand-new"
+    );
+}
+
+#[test]
+fn test_no_match_without_create_flag_fails() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let output = Command::new(&ggo)
+        .args(["zzz-does-not-exist"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .output()
+        .expect("Failed to run command");
+
+    // Without a terminal the creation prompt is skipped
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("No branches match pattern"));
+    assert!(stderr.contains("ggo -c zzz-does-not-exist"));
+
+    let branches = Command::new("git")
+        .args(["branch", "--list", "zzz-does-not-exist"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&branches.stdout).trim().is_empty());
+
+    // Only a checkout suggests creating the branch
+    let output = Command::new(&ggo)
+        .args(["delete", "-y", "zzz-does-not-exist"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .output()
+        .expect("Failed to run command");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("No branches match pattern"));
+    assert!(!stderr.contains("ggo -c"));
+}
+
+// Helper to add a linked worktree for a new branch using the git CLI
+fn add_worktree(repo_path: &std::path::Path, branch: &str, worktree_path: &std::path::Path) {
+    let output = Command::new("git")
+        .args(["worktree", "add", "-b", branch])
+        .arg(worktree_path)
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git worktree add failed");
+}
+
+#[test]
+fn test_branch_in_other_worktree_reports_path() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let worktree_dir = tempfile::tempdir().unwrap();
+    let worktree_path = worktree_dir.path().join("wt");
+    add_worktree(repo_path, "feature/in-worktree", &worktree_path);
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let output = Command::new(&ggo)
+        .args(["feature/in-worktree"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .env_remove("GGO_CD_FILE")
+        .output()
+        .expect("Failed to run command");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("already checked out in another worktree"));
+    assert!(stderr.contains("wt"));
+}
+
+#[test]
+fn test_branch_in_other_worktree_writes_cd_file() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let worktree_dir = tempfile::tempdir().unwrap();
+    let worktree_path = worktree_dir.path().join("wt");
+    add_worktree(repo_path, "feature/in-worktree", &worktree_path);
+
+    let cd_file = worktree_dir.path().join("cd-target");
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let output = Command::new(&ggo)
+        .args(["feature/in-worktree"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .env("GGO_CD_FILE", &cd_file)
+        .output()
+        .expect("Failed to run command");
+
+    assert!(output.status.success());
+    let target = std::fs::read_to_string(&cd_file).unwrap();
+    assert!(target.contains("wt"));
+}
+
+#[test]
+fn test_force_detach_checks_out_commit() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let worktree_dir = tempfile::tempdir().unwrap();
+    let worktree_path = worktree_dir.path().join("wt");
+    add_worktree(repo_path, "feature/in-worktree", &worktree_path);
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let output = Command::new(&ggo)
+        .args(["--force-detach", "feature/in-worktree"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .env_remove("GGO_CD_FILE")
+        .output()
+        .expect("Failed to run command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("detached"));
+
+    let current = Command::new("git")
+        .args(["branch", "--show-current"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&current.stdout).trim().is_empty());
+}
+
+#[test]
+fn test_recent_lists_switched_branches_in_order() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    for branch in ["feature/one", "feature/two"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    for branch in ["feature/one", "feature/two"] {
+        let output = Command::new(&ggo)
+            .args([branch])
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .output()
+            .expect("Failed to run command");
+        assert!(output.status.success());
+        // Timestamps have second resolution
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+    }
+
+    let output = Command::new(&ggo)
+        .args(["recent", "-n", "1"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .output()
+        .expect("Failed to run command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("feature/two"));
+    assert!(!stdout.contains("feature/one"));
+}
+
+#[test]
+fn test_recent_without_history() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let output = Command::new(&ggo)
+        .args(["recent"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .output()
+        .expect("Failed to run command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("No branch history"));
+}
+
+#[test]
+fn test_suggest_predicts_next_branch() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let start = get_current_branch(repo_path);
+
+    for branch in ["develop", "feature-x"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .output()
+            .expect("Failed to run command")
+    };
+
+    // start -> develop twice, start -> feature-x once
+    for target in ["develop", &start, "develop", &start, "feature-x", &start] {
+        assert!(run(&[target]).status.success());
+    }
+
+    let output = run(&["suggest", "--json"]);
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let suggestions = json.as_array().unwrap();
+    assert_eq!(suggestions.len(), 2);
+    assert_eq!(suggestions[0]["branch"], "develop");
+    assert_eq!(suggestions[0]["count"], 2);
+    assert_eq!(suggestions[1]["branch"], "feature-x");
+
+    let output = run(&["suggest"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("After '{}'", start)));
+    assert!(stdout.contains("67%"));
+}
+
+#[test]
+fn test_pinned_branch_lists_first() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    for branch in ["feature-a", "feature-b"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .output()
+            .expect("Failed to run command")
+    };
+
+    // feature-a has usage history, feature-b is pinned
+    assert!(run(&["feature-a"]).status.success());
+    let output = run(&["pin", "feature-b"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Pinned 'feature-b'"));
+
+    let output = run(&["--list", "feature"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("→ ★ feature-b"));
+    assert!(stdout.find("feature-b").unwrap() < stdout.find("feature-a").unwrap());
+
+    let output = run(&["--json", "--list", "feature"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json[0]["branch"], "feature-b");
+    assert_eq!(json[0]["pinned"], true);
+
+    // --limit keeps the pinned branch and says how many were left out
+    let output = run(&["--list", "feature", "-n", "1"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("feature-b"));
+    assert!(!stdout.contains("feature-a"));
+    assert!(stdout.contains("top 1 of 2 matches"), "{}", stdout);
+
+    let output = run(&["--json", "--list", "feature", "--limit", "1"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 1);
+    assert_eq!(json[0]["branch"], "feature-b");
+    assert!(json[0]["fuzzy_score"].is_number());
+
+    let output = run(&["--json", "--list", "feature", "--limit", "0"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 2);
+
+    assert!(run(&["unpin", "feature-b"]).status.success());
+    let output = run(&["--list", "feature"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains('★'));
+    // feature-a is checked out
+    assert!(stdout.contains("→ * feature-a"));
+}
+
+#[test]
+fn test_pin_nonexistent_branch_fails() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+
+    let output = Command::new(get_ggo_binary())
+        .args(["pin", "does-not-exist"])
+        .current_dir(temp_dir.path())
+        .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+        .output()
+        .expect("Failed to run command");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_print_outputs_match_without_checkout() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let start = get_current_branch(repo_path);
+
+    for branch in ["feature/one", "feature/two"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .output()
+            .expect("Failed to run command")
+    };
+
+    // Scores are tied, so a normal checkout would prompt; --print takes the top match
+    let output = run(&["-p", "feature"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout == "feature/one\n" || stdout == "feature/two\n");
+    assert_eq!(get_current_branch(repo_path), start);
+
+    // Aliases resolve without the "Using alias" banner
+    assert!(run(&["alias", "two", "feature/two"]).status.success());
+    let output = run(&["--print", "two"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "feature/two\n");
+
+    // Nothing matched: fail instead of offering to create a branch
+    let output = run(&["-p", "zzzz-nothing"]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+
+    // '-' prints the previous branch
+    assert!(run(&["feature/one"]).status.success());
+    let output = run(&["-p", "-"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!("{}\n", start)
+    );
+    assert_eq!(get_current_branch(repo_path), "feature/one");
+}
+
+#[test]
+fn test_non_tty_picks_top_match_instead_of_prompting() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    for branch in ["feature/one", "feature/two"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let ggo = get_ggo_binary();
+    let output = Command::new(&ggo)
+        .args(["feature"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+        .stdin(std::process::Stdio::null())
+        .output()
+        .expect("Failed to run command");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {}", stderr);
+    assert!(stderr.contains("picking the top match"));
+    assert!(get_current_branch(repo_path).starts_with("feature/"));
+}
+
+#[test]
+fn test_interactive_without_tty_fails_clearly() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    Command::new("git")
+        .args(["branch", "feature/one"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+
+    let output = Command::new(get_ggo_binary())
+        .args(["--interactive", "feature"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+        .stdin(std::process::Stdio::null())
+        .output()
+        .expect("Failed to run command");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("needs a terminal"));
+}
+
+#[test]
+fn test_selection_menus_without_tty_fail_before_printing() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    for branch in ["feature/one", "feature/two"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let run = |args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+            .stdin(std::process::Stdio::null())
+            .output()
+            .expect("Failed to run command")
+    };
+
+    for (args, hint) in [
+        (&["delete", "feature"][..], "-y"),
+        (&["batch", "feature"][..], "-y"),
+        (&["prune"][..], "--dry-run"),
+        (&["recent", "-i"][..], "-i"),
+    ] {
+        let output = run(args);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!output.status.success(), "{:?} succeeded", args);
+        assert!(
+            stderr.contains("needs a terminal"),
+            "{:?}: {}",
+            args,
+            stderr
+        );
+        assert!(stderr.contains(hint), "{:?}: {}", args, stderr);
+        assert!(!stderr.contains("not a TTY"), "{:?}: {}", args, stderr);
+        assert!(output.stdout.is_empty(), "{:?} printed output", args);
+    }
+
+    // Nothing was deleted
+    let branches = Command::new("git")
+        .args(["branch", "--list", "feature/*"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&branches.stdout).lines().count(), 2);
+}
+
+#[test]
+fn test_repo_flag_operates_on_another_repository() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let elsewhere = tempfile::tempdir().expect("Failed to create temp dir");
+
+    Command::new("git")
+        .args(["branch", "feature/remote-work"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+
+    let output = Command::new(get_ggo_binary())
+        .arg("-C")
+        .arg(repo_path)
+        .arg("remote-work")
+        .current_dir(elsewhere.path())
+        .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+        .output()
+        .expect("Failed to run command");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(get_current_branch(repo_path), "feature/remote-work");
+
+    let output = Command::new(get_ggo_binary())
+        .args(["-C", "/definitely/not/a/dir", "main"])
+        .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+        .output()
+        .expect("Failed to run command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid repository path"));
+}
+
+#[test]
+fn test_worktrees_share_repository_history() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let wt_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let wt_path = wt_dir.path().join("linked");
+
+    Command::new("git")
+        .args(["worktree", "add", "-b", "feature/linked"])
+        .arg(&wt_path)
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+
+    let run = |dir: &std::path::Path, args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(dir)
+            .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+            .output()
+            .expect("Failed to run command")
+    };
+
+    let main_branch = get_current_branch(repo_path);
+    assert!(run(repo_path, &["alias", "m", &main_branch])
+        .status
+        .success());
+
+    // The alias made in the main worktree is visible from the linked one
+    let output = run(&wt_path, &["alias", "--list"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&main_branch));
+}
+
+#[test]
+fn test_open_prints_branch_and_pr_urls() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    Command::new("git")
+        .args(["branch", "feature/login-form"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+    Command::new("git")
+        .args(["remote", "add", "origin", "git@github.com:acme/app.git"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+            .output()
+            .expect("Failed to run command")
+    };
+
+    let output = run(&["open", "login", "--url"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "https://github.com/acme/app/tree/feature/login-form"
+    );
+
+    let output = run(&["open", "--pr", "--url", "login"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "https://github.com/acme/app/compare/feature/login-form?expand=1"
+    );
+
+    // Nothing was checked out
+    assert_ne!(get_current_branch(repo_path), "feature/login-form");
+}
+
+#[test]
+fn test_pr_fetches_pull_request_into_local_branch() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let git = |args: &[&str], dir: &std::path::Path| {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+
+    let run = |args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+            .output()
+            .expect("Failed to run command")
+    };
+
+    // A local "origin" publishing a pull request head the way GitHub does,
+    // with a hook in its .ggo.toml (trusted here, even)
+    let origin = tempfile::tempdir().unwrap();
+    git(&["init", "--bare", "."], origin.path());
+    git(&["checkout", "-b", "contributor-fix"], repo_path);
+    std::fs::write(
+        repo_path.join(".ggo.toml"),
+        "[hooks]\npost_checkout = [\"touch hook-ran\"]\n",
+    )
+    .unwrap();
+    git(&["add", ".ggo.toml"], repo_path);
+    git(&["commit", "-m", "Fix"], repo_path);
+    assert!(run(&["hooks", "trust"]).status.success());
+    let head = git(&["rev-parse", "HEAD"], repo_path);
+    let origin_path = origin.path().to_str().unwrap();
+    git(&["remote", "add", "origin", origin_path], repo_path);
+    git(&["push", "origin", "HEAD:refs/pull/7/head"], repo_path);
+    git(&["checkout", "-"], repo_path);
+    git(&["branch", "-D", "contributor-fix"], repo_path);
+
+    let output = run(&["pr", "7"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(get_current_branch(repo_path), "pr/7");
+    assert_eq!(git(&["rev-parse", "HEAD"], repo_path), head);
+    // Hooks that came with the pull request never run
+    assert!(!repo_path.join("hook-ran").exists());
+
+    // '#N' works as a pattern too, updating the existing branch
+    git(&["checkout", "-"], repo_path);
+    let output = run(&["#7"]);
+    assert!(output.status.success());
+    assert_eq!(get_current_branch(repo_path), "pr/7");
+    assert!(!repo_path.join("hook-ran").exists());
+
+    let output = run(&["pr", "8"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("#8"));
+}
+
+#[test]
+fn test_undo_switches_back_and_forgets_the_switch() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let main_branch = get_current_branch(repo_path);
+
+    for branch in ["feature-alpha", "feature-beta"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let run = |args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+            .output()
+            .expect("Failed to run command")
+    };
+
+    assert!(run(&["feature-alpha"]).status.success());
+    assert!(run(&["feature-beta"]).status.success());
+
+    let output = run(&["undo"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(get_current_branch(repo_path), "feature-alpha");
+
+    let recent = String::from_utf8_lossy(&run(&["recent"]).stdout).to_string();
+    assert!(recent.contains("feature-alpha"));
+    assert!(!recent.contains("feature-beta"));
+
+    assert!(run(&["undo"]).status.success());
+    assert_eq!(get_current_branch(repo_path), main_branch);
+
+    let output = run(&["undo"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Nothing to undo"));
+}
+
+#[test]
+fn test_undo_honors_guards_and_no_record() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    for branch in ["feature-alpha", "feature-beta"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let home = temp_dir.path().join("home");
+    let config_dir = home.join(".config/ggo");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(
+        config_dir.join("config.toml"),
+        "[hooks]\nguard_dirty = \"abort\"\n",
+    )
+    .unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", temp_dir.path().join("data"))
+            .env("HOME", &home)
+            .env_remove("XDG_CONFIG_HOME")
+            .output()
+            .expect("Failed to run command")
+    };
+
+    assert!(run(&["feature-alpha"]).status.success());
+    assert!(run(&["feature-beta"]).status.success());
+
+    // Going back is a checkout, so the guards apply
+    std::fs::write(repo_path.join("test.txt"), "changed").unwrap();
+    let output = run(&["undo"]);
+    assert!(!output.status.success());
+    assert_eq!(get_current_branch(repo_path), "feature-beta");
+    Command::new("git")
+        .args(["checkout", "--", "test.txt"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+
+    // --no-record switches back but leaves the history alone
+    let output = run(&["--no-record", "undo"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(get_current_branch(repo_path), "feature-alpha");
+    let recent = String::from_utf8_lossy(&run(&["recent"]).stdout).to_string();
+    assert!(recent.contains("feature-beta"));
+}
+
+#[test]
+fn test_no_record_leaves_history_untouched() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    for branch in ["feature-alpha", "feature-beta"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let run = |args: &[&str], env: &[(&str, &str)]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+            .envs(env.iter().copied())
+            .output()
+            .expect("Failed to run command")
+    };
+
+    assert!(run(&["--no-record", "feature-alpha"], &[]).status.success());
+    assert!(run(&["feature-beta"], &[("GGO_NO_RECORD", "1")])
+        .status
+        .success());
+    assert_eq!(get_current_branch(repo_path), "feature-beta");
+
+    let recent = run(&["recent"], &[]);
+    let recent = String::from_utf8_lossy(&recent.stdout);
+    assert!(!recent.contains("feature-alpha"));
+    assert!(!recent.contains("feature-beta"));
+
+    // No previous branch was saved either
+    assert!(!run(&["-"], &[]).status.success());
+}
+
+#[test]
+fn test_forget_branches_and_repository() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let main_branch = get_current_branch(repo_path);
+
+    for branch in ["spike-one", "spike-two"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let run = |args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+            .output()
+            .expect("Failed to run command")
+    };
+    let recent = || String::from_utf8_lossy(&run(&["recent"]).stdout).to_string();
+
+    assert!(run(&["spike-one"]).status.success());
+    assert!(run(&["spike-two"]).status.success());
+    assert!(run(&[&main_branch]).status.success());
+
+    let output = run(&["forget", "spike-*", "--dry-run"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Would forget 2"));
+    assert!(recent().contains("spike-one"));
+
+    assert!(run(&["forget", "spike-*"]).status.success());
+    assert!(!recent().contains("spike-"));
+    assert!(recent().contains(&main_branch));
+
+    // The whole repository, from somewhere else
+    let output = Command::new(get_ggo_binary())
+        .args(["forget", "--repo", repo_path.to_str().unwrap()])
+        .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+        .output()
+        .unwrap();
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("Forgot"),
+        "stdout: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert!(!recent().contains(&main_branch));
+}
+
+#[test]
+fn test_export_and_import_between_data_dirs() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let main_branch = get_current_branch(repo_path);
+
+    Command::new("git")
+        .args(["branch", "feature-sync"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+
+    let run = |data_dir: &str, args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", temp_dir.path().join(data_dir))
+            .output()
+            .expect("Failed to run command")
+    };
+
+    assert!(run("laptop", &["feature-sync"]).status.success());
+    assert!(run("laptop", &[&main_branch]).status.success());
+
+    let export_file = temp_dir.path().join("history.csv");
+    let output = run(
+        "laptop",
+        &["export", "--format", "csv", export_file.to_str().unwrap()],
+    );
+    assert!(output.status.success());
+    assert!(std::fs::read_to_string(&export_file)
+        .unwrap()
+        .contains("feature-sync"));
+
+    // Merging the same export twice adds the counts twice
+    for _ in 0..2 {
+        let output = run(
+            "desktop",
+            &["import", "--merge", export_file.to_str().unwrap()],
+        );
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let output = run("desktop", &["export"]);
+    let json = String::from_utf8_lossy(&output.stdout);
+    let export: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let feature = export["branches"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|b| b["branch_name"] == "feature-sync")
+        .unwrap();
+    assert_eq!(feature["switch_count"], 2);
+
+    let output = run("desktop", &["import", "-"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_sync_between_machines_counts_switches_once() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let main_branch = get_current_branch(repo_path);
+
+    let remote = temp_dir.path().join("history.git");
+    Command::new("git")
+        .args(["init", "--quiet", "--bare"])
+        .arg(&remote)
+        .output()
+        .unwrap();
+
+    let run = |machine: &str, args: &[&str]| {
+        let home = temp_dir.path().join(machine);
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", home.join(".ggo"))
+            .env("HOME", &home)
+            .env("XDG_CONFIG_HOME", home.join(".config"))
+            .env("GIT_AUTHOR_NAME", "Test User")
+            .env("GIT_AUTHOR_EMAIL", "test@example.com")
+            .env("GIT_COMMITTER_NAME", "Test User")
+            .env("GIT_COMMITTER_EMAIL", "test@example.com")
+            .output()
+            .expect("Failed to run command")
+    };
+    let switches = |machine: &str| {
+        let output = run(machine, &["export"]);
+        let export: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        export["branches"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|b| b["switch_count"].as_i64().unwrap())
+            .sum::<i64>()
+    };
+
+    // Not configured yet
+    assert!(!run("desktop", &["sync"]).status.success());
+
+    for machine in ["laptop", "desktop"] {
+        let output = run(
+            machine,
+            &["config", "set", "sync.repo", remote.to_str().unwrap()],
+        );
+        assert!(output.status.success());
+    }
+
+    Command::new("git")
+        .args(["branch", "feature-sync"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+    assert!(run("laptop", &["feature-sync"]).status.success());
+    // Both machines share the worktree: go back so desktop really switches
+    Command::new("git")
+        .args(["checkout", &main_branch])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+    assert!(run("desktop", &["feature-sync"]).status.success());
+    assert!(run("desktop", &[&main_branch]).status.success());
+    assert_eq!(switches("laptop"), 1);
+    assert_eq!(switches("desktop"), 2);
+
+    for machine in ["laptop", "desktop", "laptop", "desktop"] {
+        let output = run(machine, &["sync"]);
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    assert_eq!(switches("laptop"), 3);
+    assert_eq!(switches("desktop"), 3);
+}
+
+#[test]
+fn test_sync_repo_is_never_a_git_option() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let home = temp_dir.path().join("home");
+    let marker = temp_dir.path().join("ran");
+    let config_dir = home.join(".config/ggo");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(
+        config_dir.join("config.toml"),
+        format!(
+            "[sync]\nrepo = \"--upload-pack=touch {}\"\n",
+            marker.display()
+        ),
+    )
+    .unwrap();
+
+    let output = Command::new(get_ggo_binary())
+        .arg("sync")
+        .current_dir(temp_dir.path())
+        .env("GGO_DATA_DIR", home.join(".ggo"))
+        .env("HOME", &home)
+        .env("XDG_CONFIG_HOME", home.join(".config"))
+        .output()
+        .expect("Failed to run command");
+
+    // git looked for a repository by that name
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("repository '--upload-pack="), "stderr: {}", stderr);
+    assert!(!marker.exists());
+}
+
+#[test]
+fn test_cleanup_dry_run_for_current_repository() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let main_branch = get_current_branch(repo_path);
+
+    Command::new("git")
+        .args(["branch", "short-lived"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+
+    let run = |args: &[&str]| {
+        let output = Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+            .output()
+            .expect("Failed to run command");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    run(&["short-lived"]);
+    run(&[&main_branch]);
+    Command::new("git")
+        .args(["branch", "-D", "short-lived"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+
+    let stdout = run(&["cleanup", "--deleted", "--dry-run", "--current"]);
+    assert!(stdout.contains("Would remove 1 stale branch records"));
+    assert!(stdout.contains("short-lived"));
+
+    // The preview removed nothing
+    let stdout = run(&["cleanup", "--deleted", "--current"]);
+    assert!(stdout.contains("Removed 1 stale branch records"));
+
+    let stdout = run(&["cleanup", "--deleted", "--dry-run", "--current"]);
+    assert!(stdout.contains("Would remove 0 stale branch records"));
+
+    let stdout = run(&["cleanup", "--deleted", "--repo", "/no/such/repo"]);
+    assert!(stdout.contains("Nothing stored about repository"));
+}
+
+#[test]
+fn test_auto_maintenance_drops_deleted_branches() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let main_branch = get_current_branch(repo_path);
+
+    Command::new("git")
+        .args(["branch", "short-lived"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+
+    let config_home = tempfile::tempdir().unwrap();
+    let config_dir = config_home.path().join(".config/ggo");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(
+        config_dir.join("config.toml"),
+        "[maintenance]\nauto = true\nevery = 1\n",
+    )
+    .unwrap();
+
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        let output = Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("HOME", config_home.path())
+            .env("XDG_CONFIG_HOME", config_home.path().join(".config"))
+            .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+            .output()
+            .expect("Failed to run command");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+    let stale_count = || run(&["cleanup", "--deleted", "--dry-run", "--current"]);
+
+    run(&["short-lived"]);
+    run(&[&main_branch]);
+    Command::new("git")
+        .args(["branch", "-D", "short-lived"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+
+    run(&["--no-auto-maintenance", "recent"]);
+    assert!(stale_count().contains("Would remove 1 stale branch records"));
+
+    run(&["recent"]);
+    assert!(stale_count().contains("Would remove 0 stale branch records"));
+}
+
+fn get_current_branch(repo_path: &std::path::Path) -> String {
+    let output = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+#[test]
+fn test_history_position_jumps_back_n_switches() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    for branch in ["feature/one", "feature/two"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let original = get_current_branch(repo_path);
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    for branch in ["feature/one", "feature/two"] {
+        let output = Command::new(&ggo)
+            .args([branch])
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .output()
+            .expect("Failed to run command");
+        assert!(output.status.success());
+    }
+
+    let output = Command::new(&ggo)
+        .args(["--history"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .output()
+        .expect("Failed to run command");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("-1   feature/one"));
+    assert!(stdout.contains(&format!("-2   {}", original)));
+
+    let output = Command::new(&ggo)
+        .args(["-2"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .output()
+        .expect("Failed to run command");
+    assert!(output.status.success());
+    assert_eq!(get_current_branch(repo_path), original);
+
+    let output = Command::new(&ggo)
+        .args(["-9"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .output()
+        .expect("Failed to run command");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("No branch at position -9"));
+}
+
+#[test]
+fn test_list_json_output() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    Command::new("git")
+        .args(["branch", "feature/test"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let output = Command::new(&ggo)
+        .args(["--json", "-l", "feature"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .output()
+        .expect("Failed to run command");
+
+    assert!(output.status.success());
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout should be valid JSON");
+    let entries = json.as_array().expect("expected a JSON array");
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["branch"], "feature/test");
+    assert!(entries[0]["fuzzy_score"].is_i64());
+    assert_eq!(entries[0]["frecency_score"], 0.0);
+    assert!(entries[0]["aliases"].as_array().unwrap().is_empty());
+    assert!(entries[0]["last_used"].is_null());
+    assert!(entries[0]["upstream"].is_null());
+    assert!(entries[0]["last_commit"].is_i64());
+    assert!(entries[0]["last_commit_summary"].is_string());
+
+    // A reader that goes away before the JSON is written isn't an error
+    let mut child = Command::new(&ggo)
+        .args(["--json", "-l", "feature"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to run command");
+    drop(child.stdout.take());
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {}", stderr);
+    assert!(!stderr.contains("Broken pipe"));
+}
+
+#[test]
+fn test_stats_json_output() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+
+    let ggo = get_ggo_binary();
+    let output = Command::new(&ggo)
+        .args(["--stats", "--json"])
+        .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+        .output()
+        .expect("Failed to run command");
+
+    assert!(output.status.success());
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout should be valid JSON");
+    assert_eq!(json["total_switches"], 0);
+    assert!(json["top_branches"].as_array().unwrap().is_empty());
+    assert!(json["repositories"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn test_config_set_get_and_list() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .env("HOME", temp_dir.path())
+            .env("XDG_CONFIG_HOME", temp_dir.path().join(".config"))
+            .output()
+            .expect("Failed to run command")
+    };
+
+    let output = run(&["config", "set", "behavior.auto_select_threshold", "3.5"]);
+    assert!(output.status.success());
+
+    let output = run(&["config", "get", "behavior.auto_select_threshold"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "3.5");
+
+    let output = run(&["config", "list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("frecency.half_life_days = 7"));
+    assert!(stdout.contains("behavior.auto_select_threshold = 3.5"));
+
+    let config_file = temp_dir.path().join(".config/ggo/config.toml");
+    let content = std::fs::read_to_string(config_file).unwrap();
+    assert!(content.contains("auto_select_threshold = 3.5"));
+
+    let output = run(&["config", "set", "behavior.picker", "fancy"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid value 'fancy'"));
+}
+
+#[test]
+fn test_config_set_keeps_comments_and_unset_defaults() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .env("HOME", temp_dir.path())
+            .env("XDG_CONFIG_HOME", temp_dir.path().join(".config"))
+            .output()
+            .expect("Failed to run command")
+    };
+
+    let config_file = temp_dir.path().join(".config/ggo/config.toml");
+    std::fs::create_dir_all(config_file.parent().unwrap()).unwrap();
+    std::fs::write(
+        &config_file,
+        "# Tuned for the monorepo\n[behavior]\n# wider net\nauto_select_threshold = 2.5\n",
+    )
+    .unwrap();
+
+    assert!(
+        run(&["config", "set", "behavior.auto_select_threshold", "3.5"])
+            .status
+            .success()
+    );
+    assert!(run(&["ignore", "wip/*"]).status.success());
+
+    let content = std::fs::read_to_string(&config_file).unwrap();
+    assert!(content.starts_with("# Tuned for the monorepo\n"));
+    assert!(content.contains("# wider net\nauto_select_threshold = 3.5"));
+    assert!(content.contains("exclude = [\"wip/*\"]"));
+    assert!(!content.contains("half_life_days"));
+}
+
+#[test]
+fn test_config_default_fuzzy_false_uses_substring_matching() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    Command::new("git")
+        .args(["branch", "feature/test"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+
+    let config_home = tempfile::tempdir().unwrap();
+    let config_dir = config_home.path().join(".config/ggo");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(
+        config_dir.join("config.toml"),
+        "[behavior]\ndefault_fuzzy = false\n",
+    )
+    .unwrap();
+
+    let ggo = get_ggo_binary();
+    let output = Command::new(&ggo)
+        .args(["-l", "ftst"])
+        .current_dir(repo_path)
+        .env("HOME", config_home.path())
+        .env("XDG_CONFIG_HOME", config_home.path().join(".config"))
+        .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+        .output()
+        .expect("Failed to run command");
+
+    // 'ftst' only matches 'feature/test' fuzzily
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_ignore_excludes_branches_from_matching_and_history() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    for branch in ["release/1.0", "feature/release-notes"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let config_home = tempfile::tempdir().unwrap();
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("HOME", config_home.path())
+            .env("XDG_CONFIG_HOME", config_home.path().join(".config"))
+            .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+            .output()
+            .expect("Failed to run command")
+    };
+
+    let output = run(&["ignore", "release/*"]);
+    assert!(output.status.success());
+
+    let output = run(&["-l", "release"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("feature/release-notes"));
+    assert!(!stdout.contains("release/1.0"));
+
+    // Checking out an excluded branch through an alias works but isn't recorded
+    assert!(run(&["alias", "rel", "release/1.0"]).status.success());
+    assert!(run(&["rel"]).status.success());
+    assert_eq!(get_current_branch(repo_path), "release/1.0");
+    let output = run(&["recent"]);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("release/1.0"));
+
+    let output = run(&["ignore"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("release/*"));
+
+    assert!(run(&["ignore", "--remove", "release/*"]).status.success());
+    let output = run(&["-l", "release"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("release/1.0"));
+}
+
+#[test]
+fn test_batch_applies_action_to_matches() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    for branch in ["spike/one", "spike/two"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .output()
+            .expect("Failed to run command")
+    };
+
+    let output = run(&["batch", "spike", "--action", "pin", "--yes"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("Branches to pin: spike/one, spike/two"));
+    assert!(stdout.contains("Pinned 'spike/one'"));
+    assert!(stdout.contains("Pinned 'spike/two'"));
+
+    let output = run(&["pin"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("★ spike/one") && stdout.contains("★ spike/two"));
+
+    // Deleting without prompting takes one named branch at a time
+    let output = run(&["batch", "spike", "-a", "delete", "-D", "-y"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("spike/one, spike/two"));
+
+    for branch in ["spike/one", "spike/two"] {
+        let output = run(&["batch", branch, "-a", "delete", "-D", "-y"]);
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stdout)
+            .contains(&format!("Branches to delete: {}", branch)));
+    }
+
+    let output = Command::new("git")
+        .args(["branch", "--list", "spike/*"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).trim().is_empty());
+
+    // Deleting the branches dropped their pins too
+    let output = run(&["pin"]);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("spike/"));
+}
+
+#[test]
+fn test_branch_groups() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    for branch in ["feature/api", "feature/db", "feature/ui", "svc/auth"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .output()
+            .expect("Failed to run command")
+    };
+
+    assert!(run(&["group", "add", "backend", "feature/api"])
+        .status
+        .success());
+    assert!(!run(&["group", "add", "backend", "feature/nope"])
+        .status
+        .success());
+    let output = run(&[
+        "batch",
+        "feature/db",
+        "-a",
+        "group",
+        "--into",
+        "backend",
+        "-y",
+    ]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Added 'feature/db' to 'backend'"));
+
+    // Teams can define groups by pattern in .ggo.toml
+    std::fs::write(
+        repo_path.join(".ggo.toml"),
+        "[groups]\nbackend = [\"svc/*\"]\n",
+    )
+    .unwrap();
+
+    let output = run(&["group"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("feature/api, feature/db, svc/*"));
+    assert!(stdout.contains("(3 branches)"));
+
+    // Without a terminal to pick in, the group's branches are listed
+    let output = run(&["group", "backend"]);
+    let listed: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect();
+    assert_eq!(listed.len(), 3);
+    assert!(!listed.contains(&"feature/ui".to_string()));
+
+    let output = run(&["-l", "feature", "--group", "backend"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("feature/api") && stdout.contains("feature/db"));
+    assert!(!stdout.contains("feature/ui"));
+
+    let output = run(&["-l", "feature", "--group", "frontend"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Group 'frontend' not found"));
+
+    assert!(run(&["group", "remove", "backend", "feature/api"])
+        .status
+        .success());
+    let output = run(&["group", "backend"]);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("feature/api"));
+}
+
+#[test]
+fn test_delete_removes_branch_and_aliases() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    Command::new("git")
+        .args(["branch", "old/feature"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let output = Command::new(&ggo)
+        .args(["alias", "of", "old/feature"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .output()
+        .expect("Failed to run command");
+    assert!(output.status.success());
+
+    let output = Command::new(&ggo)
+        .args(["delete", "--yes", "old/feature"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .output()
+        .expect("Failed to run command");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("Deleted branch 'old/feature'"));
+
+    let output = Command::new("git")
+        .args(["branch", "--list", "old/feature"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).trim().is_empty());
+
+    let output = Command::new(&ggo)
+        .args(["alias", "--list"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .output()
+        .expect("Failed to run command");
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("old/feature"));
+}
+
+#[test]
+fn test_delete_yes_only_takes_one_named_branch() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let main_branch = get_current_branch(repo_path);
+
+    for branch in [
+        "a-u-t-h",
+        "add-unit-tests-handler",
+        "auth-api",
+        "auth-ui",
+        "hot",
+    ] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let run = |args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+            .output()
+            .expect("Failed to run command")
+    };
+    let exists = |branch: &str| {
+        let output = Command::new("git")
+            .args(["branch", "--list", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+        !String::from_utf8_lossy(&output.stdout).trim().is_empty()
+    };
+
+    // Fuzzy matches are never deleted without prompting
+    let output = run(&["delete", "auth", "-y"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("auth-api, auth-ui"));
+    assert!(exists("a-u-t-h") && exists("add-unit-tests-handler"));
+    assert!(exists("auth-api") && exists("auth-ui"));
+
+    let output = run(&["delete", "ui", "-y"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Deleting 'auth-ui'"));
+    assert!(!exists("auth-ui"));
+
+    // A frequently used branch needs --force as well
+    for _ in 0..6 {
+        assert!(run(&["hot"]).status.success());
+        assert!(run(&[&main_branch]).status.success());
+    }
+    let output = run(&["delete", "hot", "-y"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("most used"));
+    assert!(exists("hot"));
+    assert!(run(&["delete", "hot", "-y", "--force"]).status.success());
+    assert!(!exists("hot"));
+}
+
+#[test]
+fn test_delete_unmerged_branch_requires_force() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let original = get_current_branch(repo_path);
+
+    for args in [
+        vec!["checkout", "-q", "-b", "wip/unmerged"],
+        vec!["commit", "-q", "--allow-empty", "-m", "wip"],
+        vec!["checkout", "-q", original.as_str()],
+    ] {
+        Command::new("git")
+            .args(&args)
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let output = Command::new(&ggo)
+        .args(["delete", "--yes", "wip/unmerged"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .output()
+        .expect("Failed to run command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not fully merged"));
+
+    let output = Command::new(&ggo)
+        .args(["delete", "--yes", "-D", "wip/unmerged"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .output()
+        .expect("Failed to run command");
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_prune_merged_branches() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    Command::new("git")
+        .args(["branch", "feature/merged"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let output = Command::new(&ggo)
+        .args(["prune", "--dry-run"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .output()
+        .expect("Failed to run command");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("feature/merged"));
+    assert!(stdout.contains("merged into"));
+    assert!(!stdout.contains("Deleted branch"));
+
+    let output = Command::new(&ggo)
+        .args(["prune", "--yes"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .output()
+        .expect("Failed to run command");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("Deleted branch 'feature/merged'"));
+
+    let output = Command::new(&ggo)
+        .args(["prune"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .output()
+        .expect("Failed to run command");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No merged or gone branches"));
+}
+
+#[test]
+fn test_prune_yes_keeps_unmerged_gone_and_frequent_branches() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let main_branch = get_current_branch(repo_path);
+
+    for args in [
+        vec!["checkout", "-q", "-b", "feature/gone"],
+        vec!["commit", "-q", "--allow-empty", "-m", "unpushed"],
+        vec!["checkout", "-q", main_branch.as_str()],
+        vec![
+            "remote",
+            "add",
+            "origin",
+            "https://example.invalid/repo.git",
+        ],
+        vec!["config", "branch.feature/gone.remote", "origin"],
+        vec![
+            "config",
+            "branch.feature/gone.merge",
+            "refs/heads/feature/gone",
+        ],
+        vec!["branch", "hot"],
+    ] {
+        Command::new("git")
+            .args(&args)
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let run = |args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+            .output()
+            .expect("Failed to run command")
+    };
+    let exists = |branch: &str| {
+        let output = Command::new("git")
+            .args(["branch", "--list", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+        !String::from_utf8_lossy(&output.stdout).trim().is_empty()
+    };
+
+    for _ in 0..6 {
+        assert!(run(&["hot"]).status.success());
+        assert!(run(&[&main_branch]).status.success());
+    }
+
+    let output = run(&["prune", "--yes"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("not fully merged"), "{}", stderr);
+    assert!(stderr.contains("most used"), "{}", stderr);
+    assert!(exists("feature/gone"));
+    assert!(exists("hot"));
+}
+
+#[test]
+fn test_stale_lists_old_branches_and_deletes_merged_ones() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let home = tempfile::tempdir().unwrap();
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path().join(".config"))
+            .env("GGO_DATA_DIR", home.path().join(".ggo"))
+            .output()
+            .expect("Failed to run command")
+    };
+    let git = |args: &[&str], env: &[(&str, &str)]| {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(repo_path)
+            .envs(env.iter().copied())
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+    };
+
+    git(&["config", "user.email", "me@example.com"], &[]);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    for (branch, days, email) in [
+        ("feature/old-merged", 200, "me@example.com"),
+        ("feature/old-open", 150, "me@example.com"),
+        ("feature/theirs", 120, "bob@example.com"),
+        ("feature/fresh", 0, "me@example.com"),
+    ] {
+        let date = format!("@{} +0000", now - days * 86400);
+        git(&["switch", "-q", "-c", branch], &[]);
+        git(
+            &["commit", "-q", "--allow-empty", "-m", branch],
+            &[
+                ("GIT_AUTHOR_DATE", &date),
+                ("GIT_COMMITTER_DATE", &date),
+                ("GIT_AUTHOR_EMAIL", email),
+            ],
+        );
+        git(&["switch", "-q", "-"], &[]);
+        if branch == "feature/old-merged" {
+            git(&["merge", "-q", "--ff-only", branch], &[]);
+        }
+    }
+
+    let stale_branches = |args: &[&str]| {
+        let output = run(args);
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let stale: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        stale
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|b| {
+                (
+                    b["branch"].as_str().unwrap().to_string(),
+                    b["merged_into"].is_string(),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        stale_branches(&["stale", "--json"]),
+        vec![
+            ("feature/old-merged".to_string(), true),
+            ("feature/old-open".to_string(), false),
+            ("feature/theirs".to_string(), false),
+        ]
+    );
+    assert_eq!(
+        stale_branches(&["stale", "--json", "--mine", "--days", "160"]),
+        vec![("feature/old-merged".to_string(), true)]
+    );
+
+    // Without a terminal and without --yes it's a report: nothing is deleted
+    let output = run(&["stale"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("feature/old-merged"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("nothing was deleted"));
+    assert_eq!(stale_branches(&["stale", "--json"]).len(), 3);
+
+    // Unmerged branches are kept without -D
+    let output = run(&["stale", "--yes", "--mine"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Deleted branch 'feature/old-merged'"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Kept 'feature/old-open'"));
+
+    let branches = Command::new("git")
+        .args(["branch", "--format=%(refname:short)"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+    let branches = String::from_utf8_lossy(&branches.stdout);
+    assert!(!branches.contains("feature/old-merged"));
+    assert!(branches.contains("feature/old-open"));
+    assert!(branches.contains("feature/theirs"));
+    assert!(branches.contains("feature/fresh"));
+}
+
+#[test]
+fn test_stats_filters_to_this_repo_and_sorts() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let other_dir = setup_test_repo().expect("Failed to create test repo");
+    let main_branch = get_current_branch(repo_path);
+
+    for branch in ["feature-busy", "feature-quiet"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+    Command::new("git")
+        .args(["branch", "feature-other"])
+        .current_dir(other_dir.path())
+        .output()
+        .unwrap();
+
+    let data_dir = temp_dir.path().join("data");
+    let run = |dir: &std::path::Path, args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(dir)
+            .env("GGO_DATA_DIR", &data_dir)
+            .output()
+            .expect("Failed to run command")
+    };
+
+    for branch in [
+        "feature-busy",
+        &main_branch,
+        "feature-busy",
+        "feature-quiet",
+    ] {
+        assert!(run(repo_path, &[branch]).status.success());
+    }
+    assert!(run(other_dir.path(), &["feature-other"]).status.success());
+
+    let output = run(
+        repo_path,
+        &[
+            "--stats",
+            "--json",
+            "--this-repo",
+            "--since",
+            "1d",
+            "--sort",
+            "switches",
+            "--top",
+            "1",
+            "--heatmap",
+        ],
+    );
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stats: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stats["unique_repos"], 1);
+    assert_eq!(stats["total_switches"], 4);
+    let top = stats["top_branches"].as_array().unwrap();
+    assert_eq!(top.len(), 1);
+    assert_eq!(top[0]["branch"], "feature-busy");
+    let heatmap = stats["heatmap"].as_array().unwrap();
+    assert_eq!(heatmap.len(), 7);
+    let switches: i64 = heatmap.iter().map(|d| d["total"].as_i64().unwrap()).sum();
+    assert_eq!(switches, 4);
+
+    let output = run(
+        repo_path,
+        &["--stats", "--format", "csv", "--sort", "switches"],
+    );
+    assert!(output.status.success());
+    let csv = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines[0],
+        "repo_path,branch_name,switch_count,last_used,frecency_score"
+    );
+    // Every record across both repositories, not just the top ones
+    assert_eq!(lines.len(), 5);
+    assert!(lines[1].contains(",feature-busy,2,"));
+
+    let output = run(repo_path, &["--stats", "--since", "soon"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_explain_shows_scores_and_decision() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let main_branch = get_current_branch(repo_path);
+
+    for branch in ["feature-auth", "feature-audit"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let run = |args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", temp_dir.path().join("data"))
+            .output()
+            .expect("Failed to run command")
+    };
+
+    for _ in 0..3 {
+        assert!(run(&["feature-auth"]).status.success());
+        assert!(run(&[&main_branch]).status.success());
+    }
+
+    let output = run(&["--json", "explain", "feature-au"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let explain: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let candidates = explain["candidates"].as_array().unwrap();
+    assert_eq!(candidates.len(), 2);
+    assert_eq!(candidates[0]["branch"], "feature-auth");
+    assert_eq!(candidates[0]["switch_count"], 3);
+    assert_eq!(candidates[1]["last_used"], serde_json::Value::Null);
+
+    // Explaining never checks anything out
+    assert_eq!(get_current_branch(repo_path), main_branch);
+
+    let output = run(&["explain", "nothing-like-this"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Decision: No match"));
+}
+
+#[test]
+fn test_auto_select_mode_and_min_score_decide() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let main_branch = get_current_branch(repo_path);
+    let home = tempfile::tempdir().unwrap();
+
+    for branch in ["feature-auth", "feature-audit"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let run = |args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path().join(".config"))
+            .env("GGO_DATA_DIR", home.path().join(".ggo"))
+            .output()
+            .expect("Failed to run command")
+    };
+    let decision = || {
+        let output = run(&["--json", "explain", "feature-au"]);
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let explain: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        explain["decision"].as_str().unwrap().to_string()
+    };
+
+    for _ in 0..3 {
+        assert!(run(&["feature-auth"]).status.success());
+        assert!(run(&[&main_branch]).status.success());
+    }
+    assert_eq!(decision(), "auto_select");
+
+    assert!(
+        run(&["config", "set", "behavior.auto_select_min_score", "100000"])
+            .status
+            .success()
+    );
+    assert_eq!(decision(), "interactive");
+
+    assert!(run(&["config", "set", "behavior.auto_select", "always"])
+        .status
+        .success());
+    assert_eq!(decision(), "auto_select");
+
+    assert!(run(&["config", "set", "behavior.auto_select", "never"])
+        .status
+        .success());
+    assert_eq!(decision(), "interactive");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_post_checkout_hooks_from_repo_file() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let main_branch = get_current_branch(repo_path);
+
+    Command::new("git")
+        .args(["branch", "feature-hooks"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+
+    std::fs::write(
+        repo_path.join(".ggo.toml"),
+        "[hooks]\npost_checkout = [\"echo \\\"$GGO_FROM_BRANCH>$GGO_TO_BRANCH\\\" >> hook.log\", \"exit 3\"]\n",
+    )
+    .unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", temp_dir.path().join("data"))
+            .output()
+            .expect("Failed to run command")
+    };
+
+    // Nothing from the file runs until it's trusted
+    let output = run(&["feature-hooks"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("ggo hooks trust"));
+    assert!(!repo_path.join("hook.log").exists());
+    assert!(run(&[&main_branch]).status.success());
+
+    let output = run(&["hooks", "trust"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("post_checkout: exit 3"));
+
+    // A failing hook warns but doesn't fail the switch
+    let output = run(&["feature-hooks"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("post-checkout hook 'exit 3' failed"));
+    assert_eq!(
+        std::fs::read_to_string(repo_path.join("hook.log")).unwrap(),
+        format!("{}>feature-hooks\n", main_branch)
+    );
+
+    let output = run(&["--no-hooks", &main_branch]);
+    assert!(output.status.success());
+    assert_eq!(get_current_branch(repo_path), main_branch);
+    assert_eq!(
+        std::fs::read_to_string(repo_path.join("hook.log"))
+            .unwrap()
+            .lines()
+            .count(),
+        1
+    );
+
+    // Changing the file withdraws the trust until it's given again
+    std::fs::write(
+        repo_path.join(".ggo.toml"),
+        "[hooks]\npost_checkout = [\"echo changed >> hook.log\"]\n",
+    )
+    .unwrap();
+    let output = run(&["feature-hooks"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("changed since you trusted it"));
+    assert_eq!(
+        std::fs::read_to_string(repo_path.join("hook.log"))
+            .unwrap()
+            .lines()
+            .count(),
+        1
+    );
+    assert!(String::from_utf8_lossy(&run(&["hooks"]).stdout).contains("Not trusted"));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_pre_checkout_guards_block_until_forced() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let main_branch = get_current_branch(repo_path);
+
+    for branch in ["locked", "feature-open"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    std::fs::write(
+        repo_path.join(".ggo.toml"),
+        "[hooks]\npre_checkout = [\"test \\\"$GGO_TO_BRANCH\\\" != locked\"]\n",
+    )
+    .unwrap();
+
+    let home = temp_dir.path().join("home");
+    let config_dir = home.join(".config/ggo");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(
+        config_dir.join("config.toml"),
+        "[hooks]\nguard_dirty = \"abort\"\n",
+    )
+    .unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", temp_dir.path().join("data"))
+            .env("HOME", &home)
+            .env_remove("XDG_CONFIG_HOME")
+            .output()
+            .expect("Failed to run command")
+    };
+
+    // The repository's hook is skipped until it's trusted
+    let output = run(&["locked"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Not running the pre-checkout hooks"));
+    assert!(run(&[&main_branch]).status.success());
+    assert!(run(&["hooks", "trust"]).status.success());
+
+    let output = run(&["locked"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("pre-checkout hook"));
+    assert_eq!(get_current_branch(repo_path), main_branch);
+
+    assert!(run(&["--force", "locked"]).status.success());
+    assert_eq!(get_current_branch(repo_path), "locked");
+
+    // Uncommitted changes to a tracked file trip the dirty guard
+    std::fs::write(repo_path.join("test.txt"), "edited").unwrap();
+    let output = run(&["feature-open"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("uncommitted changes"));
+    assert_eq!(get_current_branch(repo_path), "locked");
+
+    assert!(run(&["-f", "feature-open"]).status.success());
+    assert_eq!(get_current_branch(repo_path), "feature-open");
+}
+
+#[test]
+fn test_stale_guard_blocks_long_unused_branches() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let main_branch = get_current_branch(repo_path);
+    for branch in ["feature-old", "feature-new"] {
+        let status = Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    let config_home = tempfile::tempdir().unwrap();
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("HOME", config_home.path())
+            .env("XDG_CONFIG_HOME", config_home.path().join(".config"))
+            .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+            .output()
+            .expect("Failed to run command")
+    };
+
+    assert!(run(&["feature-old"]).status.success());
+    assert!(run(&[&main_branch]).status.success());
+
+    // Age feature-old by 200 days
+    let output = run(&["export"]);
+    let mut export: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    for branch in export["branches"].as_array_mut().unwrap() {
+        if branch["branch_name"] == "feature-old" {
+            let last_used = branch["last_used"].as_i64().unwrap();
+            branch["last_used"] = (last_used - 200 * 86400).into();
+        }
+    }
+    let export_file = temp_dir.path().join("history.json");
+    std::fs::write(&export_file, export.to_string()).unwrap();
+    assert!(run(&["import", export_file.to_str().unwrap()])
+        .status
+        .success());
+
+    assert!(run(&["config", "set", "hooks.guard_stale", "abort"])
+        .status
+        .success());
+
+    let output = run(&["feature-old"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("was last used"));
+    assert_eq!(get_current_branch(repo_path), main_branch);
+
+    // Branches ggo has never switched to have nothing to be stale by
+    assert!(run(&["feature-new"]).status.success());
+    assert_eq!(get_current_branch(repo_path), "feature-new");
+
+    assert!(run(&["--force", "feature-old"]).status.success());
+    assert_eq!(get_current_branch(repo_path), "feature-old");
+}
+
+#[test]
+fn test_stats_drills_down_into_matching_repo() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let other_dir = setup_test_repo().expect("Failed to create test repo");
+
+    for (dir, branch) in [
+        (repo_path, "feature-here"),
+        (other_dir.path(), "feature-other"),
+    ] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(dir)
+            .output()
+            .unwrap();
+    }
+
+    let data_dir = temp_dir.path().join("data");
+    let run = |args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &data_dir)
+            .output()
+            .expect("Failed to run command")
+    };
+    assert!(run(&["feature-here"]).status.success());
+    assert!(Command::new(get_ggo_binary())
+        .arg("feature-other")
+        .current_dir(other_dir.path())
+        .env("GGO_DATA_DIR", &data_dir)
+        .output()
+        .unwrap()
+        .status
+        .success());
+
+    let output = run(&["--stats"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Last activity"));
+
+    let other_name = other_dir.path().file_name().unwrap().to_str().unwrap();
+    let output = run(&["--stats", "--json", other_name]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stats: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stats["unique_repos"], 1);
+    let top = stats["top_branches"].as_array().unwrap();
+    assert_eq!(top.len(), 1);
+    assert_eq!(top[0]["branch"], "feature-other");
+    assert!(stats["repositories"][0]["last_used"].as_i64().unwrap() > 0);
+
+    let output = run(&["--stats", other_name]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Repository: "));
+    assert!(stdout.contains(other_name));
+    assert!(!stdout.contains("feature-here"));
+
+    let output = run(&["--stats", "zzqqxx"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No repositories match 'zzqqxx'"));
+}
+
+#[test]
+fn test_current_porcelain_reads_without_writing() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    for branch in ["feature-a", "feature-b"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let data_dir = temp_dir.path().join("data");
+    let run = |dir: &std::path::Path, args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(dir)
+            .env("GGO_DATA_DIR", &data_dir)
+            .output()
+            .expect("Failed to run command")
+    };
+
+    // Without any history yet, only the branch is known
+    let output = run(repo_path, &["current", "--porcelain"]);
+    assert!(output.status.success());
+    let main_branch = get_current_branch(repo_path);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!("{}\t\t\n", main_branch)
+    );
+    assert!(!data_dir.join("data.db").exists());
+
+    for branch in [
+        "feature-b",
+        "feature-a",
+        "feature-b",
+        "feature-a",
+        "feature-b",
+    ] {
+        assert!(run(repo_path, &[branch]).status.success());
+    }
+
+    // SQLite may add its shared-memory files, but the history is untouched
+    let snapshot = || {
+        let db = data_dir.join("data.db");
+        (
+            std::fs::read(&db).unwrap(),
+            std::fs::metadata(&db).unwrap().modified().unwrap(),
+        )
+    };
+    let before = snapshot();
+    let output = run(repo_path, &["current", "--porcelain"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "feature-b\tfeature-a\t1\n"
+    );
+    assert_eq!(snapshot(), before);
+
+    let output = run(repo_path, &["current"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("feature-b\n"));
+    assert!(stdout.contains("previous: feature-a"));
+    assert!(stdout.contains("frecency rank: 1 of 2"));
+
+    // Outside a repository the prompt gets nothing, not an error
+    let outside = tempfile::tempdir().unwrap();
+    let output = run(outside.path(), &["current", "--porcelain"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(!run(outside.path(), &["current"]).status.success());
+}
+
+#[test]
+fn test_read_only_runs_create_nothing() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let home = tempfile::tempdir().unwrap();
+    let data_dir = home.path().join("data");
+    let run = |args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &data_dir)
+            .env("HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path().join("config"))
+            .output()
+            .expect("Failed to run command")
+    };
+
+    let output = run(&["--version"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("ggo "));
+
+    // Nothing recorded yet: empty answers, and still no database
+    assert!(run(&["--stats"]).status.success());
+    assert!(run(&["--history"]).status.success());
+    assert!(run(&["--stats", "--slow"]).status.success());
+    assert_eq!(std::fs::read_dir(home.path()).unwrap().count(), 0);
+
+    // The first switch creates it
+    let main_branch = get_current_branch(repo_path);
+    assert!(run(&[&main_branch]).status.success());
+    assert!(data_dir.join("data.db").exists());
+}
+
+#[test]
+fn test_invocation_log_and_slow_stats() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    for branch in ["feature-login", "feature-logout"] {
+        let status = Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    let config_home = tempfile::tempdir().unwrap();
+    let data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("HOME", config_home.path())
+            .env("XDG_CONFIG_HOME", config_home.path().join(".config"))
+            .env("GGO_DATA_DIR", &data_dir)
+            .output()
+            .expect("Failed to run command")
+    };
+
+    // --log-file logs one run, with its candidates and pick
+    let log_file = config_home.path().join("run.jsonl");
+    let log_arg = log_file.to_str().unwrap();
+    assert!(run(&["--log-file", log_arg, "feature-login"])
+        .status
+        .success());
+    assert!(!run(&["--log-file", log_arg, "zzzz"]).status.success());
+
+    let content = std::fs::read_to_string(&log_file).unwrap();
+    let entries: Vec<serde_json::Value> = content
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["pattern"], "feature-login");
+    assert_eq!(entries[0]["selected"], "feature-login");
+    assert_eq!(entries[0]["candidates"][0]["branch"], "feature-login");
+    assert!(entries[0]["duration_ms"].is_u64());
+    assert!(entries[1]["error"].as_str().unwrap().contains("zzzz"));
+
+    let output = run(&["--stats", "--slow", "--json", "--log-file", log_arg]);
+    let slowest: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(slowest.as_array().unwrap().len(), 2);
+
+    // Nothing goes to the default log until it is turned on
+    let default_log = data_dir.join("logs").join("ggo.jsonl");
+    assert!(!default_log.exists());
+    assert!(run(&["config", "set", "log.enabled", "true"])
+        .status
+        .success());
+    assert!(run(&["feature-logout"]).status.success());
+    assert!(default_log.exists());
+
+    let output = run(&["--stats", "--slow"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("ggo feature-logout"));
+    assert!(stdout.contains("→ feature-logout"));
+}
+
+#[test]
+fn test_data_dir_flag_env_and_config_precedence() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let status = Command::new("git")
+        .args(["branch", "feature-login"])
+        .current_dir(repo_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let home = tempfile::tempdir().unwrap();
+    let flag_dir = home.path().join("flag");
+    let env_dir = home.path().join("env");
+    let config_dir = home.path().join("dotfiles").join("ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str], env_data_dir: Option<&std::path::Path>| {
+        let mut command = Command::new(&ggo);
+        command
+            .args(args)
+            .current_dir(repo_path)
+            .env("HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path().join(".config"))
+            .env_remove("GGO_DATA_DIR");
+        if let Some(dir) = env_data_dir {
+            command.env("GGO_DATA_DIR", dir);
+        }
+        command.output().expect("Failed to run command")
+    };
+    let stats = |args: &[&str], env_data_dir| {
+        String::from_utf8_lossy(&run(args, env_data_dir).stdout).into_owned()
+    };
+
+    let output = run(
+        &[
+            "config",
+            "set",
+            "storage.data_dir",
+            config_dir.to_str().unwrap(),
+        ],
+        None,
+    );
+    assert!(output.status.success());
+
+    // The flag wins over the environment, which wins over the config
+    let flag = flag_dir.to_str().unwrap();
+    assert!(run(&["--data-dir", flag, "feature-login"], Some(&env_dir))
+        .status
+        .success());
+    assert!(flag_dir.join("data.db").exists());
+    assert!(!env_dir.join("data.db").exists());
+    let output = stats(&["--data-dir", flag, "--stats"], Some(&env_dir));
+    assert!(
+        output.contains(&format!("Data directory: {} (--data-dir)", flag)),
+        "{}",
+        output
+    );
+    assert!(output.contains("Total branch switches: 1"), "{}", output);
+
+    let output = stats(&["--stats"], Some(&env_dir));
+    assert!(output.contains("(GGO_DATA_DIR)"), "{}", output);
+    assert!(output.contains("Total branch switches: 0"), "{}", output);
+
+    let output = stats(&["--stats"], None);
+    assert!(
+        output.contains(&format!(
+            "Data directory: {} (storage.data_dir in the config file)",
+            config_dir.display()
+        )),
+        "{}",
+        output
+    );
+    // Reading an empty history doesn't create the database
+    assert!(!config_dir.join("data.db").exists());
+}
+
+#[test]
+fn test_detach_checks_out_tags_without_recording() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let main_branch = get_current_branch(repo_path);
+
+    Command::new("git")
+        .args(["tag", "v1.0.0"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", temp_dir.path().join("data"))
+            .output()
+            .expect("Failed to run command")
+    };
+
+    let output = run(&["--detach", "v1"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("detached at 'v1.0.0'"));
+    assert_eq!(get_current_branch(repo_path), "HEAD");
+
+    // Tags never show up as branches in the history
+    let output = run(&["--stats", "--format", "csv"]);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("v1.0.0"));
+
+    // 'ggo -' returns to the branch that was left
+    assert!(run(&["-"]).status.success());
+    assert_eq!(get_current_branch(repo_path), main_branch);
+
+    let output = run(&["--detach", "no-such-tag"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No tags or recent commits"));
+}
+
+#[test]
+fn test_tag_checks_out_highest_version_or_creates_branch() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+
+    for tag in ["v1.2.0", "v1.2.10", "v1.2.9", "v2.0.0"] {
+        Command::new("git")
+            .args(["tag", tag])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let run = |args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", temp_dir.path().join("data"))
+            .output()
+            .expect("Failed to run command")
+    };
+
+    // Without a terminal the highest matching version wins
+    let output = run(&["--tags", "--print", "v1.2"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "v1.2.10");
+
+    let output = run(&["tag", "v1.2"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("detached at 'v1.2.10'"));
+    assert_eq!(get_current_branch(repo_path), "HEAD");
+
+    let output = run(&["tag", "v2", "-b", "hotfix/2.0"]);
+    assert!(output.status.success());
+    assert_eq!(get_current_branch(repo_path), "hotfix/2.0");
+
+    let output = run(&["tag", "v3"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No tags match 'v3'"));
+}
+
+#[test]
+fn test_wt_creates_then_jumps_to_worktree() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    Command::new("git")
+        .args(["branch", "feature-x"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+
+    let original_branch = get_current_branch(repo_path);
+    let worktree_dir = tempfile::tempdir().unwrap();
+    let worktree_path = worktree_dir.path().join("feature-x");
+    let cd_file = worktree_dir.path().join("cd-target");
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .env("GGO_CD_FILE", &cd_file)
+            .output()
+            .expect("Failed to run command")
+    };
+
+    // Without -c and without a terminal, a missing worktree is an error
+    let output = run(&["wt", "feature"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("ggo wt -c"));
+
+    let output = run(&[
+        "wt",
+        "feature",
+        "-c",
+        "--path",
+        worktree_path.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    let target = std::fs::read_to_string(&cd_file).unwrap();
+    assert!(target.contains("feature-x"));
+    let head = Command::new("git")
+        .args(["branch", "--show-current"])
+        .current_dir(&worktree_path)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&head.stdout).trim(), "feature-x");
+    assert_eq!(get_current_branch(repo_path), original_branch);
+
+    std::fs::remove_file(&cd_file).unwrap();
+    let output = run(&["wt", "feature"]);
+    assert!(output.status.success());
+    let target = std::fs::read_to_string(&cd_file).unwrap();
+    assert!(target.contains("feature-x"));
+}
+
+#[test]
+fn test_init_prints_shell_integration() {
+    let ggo = get_ggo_binary();
+    for shell in ["bash", "zsh", "fish"] {
+        let output = Command::new(&ggo)
+            .args(["init", shell])
+            .output()
+            .expect("Failed to run command");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success());
+        assert!(stdout.contains("GGO_CD_FILE"));
+        assert!(stdout.contains("ggo record-current"));
+    }
+
+    let output = Command::new(&ggo)
+        .args(["init", "tcsh"])
+        .output()
+        .expect("Failed to run command");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_record_current_counts_plain_git_switches_once() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    for branch in ["feature/one", "feature/plain"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .output()
+            .expect("Failed to run command")
+    };
+
+    assert!(run(&["feature/one"]).status.success());
+    Command::new("git")
+        .args(["checkout", "--quiet", "feature/plain"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+
+    // The prompt hook may fire again on the same branch (shells set up by an
+    // older 'ggo init' still use the flag)
+    assert!(run(&["record-current"]).status.success());
+    assert!(run(&["--record-current"]).status.success());
+
+    let output = run(&["--stats", "--json"]);
+    let stats: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stats["total_switches"], 2);
+
+    // 'ggo -' knows where the plain switch came from
+    assert!(run(&["-"]).status.success());
+    assert_eq!(get_current_branch(repo_path), "feature/one");
+}
+
+#[test]
+fn test_repo_jumps_to_history_and_search_root_repositories() {
+    let known = setup_test_repo().expect("Failed to create test repo");
+    let roots = tempfile::tempdir().unwrap();
+    let unseen = roots.path().join("projects/unseen-service");
+    std::fs::create_dir_all(&unseen).unwrap();
+    Command::new("git")
+        .args(["init", "--quiet"])
+        .current_dir(&unseen)
+        .output()
+        .unwrap();
+
+    let config_home = tempfile::tempdir().unwrap();
+    let config_dir = config_home.path().join(".config/ggo");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(
+        config_dir.join("config.toml"),
+        format!("[search]\nroots = [{:?}]\n", roots.path()),
+    )
+    .unwrap();
+
+    Command::new("git")
+        .args(["branch", "feature/x"])
+        .current_dir(known.path())
+        .output()
+        .unwrap();
+
+    let data_dir = roots.path().join(".ggo");
+    let cd_file = roots.path().join("cd-target");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str], dir: &std::path::Path| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(dir)
+            .env("HOME", config_home.path())
+            .env_remove("XDG_CONFIG_HOME")
+            .env("GGO_DATA_DIR", &data_dir)
+            .env("GGO_CD_FILE", &cd_file)
+            .output()
+            .expect("Failed to run command")
+    };
+
+    // A switch puts the repository in the history
+    assert!(run(&["feature/x"], known.path()).status.success());
+
+    let output = run(&["repo", "unseen"], known.path());
+    assert!(output.status.success());
+    let target = std::fs::read_to_string(&cd_file).unwrap();
+    assert_eq!(
+        std::path::PathBuf::from(target),
+        std::fs::canonicalize(&unseen).unwrap()
+    );
+
+    let known_name = known.path().file_name().unwrap().to_str().unwrap();
+    let output = run(&["repo", known_name], &unseen);
+    assert!(output.status.success());
+    let target = std::fs::read_to_string(&cd_file).unwrap();
+    assert_eq!(
+        std::path::PathBuf::from(target),
+        std::fs::canonicalize(known.path()).unwrap()
+    );
+
+    let output = run(&["repo", "zzqqxx"], known.path());
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("search.roots"));
+}
+
+#[test]
+fn test_stack_walks_and_follows_renames_and_deletes() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let base = get_current_branch(repo_path);
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+    };
+    for branch in ["a", "b", "c"] {
+        git(&["branch", branch]);
+    }
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .output()
+            .expect("Failed to run command")
+    };
+    let stack = || String::from_utf8_lossy(&run(&["stack", "--all"]).stdout).to_string();
+
+    assert!(run(&["stack", "on", &base, "-b", "a"]).status.success());
+    assert!(run(&["stack", "on", "a", "-b", "b"]).status.success());
+    assert!(run(&["stack", "on", "a", "-b", "c"]).status.success());
+    assert!(!run(&["stack", "on", "c", "-b", "a"]).status.success());
+    assert!(stack().contains("    ├── b\n    └── c"));
+
+    // Two branches on 'a': without a terminal there's no picking one
+    git(&["checkout", "--quiet", "a"]);
+    let output = run(&["up"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("b, c"));
+
+    assert!(run(&["stack", "remove", "c"]).status.success());
+    assert!(run(&["up"]).status.success());
+    assert_eq!(get_current_branch(repo_path), "b");
+    let output = run(&["stack"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("b  ← current"));
+
+    let output = run(&["down", "5"]);
+    assert!(output.status.success());
+    assert_eq!(get_current_branch(repo_path), base);
+
+    git(&["branch", "-m", "b", "b-renamed"]);
+    git(&["branch", "-D", "a"]);
+    assert_eq!(stack(), format!("{}  ← current\n└── b-renamed\n", base));
+}
+
+#[test]
+fn test_cleanup_carries_history_over_to_renamed_branches() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let base = get_current_branch(repo_path);
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        let output = Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .output()
+            .expect("Failed to run command");
+        assert!(output.status.success(), "ggo {:?} failed", args);
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    // Renamed with 'git branch -m'
+    git(&["branch", "feature/old"]);
+    run(&["feature/old"]);
+    run(&[&base]);
+    git(&["branch", "-m", "feature/old", "feature/new"]);
+
+    // Deleted and recreated at the same commit under another name
+    git(&["branch", "spike"]);
+    run(&["spike"]);
+    git(&["commit", "--quiet", "--allow-empty", "-m", "spike"]);
+    let spike_tip = git(&["rev-parse", "HEAD"]);
+    run(&[&base]);
+    git(&["branch", "-D", "spike"]);
+    git(&["branch", "spike-v2", &spike_tip]);
+
+    let output = run(&["cleanup", "--deleted", "--dry-run"]);
+    assert!(output.contains("feature/old → feature/new"));
+    assert!(output.contains("spike → spike-v2"));
+
+    run(&["cleanup", "--deleted"]);
+    let recent = run(&["recent"]);
+    assert!(recent.contains("feature/new"));
+    assert!(recent.contains("spike-v2"));
+    assert!(!recent.contains("feature/old"));
+
+    assert!(run(&["rename-record", "nope", "spike-v2"]).contains("Nothing stored"));
+}
+
+#[test]
+fn test_note_is_listed_and_searchable() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    for branch in ["feature/api", "feature/ui"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .output()
+            .expect("Failed to run command")
+    };
+    let stdout = |args: &[&str]| {
+        let output = run(args);
+        assert!(output.status.success(), "ggo {:?} failed", args);
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    assert!(stdout(&["note", "feature/ui", "waiting on review"]).contains("Noted on 'feature/ui'"));
+    assert!(!run(&["note", "missing", "text"]).status.success());
+    assert!(!run(&["note", "feature/api", "  "]).status.success());
+
+    assert_eq!(stdout(&["note", "feature/ui"]).trim(), "waiting on review");
+    assert!(stdout(&["note"]).contains("feature/ui  waiting on review"));
+    let listed = stdout(&["-l", "feature"]);
+    let line = listed.lines().find(|l| l.contains("feature/ui")).unwrap();
+    assert!(line.contains("[note: waiting on review]"), "{}", line);
+
+    // Without --notes only branch names are matched
+    assert!(!run(&["-l", "review"]).status.success());
+    let output = stdout(&["-l", "--notes", "review"]);
+    assert!(output.contains("feature/ui"));
+    assert!(!output.contains("feature/api"));
+
+    let json: serde_json::Value =
+        serde_json::from_str(&stdout(&["--json", "-l", "--notes", "review"])).unwrap();
+    assert_eq!(json[0]["note"], "waiting on review");
+
+    // Checking out by note
+    stdout(&["--notes", "review"]);
+    assert_eq!(get_current_branch(repo_path), "feature/ui");
+
+    assert!(stdout(&["note", "-r", "feature/ui"]).contains("Removed the note"));
+    assert!(stdout(&["note"]).contains("No branch notes"));
+}
+
+#[test]
+fn test_branch_description_is_matched_and_shown() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+    };
+    git(&["branch", "feature/svc-2231"]);
+    git(&["branch", "feature/svc-2232"]);
+    git(&[
+        "config",
+        "branch.feature/svc-2231.description",
+        "Payments retry queue\n\nKeeps failed charges for a second try.",
+    ]);
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        let output = Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .output()
+            .expect("Failed to run command");
+        assert!(output.status.success(), "ggo {:?} failed", args);
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    let output = run(&["-l", "payments"]);
+    assert!(output.contains("feature/svc-2231"));
+    assert!(output.contains("(Payments retry queue)"));
+    assert!(!output.contains("feature/svc-2232"));
+
+    // Shown for name matches too
+    let output = run(&["-l", "svc"]);
+    let line = output.lines().find(|l| l.contains("svc-2231")).unwrap();
+    assert!(line.contains("(Payments retry queue)"), "{}", line);
+
+    run(&["payments"]);
+    assert_eq!(get_current_branch(repo_path), "feature/svc-2231");
+}
+
+#[test]
+fn test_find_branch_by_commit() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let base = get_current_branch(repo_path);
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .args([
+                "-c",
+                "user.name=Alice",
+                "-c",
+                "user.email=alice@example.com",
+            ])
+            .args(args)
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+    };
+    git(&["checkout", "--quiet", "-b", "feature/svc-2231"]);
+    std::fs::create_dir(repo_path.join("pay")).unwrap();
+    std::fs::write(repo_path.join("pay/retry.rs"), "fn retry() {}").unwrap();
+    git(&["add", "pay"]);
+    git(&["commit", "--quiet", "-m", "Add payment retry queue"]);
+    git(&["commit", "--quiet", "--allow-empty", "-m", "Fix typo"]);
+    git(&["checkout", "--quiet", &base]);
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .output()
+            .expect("Failed to run command")
+    };
+
+    // Only the tip is searched by default
+    let output = run(&["find", "-m", "payment"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("message 'payment'"));
+
+    let output = run(&["find", "-m", "payment", "--unmerged", "-l"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("feature/svc-2231"));
+    assert!(stdout.contains("Add payment retry queue"));
+    assert_eq!(get_current_branch(repo_path), base);
+
+    let output = run(&["find", "-a", "alice", "-t", "pay", "--unmerged"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Found in 'feature/svc-2231'"));
+    assert_eq!(get_current_branch(repo_path), "feature/svc-2231");
+
+    assert!(!run(&["find", "-a", "bob", "--unmerged"]).status.success());
+}
+
+#[test]
+fn test_contains_checks_out_branch_with_commit() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let base = get_current_branch(repo_path);
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    let initial = git(&["rev-parse", "--short", "HEAD"]);
+    git(&["branch", "often-used"]);
+    git(&["checkout", "--quiet", "-b", "feature/fix"]);
+    git(&[
+        "commit",
+        "--quiet",
+        "--allow-empty",
+        "-m",
+        "Fix the flaky test",
+    ]);
+    let fix = git(&["rev-parse", "HEAD"]);
+    git(&["checkout", "--quiet", &base]);
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .output()
+            .expect("Failed to run command")
+    };
+
+    assert!(run(&["contains", &fix]).status.success());
+    assert_eq!(get_current_branch(repo_path), "feature/fix");
+
+    // Every branch has the first commit: the most used one wins
+    for _ in 0..3 {
+        assert!(run(&["often-used"]).status.success());
+        git(&["checkout", "--quiet", &base]);
+    }
+    assert!(run(&["contains", &initial]).status.success());
+    assert_eq!(get_current_branch(repo_path), "often-used");
+
+    let output = run(&["contains", "0000000deadbeef"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("is not a commit"));
+}
+
+#[test]
+fn test_cached_branch_list_follows_ref_changes() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let base = get_current_branch(repo_path);
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(repo_path)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    };
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .output()
+            .expect("Failed to run command")
+    };
+
+    git(&["branch", "feature/cache-one"]);
+    assert!(run(&["cache-one"]).status.success());
+    assert_eq!(get_current_branch(repo_path), "feature/cache-one");
+    git(&["checkout", "--quiet", &base]);
+
+    // Branches created or deleted behind ggo's back invalidate the cached list
+    git(&["branch", "feature/cache-two"]);
+    assert!(run(&["cache-two"]).status.success());
+    assert_eq!(get_current_branch(repo_path), "feature/cache-two");
+    git(&["checkout", "--quiet", &base]);
+
+    git(&["branch", "-D", "feature/cache-one"]);
+    assert!(!run(&["-p", "cache-one"]).status.success());
+
+    // Packed refs work the same way
+    git(&["pack-refs", "--all"]);
+    git(&["branch", "-D", "feature/cache-two"]);
+    assert!(!run(&["-p", "cache-two"]).status.success());
+    assert_eq!(get_current_branch(repo_path), base);
+}
+
+#[test]
+fn test_stale_alias_falls_back_to_pattern_matching() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(repo_path)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    };
+    git(&["branch", "feature/old"]);
+    git(&["branch", "feature/keep"]);
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .output()
+            .expect("Failed to run command")
+    };
+
+    assert!(run(&["alias", "keep", "feature/old"]).status.success());
+    git(&["branch", "-D", "feature/old"]);
+
+    let output = run(&["-p", "keep"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "feature/keep\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("non-existent branch 'feature/old'"));
+
+    let output = run(&["keep"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Falling back to pattern matching"));
+    assert_eq!(get_current_branch(repo_path), "feature/keep");
+}
+
+#[test]
+fn test_exit_codes_and_quiet() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let outside = tempfile::tempdir().unwrap();
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |dir: &std::path::Path, args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(dir)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .env_remove("GIT_DIR")
+            .env("GIT_CEILING_DIRECTORIES", outside.path().parent().unwrap())
+            .output()
+            .expect("Failed to run command")
+    };
+
+    let output = run(repo_path, &["no-such-branch-xyz"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Try:"));
+
+    let output = run(repo_path, &["--quiet", "no-such-branch-xyz"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Error: No branches match pattern 'no-such-branch-xyz'\n"
+    );
+
+    let output = run(outside.path(), &["main"]);
+    assert_eq!(output.status.code(), Some(3));
+
+    // The alias points nowhere: the fallback warning is advisory
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(repo_path)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    };
+    git(&["branch", "feature/old"]);
+    git(&["branch", "feature/keep"]);
+    assert!(run(repo_path, &["alias", "keep", "feature/old"])
+        .status
+        .success());
+    git(&["branch", "-D", "feature/old"]);
+
+    let output = run(repo_path, &["--quiet", "keep"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).is_empty());
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("Using alias"));
+    assert_eq!(get_current_branch(repo_path), "feature/keep");
+}
+
+#[test]
+fn test_verbosity_flags() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let main_branch = get_current_branch(repo_path);
+
+    let ggo = get_ggo_binary();
+    let run = |data_dir: &std::path::Path, args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", data_dir)
+            .env_remove("RUST_LOG")
+            .output()
+            .expect("Failed to run command")
+    };
+    let data_dir = temp_dir.path().join(".ggo");
+
+    let output = run(&data_dir, &["-V"]);
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("ggo "));
+
+    // Logging goes to stderr, so results stay clean on stdout
+    let output = run(&data_dir, &["-p", &main_branch]);
+    assert!(output.stderr.is_empty());
+    let output = run(&data_dir, &["-vv", "-p", &main_branch]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!("{}\n", main_branch)
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("DEBUG"));
+
+    // An unusable data directory: ggo warns and carries on, unless -q
+    let not_a_dir = temp_dir.path().join("not-a-dir");
+    std::fs::write(&not_a_dir, "").unwrap();
+    let output = run(&not_a_dir, &["-p", &main_branch]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Could not open ggo database"));
+
+    let output = run(&not_a_dir, &["-q", "-p", &main_branch]);
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_setup_installs_integration_config_and_reflog_history() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let main_branch = get_current_branch(repo_path);
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+    };
+    git(&["checkout", "--quiet", "-b", "feature-reflog"]);
+    git(&["checkout", "--quiet", &main_branch]);
+    git(&["checkout", "--quiet", "feature-reflog"]);
+
+    let home = tempfile::tempdir().unwrap();
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path().join(".config"))
+            .env("XDG_DATA_HOME", home.path().join(".local/share"))
+            .env("SHELL", "/bin/bash")
+            .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+            .output()
+            .expect("Failed to run command")
+    };
+
+    // Without a terminal, only --yes can answer the questions
+    let output = run(&["setup"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("needs a terminal"));
+
+    let output = run(&["setup", "--yes"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Imported 3 branch switches"), "{}", stdout);
+
+    let bashrc = std::fs::read_to_string(home.path().join(".bashrc")).unwrap();
+    assert!(bashrc.contains("eval \"$(ggo init bash)\""));
+    let completions = home
+        .path()
+        .join(".local/share/bash-completion/completions/ggo");
+    assert!(std::fs::read_to_string(completions)
+        .unwrap()
+        .contains("ggo"));
+    let output = run(&["config", "get", "frecency.half_life_days"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "7");
+
+    let output = run(&["export"]);
+    let export: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let feature = export["branches"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|b| b["branch_name"] == "feature-reflog")
+        .unwrap();
+    assert_eq!(feature["switch_count"], 2);
+
+    // Running it again changes nothing
+    let output = run(&["setup", "--yes"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("already in"));
+    assert!(stdout.contains("Keeping your config file"));
+    assert!(stdout.contains("Imported 0 branch switches"));
+    let bashrc = std::fs::read_to_string(home.path().join(".bashrc")).unwrap();
+    assert_eq!(bashrc.matches("ggo init bash").count(), 1);
+}
+
+#[test]
+fn test_color_flag() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let status = Command::new("git")
+        .args(["branch", "feature/auth"])
+        .current_dir(repo_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        let output = Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .env("NO_COLOR", "1")
+            .output()
+            .expect("Failed to run command");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    // Not a terminal, and NO_COLOR: plain unless forced
+    assert!(!run(&["-l", "feature"]).contains('\x1b'));
+    assert!(!run(&["--color", "auto", "-l", "feature"]).contains('\x1b'));
+
+    let colored = run(&["--color", "always", "-l", "feature"]);
+    assert!(colored.contains("\x1b["));
+    assert!(colored.contains("feature"));
+
+    assert!(run(&["--color", "always", "--stats"]).contains("\x1b["));
+}
+
+#[test]
+fn test_nth_checks_out_listed_entry() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let home = tempfile::tempdir().unwrap();
+
+    for branch in ["feature-alpha", "feature-beta", "feature-gamma"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    let run = |args: &[&str]| {
+        Command::new(get_ggo_binary())
+            .args(args)
+            .current_dir(repo_path)
+            .env("HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path().join(".config"))
+            .env("GGO_DATA_DIR", home.path().join(".ggo"))
+            .output()
+            .expect("Failed to run command")
+    };
+
+    let output = run(&["--nth", "1"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No recent branch listing"));
+
+    let output = run(&["--json", "-l", "feature"]);
+    assert!(output.status.success());
+    let listed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let listed: Vec<&str> = listed
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|b| b["branch"].as_str().unwrap())
+        .collect();
+    assert_eq!(listed.len(), 3);
+
+    // Right after the listing a bare number picks from it
+    assert!(run(&["2"]).status.success());
+    assert_eq!(get_current_branch(repo_path), listed[1]);
+
+    // After a switch it's a pattern again
+    assert!(!run(&["3"]).status.success());
+    assert_eq!(get_current_branch(repo_path), listed[1]);
+
+    assert!(run(&["--nth", "3"]).status.success());
+    assert_eq!(get_current_branch(repo_path), listed[2]);
+
+    let output = run(&["--nth", "4"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no entry 4"));
+}
+
+#[test]
+fn test_list_format_template() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    for branch in ["feature/auth", "feature/billing"] {
+        let status = Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .output()
+            .expect("Failed to run command")
+    };
+
+    assert!(run(&["feature/auth"]).status.success());
+    assert!(run(&["alias", "fa", "feature/auth"]).status.success());
+
+    let output = run(&[
+        "-l",
+        "feature",
+        "--format",
+        r"{name}\t{switches}\t{aliases}\t{current}",
+    ]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines,
+        vec!["feature/auth\t1\tfa\ttrue", "feature/billing\t0\t\tfalse"]
+    );
+
+    // A reader that stops after the first line isn't an error
+    let mut child = Command::new(&ggo)
+        .args(["-l", "", "--format", "{name}"])
+        .current_dir(repo_path)
+        .env("GGO_DATA_DIR", &test_data_dir)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to run command");
+    drop(child.stdout.take());
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {}", stderr);
+    assert!(!stderr.contains("Broken pipe"));
+
+    let output = run(&["-l", "feature", "--format", "{branch}"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown placeholder '{branch}'"));
+
+    let output = run(&["--stats", "--format", "{name}"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--stats takes json or csv"));
+}
+
+#[test]
+fn test_list_drops_columns_in_narrow_terminal() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    for branch in ["feature/auth", "feature/billing"] {
+        let status = Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str], columns: &str| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .env("COLUMNS", columns)
+            .output()
+            .expect("Failed to run command")
+    };
+
+    let wide = "200";
+    assert!(run(&["feature/auth"], wide).status.success());
+    assert!(run(&["alias", "fa", "feature/auth"], wide).status.success());
+    let list = |columns| {
+        let output = run(&["-l", "feature", "--color", "never"], columns);
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    // Wide enough for the scores and last commits
+    let wide = list(wide);
+    assert!(wide.contains("feature/auth ("), "{}", wide);
+    assert!(wide.contains("— Initial commit"), "{}", wide);
+
+    // Narrow: the scores and last commits go, aliases stay
+    let narrow = list("60");
+    assert!(!narrow.contains("feature/auth ("), "{}", narrow);
+    assert!(!narrow.contains("Initial commit"), "{}", narrow);
+    assert!(narrow.contains("[alias: fa]"), "{}", narrow);
+    for line in narrow.lines().filter(|line| line.contains("feature/")) {
+        assert!(line.chars().count() <= 60, "{}", line);
+    }
+
+    // Very narrow: only the names
+    let tiny = list("40");
+    assert!(tiny.contains("feature/auth\n"), "{}", tiny);
+    assert!(!tiny.contains("[alias"), "{}", tiny);
+}
+
+#[test]
+fn test_session_branches_beat_lifetime_favorites() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    for branch in ["work-old", "work-api", "work-ui"] {
+        let status = Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    let config_home = tempfile::tempdir().unwrap();
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("HOME", config_home.path())
+            .env("XDG_CONFIG_HOME", config_home.path().join(".config"))
+            .env("GGO_DATA_DIR", temp_dir.path().join(".ggo"))
+            .output()
+            .expect("Failed to run command")
+    };
+
+    // A long-time favorite with a big switch count, but no checkouts this session
+    assert!(run(&["work-api"]).status.success());
+    let output = run(&["export"]);
+    let mut export: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let branches = export["branches"].as_array_mut().unwrap();
+    let mut favorite = branches
+        .iter()
+        .find(|b| b["branch_name"] == "work-api")
+        .unwrap()
+        .clone();
+    favorite["branch_name"] = "work-old".into();
+    favorite["switch_count"] = 200.into();
+    branches.push(favorite);
+    let export_file = temp_dir.path().join("history.json");
+    std::fs::write(&export_file, export.to_string()).unwrap();
+    let output = run(&["import", export_file.to_str().unwrap()]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // Ping-ponging between two branches: the one just left wins
+    assert!(run(&["work-ui"]).status.success());
+    assert!(run(&["work-api"]).status.success());
+    assert!(run(&["work-ui"]).status.success());
+    let output = run(&["-p", "work"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "work-api");
+
+    let output = run(&["explain", "work"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Session"));
+
+    // Without sessions, lifetime use decides
+    assert!(run(&["config", "set", "frecency.session_minutes", "0"])
+        .status
+        .success());
+    let output = run(&["-p", "work"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "work-old");
+}
+
+#[test]
+fn test_alias_rename_export_and_import() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    for branch in ["feature/auth", "release/1.42"] {
+        let status = Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    let ggo = get_ggo_binary();
+    let run = |data_dir: &str, args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", temp_dir.path().join(data_dir))
+            .output()
+            .expect("Failed to run command")
+    };
+
+    assert!(run("laptop", &["alias", "fa", "feature/auth"])
+        .status
+        .success());
+    assert!(run("laptop", &["alias", "rel", "release/1.42"])
+        .status
+        .success());
+
+    let output = run("laptop", &["alias", "--rename", "fa", "auth"]);
+    assert!(output.status.success());
+    let output = run("laptop", &["alias", "auth"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("auth → feature/auth"));
+    let output = run("laptop", &["alias", "--rename", "auth", "rel"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("already exists"));
+
+    let output = run("laptop", &["alias", "export"]);
+    assert!(output.status.success());
+    let mut aliases = String::from_utf8_lossy(&output.stdout).to_string();
+    assert!(aliases.contains("auth = \"feature/auth\""));
+    aliases.push_str("gone = \"feature/deleted\"\n");
+    let alias_file = temp_dir.path().join("aliases.toml");
+    std::fs::write(&alias_file, aliases).unwrap();
+
+    let output = run(
+        "desktop",
+        &["alias", "import", alias_file.to_str().unwrap()],
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Imported 2 aliases"));
+    assert!(stdout.contains("Skipped 1:"));
+    assert!(stdout.contains("gone → feature/deleted"));
+
+    let output = run("desktop", &["alias", "--list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("auth → feature/auth"));
+    assert!(stdout.contains("rel → release/1.42"));
+    assert!(!stdout.contains("gone"));
+
+    // An alias already pointing elsewhere is only replaced with --force
+    std::fs::write(
+        &alias_file,
+        "[aliases]\nauth = \"release/1.42\"\n\"bad name\" = \"main\"\n",
+    )
+    .unwrap();
+    let output = run(
+        "desktop",
+        &["alias", "import", alias_file.to_str().unwrap()],
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Imported 0 aliases"), "{}", stdout);
+    assert!(stdout.contains("already an alias for feature/auth"));
+    assert!(stdout.contains("Invalid alias name: bad name"));
+    assert!(stdout.contains("--force"));
+    let output = run("desktop", &["alias", "auth"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("auth → feature/auth"));
+
+    let output = run(
+        "desktop",
+        &["alias", "import", "--force", alias_file.to_str().unwrap()],
+    );
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Imported 1 aliases"));
+    let output = run("desktop", &["alias", "auth"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("auth → release/1.42"));
+}
+
+#[test]
+fn test_template_alias_expands_arguments() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    for branch in ["release/1.41", "release/1.42", "alice/fix-login"] {
+        let status = Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .output()
+            .expect("Failed to run command")
+    };
+
+    let output = run(&["alias", "rel", "release/{}"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("use: ggo rel <value>"));
+    assert!(run(&["alias", "fix", "{}/fix-{}"]).status.success());
+    assert!(!run(&["alias", "bad", "release/{version}"]).status.success());
+
+    let output = run(&["rel", "1.42"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(get_current_branch(repo_path), "release/1.42");
+
+    let output = run(&["-p", "fix", "alice", "login"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "alice/fix-login"
+    );
+
+    let output = run(&["rel"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("takes 1 argument(s)"));
+
+    let output = run(&["release", "1.41"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unexpected argument '1.41'"));
+}
+
+#[test]
+fn test_alias_list_all_groups_by_repository() {
+    let api = setup_test_repo().expect("Failed to create test repo");
+    let web = setup_test_repo().expect("Failed to create test repo");
+    let data_dir = tempfile::tempdir().unwrap();
+    for (repo, branch) in [
+        (&api, "hotfix/1.2"),
+        (&api, "feature/old"),
+        (&web, "develop"),
+    ] {
+        let status = Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    let ggo = get_ggo_binary();
+    let run = |repo: &std::path::Path, args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo)
+            .env("GGO_DATA_DIR", data_dir.path())
+            .output()
+            .expect("Failed to run command")
+    };
+
+    assert!(run(api.path(), &["alias", "hotfix", "hotfix/1.2"])
+        .status
+        .success());
+    assert!(run(api.path(), &["alias", "old", "feature/old"])
+        .status
+        .success());
+    assert!(run(web.path(), &["alias", "d", "develop"]).status.success());
+    Command::new("git")
+        .args(["branch", "-D", "feature/old"])
+        .current_dir(api.path())
+        .output()
+        .unwrap();
+
+    let output = run(web.path(), &["alias", "--list", "--all"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("✓ hotfix → hotfix/1.2"));
+    assert!(stdout.contains("✗ old → feature/old (branch deleted)"));
+    assert!(stdout.contains("✓ d → develop"));
+    assert!(stdout.contains("(current)"));
+
+    // Only the current repository without --all
+    let output = run(web.path(), &["alias", "--list"]);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("hotfix"));
+
+    // Without the repository there's nothing to check the branch against
+    drop(web);
+    let output = run(api.path(), &["alias", "--list", "--all"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("(repository not found)"));
+    assert!(stdout.contains("    d → develop"));
+}
+
+#[test]
+fn test_previous_location_returns_to_detached_head() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    git(&["branch", "feature/a"]);
+    git(&["branch", "feature/b"]);
+    git(&["checkout", "--quiet", "--detach"]);
+    let commit = git(&["rev-parse", "HEAD"]);
+
+    let test_data_dir = temp_dir.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("GGO_DATA_DIR", &test_data_dir)
+            .output()
+            .expect("Failed to run command")
+    };
+
+    // Listings say where HEAD is rather than marking a branch
+    let output = run(&["-l", "feature"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("(detached at {}", &commit[..7])));
+
+    assert!(run(&["feature/a"]).status.success());
+    let output = run(&["-"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("HEAD is now detached"));
+    assert_eq!(git(&["rev-parse", "HEAD"]), commit);
+    assert!(git(&["branch", "--show-current"]).is_empty());
+
+    // And back to the branch
+    assert!(run(&["-"]).status.success());
+    assert_eq!(git(&["branch", "--show-current"]), "feature/a");
+}
+
+#[test]
+fn test_bare_repository_and_git_dir() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    Command::new("git")
+        .args(["branch", "feature/a"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+    let outside = tempfile::tempdir().unwrap();
+    let bare_path = outside.path().join("app.git");
+    let status = Command::new("git")
+        .args(["clone", "--quiet", "--bare"])
+        .arg(repo_path)
+        .arg(&bare_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let data_dir = outside.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |dir: &std::path::Path, env: &[(&str, &std::path::Path)], args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(dir)
+            .envs(env.iter().copied())
+            .env("GGO_DATA_DIR", &data_dir)
+            .env_remove("GGO_CD_FILE")
+            .output()
+            .expect("Failed to run command")
+    };
+
+    // Listing works in a bare repository, with no branch marked as current
+    let output = run(&bare_path, &[], &["-l", "feature"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("feature/a"));
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("* "));
+    assert!(run(&bare_path, &[], &["--stats"]).status.success());
+
+    let output = run(&bare_path, &[], &["feature/a"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("bare repository"));
+
+    // Worktrees of a bare repository go next to it
+    let output = run(&bare_path, &[], &["wt", "feature/a", "--create"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(outside.path().join("app-feature-a").join(".git").exists());
+
+    // GIT_DIR/GIT_WORK_TREE from elsewhere use the repository's own history
+    let git_dir = repo_path.join(".git");
+    let env = [("GIT_DIR", git_dir.as_path()), ("GIT_WORK_TREE", repo_path)];
+    let output = run(outside.path(), &env, &["feature/a"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(get_current_branch(repo_path), "feature/a");
+
+    let output = run(repo_path, &[], &["recent"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("feature/a"));
+}
+
+#[test]
+fn test_migrate_storage_moves_history() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let home = tempfile::tempdir().unwrap();
+    let data_dir = home.path().join(".ggo");
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path().join(".config"))
+            .env("GGO_DATA_DIR", &data_dir)
+            .output()
+            .expect("Failed to run command")
+    };
+
+    Command::new("git")
+        .args(["branch", "feature-moved"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+    assert!(run(&["feature-moved"]).status.success());
+
+    let output = run(&["migrate-storage", "json"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Moved 1 branch records"));
+
+    let output = run(&["config", "get", "storage.backend"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "json");
+    let output = run(&["export"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("feature-moved"));
+
+    assert!(data_dir.join("history.json").exists());
+
+    // Going back refuses to overwrite the history still in data.db
+    let output = run(&["migrate-storage", "sqlite"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--force"));
+    assert!(run(&["migrate-storage", "sqlite", "--force"])
+        .status
+        .success());
+}
+
+#[test]
+fn test_switch_last_modified_picks_my_latest_commit() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let home = tempfile::tempdir().unwrap();
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path().join(".config"))
+            .env("GGO_DATA_DIR", home.path().join(".ggo"))
+            .output()
+            .expect("Failed to run command")
+    };
+    let git = |args: &[&str], date: &str| {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(repo_path)
+            .env("GIT_AUTHOR_DATE", date)
+            .env("GIT_COMMITTER_DATE", date)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+    };
+
+    // The initial commit is someone else's
+    git(&["config", "user.email", "me@example.com"], "");
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let days_ago = |days: u64| format!("@{} +0000", now - days * 86400);
+    for (branch, days) in [("feature-older", 3), ("feature-newer", 1)] {
+        git(&["switch", "-q", "-c", branch], "");
+        git(
+            &["commit", "-q", "--allow-empty", "-m", branch],
+            &days_ago(days),
+        );
+        git(&["switch", "-q", "-"], "");
+    }
+
+    let output = run(&["switch-last-modified", "--json"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let active: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let branches: Vec<&str> = active
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a["branch"].as_str().unwrap())
+        .collect();
+    assert_eq!(branches, vec!["feature-newer", "feature-older"]);
+
+    let output = run(&["switch-last-modified"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Switched to branch 'feature-newer'"));
+}
+
+#[test]
+fn test_query_ranks_without_switching() {
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let home = tempfile::tempdir().unwrap();
+    let ggo = get_ggo_binary();
+    let run = |args: &[&str]| {
+        Command::new(&ggo)
+            .args(args)
+            .current_dir(repo_path)
+            .env("HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path().join(".config"))
+            .env("GGO_DATA_DIR", home.path().join(".ggo"))
+            .output()
+            .expect("Failed to run command")
+    };
+    for branch in ["feature-a", "feature-b", "bugfix"] {
+        Command::new("git")
+            .args(["branch", branch])
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+    assert!(run(&["feature-b"]).status.success());
+    assert!(run(&["-"]).status.success());
+    let head = || std::fs::read_to_string(repo_path.join(".git/HEAD")).unwrap();
+    let before = head();
+
+    let output = run(&["query", "feature", "--json", "--limit", "1"]);
+    assert!(output.status.success());
+    let queried: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let queried = queried.as_array().unwrap();
+    assert_eq!(queried.len(), 1);
+    assert_eq!(queried[0]["branch"], "feature-b");
+    assert_eq!(queried[0]["switch_count"], 1);
+    assert_eq!(queried[0]["current"], false);
+
+    let output = run(&["query"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.lines().count(), 4);
+    assert!(stdout.lines().all(|line| line.contains('\t')));
+
+    // Nothing matching is an empty list for the picker
+    let output = run(&["query", "zzzz", "--json"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "[]");
+
+    assert_eq!(head(), before);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_serve_answers_json_rpc() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let temp_dir = setup_test_repo().expect("Failed to create test repo");
+    let repo_path = temp_dir.path();
+    let home = tempfile::tempdir().unwrap();
+    let socket = home.path().join("ggo.sock");
+    Command::new("git")
+        .args(["branch", "feature-served"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+
+    let ggo = get_ggo_binary();
+    let mut server = Command::new(&ggo)
+        .args(["serve", "--socket"])
+        .arg(&socket)
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .env("GGO_DATA_DIR", home.path().join(".ggo"))
+        .stdout(std::process::Stdio::null())
+        .spawn()
+        .expect("Failed to start ggo serve");
+
+    let mut stream = None;
+    for _ in 0..100 {
+        if let Ok(connected) = UnixStream::connect(&socket) {
+            stream = Some(connected);
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    let mut stream = stream.expect("ggo serve never listened");
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&socket).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        // The directory it was bound in is gone
+        let names: Vec<_> = std::fs::read_dir(home.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert!(names
+            .iter()
+            .all(|name| !name.to_string_lossy().starts_with(".ggo-serve-")));
+    }
+    let mut responses = BufReader::new(stream.try_clone().unwrap());
+    let mut call = |request: serde_json::Value| {
+        writeln!(stream, "{}", request).unwrap();
+        let mut line = String::new();
+        responses.read_line(&mut line).unwrap();
+        serde_json::from_str::<serde_json::Value>(&line).unwrap()
+    };
+    let repo = repo_path.to_str().unwrap();
+
+    let response = call(serde_json::json!({
+        "jsonrpc": "2.0", "id": 1, "method": "checkout",
+        "params": {"repo": repo, "branch": "feature-served"}
+    }));
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["result"]["branch"], "feature-served");
+
+    let response = call(serde_json::json!({
+        "jsonrpc": "2.0", "id": 2, "method": "list-ranked",
+        "params": {"repo": repo, "pattern": "served"}
+    }));
+    let ranked = response["result"].as_array().unwrap();
+    assert_eq!(ranked.len(), 1);
+    assert_eq!(ranked[0]["branch"], "feature-served");
+    assert_eq!(ranked[0]["switch_count"], 1);
+
+    let response = call(serde_json::json!({
+        "jsonrpc": "2.0", "id": 3, "method": "aliases", "params": {"repo": repo}
+    }));
+    assert_eq!(response["result"], serde_json::json!([]));
+
+    // A second server refuses to take over the socket
+    let output = Command::new(&ggo)
+        .args(["serve", "--socket"])
+        .arg(&socket)
+        .env("HOME", home.path())
+        .env("GGO_DATA_DIR", home.path().join(".ggo"))
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Another 'ggo serve'"));
+
+    server.kill().unwrap();
+    server.wait().unwrap();
+}